@@ -86,6 +86,8 @@ impl From<&InputRules> for external::InputRules {
         }
 
         input_rules.set_min_partial_fill_value(source.min_partial_fill_value);
+        input_rules.set_valid_from_block(source.valid_from_block);
+        input_rules.set_max_block_timestamp(source.max_block_timestamp);
 
         input_rules
     }
@@ -113,12 +115,16 @@ impl TryFrom<&external::InputRules> for InputRules {
             .map(RevealedTxOut::try_from)
             .transpose()?;
         let min_partial_fill_value = source.min_partial_fill_value;
+        let valid_from_block = source.valid_from_block;
+        let max_block_timestamp = source.max_block_timestamp;
         Ok(InputRules {
             required_outputs,
             max_tombstone_block,
             partial_fill_outputs,
             partial_fill_change,
             min_partial_fill_value,
+            valid_from_block,
+            max_block_timestamp,
         })
     }
 }
@@ -0,0 +1,263 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Validation and signing helpers for invoices.
+
+use crate::{external, printable};
+use displaydoc::Display;
+use mc_crypto_keys::{Ed25519Pair, Ed25519Public, Ed25519Signature, Signer, Verifier};
+use mc_transaction_core::TokenId;
+use std::collections::BTreeMap;
+
+/// Domain separator for merchant signatures over invoices.
+const INVOICE_SIGNATURE_DOMAIN_TAG: &[u8] = b"mc_invoice_merchant_signature";
+
+/// Invoice validation errors
+#[derive(Clone, Debug, Eq, PartialEq, Display)]
+pub enum InvoiceError {
+    /// The invoice has no public address
+    MissingPublicAddress,
+
+    /// The invoice has no line items
+    NoLineItems,
+
+    /// Line item {0} has no amount
+    MissingAmount(usize),
+
+    /// Line item {0} has a zero amount
+    ZeroAmount(usize),
+
+    /// The total for token {0} overflows
+    TotalOverflow(TokenId),
+
+    /// The invoice expired at block {0}
+    ExpiredAtBlock(u64),
+
+    /// The invoice expired at timestamp {0}
+    ExpiredAtTimestamp(u64),
+
+    /// The merchant signing key is invalid
+    InvalidSigningKey,
+
+    /// The merchant signature is invalid
+    InvalidSignature,
+}
+
+impl std::error::Error for InvoiceError {}
+
+impl printable::Invoice {
+    /// The total owed in each token, summed over the line items.
+    pub fn totals(&self) -> Result<BTreeMap<TokenId, u64>, InvoiceError> {
+        let mut totals = BTreeMap::<TokenId, u64>::new();
+        for (index, line_item) in self.get_line_items().iter().enumerate() {
+            if !line_item.has_amount() {
+                return Err(InvoiceError::MissingAmount(index));
+            }
+            let amount = line_item.get_amount();
+            if amount.get_value() == 0 {
+                return Err(InvoiceError::ZeroAmount(index));
+            }
+            let token_id = TokenId::from(amount.get_token_id());
+            let total = totals.entry(token_id).or_default();
+            *total = total
+                .checked_add(amount.get_value())
+                .ok_or(InvoiceError::TotalOverflow(token_id))?;
+        }
+        Ok(totals)
+    }
+
+    /// Check whether the invoice has expired at the given block index and
+    /// unix timestamp, in seconds.
+    pub fn check_expiry(&self, block_index: u64, timestamp: u64) -> Result<(), InvoiceError> {
+        let expiry_block = self.get_expiry_block();
+        if expiry_block != 0 && block_index >= expiry_block {
+            return Err(InvoiceError::ExpiredAtBlock(expiry_block));
+        }
+        let expiry_timestamp = self.get_expiry_timestamp();
+        if expiry_timestamp != 0 && timestamp >= expiry_timestamp {
+            return Err(InvoiceError::ExpiredAtTimestamp(expiry_timestamp));
+        }
+        Ok(())
+    }
+
+    /// Check that the invoice is well-formed and, if it carries a merchant
+    /// identity, that it is correctly signed. Returns the totals per token.
+    ///
+    /// This does not check expiry, see [Self::check_expiry].
+    pub fn validate(&self) -> Result<BTreeMap<TokenId, u64>, InvoiceError> {
+        if !self.has_public_address() {
+            return Err(InvoiceError::MissingPublicAddress);
+        }
+        if self.get_line_items().is_empty() {
+            return Err(InvoiceError::NoLineItems);
+        }
+        let totals = self.totals()?;
+        self.verify_merchant_signature()?;
+        Ok(totals)
+    }
+
+    /// Set the merchant identity, signing the invoice contents with the
+    /// merchant's key. Any later change to the invoice invalidates the
+    /// signature.
+    pub fn sign_merchant_identity(&mut self, name: &str, signer: &Ed25519Pair) {
+        let mut identity = printable::MerchantIdentity::new();
+        identity.set_name(name.to_owned());
+        identity.set_signing_key(external::Ed25519Public::from(&signer.public_key()));
+        self.set_merchant_identity(identity);
+
+        let signature: Ed25519Signature = signer.sign(&self.merchant_signing_message());
+        self.mut_merchant_identity()
+            .set_signature(external::Ed25519Signature::from(&signature));
+    }
+
+    /// Verify the merchant signature. Returns the merchant's signing key if the
+    /// invoice is signed, or None if it has no merchant identity.
+    pub fn verify_merchant_signature(&self) -> Result<Option<Ed25519Public>, InvoiceError> {
+        if !self.has_merchant_identity() {
+            return Ok(None);
+        }
+        let identity = self.get_merchant_identity();
+        let signing_key = Ed25519Public::try_from(identity.get_signing_key())
+            .map_err(|_| InvoiceError::InvalidSigningKey)?;
+        let signature = Ed25519Signature::try_from(identity.get_signature())
+            .map_err(|_| InvoiceError::InvalidSignature)?;
+        signing_key
+            .verify(&self.merchant_signing_message(), &signature)
+            .map_err(|_| InvoiceError::InvalidSignature)?;
+        Ok(Some(signing_key))
+    }
+
+    /// The message the merchant signs. Every variable-length field is length
+    /// prefixed, so that distinct invoices can't produce the same message.
+    fn merchant_signing_message(&self) -> Vec<u8> {
+        fn put_bytes(message: &mut Vec<u8>, bytes: &[u8]) {
+            message.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+            message.extend_from_slice(bytes);
+        }
+
+        let mut message = Vec::new();
+        put_bytes(&mut message, INVOICE_SIGNATURE_DOMAIN_TAG);
+
+        let address = self.get_public_address();
+        put_bytes(&mut message, address.get_view_public_key().get_data());
+        put_bytes(&mut message, address.get_spend_public_key().get_data());
+        put_bytes(&mut message, address.get_fog_report_url().as_bytes());
+        put_bytes(&mut message, address.get_fog_report_id().as_bytes());
+        put_bytes(&mut message, address.get_fog_authority_sig());
+
+        message.extend_from_slice(&(self.get_line_items().len() as u64).to_le_bytes());
+        for line_item in self.get_line_items() {
+            put_bytes(&mut message, line_item.get_description().as_bytes());
+            message.extend_from_slice(&line_item.get_amount().get_value().to_le_bytes());
+            message.extend_from_slice(&line_item.get_amount().get_token_id().to_le_bytes());
+        }
+
+        put_bytes(&mut message, self.get_memo().as_bytes());
+        message.extend_from_slice(&self.get_payment_intent_id().to_le_bytes());
+        message.extend_from_slice(&self.get_expiry_block().to_le_bytes());
+        message.extend_from_slice(&self.get_expiry_timestamp().to_le_bytes());
+        put_bytes(&mut message, self.get_callback_url().as_bytes());
+
+        let identity = self.get_merchant_identity();
+        put_bytes(&mut message, identity.get_name().as_bytes());
+        put_bytes(&mut message, identity.get_signing_key().get_data());
+
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_util_from_random::FromRandom;
+    use rand::rngs::StdRng;
+    use rand_core::SeedableRng;
+
+    fn line_item(value: u64, token_id: u64) -> printable::InvoiceLineItem {
+        let mut amount = external::Amount::new();
+        amount.set_value(value);
+        amount.set_token_id(token_id);
+        let mut line_item = printable::InvoiceLineItem::new();
+        line_item.set_description(format!("{value} of token {token_id}"));
+        line_item.set_amount(amount);
+        line_item
+    }
+
+    fn sample_invoice() -> printable::Invoice {
+        let mut public_address = external::PublicAddress::new();
+        public_address.mut_view_public_key().set_data(vec![1u8; 32]);
+        public_address
+            .mut_spend_public_key()
+            .set_data(vec![2u8; 32]);
+
+        let mut invoice = printable::Invoice::new();
+        invoice.set_public_address(public_address);
+        invoice.mut_line_items().push(line_item(10, 0));
+        invoice.mut_line_items().push(line_item(5, 1));
+        invoice.mut_line_items().push(line_item(7, 0));
+        invoice.set_payment_intent_id(1234);
+        invoice.set_expiry_block(100);
+        invoice.set_expiry_timestamp(1_700_000_000);
+        invoice
+    }
+
+    #[test]
+    fn test_invoice_totals_and_expiry() {
+        let invoice = sample_invoice();
+        let totals = invoice.validate().unwrap();
+        assert_eq!(
+            totals,
+            BTreeMap::from([(TokenId::from(0), 17), (TokenId::from(1), 5)])
+        );
+
+        invoice.check_expiry(99, 1_699_999_999).unwrap();
+        assert_eq!(
+            invoice.check_expiry(100, 0),
+            Err(InvoiceError::ExpiredAtBlock(100))
+        );
+        assert_eq!(
+            invoice.check_expiry(0, 1_700_000_000),
+            Err(InvoiceError::ExpiredAtTimestamp(1_700_000_000))
+        );
+
+        let mut invoice = sample_invoice();
+        invoice.mut_line_items().push(line_item(0, 2));
+        assert_eq!(invoice.validate(), Err(InvoiceError::ZeroAmount(3)));
+    }
+
+    #[test]
+    fn test_invoice_merchant_signature() {
+        let mut rng = StdRng::from_seed([1u8; 32]);
+        let signer = Ed25519Pair::from_random(&mut rng);
+
+        let mut invoice = sample_invoice();
+        assert_eq!(invoice.verify_merchant_signature(), Ok(None));
+
+        invoice.sign_merchant_identity("Coffee Shop", &signer);
+        assert_eq!(
+            invoice.verify_merchant_signature(),
+            Ok(Some(signer.public_key()))
+        );
+
+        // The signature survives b58 encoding.
+        let mut wrapper = printable::PrintableWrapper::new();
+        wrapper.set_invoice(invoice.clone());
+        let decoded = printable::PrintableWrapper::b58_decode(wrapper.b58_encode().unwrap())
+            .unwrap()
+            .take_invoice();
+        assert_eq!(decoded.validate(), invoice.validate());
+
+        // Any change to the invoice breaks the signature.
+        let mut tampered = invoice.clone();
+        tampered.mut_line_items()[0].mut_amount().set_value(1);
+        assert_eq!(tampered.validate(), Err(InvoiceError::InvalidSignature));
+
+        let mut tampered = invoice;
+        tampered
+            .mut_merchant_identity()
+            .set_name("Other Shop".to_owned());
+        assert_eq!(
+            tampered.verify_merchant_signature(),
+            Err(InvoiceError::InvalidSignature)
+        );
+    }
+}
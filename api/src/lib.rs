@@ -19,5 +19,6 @@ mod autogenerated_code {
 mod convert;
 
 pub mod display;
+pub mod invoice;
 
 pub use crate::{autogenerated_code::*, convert::*};
@@ -55,6 +55,12 @@ pub struct BlockMetadataContents {
     /// Responder ID of the consensus node that externalized this block.
     #[prost(message, required, tag = 4)]
     responder_id: ResponderId,
+
+    /// An approximate time at which the block was externalized, in seconds
+    /// since the Unix epoch, or 0 if unknown.
+    #[prost(uint64, tag = 6)]
+    #[digestible(omit_when = 0)]
+    timestamp: u64,
 }
 
 impl BlockMetadataContents {
@@ -70,9 +76,15 @@ impl BlockMetadataContents {
             quorum_set,
             attestation_evidence: Some(attestation_evidence),
             responder_id,
+            timestamp: 0,
         }
     }
 
+    /// Set the value of `timestamp`.
+    pub fn set_timestamp(&mut self, timestamp: u64) {
+        self.timestamp = timestamp;
+    }
+
     /// Get the [BlockID].
     pub fn block_id(&self) -> &BlockID {
         &self.block_id
@@ -94,6 +106,11 @@ impl BlockMetadataContents {
     pub fn responder_id(&self) -> &ResponderId {
         &self.responder_id
     }
+
+    /// Get the timestamp, in seconds since the Unix epoch, or 0 if unknown.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
 }
 
 /// Signed metadata for a block.
@@ -214,6 +231,7 @@ mod test {
             assert_eq!(block_v4.block_id(), &BlockID([1; 32]));
             assert_eq!(block_v4.quorum_set(), &quorum_set);
             assert_eq!(block_v4.responder_id(), &ResponderId("hello".into()));
+            assert_eq!(block_v4.timestamp(), 0);
             assert_eq!(
                 block_v4.attestation_evidence,
                 Some(AttestationEvidence::VerificationReport(report))
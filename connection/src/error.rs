@@ -43,6 +43,10 @@ impl Error {
             Error::Grpc(_) => true,
             Error::Attestation(err) => err.should_retry(),
             Error::TransactionValidation(ProposeTxResult::LedgerTxOutIndexOutOfBounds, _) => true,
+            Error::TransactionValidation(
+                ProposeTxResult::ClientRateLimited | ProposeTxResult::NodeBackpressure,
+                _,
+            ) => true,
             _ => false,
         }
     }
@@ -10,12 +10,15 @@ use mc_common::{
 use mc_util_uri::ConnectionUri;
 use std::{
     collections::BTreeMap,
-    sync::{Arc, RwLock, RwLockReadGuard},
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
 };
 
 struct ConnectionManagerInner<C: Connection> {
     /// Map of responder id -> retryable connection.
     id_to_conn: BTreeMap<ResponderId, SyncConnection<C>>,
+
+    /// Logger used for connections added after construction.
+    logger: Logger,
 }
 
 /// A connection manager manages a list of peers it is connected to.
@@ -38,23 +41,9 @@ impl<C: Connection> ConnectionManager<C> {
             inner: Arc::new(RwLock::new(ConnectionManagerInner {
                 id_to_conn: conns
                     .into_iter()
-                    .map(|conn| {
-                        let name = conn.to_string();
-                        let responder_id =
-                            conn.uri()
-                                .host_and_port_responder_id()
-                                .unwrap_or_else(|err| {
-                                    panic!(
-                                        "Could not create responder_id from {:?}: {}",
-                                        conn.uri().to_string(),
-                                        err
-                                    )
-                                });
-                        let sync_conn =
-                            SyncConnection::new(conn, logger.new(o!("mc.peers.peer_name" => name)));
-                        (responder_id, sync_conn)
-                    })
+                    .map(|conn| Self::sync_conn(conn, &logger))
                     .collect(),
+                logger,
             })),
         }
     }
@@ -63,6 +52,42 @@ impl<C: Connection> ConnectionManager<C> {
         self.inner.read().expect("ConnectionManager lock poisoned")
     }
 
+    fn write(&self) -> RwLockWriteGuard<ConnectionManagerInner<C>> {
+        self.inner.write().expect("ConnectionManager lock poisoned")
+    }
+
+    /// Wrap a connection, keyed by the responder id of its URI.
+    fn sync_conn(conn: C, logger: &Logger) -> (ResponderId, SyncConnection<C>) {
+        let name = conn.to_string();
+        let responder_id = conn
+            .uri()
+            .host_and_port_responder_id()
+            .unwrap_or_else(|err| {
+                panic!(
+                    "Could not create responder_id from {:?}: {}",
+                    conn.uri().to_string(),
+                    err
+                )
+            });
+        let sync_conn = SyncConnection::new(conn, logger.new(o!("mc.peers.peer_name" => name)));
+        (responder_id, sync_conn)
+    }
+
+    /// Add a connection, replacing any existing connection with the same
+    /// responder id. Returns the newly added connection.
+    pub fn add_conn(&self, conn: C) -> SyncConnection<C> {
+        let mut inner = self.write();
+        let (responder_id, sync_conn) = Self::sync_conn(conn, &inner.logger);
+        inner.id_to_conn.insert(responder_id, sync_conn.clone());
+        sync_conn
+    }
+
+    /// Remove the connection for a given ResponderId, returning it if it was
+    /// present.
+    pub fn remove_conn(&self, responder_id: &ResponderId) -> Option<SyncConnection<C>> {
+        self.write().id_to_conn.remove(responder_id)
+    }
+
     /// Retrieve a vector of all the connection URLs owned by this manager.
     pub fn responder_ids(&self) -> Vec<ResponderId> {
         self.read().id_to_conn.keys().cloned().collect()
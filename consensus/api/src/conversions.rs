@@ -83,6 +83,11 @@ impl From<InputRuleError> for ProposeTxResult {
             | InputRuleError::FractionalChangeOutputAmountExceedsLimit => {
                 Self::InputRulePartialFill
             }
+            InputRuleError::ValidityWindowNotSupported
+            | InputRuleError::TombstoneBlockBeforeValidFromBlock
+            | InputRuleError::BlockTimestampUnknown => Self::InputRuleValidityWindow,
+            InputRuleError::BlockIndexBeforeValidFromBlock => Self::InputRuleNotYetValid,
+            InputRuleError::MaxBlockTimestampExceeded => Self::InputRuleMaxBlockTimestampExceeded,
             InputRuleError::RevealedTxOut(rtxo_err) => match rtxo_err {
                 RevealedTxOutError::InvalidAmountSharedSecret => {
                     Self::InputRuleInvalidAmountSharedSecret
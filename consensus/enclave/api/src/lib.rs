@@ -76,6 +76,14 @@ pub struct WellFormedTxContext {
 
     /// Output public keys.
     output_public_keys: Vec<CompressedRistrettoPublic>,
+
+    /// The first block index at which the input rules of the tx allow it to be
+    /// included, or 0.
+    valid_from_block: u64,
+
+    /// The latest block timestamp at which the input rules of the tx allow it
+    /// to be included, or 0 if there is no limit.
+    max_block_timestamp: u64,
 }
 
 impl WellFormedTxContext {
@@ -95,11 +103,28 @@ impl WellFormedTxContext {
             key_images,
             highest_indices,
             output_public_keys,
+            valid_from_block: 0,
+            max_block_timestamp: 0,
         }
     }
 
     /// Create a new WellFormedTxContext, from a Tx and its priority.
     pub fn from_tx(tx: &Tx, priority: u64) -> Self {
+        let input_rules = tx
+            .prefix
+            .inputs
+            .iter()
+            .filter_map(|input| input.input_rules.as_ref());
+        let valid_from_block = input_rules
+            .clone()
+            .map(|rules| rules.valid_from_block)
+            .max()
+            .unwrap_or(0);
+        let max_block_timestamp = input_rules
+            .map(|rules| rules.max_block_timestamp)
+            .filter(|timestamp| *timestamp != 0)
+            .min()
+            .unwrap_or(0);
         Self {
             priority,
             tx_hash: tx.tx_hash(),
@@ -107,6 +132,8 @@ impl WellFormedTxContext {
             key_images: tx.key_images(),
             highest_indices: tx.get_membership_proof_highest_indices(),
             output_public_keys: tx.output_public_keys(),
+            valid_from_block,
+            max_block_timestamp,
         }
     }
 
@@ -139,6 +166,17 @@ impl WellFormedTxContext {
     pub fn output_public_keys(&self) -> &Vec<CompressedRistrettoPublic> {
         &self.output_public_keys
     }
+
+    /// Get the first block index at which the tx can be included
+    pub fn valid_from_block(&self) -> u64 {
+        self.valid_from_block
+    }
+
+    /// Get the latest block timestamp at which the tx can be included, or 0
+    /// if there is no limit
+    pub fn max_block_timestamp(&self) -> u64 {
+        self.max_block_timestamp
+    }
 }
 
 /// Defines a sort order for transactions in a block.
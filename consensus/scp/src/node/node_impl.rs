@@ -308,6 +308,31 @@ impl<V: Value, ValidationError: Clone + Display + 'static> ScpNode<V> for Node<V
 
         self.externalized_slots.clear();
     }
+
+    /// Replace the local node quorum set.
+    fn set_quorum_set(&mut self, quorum_set: QuorumSet) {
+        log::info!(
+            self.logger,
+            "Quorum set changed from {:?} to {:?} at slot {}",
+            self.Q,
+            quorum_set,
+            self.current_slot_index()
+        );
+        self.Q = quorum_set;
+
+        // A slot that has not participated yet can safely be restarted with the
+        // new quorum set.
+        if self.current_slot.get_last_message_sent().is_none() {
+            self.current_slot = Box::new(Slot::new(
+                self.ID.clone(),
+                self.Q.clone(),
+                self.current_slot_index(),
+                self.validity_fn.clone(),
+                self.combine_fn.clone(),
+                self.logger.clone(),
+            ));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -726,6 +751,49 @@ mod tests {
         assert_eq!(node.externalized_slots.len(), 0);
     }
 
+    #[test_with_logger]
+    // A slot that has not issued any message should be restarted with the new
+    // quorum set.
+    fn test_set_quorum_set_restarts_idle_slot(logger: Logger) {
+        let slot_index = 5;
+        let mut node = get_node(slot_index, logger);
+
+        let new_quorum_set = QuorumSet::new_with_node_ids(1, vec![test_node_id(3)]);
+        node.set_quorum_set(new_quorum_set.clone());
+        assert_eq!(node.quorum_set(), new_quorum_set);
+        assert_eq!(node.current_slot_index(), slot_index);
+
+        let msg = node
+            .propose_values(btreeset!["a"])
+            .expect("error proposing values")
+            .expect("no msg?");
+        assert_eq!(msg.quorum_set, new_quorum_set);
+    }
+
+    #[test_with_logger]
+    // A slot that already issued a message should keep its quorum set.
+    fn test_set_quorum_set_keeps_active_slot(logger: Logger) {
+        let slot_index = 5;
+        let mut node = get_node(slot_index, logger);
+        let old_quorum_set = node.quorum_set();
+
+        let msg = node
+            .propose_values(btreeset!["a"])
+            .expect("error proposing values")
+            .expect("no msg?");
+        assert_eq!(msg.quorum_set, old_quorum_set);
+
+        let new_quorum_set = QuorumSet::new_with_node_ids(1, vec![test_node_id(3)]);
+        node.set_quorum_set(new_quorum_set.clone());
+        assert_eq!(node.quorum_set(), new_quorum_set);
+
+        let msg = node
+            .propose_values(btreeset!["b"])
+            .expect("error proposing values")
+            .expect("no msg?");
+        assert_eq!(msg.quorum_set, old_quorum_set);
+    }
+
     #[test_with_logger]
     /// Steps through a sequence of messages that allow a two-node network to
     /// reach consensus.
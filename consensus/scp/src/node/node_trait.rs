@@ -47,4 +47,11 @@ pub trait ScpNode<V: Value>: Send {
     /// Set the node's current slot index, abandoning any current and
     /// externalized slots.
     fn reset_slot_index(&mut self, slot_index: SlotIndex);
+
+    /// Replace the local node quorum set.
+    ///
+    /// If the current slot has not yet issued any message it is restarted with
+    /// the new quorum set, otherwise the new quorum set takes effect from the
+    /// next slot.
+    fn set_quorum_set(&mut self, quorum_set: QuorumSet);
 }
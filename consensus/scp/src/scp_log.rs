@@ -306,6 +306,10 @@ impl<V: Value, N: ScpNode<V>> ScpNode<V> for LoggingScpNode<V, N> {
     fn reset_slot_index(&mut self, slot_index: SlotIndex) {
         self.node.reset_slot_index(slot_index)
     }
+
+    fn set_quorum_set(&mut self, quorum_set: QuorumSet) {
+        self.node.set_quorum_set(quorum_set)
+    }
 }
 
 /// An SCP log reader, to read a series of SCP messages.
@@ -0,0 +1,73 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Admission control configuration for the client API.
+
+use clap::Args;
+
+/// Default number of proposals a single client session may burst.
+pub const DEFAULT_CLIENT_SESSION_BURST: u32 = 10;
+
+/// Default number of proposals a single client IP address may burst.
+pub const DEFAULT_CLIENT_IP_BURST: u32 = 50;
+
+/// Limits applied to transactions proposed through the client API, before they
+/// are handed to the tx manager.
+///
+/// Every limit is optional, and admission control is effectively disabled when
+/// none of them are set.
+#[derive(Clone, Debug, Args, PartialEq)]
+pub struct AdmissionControlConfig {
+    /// Sustained number of tx proposals per second allowed from a single
+    /// attested client session.
+    #[clap(long, env = "MC_CLIENT_SESSION_TX_RATE")]
+    pub client_session_tx_rate: Option<f64>,
+
+    /// Number of tx proposals a single client session may submit in a burst
+    /// before being throttled to `client_session_tx_rate`.
+    #[clap(long, default_value_t = DEFAULT_CLIENT_SESSION_BURST, env = "MC_CLIENT_SESSION_TX_BURST")]
+    pub client_session_tx_burst: u32,
+
+    /// Sustained number of tx proposals per second allowed from a single
+    /// client IP address, across all of its sessions.
+    #[clap(long, env = "MC_CLIENT_IP_TX_RATE")]
+    pub client_ip_tx_rate: Option<f64>,
+
+    /// Number of tx proposals a single client IP address may submit in a burst
+    /// before being throttled to `client_ip_tx_rate`.
+    #[clap(long, default_value_t = DEFAULT_CLIENT_IP_BURST, env = "MC_CLIENT_IP_TX_BURST")]
+    pub client_ip_tx_burst: u32,
+
+    /// Maximum number of transactions proposed by a single client session that
+    /// may be waiting in the tx cache at any given time.
+    #[clap(long, env = "MC_MAX_PENDING_TXS_PER_CLIENT")]
+    pub max_pending_txs_per_client: Option<usize>,
+
+    /// Number of pending values above which newly proposed transactions are
+    /// turned away with a back-pressure result, asking the client to retry
+    /// later. This should be lower than the hard capacity limit of the node.
+    #[clap(long, env = "MC_PENDING_VALUES_HIGH_WATER_MARK")]
+    pub pending_values_high_water_mark: Option<i64>,
+}
+
+impl AdmissionControlConfig {
+    /// Returns true if any admission control limit is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.client_session_tx_rate.is_some()
+            || self.client_ip_tx_rate.is_some()
+            || self.max_pending_txs_per_client.is_some()
+            || self.pending_values_high_water_mark.is_some()
+    }
+}
+
+impl Default for AdmissionControlConfig {
+    fn default() -> Self {
+        Self {
+            client_session_tx_rate: None,
+            client_session_tx_burst: DEFAULT_CLIENT_SESSION_BURST,
+            client_ip_tx_rate: None,
+            client_ip_tx_burst: DEFAULT_CLIENT_IP_BURST,
+            max_pending_txs_per_client: None,
+            pending_values_high_water_mark: None,
+        }
+    }
+}
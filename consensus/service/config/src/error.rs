@@ -70,14 +70,27 @@ pub enum Error {
     /// Known peers should not contain our peer responder id ({0})
     KnownPeersContainsSelf(ResponderId),
 
-    /// Missing tx_source_urls
-    MissingTxSourceUrls,
+    /// Invalid quorum set
+    InvalidQuorumSet,
+
+    /// Quorum set member {0} is not one of the configured peers
+    UnknownQuorumSetMember(ResponderId),
 
     /// Missing governors_signature configuration key
     MissingGovernorsSignature,
 
     /// Signature error: {0}
     Signature(SignatureError),
+
+    /// Network reload sequence number {0} is not newer than the last accepted
+    /// sequence number {1}
+    StaleReloadSequence(u64, u64),
+
+    /// Network reload sequence file {0} does not contain an accepted reload
+    InvalidReloadSequenceFile(String),
+
+    /// Network reload cannot change tx_source_urls without a restart
+    TxSourceUrlsChanged,
 }
 
 impl From<IoError> for Error {
@@ -3,14 +3,20 @@
 //! Configuration parameters for the Consensus Service application.
 #![deny(missing_docs)]
 
+mod admission_control;
 mod error;
 mod network;
+mod network_reload;
 mod signer_identity;
 mod tokens;
 
 pub use crate::{
+    admission_control::AdmissionControlConfig,
     error::Error,
     network::NetworkConfig,
+    network_reload::{
+        sign_network_reload, verify_network_reload, NetworkReloadSequence, NETWORK_RELOAD_CONTEXT,
+    },
     signer_identity::{Error as SignerIdentityError, SignerIdentity, SignerIdentityMap},
     tokens::TokensConfig,
 };
@@ -18,7 +24,7 @@ pub use crate::{
 use base64::{engine::general_purpose::STANDARD as BASE64_ENGINE, Engine};
 use clap::Parser;
 use mc_common::{NodeID, ResponderId};
-use mc_crypto_keys::{DistinguishedEncoding, Ed25519Pair, Ed25519Private};
+use mc_crypto_keys::{DistinguishedEncoding, Ed25519Pair, Ed25519Private, Ed25519Public};
 use mc_transaction_core::BlockVersion;
 use mc_util_parse::parse_duration_in_seconds;
 use mc_util_uri::{AdminUri, ConsensusClientUri as ClientUri, ConsensusPeerUri as PeerUri};
@@ -127,6 +133,32 @@ pub struct Config {
     /// config setting to match.
     #[clap(long, default_value = "10000", env = "MC_CLIENT_TRACKING_CAPACITY")]
     pub client_tracking_capacity: usize,
+
+    /// Rate limits and back-pressure applied to client tx proposals.
+    #[clap(flatten)]
+    pub admission_control: AdmissionControlConfig,
+
+    /// Public key authorized to sign network configuration reloads submitted
+    /// through the admin API.
+    ///
+    /// The value provided via config is a base64 DER-encoded Ed25519 public
+    /// key. Network configuration reloads are disabled when this is not set.
+    #[clap(
+        long,
+        value_parser = public_key_from_base64,
+        env = "MC_NETWORK_ADMIN_PUBLIC_KEY",
+        requires = "network_reload_sequence_path"
+    )]
+    pub network_admin_public_key: Option<Ed25519Public>,
+
+    /// File recording the sequence number and network configuration of the
+    /// last network configuration reload accepted by this node. Reloads whose
+    /// sequence number is not greater than the recorded one are rejected, so
+    /// that old signed reloads cannot be replayed. The recorded configuration
+    /// takes precedence over network_path on restart. Required when
+    /// network_admin_public_key is set.
+    #[clap(long, env = "MC_NETWORK_RELOAD_SEQUENCE_PATH")]
+    pub network_reload_sequence_path: Option<PathBuf>,
 }
 
 impl Config {
@@ -178,6 +210,19 @@ fn keypair_from_base64(private_key: &str) -> Result<Arc<Ed25519Pair>, String> {
     Ok(Arc::new(Ed25519Pair::from(secret_key)))
 }
 
+/// Decodes an Ed25519 public key.
+///
+/// # Arguments
+/// * `public_key` - A DER formatted, Base64 encoded Ed25519 public key.
+fn public_key_from_base64(public_key: &str) -> Result<Ed25519Public, String> {
+    let pubkey_bytes = BASE64_ENGINE
+        .decode(public_key)
+        .map_err(|err| format!("Could not decode public key from base64 {err:?}"))?;
+
+    Ed25519Public::try_from_der(pubkey_bytes.as_slice())
+        .map_err(|err| format!("Could not get Ed25519Public from der {err:?}"))
+}
+
 /// Helper for parsing a BlockVersion
 fn parse_block_version(s: &str) -> Result<BlockVersion, String> {
     // FromStr for BlockVersion uses BlockVersionError, which is not easily
@@ -213,6 +258,9 @@ mod tests {
             tokens_path: None,
             block_version: BlockVersion::ZERO,
             client_tracking_capacity: 4096,
+            admission_control: AdmissionControlConfig::default(),
+            network_admin_public_key: None,
+            network_reload_sequence_path: None,
         };
 
         assert_eq!(
@@ -280,6 +328,9 @@ mod tests {
             tokens_path: None,
             block_version: BlockVersion::ZERO,
             client_tracking_capacity: 4096,
+            admission_control: AdmissionControlConfig::default(),
+            network_admin_public_key: None,
+            network_reload_sequence_path: None,
         };
 
         assert_eq!(
@@ -18,7 +18,9 @@ pub struct NetworkConfig {
     /// List of peers we connect to.
     pub broadcast_peers: Vec<PeerUri>,
 
-    /// List of URLs to use for transaction data.
+    /// List of URLs to use for transaction data. If empty, transaction data
+    /// is fetched from peers.
+    #[serde(default)]
     pub tx_source_urls: Vec<String>,
 
     /// Optional list of peers we are aware of.
@@ -44,14 +46,32 @@ impl NetworkConfig {
             Some(ext) => Err(Error::UnrecognizedExtension(ext.to_string())),
         }?;
 
+        network.validate(peer_responder_id)?;
+
+        // Success.
+        Ok(network)
+    }
+
+    /// Parse a JSON-encoded network configuration, performing the same sanity
+    /// checks as [NetworkConfig::load_from_path], and additionally checking
+    /// that the quorum set can be resolved.
+    pub fn load_from_json(data: &str, peer_responder_id: &ResponderId) -> Result<Self, Error> {
+        let network: Self = serde_json::from_str(data)?;
+        network.validate(peer_responder_id)?;
+        network.validate_quorum_set()?;
+        Ok(network)
+    }
+
+    /// Sanity-check the peers configuration.
+    pub fn validate(&self, peer_responder_id: &ResponderId) -> Result<(), Error> {
         // Sanity tests:
         // - Our responder ID should not appear in `broadcast_peers` or `known_peers`.
         //   This also ensures it is not part of the quorum set.
         // - Each responder ID is unique.
-        let peer_uris = network
+        let peer_uris = self
             .broadcast_peers
             .iter()
-            .chain(network.known_peers.iter().flatten());
+            .chain(self.known_peers.iter().flatten());
         let mut spotted_responder_ids = HashSet::default();
         for peer_uri in peer_uris {
             let responder_id = peer_uri
@@ -67,14 +87,35 @@ impl NetworkConfig {
             }
         }
 
-        // Sanity test: We should have at least one source of transactions, if we have
-        // any peers configured.
-        if !network.broadcast_peers.is_empty() && network.tx_source_urls.is_empty() {
-            return Err(Error::MissingTxSourceUrls);
+        Ok(())
+    }
+
+    /// Check that the quorum set is valid and that every node in it is one of
+    /// the configured peers, so that [NetworkConfig::quorum_set] will not
+    /// panic.
+    pub fn validate_quorum_set(&self) -> Result<(), Error> {
+        if !self.quorum_set.is_valid() {
+            return Err(Error::InvalidQuorumSet);
         }
 
-        // Success.
-        Ok(network)
+        let peer_responder_ids = self
+            .broadcast_peers
+            .iter()
+            .chain(self.known_peers.iter().flatten())
+            .map(|peer_uri| {
+                peer_uri
+                    .responder_id()
+                    .map_err(|err| Error::UriConversion(peer_uri.to_string(), err))
+            })
+            .collect::<Result<HashSet<_>, _>>()?;
+
+        for responder_id in self.quorum_set.nodes() {
+            if !peer_responder_ids.contains(&responder_id) {
+                return Err(Error::UnknownQuorumSetMember(responder_id));
+            }
+        }
+
+        Ok(())
     }
 
     /// Construct a quorum set from the configuration.
@@ -288,4 +329,42 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_load_from_json_validates_quorum_set() {
+        let local = ResponderId::from_str("0.0.0.0:8081").unwrap();
+        let valid_json: &str = r#"{
+            "broadcast_peers": [
+                "insecure-mcp://0.0.0.0:8082?consensus-msg-key=MCowBQYDK2VwAyEA_ii3rCch5qhMbLZ2vVgpQr1iTrq1BBN2-i0mMPuAJhQ="
+            ],
+            "tx_source_urls": ["file:///tmp/dump"],
+            "quorum_set": { "threshold": 1, "members": [
+                { "type": "Node", "args": "0.0.0.0:8082" }
+            ] }
+        }"#;
+        let network = NetworkConfig::load_from_json(valid_json, &local).unwrap();
+        assert_eq!(network.quorum_set().members.len(), 1);
+
+        // Quorum set references a node we have no URI for.
+        let unknown_member_json =
+            valid_json.replace(r#""args": "0.0.0.0:8082""#, r#""args": "0.0.0.0:8083""#);
+        assert!(matches!(
+            NetworkConfig::load_from_json(&unknown_member_json, &local),
+            Err(Error::UnknownQuorumSetMember(_))
+        ));
+
+        // Threshold larger than the number of members.
+        let invalid_json = valid_json.replace(r#""threshold": 1"#, r#""threshold": 2"#);
+        assert!(matches!(
+            NetworkConfig::load_from_json(&invalid_json, &local),
+            Err(Error::InvalidQuorumSet)
+        ));
+
+        // Our own responder id may not be one of the peers.
+        let local = ResponderId::from_str("0.0.0.0:8082").unwrap();
+        assert!(matches!(
+            NetworkConfig::load_from_json(valid_json, &local),
+            Err(Error::KnownPeersContainsSelf(_))
+        ));
+    }
 }
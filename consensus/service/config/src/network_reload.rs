@@ -0,0 +1,221 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Signing and verification of network configuration reload requests.
+//!
+//! A reload request carries the new network configuration as JSON, a sequence
+//! number, and a tombstone block index past which the request is no longer
+//! accepted. All three are covered by an Ed25519 signature from the network
+//! admin key. Each node persists the last reload it accepted, and rejects any
+//! request whose sequence number is not newer, so a captured request cannot be
+//! replayed to roll the configuration back. The persisted configuration
+//! replaces the network file when the node restarts.
+
+use crate::error::Error;
+use mc_crypto_keys::{Ed25519Pair, Ed25519Public, Ed25519Signature, Signer, Verifier};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+/// Domain separator for network configuration reload signatures.
+pub const NETWORK_RELOAD_CONTEXT: &[u8] = b"mc-consensus-network-config-reload";
+
+/// Construct the message that is signed for a network configuration reload.
+fn reload_message(network_config_json: &str, sequence: u64, tombstone_block: u64) -> Vec<u8> {
+    let mut message =
+        Vec::with_capacity(NETWORK_RELOAD_CONTEXT.len() + 16 + network_config_json.len());
+    message.extend_from_slice(NETWORK_RELOAD_CONTEXT);
+    message.extend_from_slice(&sequence.to_le_bytes());
+    message.extend_from_slice(&tombstone_block.to_le_bytes());
+    message.extend_from_slice(network_config_json.as_bytes());
+    message
+}
+
+/// Sign a network configuration reload request.
+pub fn sign_network_reload(
+    signer: &Ed25519Pair,
+    network_config_json: &str,
+    sequence: u64,
+    tombstone_block: u64,
+) -> Ed25519Signature {
+    signer.sign(&reload_message(
+        network_config_json,
+        sequence,
+        tombstone_block,
+    ))
+}
+
+/// Verify the signature on a network configuration reload request.
+pub fn verify_network_reload(
+    public_key: &Ed25519Public,
+    network_config_json: &str,
+    sequence: u64,
+    tombstone_block: u64,
+    signature: &Ed25519Signature,
+) -> Result<(), Error> {
+    public_key.verify(
+        &reload_message(network_config_json, sequence, tombstone_block),
+        signature,
+    )?;
+    Ok(())
+}
+
+/// The last network configuration reload accepted by this node, persisted to a
+/// file so that it survives restarts.
+#[derive(Debug)]
+pub struct NetworkReloadSequence {
+    /// File holding the last accepted reload, as JSON.
+    path: PathBuf,
+
+    /// The last accepted sequence number, or 0 if no reload has been accepted.
+    last: u64,
+
+    /// The network configuration JSON of the last accepted reload.
+    network_config_json: Option<String>,
+}
+
+/// The contents of the file backing a [NetworkReloadSequence].
+#[derive(Deserialize, Serialize)]
+struct AcceptedReload {
+    sequence: u64,
+    network_config_json: String,
+}
+
+impl NetworkReloadSequence {
+    /// Load the last accepted reload from `path`. A missing file means no
+    /// reload has been accepted yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let accepted = match fs::read_to_string(&path) {
+            Ok(data) => Some(
+                serde_json::from_str::<AcceptedReload>(&data)
+                    .map_err(|_| Error::InvalidReloadSequenceFile(path.display().to_string()))?,
+            ),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(match accepted {
+            Some(accepted) => Self {
+                path,
+                last: accepted.sequence,
+                network_config_json: Some(accepted.network_config_json),
+            },
+            None => Self {
+                path,
+                last: 0,
+                network_config_json: None,
+            },
+        })
+    }
+
+    /// The last accepted sequence number, or 0 if no reload has been accepted.
+    pub fn last(&self) -> u64 {
+        self.last
+    }
+
+    /// The network configuration JSON of the last accepted reload, if any.
+    pub fn network_config_json(&self) -> Option<&str> {
+        self.network_config_json.as_deref()
+    }
+
+    /// Check that `sequence` is newer than the last accepted sequence number.
+    pub fn check(&self, sequence: u64) -> Result<(), Error> {
+        if sequence <= self.last {
+            return Err(Error::StaleReloadSequence(sequence, self.last));
+        }
+        Ok(())
+    }
+
+    /// Record the reload with the given `sequence` and network configuration
+    /// as accepted. The sequence number and the configuration are written to
+    /// the same file, which is replaced atomically, so a crash leaves either
+    /// the old or the new reload in place.
+    pub fn advance(&mut self, sequence: u64, network_config_json: &str) -> Result<(), Error> {
+        self.check(sequence)?;
+
+        let data = serde_json::to_string(&AcceptedReload {
+            sequence,
+            network_config_json: network_config_json.to_owned(),
+        })?;
+        let tmp_path = self.path.with_extension("tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(data.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        self.last = sequence;
+        self.network_config_json = Some(network_config_json.to_owned());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_crypto_keys::Ed25519Private;
+    use tempfile::TempDir;
+
+    fn keypair(seed: u8) -> Ed25519Pair {
+        Ed25519Pair::from(Ed25519Private::try_from(&[seed; 32][..]).unwrap())
+    }
+
+    #[test]
+    fn sign_and_verify_round_trip() {
+        let signer = keypair(1);
+        let json = r#"{"quorum_set": {}}"#;
+
+        let signature = sign_network_reload(&signer, json, 3, 10);
+        verify_network_reload(&signer.public_key(), json, 3, 10, &signature).unwrap();
+
+        // Changing the payload, the sequence or the tombstone invalidates the
+        // signature.
+        assert!(verify_network_reload(&signer.public_key(), json, 3, 11, &signature).is_err());
+        assert!(verify_network_reload(&signer.public_key(), json, 4, 10, &signature).is_err());
+        assert!(verify_network_reload(&signer.public_key(), "{}", 3, 10, &signature).is_err());
+
+        let other = keypair(2);
+        assert!(verify_network_reload(&other.public_key(), json, 3, 10, &signature).is_err());
+    }
+
+    #[test]
+    fn sequence_only_moves_forward_and_persists() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("network-reload-sequence");
+
+        let mut sequence = NetworkReloadSequence::load(&path).unwrap();
+        assert_eq!(sequence.last(), 0);
+        assert_eq!(sequence.network_config_json(), None);
+        assert!(sequence.check(0).is_err());
+
+        sequence.advance(5, "five").unwrap();
+        assert_eq!(sequence.last(), 5);
+        assert!(sequence.advance(5, "again").is_err());
+        assert!(sequence.advance(4, "four").is_err());
+        assert_eq!(sequence.network_config_json(), Some("five"));
+
+        // The accepted reload survives a restart.
+        let mut reloaded = NetworkReloadSequence::load(&path).unwrap();
+        assert_eq!(reloaded.last(), 5);
+        assert_eq!(reloaded.network_config_json(), Some("five"));
+        assert!(reloaded.check(5).is_err());
+        reloaded.advance(6, "six").unwrap();
+
+        let reloaded = NetworkReloadSequence::load(&path).unwrap();
+        assert_eq!(reloaded.last(), 6);
+        assert_eq!(reloaded.network_config_json(), Some("six"));
+    }
+
+    #[test]
+    fn corrupt_sequence_file_is_rejected() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("network-reload-sequence");
+        fs::write(&path, "not a number").unwrap();
+
+        assert!(matches!(
+            NetworkReloadSequence::load(&path),
+            Err(Error::InvalidReloadSequenceFile(_))
+        ));
+    }
+}
@@ -0,0 +1,562 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Serves administrative API requests that change the node's configuration
+//! at runtime.
+
+use crate::{counters, SVC_COUNTERS};
+use grpcio::{RpcContext, RpcStatus, UnarySink};
+use mc_common::{
+    logger::{log, Logger},
+    ResponderId,
+};
+use mc_consensus_api::{
+    consensus_admin::{
+        PeerAttestationStatus, PeerHealth as ProtoPeerHealth, PeerHealthResponse,
+        PeerHealthStatus as ProtoPeerHealthStatus, ReloadNetworkConfigRequest,
+        ReloadNetworkConfigResponse,
+    },
+    consensus_admin_grpc::ConsensusAdminApi,
+    empty::Empty,
+};
+use mc_consensus_service_config::{
+    verify_network_reload, Error as ConfigError, NetworkConfig, NetworkReloadSequence,
+};
+use mc_crypto_keys::{Ed25519Public, Ed25519Signature};
+use mc_ledger_db::Ledger;
+use mc_peers::{PeerHealth, PeerHealthStatus};
+use mc_util_grpc::{
+    rpc_database_err, rpc_internal_error, rpc_invalid_arg_error, rpc_logger, rpc_permissions_error,
+    rpc_precondition_error, send_result,
+};
+use protobuf::RepeatedField;
+use std::{
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+
+/// Callback for scheduling a validated network configuration to be applied at
+/// the next slot boundary.
+pub type UpdateNetworkFn = Arc<dyn Fn(NetworkConfig) -> Result<(), String> + Sync + Send>;
+
+/// Callback for getting the health of the peers the node broadcasts to.
+pub type PeerHealthFn = Arc<dyn Fn() -> Vec<PeerHealth> + Sync + Send>;
+
+#[derive(Clone)]
+pub struct ConsensusAdminService {
+    /// Key that must sign network configuration reloads. Reloads are rejected
+    /// when this is not set.
+    network_admin_public_key: Option<Ed25519Public>,
+
+    /// The last accepted reload, shared with the service so that it can report
+    /// the accepted configuration. Reloads are rejected when this is not set.
+    reload_sequence: Option<Arc<Mutex<NetworkReloadSequence>>>,
+
+    /// The tx_source_urls of the running configuration. These are only read at
+    /// startup, so a reload must not change them.
+    tx_source_urls: Vec<String>,
+
+    /// The local node's peer responder ID.
+    peer_responder_id: ResponderId,
+
+    /// Ledger database.
+    ledger: Arc<dyn Ledger + Send + Sync>,
+
+    /// Callback for applying a new network configuration.
+    update_network_fn: UpdateNetworkFn,
+
+    /// Callback for getting peer health.
+    peer_health_fn: PeerHealthFn,
+
+    /// Logger.
+    logger: Logger,
+}
+
+impl ConsensusAdminService {
+    /// Creates a ConsensusAdminService.
+    ///
+    /// # Arguments:
+    /// * `network_admin_public_key` - Key that must sign network reloads, if
+    ///   reloads are enabled.
+    /// * `reload_sequence` - The last accepted reload, if reloads are enabled.
+    /// * `tx_source_urls` - The tx_source_urls the node was started with.
+    /// * `peer_responder_id` - The local node's peer responder ID.
+    /// * `ledger` - The local node's ledger.
+    /// * `update_network_fn` - Applies a validated network configuration.
+    /// * `peer_health_fn` - Returns the health of our peers.
+    /// * `logger` - Logger.
+    pub fn new(
+        network_admin_public_key: Option<Ed25519Public>,
+        reload_sequence: Option<Arc<Mutex<NetworkReloadSequence>>>,
+        tx_source_urls: Vec<String>,
+        peer_responder_id: ResponderId,
+        ledger: Arc<dyn Ledger + Send + Sync>,
+        update_network_fn: UpdateNetworkFn,
+        peer_health_fn: PeerHealthFn,
+        logger: Logger,
+    ) -> Self {
+        Self {
+            network_admin_public_key,
+            reload_sequence,
+            tx_source_urls,
+            peer_responder_id,
+            ledger,
+            update_network_fn,
+            peer_health_fn,
+            logger,
+        }
+    }
+
+    fn reload_network_config_impl(
+        &mut self,
+        request: ReloadNetworkConfigRequest,
+        logger: &Logger,
+    ) -> Result<ReloadNetworkConfigResponse, RpcStatus> {
+        let network_admin_public_key = self.network_admin_public_key.as_ref().ok_or_else(|| {
+            rpc_precondition_error(
+                "reload_network_config",
+                "network admin public key is not configured",
+                logger,
+            )
+        })?;
+        let reload_sequence = self.reload_sequence.as_ref().ok_or_else(|| {
+            rpc_precondition_error(
+                "reload_network_config",
+                "network reload sequence path is not configured",
+                logger,
+            )
+        })?;
+
+        let signature = Ed25519Signature::try_from(request.get_signature().get_data())
+            .map_err(|err| rpc_invalid_arg_error("signature", err, logger))?;
+
+        verify_network_reload(
+            network_admin_public_key,
+            request.get_network_config_json(),
+            request.get_sequence(),
+            request.get_tombstone_block(),
+            &signature,
+        )
+        .map_err(|err| rpc_permissions_error("reload_network_config", err, logger))?;
+
+        let num_blocks = self
+            .ledger
+            .num_blocks()
+            .map_err(|err| rpc_database_err(err, logger))?;
+        if request.get_tombstone_block() <= num_blocks {
+            return Err(rpc_precondition_error(
+                "tombstone_block",
+                format!(
+                    "tombstone block {} has passed (ledger has {} blocks)",
+                    request.get_tombstone_block(),
+                    num_blocks
+                ),
+                logger,
+            ));
+        }
+
+        let network_config = NetworkConfig::load_from_json(
+            request.get_network_config_json(),
+            &self.peer_responder_id,
+        )
+        .map_err(|err| rpc_invalid_arg_error("network_config_json", err, logger))?;
+
+        // known_peers are applied along with the quorum set they help resolve, but the
+        // transactions fetcher is only built at startup.
+        if network_config.tx_source_urls != self.tx_source_urls {
+            return Err(rpc_invalid_arg_error(
+                "network_config_json",
+                ConfigError::TxSourceUrlsChanged,
+                logger,
+            ));
+        }
+
+        // The sequence number and the configuration are recorded before the update is
+        // scheduled, so that a crash in between cannot leave a replayable request
+        // behind, and the node restarts with the accepted configuration. Holding the
+        // lock until the update is scheduled keeps concurrent reloads in
+        // sequence order.
+        let mut reload_sequence = reload_sequence.lock().expect("mutex poisoned");
+        reload_sequence
+            .check(request.get_sequence())
+            .map_err(|err| rpc_precondition_error("sequence", err, logger))?;
+        reload_sequence
+            .advance(request.get_sequence(), request.get_network_config_json())
+            .map_err(|err| rpc_internal_error("reload_sequence", err, logger))?;
+
+        (self.update_network_fn)(network_config)
+            .map_err(|err| rpc_internal_error("update_network", err, logger))?;
+
+        log::info!(
+            logger,
+            "Accepted network configuration reload {} at block {}",
+            request.get_sequence(),
+            num_blocks
+        );
+
+        let mut response = ReloadNetworkConfigResponse::new();
+        response.set_accepted_at_block(num_blocks);
+        Ok(response)
+    }
+}
+
+/// Convert peer health into its protobuf representation.
+fn peer_health_to_proto(health: &PeerHealth) -> ProtoPeerHealth {
+    let mut proto = ProtoPeerHealth::new();
+    proto.set_responder_id(health.responder_id.to_string());
+    proto.set_status(match health.status() {
+        PeerHealthStatus::Unknown => ProtoPeerHealthStatus::UnknownHealth,
+        PeerHealthStatus::Healthy => ProtoPeerHealthStatus::Healthy,
+        PeerHealthStatus::Degraded => ProtoPeerHealthStatus::Degraded,
+        PeerHealthStatus::Unhealthy => ProtoPeerHealthStatus::Unhealthy,
+    });
+    proto.set_latency_ms(
+        health
+            .latency
+            .map_or(0, |latency| latency.as_millis() as u64),
+    );
+    proto.set_error_rate(health.error_rate);
+    proto.set_successes(health.successes);
+    proto.set_failures(health.failures);
+    proto.set_consecutive_failures(health.consecutive_failures);
+    proto.set_last_success_timestamp(
+        health
+            .last_success
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_secs()),
+    );
+    proto.set_last_error(health.last_error.clone().unwrap_or_default());
+    proto.set_attestation_status(match health.attested {
+        None => PeerAttestationStatus::AttestationUnknown,
+        Some(true) => PeerAttestationStatus::Attested,
+        Some(false) => PeerAttestationStatus::AttestationFailed,
+    });
+    proto.set_dropped_propose_txs(health.dropped_propose_txs);
+    proto
+}
+
+impl ConsensusAdminApi for ConsensusAdminService {
+    fn reload_network_config(
+        &mut self,
+        ctx: RpcContext,
+        request: ReloadNetworkConfigRequest,
+        sink: UnarySink<ReloadNetworkConfigResponse>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        counters::NETWORK_RELOAD_REQUESTS.inc();
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            let result = self.reload_network_config_impl(request, logger);
+            if result.is_err() {
+                counters::NETWORK_RELOAD_REJECTED.inc();
+            }
+            send_result(ctx, sink, result, logger)
+        });
+    }
+
+    fn get_peer_health(
+        &mut self,
+        ctx: RpcContext,
+        _request: Empty,
+        sink: UnarySink<PeerHealthResponse>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            let mut response = PeerHealthResponse::new();
+            response.set_peers(RepeatedField::from_vec(
+                (self.peer_health_fn)()
+                    .iter()
+                    .map(peer_health_to_proto)
+                    .collect(),
+            ));
+            send_result(ctx, sink, Ok(response), logger)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grpcio::{ChannelBuilder, Environment, Server, ServerBuilder, ServerCredentials};
+    use mc_common::logger::test_with_logger;
+    use mc_consensus_api::{
+        consensus_admin_grpc::{create_consensus_admin_api, ConsensusAdminApiClient},
+        external,
+    };
+    use mc_consensus_service_config::sign_network_reload;
+    use mc_crypto_keys::{Ed25519Pair, Ed25519Private};
+    use mc_ledger_db::MockLedger;
+    use std::{path::Path, str::FromStr, time::Duration};
+    use tempfile::TempDir;
+
+    const NETWORK_JSON: &str = r#"{
+        "broadcast_peers": [
+            "insecure-mcp://node2.test:8443?consensus-msg-key=MCowBQYDK2VwAyEA_ii3rCch5qhMbLZ2vVgpQr1iTrq1BBN2-i0mMPuAJhQ="
+        ],
+        "tx_source_urls": ["file:///tmp/dump"],
+        "quorum_set": { "threshold": 1, "members": [
+            { "type": "Node", "args": "node2.test:8443" }
+        ] }
+    }"#;
+
+    fn admin_keypair() -> Ed25519Pair {
+        Ed25519Pair::from(Ed25519Private::try_from(&[7u8; 32][..]).unwrap())
+    }
+
+    fn get_client_server(
+        network_admin_public_key: Option<Ed25519Public>,
+        reload_sequence_path: &Path,
+        num_blocks: u64,
+        applied: Arc<Mutex<Vec<NetworkConfig>>>,
+        peer_health: Vec<PeerHealth>,
+        logger: Logger,
+    ) -> (ConsensusAdminApiClient, Server) {
+        let mut ledger = MockLedger::new();
+        ledger.expect_num_blocks().return_const(Ok(num_blocks));
+
+        let update_network_fn: UpdateNetworkFn = Arc::new(move |network_config| {
+            applied.lock().unwrap().push(network_config);
+            Ok(())
+        });
+
+        let reload_sequence = network_admin_public_key
+            .map(|_| NetworkReloadSequence::load(reload_sequence_path).unwrap())
+            .map(|sequence| Arc::new(Mutex::new(sequence)));
+
+        let instance = ConsensusAdminService::new(
+            network_admin_public_key,
+            reload_sequence,
+            vec!["file:///tmp/dump".to_owned()],
+            ResponderId::from_str("node1.test:8443").unwrap(),
+            Arc::new(ledger),
+            update_network_fn,
+            Arc::new(move || peer_health.clone()),
+            logger,
+        );
+
+        let service = create_consensus_admin_api(instance);
+        let env = Arc::new(Environment::new(1));
+        let mut server = ServerBuilder::new(env.clone())
+            .register_service(service)
+            .build()
+            .expect("Could not create GRPC server");
+        let port = server
+            .add_listening_port("127.0.0.1:0", ServerCredentials::insecure())
+            .expect("Could not create anonymous bind");
+        server.start();
+        let ch = ChannelBuilder::new(env).connect(&format!("127.0.0.1:{port}"));
+        (ConsensusAdminApiClient::new(ch), server)
+    }
+
+    fn request(
+        signer: &Ed25519Pair,
+        json: &str,
+        sequence: u64,
+        tombstone_block: u64,
+    ) -> ReloadNetworkConfigRequest {
+        let signature = sign_network_reload(signer, json, sequence, tombstone_block);
+        let mut proto_signature = external::Ed25519Signature::new();
+        proto_signature.set_data(signature.as_ref().to_vec());
+
+        let mut request = ReloadNetworkConfigRequest::new();
+        request.set_network_config_json(json.to_owned());
+        request.set_sequence(sequence);
+        request.set_tombstone_block(tombstone_block);
+        request.set_signature(proto_signature);
+        request
+    }
+
+    #[test_with_logger]
+    fn test_reload_network_config_accepts_signed_request(logger: Logger) {
+        let dir = TempDir::new().unwrap();
+        let sequence_path = dir.path().join("reload-sequence");
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let (client, _server) = get_client_server(
+            Some(admin_keypair().public_key()),
+            &sequence_path,
+            10,
+            applied.clone(),
+            Vec::new(),
+            logger,
+        );
+
+        let response = client
+            .reload_network_config(&request(&admin_keypair(), NETWORK_JSON, 1, 11))
+            .expect("reload failed");
+        assert_eq!(response.get_accepted_at_block(), 10);
+
+        let applied = applied.lock().unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0].broadcast_peers.len(), 1);
+        let accepted = NetworkReloadSequence::load(&sequence_path).unwrap();
+        assert_eq!(accepted.last(), 1);
+        assert_eq!(accepted.network_config_json(), Some(NETWORK_JSON));
+    }
+
+    #[test_with_logger]
+    fn test_reload_network_config_rejects_bad_requests(logger: Logger) {
+        let dir = TempDir::new().unwrap();
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let (client, _server) = get_client_server(
+            Some(admin_keypair().public_key()),
+            &dir.path().join("reload-sequence"),
+            10,
+            applied.clone(),
+            Vec::new(),
+            logger,
+        );
+
+        // Signed by the wrong key.
+        let other = Ed25519Pair::from(Ed25519Private::try_from(&[8u8; 32][..]).unwrap());
+        assert!(client
+            .reload_network_config(&request(&other, NETWORK_JSON, 1, 11))
+            .is_err());
+
+        // Tombstone block has passed.
+        assert!(client
+            .reload_network_config(&request(&admin_keypair(), NETWORK_JSON, 1, 10))
+            .is_err());
+
+        // Signed, but the quorum set references an unknown node.
+        let bad_json = NETWORK_JSON.replace(
+            r#""args": "node2.test:8443""#,
+            r#""args": "node3.test:8443""#,
+        );
+        assert!(client
+            .reload_network_config(&request(&admin_keypair(), &bad_json, 1, 11))
+            .is_err());
+
+        // Signed, but changes tx_source_urls, which only take effect on restart.
+        let urls_json = NETWORK_JSON.replace("file:///tmp/dump", "file:///tmp/other");
+        assert!(client
+            .reload_network_config(&request(&admin_keypair(), &urls_json, 1, 11))
+            .is_err());
+
+        // Signature does not cover a modified payload.
+        let mut tampered = request(&admin_keypair(), NETWORK_JSON, 1, 11);
+        tampered.set_tombstone_block(12);
+        assert!(client.reload_network_config(&tampered).is_err());
+
+        let mut tampered = request(&admin_keypair(), NETWORK_JSON, 1, 11);
+        tampered.set_sequence(2);
+        assert!(client.reload_network_config(&tampered).is_err());
+
+        // The first sequence number is 1.
+        assert!(client
+            .reload_network_config(&request(&admin_keypair(), NETWORK_JSON, 0, 11))
+            .is_err());
+
+        assert!(applied.lock().unwrap().is_empty());
+    }
+
+    #[test_with_logger]
+    fn test_reload_network_config_rejects_replays(logger: Logger) {
+        let dir = TempDir::new().unwrap();
+        let sequence_path = dir.path().join("reload-sequence");
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let (client, server) = get_client_server(
+            Some(admin_keypair().public_key()),
+            &sequence_path,
+            10,
+            applied.clone(),
+            Vec::new(),
+            logger.clone(),
+        );
+
+        let first = request(&admin_keypair(), NETWORK_JSON, 1, 20);
+        let second = request(&admin_keypair(), NETWORK_JSON, 2, 20);
+        client
+            .reload_network_config(&second)
+            .expect("reload failed");
+
+        // Neither the same request nor an older one can be applied again.
+        assert!(client.reload_network_config(&second).is_err());
+        assert!(client.reload_network_config(&first).is_err());
+        assert_eq!(applied.lock().unwrap().len(), 1);
+
+        // The sequence number survives a restart.
+        drop(client);
+        drop(server);
+        let (client, _server) = get_client_server(
+            Some(admin_keypair().public_key()),
+            &sequence_path,
+            10,
+            applied.clone(),
+            Vec::new(),
+            logger,
+        );
+        assert!(client.reload_network_config(&second).is_err());
+        client
+            .reload_network_config(&request(&admin_keypair(), NETWORK_JSON, 3, 20))
+            .expect("reload failed");
+        assert_eq!(applied.lock().unwrap().len(), 2);
+    }
+
+    #[test_with_logger]
+    fn test_reload_network_config_disabled_without_key(logger: Logger) {
+        let dir = TempDir::new().unwrap();
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let (client, _server) = get_client_server(
+            None,
+            &dir.path().join("reload-sequence"),
+            10,
+            applied.clone(),
+            Vec::new(),
+            logger,
+        );
+
+        assert!(client
+            .reload_network_config(&request(&admin_keypair(), NETWORK_JSON, 1, 11))
+            .is_err());
+        assert!(applied.lock().unwrap().is_empty());
+    }
+
+    #[test_with_logger]
+    fn test_get_peer_health(logger: Logger) {
+        let mut healthy = PeerHealth::new(ResponderId::from_str("node2.test:8443").unwrap());
+        healthy.latency = Some(Duration::from_millis(25));
+        healthy.successes = 4;
+        healthy.last_success = Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        healthy.attested = Some(true);
+
+        let mut unhealthy = PeerHealth::new(ResponderId::from_str("node3.test:8443").unwrap());
+        unhealthy.failures = 5;
+        unhealthy.consecutive_failures = 5;
+        unhealthy.error_rate = 0.9;
+        unhealthy.last_error = Some("attestation failed".to_owned());
+        unhealthy.attested = Some(false);
+        unhealthy.dropped_propose_txs = 12;
+
+        let dir = TempDir::new().unwrap();
+        let (client, _server) = get_client_server(
+            None,
+            &dir.path().join("reload-sequence"),
+            10,
+            Default::default(),
+            vec![healthy, unhealthy],
+            logger,
+        );
+
+        let response = client
+            .get_peer_health(&Empty::new())
+            .expect("get_peer_health failed");
+        let peers = response.get_peers();
+        assert_eq!(peers.len(), 2);
+
+        assert_eq!(peers[0].get_responder_id(), "node2.test:8443");
+        assert_eq!(peers[0].get_status(), ProtoPeerHealthStatus::Healthy);
+        assert_eq!(peers[0].get_latency_ms(), 25);
+        assert_eq!(peers[0].get_last_success_timestamp(), 1_700_000_000);
+        assert_eq!(
+            peers[0].get_attestation_status(),
+            PeerAttestationStatus::Attested
+        );
+
+        assert_eq!(peers[1].get_responder_id(), "node3.test:8443");
+        assert_eq!(peers[1].get_status(), ProtoPeerHealthStatus::Unhealthy);
+        assert_eq!(peers[1].get_last_error(), "attestation failed");
+        assert_eq!(
+            peers[1].get_attestation_status(),
+            PeerAttestationStatus::AttestationFailed
+        );
+        assert_eq!(peers[1].get_dropped_propose_txs(), 12);
+    }
+}
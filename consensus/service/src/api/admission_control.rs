@@ -0,0 +1,526 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Admission control for transactions proposed through the client API.
+//!
+//! This throttles individual client sessions and IP addresses using token
+//! buckets, caps how many transactions a single session may have waiting in
+//! the tx cache, and turns clients away when the node has too many pending
+//! values.
+
+use crate::{api::grpc_error::ConsensusGrpcError, counters, tx_manager::TxManager};
+use mc_attest_enclave_api::ClientSession;
+use mc_common::{HashSet, LruCache};
+use mc_consensus_service_config::AdmissionControlConfig;
+use mc_transaction_core::tx::TxHash;
+use std::time::Instant;
+
+/// A token bucket which refills continuously at a fixed rate, up to its
+/// capacity.
+#[derive(Clone, Debug)]
+pub struct TokenBucket {
+    /// Maximum number of tokens the bucket can hold.
+    capacity: f64,
+    /// Number of tokens added per second.
+    refill_rate: f64,
+    /// Number of tokens currently available.
+    tokens: f64,
+    /// The last time tokens were added to the bucket.
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket.
+    pub fn new(capacity: u32, refill_rate: f64, now: Instant) -> Self {
+        let capacity = f64::from(capacity.max(1));
+        Self {
+            capacity,
+            refill_rate,
+            tokens: capacity,
+            last_refill: now,
+        }
+    }
+
+    /// Refill the bucket according to the time elapsed since the last refill,
+    /// then check whether a whole token is available, without taking it.
+    pub fn has_token(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.refill_rate).min(self.capacity);
+        self.last_refill = now;
+
+        self.tokens >= 1.0
+    }
+
+    /// Take a single token from the bucket.
+    ///
+    /// Concurrent requests may all have seen the same token available, so
+    /// the bucket is allowed to go into debt, which later requests pay off by
+    /// waiting longer for a refill.
+    pub fn take(&mut self) {
+        self.tokens -= 1.0;
+    }
+}
+
+/// Tracks per-client state needed to decide whether a proposed transaction
+/// should be admitted.
+pub struct AdmissionControl {
+    config: AdmissionControlConfig,
+
+    /// Token buckets for client sessions.
+    session_buckets: LruCache<ClientSession, TokenBucket>,
+
+    /// Token buckets for client IP addresses.
+    ip_buckets: LruCache<String, TokenBucket>,
+
+    /// Hashes of transactions proposed by each client session which may still
+    /// be in the tx cache.
+    pending_txs: LruCache<ClientSession, HashSet<TxHash>>,
+}
+
+impl AdmissionControl {
+    /// Create a new admission control.
+    ///
+    /// # Arguments
+    /// * `config` - The configured limits.
+    /// * `tracking_capacity` - Maximum number of sessions and IP addresses to
+    ///   track state for. The least recently used entries are evicted first.
+    pub fn new(config: AdmissionControlConfig, tracking_capacity: usize) -> Self {
+        Self {
+            config,
+            session_buckets: LruCache::new(tracking_capacity),
+            ip_buckets: LruCache::new(tracking_capacity),
+            pending_txs: LruCache::new(tracking_capacity),
+        }
+    }
+
+    /// Decide whether a transaction proposed by the given client may be
+    /// admitted.
+    ///
+    /// This doesn't use up any rate limit tokens: they are only charged by
+    /// [AdmissionControl::admit], once the transaction passed validation.
+    ///
+    /// # Arguments
+    /// * `session` - The client session the proposal arrived on.
+    /// * `peer` - The gRPC peer string of the client, e.g. `ipv4:1.2.3.4:5678`.
+    /// * `num_pending_values` - Number of values currently pending in
+    ///   consensus.
+    /// * `tx_manager` - Used to forget about transactions that have left the tx
+    ///   cache.
+    /// * `now` - The current time.
+    pub fn check(
+        &mut self,
+        session: &ClientSession,
+        peer: &str,
+        num_pending_values: i64,
+        tx_manager: &dyn TxManager,
+        now: Instant,
+    ) -> Result<(), ConsensusGrpcError> {
+        if let Some(high_water_mark) = self.config.pending_values_high_water_mark {
+            if num_pending_values >= high_water_mark {
+                counters::ADMISSION_BACKPRESSURE.inc();
+                return Err(ConsensusGrpcError::Backpressure);
+            }
+        }
+
+        if let Some(max_pending) = self.config.max_pending_txs_per_client {
+            if self.num_pending_txs(session, tx_manager) >= max_pending {
+                counters::ADMISSION_TOO_MANY_PENDING_TXS.inc();
+                return Err(ConsensusGrpcError::TooManyPendingTxs);
+            }
+        }
+
+        if let Some(rate) = self.config.client_session_tx_rate {
+            let burst = self.config.client_session_tx_burst;
+            if !bucket(&mut self.session_buckets, session, burst, rate, now).has_token(now) {
+                counters::ADMISSION_SESSION_RATE_LIMITED.inc();
+                return Err(ConsensusGrpcError::RateLimited);
+            }
+        }
+
+        if let Some(rate) = self.config.client_ip_tx_rate {
+            let burst = self.config.client_ip_tx_burst;
+            let ip = peer_ip(peer).to_string();
+            if !bucket(&mut self.ip_buckets, &ip, burst, rate, now).has_token(now) {
+                counters::ADMISSION_IP_RATE_LIMITED.inc();
+                return Err(ConsensusGrpcError::RateLimited);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Charge the given client for a transaction which passed
+    /// [AdmissionControl::check] and validation, and record that it is in
+    /// the tx cache.
+    ///
+    /// # Arguments
+    /// * `session` - The client session the proposal arrived on.
+    /// * `peer` - The gRPC peer string of the client.
+    /// * `tx_hash` - The hash of the admitted transaction.
+    /// * `now` - The current time.
+    pub fn admit(&mut self, session: &ClientSession, peer: &str, tx_hash: TxHash, now: Instant) {
+        if let Some(rate) = self.config.client_session_tx_rate {
+            let burst = self.config.client_session_tx_burst;
+            bucket(&mut self.session_buckets, session, burst, rate, now).take();
+        }
+
+        if let Some(rate) = self.config.client_ip_tx_rate {
+            let burst = self.config.client_ip_tx_burst;
+            let ip = peer_ip(peer).to_string();
+            bucket(&mut self.ip_buckets, &ip, burst, rate, now).take();
+        }
+
+        self.record_pending_tx(session, tx_hash);
+    }
+
+    /// Record that a transaction proposed by the given session has been
+    /// inserted into the tx cache.
+    fn record_pending_tx(&mut self, session: &ClientSession, tx_hash: TxHash) {
+        if self.config.max_pending_txs_per_client.is_none() {
+            return;
+        }
+
+        if let Some(tx_hashes) = self.pending_txs.get_mut(session) {
+            tx_hashes.insert(tx_hash);
+        } else {
+            let mut tx_hashes = HashSet::default();
+            tx_hashes.insert(tx_hash);
+            self.pending_txs.put(session.clone(), tx_hashes);
+        }
+        counters::ADMISSION_TRACKED_PENDING_CLIENTS.set(self.pending_txs.len() as i64);
+    }
+
+    /// Number of transactions proposed by the given session that are still in
+    /// the tx cache. Transactions that have been externalized or expired are
+    /// forgotten.
+    fn num_pending_txs(&mut self, session: &ClientSession, tx_manager: &dyn TxManager) -> usize {
+        let num_pending = match self.pending_txs.get_mut(session) {
+            Some(tx_hashes) => {
+                tx_hashes.retain(|tx_hash| tx_manager.contains(tx_hash));
+                tx_hashes.len()
+            }
+            None => 0,
+        };
+
+        if num_pending == 0 {
+            self.pending_txs.pop(session);
+        }
+        counters::ADMISSION_TRACKED_PENDING_CLIENTS.set(self.pending_txs.len() as i64);
+
+        num_pending
+    }
+}
+
+/// Get the bucket stored under `key`, creating a full bucket if there isn't
+/// one yet.
+fn bucket<'a, K: Clone + Eq + core::hash::Hash>(
+    buckets: &'a mut LruCache<K, TokenBucket>,
+    key: &K,
+    burst: u32,
+    rate: f64,
+    now: Instant,
+) -> &'a mut TokenBucket {
+    if !buckets.contains(key) {
+        buckets.put(key.clone(), TokenBucket::new(burst, rate, now));
+    }
+    buckets.get_mut(key).expect("bucket was just inserted")
+}
+
+/// Extract the address portion of a gRPC peer string, e.g. `ipv4:1.2.3.4:5678`
+/// becomes `1.2.3.4` and `ipv6:[::1]:5678` becomes `[::1]`.
+fn peer_ip(peer: &str) -> &str {
+    let address = peer.split_once(':').map_or(peer, |(_scheme, rest)| rest);
+    address
+        .rsplit_once(':')
+        .map_or(address, |(host, _port)| host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx_manager::MockTxManager;
+    use std::time::Duration;
+
+    fn session(id: u8) -> ClientSession {
+        ClientSession::from(vec![id; 32])
+    }
+
+    // Take a token from the bucket if it has one.
+    fn try_take(bucket: &mut TokenBucket, now: Instant) -> bool {
+        let available = bucket.has_token(now);
+        if available {
+            bucket.take();
+        }
+        available
+    }
+
+    // Check a proposal from the given client and, if it passes, admit it.
+    fn propose(
+        admission_control: &mut AdmissionControl,
+        session: &ClientSession,
+        peer: &str,
+        tx_manager: &MockTxManager,
+        now: Instant,
+    ) -> Result<(), ConsensusGrpcError> {
+        admission_control.check(session, peer, 0, tx_manager, now)?;
+        admission_control.admit(session, peer, TxHash([0u8; 32]), now);
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_bucket_refills_over_time() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2, 1.0, start);
+
+        assert!(try_take(&mut bucket, start));
+        assert!(try_take(&mut bucket, start));
+        assert!(!try_take(&mut bucket, start));
+
+        // Half a second is not enough for a whole token.
+        assert!(!try_take(&mut bucket, start + Duration::from_millis(500)));
+        assert!(try_take(&mut bucket, start + Duration::from_millis(1000)));
+
+        // The bucket never holds more than its capacity.
+        let later = start + Duration::from_secs(100);
+        assert!(try_take(&mut bucket, later));
+        assert!(try_take(&mut bucket, later));
+        assert!(!try_take(&mut bucket, later));
+    }
+
+    #[test]
+    fn test_peer_ip() {
+        assert_eq!(peer_ip("ipv4:127.0.0.1:1234"), "127.0.0.1");
+        assert_eq!(peer_ip("ipv6:[::1]:1234"), "[::1]");
+        assert_eq!(peer_ip("unix:/tmp/socket"), "/tmp/socket");
+    }
+
+    #[test]
+    fn test_disabled_admits_everything() {
+        let mut admission_control = AdmissionControl::new(Default::default(), 16);
+        let tx_manager = MockTxManager::new();
+        let now = Instant::now();
+
+        for _ in 0..1000 {
+            assert!(admission_control
+                .check(&session(1), "ipv4:127.0.0.1:1", 1000, &tx_manager, now)
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn test_backpressure() {
+        let config = AdmissionControlConfig {
+            pending_values_high_water_mark: Some(100),
+            ..Default::default()
+        };
+        let mut admission_control = AdmissionControl::new(config, 16);
+        let tx_manager = MockTxManager::new();
+        let now = Instant::now();
+
+        assert!(admission_control
+            .check(&session(1), "ipv4:127.0.0.1:1", 99, &tx_manager, now)
+            .is_ok());
+        assert!(matches!(
+            admission_control.check(&session(1), "ipv4:127.0.0.1:1", 100, &tx_manager, now),
+            Err(ConsensusGrpcError::Backpressure)
+        ));
+    }
+
+    #[test]
+    fn test_session_rate_limit() {
+        let config = AdmissionControlConfig {
+            client_session_tx_rate: Some(1.0),
+            client_session_tx_burst: 3,
+            ..Default::default()
+        };
+        let mut admission_control = AdmissionControl::new(config, 16);
+        let tx_manager = MockTxManager::new();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(propose(
+                &mut admission_control,
+                &session(1),
+                "ipv4:127.0.0.1:1",
+                &tx_manager,
+                now
+            )
+            .is_ok());
+        }
+        assert!(matches!(
+            propose(
+                &mut admission_control,
+                &session(1),
+                "ipv4:127.0.0.1:1",
+                &tx_manager,
+                now
+            ),
+            Err(ConsensusGrpcError::RateLimited)
+        ));
+
+        // Other sessions are unaffected.
+        assert!(propose(
+            &mut admission_control,
+            &session(2),
+            "ipv4:127.0.0.1:1",
+            &tx_manager,
+            now
+        )
+        .is_ok());
+
+        // The session is admitted again once its bucket refills.
+        assert!(propose(
+            &mut admission_control,
+            &session(1),
+            "ipv4:127.0.0.1:1",
+            &tx_manager,
+            now + Duration::from_secs(1)
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_ip_rate_limit_spans_sessions() {
+        let config = AdmissionControlConfig {
+            client_ip_tx_rate: Some(1.0),
+            client_ip_tx_burst: 2,
+            ..Default::default()
+        };
+        let mut admission_control = AdmissionControl::new(config, 16);
+        let tx_manager = MockTxManager::new();
+        let now = Instant::now();
+
+        assert!(propose(
+            &mut admission_control,
+            &session(1),
+            "ipv4:10.0.0.1:1000",
+            &tx_manager,
+            now
+        )
+        .is_ok());
+        assert!(propose(
+            &mut admission_control,
+            &session(2),
+            "ipv4:10.0.0.1:2000",
+            &tx_manager,
+            now
+        )
+        .is_ok());
+        assert!(matches!(
+            propose(
+                &mut admission_control,
+                &session(3),
+                "ipv4:10.0.0.1:3000",
+                &tx_manager,
+                now
+            ),
+            Err(ConsensusGrpcError::RateLimited)
+        ));
+
+        // A different address has its own bucket.
+        assert!(propose(
+            &mut admission_control,
+            &session(3),
+            "ipv4:10.0.0.2:3000",
+            &tx_manager,
+            now
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_only_admitted_proposals_are_charged() {
+        let config = AdmissionControlConfig {
+            client_session_tx_rate: Some(1.0),
+            client_session_tx_burst: 2,
+            client_ip_tx_rate: Some(1.0),
+            client_ip_tx_burst: 1,
+            ..Default::default()
+        };
+        let mut admission_control = AdmissionControl::new(config, 16);
+        let tx_manager = MockTxManager::new();
+        let now = Instant::now();
+
+        // Checking alone doesn't use up any tokens.
+        for _ in 0..3 {
+            assert!(admission_control
+                .check(&session(1), "ipv4:10.0.0.1:1", 0, &tx_manager, now)
+                .is_ok());
+        }
+
+        assert!(propose(
+            &mut admission_control,
+            &session(1),
+            "ipv4:10.0.0.1:1",
+            &tx_manager,
+            now
+        )
+        .is_ok());
+
+        // The address is out of tokens, which must not cost the session one.
+        assert!(matches!(
+            propose(
+                &mut admission_control,
+                &session(1),
+                "ipv4:10.0.0.1:1",
+                &tx_manager,
+                now
+            ),
+            Err(ConsensusGrpcError::RateLimited)
+        ));
+        assert!(propose(
+            &mut admission_control,
+            &session(1),
+            "ipv4:10.0.0.2:1",
+            &tx_manager,
+            now
+        )
+        .is_ok());
+
+        // Now the session is out of tokens.
+        assert!(matches!(
+            propose(
+                &mut admission_control,
+                &session(1),
+                "ipv4:10.0.0.3:1",
+                &tx_manager,
+                now
+            ),
+            Err(ConsensusGrpcError::RateLimited)
+        ));
+    }
+
+    #[test]
+    fn test_max_pending_txs_per_client() {
+        let config = AdmissionControlConfig {
+            max_pending_txs_per_client: Some(2),
+            ..Default::default()
+        };
+        let mut admission_control = AdmissionControl::new(config, 16);
+        let now = Instant::now();
+
+        let tx_hash_1 = TxHash([1u8; 32]);
+        let tx_hash_2 = TxHash([2u8; 32]);
+        admission_control.record_pending_tx(&session(1), tx_hash_1);
+        admission_control.record_pending_tx(&session(1), tx_hash_2);
+
+        // Both transactions are still in the cache.
+        let mut tx_manager = MockTxManager::new();
+        tx_manager.expect_contains().return_const(true);
+        assert!(matches!(
+            admission_control.check(&session(1), "ipv4:127.0.0.1:1", 0, &tx_manager, now),
+            Err(ConsensusGrpcError::TooManyPendingTxs)
+        ));
+        assert!(admission_control
+            .check(&session(2), "ipv4:127.0.0.1:1", 0, &tx_manager, now)
+            .is_ok());
+
+        // One of them left the cache.
+        let mut tx_manager = MockTxManager::new();
+        tx_manager
+            .expect_contains()
+            .returning(move |tx_hash| *tx_hash == tx_hash_1);
+        assert!(admission_control
+            .check(&session(1), "ipv4:127.0.0.1:1", 0, &tx_manager, now)
+            .is_ok());
+    }
+}
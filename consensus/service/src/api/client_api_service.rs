@@ -3,7 +3,7 @@
 //! Serves client-to-node gRPC requests.
 
 use crate::{
-    api::grpc_error::ConsensusGrpcError,
+    api::{admission_control::AdmissionControl, grpc_error::ConsensusGrpcError},
     consensus_service::ProposeTxCallback,
     counters,
     mint_tx_manager::MintTxManager,
@@ -92,6 +92,8 @@ pub struct ClientApiService {
     /// Information kept regarding sessions between clients and consensus
     /// so that we can drop bad sessions.
     tracked_sessions: Arc<Mutex<LruCache<ClientSession, ClientSessionTracking>>>,
+    /// Rate limits and back-pressure applied to proposed transactions.
+    admission_control: Arc<Mutex<AdmissionControl>>,
 }
 
 impl ClientApiService {
@@ -107,6 +109,10 @@ impl ClientApiService {
         logger: Logger,
         tracked_sessions: Arc<Mutex<LruCache<ClientSession, ClientSessionTracking>>>,
     ) -> Self {
+        let admission_control = Arc::new(Mutex::new(AdmissionControl::new(
+            config.admission_control.clone(),
+            config.client_tracking_capacity,
+        )));
         Self {
             config,
             enclave,
@@ -118,17 +124,38 @@ impl ClientApiService {
             authenticator,
             logger,
             tracked_sessions,
+            admission_control,
         }
     }
 
+    /// Applies the configured admission control limits to a proposal from the
+    /// given client.
+    fn check_admission(
+        &self,
+        ctx: &RpcContext,
+        session: &ClientSession,
+    ) -> Result<(), ConsensusGrpcError> {
+        self.admission_control
+            .lock()
+            .expect("Mutex poisoned")
+            .check(
+                session,
+                &ctx.peer(),
+                counters::CUR_NUM_PENDING_VALUES.get(),
+                self.tx_manager.as_ref(),
+                Instant::now(),
+            )
+    }
+
     /// Handles a client's proposed transaction.
     ///
     /// # Arguments
     /// `msg` - An encrypted message from a client to the enclave.
-    /// `logger` - Logger
+    /// `peer` - The client's address, as reported by grpc.
     fn handle_proposed_tx(
         &mut self,
         msg: Message,
+        peer: &str,
     ) -> Result<ProposeTxResponse, ConsensusGrpcError> {
         counters::ADD_TX_INITIATED.inc();
         let session_id = ClientSession::from(msg.channel_id.clone());
@@ -162,6 +189,13 @@ impl ClientApiService {
         // transaction.
         self.tx_manager.validate(&tx_hash)?;
 
+        // Only transactions which passed validation count against the client's
+        // limits.
+        self.admission_control
+            .lock()
+            .expect("Mutex poisoned")
+            .admit(&session_id, peer, tx_hash, Instant::now());
+
         // The transaction can be considered by the network.
         (*self.propose_tx_callback)(ConsensusValue::TxHash(tx_hash), None, None);
         counters::ADD_TX.inc();
@@ -273,13 +307,13 @@ impl ConsensusClientApi for ClientApiService {
     ) {
         let _timer = SVC_COUNTERS.req(&ctx);
 
+        let session = ClientSession::from(msg.channel_id.clone());
         {
-            let session = ClientSession::from(msg.channel_id.clone());
             let mut tracker = self.tracked_sessions.lock().expect("Mutex poisoned");
             // Calling get() on the LRU bumps the entry to show up as more
             // recently-used.
             if tracker.get(&session).is_none() {
-                tracker.put(session, ClientSessionTracking::new());
+                tracker.put(session.clone(), ClientSessionTracking::new());
             }
         }
 
@@ -306,8 +340,15 @@ impl ConsensusClientApi for ClientApiService {
                 } else {
                     ConsensusGrpcError::NotServing.into()
                 }
+            } else if let Err(err) = self.check_admission(&ctx, &session) {
+                // This client is being throttled, or the node is asking clients to back off.
+                if let Err(e) = self.enclave.client_discard_message(msg.into()) {
+                    ConsensusGrpcError::Enclave(e).into()
+                } else {
+                    err.into()
+                }
             } else {
-                self.handle_proposed_tx(msg)
+                self.handle_proposed_tx(msg, &ctx.peer())
                     .or_else(ConsensusGrpcError::into)
             };
 
@@ -1039,6 +1080,74 @@ mod client_api_tests {
         counters::CUR_NUM_PENDING_VALUES.set(0);
     }
 
+    #[test_with_logger]
+    #[serial(counters)]
+    // Should return ProposeTxResult::NodeBackpressure if the number of pending
+    // values is above the configured high-water mark.
+    fn test_client_tx_propose_tx_backpressure(logger: Logger) {
+        let mut enclave = MockConsensusEnclave::new();
+        enclave
+            .expect_client_discard_message()
+            .times(1)
+            .return_const(Ok(()));
+
+        let num_blocks = 5;
+        let mut ledger = MockLedger::new();
+        ledger
+            .expect_num_blocks()
+            .times(1)
+            .return_const(Ok(num_blocks));
+
+        let is_serving_fn = Arc::new(|| -> bool { true });
+
+        let scp_client_value_sender = Arc::new(
+            |_value: ConsensusValue,
+             _node_id: Option<&NodeID>,
+             _responder_id: Option<&ResponderId>| {},
+        );
+
+        let authenticator = AnonymousAuthenticator;
+
+        let tracked_sessions = Arc::new(Mutex::new(LruCache::new(4096)));
+
+        let mut config = get_config();
+        config.admission_control.pending_values_high_water_mark = Some(PENDING_LIMIT / 2);
+
+        let instance = ClientApiService::new(
+            config,
+            Arc::new(enclave),
+            scp_client_value_sender,
+            Arc::new(ledger),
+            Arc::new(MockTxManager::new()),
+            Arc::new(MockMintTxManager::new()),
+            is_serving_fn,
+            Arc::new(authenticator),
+            logger,
+            tracked_sessions,
+        );
+
+        // gRPC client and server.
+        let (client, _server) = get_client_server(instance);
+
+        // This is a global variable, and so affects other unit tests. It must be reset
+        // afterwards.
+        counters::CUR_NUM_PENDING_VALUES.set(PENDING_LIMIT / 2);
+
+        let message = Message::default();
+        match client.client_tx_propose(&message) {
+            Ok(propose_tx_response) => {
+                assert_eq!(
+                    propose_tx_response.get_result(),
+                    ProposeTxResult::NodeBackpressure
+                );
+                assert_eq!(propose_tx_response.get_block_count(), num_blocks);
+            }
+            Err(e) => panic!("Unexpected error: {e:?}"),
+        }
+
+        counters::CUR_NUM_PENDING_VALUES.set(0);
+    }
+
     #[test_with_logger]
     #[serial(counters)]
     fn test_client_tx_propose_rejects_unauthenticated(logger: Logger) {
@@ -27,6 +27,15 @@ pub enum ConsensusGrpcError {
     /// Service is currently not serving requests
     NotServing,
 
+    /// Client exceeded its tx proposal rate limit
+    RateLimited,
+
+    /// Client has too many pending transactions
+    TooManyPendingTxs,
+
+    /// Service is applying back-pressure, retry later
+    Backpressure,
+
     /// Enclave error: `{0}`
     Enclave(EnclaveError),
 
@@ -157,6 +166,24 @@ impl From<ConsensusGrpcError> for Result<ProposeTxResponse, RpcStatus> {
                 resp.set_result(ProposeTxResult::FeeMapDigestMismatch);
                 Ok(resp)
             }
+            ConsensusGrpcError::RateLimited => {
+                let mut resp = ProposeTxResponse::new();
+                resp.set_err_msg(src.to_string());
+                resp.set_result(ProposeTxResult::ClientRateLimited);
+                Ok(resp)
+            }
+            ConsensusGrpcError::TooManyPendingTxs => {
+                let mut resp = ProposeTxResponse::new();
+                resp.set_err_msg(src.to_string());
+                resp.set_result(ProposeTxResult::TooManyPendingTxs);
+                Ok(resp)
+            }
+            ConsensusGrpcError::Backpressure => {
+                let mut resp = ProposeTxResponse::new();
+                resp.set_err_msg(src.to_string());
+                resp.set_result(ProposeTxResult::NodeBackpressure);
+                Ok(resp)
+            }
 
             _ => Err(RpcStatus::from(src)),
         }
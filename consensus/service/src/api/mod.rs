@@ -3,6 +3,8 @@
 //! gRPC APIs
 #![allow(clippy::result_large_err)]
 
+mod admin_api_service;
+mod admission_control;
 mod attested_api_service;
 mod blockchain_api_service;
 mod client_api_service;
@@ -10,7 +12,8 @@ mod grpc_error;
 mod peer_api_service;
 mod peer_service_error;
 
+pub use admin_api_service::{ConsensusAdminService, PeerHealthFn, UpdateNetworkFn};
 pub use attested_api_service::AttestedApiService;
 pub use blockchain_api_service::BlockchainApiService;
 pub use client_api_service::{ClientApiService, ClientSessionTracking};
-pub use peer_api_service::PeerApiService;
+pub use peer_api_service::{PeerApiService, MAX_BLOCK_DATA_PER_REQUEST};
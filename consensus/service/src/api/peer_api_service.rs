@@ -10,7 +10,8 @@ use crate::{
     tx_manager::{TxManager, TxManagerError},
     SVC_COUNTERS,
 };
-use grpcio::{RpcContext, RpcStatus, UnarySink};
+use futures::{stream, SinkExt};
+use grpcio::{RpcContext, RpcStatus, ServerStreamingSink, UnarySink, WriteFlags};
 use mc_attest_api::attest::Message;
 use mc_attest_enclave_api::{EnclaveMessage, PeerSession};
 use mc_common::{
@@ -18,7 +19,8 @@ use mc_common::{
     ResponderId,
 };
 use mc_consensus_api::{
-    consensus_common::ProposeTxResponse,
+    blockchain::ArchiveBlock,
+    consensus_common::{BlocksRequest, ProposeTxResponse},
     consensus_peer::{
         ConsensusMsg as GrpcConsensusMsg, ConsensusMsgResponse, ConsensusMsgResult,
         GetLatestMsgResponse, GetTxsRequest, GetTxsResponse, TxHashesNotInCache,
@@ -31,15 +33,24 @@ use mc_ledger_db::Ledger;
 use mc_peers::{ConsensusValue, TxProposeAAD};
 use mc_transaction_core::tx::TxHash;
 use mc_util_grpc::{
-    rpc_internal_error, rpc_invalid_arg_error, rpc_logger, rpc_permissions_error, send_result,
+    rpc_database_err, rpc_internal_error, rpc_invalid_arg_error, rpc_logger, rpc_permissions_error,
+    send_result,
 };
 use mc_util_serial::deserialize;
-use std::{str::FromStr, sync::Arc};
+use std::{cmp::min, str::FromStr, sync::Arc};
+
+/// Maximum number of blocks returned by a single `get_block_data` call.
+pub const MAX_BLOCK_DATA_PER_REQUEST: u32 = 100;
 
 // Callback method for returning the latest SCP message issued by the local
 // node, used to implement the `fetch_latest_msg` RPC call.
 type FetchLatestMsgFn = Arc<dyn Fn() -> Option<mc_peers::ConsensusMsg> + Sync + Send>;
 
+// Callback method for returning the responder IDs of the peers the local node
+// is currently connected to. This changes when the network configuration is
+// reloaded.
+type KnownResponderIdsFn = Arc<dyn Fn() -> Vec<ResponderId> + Sync + Send>;
+
 #[derive(Clone)]
 pub struct PeerApiService {
     /// Enclave instance.
@@ -66,7 +77,7 @@ pub struct PeerApiService {
     /// requests to. That is necessary for resolving TxHashes into Txs. If
     /// we received a consensus message from a peer not on this list, we
     /// won't be able to reach out to it to ask for the transaction contents.
-    known_responder_ids: Vec<ResponderId>,
+    known_responder_ids_fn: KnownResponderIdsFn,
 
     /// Logger.
     logger: Logger,
@@ -83,8 +94,8 @@ impl PeerApiService {
     ///   message from a peer.
     /// * `scp_client_value_sender` - Callback for proposed transactions.
     /// * `fetch_latest_msg_fn` - Returns highest message emitted by this node.
-    /// * `known_responder_ids` - Messages from peers not on this "whitelist"
-    ///   are ignored.
+    /// * `known_responder_ids_fn` - Returns the current "whitelist" of peers.
+    ///   Messages from peers not on it are ignored.
     /// * `logger` - Logger.
     pub fn new(
         consensus_enclave: Arc<dyn ConsensusEnclave + Send + Sync>,
@@ -93,7 +104,7 @@ impl PeerApiService {
         incoming_consensus_msgs_sender: BackgroundWorkQueueSenderFn<IncomingConsensusMsg>,
         scp_client_value_sender: ProposeTxCallback,
         fetch_latest_msg_fn: FetchLatestMsgFn,
-        known_responder_ids: Vec<ResponderId>,
+        known_responder_ids_fn: KnownResponderIdsFn,
         logger: Logger,
     ) -> Self {
         Self {
@@ -103,7 +114,7 @@ impl PeerApiService {
             scp_client_value_sender,
             ledger,
             fetch_latest_msg_fn,
-            known_responder_ids,
+            known_responder_ids_fn,
             logger,
         }
     }
@@ -188,7 +199,7 @@ impl PeerApiService {
         from_responder_id: ResponderId,
     ) -> Result<(), PeerServiceError> {
         // Ignore a consensus message from an unknown peer.
-        if !self.known_responder_ids.contains(&from_responder_id) {
+        if !(self.known_responder_ids_fn)().contains(&from_responder_id) {
             return Err(PeerServiceError::UnknownPeer(from_responder_id.to_string()));
         }
 
@@ -224,6 +235,26 @@ impl PeerApiService {
                 }
             })
     }
+
+    /// Reads the data of up to `limit` blocks, starting at `offset`. Stops
+    /// early at the end of the ledger.
+    fn handle_get_block_data(
+        &self,
+        offset: u64,
+        limit: u32,
+        logger: &Logger,
+    ) -> Result<Vec<ArchiveBlock>, RpcStatus> {
+        let limit = min(limit, MAX_BLOCK_DATA_PER_REQUEST) as u64;
+        let mut archive_blocks = Vec::new();
+        for block_index in offset..offset.saturating_add(limit) {
+            match self.ledger.get_block_data(block_index) {
+                Ok(block_data) => archive_blocks.push(ArchiveBlock::from(&block_data)),
+                Err(mc_ledger_db::Error::NotFound) => break,
+                Err(err) => return Err(rpc_database_err(err, logger)),
+            }
+        }
+        Ok(archive_blocks)
+    }
 }
 
 impl ConsensusPeerApi for PeerApiService {
@@ -389,16 +420,50 @@ impl ConsensusPeerApi for PeerApiService {
             send_result(ctx, sink, result, logger)
         });
     }
+
+    /// Streams the full data of a range of blocks, so that peers can catch up
+    /// without an archive.
+    fn get_block_data(
+        &mut self,
+        ctx: RpcContext,
+        request: BlocksRequest,
+        mut sink: ServerStreamingSink<ArchiveBlock>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let logger = rpc_logger(&ctx, &self.logger);
+        let result = self.handle_get_block_data(request.offset, request.limit, &logger);
+
+        ctx.spawn(async move {
+            let send_result = match result {
+                Ok(archive_blocks) => {
+                    let mut archive_blocks = stream::iter(
+                        archive_blocks
+                            .into_iter()
+                            .map(|archive_block| Ok((archive_block, WriteFlags::default()))),
+                    );
+                    match sink.send_all(&mut archive_blocks).await {
+                        Ok(()) => sink.close().await,
+                        Err(err) => Err(err),
+                    }
+                }
+                Err(status) => sink.fail(status).await,
+            };
+            if let Err(err) = send_result {
+                log::error!(logger, "Failed to reply to get_block_data: {:?}", err);
+            }
+        });
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{background_work_queue::BackgroundWorkQueueError, tx_manager::MockTxManager};
+    use futures::{executor::block_on, TryStreamExt};
     use grpcio::{
         ChannelBuilder, Environment, Error::RpcFailure, Server, ServerBuilder, ServerCredentials,
     };
-    use mc_blockchain_types::Block;
+    use mc_blockchain_types::{Block, BlockData};
     use mc_common::{logger::test_with_logger, NodeID};
     use mc_consensus_api::{
         consensus_peer::ConsensusMsg,
@@ -410,7 +475,7 @@ mod tests {
         Msg, QuorumSet,
     };
     use mc_crypto_keys::{Ed25519Pair, Ed25519Private};
-    use mc_ledger_db::MockLedger;
+    use mc_ledger_db::{test_utils::get_test_ledger_blocks, MockLedger};
     use mc_util_from_random::FromRandom;
     use rand::{rngs::StdRng, SeedableRng};
 
@@ -450,6 +515,13 @@ mod tests {
         Arc::new(|| None)
     }
 
+    // Returns a fixed list of responder IDs.
+    fn get_known_responder_ids_fn(
+        known_responder_ids: Vec<ResponderId>,
+    ) -> Arc<dyn Fn() -> Vec<ResponderId> + Sync + Send> {
+        Arc::new(move || known_responder_ids.clone())
+    }
+
     fn get_client_server(instance: PeerApiService) -> (ConsensusPeerApiClient, Server) {
         let service = create_consensus_peer_api(instance);
         let env = Arc::new(Environment::new(1));
@@ -485,7 +557,7 @@ mod tests {
             get_incoming_consensus_msgs_sender_ok(),
             get_scp_client_value_sender(),
             get_fetch_latest_msg_fn(),
-            known_responder_ids,
+            get_known_responder_ids_fn(known_responder_ids),
             logger,
         );
 
@@ -559,7 +631,7 @@ mod tests {
             get_incoming_consensus_msgs_sender_ok(),
             get_scp_client_value_sender(),
             get_fetch_latest_msg_fn(),
-            known_responder_ids.clone(),
+            get_known_responder_ids_fn(known_responder_ids.clone()),
             logger,
         );
 
@@ -625,7 +697,7 @@ mod tests {
             get_incoming_consensus_msgs_sender_ok(),
             get_scp_client_value_sender(),
             get_fetch_latest_msg_fn(),
-            known_responder_ids.clone(),
+            get_known_responder_ids_fn(known_responder_ids.clone()),
             logger,
         );
 
@@ -673,7 +745,7 @@ mod tests {
             get_incoming_consensus_msgs_sender_ok(),
             get_scp_client_value_sender(),
             get_fetch_latest_msg_fn(),
-            known_responder_ids.clone(),
+            get_known_responder_ids_fn(known_responder_ids.clone()),
             logger,
         );
 
@@ -730,4 +802,48 @@ mod tests {
     // TODO: fetch_txs
 
     // TODO: peer_tx_propose
+
+    #[test_with_logger]
+    // Should stream block data until the end of the ledger.
+    fn test_get_block_data(logger: Logger) {
+        let (consensus_enclave, mut ledger, tx_manager) = get_mocks();
+
+        let blocks = get_test_ledger_blocks(5);
+        let ledger_blocks = blocks.clone();
+        ledger
+            .expect_get_block_data()
+            .returning(move |block_index| {
+                ledger_blocks
+                    .get(block_index as usize)
+                    .cloned()
+                    .ok_or(mc_ledger_db::Error::NotFound)
+            });
+
+        let instance = PeerApiService::new(
+            Arc::new(consensus_enclave),
+            Arc::new(ledger),
+            Arc::new(tx_manager),
+            get_incoming_consensus_msgs_sender_ok(),
+            get_scp_client_value_sender(),
+            get_fetch_latest_msg_fn(),
+            get_known_responder_ids_fn(vec![]),
+            logger,
+        );
+        let (client, _server) = get_client_server(instance);
+
+        let mut request = BlocksRequest::new();
+        request.set_offset(2);
+        request.set_limit(10);
+        let receiver = client
+            .get_block_data(&request)
+            .expect("get_block_data failed");
+        let archive_blocks: Vec<ArchiveBlock> =
+            block_on(receiver.try_collect()).expect("Failed reading stream");
+
+        let block_data = archive_blocks
+            .iter()
+            .map(|archive_block| BlockData::try_from(archive_block).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(block_data, blocks[2..]);
+    }
 }
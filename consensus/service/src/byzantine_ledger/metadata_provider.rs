@@ -42,12 +42,16 @@ impl<E: ReportableEnclave> BlockMetadataProvider for ConsensusMetadataProvider<E
             .expect("failed to get attestation evidence");
         let prost_evidence = prost::DcapEvidence::try_from(&attestation_evidence)
             .expect("failed to convert to prost evidence");
-        let contents = BlockMetadataContents::new(
+        let mut contents = BlockMetadataContents::new(
             block_data.block().id.clone(),
             self.quorum_set.clone(),
             prost_evidence.into(),
             self.responder_id.clone(),
         );
+        // The block was signed by the node that externalized it, use that time.
+        if let Some(signature) = block_data.signature() {
+            contents.set_timestamp(signature.signed_at());
+        }
         Some(
             BlockMetadata::from_contents_and_keypair(contents, &self.msg_signer_key)
                 .expect("failed to sign metadata"),
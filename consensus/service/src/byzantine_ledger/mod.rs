@@ -9,11 +9,16 @@ mod ledger_sync_state;
 mod metadata_provider;
 mod pending_values;
 mod task_message;
+mod transactions_fetcher;
 mod worker;
 
 use self::metadata_provider::ConsensusMetadataProvider;
 use crate::{
-    byzantine_ledger::{task_message::TaskMessage, worker::ByzantineLedgerWorker},
+    byzantine_ledger::{
+        task_message::{PendingNetworkUpdate, TaskMessage},
+        transactions_fetcher::ConsensusTransactionsFetcher,
+        worker::ByzantineLedgerWorker,
+    },
     counters,
     mint_tx_manager::{MintTxManager, MintTxManagerError},
     tx_manager::{TxManager, TxManagerError},
@@ -22,10 +27,10 @@ use displaydoc::Display;
 use mc_common::{logger::Logger, NodeID, ResponderId};
 use mc_connection::{BlockchainConnection, ConnectionManager};
 use mc_consensus_enclave::ConsensusEnclave;
-use mc_consensus_scp::{scp_log::LoggingScpNode, Node, QuorumSet, ScpNode};
+use mc_consensus_scp::{scp_log::LoggingScpNode, Node, QuorumSet, ScpNode, SlotIndex};
 use mc_crypto_keys::Ed25519Pair;
 use mc_ledger_db::Ledger;
-use mc_ledger_sync::{LedgerSyncService, ReqwestTransactionsFetcher};
+use mc_ledger_sync::LedgerSyncService;
 use mc_peers::{
     Broadcast, ConsensusConnection, ConsensusMsg, ConsensusValue, VerifiedConsensusMsg,
 };
@@ -104,7 +109,8 @@ impl ByzantineLedger {
     /// * `mint_tx_manager` - MintTxManager
     /// * `broadcaster` - Broadcaster
     /// * `msg_signer_key` - Signs consensus messages issued by this node.
-    /// * `tx_source_urls` - Source URLs for fetching block contents.
+    /// * `tx_source_urls` - Source URLs for fetching block contents. If empty,
+    ///   block contents are fetched from peers.
     /// * `scp_debug_dir` - If Some, debugging info will be written in this
     ///   directory.
     /// * `logger` - Logger.
@@ -223,7 +229,12 @@ impl ByzantineLedger {
                 ),
                 ledger.clone(),
                 peer_manager.clone(),
-                ReqwestTransactionsFetcher::new(tx_source_urls, logger.clone()).unwrap(),
+                ConsensusTransactionsFetcher::new(
+                    tx_source_urls,
+                    peer_manager.clone(),
+                    logger.clone(),
+                )
+                .unwrap(),
                 logger.clone(),
             );
 
@@ -284,6 +295,25 @@ impl ByzantineLedger {
             .expect("Could not send consensus msg");
     }
 
+    /// Replace the local node's quorum set at the next slot boundary.
+    ///
+    /// # Arguments
+    /// * `quorum_set` - The quorum set to use from the next slot onwards.
+    /// * `on_apply` - Invoked by the worker thread with the index of the first
+    ///   slot using `quorum_set`.
+    pub fn update_network(
+        &self,
+        quorum_set: QuorumSet,
+        on_apply: impl FnOnce(SlotIndex) + Send + 'static,
+    ) {
+        self.task_sender
+            .send(TaskMessage::UpdateNetwork(PendingNetworkUpdate {
+                quorum_set,
+                on_apply: Box::new(on_apply),
+            }))
+            .expect("Could not send network update");
+    }
+
     pub fn stop(&mut self) {
         let _ = self.task_sender.send(TaskMessage::StopTrigger);
         self.join();
@@ -1,8 +1,9 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
 use mc_common::ResponderId;
+use mc_consensus_scp::{QuorumSet, SlotIndex};
 use mc_peers::{ConsensusValue, VerifiedConsensusMsg};
-use std::time::Instant;
+use std::{fmt, time::Instant};
 
 #[derive(Debug)]
 pub enum TaskMessage {
@@ -14,6 +15,29 @@ pub enum TaskMessage {
     /// SCP Statement.
     ConsensusMsg(VerifiedConsensusMsg, ResponderId),
 
+    /// A network configuration change, to be applied at the next slot
+    /// boundary.
+    UpdateNetwork(PendingNetworkUpdate),
+
     /// Stop trigger, used for notifying the worker thread to terminate.
     StopTrigger,
 }
+
+/// A network configuration change waiting for the current slot to end.
+pub struct PendingNetworkUpdate {
+    /// The quorum set to use from the next slot onwards.
+    pub quorum_set: QuorumSet,
+
+    /// Invoked with the index of the first slot using the new quorum set, once
+    /// it has been installed. Used to bring peer connections in line with the
+    /// new configuration.
+    pub on_apply: Box<dyn FnOnce(SlotIndex) + Send>,
+}
+
+impl fmt::Debug for PendingNetworkUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingNetworkUpdate")
+            .field("quorum_set", &self.quorum_set)
+            .finish_non_exhaustive()
+    }
+}
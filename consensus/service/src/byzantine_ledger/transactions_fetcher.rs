@@ -0,0 +1,247 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! `TransactionsFetcher` implementations used by the consensus node's ledger
+//! sync. Block data comes from an archive when one is configured, and from
+//! other consensus nodes over the peer API otherwise, e.g. on private test
+//! networks.
+
+use crate::api::MAX_BLOCK_DATA_PER_REQUEST;
+use displaydoc::Display;
+use mc_blockchain_types::{Block, BlockData, BlockIndex};
+use mc_common::{
+    logger::{log, Logger},
+    LruCache, ResponderId,
+};
+use mc_connection::ConnectionManager;
+use mc_ledger_sync::{
+    ReqwestTransactionsFetcher, ReqwestTransactionsFetcherError, TransactionFetcherError,
+    TransactionsFetcher,
+};
+use mc_peers::{ConsensusConnection, RetryableConsensusConnection};
+use std::{iter, sync::Mutex};
+
+/// Maximum number of pre-fetched blocks to keep in cache.
+pub const MAX_PREFETCHED_BLOCKS: usize = 1000;
+
+#[derive(Debug, Display)]
+pub enum PeerTransactionsFetcherError {
+    /// None of the safe peers returned data for block {0}
+    BlockNotFound(BlockIndex),
+}
+
+impl TransactionFetcherError for PeerTransactionsFetcherError {}
+
+#[derive(Debug, Display)]
+pub enum ConsensusTransactionsFetcherError {
+    /// Archive: {0}
+    Archive(ReqwestTransactionsFetcherError),
+
+    /// Peers: {0}
+    Peers(PeerTransactionsFetcherError),
+}
+
+impl TransactionFetcherError for ConsensusTransactionsFetcherError {}
+
+/// Fetches block data from an archive if any archive URLs are configured, and
+/// from peers otherwise.
+pub enum ConsensusTransactionsFetcher<PC: ConsensusConnection + 'static> {
+    Archive(ReqwestTransactionsFetcher),
+    Peers(PeerTransactionsFetcher<PC>),
+}
+
+impl<PC: ConsensusConnection + 'static> ConsensusTransactionsFetcher<PC> {
+    /// Create a fetcher for the given archive URLs, falling back to fetching
+    /// from peers when `tx_source_urls` is empty.
+    pub fn new(
+        tx_source_urls: Vec<String>,
+        peer_manager: ConnectionManager<PC>,
+        logger: Logger,
+    ) -> Result<Self, ConsensusTransactionsFetcherError> {
+        if tx_source_urls.is_empty() {
+            log::info!(
+                logger,
+                "No tx source URLs configured, fetching blocks from peers"
+            );
+            Ok(Self::Peers(PeerTransactionsFetcher::new(
+                peer_manager,
+                logger,
+            )))
+        } else {
+            ReqwestTransactionsFetcher::new(tx_source_urls, logger)
+                .map(Self::Archive)
+                .map_err(ConsensusTransactionsFetcherError::Archive)
+        }
+    }
+}
+
+impl<PC: ConsensusConnection + 'static> TransactionsFetcher for ConsensusTransactionsFetcher<PC> {
+    type Error = ConsensusTransactionsFetcherError;
+
+    fn get_block_data(
+        &self,
+        safe_responder_ids: &[ResponderId],
+        block: &Block,
+    ) -> Result<BlockData, Self::Error> {
+        match self {
+            Self::Archive(fetcher) => fetcher
+                .get_block_data(safe_responder_ids, block)
+                .map_err(ConsensusTransactionsFetcherError::Archive),
+            Self::Peers(fetcher) => fetcher
+                .get_block_data(safe_responder_ids, block)
+                .map_err(ConsensusTransactionsFetcherError::Peers),
+        }
+    }
+}
+
+pub struct PeerTransactionsFetcher<PC: ConsensusConnection + 'static> {
+    /// Connections to our peers.
+    peer_manager: ConnectionManager<PC>,
+
+    /// Blocks received from peers but not yet requested by the caller.
+    blocks_cache: Mutex<LruCache<BlockIndex, BlockData>>,
+
+    /// Held while downloading, so that concurrent callers wait for a batch
+    /// that is already in flight instead of requesting overlapping ranges.
+    fetch_lock: Mutex<()>,
+
+    /// Logger.
+    logger: Logger,
+}
+
+impl<PC: ConsensusConnection + 'static> PeerTransactionsFetcher<PC> {
+    pub fn new(peer_manager: ConnectionManager<PC>, logger: Logger) -> Self {
+        Self {
+            peer_manager,
+            blocks_cache: Mutex::new(LruCache::new(MAX_PREFETCHED_BLOCKS)),
+            fetch_lock: Mutex::new(()),
+            logger,
+        }
+    }
+
+    /// Take a block out of the cache, if it is there and matches the expected
+    /// block.
+    fn get_cached_block_data(&self, block: &Block) -> Option<BlockData> {
+        // LedgerSyncService does not ask for the same block twice once it has
+        // a valid copy, so there is no need to keep it around.
+        self.blocks_cache
+            .lock()
+            .expect("mutex poisoned")
+            .pop(&block.index)
+            .filter(|block_data| block_data.block() == block)
+    }
+
+    /// Download a batch of blocks starting at `block_index` from a peer, and
+    /// add them to the cache.
+    fn fetch_from_peer(&self, responder_id: &ResponderId, block_index: BlockIndex) -> bool {
+        let conn = match self.peer_manager.conn(responder_id) {
+            Some(conn) => conn,
+            None => return false,
+        };
+
+        let range = block_index..block_index + MAX_BLOCK_DATA_PER_REQUEST as u64;
+        match conn.fetch_block_data(range, iter::empty()) {
+            Ok(blocks_data) => {
+                let mut blocks_cache = self.blocks_cache.lock().expect("mutex poisoned");
+                for block_data in blocks_data {
+                    blocks_cache.put(block_data.block().index, block_data);
+                }
+                true
+            }
+            Err(err) => {
+                log::debug!(
+                    self.logger,
+                    "Failed fetching block data starting at {} from {}: {}",
+                    block_index,
+                    responder_id,
+                    err
+                );
+                false
+            }
+        }
+    }
+}
+
+impl<PC: ConsensusConnection + 'static> TransactionsFetcher for PeerTransactionsFetcher<PC> {
+    type Error = PeerTransactionsFetcherError;
+
+    fn get_block_data(
+        &self,
+        safe_responder_ids: &[ResponderId],
+        block: &Block,
+    ) -> Result<BlockData, Self::Error> {
+        if let Some(block_data) = self.get_cached_block_data(block) {
+            return Ok(block_data);
+        }
+
+        let _fetch_guard = self.fetch_lock.lock().expect("mutex poisoned");
+
+        // Another caller may have fetched the block while we were waiting.
+        if let Some(block_data) = self.get_cached_block_data(block) {
+            return Ok(block_data);
+        }
+
+        for responder_id in safe_responder_ids {
+            if !self.fetch_from_peer(responder_id, block.index) {
+                continue;
+            }
+            if let Some(block_data) = self.get_cached_block_data(block) {
+                return Ok(block_data);
+            }
+            log::warn!(
+                self.logger,
+                "{} did not return a matching copy of block {}",
+                responder_id,
+                block.index
+            );
+        }
+
+        Err(PeerTransactionsFetcherError::BlockNotFound(block.index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_blockchain_types::BlockVersion;
+    use mc_common::logger::test_with_logger;
+    use mc_ledger_db::test_utils::{create_ledger, initialize_ledger};
+    use mc_peers_test_utils::{test_peer_uri, MockPeerConnection};
+    use mc_transaction_core_test_utils::AccountKey;
+    use mc_util_uri::ConnectionUri;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test_with_logger]
+    fn test_get_block_data_from_peers(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([77u8; 32]);
+        let mut ledger = create_ledger();
+        let sender = AccountKey::random(&mut rng);
+        let blocks = initialize_ledger(BlockVersion::ZERO, &mut ledger, 5, &sender, &mut rng);
+
+        let local_node_id = test_peer_uri(1).node_id().unwrap();
+        let peer_uri = test_peer_uri(2);
+        let responder_id = peer_uri.responder_id().unwrap();
+        let peer_manager = ConnectionManager::new(
+            vec![MockPeerConnection::new(peer_uri, local_node_id, ledger, 0)],
+            logger.clone(),
+        );
+        let fetcher = PeerTransactionsFetcher::new(peer_manager, logger);
+
+        // The first request caches the rest of the batch.
+        for block_data in blocks.iter() {
+            let fetched = fetcher
+                .get_block_data(&[responder_id.clone()], block_data.block())
+                .expect("get_block_data failed");
+            assert_eq!(&fetched, block_data);
+        }
+
+        // A block the peer does not have.
+        let mut unknown_block = blocks[4].block().clone();
+        unknown_block.index = 10;
+        assert!(fetcher
+            .get_block_data(&[responder_id], &unknown_block)
+            .is_err());
+
+        // No safe peers.
+        assert!(fetcher.get_block_data(&[], blocks[0].block()).is_err());
+    }
+}
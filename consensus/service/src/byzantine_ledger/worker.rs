@@ -2,8 +2,10 @@
 
 use crate::{
     byzantine_ledger::{
-        ledger_sync_state::LedgerSyncState, pending_values::PendingValues,
-        task_message::TaskMessage, IS_BEHIND_GRACE_PERIOD, MAX_PENDING_VALUES_TO_NOMINATE,
+        ledger_sync_state::LedgerSyncState,
+        pending_values::PendingValues,
+        task_message::{PendingNetworkUpdate, TaskMessage},
+        IS_BEHIND_GRACE_PERIOD, MAX_PENDING_VALUES_TO_NOMINATE,
     },
     counters,
     mint_tx_manager::MintTxManager,
@@ -112,6 +114,9 @@ pub struct ByzantineLedgerWorker<
     // scp_node.
     need_nominate: bool,
 
+    // Network configuration change waiting for the current slot to end.
+    pending_network_update: Option<PendingNetworkUpdate>,
+
     logger: Logger,
 }
 
@@ -183,6 +188,7 @@ impl<
             pending_consensus_msgs: Default::default(),
             pending_values: PendingValues::new(tx_manager, mint_tx_manager),
             need_nominate: false,
+            pending_network_update: None,
             network_state,
             ledger_sync_service,
             ledger_sync_state: LedgerSyncState::InSync,
@@ -198,6 +204,8 @@ impl<
             return false;
         }
 
+        self.maybe_apply_network_update();
+
         // Advance the "sync state" state machine.
         let previous_sync_state = {
             let next_state = self.next_sync_state(Instant::now());
@@ -414,6 +422,17 @@ impl<
                         .push((consensus_msg, from_responder_id));
                 }
 
+                // Network configuration change. A later update supersedes one that has not
+                // been applied yet.
+                TaskMessage::UpdateNetwork(update) => {
+                    log::info!(
+                        self.logger,
+                        "Network update received, waiting for slot {} to end",
+                        self.current_slot_index
+                    );
+                    self.pending_network_update = Some(update);
+                }
+
                 // Request to stop thread
                 TaskMessage::StopTrigger => {
                     return false;
@@ -538,6 +557,47 @@ impl<
         }
     }
 
+    // Installs a pending network configuration change, provided this node has not
+    // yet issued any message for the current slot. This is the case right after
+    // a slot has been completed (or the ledger has been synced), so in practice
+    // changes take effect at the next slot boundary.
+    fn maybe_apply_network_update(&mut self) {
+        if self.pending_network_update.is_none() {
+            return;
+        }
+
+        let current_slot_is_idle = self
+            .highest_issued_msg
+            .lock()
+            .expect("mutex poisoned")
+            .as_ref()
+            .map_or(true, |msg| msg.scp_msg.slot_index < self.current_slot_index);
+        if !current_slot_is_idle {
+            return;
+        }
+
+        let PendingNetworkUpdate {
+            quorum_set,
+            on_apply,
+        } = self.pending_network_update.take().unwrap();
+
+        log::info!(
+            self.logger,
+            "Applying network update at slot {}: {:?}",
+            self.current_slot_index,
+            quorum_set
+        );
+
+        self.scp_node.set_quorum_set(quorum_set.clone());
+        // Peers' progress is kept, so the node does not lose track of whether it
+        // has fallen behind.
+        self.network_state.set_quorum_set(quorum_set.clone());
+        on_apply(self.current_slot_index);
+
+        counters::NETWORK_RECONFIGURATIONS.inc();
+        counters::QUORUM_SET_SIZE.set(quorum_set.nodes().len() as i64);
+    }
+
     fn complete_current_slot(&mut self, externalized: Vec<ConsensusValue>) {
         let tracer = tracer!();
 
@@ -873,14 +933,15 @@ impl<
             .expect("form_block failed");
 
         // The enclave cannot provide a timestamp, so this happens in untrusted.
-        signature.set_signed_at(chrono::Utc::now().timestamp() as u64);
+        let timestamp = chrono::Utc::now().timestamp() as u64;
+        signature.set_signed_at(timestamp);
 
-        let metadata = self.get_block_metadata(&block.id);
+        let metadata = self.get_block_metadata(&block.id, timestamp);
 
         BlockData::new(block, block_contents, signature, metadata)
     }
 
-    fn get_block_metadata(&self, block_id: &BlockID) -> BlockMetadata {
+    fn get_block_metadata(&self, block_id: &BlockID, timestamp: u64) -> BlockMetadata {
         let dcap_evidence = self
             .enclave
             .get_attestation_evidence()
@@ -894,12 +955,13 @@ impl<
                 "Failed to convert attestation evidence to prost after forming block {block_id:?}: {err}"
             )
         });
-        let contents = BlockMetadataContents::new(
+        let mut contents = BlockMetadataContents::new(
             block_id.clone(),
             self.scp_node.quorum_set(),
             prost_evidence.into(),
             self.scp_node.node_id().responder_id,
         );
+        contents.set_timestamp(timestamp);
 
         BlockMetadata::from_contents_and_keypair(contents, &self.msg_signer_key).unwrap_or_else(
             |err| panic!("Failed to sign block metadata for block {block_id:?}: {err}"),
@@ -1049,6 +1111,87 @@ mod tests {
         assert_eq!(worker.ledger_sync_state, LedgerSyncState::InSync);
     }
 
+    #[test_with_logger]
+    // A network update is applied once the current slot is idle, and not while
+    // the node is participating in it.
+    fn test_network_update_waits_for_slot_boundary(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([7u8; 32]);
+        let (local_node_id, _local_node_uri, msg_signer_key) = get_local_node_config(11);
+
+        let peers = get_peers(&[22, 33], &mut rng);
+        let quorum_set =
+            QuorumSet::new_with_node_ids(2, vec![peers[0].id.clone(), peers[1].id.clone()]);
+        let new_quorum_set = QuorumSet::new_with_node_ids(1, vec![peers[0].id.clone()]);
+
+        let num_blocks = 15;
+        let (enclave, mut scp_node, ledger, ledger_sync, tx_manager, mint_tx_manager, broadcast) =
+            get_mocks(&local_node_id, &quorum_set, num_blocks);
+        scp_node
+            .expect_set_quorum_set()
+            .with(eq(new_quorum_set.clone()))
+            .times(1)
+            .return_const(());
+
+        let connection_manager = get_connection_manager(&local_node_id, &peers, &logger);
+        let (task_sender, task_receiver) = get_channel();
+
+        // Pretend we already issued a message for the current slot.
+        let issued_msg = ConsensusMsg {
+            scp_msg: Msg {
+                sender_id: local_node_id.clone(),
+                slot_index: num_blocks,
+                quorum_set: quorum_set.clone(),
+                topic: Nominate(NominatePayload {
+                    X: Default::default(),
+                    Y: Default::default(),
+                }),
+            },
+            prev_block_id: Default::default(),
+            signature: Default::default(),
+        };
+        let highest_issued_msg = Arc::new(Mutex::new(Some(issued_msg)));
+
+        let mut worker = ByzantineLedgerWorker::new(
+            enclave,
+            Box::new(scp_node),
+            msg_signer_key,
+            ledger,
+            ledger_sync,
+            connection_manager,
+            Arc::new(tx_manager),
+            Arc::new(mint_tx_manager),
+            Arc::new(Mutex::new(broadcast)),
+            task_receiver,
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicU64::new(0)),
+            highest_issued_msg.clone(),
+            logger,
+        );
+
+        let applied_at = Arc::new(Mutex::new(None));
+        let applied_at_clone = applied_at.clone();
+        task_sender
+            .send(TaskMessage::UpdateNetwork(PendingNetworkUpdate {
+                quorum_set: new_quorum_set,
+                on_apply: Box::new(move |slot_index| {
+                    *applied_at_clone.lock().unwrap() = Some(slot_index);
+                }),
+            }))
+            .unwrap();
+
+        // The current slot is active, so the update stays pending.
+        assert!(worker.receive_tasks());
+        worker.maybe_apply_network_update();
+        assert!(worker.pending_network_update.is_some());
+        assert_eq!(*applied_at.lock().unwrap(), None);
+
+        // Once the slot is over, the update is applied.
+        worker.current_slot_index += 1;
+        worker.maybe_apply_network_update();
+        assert!(worker.pending_network_update.is_none());
+        assert_eq!(*applied_at.lock().unwrap(), Some(num_blocks + 1));
+    }
+
     /// Asserts that next_sync_state maps (initial_state, is_behind, now) -->
     /// expected_state
     fn next_sync_state_helper(
@@ -5,7 +5,7 @@
 use crate::{
     api::{
         AttestedApiService, BlockchainApiService, ClientApiService, ClientSessionTracking,
-        PeerApiService,
+        ConsensusAdminService, PeerApiService, PeerHealthFn, UpdateNetworkFn,
     },
     background_work_queue::BackgroundWorkQueue,
     byzantine_ledger::ByzantineLedger,
@@ -26,12 +26,19 @@ use mc_common::{
     LruCache, NodeID, ResponderId,
 };
 use mc_connection::{Connection, ConnectionManager};
-use mc_consensus_api::{consensus_client_grpc, consensus_common_grpc, consensus_peer_grpc};
+use mc_consensus_api::{
+    consensus_admin_grpc, consensus_client_grpc, consensus_common_grpc, consensus_peer_grpc,
+};
 use mc_consensus_enclave::{ConsensusEnclave, Error as ConsensusEnclaveError};
-use mc_consensus_service_config::{Config, Error as ConfigError};
+use mc_consensus_service_config::{
+    Config, Error as ConfigError, NetworkConfig, NetworkReloadSequence,
+};
 use mc_crypto_keys::DistinguishedEncoding;
 use mc_ledger_db::{Error as LedgerDbError, Ledger, LedgerDB};
-use mc_peers::{ConsensusValue, PeerConnection, ThreadedBroadcaster, VerifiedConsensusMsg};
+use mc_peers::{
+    ConsensusValue, PeerConnection, ThreadedBroadcaster, ThreadedBroadcasterAdaptiveRetryPolicy,
+    VerifiedConsensusMsg,
+};
 use mc_sgx_report_cache_untrusted::{Error as ReportCacheError, ReportCacheThread};
 use mc_util_grpc::{
     AdminServer, AnonymousAuthenticator, Authenticator, BuildInfoService,
@@ -42,6 +49,7 @@ use mc_util_uri::{ConnectionUri, ConsensusPeerUriApi};
 use once_cell::sync::OnceCell;
 use serde_json::json;
 use std::{
+    collections::HashMap,
     env,
     sync::{Arc, Mutex},
     time::Instant,
@@ -127,7 +135,7 @@ pub struct ConsensusService<
     // the LRU cache requires exclusive access, among other reasons.
     // The contention for this is (at time of writing), (one) ByzantineLedger worker thread,
     // and the client and peer api services, via the ProposeTxCallback
-    broadcaster: Arc<Mutex<ThreadedBroadcaster>>,
+    broadcaster: Arc<Mutex<ThreadedBroadcaster<ThreadedBroadcasterAdaptiveRetryPolicy>>>,
     tx_manager: Arc<TXM>,
     mint_tx_manager: Arc<MTXM>,
     // Option is only here because we need a way to drop the PeerKeepalive without mutex,
@@ -146,6 +154,10 @@ pub struct ConsensusService<
     /// Information kept regarding sessions between clients and consensus
     /// so that we can drop bad sessions.
     tracked_sessions: Arc<Mutex<LruCache<ClientSession, ClientSessionTracking>>>,
+
+    /// The last network configuration reload accepted by this node, if reloads
+    /// are enabled.
+    network_reload: Option<Arc<Mutex<NetworkReloadSequence>>>,
 }
 
 impl<
@@ -176,9 +188,20 @@ impl<
 
         let local_node_id = config.node_id();
 
+        // Network configuration reloads are rejected unless an admin key is
+        // configured. A reload accepted before a restart replaces the network file.
+        let network_reload = config
+            .network_admin_public_key
+            .and(config.network_reload_sequence_path.as_ref())
+            .map(|path| {
+                NetworkReloadSequence::load(path).unwrap_or_else(|err| {
+                    panic!("Failed loading network reload sequence from {path:?}: {err}")
+                })
+            })
+            .map(|sequence| Arc::new(Mutex::new(sequence)));
+
         // Peers
-        let peers: Vec<PeerConnection<E>> = config
-            .network()
+        let peers: Vec<PeerConnection<E>> = current_network(&config, network_reload.as_deref())
             .broadcast_peers()
             .into_iter()
             .map(|peer_uri| {
@@ -197,7 +220,7 @@ impl<
         // Broadcaster
         let broadcaster = Arc::new(Mutex::new(ThreadedBroadcaster::new(
             &peer_manager,
-            &mc_peers::ThreadedBroadcasterFibonacciRetryPolicy::default(),
+            &ThreadedBroadcasterAdaptiveRetryPolicy::default(),
             logger.clone(),
         )));
 
@@ -245,6 +268,7 @@ impl<
             user_rpc_server: None,
             byzantine_ledger: Some(Arc::new(Default::default())),
             tracked_sessions,
+            network_reload,
         }
     }
 
@@ -252,6 +276,12 @@ impl<
         self.config.clone()
     }
 
+    /// The network configuration in effect: the last accepted reload, or the
+    /// network file if there is none.
+    pub fn network(&self) -> NetworkConfig {
+        current_network(&self.config, self.network_reload.as_deref())
+    }
+
     pub fn start(&mut self) -> Result<(), ConsensusServiceError> {
         let ret = {
             self.report_cache_thread = Some(ReportCacheThread::start(
@@ -411,6 +441,18 @@ impl<
 
     fn start_admin_rpc_server(&mut self) -> Result<(), ConsensusServiceError> {
         if let Some(admin_listen_uri) = self.config.admin_listen_uri.as_ref() {
+            let admin_service =
+                consensus_admin_grpc::create_consensus_admin_api(ConsensusAdminService::new(
+                    self.config.network_admin_public_key,
+                    self.network_reload.clone(),
+                    self.network().tx_source_urls,
+                    self.config.peer_responder_id.clone(),
+                    Arc::new(self.ledger_db.clone()),
+                    self.create_update_network_fn(),
+                    self.create_peer_health_fn(),
+                    self.logger.clone(),
+                ));
+
             self.admin_rpc_server = Some(
                 AdminServer::start(
                     Some(self.env.clone()),
@@ -418,7 +460,7 @@ impl<
                     "Consensus Service".to_owned(),
                     self.config.peer_responder_id.to_string(),
                     Some(self.create_get_config_json_fn()),
-                    vec![],
+                    vec![admin_service],
                     self.logger.clone(),
                 )
                 .expect("Failed starting admin grpc server"),
@@ -470,7 +512,10 @@ impl<
             self.consensus_msgs_from_network.get_sender_fn(),
             self.create_scp_client_value_sender_fn(),
             get_highest_scp_message_fn,
-            self.peer_manager.responder_ids(),
+            {
+                let peer_manager = self.peer_manager.clone();
+                Arc::new(move || peer_manager.responder_ids())
+            },
             self.logger.clone(),
         ));
 
@@ -518,7 +563,7 @@ impl<
         if byzantine_ledger_arc
             .set(ByzantineLedger::new(
                 self.local_node_id.clone(),
-                self.config.network().quorum_set(),
+                self.network().quorum_set(),
                 self.enclave.clone(),
                 self.peer_manager.clone(),
                 self.ledger_db.clone(),
@@ -526,7 +571,7 @@ impl<
                 self.mint_tx_manager.clone(),
                 self.broadcaster.clone(),
                 self.config.msg_signer_key.clone(),
-                self.config.network().tx_source_urls,
+                self.network().tx_source_urls,
                 self.config.scp_debug_dump.clone(),
                 self.logger.clone(),
             ))
@@ -601,31 +646,17 @@ impl<
         let local_node_id = self.local_node_id.clone();
         let broadcaster = self.broadcaster.clone();
 
-        // Figure out which node IDs we are going to be relaying received transactions
-        // from. See comment below ("Broadcast to peers") for more details.
-        let relay_from_nodes: Vec<ResponderId> = self
-            .peer_manager
-            .conns()
-            .iter()
-            .filter_map(|conn| {
-                let uri = conn.uri();
-                if uri.consensus_relay_incoming_txs() {
-                    match uri.responder_id() {
-                        Ok(responder_id) => Some(responder_id),
-                        Err(_e) => {
-                            log::warn!(
-                                self.logger,
-                                "Could not get responder_id from {:?}",
-                                uri.to_string()
-                            );
-                            None
-                        }
-                    }
-                } else {
-                    None
-                }
-            })
-            .collect();
+        // Peers whose URI asks for transactions received from them to be relayed. See
+        // comment below ("Broadcast to peers") for more details. This is looked up on
+        // every value since peers may change when the network configuration is
+        // reloaded.
+        let peer_manager = self.peer_manager.clone();
+        let relay_from_node = move |responder_id: &ResponderId| {
+            peer_manager
+                .conn(responder_id)
+                .map(|conn| conn.uri().consensus_relay_incoming_txs())
+                .unwrap_or(false)
+        };
 
         Arc::new(move |scp_value, origin_node, relayed_from| {
             let origin_node = origin_node.unwrap_or(&local_node_id);
@@ -641,9 +672,7 @@ impl<
                     // However, in non-mesh configurations, network operators might want to
                     // selectively have incoming transactions from certain peers be
                     // relayed to other peers in order to improve consensus time.
-                    if origin_node == &local_node_id
-                        || relay_from_nodes.contains(&origin_node.responder_id)
-                    {
+                    if origin_node == &local_node_id || relay_from_node(&origin_node.responder_id) {
                         if let Some(encrypted_tx) = tx_manager.get_encrypted_tx(&tx_hash) {
                             broadcaster
                                 .lock()
@@ -684,6 +713,112 @@ impl<
         })
     }
 
+    /// Returns a callback that reports the health of the peers we broadcast to.
+    fn create_peer_health_fn(&self) -> PeerHealthFn {
+        let health_tracker = self
+            .broadcaster
+            .lock()
+            .expect("mutex poisoned")
+            .health_tracker();
+        Arc::new(move || health_tracker.snapshot())
+    }
+
+    /// Creates a function that schedules a new network configuration to be
+    /// applied by ByzantineLedger at the next slot boundary. Once applied, peer
+    /// connections and broadcasts are brought in line with the new
+    /// `broadcast_peers`.
+    fn create_update_network_fn(&self) -> UpdateNetworkFn {
+        let byzantine_ledger = self
+            .byzantine_ledger
+            .as_ref()
+            .map(Arc::downgrade)
+            .expect("Server was not initialized");
+        let peer_keepalive = self
+            .peer_keepalive
+            .as_ref()
+            .map(Arc::downgrade)
+            .expect("Server was not initialized");
+        let enclave = self.enclave.clone();
+        let local_node_id = self.local_node_id.clone();
+        let peer_manager = self.peer_manager.clone();
+        let broadcaster = self.broadcaster.clone();
+        let env = self.env.clone();
+        let logger = self.logger.clone();
+
+        Arc::new(move |network_config: NetworkConfig| {
+            let ledger = byzantine_ledger
+                .upgrade()
+                .ok_or_else(|| "ByzantineLedger has stopped".to_string())?;
+            let ledger = ledger
+                .get()
+                .ok_or_else(|| "ByzantineLedger has not started".to_string())?;
+
+            let enclave = enclave.clone();
+            let local_node_id = local_node_id.clone();
+            let peer_manager = peer_manager.clone();
+            let broadcaster = broadcaster.clone();
+            let peer_keepalive = peer_keepalive.clone();
+            let env = env.clone();
+            let logger = logger.clone();
+            let broadcast_peers = network_config
+                .broadcast_peers()
+                .into_iter()
+                .map(|peer_uri| {
+                    let responder_id = peer_uri
+                        .responder_id()
+                        .map_err(|err| format!("Invalid peer uri {peer_uri}: {err}"))?;
+                    Ok((responder_id, peer_uri))
+                })
+                .collect::<Result<HashMap<_, _>, String>>()?;
+
+            ledger.update_network(network_config.quorum_set(), move |slot_index| {
+                let peer_keepalive = peer_keepalive.upgrade();
+                let mut broadcaster = broadcaster.lock().expect("mutex poisoned");
+
+                for responder_id in peer_manager.responder_ids() {
+                    if !broadcast_peers.contains_key(&responder_id) {
+                        log::info!(
+                            logger,
+                            "Removing peer {} at slot {}",
+                            responder_id,
+                            slot_index
+                        );
+                        peer_manager.remove_conn(&responder_id);
+                        broadcaster.remove_peer(&responder_id);
+                        if let Some(peer_keepalive) = peer_keepalive.as_ref() {
+                            peer_keepalive.forget_peer(&responder_id);
+                        }
+                    }
+                }
+
+                for (responder_id, peer_uri) in broadcast_peers {
+                    let unchanged = peer_manager
+                        .conn(&responder_id)
+                        .map(|conn| conn.uri() == peer_uri)
+                        .unwrap_or(false);
+                    if unchanged {
+                        continue;
+                    }
+
+                    log::info!(logger, "Adding peer {} at slot {}", peer_uri, slot_index);
+                    let conn = peer_manager.add_conn(PeerConnection::new(
+                        enclave.clone(),
+                        local_node_id.clone(),
+                        peer_uri,
+                        env.clone(),
+                        logger.clone(),
+                    ));
+                    broadcaster.add_peer(conn);
+                    if let Some(peer_keepalive) = peer_keepalive.as_ref() {
+                        peer_keepalive.heard_from_peer(responder_id);
+                    }
+                }
+            });
+
+            Ok(())
+        })
+    }
+
     /// Helper method for creating the get config json function needed by the
     /// GRPC admin service.
     fn create_get_config_json_fn(&self) -> GetConfigJsonFn {
@@ -694,8 +829,10 @@ impl<
             .map(Arc::downgrade)
             .expect("Server was not initialized");
         let config = self.config.clone();
+        let network_reload = self.network_reload.clone();
         let logger = self.logger.clone();
         Arc::new(move || {
+            let network = current_network(&config, network_reload.as_deref());
             let mut sync_status = "synced";
             let mut peer_block_height: u64 = 0;
             byzantine_ledger.upgrade().map(|ledger| {
@@ -767,12 +904,12 @@ impl<
                     "client_auth_token_enabled": config.client_auth_token_secret.map(|_| true).unwrap_or(false),
                     "client_auth_token_max_lifetime": config.client_auth_token_max_lifetime.as_secs(),
                 },
-                "network": config.network(),
+                "network": network,
                 "status": {
                     "block_height": block_height,
                     "version": VERSION,
-                    "broadcast_peer_count": config.network().broadcast_peers.len(),
-                    "known_peer_count": config.network().known_peers.map_or(0, |x| x.len()),
+                    "broadcast_peer_count": network.broadcast_peers.len(),
+                    "known_peer_count": network.known_peers.as_ref().map_or(0, |x| x.len()),
                     "sync_status": sync_status,
                     "blocks_behind": blocks_behind,
                     "latest_block_hash": latest_block_hash,
@@ -784,6 +921,29 @@ impl<
     }
 }
 
+/// The network configuration in effect: the last accepted reload, or the
+/// network file if there is none. This will panic if the configuration is
+/// invalid.
+fn current_network(
+    config: &Config,
+    network_reload: Option<&Mutex<NetworkReloadSequence>>,
+) -> NetworkConfig {
+    let network_config_json = network_reload.and_then(|network_reload| {
+        network_reload
+            .lock()
+            .expect("mutex poisoned")
+            .network_config_json()
+            .map(ToOwned::to_owned)
+    });
+    match network_config_json {
+        Some(json) => NetworkConfig::load_from_json(&json, &config.peer_responder_id)
+            .unwrap_or_else(|err| {
+                panic!("Failed loading the reloaded network configuration: {err}")
+            }),
+        None => config.network(),
+    }
+}
+
 impl<
         E: ConsensusEnclave + Clone + Send + Sync + 'static,
         TXM: TxManager + Clone + Send + Sync + 'static,
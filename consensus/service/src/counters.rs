@@ -113,6 +113,33 @@ lazy_static::lazy_static! {
 
     // Number of times a ProposeMintTx call has returned a response.
     pub static ref PROPOSE_MINT_TX: IntCounter = OP_COUNTERS.counter("propose_mint_tx");
+
+    // Number of tx proposals rejected because a client session exceeded its rate limit.
+    pub static ref ADMISSION_SESSION_RATE_LIMITED: IntCounter = OP_COUNTERS.counter("admission_session_rate_limited");
+
+    // Number of tx proposals rejected because a client IP address exceeded its rate limit.
+    pub static ref ADMISSION_IP_RATE_LIMITED: IntCounter = OP_COUNTERS.counter("admission_ip_rate_limited");
+
+    // Number of tx proposals rejected because the client had too many pending transactions.
+    pub static ref ADMISSION_TOO_MANY_PENDING_TXS: IntCounter = OP_COUNTERS.counter("admission_too_many_pending_txs");
+
+    // Number of tx proposals rejected because pending values exceeded the high-water mark.
+    pub static ref ADMISSION_BACKPRESSURE: IntCounter = OP_COUNTERS.counter("admission_backpressure");
+
+    // Number of client sessions with pending transactions tracked by admission control.
+    pub static ref ADMISSION_TRACKED_PENDING_CLIENTS: IntGauge = OP_COUNTERS.gauge("admission_tracked_pending_clients");
+
+    // Number of network configuration reloads requested through the admin API.
+    pub static ref NETWORK_RELOAD_REQUESTS: IntCounter = OP_COUNTERS.counter("network_reload_requests");
+
+    // Number of network configuration reloads rejected by the admin API.
+    pub static ref NETWORK_RELOAD_REJECTED: IntCounter = OP_COUNTERS.counter("network_reload_rejected");
+
+    // Number of network configuration changes applied at a slot boundary.
+    pub static ref NETWORK_RECONFIGURATIONS: IntCounter = OP_COUNTERS.counter("network_reconfigurations");
+
+    // Number of nodes in the quorum set applied by the most recent network configuration change.
+    pub static ref QUORUM_SET_SIZE: IntGauge = OP_COUNTERS.gauge("quorum_set_size");
 }
 
 /// TxValidationErrorMetrics keeps track of tx validation errors upon ingress
@@ -93,6 +93,16 @@ impl PeerKeepalive {
         responder_id_to_last_heard.insert(responder_id, Instant::now());
     }
 
+    /// Stop tracking a peer, e.g. after it has been removed from the network
+    /// configuration.
+    pub fn forget_peer(&self, responder_id: &ResponderId) {
+        let mut responder_id_to_last_heard = self
+            .responder_id_to_last_heard
+            .lock()
+            .expect("mutex poisoned");
+        responder_id_to_last_heard.remove(responder_id);
+    }
+
     fn thread_entrypoint<CC: ConsensusConnection>(
         conn_manager: ConnectionManager<CC>,
         stop_requested: Arc<AtomicBool>,
@@ -95,6 +95,8 @@ impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManagerImpl<E
             current_block_index,
             highest_index_proofs,
         )?;
+        self.untrusted
+            .well_formed_context_check(&well_formed_tx_context)?;
 
         Ok(CacheEntry {
             encrypted_tx: well_formed_encrypted_tx,
@@ -353,6 +355,10 @@ mod tests {
             .expect_well_formed_check()
             .times(1)
             .return_const(Ok((0, vec![])));
+        mock_untrusted
+            .expect_well_formed_context_check()
+            .times(1)
+            .return_const(Ok(()));
 
         // The enclave's well-formed check also ought to be called, and should return
         // Ok.
@@ -394,6 +400,10 @@ mod tests {
             .expect_well_formed_check()
             .times(1)
             .return_const(Ok((0, vec![])));
+        mock_untrusted
+            .expect_well_formed_context_check()
+            .times(1)
+            .return_const(Ok(()));
 
         // The enclave's well-formed check also ought to be called, and should return
         // Ok.
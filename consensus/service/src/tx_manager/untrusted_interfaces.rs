@@ -23,6 +23,14 @@ pub trait UntrustedInterfaces: Send + Sync {
         tx_context: &TxContext,
     ) -> TransactionValidationResult<(u64, Vec<TxOutMembershipProof>)>;
 
+    /// Performs the part of the well-formed check that needs the enclave's
+    /// view of the transaction, before it enters the pool. These checks depend
+    /// on node-local state, so they are not repeated when forming a block.
+    fn well_formed_context_check(
+        &self,
+        context: &WellFormedTxContext,
+    ) -> TransactionValidationResult<()>;
+
     /// Checks if a transaction is valid (see definition in validators.rs).
     fn is_valid(&self, context: Arc<WellFormedTxContext>) -> TransactionValidationResult<()>;
 
@@ -30,6 +30,7 @@ use mc_transaction_core::{
     ring_signature::KeyImage,
     tx::{TxHash, TxOutMembershipProof},
     validation::{validate_tombstone, TransactionValidationError, TransactionValidationResult},
+    InputRuleError, InputRules,
 };
 use std::{collections::HashSet, sync::Arc};
 
@@ -70,6 +71,32 @@ impl<L: Ledger + Sync> TxManagerUntrustedInterfaces for DefaultTxManagerUntruste
         Ok((num_blocks - 1, membership_proofs))
     }
 
+    /// Checks the input rules' max block timestamp against the timestamp in
+    /// the latest block's metadata. Block metadata is local to each node, so
+    /// this is only checked before a transaction enters the pool, and not
+    /// when a block is formed.
+    fn well_formed_context_check(
+        &self,
+        context: &WellFormedTxContext,
+    ) -> TransactionValidationResult<()> {
+        if context.max_block_timestamp() == 0 {
+            return Ok(());
+        }
+        let num_blocks = self
+            .ledger
+            .num_blocks()
+            .map_err(|e| TransactionValidationError::Ledger(e.to_string()))?;
+        let latest_block_timestamp = self
+            .ledger
+            .get_block_timestamp(num_blocks - 1)
+            .map_err(|e| TransactionValidationError::Ledger(e.to_string()))?;
+        let rules = InputRules {
+            max_block_timestamp: context.max_block_timestamp(),
+            ..Default::default()
+        };
+        Ok(rules.verify_block_timestamp(latest_block_timestamp)?)
+    }
+
     /// Checks if a transaction is valid (see definition at top of this file).
     fn is_valid(&self, context: Arc<WellFormedTxContext>) -> TransactionValidationResult<()> {
         // Get the index of the current block we will be building.
@@ -82,6 +109,12 @@ impl<L: Ledger + Sync> TxManagerUntrustedInterfaces for DefaultTxManagerUntruste
         // too far in the future.
         validate_tombstone(current_block_index, context.tombstone_block())?;
 
+        // The input rules must allow the transaction to be included in this block.
+        // The enclave checks the same rule when forming the block.
+        if current_block_index < context.valid_from_block() {
+            return Err(InputRuleError::BlockIndexBeforeValidFromBlock.into());
+        }
+
         // The `key_images` must not have already been spent.
         let contains_spent_key_image = context
             .key_images()
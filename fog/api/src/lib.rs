@@ -32,6 +32,7 @@ use std::{collections::BTreeSet, str::FromStr};
 // They implement PartialEq but not Eq for some reason
 impl Eq for autogenerated_code::view::QueryRequest {}
 impl Eq for autogenerated_code::view::QueryRequestAAD {}
+impl Eq for autogenerated_code::ledger::CheckKeyImagesRequestAAD {}
 impl Eq for autogenerated_code::kex_rng::KexRngPubkey {}
 impl Eq for autogenerated_code::kex_rng::StoredRng {}
 
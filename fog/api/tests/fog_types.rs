@@ -281,6 +281,29 @@ fn check_key_images_response_round_trip() {
     });
 }
 
+/// Test that many random instances of prosty CheckKeyImagesRequestAAD round
+/// trip with protobufy CheckKeyImagesRequestAAD
+#[test]
+fn check_key_images_request_aad_round_trip() {
+    {
+        let test_val = mc_fog_types::ledger::CheckKeyImagesRequestAAD::default();
+        round_trip_message::<
+            mc_fog_types::ledger::CheckKeyImagesRequestAAD,
+            mc_fog_api::ledger::CheckKeyImagesRequestAAD,
+        >(&test_val);
+    }
+
+    run_with_several_seeds(|mut rng| {
+        let test_val = mc_fog_types::ledger::CheckKeyImagesRequestAAD {
+            start_from_block_index: rng.next_u64(),
+        };
+        round_trip_message::<
+            mc_fog_types::ledger::CheckKeyImagesRequestAAD,
+            mc_fog_api::ledger::CheckKeyImagesRequestAAD,
+        >(&test_val);
+    });
+}
+
 /// Test that .proto enum values match what is in
 /// src/fog/recovery_db_iface/src/types.rs
 #[test]
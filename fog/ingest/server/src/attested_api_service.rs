@@ -6,30 +6,34 @@ use crate::{controller::IngestController, error::IngestServiceError, SVC_COUNTER
 use grpcio::{RpcContext, UnarySink};
 use mc_attest_api::{attest::AuthMessage, attest_grpc::AttestedApi};
 use mc_common::logger::{log, Logger};
+use mc_fog_ingest_enclave_api::IngestEnclaveProxy;
 use mc_fog_recovery_db_iface::{RecoveryDb, ReportDb};
 use mc_util_grpc::{rpc_logger, rpc_permissions_error, send_result};
 use std::sync::Arc;
 
 #[derive(Clone)]
-pub struct AttestedApiService<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static>
-where
+pub struct AttestedApiService<
+    DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static,
+    E: IngestEnclaveProxy,
+> where
     IngestServiceError: From<<DB as RecoveryDb>::Error>,
 {
-    controller: Arc<IngestController<DB>>,
+    controller: Arc<IngestController<DB, E>>,
     logger: Logger,
 }
 
-impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static> AttestedApiService<DB>
+impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static, E: IngestEnclaveProxy>
+    AttestedApiService<DB, E>
 where
     IngestServiceError: From<<DB as RecoveryDb>::Error>,
 {
-    pub fn new(controller: Arc<IngestController<DB>>, logger: Logger) -> Self {
+    pub fn new(controller: Arc<IngestController<DB, E>>, logger: Logger) -> Self {
         Self { controller, logger }
     }
 }
 
-impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static> AttestedApi
-    for AttestedApiService<DB>
+impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static, E: IngestEnclaveProxy> AttestedApi
+    for AttestedApiService<DB, E>
 where
     IngestServiceError: From<<DB as RecoveryDb>::Error>,
 {
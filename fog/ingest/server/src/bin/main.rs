@@ -11,7 +11,7 @@ use mc_fog_ingest_server::{
     server::{IngestServer, IngestServerConfig},
     state_file::StateFile,
 };
-use mc_fog_sql_recovery_db::SqlRecoveryDb;
+use mc_fog_recovery_db::AnyRecoveryDb;
 use mc_ledger_db::LedgerDB;
 use mc_util_cli::ParserWithBuildInfo;
 use mc_util_grpc::AdminServer;
@@ -50,7 +50,7 @@ fn main() {
     // Open databases.
     let database_url =
         std::env::var("DATABASE_URL").expect("DATABASE_URL environment variable missing");
-    let recovery_db = SqlRecoveryDb::new_from_url(
+    let recovery_db = AnyRecoveryDb::new_from_url(
         &database_url,
         config.postgres_config.clone(),
         logger.clone(),
@@ -98,6 +98,7 @@ fn main() {
         state_file: Some(StateFile::new(state_file_path)),
         enclave_path,
         poll_interval: config.poll_interval,
+        block_batch_size: config.block_batch_size,
     };
 
     let mut server = IngestServer::new(server_config, recovery_db, block_provider, logger.clone());
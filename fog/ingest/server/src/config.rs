@@ -109,6 +109,12 @@ pub struct IngestConfig {
     /// How many milliseconds to wait between polling.
     #[clap(long = "poll_interval_ms", default_value = "250", value_parser = parse_duration_in_millis, env = "MC_POLL_INTERVAL_MS")]
     pub poll_interval: Duration,
+
+    /// The maximum number of blocks to fetch from the ledger and process at a
+    /// time, when ingest is behind the ledger. Writing a block to the recovery
+    /// db overlaps with the enclave ingesting the next block.
+    #[clap(long, default_value = "10", env = "MC_BLOCK_BATCH_SIZE")]
+    pub block_batch_size: usize,
 }
 
 #[cfg(test)]
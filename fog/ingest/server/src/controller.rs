@@ -23,13 +23,13 @@ use mc_fog_api::{
     report_parse::try_extract_unvalidated_ingress_pubkey_from_fog_evidence,
 };
 use mc_fog_ingest_enclave::{
-    Error as EnclaveError, IngestEnclave, IngestSgxEnclave, NewEnclaveError,
+    Error as EnclaveError, IngestEnclave, IngestEnclaveProxy, IngestSgxEnclave, NewEnclaveError,
 };
 use mc_fog_recovery_db_iface::{
-    IngressPublicKeyRecord, IngressPublicKeyRecordFilters, IngressPublicKeyStatus, RecoveryDb,
-    ReportData, ReportDb,
+    IngestInvocationId, IngressPublicKeyRecord, IngressPublicKeyRecordFilters,
+    IngressPublicKeyStatus, RecoveryDb, ReportData, ReportDb,
 };
-use mc_fog_types::{common::BlockRange, ingest::TxsForIngest};
+use mc_fog_types::{common::BlockRange, ingest::TxsForIngest, view::KexRngPubkey, ETxOutRecord};
 use mc_fog_uri::IngestPeerUri;
 use mc_sgx_report_cache_api::ReportableEnclave;
 use mc_sgx_report_cache_untrusted::{Error as ReportCacheError, ReportCache};
@@ -39,6 +39,7 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     io::ErrorKind,
     sync::{Arc, Mutex, MutexGuard},
+    thread::ScopedJoinHandle,
 };
 
 /// The ingest controller sits under the grpc / networking layer, and implements
@@ -59,8 +60,10 @@ use std::{
 /// So the idea here is instead that the IngestController owns no threads, the
 /// IngestWorker is external to it, and all the grpcio threads are also external
 /// to it, and talk to Arc<IngestController> to accomplish their tasks.
-pub struct IngestController<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static>
-where
+pub struct IngestController<
+    DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static,
+    E: IngestEnclaveProxy = IngestSgxEnclave,
+> where
     Error: From<<DB as RecoveryDb>::Error>,
 {
     /// The config object for the server
@@ -68,11 +71,11 @@ where
     /// State controlling the operation of the server
     controller_state: Arc<Mutex<IngestControllerState>>,
     /// The enclave supporting the server's operation
-    enclave: IngestSgxEnclave,
+    enclave: E,
     /// The recovery db that we write rng records and txout records to
     recovery_db: DB,
     /// The cache for reports from this enclave
-    report_cache: Arc<Mutex<ReportCache<IngestSgxEnclave>>>,
+    report_cache: Arc<Mutex<ReportCache<E>>>,
     /// grpc environment (thread pool) for grpc connections to our peers
     /// Note: we only make synchronous grpc calls in igp connection object,
     /// and this env isn't used to recieve any connections,
@@ -85,17 +88,22 @@ where
     logger: Logger,
 }
 
+/// The enclave output for one block, before it is written to recovery db
+struct IngestedBlockTxs {
+    /// The encrypted tx out records of the block
+    tx_rows: Vec<ETxOutRecord>,
+    /// The new kex rng pubkeys that the enclave emitted while ingesting the
+    /// block, in order
+    new_kex_rng_pubkeys: Vec<KexRngPubkey>,
+}
+
 impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static> IngestController<DB>
 where
     Error: From<<DB as RecoveryDb>::Error>,
 {
-    /// Create a new ingest controller
+    /// Create a new ingest controller, with an SGX enclave loaded from the
+    /// configured enclave path
     pub fn new(config: IngestServerConfig, recovery_db: DB, logger: Logger) -> Self {
-        let controller_state = Arc::new(Mutex::new(IngestControllerState::new(
-            &config,
-            logger.clone(),
-        )));
-
         // Load the statefile if there is one:
         let state_file_data: Option<IngestStateFile> =
             config.state_file.as_ref().and_then(|file| {
@@ -175,6 +183,41 @@ where
             }
         };
 
+        Self::from_enclave(config, enclave, recovery_db, state_file_data, logger)
+    }
+}
+
+impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static, E: IngestEnclaveProxy>
+    IngestController<DB, E>
+where
+    Error: From<<DB as RecoveryDb>::Error>,
+{
+    /// Create a new ingest controller around an enclave which has already been
+    /// initialized, e.g. an in-process enclave for tests. No state is restored
+    /// from the state file.
+    pub fn new_with_enclave(
+        config: IngestServerConfig,
+        enclave: E,
+        recovery_db: DB,
+        logger: Logger,
+    ) -> Self {
+        Self::from_enclave(config, enclave, recovery_db, None, logger)
+    }
+
+    /// Finish creating the controller, once the enclave is initialized,
+    /// restoring state from the state file data if there is any.
+    fn from_enclave(
+        config: IngestServerConfig,
+        enclave: E,
+        recovery_db: DB,
+        state_file_data: Option<IngestStateFile>,
+        logger: Logger,
+    ) -> Self {
+        let controller_state = Arc::new(Mutex::new(IngestControllerState::new(
+            &config,
+            logger.clone(),
+        )));
+
         // Initialize report cache
         let report_cache = Arc::new(Mutex::new(ReportCache::new(
             enclave.clone(),
@@ -385,6 +428,33 @@ where
     /// If we cannot, because the ingress key is retired and there is no more
     /// work to do with it, set ourselves to the idle state and early
     /// return.
+    pub fn process_next_block(
+        &self,
+        block: &Block,
+        block_contents: &BlockContents,
+        timestamp: u64,
+    ) {
+        self.process_next_blocks(&[(block, block_contents, timestamp)])
+    }
+
+    /// Process consecutive blocks, starting at the next block index, as
+    /// process_next_block does for one block.
+    ///
+    /// The blocks are pipelined: while the ETxOutRecord's of one block are
+    /// written to recovery db, the enclave already ingests the next block.
+    /// Publishing reports, rotating ingest invocations and updating our state
+    /// still happen in block order, only after the previous block is written.
+    ///
+    /// Each block still goes to the enclave in its own chunks, rather than
+    /// in chunks spanning several blocks:
+    /// * A TxsForIngest carries a single block index, global txo index and
+    ///   timestamp, so multi-block chunks would change the enclave's ecall API
+    ///   and its measurement.
+    /// * The enclave reports at most one kex rng rotation per call, and
+    ///   prepare_block_data needs to know which block it happened in, to start
+    ///   the new ingest invocation at that block.
+    /// * Losing the publish race would throw away the rng outputs of a whole
+    ///   chunk instead of those of a single block.
     ///
     /// This function must maintain an invariant around the ingest invocation
     /// id:
@@ -397,25 +467,161 @@ where
     ///   database, there will be gaps in the RNG sequences (some of the entries
     ///   won't make it to database), and the client's won't be able to perform
     ///   balance checks successfully then.
-    pub fn process_next_block(
+    ///
+    /// If a block is not published because another server published it
+    /// first, the enclave already consumed rng outputs for the following
+    /// block, so the egress key is rotated once more, and we stop.
+    /// We also stop if the enclave fails, or our ingress key expired.
+    pub fn process_next_blocks(&self, blocks: &[(&Block, &BlockContents, u64)]) {
+        std::thread::scope(|scope| {
+            let mut pending_write: Option<ScopedJoinHandle<bool>> = None;
+
+            for &(block, block_contents, timestamp) in blocks {
+                let _process_next_block_timer = counters::PROCESS_NEXT_BLOCK_TIME.start_timer();
+
+                let ingress_pubkey: CompressedRistrettoPublic = self
+                    .enclave
+                    .get_ingress_pubkey()
+                    .expect("Failed to get ingress pubkey")
+                    .into();
+
+                let initial_kex_rng_pubkey = self
+                    .enclave
+                    .get_kex_rng_pubkey()
+                    .expect("Failed to get kex rng pubkey");
+
+                let ingested = self.ingest_block_txs(block, block_contents, timestamp);
+
+                if let Some(pending_write) = pending_write.take() {
+                    let published = pending_write
+                        .join()
+                        .expect("Thread writing block data panicked");
+                    if !published {
+                        log::info!(self.logger, "Discarding the enclave output for block {}, since the previous block was not published", block.index);
+                        let mut state = self.get_state();
+                        self.new_egress_key(&mut state)
+                            .expect("Failure to rotate egress key after we can't publish data isn't recoverable, the RNGs would have gaps that the clients can't deal with");
+                        return;
+                    }
+                }
+
+                let ingested = match ingested {
+                    Some(ingested) => ingested,
+                    None => return,
+                };
+
+                let iid = match self.prepare_block_data(
+                    block,
+                    &ingress_pubkey,
+                    &initial_kex_rng_pubkey,
+                    ingested.new_kex_rng_pubkeys,
+                ) {
+                    Some(iid) => iid,
+                    None => return,
+                };
+
+                pending_write = Some(scope.spawn(move || {
+                    self.write_block_data(
+                        &iid,
+                        block,
+                        timestamp,
+                        &ingested.tx_rows,
+                        &ingress_pubkey,
+                    )
+                }));
+            }
+
+            if let Some(pending_write) = pending_write {
+                pending_write
+                    .join()
+                    .expect("Thread writing block data panicked");
+            }
+        })
+    }
+
+    // Helper for process_next_blocks which runs the txs of a block through the
+    // enclave, returning None if the enclave failed.
+    fn ingest_block_txs(
         &self,
         block: &Block,
         block_contents: &BlockContents,
         timestamp: u64,
-    ) {
-        let _process_next_block_timer = counters::PROCESS_NEXT_BLOCK_TIME.start_timer();
+    ) -> Option<IngestedBlockTxs> {
+        // TxsForIngest expects global_txo_index to be the index of the first TxOut in
+        // the block handed to it.
+        assert!(block.cumulative_txo_count >= block_contents.outputs.len() as u64);
+        let mut global_txo_index = block.cumulative_txo_count - block_contents.outputs.len() as u64;
+        let initial_global_txo_index = global_txo_index;
 
-        let ingress_pubkey: CompressedRistrettoPublic = self
-            .enclave
-            .get_ingress_pubkey()
-            .expect("Failed to get ingress pubkey")
-            .into();
+        // tx_rows are records containing tx outs, encrypted for the users.
+        // there is typically (and at most) one tx row per tx out that comes in.
+        let mut tx_rows = Vec::with_capacity(block_contents.outputs.len());
+        let mut new_kex_rng_pubkeys = Vec::new();
+        let chunks = block_contents.outputs.chunks(self.config.max_transactions);
+        let num_chunks = chunks.len();
+        for (chunk_index, chunk) in chunks.enumerate() {
+            log::trace!(
+                self.logger,
+                "Chunk {}/{} with {} TxOuts",
+                chunk_index + 1,
+                num_chunks,
+                chunk.len()
+            );
 
-        let initial_kex_rng_pubkey = self
-            .enclave
-            .get_kex_rng_pubkey()
-            .expect("Failed to get kex rng pubkey");
+            let txs_chunk = TxsForIngest {
+                block_index: block.index,
+                global_txo_index,
+                redacted_txs: chunk.to_vec(),
+                timestamp,
+            };
+
+            log::trace!(self.logger, "into enclave");
+            let ingest_txs_timer = counters::INGEST_TXS_TIME.start_timer();
+            let (new_tx_rows, maybe_kex_rng_pubkey) = match self.enclave.ingest_txs(txs_chunk) {
+                Ok(pair) => pair,
+                Err(err) => {
+                    log::error!(self.logger, "Failed ingesting txs: {}", err);
+                    return None;
+                }
+            };
+            drop(ingest_txs_timer);
+            log::trace!(self.logger, "out enclave");
+
+            // Don't commit the results immediately, try to commit the whole
+            // block transactionally
+            tx_rows.extend(new_tx_rows);
+            global_txo_index += chunk.len() as u64;
+
+            // If the enclave emitted a new rng pubkey, we need to decommission
+            // the old one and put the new one in the database, which happens
+            // once the previous block is written.
+            new_kex_rng_pubkeys.extend(maybe_kex_rng_pubkey);
+
+            log::debug!(
+                self.logger,
+                "Ingesting block #{:?}: {}/{} txs ingested",
+                block.index,
+                global_txo_index - initial_global_txo_index,
+                block_contents.outputs.len()
+            );
+        }
+
+        Some(IngestedBlockTxs {
+            tx_rows,
+            new_kex_rng_pubkeys,
+        })
+    }
 
+    // Helper for process_next_blocks which publishes a report and gets the
+    // ingest invocation id to write a block with, after the previous block was
+    // written. Returns None if the ingress key expired and we became idle.
+    fn prepare_block_data(
+        &self,
+        block: &Block,
+        ingress_pubkey: &CompressedRistrettoPublic,
+        initial_kex_rng_pubkey: &KexRngPubkey,
+        new_kex_rng_pubkeys: Vec<KexRngPubkey>,
+    ) -> Option<IngestInvocationId> {
         // Scope for mutex: Get invocation id, confirm next_block_index
         let mut iid = {
             let mut state = self.get_state();
@@ -440,7 +646,7 @@ where
                 log::trace!(self.logger, "publish report");
                 let mut retry_seconds = 1;
                 loop {
-                    match self.publish_report(&ingress_pubkey, &state) {
+                    match self.publish_report(ingress_pubkey, &state) {
                         Ok(ingress_key_status) => {
                             // If our key is retired, and the index we want to scan is past expiry,
                             // early return. Note, we don't even NEED to scan
@@ -457,7 +663,7 @@ where
                                 state.set_idle();
                                 self.new_egress_key(&mut state)
                                     .expect("Failure to rotate egress key can't be recovered from");
-                                return;
+                                return None;
                             }
                             break;
                         }
@@ -499,8 +705,8 @@ where
                     self.recovery_db
                         .new_ingest_invocation(
                             None,
-                            &ingress_pubkey,
-                            &initial_kex_rng_pubkey,
+                            ingress_pubkey,
+                            initial_kex_rng_pubkey,
                             block.index,
                         )
                         .expect("Failed recording new ingest invocation and kex rng pubkey"),
@@ -508,91 +714,55 @@ where
                 state.set_ingest_invocation_id(&iid);
             }
 
-            iid
+            iid.expect("no ingest_invocation_id")
         };
 
-        // TxsForIngest expects global_txo_index to be the index of the first TxOut in
-        // the block handed to it.
-        assert!(block.cumulative_txo_count >= block_contents.outputs.len() as u64);
-        let mut global_txo_index = block.cumulative_txo_count - block_contents.outputs.len() as u64;
-        let initial_global_txo_index = global_txo_index;
-
-        // tx_rows are records containing tx outs, encrypted for the users.
-        // there is typically (and at most) one tx row per tx out that comes in.
-        let mut tx_rows = Vec::with_capacity(block_contents.outputs.len());
-        let chunks = block_contents.outputs.chunks(self.config.max_transactions);
-        let num_chunks = chunks.len();
-        for (chunk_index, chunk) in chunks.enumerate() {
-            log::trace!(
-                self.logger,
-                "Chunk {}/{} with {} TxOuts",
-                chunk_index + 1,
-                num_chunks,
-                chunk.len()
-            );
-
-            let txs_chunk = TxsForIngest {
-                block_index: block.index,
-                global_txo_index,
-                redacted_txs: chunk.to_vec(),
-                timestamp,
-            };
-
-            log::trace!(self.logger, "into enclave");
-            let ingest_txs_timer = counters::INGEST_TXS_TIME.start_timer();
-            let (new_tx_rows, maybe_kex_rng_pubkey) = match self.enclave.ingest_txs(txs_chunk) {
-                Ok(pair) => pair,
-                Err(err) => {
-                    log::error!(self.logger, "Failed ingesting txs: {}", err);
-                    return;
-                }
-            };
-            drop(ingest_txs_timer);
-            log::trace!(self.logger, "out enclave");
-
-            // Don't commit the results immediately, try to commit the whole
-            // block transactionally
-            tx_rows.extend(new_tx_rows);
-            global_txo_index += chunk.len() as u64;
-
-            // If the enclave emitted a new rng pubkey, we need to decommission
-            // the old one and put the new one in the database.
-            // This should happen only rarely, when ingest hashmap overflows
-            // FIXME: FOG-390: We should queue these up and make these additions
-            // atomic in the add-block-data operation, so that none of them happen,
-            // and the clients never see them, unless we manage to publish a block
-            if let Some(new_kex_rng_pubkey) = maybe_kex_rng_pubkey {
-                let mut retry_seconds = 1;
-                let new_iid = loop {
-                    match self.recovery_db.new_ingest_invocation(
-                        Some(iid.expect("no ingest_invocation_id")),
-                        &ingress_pubkey,
-                        &new_kex_rng_pubkey,
-                        block.index,
-                    ) {
-                        Ok(new_iid) => {
-                            break new_iid;
-                        }
-                        Err(err) => {
-                            log::crit!(self.logger, "Could not rotate kex rng pubkey in recovery database! Retrying...: {}", err);
-                            std::thread::sleep(std::time::Duration::from_secs(retry_seconds));
-                            retry_seconds = std::cmp::min(retry_seconds + 1, 30);
-                        }
-                    };
+        // If the enclave emitted new rng pubkeys, we need to decommission
+        // the old one and put the new one in the database.
+        // This should happen only rarely, when ingest hashmap overflows
+        // FIXME: FOG-390: We should queue these up and make these additions
+        // atomic in the add-block-data operation, so that none of them happen,
+        // and the clients never see them, unless we manage to publish a block
+        for new_kex_rng_pubkey in new_kex_rng_pubkeys {
+            let mut retry_seconds = 1;
+            iid = loop {
+                match self.recovery_db.new_ingest_invocation(
+                    Some(iid),
+                    ingress_pubkey,
+                    &new_kex_rng_pubkey,
+                    block.index,
+                ) {
+                    Ok(new_iid) => {
+                        break new_iid;
+                    }
+                    Err(err) => {
+                        log::crit!(
+                            self.logger,
+                            "Could not rotate kex rng pubkey in recovery database! Retrying...: {}",
+                            err
+                        );
+                        std::thread::sleep(std::time::Duration::from_secs(retry_seconds));
+                        retry_seconds = std::cmp::min(retry_seconds + 1, 30);
+                    }
                 };
-                iid = Some(new_iid);
-                self.get_state().set_ingest_invocation_id(&iid);
-            }
-
-            log::debug!(
-                self.logger,
-                "Ingesting block #{:?}: {}/{} txs ingested",
-                block.index,
-                global_txo_index - initial_global_txo_index,
-                block_contents.outputs.len()
-            );
+            };
+            self.get_state().set_ingest_invocation_id(&Some(iid));
         }
 
+        Some(iid)
+    }
+
+    // Helper for process_next_blocks which commits the ETxOutRecord's of a
+    // block to the database, and increments next_block_index. Returns false if
+    // another server published the block first, and we became idle.
+    fn write_block_data(
+        &self,
+        iid: &IngestInvocationId,
+        block: &Block,
+        timestamp: u64,
+        tx_rows: &[ETxOutRecord],
+        ingress_pubkey: &CompressedRistrettoPublic,
+    ) -> bool {
         log::info!(self.logger, "add_block_data");
 
         // Commit all the new data to the database,
@@ -609,21 +779,17 @@ where
         // different ingest server with the same ingress public key
         // as this server has already published data for this block.
         let mut retry_seconds = 1;
-        loop {
+        let published = loop {
             let db_metrics_timer = counters::DB_ADD_BLOCK_DATA_TIME.start_timer();
-            match self.recovery_db.add_block_data(
-                // It's okay to .expect here since this code should not run if we did not get an
-                // ingest invocation id.
-                iid.as_ref().expect("no ingest invocation id"),
-                block,
-                timestamp,
-                &tx_rows,
-            ) {
+            match self
+                .recovery_db
+                .add_block_data(iid, block, timestamp, tx_rows)
+            {
                 Ok(add_blocks_result) => {
                     log::trace!(self.logger, "state update");
                     let mut state = self.get_state();
 
-                    if add_blocks_result.block_already_scanned_with_this_key {
+                    let published = if add_blocks_result.block_already_scanned_with_this_key {
                         // We lost the race to publish this block
                         log::info!(self.logger, "Another active server did work for block {}, we should become idle and back off", block.index);
                         state.set_idle();
@@ -631,6 +797,7 @@ where
                         // scanned something that didn't get published
                         // new_egress_key also makes sure our rng is decommissioned
                         self.new_egress_key(&mut state).expect("Failure to rotate egress key after we can't publish data isn't recoverable, the RNGs would have gaps that the clients can't deal with");
+                        false
                     } else {
                         // We won the race to publish this block
                         log::info!(
@@ -642,10 +809,11 @@ where
                         );
                         log::trace!(self.logger, "increment_next_block_index");
                         state.increment_next_block_index();
-                    }
+                        true
+                    };
 
                     log::debug!(self.logger, "Controller state: {}", state);
-                    break;
+                    break published;
                 }
                 Err(err) => {
                     log::crit!(self.logger, "add_block_data failed while attempting to add {} rows for block #{}: {}. Retrying in {} seconds", tx_rows.len(), block.index, err, retry_seconds);
@@ -654,13 +822,15 @@ where
                     let _ = db_metrics_timer.stop_and_discard();
                 }
             }
-        }
+        };
 
         log::info!(&self.logger, "Finished ingesting block #{:?}", block.index);
         counters::LAST_PROCESSED_BLOCK_INDEX.set(block.index as i64);
         counters::BLOCKS_PROCESSED_COUNT.inc();
 
         self.write_state_file();
+
+        published
     }
 
     /// Attempt to put this server safely in the active mode
@@ -707,7 +877,7 @@ where
                     None
                 } else {
                     log::info!(self.logger, "activate: connect to peer {}", peer);
-                    Some(PeerConnection::<IngestSgxEnclave>::new(
+                    Some(PeerConnection::<E>::new(
                         self.enclave.clone(),
                         self.config.local_node_id.clone(),
                         peer.clone(),
@@ -895,7 +1065,7 @@ where
 
         log::info!(self.logger, "Syncing from Remote URI: {}", remote);
 
-        let mut connection = PeerConnection::<IngestSgxEnclave>::new(
+        let mut connection = PeerConnection::<E>::new(
             self.enclave.clone(),
             self.config.local_node_id.clone(),
             remote.clone(),
@@ -1066,7 +1236,7 @@ where
                     continue;
                 }
 
-                let mut conn = PeerConnection::<IngestSgxEnclave>::new(
+                let mut conn = PeerConnection::<E>::new(
                     self.enclave.clone(),
                     self.config.local_node_id.clone(),
                     peer_uri.clone(),
@@ -1358,7 +1528,7 @@ where
 
             log::debug!(self.logger, "Checking on peer: {}", peer_uri);
             // Build a peer connection
-            let mut conn = PeerConnection::<IngestSgxEnclave>::new(
+            let mut conn = PeerConnection::<E>::new(
                 self.enclave.clone(),
                 self.config.local_node_id.clone(),
                 peer_uri.clone(),
@@ -1422,7 +1592,7 @@ where
     /// - Err if the peer is not now a correctly configured backup.
     fn confirm_backup(
         &self,
-        conn: &mut PeerConnection<IngestSgxEnclave>,
+        conn: &mut PeerConnection<E>,
         cached_summary: Option<&IngestSummary>,
         cached_our_pubkey: Option<&CompressedRistrettoPublic>,
         cached_our_peers: Option<&BTreeSet<IngestPeerUri>>,
@@ -12,7 +12,7 @@ use mc_fog_api::{
     ingest_peer::*,
     Empty,
 };
-use mc_fog_ingest_enclave_api::Error as EnclaveError;
+use mc_fog_ingest_enclave_api::{Error as EnclaveError, IngestEnclaveProxy};
 use mc_fog_recovery_db_iface::{RecoveryDb, ReportDb};
 use mc_fog_uri::IngestPeerUri;
 use mc_util_grpc::{
@@ -23,21 +23,24 @@ use std::{str::FromStr, sync::Arc};
 
 /// Implements the Ingest Peer grpc api
 #[derive(Clone)]
-pub struct IngestPeerService<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static>
-where
+pub struct IngestPeerService<
+    DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static,
+    E: IngestEnclaveProxy,
+> where
     IngestServiceError: From<<DB as RecoveryDb>::Error>,
 {
-    controller: Arc<IngestController<DB>>,
+    controller: Arc<IngestController<DB, E>>,
     logger: Logger,
 }
 
-impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static> IngestPeerService<DB>
+impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static, E: IngestEnclaveProxy>
+    IngestPeerService<DB, E>
 where
     IngestServiceError: From<<DB as RecoveryDb>::Error>,
 {
     /// Creates a new ingest node (but does not create sockets and start it
     /// etc.)
-    pub fn new(controller: Arc<IngestController<DB>>, logger: Logger) -> Self {
+    pub fn new(controller: Arc<IngestController<DB, E>>, logger: Logger) -> Self {
         Self { controller, logger }
     }
 
@@ -108,8 +111,8 @@ where
     }
 }
 
-impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static>
-    mc_fog_api::ingest_peer_grpc::AccountIngestPeerApi for IngestPeerService<DB>
+impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static, E: IngestEnclaveProxy>
+    mc_fog_api::ingest_peer_grpc::AccountIngestPeerApi for IngestPeerService<DB, E>
 where
     IngestServiceError: From<<DB as RecoveryDb>::Error>,
 {
@@ -18,7 +18,7 @@ use mc_fog_api::{
     Empty,
 };
 use mc_fog_block_provider::BlockProvider;
-use mc_fog_ingest_enclave_api::Error as EnclaveError;
+use mc_fog_ingest_enclave_api::{Error as EnclaveError, IngestEnclaveProxy};
 use mc_fog_recovery_db_iface::{RecoveryDb, ReportDb};
 use mc_fog_uri::IngestPeerUri;
 use mc_util_grpc::{
@@ -30,23 +30,26 @@ use std::{str::FromStr, sync::Arc};
 
 /// Implements the ingest grpc api
 #[derive(Clone)]
-pub struct IngestService<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static>
-where
+pub struct IngestService<
+    DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static,
+    E: IngestEnclaveProxy,
+> where
     Error: From<<DB as RecoveryDb>::Error>,
 {
-    controller: Arc<IngestController<DB>>,
+    controller: Arc<IngestController<DB, E>>,
     block_provider: Box<dyn BlockProvider>,
     logger: Logger,
 }
 
-impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static> IngestService<DB>
+impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static, E: IngestEnclaveProxy>
+    IngestService<DB, E>
 where
     Error: From<<DB as RecoveryDb>::Error>,
 {
     /// Creates a new ingest node (but does not create sockets and start it
     /// etc.)
     pub fn new(
-        controller: Arc<IngestController<DB>>,
+        controller: Arc<IngestController<DB, E>>,
         block_provider: Box<dyn BlockProvider>,
         logger: Logger,
     ) -> Self {
@@ -262,8 +265,8 @@ where
     }
 }
 
-impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static>
-    mc_fog_api::ingest_grpc::AccountIngestApi for IngestService<DB>
+impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static, E: IngestEnclaveProxy>
+    mc_fog_api::ingest_grpc::AccountIngestApi for IngestService<DB, E>
 where
     Error: From<<DB as RecoveryDb>::Error>,
 {
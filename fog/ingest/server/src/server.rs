@@ -22,6 +22,7 @@ use mc_fog_api::{
     ingest_grpc, ingest_peer_grpc,
 };
 use mc_fog_block_provider::BlockProvider;
+use mc_fog_ingest_enclave::{IngestEnclaveProxy, IngestSgxEnclave};
 use mc_fog_recovery_db_iface::{RecoveryDb, ReportDb};
 use mc_fog_uri::{FogIngestUri, IngestPeerUri};
 use mc_util_grpc::ConnectionUriGrpcioServer;
@@ -92,17 +93,24 @@ pub struct IngestServerConfig {
 
     /// Time to wait between ledger polls
     pub poll_interval: Duration,
+
+    /// The maximum number of blocks to fetch from the ledger and process at a
+    /// time. The database write of each block overlaps with enclave work on
+    /// the next one, and the next batch is fetched while a batch is processed.
+    pub block_batch_size: usize,
 }
 
 /// All of the state and grpcio objects and threads associated to the ingest
 /// server
-pub struct IngestServer<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static>
-where
+pub struct IngestServer<
+    DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static,
+    E: IngestEnclaveProxy = IngestSgxEnclave,
+> where
     IngestServiceError: From<<DB as RecoveryDb>::Error>,
 {
     config: IngestServerConfig,
     block_provider: Box<dyn BlockProvider>,
-    controller: Arc<IngestController<DB>>,
+    controller: Arc<IngestController<DB, E>>,
     server: Option<grpcio::Server>,
     peer_server: Option<grpcio::Server>,
     ingest_worker: Option<IngestWorker>,
@@ -115,32 +123,15 @@ impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static> IngestServer<DB>
 where
     IngestServiceError: From<<DB as RecoveryDb>::Error>,
 {
-    /// Create a new ingest server from config object, enclave, and a series of
-    /// db's
+    /// Create a new ingest server from config object, and a series of db's,
+    /// with an SGX enclave loaded from the configured enclave path
     pub fn new(
         config: IngestServerConfig,
         recovery_db: DB,
         block_provider: Box<dyn BlockProvider>,
         logger: Logger,
     ) -> Self {
-        // Validate peer list in config:
-        // - Each peers responder id should be unique
-        // - Our responder id ("local-node-id") should be one of them
-        let peer_responder_ids: BTreeSet<ResponderId> = config
-            .peers
-            .iter()
-            .map(|uri| {
-                uri.responder_id()
-                    .expect("Could not compute responder id for one of our peers")
-            })
-            .collect();
-        if peer_responder_ids.len() != config.peers.len() {
-            panic!("Invalid configuration: Had {} peer uris, but only {} unique responder id's among them. Peers: {}, Responder Ids: {:?}", config.peers.len(), peer_responder_ids.len(), SeqDisplay(config.peers.iter()), peer_responder_ids);
-        }
-
-        if !peer_responder_ids.contains(&config.local_node_id) {
-            panic!("Invaild configuration: Our local node id does not appear as one of the respond ids of one of the uris in the peer list, but that is required.");
-        }
+        Self::validate_peers(&config);
 
         let controller = Arc::new(IngestController::new(
             config.clone(),
@@ -148,6 +139,45 @@ where
             logger.clone(),
         ));
 
+        Self::from_controller(config, controller, block_provider, logger)
+    }
+}
+
+impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static, E: IngestEnclaveProxy>
+    IngestServer<DB, E>
+where
+    IngestServiceError: From<<DB as RecoveryDb>::Error>,
+{
+    /// Create a new ingest server from config object, an enclave which has
+    /// already been initialized, and a series of db's
+    ///
+    /// The state file is not read, since the sealed ingress key in it could
+    /// not be given to the enclave any more. It is still written.
+    pub fn new_with_enclave(
+        config: IngestServerConfig,
+        enclave: E,
+        recovery_db: DB,
+        block_provider: Box<dyn BlockProvider>,
+        logger: Logger,
+    ) -> Self {
+        Self::validate_peers(&config);
+
+        let controller = Arc::new(IngestController::new_with_enclave(
+            config.clone(),
+            enclave,
+            recovery_db,
+            logger.clone(),
+        ));
+
+        Self::from_controller(config, controller, block_provider, logger)
+    }
+
+    fn from_controller(
+        config: IngestServerConfig,
+        controller: Arc<IngestController<DB, E>>,
+        block_provider: Box<dyn BlockProvider>,
+        logger: Logger,
+    ) -> Self {
         Self {
             config,
             block_provider,
@@ -161,6 +191,27 @@ where
         }
     }
 
+    fn validate_peers(config: &IngestServerConfig) {
+        // Validate peer list in config:
+        // - Each peers responder id should be unique
+        // - Our responder id ("local-node-id") should be one of them
+        let peer_responder_ids: BTreeSet<ResponderId> = config
+            .peers
+            .iter()
+            .map(|uri| {
+                uri.responder_id()
+                    .expect("Could not compute responder id for one of our peers")
+            })
+            .collect();
+        if peer_responder_ids.len() != config.peers.len() {
+            panic!("Invalid configuration: Had {} peer uris, but only {} unique responder id's among them. Peers: {}, Responder Ids: {:?}", config.peers.len(), peer_responder_ids.len(), SeqDisplay(config.peers.iter()), peer_responder_ids);
+        }
+
+        if !peer_responder_ids.contains(&config.local_node_id) {
+            panic!("Invaild configuration: Our local node id does not appear as one of the respond ids of one of the uris in the peer list, but that is required.");
+        }
+    }
+
     /// Start all the grpc services and threads in the server
     pub fn start(&mut self) -> Result<(), IngestServiceError> {
         let ret = self.start_helper();
@@ -285,6 +336,7 @@ where
             self.block_provider.clone(),
             self.config.watcher_timeout,
             self.config.poll_interval,
+            self.config.block_batch_size,
             self.logger.clone(),
         ));
 
@@ -381,7 +433,8 @@ where
     }
 }
 
-impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static> Drop for IngestServer<DB>
+impl<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static, E: IngestEnclaveProxy> Drop
+    for IngestServer<DB, E>
 where
     IngestServiceError: From<<DB as RecoveryDb>::Error>,
 {
@@ -1,9 +1,10 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
 use crate::{controller::IngestController, error::IngestServiceError};
-use mc_blockchain_types::BlockIndex;
+use mc_blockchain_types::{Block, BlockContents, BlockData, BlockIndex};
 use mc_common::logger::{log, Logger};
-use mc_fog_block_provider::{BlockDataResponse, BlockProvider, Error as BlockProviderError};
+use mc_fog_block_provider::{BlockProvider, BlocksDataResponse, Error as BlockProviderError};
+use mc_fog_ingest_enclave_api::IngestEnclaveProxy;
 use mc_fog_recovery_db_iface::{RecoveryDb, ReportDb};
 use mc_sgx_report_cache_untrusted::REPORT_REFRESH_INTERVAL;
 use mc_util_telemetry::{
@@ -50,14 +51,16 @@ impl IngestWorker {
     /// * Watcher timeout (how long before we log a warning about failing to get
     ///   a timestamp)
     /// * Polling interval (how long to wait between polls)
+    /// * Block batch size (how many blocks to fetch and process at a time)
     /// * Logger to send log messages to
     ///
     /// Returns a freshly started IngestWorker thread handle
-    pub fn new<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static>(
-        controller: Arc<IngestController<DB>>,
+    pub fn new<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static, E: IngestEnclaveProxy>(
+        controller: Arc<IngestController<DB, E>>,
         block_provider: Box<dyn BlockProvider>,
         watcher_timeout: Duration,
         poll_interval: Duration,
+        block_batch_size: usize,
         logger: Logger,
     ) -> Self
     where
@@ -68,6 +71,9 @@ impl IngestWorker {
             stop_requested: stop_requested.clone(),
             thread: Some(std::thread::spawn(move || {
                 let mut last_not_found_log: Option<LastNotFound> = None;
+                // The batch after the one we last processed, fetched while the
+                // controller was processing it
+                let mut prefetched: Option<BlockBatch> = None;
                 loop {
                     let (next_block_index, is_idle) = controller.get_next_block_index();
 
@@ -82,13 +88,26 @@ impl IngestWorker {
                     }
 
                     if is_idle {
+                        prefetched = None;
                         std::thread::sleep(poll_interval);
                         continue;
                     }
 
                     let start_time = SystemTime::now();
 
-                    match block_provider.get_block_data(next_block_index) {
+                    // The prefetched batch is stale if the controller didn't
+                    // finish the previous batch, e.g. because it became idle
+                    let batch = match prefetched.take() {
+                        Some(batch) if batch.first_block_index == next_block_index => Ok(batch),
+                        _ => BlockBatch::fetch(
+                            &*block_provider,
+                            next_block_index,
+                            block_batch_size,
+                            watcher_timeout,
+                        ),
+                    };
+
+                    match batch {
                         Err(BlockProviderError::NotFound) => {
                             if let Some(rec) = &mut last_not_found_log {
                                 if rec.block_index == next_block_index {
@@ -121,7 +140,7 @@ impl IngestWorker {
                             );
                             std::thread::sleep(Self::ERROR_RETRY_FREQUENCY);
                         }
-                        Ok(BlockDataResponse { result, .. }) => {
+                        Ok(batch) => {
                             last_not_found_log = None;
 
                             // Tracing
@@ -138,24 +157,25 @@ impl IngestWorker {
 
                             let _active = mark_span_as_active(span);
 
-                            // Get the timestamp for the block.
-                            let timestamp = if result.block_timestamp_result_code
-                                == TimestampResultCode::TimestampFound
-                            {
-                                result.block_timestamp
-                            } else {
-                                tracer.in_span("poll_block_timestamp", |_cx| {
-                                    block_provider
-                                        .poll_block_timestamp(next_block_index, watcher_timeout)
-                                })
-                            };
-
-                            tracer.in_span("process_next_block", |_cx| {
-                                controller.process_next_block(
-                                    result.block_data.block(),
-                                    result.block_data.contents(),
-                                    timestamp,
-                                );
+                            // Fetch the next batch while the controller works on
+                            // this one
+                            let next_batch_index = batch.end_block_index();
+                            prefetched = std::thread::scope(|scope| {
+                                let prefetch = scope.spawn(|| {
+                                    BlockBatch::fetch(
+                                        &*block_provider,
+                                        next_batch_index,
+                                        block_batch_size,
+                                        watcher_timeout,
+                                    )
+                                    .ok()
+                                });
+
+                                tracer.in_span("process_next_blocks", |_cx| {
+                                    controller.process_next_blocks(&batch.blocks());
+                                });
+
+                                prefetch.join().expect("Block prefetch thread panicked")
                             });
                         }
                     }
@@ -190,6 +210,72 @@ impl LastNotFound {
     }
 }
 
+/// Consecutive blocks and their timestamps, as fed to the ingest controller
+struct BlockBatch {
+    first_block_index: BlockIndex,
+    blocks: Vec<(BlockData, u64)>,
+}
+
+impl BlockBatch {
+    /// Get the data and timestamps of up to `max_blocks` consecutive blocks,
+    /// starting at `first_block_index`. Fails with NotFound if the first block
+    /// is not in the ledger yet.
+    ///
+    /// Only the timestamp of the first block is polled for. The batch stops
+    /// before any later block whose timestamp is not available yet, so that
+    /// the timestamps of a batch are not waited for one after another.
+    fn fetch(
+        block_provider: &dyn BlockProvider,
+        first_block_index: BlockIndex,
+        max_blocks: usize,
+        watcher_timeout: Duration,
+    ) -> Result<Self, BlockProviderError> {
+        let block_indices = (first_block_index..)
+            .take(max_blocks.max(1))
+            .collect::<Vec<_>>();
+        let BlocksDataResponse { results, .. } = block_provider.get_blocks_data(&block_indices)?;
+
+        let blocks = results
+            .into_iter()
+            .zip(block_indices)
+            .map_while(|(result, block_index)| {
+                let result = result?;
+                // Get the timestamp for the block.
+                let timestamp =
+                    if result.block_timestamp_result_code == TimestampResultCode::TimestampFound {
+                        result.block_timestamp
+                    } else if block_index == first_block_index {
+                        block_provider.poll_block_timestamp(block_index, watcher_timeout)
+                    } else {
+                        return None;
+                    };
+                Some((result.block_data, timestamp))
+            })
+            .collect::<Vec<_>>();
+
+        if blocks.is_empty() {
+            return Err(BlockProviderError::NotFound);
+        }
+        Ok(Self {
+            first_block_index,
+            blocks,
+        })
+    }
+
+    /// The index of the block after this batch
+    fn end_block_index(&self) -> BlockIndex {
+        self.first_block_index + self.blocks.len() as u64
+    }
+
+    /// The blocks, as taken by IngestController::process_next_blocks
+    fn blocks(&self) -> Vec<(&Block, &BlockContents, u64)> {
+        self.blocks
+            .iter()
+            .map(|(block_data, timestamp)| (block_data.block(), block_data.contents(), *timestamp))
+            .collect()
+    }
+}
+
 /// The peer checkup worker is a thread responsible for periodically checking up
 /// on our peers, if we are active, and making sure they are functioning as
 /// backups. This is a separate thread so that it can be on a time-based
@@ -208,8 +294,8 @@ impl PeerCheckupWorker {
     /// * Logger to send log messages to
     ///
     /// Returns a freshly started PeerCheckupWorker thread handle
-    pub fn new<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static>(
-        controller: Arc<IngestController<DB>>,
+    pub fn new<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static, E: IngestEnclaveProxy>(
+        controller: Arc<IngestController<DB, E>>,
         peer_checkup_period: Duration,
         logger: Logger,
     ) -> Self
@@ -269,8 +355,8 @@ impl ReportCacheWorker {
     /// * Logger to send log messages to
     ///
     /// Returns a freshly started ReportCacheWorker thread handle
-    pub fn new<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static>(
-        controller: Arc<IngestController<DB>>,
+    pub fn new<DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static, E: IngestEnclaveProxy>(
+        controller: Arc<IngestController<DB, E>>,
         logger: Logger,
     ) -> Self
     where
@@ -0,0 +1,98 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Measures fog ingest throughput for a backlog of blocks.
+//!
+//! Needs a postgres server, like the ingest server tests. The size of the
+//! backlog and the ingest block batch size are read from the environment:
+//! NUM_BLOCKS (default 100), TXS_PER_BLOCK (default 500) and
+//! BLOCK_BATCH_SIZE (default 10).
+
+use mc_blockchain_test_utils::get_blocks;
+use mc_common::logger::create_null_logger;
+use mc_fog_ingest_server_test_utils::IngestServerTestHelper;
+use mc_ledger_db::Ledger;
+use mc_transaction_core::BlockVersion;
+use std::{
+    env,
+    str::FromStr,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use url::Url;
+
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .map(|value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid value for {name}: {value}"))
+        })
+        .unwrap_or(default)
+}
+
+fn main() {
+    let num_blocks = env_or("NUM_BLOCKS", 100usize);
+    let txs_per_block = env_or("TXS_PER_BLOCK", 500usize);
+    let block_batch_size = env_or("BLOCK_BATCH_SIZE", 10usize);
+
+    let mut helper = IngestServerTestHelper::new(3900, create_null_logger());
+    helper.block_batch_size = block_batch_size;
+    helper.add_origin_block();
+
+    // Build the whole backlog before the server is activated, so that only
+    // ingest is measured.
+    let prev_block = helper
+        .ledger
+        .get_latest_block()
+        .expect("Could not get last block");
+    let blocks = get_blocks(
+        BlockVersion::MAX,
+        num_blocks,
+        txs_per_block,
+        1,
+        1,
+        42,
+        prev_block,
+        &mut helper.rng,
+    );
+    let tx_source_url = Url::from_str("https://localhost").unwrap();
+    for block_data in &blocks {
+        helper
+            .ledger
+            .append_block_data(block_data)
+            .expect("Could not append block");
+        helper
+            .watcher
+            .add_block_data(&tx_source_url, block_data)
+            .expect("Could not add block data to watcher");
+        helper
+            .watcher
+            .add_block_signature(
+                &tx_source_url,
+                block_data.block().index,
+                block_data.signature().cloned().unwrap(),
+                "archive".to_string(),
+            )
+            .expect("Could not add block signature to watcher");
+    }
+    let total_blocks = helper.ledger.num_blocks().expect("ledger.num_blocks");
+
+    let node = helper.make_node(0, 0..1);
+    let started_at = Instant::now();
+    node.activate().expect("Could not activate ingest server");
+    while node.get_ingest_summary().next_block_index < total_blocks {
+        sleep(Duration::from_millis(10));
+    }
+    let duration = started_at.elapsed();
+
+    let blocks_per_sec = num_blocks as f64 / duration.as_secs_f64();
+    println!(
+        "{} blocks of {} txs with batch size {} in {} millis, {:.1} blocks/s, {:.0} txs/s",
+        num_blocks,
+        txs_per_block,
+        block_batch_size,
+        duration.as_millis(),
+        blocks_per_sec,
+        blocks_per_sec * txs_per_block as f64
+    );
+}
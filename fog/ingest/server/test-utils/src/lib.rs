@@ -102,6 +102,7 @@ pub struct IngestServerTestHelper {
     pub db_test_context: Arc<SqlRecoveryDbTestContext>,
     pub recovery_db: SqlRecoveryDb,
     pub rng: Hc128Rng,
+    pub block_batch_size: usize,
     pub logger: Logger,
 }
 
@@ -159,6 +160,7 @@ impl IngestServerTestHelper {
             db_test_context,
             recovery_db,
             rng,
+            block_batch_size: 10,
             logger,
         }
     }
@@ -201,6 +203,37 @@ impl IngestServerTestHelper {
         idx: u8,
         peer_idxs: impl Iterator<Item = u8>,
         state_file_path: PathBuf,
+    ) -> TestIngestNode {
+        let mut node = self.make_unstarted_node_with_state(idx, peer_idxs, state_file_path);
+        node.start().expect("Failed to start IngestServer");
+        assert!(
+            !node.is_active(),
+            "Newly created IngestServer should never be active"
+        );
+        node
+    }
+
+    /// Helper which makes i'th server without starting it, so that a test can
+    /// activate it and prepare the ledger before it ingests any block.
+    pub fn make_unstarted_node(
+        &self,
+        idx: u8,
+        peer_idxs: impl Iterator<Item = u8>,
+    ) -> TestIngestNode {
+        let state_file_path = TempDir::new()
+            .expect("Could not make tempdir for ingest state")
+            .into_path()
+            .join(format!("mc-fog-ingest-state-{idx}"));
+        self.make_unstarted_node_with_state(idx, peer_idxs, state_file_path)
+    }
+
+    /// Set up a test server reusing a [StateFile] at the given path, without
+    /// starting it.
+    pub fn make_unstarted_node_with_state(
+        &self,
+        idx: u8,
+        peer_idxs: impl Iterator<Item = u8>,
+        state_file_path: PathBuf,
     ) -> TestIngestNode {
         let logger = self.logger.new(o!("mc.node_id" => idx.to_string()));
 
@@ -227,19 +260,15 @@ impl IngestServerTestHelper {
             enclave_path: get_enclave_path(mc_fog_ingest_enclave::ENCLAVE_FILE),
             omap_capacity: OMAP_CAPACITY,
             poll_interval: Duration::from_millis(250),
+            block_batch_size: self.block_batch_size,
         };
 
-        let mut server = IngestServer::new(
+        let server = IngestServer::new(
             config,
             self.recovery_db.clone(),
             LocalBlockProvider::new(self.ledger.clone(), self.watcher.clone()),
             logger,
         );
-        server.start().expect("Failed to start IngestServer");
-        assert!(
-            !server.is_active(),
-            "Newly created IngestServer should never be active"
-        );
 
         TestIngestNode {
             server,
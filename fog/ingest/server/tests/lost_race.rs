@@ -0,0 +1,136 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+// This test shows that when an ingest server loses the race to publish a
+// block, in the middle of a pipelined batch of blocks, it discards what the
+// enclave already ingested for the next block, and never publishes it.
+
+use mc_common::logger::{
+    slog::{self, Drain, Never, OwnedKVList, Record},
+    test_with_logger, Logger,
+};
+use mc_fog_ingest_server_test_utils::IngestServerTestHelper;
+use mc_fog_recovery_db_iface::RecoveryDb;
+use mc_fog_types::view::KexRngPubkey;
+use mc_ledger_db::Ledger;
+use std::{
+    sync::{Arc, Mutex},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const BASE_PORT: u16 = 3667;
+
+/// A drain which remembers the messages logged by the ingest server.
+#[derive(Clone, Default)]
+struct CapturedMessages(Arc<Mutex<Vec<String>>>);
+
+impl CapturedMessages {
+    fn contains(&self, msg: &str) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|captured| captured == msg)
+    }
+}
+
+impl Drain for CapturedMessages {
+    type Ok = ();
+    type Err = Never;
+
+    fn log(&self, record: &Record, _values: &OwnedKVList) -> Result<(), Never> {
+        self.0.lock().unwrap().push(record.msg().to_string());
+        Ok(())
+    }
+}
+
+#[test_with_logger]
+fn test_lost_race_discards_next_block(logger: Logger) {
+    let captured = CapturedMessages::default();
+    let logger = Logger::root(
+        slog::Duplicate::new(logger, captured.clone()).ignore_res(),
+        slog::o!(),
+    );
+
+    let mut helper = IngestServerTestHelper::new(BASE_PORT, logger);
+    helper.add_origin_block();
+
+    // Activate the server before starting it, so that it can't ingest
+    // anything before both blocks of the batch are in the ledger.
+    let mut node = helper.make_unstarted_node(0, 0..1);
+    let summary = node.activate().expect("Could not activate ingest");
+    let ingress_key = node.get_ingress_key();
+    let initial_egress_key = summary.get_egress_pubkey().to_vec();
+    let block_index = summary.next_block_index;
+    assert_eq!(block_index, helper.ledger.num_blocks().unwrap());
+
+    helper.add_test_blocks(2);
+
+    // Another server with the same ingress key publishes the first block of
+    // the batch.
+    let block = helper.ledger.get_block(block_index).unwrap();
+    let other_egress_key = KexRngPubkey {
+        public_key: [7u8; 32].to_vec(),
+        version: 1,
+    };
+    let other_iid = helper
+        .recovery_db
+        .new_ingest_invocation(None, &ingress_key, &other_egress_key, block_index)
+        .unwrap();
+    helper
+        .recovery_db
+        .add_block_data(&other_iid, &block, 0, &[])
+        .unwrap();
+
+    node.start().expect("Failed to start IngestServer");
+
+    let discarding = format!(
+        "Discarding the enclave output for block {}, since the previous block was not published",
+        block_index + 1
+    );
+    let timeout = Duration::from_secs(60);
+    let start = Instant::now();
+    while !captured.contains(&discarding) {
+        assert!(
+            start.elapsed() <= timeout,
+            "Timed out waiting for the server to lose the race"
+        );
+        sleep(Duration::from_millis(100));
+    }
+
+    // Stopping joins the ingest worker, so the egress key is rotated by now.
+    node.stop();
+
+    let summary = node.get_ingest_summary();
+    assert!(node.is_idle());
+    assert_eq!(summary.next_block_index, block_index);
+    assert_ne!(summary.get_egress_pubkey(), &initial_egress_key[..]);
+
+    // Only the other server's data made it to the database.
+    assert_eq!(
+        helper
+            .recovery_db
+            .get_tx_outs_by_block_and_key(ingress_key, block_index)
+            .unwrap(),
+        Some(vec![])
+    );
+    assert_eq!(
+        helper
+            .recovery_db
+            .get_tx_outs_by_block_and_key(ingress_key, block_index + 1)
+            .unwrap(),
+        None
+    );
+
+    // Our ingest invocation was decommissioned without ingesting anything.
+    let ranges = helper.recovery_db.get_ingestable_ranges().unwrap();
+    assert_eq!(ranges.len(), 2);
+    for range in ranges {
+        if range.id == other_iid {
+            assert_eq!(range.last_ingested_block, Some(block_index));
+        } else {
+            assert!(range.decommissioned);
+            assert_eq!(range.last_ingested_block, None);
+        }
+    }
+}
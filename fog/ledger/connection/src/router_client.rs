@@ -21,7 +21,9 @@ use mc_fog_api::{
     ledger::{LedgerRequest, LedgerResponse},
     ledger_grpc::LedgerApiClient,
 };
-use mc_fog_types::ledger::{CheckKeyImagesRequest, CheckKeyImagesResponse, KeyImageQuery};
+use mc_fog_types::ledger::{
+    CheckKeyImagesRequest, CheckKeyImagesRequestAAD, CheckKeyImagesResponse, KeyImageQuery,
+};
 use mc_fog_uri::FogLedgerUri;
 use mc_rand::McRng;
 use mc_transaction_core::ring_signature::KeyImage;
@@ -146,6 +148,22 @@ impl LedgerGrpcClient {
     pub async fn check_key_images(
         &mut self,
         key_images: &[KeyImage],
+    ) -> Result<CheckKeyImagesResponse, Error> {
+        self.check_key_images_since(key_images, 0).await
+    }
+
+    /// Check one or more key images against the ledger router service, only
+    /// looking for spends from `start_block` on.
+    ///
+    /// A key image spent before `start_block` may be reported as not spent.
+    /// Key image stores can answer requests about recent blocks only much
+    /// faster, so this is useful for e.g. checking on recently submitted
+    /// transactions. The start block is sent unencrypted, so the servers
+    /// learn it.
+    pub async fn check_key_images_since(
+        &mut self,
+        key_images: &[KeyImage],
+        start_block: u64,
     ) -> Result<CheckKeyImagesResponse, Error> {
         trace_time!(self.logger, "LedgerGrpcClient::check_key_images");
 
@@ -158,15 +176,16 @@ impl LedgerGrpcClient {
             .iter()
             .map(|&key_image| KeyImageQuery {
                 key_image,
-                start_block: 0,
+                start_block,
             })
             .collect();
         let key_images_request = CheckKeyImagesRequest {
             queries: key_images_queries,
         };
 
-        // No authenticated data associated with ledger query
-        let aad = vec![];
+        let aad = mc_util_serial::encode(&CheckKeyImagesRequestAAD {
+            start_from_block_index: start_block,
+        });
 
         let msg = {
             let attest_cipher = self
@@ -8,10 +8,10 @@ use aligned_cmov::{
     typenum::{U1024, U16, U32, U4096, U64},
     A8Bytes, CMov,
 };
-use alloc::boxed::Box;
+use alloc::{boxed::Box, vec, vec::Vec};
 use mc_common::logger::{log, Logger};
 use mc_fog_ledger_enclave_api::AddRecordsError;
-use mc_fog_types::ledger::{KeyImageResult, KeyImageResultCode};
+use mc_fog_types::ledger::{KeyImageQuery, KeyImageResult, KeyImageResultCode};
 use mc_oblivious_map::CuckooHashTableCreator;
 use mc_oblivious_ram::PathORAM4096Z4Creator;
 use mc_oblivious_traits::{
@@ -47,6 +47,10 @@ const STASH_SIZE: usize = 32;
 /// This selects the oblivious map algorithm
 type ObliviousMapCreator<OSC> = CuckooHashTableCreator<BlockSize, McRng, ObliviousRAMAlgo<OSC>>;
 
+/// The number of most recently added key images which are also kept outside
+/// of the oblivious map, see [RecentKeyImages]
+pub const RECENT_KEY_IMAGES_CAPACITY: usize = 1024;
+
 /// Object which holds ORAM and services KeyImageRecord requests
 ///
 /// This object handles translations between protobuf types, and the aligned
@@ -63,6 +67,9 @@ pub struct KeyImageStore<OSC: ORAMStorageCreator<StorageDataSize, StorageMetaSiz
     /// Oblivious map to hold KeyImageStoreRecords
     omap: Box<<ObliviousMapCreator<OSC> as OMapCreator<KeySize, ValueSize, McRng>>::Output>,
 
+    /// The most recently added key image records
+    recent: RecentKeyImages,
+
     /// The logger object
     logger: Logger,
 }
@@ -77,6 +84,7 @@ impl<OSC: ORAMStorageCreator<StorageDataSize, StorageMetaSize>> KeyImageStore<OS
             >>::create(
                 desired_capacity, STASH_SIZE, McRng::default
             )),
+            recent: RecentKeyImages::new(),
             logger,
         }
     }
@@ -89,15 +97,8 @@ impl<OSC: ORAMStorageCreator<StorageDataSize, StorageMetaSize>> KeyImageStore<OS
         timestamp: u64,
     ) -> Result<(), AddRecordsError> {
         let mut value = A8Bytes::<ValueSize>::default();
-        let mut key = A8Bytes::<KeySize>::default(); // key used to add to the oram for key image
-        key.clone_from_slice(key_image.as_ref());
-        // Flip the first byte of key image, when used as a key in the oblivious
-        // map. This is because we will use key image as a key in the map,
-        // but the map does not support all zeroes as a key. All zeroes is a
-        // valid curve point. But if we flip the first byte, it turns out that
-        // isn't a valid curve point, in the Ristretto group.
-        // So this prevents the OMAP_INVALID_KEY error path.
-        key[0] = !key[0];
+        // key used to add to the oram for key image
+        let key = omap_key(key_image);
         // write block index data to  value[0..8] write the time stamp data to
         // value[8..16]
         value[0..8].clone_from_slice(&block_index.to_le_bytes());
@@ -120,9 +121,40 @@ impl<OSC: ORAMStorageCreator<StorageDataSize, StorageMetaSize>> KeyImageStore<OS
                 omap_result_code
             );
         }
+        self.recent.push(&key, block_index, timestamp);
         Ok(())
     }
 
+    /// Answer the key image queries of a request, which only asks about key
+    /// images spent from `start_from_block_index` on.
+    ///
+    /// If the recent key images hold every key image spent from that block
+    /// on, they answer all of the queries, otherwise the oblivious map does.
+    /// Either way a key image spent before `start_from_block_index` may be
+    /// reported as not spent.
+    ///
+    /// Which of the two is used depends only on `start_from_block_index`,
+    /// which comes from the request's AAD, and on the blocks added so far, so
+    /// the untrusted side learns nothing about the key images from it. The
+    /// answer to a query doesn't depend on the other queries.
+    pub fn find_records(
+        &mut self,
+        queries: &[KeyImageQuery],
+        start_from_block_index: u64,
+    ) -> Vec<KeyImageResult> {
+        if start_from_block_index >= self.recent.start_block {
+            queries
+                .iter()
+                .map(|query| self.recent.find_record(&query.key_image))
+                .collect()
+        } else {
+            queries
+                .iter()
+                .map(|query| self.find_record(&query.key_image))
+                .collect()
+        }
+    }
+
     /// return new struct KeyImageResult which contains block index and
     /// timestamp of key image as ref to convert key image to 32 bits,
     /// call the oram to query to to key image data
@@ -135,15 +167,8 @@ impl<OSC: ORAMStorageCreator<StorageDataSize, StorageMetaSize>> KeyImageStore<OS
             timestamp_result_code: TimestampResultCode::TimestampFound as u32,
         };
 
-        let mut key = A8Bytes::<KeySize>::default(); // key used to query the oram for key image
-        key.clone_from_slice(key_image.as_ref());
-        // Flip the first byte of key image, when used as a key in the oblivious
-        // map. This is because we will use key image as a key in the map,
-        // but the map does not support all zeroes as a key. All zeroes is a
-        // valid curve point. But if we flip the first byte, it turns out that
-        // isn't a valid curve point, in the Ristretto group.
-        // So this prevents the OMAP_INVALID_KEY error path.
-        key[0] = !key[0];
+        // key used to query the oram for key image
+        let key = omap_key(key_image);
 
         // value used to save the reuslt of querying
         //the oram for key image value using key
@@ -194,3 +219,112 @@ impl<OSC: ORAMStorageCreator<StorageDataSize, StorageMetaSize>> KeyImageStore<OS
         result
     }
 }
+
+/// The key used for a key image in the oblivious map
+fn omap_key(key_image: &KeyImage) -> A8Bytes<KeySize> {
+    let mut key = A8Bytes::<KeySize>::default();
+    key.clone_from_slice(key_image.as_ref());
+    // Flip the first byte of key image, when used as a key in the oblivious
+    // map. This is because we will use key image as a key in the map,
+    // but the map does not support all zeroes as a key. All zeroes is a
+    // valid curve point. But if we flip the first byte, it turns out that
+    // isn't a valid curve point, in the Ristretto group.
+    // So this prevents the OMAP_INVALID_KEY error path.
+    key[0] = !key[0];
+    key
+}
+
+/// A slot of [RecentKeyImages]
+#[derive(Clone, Default)]
+struct RecentKeyImage {
+    /// The oblivious map key of the key image, all zeroes if the slot is empty
+    key: A8Bytes<KeySize>,
+    /// The block index the key image was spent at
+    block_index: u64,
+    /// The timestamp of that block
+    timestamp: u64,
+}
+
+/// The most recently added key image records, in a ring of
+/// [RECENT_KEY_IMAGES_CAPACITY] slots.
+///
+/// Every record with a block index of at least `start_block` is in the ring:
+/// evicting a record moves `start_block` past its block. Requests which only
+/// ask about blocks from `start_block` on can thus be answered exactly from
+/// the ring, which is much cheaper to scan than reading from the oblivious
+/// map.
+///
+/// Both adding and finding a record visit every slot in the same order, and
+/// only change slots and results with conditional moves, so neither the
+/// memory accesses nor the time taken depend on the key images.
+struct RecentKeyImages {
+    /// The slots, overwritten oldest first
+    slots: Vec<RecentKeyImage>,
+    /// The number of records added so far
+    num_added: u64,
+    /// The first block index for which all records are in the ring
+    start_block: u64,
+}
+
+impl RecentKeyImages {
+    fn new() -> Self {
+        Self {
+            slots: vec![RecentKeyImage::default(); RECENT_KEY_IMAGES_CAPACITY],
+            num_added: 0,
+            start_block: 0,
+        }
+    }
+
+    /// Add a record, evicting the oldest record if the ring is full
+    fn push(&mut self, key: &A8Bytes<KeySize>, block_index: u64, timestamp: u64) {
+        let next = self.num_added % RECENT_KEY_IMAGES_CAPACITY as u64;
+
+        let mut evicted_block_index = 0;
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            let is_next = (index as u64).ct_eq(&next);
+            evicted_block_index.cmov(is_next, &slot.block_index);
+            slot.key.cmov(is_next, key);
+            slot.block_index.cmov(is_next, &block_index);
+            slot.timestamp.cmov(is_next, &timestamp);
+        }
+
+        // Whether a record was evicted only depends on the number of records
+        if self.num_added >= RECENT_KEY_IMAGES_CAPACITY as u64 {
+            self.start_block = core::cmp::max(self.start_block, evicted_block_index + 1);
+        }
+        self.num_added += 1;
+    }
+
+    /// Look up a key image, with the same result as
+    /// [KeyImageStore::find_record] if it was spent from `start_block` on.
+    fn find_record(&self, key_image: &KeyImage) -> KeyImageResult {
+        let key = omap_key(key_image);
+        let mut result = KeyImageResult {
+            key_image: *key_image,
+            spent_at: u64::MAX,
+            key_image_result_code: KeyImageResultCode::NotSpent as u32,
+            timestamp: u64::MAX,
+            timestamp_result_code: TimestampResultCode::TimestampFound as u32,
+        };
+
+        for slot in self.slots.iter() {
+            let found = slot.key[..].ct_eq(&key[..]);
+            result.spent_at.cmov(found, &slot.block_index);
+            result.timestamp.cmov(found, &slot.timestamp);
+            result
+                .key_image_result_code
+                .cmov(found, &(KeyImageResultCode::Spent as u32));
+        }
+
+        // The oblivious map rejects an all zeroes key, which also marks the
+        // empty slots here
+        let invalid = key[..].ct_eq(&[0u8; 32][..]);
+        result.spent_at.cmov(invalid, &u64::MAX);
+        result.timestamp.cmov(invalid, &u64::MAX);
+        result
+            .key_image_result_code
+            .cmov(invalid, &(KeyImageResultCode::KeyImageError as u32));
+
+        result
+    }
+}
@@ -35,7 +35,8 @@ use mc_fog_ledger_enclave_api::{
 use mc_fog_types::{
     common::BlockRange,
     ledger::{
-        CheckKeyImagesRequest, CheckKeyImagesResponse, GetOutputsRequest, GetOutputsResponse,
+        CheckKeyImagesRequest, CheckKeyImagesRequestAAD, CheckKeyImagesResponse, GetOutputsRequest,
+        GetOutputsResponse,
     },
 };
 use mc_oblivious_traits::ORAMStorageCreator;
@@ -164,12 +165,17 @@ where
         untrusted_key_image_query_response: UntrustedKeyImageQueryResponse,
     ) -> Result<Vec<u8>> {
         let channel_id = msg.channel_id.clone(); //client session does not implement copy trait so clone
+        let aad = msg.aad.clone();
         let user_plaintext = self.ake.client_decrypt(msg)?;
 
         let req: CheckKeyImagesRequest = mc_util_serial::decode(&user_plaintext).map_err(|e| {
             log::error!(self.logger, "Could not decode user request: {}", e);
             Error::ProstDecode
         })?;
+        let req_aad: CheckKeyImagesRequestAAD = mc_util_serial::decode(&aad).map_err(|e| {
+            log::error!(self.logger, "Could not decode user request aad: {}", e);
+            Error::ProstDecode
+        })?;
 
         let mut resp = CheckKeyImagesResponse {
             // `num_blocks` is a count, `end_block` is an exclusive index.
@@ -189,11 +195,7 @@ where
             let mut lk = self.key_image_store.lock()?;
             let store = lk.as_mut().ok_or(Error::EnclaveNotInitialized)?;
 
-            resp.results = req
-                .queries
-                .iter() //  get the key images used to find the key image data using the oram
-                .map(|key| store.find_record(&key.key_image))
-                .collect();
+            resp.results = store.find_records(&req.queries, req_aad.start_from_block_index);
         }
 
         let response_plaintext_bytes = mc_util_serial::encode(&resp);
@@ -315,12 +317,17 @@ where
         untrusted_key_image_query_response: UntrustedKeyImageQueryResponse,
     ) -> Result<EnclaveMessage<NonceSession>> {
         let channel_id = msg.channel_id.clone();
+        let aad = msg.aad.clone();
         let user_plaintext = self.ake.frontend_decrypt(msg)?;
 
         let req: CheckKeyImagesRequest = mc_util_serial::decode(&user_plaintext).map_err(|e| {
             log::error!(self.logger, "Could not decode user request: {}", e);
             Error::ProstDecode
         })?;
+        let req_aad: CheckKeyImagesRequestAAD = mc_util_serial::decode(&aad).map_err(|e| {
+            log::error!(self.logger, "Could not decode user request aad: {}", e);
+            Error::ProstDecode
+        })?;
 
         let mut resp = ShardKeyImageResponse {
             untrusted_response: untrusted_key_image_query_response,
@@ -331,11 +338,7 @@ where
             let mut lk = self.key_image_store.lock()?;
             let store = lk.as_mut().ok_or(Error::EnclaveNotInitialized)?;
 
-            resp.results = req
-                .queries
-                .iter() //  get the key images used to find the key image data using the oram
-                .map(|key| store.find_record(&key.key_image))
-                .collect();
+            resp.results = store.find_records(&req.queries, req_aad.start_from_block_index);
         }
 
         // Encrypt for return to router
@@ -401,7 +404,9 @@ fn merge_untrusted_responses<'a>(
 mod tests {
     use super::*;
     use alloc::vec;
+    use key_image_store::RECENT_KEY_IMAGES_CAPACITY;
     use mc_common::logger::create_root_logger;
+    use mc_fog_types::ledger::{KeyImageQuery, KeyImageResultCode};
     use mc_oblivious_traits::HeapORAMStorageCreator;
     use mc_transaction_core::ring_signature::KeyImage;
     use yare::parameterized;
@@ -504,6 +509,90 @@ mod tests {
         );
     }
 
+    // Test that requests about recent blocks get the same answers from the
+    // recent key images as from the oram
+    #[test]
+    fn test_find_records_since() {
+        let logger = create_root_logger();
+        let mut key_image_store = KeyImageStore::<HeapORAMStorageCreator>::new(1024 * 1024, logger);
+        let queries = |key_images: &[KeyImage]| {
+            key_images
+                .iter()
+                .map(|&key_image| KeyImageQuery {
+                    key_image,
+                    start_block: 0,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Four key images per block, with twice as many key images as are
+        // kept as recent ones, so the first half of the blocks are evicted
+        let num_key_images = 2 * RECENT_KEY_IMAGES_CAPACITY as u64;
+        let num_blocks = num_key_images / 4;
+        for i in 1..=num_key_images {
+            key_image_store
+                .add_record(&KeyImage::from(i), i / 4, 1000 + i / 4)
+                .unwrap();
+        }
+
+        let oldest = KeyImage::from(1);
+        let newest = KeyImage::from(num_key_images);
+        let unknown = KeyImage::from(num_key_images + 1);
+        // The oblivious map key of this is all zeroes
+        let mut invalid_bytes = [0u8; 32];
+        invalid_bytes[0] = 0xff;
+        let invalid = KeyImage::try_from(invalid_bytes).unwrap();
+
+        // The first block whose key images were all kept
+        let first_recent_block = num_blocks / 2 + 1;
+        for start_from_block_index in [first_recent_block, num_blocks, num_blocks + 1] {
+            let recent = KeyImage::from(4 * start_from_block_index);
+            let key_images = [recent, newest, unknown, invalid];
+            let results =
+                key_image_store.find_records(&queries(&key_images), start_from_block_index);
+            for (key_image, result) in key_images.iter().zip(results.iter()) {
+                assert_eq!(*result, key_image_store.find_record(key_image));
+            }
+            assert_eq!(
+                results[1].key_image_result_code,
+                KeyImageResultCode::Spent as u32
+            );
+            assert_eq!(results[1].spent_at, num_blocks);
+            assert_eq!(results[1].timestamp, 1000 + num_blocks);
+            assert_eq!(
+                results[2].key_image_result_code,
+                KeyImageResultCode::NotSpent as u32
+            );
+            assert_eq!(
+                results[3].key_image_result_code,
+                KeyImageResultCode::KeyImageError as u32
+            );
+        }
+
+        // The oldest key image was spent before the recent blocks, so it is
+        // reported as not spent when only the recent blocks are asked about,
+        // whatever else is in the request
+        for key_images in [&[oldest][..], &[oldest, newest], &[newest, oldest, unknown]] {
+            let results = key_image_store.find_records(&queries(key_images), first_recent_block);
+            let result = results.iter().find(|r| r.key_image == oldest).unwrap();
+            assert_eq!(
+                result.key_image_result_code,
+                KeyImageResultCode::NotSpent as u32
+            );
+        }
+
+        // When older blocks are asked about, the oram answers
+        let results =
+            key_image_store.find_records(&queries(&[oldest, newest]), first_recent_block - 1);
+        assert_eq!(results[0], key_image_store.find_record(&oldest));
+        assert_eq!(
+            results[0].key_image_result_code,
+            KeyImageResultCode::Spent as u32
+        );
+        assert_eq!(results[0].spent_at, 0);
+        assert_eq!(results[1], key_image_store.find_record(&newest));
+    }
+
     type UntrustedResponseTuple = ((u64, u64), u64, u32, u32);
     #[parameterized(
         all_consecutive = { vec![((0, 5), 15, 0, 0), ((5, 8), 24, 1, 2)], ((0, 8), 24, 1, 2) },
@@ -0,0 +1,88 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+use displaydoc::Display;
+use lmdb::Error as LmdbError;
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_recovery_db_iface::{IngestInvocationId, RecoveryDbError};
+use mc_fog_types::common::BlockRange;
+use mc_util_lmdb::MetadataStoreError;
+use prost::DecodeError;
+
+/// Enum for error types.
+#[derive(Display, Debug)]
+pub enum Error {
+    /// LMDB: {0}
+    Lmdb(LmdbError),
+
+    /// Metadata store: {0}
+    MetadataStore(MetadataStoreError),
+
+    /// IO: {0}
+    Io(std::io::Error),
+
+    /// Invalid database url, expected {0}://<path>: {1}
+    InvalidUrl(&'static str, String),
+
+    /// The following ingress key was not found: {0:?}
+    MissingIngressKey(CompressedRistrettoPublic),
+
+    /// Invalid ingress key bytes: {0:?}
+    InvalidIngressKey(Vec<u8>),
+
+    /// The following ingress key already exists: {0:?}
+    IngressKeyExists(CompressedRistrettoPublic),
+
+    /// The following ingest invocation was not found: {0}
+    MissingIngestInvocation(IngestInvocationId),
+
+    /// The following ingest invocation is already decommissioned: {0}
+    AlreadyDecommissioned(IngestInvocationId),
+
+    /// The following missed blocks range was already reported: {0:?}
+    DuplicateMissedBlocksRange(BlockRange),
+
+    /// IngestedBlock schema violation: {0}
+    IngestedBlockSchemaViolation(String),
+
+    /// UserEvent schema violation on row #{0}: {1}
+    UserEventSchemaViolation(i64, &'static str),
+
+    /// Invalid timestamp: {0}
+    InvalidTimestamp(i64),
+
+    /// Decode: {0}
+    Decode(DecodeError),
+}
+
+impl RecoveryDbError for Error {
+    /// Policy decision, whether the call should be retried.
+    ///
+    /// LMDB is local, so there are no transient connection errors to retry.
+    fn should_retry(&self) -> bool {
+        false
+    }
+}
+
+impl From<LmdbError> for Error {
+    fn from(src: LmdbError) -> Self {
+        Self::Lmdb(src)
+    }
+}
+
+impl From<MetadataStoreError> for Error {
+    fn from(src: MetadataStoreError) -> Self {
+        Self::MetadataStore(src)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(src: std::io::Error) -> Self {
+        Self::Io(src)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(src: DecodeError) -> Self {
+        Self::Decode(src)
+    }
+}
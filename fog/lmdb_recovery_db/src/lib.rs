@@ -0,0 +1,1141 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+#![deny(missing_docs)]
+
+//! Recovery db implementation using an embedded LMDB database.
+//!
+//! This backend keeps all of its state in a local directory, which makes it
+//! convenient for local development and CI, where running a PostgreSQL server
+//! is a burden. It is not meant to be shared between hosts, so production
+//! deployments with several ingest, view and report servers should keep using
+//! the SQL backend.
+
+mod error;
+mod records;
+
+pub use error::Error;
+
+use crate::records::{
+    IngestInvocationRecord, IngestedBlockRecord, IngressKeyRecord, ReportRecord, UserEvent,
+    UserEventRecord,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use lmdb::{Database, DatabaseFlags, Environment, RwTransaction, Transaction, WriteFlags};
+use mc_attest_verifier_types::EvidenceKind;
+use mc_blockchain_types::Block;
+use mc_common::{
+    logger::{log, Logger},
+    HashMap,
+};
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_kex_rng::KexRngPubkey;
+use mc_fog_recovery_db_iface::{
+    AddBlockDataStatus, ExpiredInvocationRecord, FogUserEvent, IngestInvocationId, IngestableRange,
+    IngressPublicKeyRecord, IngressPublicKeyRecordFilters, IngressPublicKeyStatus, RecoveryDb,
+    ReportData, ReportDb,
+};
+use mc_fog_types::{
+    common::BlockRange,
+    view::{
+        DecommissionedIngestInvocation, FixedTxOutSearchResult, RngRecord, TxOutSearchResultCode,
+    },
+    ETxOutRecord,
+};
+use mc_util_lmdb::{MetadataStore, MetadataStoreSettings};
+use prost::Message;
+use std::{cmp::max, path::Path, sync::Arc};
+
+/// The database url scheme which selects this backend, e.g.
+/// `lmdb:///var/lib/fog/recovery-db`.
+pub const LMDB_URL_SCHEME: &str = "lmdb";
+
+// LMDB Constants
+const MAX_LMDB_FILE_SIZE: usize = 1_099_511_627_776; // 1 TB
+
+// LMDB database names.
+const INGRESS_KEYS_DB_NAME: &str = "fog_recovery_db:ingress_keys";
+const INGEST_INVOCATIONS_DB_NAME: &str = "fog_recovery_db:ingest_invocations";
+const INGESTED_BLOCKS_DB_NAME: &str = "fog_recovery_db:ingested_blocks";
+const BLOCK_INDEX_TO_INGRESS_KEYS_DB_NAME: &str = "fog_recovery_db:block_index_to_ingress_keys";
+const USER_EVENTS_DB_NAME: &str = "fog_recovery_db:user_events";
+const REPORTS_DB_NAME: &str = "fog_recovery_db:reports";
+const COUNTERS_DB_NAME: &str = "fog_recovery_db:counters";
+
+// Keys in the counters database.
+const NEXT_INGEST_INVOCATION_ID_KEY: &str = "next_ingest_invocation_id";
+const NEXT_USER_EVENT_ID_KEY: &str = "next_user_event_id";
+const NEXT_REPORT_ID_KEY: &str = "next_report_id";
+const HIGHEST_KNOWN_BLOCK_INDEX_KEY: &str = "highest_known_block_index";
+
+/// Metadata store settings that are used for version control.
+#[derive(Clone, Default, Debug)]
+pub struct LmdbRecoveryDbMetadataStoreSettings;
+impl MetadataStoreSettings for LmdbRecoveryDbMetadataStoreSettings {
+    // Default database version. This should be bumped when breaking changes are
+    // introduced. If this is properly maintained, we could check during
+    // db opening for any incompatibilities, and either refuse to open or
+    // perform a migration.
+    #[allow(clippy::unreadable_literal)]
+    const LATEST_VERSION: u64 = 20261018;
+
+    /// The current crate version that manages the database.
+    const CRATE_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    /// LMDB Database name to use for storing the metadata information.
+    const DB_NAME: &'static str = "fog_recovery_db_metadata";
+}
+
+/// LMDB-backed recovery database.
+#[derive(Clone)]
+pub struct LmdbRecoveryDb {
+    /// LMDB Environment (database).
+    env: Arc<Environment>,
+
+    /// Compressed ingress public key -> IngressKeyRecord
+    ingress_keys: Database,
+
+    /// Ingest invocation id -> IngestInvocationRecord
+    ingest_invocations: Database,
+
+    /// (Compressed ingress public key, block index) -> IngestedBlockRecord
+    ingested_blocks: Database,
+
+    /// Block index -> compressed ingress public keys it was scanned with
+    /// (DUP_SORT)
+    block_index_to_ingress_keys: Database,
+
+    /// User event id -> UserEventRecord
+    user_events: Database,
+
+    /// Report id -> ReportRecord
+    reports: Database,
+
+    /// Counter name -> u64
+    counters: Database,
+
+    /// Logger.
+    logger: Logger,
+}
+
+impl LmdbRecoveryDb {
+    /// Open the database at the given directory, creating it if needed.
+    pub fn open(path: impl AsRef<Path>, logger: Logger) -> Result<Self, Error> {
+        let path = path.as_ref();
+        std::fs::create_dir_all(path)?;
+
+        let env = Arc::new(
+            Environment::new()
+                .set_max_dbs(8)
+                .set_map_size(MAX_LMDB_FILE_SIZE)
+                .open(path)?,
+        );
+
+        let metadata_store =
+            MetadataStore::<LmdbRecoveryDbMetadataStoreSettings>::open_or_create(&env)?;
+
+        let db_txn = env.begin_ro_txn()?;
+        let version = metadata_store.get_version(&db_txn)?;
+        log::info!(
+            logger,
+            "Fog recovery db is currently at version: {:?}",
+            version
+        );
+        db_txn.commit()?;
+
+        version.is_compatible_with_latest()?;
+
+        let ingress_keys = env.create_db(Some(INGRESS_KEYS_DB_NAME), DatabaseFlags::empty())?;
+        let ingest_invocations =
+            env.create_db(Some(INGEST_INVOCATIONS_DB_NAME), DatabaseFlags::empty())?;
+        let ingested_blocks =
+            env.create_db(Some(INGESTED_BLOCKS_DB_NAME), DatabaseFlags::empty())?;
+        let block_index_to_ingress_keys = env.create_db(
+            Some(BLOCK_INDEX_TO_INGRESS_KEYS_DB_NAME),
+            DatabaseFlags::DUP_SORT,
+        )?;
+        let user_events = env.create_db(Some(USER_EVENTS_DB_NAME), DatabaseFlags::empty())?;
+        let reports = env.create_db(Some(REPORTS_DB_NAME), DatabaseFlags::empty())?;
+        let counters = env.create_db(Some(COUNTERS_DB_NAME), DatabaseFlags::empty())?;
+
+        Ok(Self {
+            env,
+            ingress_keys,
+            ingest_invocations,
+            ingested_blocks,
+            block_index_to_ingress_keys,
+            user_events,
+            reports,
+            counters,
+            logger,
+        })
+    }
+
+    /// Open the database named by a `lmdb://<path>` url.
+    pub fn new_from_url(database_url: &str, logger: Logger) -> Result<Self, Error> {
+        let path = database_url
+            .strip_prefix(LMDB_URL_SCHEME)
+            .and_then(|rest| rest.strip_prefix("://"))
+            .filter(|path| !path.is_empty())
+            .ok_or_else(|| Error::InvalidUrl(LMDB_URL_SCHEME, database_url.to_owned()))?;
+        Self::open(path, logger)
+    }
+
+    /// Allocate the next value of an id counter. Ids start from 1, so that 0
+    /// can be used as a cursor which comes before every id.
+    fn next_id(&self, db_txn: &mut RwTransaction, counter: &str) -> Result<u64, Error> {
+        let id = self.get_counter(db_txn, counter)?.unwrap_or(0) + 1;
+        db_txn.put(
+            self.counters,
+            &counter,
+            &id.to_be_bytes(),
+            WriteFlags::empty(),
+        )?;
+        Ok(id)
+    }
+
+    fn get_counter(&self, db_txn: &impl Transaction, counter: &str) -> Result<Option<u64>, Error> {
+        match db_txn.get(self.counters, &counter) {
+            Ok(bytes) => Ok(Some(u64_from_be_bytes(bytes)?)),
+            Err(lmdb::Error::NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn get_highest_known_block_index_impl(
+        &self,
+        db_txn: &impl Transaction,
+    ) -> Result<Option<u64>, Error> {
+        self.get_counter(db_txn, HIGHEST_KNOWN_BLOCK_INDEX_KEY)
+    }
+
+    fn get_ingress_key(
+        &self,
+        db_txn: &impl Transaction,
+        key: &CompressedRistrettoPublic,
+    ) -> Result<Option<IngressKeyRecord>, Error> {
+        let key_bytes: &[u8] = key.as_ref();
+        get_record(db_txn, self.ingress_keys, key_bytes)
+    }
+
+    fn get_ingest_invocation(
+        &self,
+        db_txn: &impl Transaction,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<Option<IngestInvocationRecord>, Error> {
+        get_record(
+            db_txn,
+            self.ingest_invocations,
+            &invocation_key(ingest_invocation_id),
+        )
+    }
+
+    fn get_ingested_block(
+        &self,
+        db_txn: &impl Transaction,
+        ingress_key_bytes: &[u8],
+        block_index: u64,
+    ) -> Result<Option<IngestedBlockRecord>, Error> {
+        get_record(
+            db_txn,
+            self.ingested_blocks,
+            &ingested_block_key(ingress_key_bytes, block_index),
+        )
+    }
+
+    /// Get all the blocks ingested for a given block index, with any ingress
+    /// key.
+    fn get_ingested_blocks_for_index(
+        &self,
+        db_txn: &impl Transaction,
+        block_index: u64,
+    ) -> Result<Vec<IngestedBlockRecord>, Error> {
+        let mut cursor = db_txn.open_ro_cursor(self.block_index_to_ingress_keys)?;
+        let ingress_keys = cursor
+            .iter_dup_of(&block_index.to_be_bytes())
+            .map(|result| Ok(result?.1.to_vec()))
+            .collect::<Result<Vec<_>, Error>>()?;
+        drop(cursor);
+
+        ingress_keys
+            .iter()
+            .map(|ingress_key_bytes| {
+                self.get_ingested_block(db_txn, ingress_key_bytes, block_index)?
+                    .ok_or_else(|| {
+                        Error::IngestedBlockSchemaViolation(format!(
+                            "block index {block_index} is indexed but has no data"
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    fn add_user_event(&self, db_txn: &mut RwTransaction, event: UserEvent) -> Result<(), Error> {
+        let id = self.next_id(db_txn, NEXT_USER_EVENT_ID_KEY)?;
+        let record = UserEventRecord { event: Some(event) };
+        db_txn.put(
+            self.user_events,
+            &id.to_be_bytes(),
+            &record.encode_to_vec(),
+            WriteFlags::NO_OVERWRITE,
+        )?;
+        Ok(())
+    }
+
+    /// All user events with an id greater than the given one, in order.
+    fn get_user_events_after(
+        &self,
+        db_txn: &impl Transaction,
+        start_after_user_event_id: i64,
+    ) -> Result<Vec<(i64, UserEvent)>, Error> {
+        let start_key = (max(start_after_user_event_id, 0) as u64 + 1).to_be_bytes();
+        let mut cursor = db_txn.open_ro_cursor(self.user_events)?;
+        cursor
+            .iter_from(start_key)
+            .map(|result| {
+                let (key_bytes, value_bytes) = result?;
+                let id = u64_from_be_bytes(key_bytes)? as i64;
+                let record = UserEventRecord::decode(value_bytes)?;
+                let event = record
+                    .event
+                    .ok_or(Error::UserEventSchemaViolation(id, "missing event"))?;
+                Ok((id, event))
+            })
+            .collect()
+    }
+
+    fn get_missed_block_ranges_impl(
+        &self,
+        db_txn: &impl Transaction,
+    ) -> Result<Vec<BlockRange>, Error> {
+        Ok(self
+            .get_user_events_after(db_txn, 0)?
+            .into_iter()
+            .filter_map(|(_id, event)| match event {
+                UserEvent::MissingBlocks(range) => Some(range),
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn put_ingest_invocation(
+        &self,
+        db_txn: &mut RwTransaction,
+        ingest_invocation_id: &IngestInvocationId,
+        record: &IngestInvocationRecord,
+    ) -> Result<(), Error> {
+        db_txn.put(
+            self.ingest_invocations,
+            &invocation_key(ingest_invocation_id),
+            &record.encode_to_vec(),
+            WriteFlags::empty(),
+        )?;
+        Ok(())
+    }
+
+    fn put_ingress_key(
+        &self,
+        db_txn: &mut RwTransaction,
+        key_bytes: &[u8],
+        record: &IngressKeyRecord,
+    ) -> Result<(), Error> {
+        db_txn.put(
+            self.ingress_keys,
+            &key_bytes,
+            &record.encode_to_vec(),
+            WriteFlags::empty(),
+        )?;
+        Ok(())
+    }
+
+    /// Mark a given ingest invocation as decommissioned.
+    fn decommission_ingest_invocation_impl(
+        &self,
+        db_txn: &mut RwTransaction,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<(), Error> {
+        let mut record = self
+            .get_ingest_invocation(db_txn, ingest_invocation_id)?
+            .ok_or(Error::MissingIngestInvocation(*ingest_invocation_id))?;
+        if record.decommissioned {
+            return Err(Error::AlreadyDecommissioned(*ingest_invocation_id));
+        }
+
+        record.decommissioned = true;
+        record.last_active_at_micros = Utc::now().timestamp_micros();
+        self.put_ingest_invocation(db_txn, ingest_invocation_id, &record)?;
+
+        self.add_user_event(
+            db_txn,
+            UserEvent::DecommissionIngestInvocation(**ingest_invocation_id),
+        )
+    }
+}
+
+/// See trait `fog_recovery_db_iface::RecoveryDb` for documentation.
+impl RecoveryDb for LmdbRecoveryDb {
+    type Error = Error;
+
+    fn get_ingress_key_status(
+        &self,
+        key: &CompressedRistrettoPublic,
+    ) -> Result<Option<IngressPublicKeyStatus>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        Ok(self
+            .get_ingress_key(&db_txn, key)?
+            .map(|record| record.status()))
+    }
+
+    fn new_ingress_key(
+        &self,
+        key: &CompressedRistrettoPublic,
+        start_block_count: u64,
+    ) -> Result<u64, Self::Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        let highest_known_block_count = self
+            .get_highest_known_block_index_impl(&db_txn)?
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let accepted_start_block_count = max(start_block_count, highest_known_block_count);
+
+        let record = IngressKeyRecord {
+            start_block: accepted_start_block_count,
+            ..Default::default()
+        };
+        let key_bytes: &[u8] = key.as_ref();
+        match db_txn.put(
+            self.ingress_keys,
+            &key_bytes,
+            &record.encode_to_vec(),
+            WriteFlags::NO_OVERWRITE,
+        ) {
+            Ok(()) => {}
+            Err(lmdb::Error::KeyExist) => return Err(Error::IngressKeyExists(*key)),
+            Err(err) => return Err(err.into()),
+        }
+
+        db_txn.commit()?;
+        Ok(accepted_start_block_count)
+    }
+
+    fn retire_ingress_key(
+        &self,
+        key: &CompressedRistrettoPublic,
+        set_retired: bool,
+    ) -> Result<(), Self::Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+        if let Some(mut record) = self.get_ingress_key(&db_txn, key)? {
+            record.retired = set_retired;
+            self.put_ingress_key(&mut db_txn, key.as_ref(), &record)?;
+        }
+        db_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_last_scanned_block_index(
+        &self,
+        key: &CompressedRistrettoPublic,
+    ) -> Result<Option<u64>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        Ok(self
+            .get_ingress_key(&db_txn, key)?
+            .and_then(|record| record.last_scanned_block))
+    }
+
+    fn get_ingress_key_records(
+        &self,
+        start_block_at_least: u64,
+        ingress_public_key_record_filters: &IngressPublicKeyRecordFilters,
+    ) -> Result<Vec<IngressPublicKeyRecord>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        let mut cursor = db_txn.open_ro_cursor(self.ingress_keys)?;
+
+        let mut results = Vec::new();
+        for result in cursor.iter_start() {
+            let (key_bytes, value_bytes) = result?;
+            let record = IngressKeyRecord::decode(value_bytes)?;
+
+            if record.start_block < start_block_at_least {
+                continue;
+            }
+            if ingress_public_key_record_filters.should_only_include_unexpired_keys
+                && !record
+                    .last_scanned_block
+                    .is_some_and(|last_scanned| record.pubkey_expiry > last_scanned)
+            {
+                continue;
+            }
+            if !ingress_public_key_record_filters.should_include_lost_keys && record.lost {
+                continue;
+            }
+            if !ingress_public_key_record_filters.should_include_retired_keys && record.retired {
+                continue;
+            }
+
+            let key = CompressedRistrettoPublic::try_from(key_bytes)
+                .map_err(|_| Error::InvalidIngressKey(key_bytes.to_vec()))?;
+            results.push(IngressPublicKeyRecord {
+                key,
+                status: record.status(),
+                last_scanned_block: record.last_scanned_block,
+            });
+        }
+        Ok(results)
+    }
+
+    fn new_ingest_invocation(
+        &self,
+        prev_ingest_invocation_id: Option<IngestInvocationId>,
+        ingress_public_key: &CompressedRistrettoPublic,
+        egress_public_key: &KexRngPubkey,
+        start_block: u64,
+    ) -> Result<IngestInvocationId, Self::Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        // Optionally decommission old invocation.
+        if let Some(prev_ingest_invocation_id) = prev_ingest_invocation_id {
+            self.decommission_ingest_invocation_impl(&mut db_txn, &prev_ingest_invocation_id)?;
+        }
+
+        if self.get_ingress_key(&db_txn, ingress_public_key)?.is_none() {
+            return Err(Error::MissingIngressKey(*ingress_public_key));
+        }
+
+        // Write new invocation.
+        let id = IngestInvocationId::from(
+            self.next_id(&mut db_txn, NEXT_INGEST_INVOCATION_ID_KEY)? as i64,
+        );
+        let record = IngestInvocationRecord {
+            ingress_public_key: ingress_public_key.as_ref().to_vec(),
+            egress_public_key: egress_public_key.public_key.clone(),
+            rng_version: egress_public_key.version,
+            start_block,
+            decommissioned: false,
+            last_active_at_micros: Utc::now().timestamp_micros(),
+            last_ingested_block: None,
+        };
+        self.put_ingest_invocation(&mut db_txn, &id, &record)?;
+
+        // Write a user event.
+        self.add_user_event(&mut db_txn, UserEvent::NewIngestInvocation(*id))?;
+
+        db_txn.commit()?;
+        Ok(id)
+    }
+
+    fn get_ingestable_ranges(&self) -> Result<Vec<IngestableRange>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        let mut cursor = db_txn.open_ro_cursor(self.ingest_invocations)?;
+        cursor
+            .iter_start()
+            .map(|result| {
+                let (key_bytes, value_bytes) = result?;
+                let record = IngestInvocationRecord::decode(value_bytes)?;
+                Ok(IngestableRange {
+                    id: IngestInvocationId::from(u64_from_be_bytes(key_bytes)? as i64),
+                    start_block: record.start_block,
+                    decommissioned: record.decommissioned,
+                    last_ingested_block: record.last_ingested_block,
+                })
+            })
+            .collect()
+    }
+
+    fn decommission_ingest_invocation(
+        &self,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<(), Self::Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+        self.decommission_ingest_invocation_impl(&mut db_txn, ingest_invocation_id)?;
+        db_txn.commit()?;
+        Ok(())
+    }
+
+    fn add_block_data(
+        &self,
+        ingest_invocation_id: &IngestInvocationId,
+        block: &Block,
+        block_signature_timestamp: u64,
+        txs: &[ETxOutRecord],
+    ) -> Result<AddBlockDataStatus, Self::Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        let mut invocation = self
+            .get_ingest_invocation(&db_txn, ingest_invocation_id)?
+            .ok_or(Error::MissingIngestInvocation(*ingest_invocation_id))?;
+        let ingress_key_bytes = invocation.ingress_public_key.clone();
+
+        // A block is only scanned once with each ingress key. If an ingest server
+        // hits this, it learns it is behind someone else in its cluster.
+        let record = IngestedBlockRecord {
+            ingest_invocation_id: **ingest_invocation_id,
+            cumulative_txo_count: block.cumulative_txo_count,
+            block_signature_timestamp,
+            e_tx_out_records: txs.to_vec(),
+        };
+        match db_txn.put(
+            self.ingested_blocks,
+            &ingested_block_key(&ingress_key_bytes, block.index),
+            &record.encode_to_vec(),
+            WriteFlags::NO_OVERWRITE,
+        ) {
+            Ok(()) => {}
+            Err(lmdb::Error::KeyExist) => {
+                log::info!(
+                    self.logger,
+                    "Block {} was already scanned with the ingress key of ingest invocation id {}",
+                    block.index,
+                    ingest_invocation_id
+                );
+                return Ok(AddBlockDataStatus {
+                    block_already_scanned_with_this_key: true,
+                });
+            }
+            Err(err) => return Err(err.into()),
+        }
+        db_txn.put(
+            self.block_index_to_ingress_keys,
+            &block.index.to_be_bytes(),
+            &ingress_key_bytes,
+            WriteFlags::empty(),
+        )?;
+
+        // Update the invocation, which is also marked as still alive.
+        invocation.last_ingested_block = Some(
+            invocation
+                .last_ingested_block
+                .map_or(block.index, |index| max(index, block.index)),
+        );
+        invocation.last_active_at_micros = Utc::now().timestamp_micros();
+        self.put_ingest_invocation(&mut db_txn, ingest_invocation_id, &invocation)?;
+
+        // Update the last scanned block of the ingress key.
+        let mut ingress_key: IngressKeyRecord =
+            get_record(&db_txn, self.ingress_keys, &ingress_key_bytes)?
+                .ok_or_else(|| Error::InvalidIngressKey(ingress_key_bytes.clone()))?;
+        ingress_key.last_scanned_block = Some(
+            ingress_key
+                .last_scanned_block
+                .map_or(block.index, |index| max(index, block.index)),
+        );
+        self.put_ingress_key(&mut db_txn, &ingress_key_bytes, &ingress_key)?;
+
+        // Update the highest known block index.
+        let highest_known_block_index = self
+            .get_highest_known_block_index_impl(&db_txn)?
+            .map_or(block.index, |index| max(index, block.index));
+        db_txn.put(
+            self.counters,
+            &HIGHEST_KNOWN_BLOCK_INDEX_KEY,
+            &highest_known_block_index.to_be_bytes(),
+            WriteFlags::empty(),
+        )?;
+
+        db_txn.commit()?;
+        Ok(AddBlockDataStatus {
+            block_already_scanned_with_this_key: false,
+        })
+    }
+
+    fn report_lost_ingress_key(
+        &self,
+        lost_ingress_key: CompressedRistrettoPublic,
+    ) -> Result<(), Self::Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        // Find the ingress key and mark it lost.
+        let mut record = self
+            .get_ingress_key(&db_txn, &lost_ingress_key)?
+            .ok_or(Error::MissingIngressKey(lost_ingress_key))?;
+        record.lost = true;
+        self.put_ingress_key(&mut db_txn, lost_ingress_key.as_ref(), &record)?;
+
+        // Compute a missed block range based on the key status, which is correct
+        // if no blocks have actually been scanned using the key.
+        let mut missed_block_range = BlockRange::new(record.start_block, record.pubkey_expiry);
+
+        if let Some(block_index) = record.last_scanned_block {
+            if block_index + 1 >= missed_block_range.end_block {
+                // There aren't actually any blocks that need to be scanned, so we are done
+                // without creating a user event.
+                db_txn.commit()?;
+                return Ok(());
+            }
+            // If we did actually scan some blocks, then report a smaller range
+            if block_index + 1 > missed_block_range.start_block {
+                missed_block_range.start_block = block_index + 1;
+            }
+        }
+
+        // If the missed block range is invalid (empty), we don't have to add it.
+        // This can happen if the ingress key was never actually published to the report
+        // server, and then pubkey_expiry is zero.
+        if missed_block_range.is_valid() {
+            if self
+                .get_missed_block_ranges_impl(&db_txn)?
+                .contains(&missed_block_range)
+            {
+                return Err(Error::DuplicateMissedBlocksRange(missed_block_range));
+            }
+            self.add_user_event(&mut db_txn, UserEvent::MissingBlocks(missed_block_range))?;
+        }
+
+        db_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_missed_block_ranges(&self) -> Result<Vec<BlockRange>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        self.get_missed_block_ranges_impl(&db_txn)
+    }
+
+    fn search_user_events(
+        &self,
+        start_from_user_event_id: i64,
+    ) -> Result<(Vec<FogUserEvent>, i64), Self::Error> {
+        // Early return if start_from_user_event_id is max
+        if start_from_user_event_id == i64::MAX {
+            return Ok((Default::default(), i64::MAX));
+        }
+
+        let db_txn = self.env.begin_ro_txn()?;
+
+        // If no events are found, return start_from_user_event_id and not 0
+        let mut max_user_event_id = start_from_user_event_id;
+        let mut events = Vec::new();
+        for (user_event_id, event) in
+            self.get_user_events_after(&db_txn, start_from_user_event_id)?
+        {
+            max_user_event_id = max(max_user_event_id, user_event_id);
+
+            events.push(match event {
+                UserEvent::NewIngestInvocation(ingest_invocation_id) => {
+                    let invocation = self
+                        .get_ingest_invocation(&db_txn, &ingest_invocation_id.into())?
+                        .ok_or(Error::UserEventSchemaViolation(
+                            user_event_id,
+                            "missing ingest invocation",
+                        ))?;
+                    FogUserEvent::NewRngRecord(RngRecord {
+                        ingest_invocation_id,
+                        pubkey: KexRngPubkey {
+                            public_key: invocation.egress_public_key,
+                            version: invocation.rng_version,
+                        },
+                        start_block: invocation.start_block,
+                    })
+                }
+                UserEvent::DecommissionIngestInvocation(ingest_invocation_id) => {
+                    let invocation = self
+                        .get_ingest_invocation(&db_txn, &ingest_invocation_id.into())?
+                        .ok_or(Error::UserEventSchemaViolation(
+                            user_event_id,
+                            "missing ingest invocation",
+                        ))?;
+                    FogUserEvent::DecommissionIngestInvocation(DecommissionedIngestInvocation {
+                        ingest_invocation_id,
+                        last_ingested_block: invocation.last_ingested_block.unwrap_or(0),
+                    })
+                }
+                UserEvent::MissingBlocks(range) => FogUserEvent::MissingBlocks(range),
+            });
+        }
+
+        Ok((events, max_user_event_id))
+    }
+
+    /// Get any TxOutSearchResults corresponding to given search keys.
+    ///
+    /// Note: This scans every block from start_block onwards, and should not
+    /// be used except in tests.
+    fn get_tx_outs(
+        &self,
+        start_block: u64,
+        search_keys: &[Vec<u8>],
+    ) -> Result<Vec<FixedTxOutSearchResult>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        let mut cursor = db_txn.open_ro_cursor(self.block_index_to_ingress_keys)?;
+
+        let mut search_key_to_payload = HashMap::<Vec<u8>, Vec<u8>>::default();
+        for result in cursor.iter_from(start_block.to_be_bytes()) {
+            let (index_bytes, ingress_key_bytes) = result?;
+            let block_index = u64_from_be_bytes(index_bytes)?;
+            let record = self
+                .get_ingested_block(&db_txn, ingress_key_bytes, block_index)?
+                .ok_or_else(|| {
+                    Error::IngestedBlockSchemaViolation(format!(
+                        "block index {block_index} is indexed but has no data"
+                    ))
+                })?;
+            for e_tx_out_record in record.e_tx_out_records {
+                search_key_to_payload.insert(e_tx_out_record.search_key, e_tx_out_record.payload);
+            }
+        }
+
+        Ok(search_keys
+            .iter()
+            .map(|search_key| match search_key_to_payload.get(search_key) {
+                Some(payload) => FixedTxOutSearchResult::new(
+                    search_key.clone(),
+                    payload,
+                    TxOutSearchResultCode::Found,
+                ),
+                None => FixedTxOutSearchResult::new_not_found(search_key.clone()),
+            })
+            .collect())
+    }
+
+    fn update_last_active_at(
+        &self,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<(), Self::Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+        if let Some(mut record) = self.get_ingest_invocation(&db_txn, ingest_invocation_id)? {
+            record.last_active_at_micros = Utc::now().timestamp_micros();
+            self.put_ingest_invocation(&mut db_txn, ingest_invocation_id, &record)?;
+        }
+        db_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_tx_outs_by_block_and_key(
+        &self,
+        ingress_key: CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<Vec<ETxOutRecord>>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        Ok(self
+            .get_ingested_block(&db_txn, ingress_key.as_ref(), block_index)?
+            .map(|record| record.e_tx_out_records))
+    }
+
+    fn get_tx_outs_by_block_range_and_key(
+        &self,
+        ingress_key: CompressedRistrettoPublic,
+        block_range: &BlockRange,
+    ) -> Result<Vec<Vec<ETxOutRecord>>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+
+        // Return consecutive blocks from the start of the range, stopping at the
+        // first gap.
+        let mut result = Vec::new();
+        for block_index in block_range.start_block..block_range.end_block {
+            match self.get_ingested_block(&db_txn, ingress_key.as_ref(), block_index)? {
+                Some(record) => result.push(record.e_tx_out_records),
+                None => break,
+            }
+        }
+        Ok(result)
+    }
+
+    fn get_invocation_id_by_block_and_key(
+        &self,
+        ingress_key: CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<IngestInvocationId>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        Ok(self
+            .get_ingested_block(&db_txn, ingress_key.as_ref(), block_index)?
+            .map(|record| record.ingest_invocation_id.into()))
+    }
+
+    fn get_cumulative_txo_count_for_block(
+        &self,
+        block_index: u64,
+    ) -> Result<Option<u64>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        let data = self
+            .get_ingested_blocks_for_index(&db_txn, block_index)?
+            .into_iter()
+            .map(|record| record.cumulative_txo_count)
+            .collect::<Vec<_>>();
+
+        match data.first() {
+            None => Ok(None),
+            Some(cumulative_txo_count) if data.iter().all(|val| val == cumulative_txo_count) => {
+                Ok(Some(*cumulative_txo_count))
+            }
+            Some(_) => Err(Error::IngestedBlockSchemaViolation(format!(
+                "Found multiple cumulative_txo_count values for block {block_index}: {data:?}"
+            ))),
+        }
+    }
+
+    fn get_block_signature_timestamp_for_block(
+        &self,
+        block_index: u64,
+    ) -> Result<Option<u64>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        Ok(self
+            .get_ingested_blocks_for_index(&db_txn, block_index)?
+            .first()
+            .map(|record| record.block_signature_timestamp))
+    }
+
+    fn get_highest_known_block_index(&self) -> Result<Option<u64>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        self.get_highest_known_block_index_impl(&db_txn)
+    }
+
+    fn get_expired_invocations(
+        &self,
+        expiration: NaiveDateTime,
+    ) -> Result<Vec<ExpiredInvocationRecord>, Self::Error> {
+        let expiration_micros = expiration.and_utc().timestamp_micros();
+
+        let db_txn = self.env.begin_ro_txn()?;
+        let mut cursor = db_txn.open_ro_cursor(self.ingest_invocations)?;
+
+        let mut results = Vec::new();
+        for result in cursor.iter_start() {
+            let (key_bytes, value_bytes) = result?;
+            let record = IngestInvocationRecord::decode(value_bytes)?;
+            if record.last_active_at_micros >= expiration_micros {
+                continue;
+            }
+            results.push(ExpiredInvocationRecord {
+                ingest_invocation_id: u64_from_be_bytes(key_bytes)? as i64,
+                egress_public_key: KexRngPubkey {
+                    public_key: record.egress_public_key,
+                    version: record.rng_version,
+                },
+                last_active_at: naive_date_time_from_micros(record.last_active_at_micros)?,
+            });
+        }
+        Ok(results)
+    }
+}
+
+/// See trait `fog_recovery_db_iface::ReportDb` for documentation.
+impl ReportDb for LmdbRecoveryDb {
+    type Error = Error;
+
+    fn get_all_reports(&self) -> Result<Vec<(String, ReportData)>, Self::Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        let mut cursor = db_txn.open_ro_cursor(self.reports)?;
+
+        let mut records = cursor
+            .iter_start()
+            .map(|result| Ok(ReportRecord::decode(result?.1)?))
+            .collect::<Result<Vec<_>, Error>>()?;
+        records.sort_by_key(|record| record.id);
+
+        records
+            .into_iter()
+            .map(|record| {
+                let attestation_evidence = EvidenceKind::from_bytes(&record.report)?;
+                Ok((
+                    record.report_id,
+                    ReportData {
+                        ingest_invocation_id: record
+                            .ingest_invocation_id
+                            .map(IngestInvocationId::from),
+                        attestation_evidence: attestation_evidence.into(),
+                        pubkey_expiry: record.pubkey_expiry,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    fn set_report(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        report_id: &str,
+        data: &ReportData,
+    ) -> Result<IngressPublicKeyStatus, Self::Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        // First, try to update the pubkey_expiry value on this ingress key, only
+        // allowing it to increase, and only if it is not retired
+        let mut key_record = self
+            .get_ingress_key(&db_txn, ingress_key)?
+            .ok_or(Error::MissingIngressKey(*ingress_key))?;
+        if !key_record.retired && key_record.pubkey_expiry < data.pubkey_expiry {
+            key_record.pubkey_expiry = data.pubkey_expiry;
+            self.put_ingress_key(&mut db_txn, ingress_key.as_ref(), &key_record)?;
+        }
+
+        let result = key_record.status();
+        log::info!(self.logger, "Got status for key: {:?}", result);
+        if result.retired {
+            log::info!(self.logger, "Cannot publish key because it is retired");
+            return Ok(result);
+        }
+
+        // Replacing a report keeps its position in the list.
+        let existing: Option<ReportRecord> = get_record(&db_txn, self.reports, report_id)?;
+        let id = match existing {
+            Some(existing) => existing.id,
+            None => self.next_id(&mut db_txn, NEXT_REPORT_ID_KEY)?,
+        };
+        let report = ReportRecord {
+            id,
+            ingress_public_key: ingress_key.as_ref().to_vec(),
+            ingest_invocation_id: data.ingest_invocation_id.map(i64::from),
+            report: EvidenceKind::from(data.attestation_evidence.clone()).into_bytes(),
+            pubkey_expiry: data.pubkey_expiry,
+            report_id: report_id.to_owned(),
+        };
+        db_txn.put(
+            self.reports,
+            &report_id,
+            &report.encode_to_vec(),
+            WriteFlags::empty(),
+        )?;
+
+        db_txn.commit()?;
+        Ok(result)
+    }
+
+    fn remove_report(&self, report_id: &str) -> Result<(), Self::Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+        match db_txn.del(self.reports, &report_id, None) {
+            Ok(()) | Err(lmdb::Error::NotFound) => {}
+            Err(err) => return Err(err.into()),
+        }
+        db_txn.commit()?;
+        Ok(())
+    }
+}
+
+impl IngressKeyRecord {
+    fn status(&self) -> IngressPublicKeyStatus {
+        IngressPublicKeyStatus {
+            start_block: self.start_block,
+            pubkey_expiry: self.pubkey_expiry,
+            retired: self.retired,
+            lost: self.lost,
+        }
+    }
+}
+
+fn get_record<M: Message + Default>(
+    db_txn: &impl Transaction,
+    db: Database,
+    key: impl AsRef<[u8]>,
+) -> Result<Option<M>, Error> {
+    match db_txn.get(db, &key) {
+        Ok(bytes) => Ok(Some(M::decode(bytes)?)),
+        Err(lmdb::Error::NotFound) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn invocation_key(ingest_invocation_id: &IngestInvocationId) -> [u8; 8] {
+    (**ingest_invocation_id as u64).to_be_bytes()
+}
+
+fn ingested_block_key(ingress_key_bytes: &[u8], block_index: u64) -> Vec<u8> {
+    [ingress_key_bytes, &block_index.to_be_bytes()].concat()
+}
+
+fn u64_from_be_bytes(bytes: &[u8]) -> Result<u64, Error> {
+    let bytes = <[u8; 8]>::try_from(bytes).map_err(|_| {
+        Error::IngestedBlockSchemaViolation(format!("expected 8 bytes, found {bytes:?}"))
+    })?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+fn naive_date_time_from_micros(micros: i64) -> Result<NaiveDateTime, Error> {
+    DateTime::<Utc>::from_timestamp(
+        micros.div_euclid(1_000_000),
+        (micros.rem_euclid(1_000_000) * 1_000) as u32,
+    )
+    .map(|date_time| date_time.naive_utc())
+    .ok_or(Error::InvalidTimestamp(micros))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_fog_test_infra::db_tests::{random_block, random_kex_rng_pubkey};
+    use mc_util_from_random::FromRandom;
+    use mc_util_test_helper::{RngType, SeedableRng};
+    use tempfile::TempDir;
+
+    #[test_with_logger]
+    fn test_reopen_and_urls(logger: Logger) {
+        let mut rng = RngType::from_seed([1u8; 32]);
+        let db_dir = TempDir::new().unwrap();
+        let url = format!("lmdb://{}", db_dir.path().display());
+
+        let ingress_key = CompressedRistrettoPublic::from_random(&mut rng);
+        {
+            let db = LmdbRecoveryDb::new_from_url(&url, logger.clone()).unwrap();
+            db.new_ingress_key(&ingress_key, 0).unwrap();
+            let invoc_id = db
+                .new_ingest_invocation(None, &ingress_key, &random_kex_rng_pubkey(&mut rng), 0)
+                .unwrap();
+            let (block, records) = random_block(&mut rng, 0, 5);
+            db.add_block_data(&invoc_id, &block, 10, &records).unwrap();
+            assert!(
+                db.add_block_data(&invoc_id, &block, 10, &records)
+                    .unwrap()
+                    .block_already_scanned_with_this_key
+            );
+        }
+
+        // The data survives reopening the database.
+        let db = LmdbRecoveryDb::open(db_dir.path(), logger.clone()).unwrap();
+        assert_eq!(db.get_highest_known_block_index().unwrap(), Some(0));
+        assert_eq!(
+            db.get_last_scanned_block_index(&ingress_key).unwrap(),
+            Some(0)
+        );
+        assert_eq!(
+            db.get_block_signature_timestamp_for_block(0).unwrap(),
+            Some(10)
+        );
+        assert_eq!(
+            db.get_tx_outs_by_block_and_key(ingress_key, 0)
+                .unwrap()
+                .map(|records| records.len()),
+            Some(5)
+        );
+
+        // Other schemes are rejected.
+        assert!(matches!(
+            LmdbRecoveryDb::new_from_url("postgres://localhost/fog", logger.clone()),
+            Err(Error::InvalidUrl(..))
+        ));
+        assert!(matches!(
+            LmdbRecoveryDb::new_from_url("lmdb://", logger),
+            Err(Error::InvalidUrl(..))
+        ));
+    }
+
+    #[test_with_logger]
+    fn test_expired_invocations(logger: Logger) {
+        let mut rng = RngType::from_seed([2u8; 32]);
+        let db_dir = TempDir::new().unwrap();
+        let db = LmdbRecoveryDb::open(db_dir.path(), logger).unwrap();
+
+        let ingress_key = CompressedRistrettoPublic::from_random(&mut rng);
+        db.new_ingress_key(&ingress_key, 0).unwrap();
+        let kex_rng_pubkey = random_kex_rng_pubkey(&mut rng);
+        let invoc_id = db
+            .new_ingest_invocation(None, &ingress_key, &kex_rng_pubkey, 0)
+            .unwrap();
+
+        let before = Utc::now().naive_utc() - chrono::Duration::seconds(60);
+        assert!(db.get_expired_invocations(before).unwrap().is_empty());
+
+        let after = Utc::now().naive_utc() + chrono::Duration::seconds(60);
+        let expired = db.get_expired_invocations(after).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(
+            IngestInvocationId::from(expired[0].ingest_invocation_id),
+            invoc_id
+        );
+        assert_eq!(expired[0].egress_public_key, kex_rng_pubkey);
+        assert!(expired[0].last_active_at > before && expired[0].last_active_at < after);
+
+        // Invocations can only be decommissioned once.
+        db.decommission_ingest_invocation(&invoc_id).unwrap();
+        assert!(matches!(
+            db.decommission_ingest_invocation(&invoc_id),
+            Err(Error::AlreadyDecommissioned(_))
+        ));
+    }
+}
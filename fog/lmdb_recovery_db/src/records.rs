@@ -0,0 +1,137 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Protobuf records stored in the LMDB databases.
+
+use mc_fog_types::{common::BlockRange, ETxOutRecord};
+use prost::{Message, Oneof};
+
+/// An ingress key, keyed by the compressed public key bytes.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct IngressKeyRecord {
+    /// The first block this key could have been used for.
+    #[prost(uint64, tag = 1)]
+    pub start_block: u64,
+
+    /// The largest pubkey_expiry value ever published in a report for this
+    /// key.
+    #[prost(uint64, tag = 2)]
+    pub pubkey_expiry: u64,
+
+    /// Whether this key is retired.
+    #[prost(bool, tag = 3)]
+    pub retired: bool,
+
+    /// Whether this key is lost.
+    #[prost(bool, tag = 4)]
+    pub lost: bool,
+
+    /// The highest block index scanned with this key, if any.
+    #[prost(uint64, optional, tag = 5)]
+    pub last_scanned_block: Option<u64>,
+}
+
+/// An ingest invocation, keyed by its id.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct IngestInvocationRecord {
+    /// The ingress public key this invocation is scanning with.
+    #[prost(bytes, tag = 1)]
+    pub ingress_public_key: Vec<u8>,
+
+    /// The egress key this invocation is generating search keys with.
+    #[prost(bytes, tag = 2)]
+    pub egress_public_key: Vec<u8>,
+
+    /// The rng algorithm version number.
+    #[prost(uint32, tag = 3)]
+    pub rng_version: u32,
+
+    /// The first block this invocation scanned.
+    #[prost(uint64, tag = 4)]
+    pub start_block: u64,
+
+    /// Whether this invocation is decommissioned.
+    #[prost(bool, tag = 5)]
+    pub decommissioned: bool,
+
+    /// The last time this invocation was active, in microseconds since the
+    /// unix epoch.
+    #[prost(int64, tag = 6)]
+    pub last_active_at_micros: i64,
+
+    /// The highest block index this invocation ingested, if any.
+    #[prost(uint64, optional, tag = 7)]
+    pub last_ingested_block: Option<u64>,
+}
+
+/// A block ingested with a given ingress key, keyed by the ingress key and
+/// the block index.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct IngestedBlockRecord {
+    /// The invocation that produced this record.
+    #[prost(int64, tag = 1)]
+    pub ingest_invocation_id: i64,
+
+    /// The cumulative txo count from the block header.
+    #[prost(uint64, tag = 2)]
+    pub cumulative_txo_count: u64,
+
+    /// The block signature timestamp, in seconds since the unix epoch.
+    #[prost(uint64, tag = 3)]
+    pub block_signature_timestamp: u64,
+
+    /// Any ETxOutRecord's that fog ingest emitted in connection to this block.
+    #[prost(message, repeated, tag = 4)]
+    pub e_tx_out_records: Vec<ETxOutRecord>,
+}
+
+/// A user event, keyed by its id.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct UserEventRecord {
+    /// The event.
+    #[prost(oneof = "UserEvent", tags = "1, 2, 3")]
+    pub event: Option<UserEvent>,
+}
+
+/// The kinds of user events.
+#[derive(Clone, Eq, PartialEq, Oneof)]
+pub enum UserEvent {
+    /// A new ingest invocation, by id.
+    #[prost(int64, tag = 1)]
+    NewIngestInvocation(i64),
+
+    /// A decommissioned ingest invocation, by id.
+    #[prost(int64, tag = 2)]
+    DecommissionIngestInvocation(i64),
+
+    /// A range of blocks that will never be scanned.
+    #[prost(message, tag = 3)]
+    MissingBlocks(BlockRange),
+}
+
+/// A fog report, keyed by its report id.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct ReportRecord {
+    /// The order this report id was first published in.
+    #[prost(uint64, tag = 1)]
+    pub id: u64,
+
+    /// The ingress key this report is for.
+    #[prost(bytes, tag = 2)]
+    pub ingress_public_key: Vec<u8>,
+
+    /// The ingest invocation that published this report, if known.
+    #[prost(int64, optional, tag = 3)]
+    pub ingest_invocation_id: Option<i64>,
+
+    /// The attestation evidence, encoded.
+    #[prost(bytes, tag = 4)]
+    pub report: Vec<u8>,
+
+    /// The pubkey expiry published in this report.
+    #[prost(uint64, tag = 5)]
+    pub pubkey_expiry: u64,
+
+    /// The report id, duplicated from the key.
+    #[prost(string, tag = 6)]
+    pub report_id: String,
+}
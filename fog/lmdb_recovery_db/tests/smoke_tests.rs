@@ -0,0 +1,46 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+use mc_common::logger::{test_with_logger, Logger};
+use mc_fog_lmdb_recovery_db::LmdbRecoveryDb;
+use mc_fog_test_infra::db_tests::*;
+use tempfile::TempDir;
+
+#[test_with_logger]
+fn lmdb_recovery_db_smoke_tests_new_apis(logger: Logger) {
+    mc_util_test_helper::run_with_several_seeds(|mut rng| {
+        let db_dir = TempDir::new().unwrap();
+        let db = LmdbRecoveryDb::open(db_dir.path(), logger.clone()).unwrap();
+
+        recovery_db_smoke_tests_new_apis(&mut rng, &db);
+    })
+}
+
+#[test_with_logger]
+fn lmdb_recovery_db_missed_blocks_reporting(logger: Logger) {
+    mc_util_test_helper::run_with_several_seeds(|mut rng| {
+        let db_dir = TempDir::new().unwrap();
+        let db = LmdbRecoveryDb::open(db_dir.path(), logger.clone()).unwrap();
+
+        recovery_db_missed_blocks_reporting(&mut rng, &db);
+    })
+}
+
+#[test_with_logger]
+fn lmdb_recovery_db_rng_records_decommissioning(logger: Logger) {
+    mc_util_test_helper::run_with_several_seeds(|mut rng| {
+        let db_dir = TempDir::new().unwrap();
+        let db = LmdbRecoveryDb::open(db_dir.path(), logger.clone()).unwrap();
+
+        recovery_db_rng_records_decommissioning(&mut rng, &db);
+    })
+}
+
+#[test_with_logger]
+fn lmdb_recovery_db_ingress_keys(logger: Logger) {
+    mc_util_test_helper::run_with_several_seeds(|mut rng| {
+        let db_dir = TempDir::new().unwrap();
+        let db = LmdbRecoveryDb::open(db_dir.path(), logger.clone()).unwrap();
+
+        test_recovery_db_ingress_keys(&mut rng, &db);
+    })
+}
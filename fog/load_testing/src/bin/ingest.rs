@@ -21,7 +21,7 @@ use mc_blockchain_types::{BlockSignature, BlockVersion};
 use mc_common::logger::{log, Logger};
 use mc_crypto_keys::Ed25519Pair;
 use mc_fog_ingest_client::FogIngestGrpcClient;
-use mc_fog_load_testing::get_bin_path;
+use mc_fog_load_testing::{compute_basic_stats, get_bin_path, AutoKillChild, BasicTimingStats};
 use mc_fog_recovery_db_iface::RecoveryDb;
 use mc_fog_sql_recovery_db::test_utils::SqlRecoveryDbTestContext;
 use mc_fog_uri::{ConnectionUri, FogIngestUri, IngestPeerUri};
@@ -41,52 +41,6 @@ use std::{
 };
 use tempfile::TempDir;
 
-// Compute mean and std_dev of timings
-#[derive(Default, Clone)]
-struct BasicTimingStats {
-    pub num_samples: usize,
-    pub mean: Duration,
-    pub std_dev: Duration,
-}
-
-impl core::fmt::Display for BasicTimingStats {
-    fn fmt(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
-        write!(
-            formatter,
-            "num samples: {}, avg: {} ms +/- {} ms",
-            self.num_samples,
-            self.mean.as_secs_f64() * 1000f64,
-            self.std_dev.as_secs_f64() * 1000f64
-        )
-    }
-}
-
-// Take a series of durations, and compute their mean and standard deviation
-fn compute_basic_stats(data: &[Duration], logger: &Logger) -> BasicTimingStats {
-    let num_samples = data.len();
-
-    let mean = data.iter().fold(0f64, |l, r| l + r.as_secs_f64()) / num_samples as f64;
-    let variance = data
-        .iter()
-        .fold(0f64, |l, r| l + (r.as_secs_f64() - mean).powi(2))
-        / num_samples as f64;
-    let std_dev = variance.sqrt();
-
-    log::debug!(
-        logger,
-        "mean sec = {}, variance sec = {}, std_dev sec = {}",
-        mean,
-        variance,
-        std_dev
-    );
-
-    BasicTimingStats {
-        num_samples,
-        mean: Duration::from_secs_f64(mean),
-        std_dev: Duration::from_secs_f64(std_dev),
-    }
-}
-
 // Parameters of interest to twiddle for purposes of load testing
 #[derive(Default, Clone)]
 struct TestParams {
@@ -117,42 +71,6 @@ impl core::fmt::Display for TestResult {
     }
 }
 
-/// RAII gaurd for a child process, which kills the child on drop.
-pub struct AutoKillChild(pub std::process::Child);
-
-impl AutoKillChild {
-    /// Assert that the child process is still alive.
-    ///
-    /// Panics:
-    ///  * If the process stopped unexpectedly.
-    ///  * If there is an error while getting the process' status.
-    pub fn assert_not_stopped(&mut self) {
-        match self.0.try_wait() {
-            Ok(Some(stat)) => {
-                panic!("child stopped unexpectedly: status: {stat}")
-            }
-            Ok(None) => {}
-            Err(err) => {
-                panic!("error getting child status: {err}")
-            }
-        }
-    }
-}
-
-impl Drop for AutoKillChild {
-    fn drop(&mut self) {
-        if let Err(err) = self.0.kill() {
-            // Invalid input means the process is already dead, and we don't have to kill
-            // it. Anything else means that killing it failed somehow
-            if err.kind() != std::io::ErrorKind::InvalidInput {
-                panic!("Could not send SIGKILL to child: {err}")
-            }
-        }
-
-        self.0.wait().expect("Could not reap child");
-    }
-}
-
 struct TestConfig {
     // path to ingest server binary to launch
     pub ingest_server_binary: PathBuf,
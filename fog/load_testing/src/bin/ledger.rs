@@ -0,0 +1,425 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+#![deny(missing_docs)]
+
+//! This load test creates a ledger with spent key images, starts a key image
+//! store and a ledger router serving it, and checks key images through the
+//! router.
+//!
+//! It attempts to measure:
+//! - How long does checking a batch of key images take, when searching the
+//!   whole ledger?
+//! - How much faster is it when only searching recent blocks, which key image
+//!   stores answer from their recent key images instead of the ORAM?
+
+use clap::Parser;
+use futures::executor::block_on;
+use grpcio::{ChannelBuilder, Error as GrpcioError};
+use mc_account_keys::AccountKey;
+use mc_blockchain_types::BlockVersion;
+use mc_common::logger::{log, Logger};
+use mc_fog_ledger_connection::{KeyImageResultExtension, LedgerGrpcClient};
+use mc_fog_load_testing::{compute_basic_stats, get_bin_path, AutoKillChild, BasicTimingStats};
+use mc_fog_uri::{ConnectionUri, FogLedgerUri, KeyImageStoreUri};
+use mc_ledger_db::{test_utils::add_block_to_ledger, LedgerDB};
+use mc_rand::{McRng, RngCore};
+use mc_transaction_core::{ring_signature::KeyImage, tokens::Mob, Amount, Token};
+use mc_util_grpc::{admin_grpc::AdminApiClient, ConnectionUriGrpcioChannel, Empty};
+use mc_util_uri::AdminUri;
+use mc_watcher::watcher_db::WatcherDB;
+use retry::{delay, retry, OperationResult};
+use std::{
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use tempfile::TempDir;
+
+// Parameters of interest to twiddle for purposes of load testing
+#[derive(Default, Clone)]
+struct TestParams {
+    omap_capacity: u64,
+}
+
+impl core::fmt::Display for TestParams {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(formatter, "{{ omap_capacity: {} }}", self.omap_capacity)
+    }
+}
+
+// The results of a load test
+#[derive(Default, Clone)]
+struct TestResult {
+    params: TestParams,
+    batch_size: usize,
+    full_timings: BasicTimingStats,
+    recent_timings: BasicTimingStats,
+}
+
+impl core::fmt::Display for TestResult {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let key_images_per_sec = |stats: &BasicTimingStats| {
+            self.batch_size as f64 / stats.mean.as_secs_f64().max(f64::EPSILON)
+        };
+        write!(
+            formatter,
+            "{}:\nCheck {} key images since block 0: {} ({:.0} key images/s)\nCheck {} key images since a recent block: {} ({:.0} key images/s)",
+            self.params,
+            self.batch_size,
+            self.full_timings,
+            key_images_per_sec(&self.full_timings),
+            self.batch_size,
+            self.recent_timings,
+            key_images_per_sec(&self.recent_timings),
+        )
+    }
+}
+
+struct TestConfig {
+    // path to key image store binary to launch
+    pub key_image_store_binary: PathBuf,
+    // path to ledger router binary to launch
+    pub ledger_router_binary: PathBuf,
+    // how many blocks with key images to put in the ledger
+    pub num_blocks: usize,
+    // how many key images to put in a block
+    pub key_images_per_block: usize,
+    // how many key images to check per request
+    pub batch_size: usize,
+    // how many of the last blocks the recent checks search
+    pub recent_blocks: usize,
+    // how many requests to time for each kind of check
+    pub repetitions: usize,
+}
+
+fn load_test(config: &TestConfig, test_params: TestParams, logger: Logger) -> TestResult {
+    let mut test_results = TestResult {
+        params: test_params.clone(),
+        batch_size: config.batch_size,
+        ..Default::default()
+    };
+
+    let mut rng = McRng {};
+
+    {
+        // First make grpcio env
+        // Note: This needs to be destroyed when the servers are destroyed,
+        // then we have to sleep, see end of this scope
+        let grpcio_env = Arc::new(grpcio::EnvBuilder::new().build());
+
+        let base_port = 3060;
+        let store_client_port = base_port + 4;
+        let router_client_port = base_port + 5;
+        let store_uri = KeyImageStoreUri::from_str(&format!(
+            "insecure-key-image-store://127.0.0.1:{store_client_port}"
+        ))
+        .unwrap();
+        let router_uri = FogLedgerUri::from_str(&format!(
+            "insecure-fog-ledger://127.0.0.1:{router_client_port}"
+        ))
+        .unwrap();
+
+        let store_admin_uri = AdminUri::from_str("insecure-mca://127.0.0.1:8004/").unwrap();
+        let router_admin_uri = AdminUri::from_str("insecure-mca://127.0.0.1:8005/").unwrap();
+
+        // Set up the Watcher DB
+        let watcher_db_path = TempDir::new().expect("Could not make tempdir for wallet db");
+        WatcherDB::create(watcher_db_path.path()).unwrap();
+        let watcher = WatcherDB::open_rw(
+            watcher_db_path.path(),
+            &["http://bash.org".parse().unwrap()],
+            logger.clone(),
+        )
+        .unwrap();
+
+        // Set up a fresh ledger db, with key images in every block but the
+        // origin block
+        let ledger_db_path = TempDir::new().expect("Could not make tempdir for ledger db");
+        LedgerDB::create(ledger_db_path.path()).unwrap();
+        let mut ledger_db = LedgerDB::open(ledger_db_path.path()).unwrap();
+
+        log::info!(
+            logger,
+            "Adding {} blocks with {} key images each",
+            config.num_blocks,
+            config.key_images_per_block
+        );
+        let recipients = vec![AccountKey::random(&mut rng).default_subaddress()];
+        let mut key_images = Vec::with_capacity(config.num_blocks);
+        for block_index in 0..=config.num_blocks {
+            let block_key_images = if block_index == 0 {
+                vec![]
+            } else {
+                (0..config.key_images_per_block)
+                    .map(|_| KeyImage::from(rng.next_u64()))
+                    .collect()
+            };
+            let block_data = add_block_to_ledger(
+                &mut ledger_db,
+                BlockVersion::MAX,
+                &recipients,
+                Amount::new(10, Mob::ID),
+                &block_key_images,
+                &mut rng,
+            )
+            .expect("Adding block failed");
+
+            let signature = block_data.signature().expect("missing signature");
+            for src_url in watcher.get_config_urls().unwrap().iter() {
+                watcher
+                    .add_block_signature(
+                        src_url,
+                        block_index as u64,
+                        signature.clone(),
+                        format!("00/{block_index}"),
+                    )
+                    .expect("Could not add block signature");
+            }
+            if block_index > 0 {
+                key_images.push(block_key_images);
+            }
+        }
+
+        // Start the key image store, serving the whole ledger
+        let mut command =
+            std::process::Command::new(config.key_image_store_binary.to_str().unwrap());
+        command
+            .args(["--chain-id", "local"])
+            .args(["--ledger-db", ledger_db_path.path().to_str().unwrap()])
+            .args(["--watcher-db", watcher_db_path.path().to_str().unwrap()])
+            .args(["--client-listen-uri", store_uri.as_ref()])
+            .args([
+                "--client-responder-id",
+                &store_uri.responder_id().unwrap().to_string(),
+            ])
+            .args(["--admin-listen-uri", store_admin_uri.as_ref()])
+            .args(["--omap-capacity", &test_params.omap_capacity.to_string()]);
+
+        log::info!(logger, "Spawning key image store: {:?}", command);
+
+        let mut key_image_store =
+            AutoKillChild(command.spawn().expect("Could not spawn key image store"));
+
+        // Start the router in front of it
+        let mut command = std::process::Command::new(config.ledger_router_binary.to_str().unwrap());
+        command
+            .args(["--chain-id", "local"])
+            .args(["--ledger-db", ledger_db_path.path().to_str().unwrap()])
+            .args(["--watcher-db", watcher_db_path.path().to_str().unwrap()])
+            .args(["--client-listen-uri", router_uri.as_ref()])
+            .args([
+                "--client-responder-id",
+                &router_uri.responder_id().unwrap().to_string(),
+            ])
+            .args(["--admin-listen-uri", router_admin_uri.as_ref()])
+            .args(["--shard-uris", store_uri.as_ref()]);
+
+        log::info!(logger, "Spawning ledger router: {:?}", command);
+
+        let mut ledger_router =
+            AutoKillChild(command.spawn().expect("Could not spawn ledger router"));
+
+        // Wait for the admin apis to be reachable
+        for (name, admin_uri) in [
+            ("key image store", &store_admin_uri),
+            ("ledger router", &router_admin_uri),
+        ] {
+            let admin_client = {
+                let ch = ChannelBuilder::new(grpcio_env.clone()).connect_to_uri(admin_uri, &logger);
+                AdminApiClient::new(ch)
+            };
+
+            let info = retry(delay::Fixed::from_millis(5000).map(delay::jitter), || {
+                key_image_store.assert_not_stopped();
+                ledger_router.assert_not_stopped();
+
+                match admin_client.get_info(&Empty::default()) {
+                    Ok(info) => OperationResult::Ok(info),
+                    Err(GrpcioError::RpcFailure(err)) => {
+                        log::info!(&logger, "Waiting for {} to become available", name);
+                        OperationResult::Retry(GrpcioError::RpcFailure(err))
+                    }
+                    Err(err) => OperationResult::Err(err),
+                }
+            })
+            .unwrap_or_else(|err| panic!("Could not connect to {name}: {err:?}"));
+
+            log::info!(
+                logger,
+                "Connected to {}:\nbuild_info: {}\nconfig_json: {}",
+                name,
+                info.build_info_json,
+                info.config_json
+            );
+        }
+
+        let mut client = LedgerGrpcClient::new(
+            router_uri,
+            [mc_fog_ledger_enclave_measurement::mr_signer_identity(None)],
+            grpcio_env,
+            logger.clone(),
+        );
+
+        // Wait for the store to load the whole ledger
+        {
+            let last_key_image = key_images.last().unwrap()[0];
+            let start = Instant::now();
+            loop {
+                let loaded = block_on(client.check_key_images(&[last_key_image]))
+                    .map(|response| {
+                        response.results[0].status() == Ok(Some(config.num_blocks as u64))
+                    })
+                    .unwrap_or(false);
+                if loaded {
+                    break;
+                }
+                log::info!(logger, "Waiting for key image store to load the ledger");
+                sleep(Duration::from_millis(1000));
+                if start.elapsed() >= Duration::from_secs(600) {
+                    panic!("Time exceeded 600 seconds");
+                }
+                key_image_store.assert_not_stopped();
+                ledger_router.assert_not_stopped();
+            }
+            log::info!(
+                logger,
+                "Key image store loaded the ledger in {:?}",
+                start.elapsed()
+            );
+        }
+
+        // Measure key image checks. Both kinds of checks ask about the same
+        // key images, spent in the last recent_blocks blocks.
+        {
+            let recent_key_images = key_images[config.num_blocks - config.recent_blocks..]
+                .iter()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>();
+            let start_block = (config.num_blocks - config.recent_blocks + 1) as u64;
+
+            let mut full_timings = Vec::<Duration>::with_capacity(config.repetitions);
+            let mut recent_timings = Vec::<Duration>::with_capacity(config.repetitions);
+            for repetition in 0..config.repetitions {
+                let batch = (0..config.batch_size)
+                    .map(|i| {
+                        recent_key_images
+                            [(repetition * config.batch_size + i) % recent_key_images.len()]
+                    })
+                    .collect::<Vec<_>>();
+
+                let start = Instant::now();
+                let full_response =
+                    block_on(client.check_key_images(&batch)).expect("Checking key images failed");
+                full_timings.push(start.elapsed());
+
+                let start = Instant::now();
+                let recent_response = block_on(client.check_key_images_since(&batch, start_block))
+                    .expect("Checking key images failed");
+                recent_timings.push(start.elapsed());
+
+                // Sanity check that both kinds of checks agree
+                for (full, recent) in full_response
+                    .results
+                    .iter()
+                    .zip(recent_response.results.iter())
+                {
+                    assert!(matches!(full.status(), Ok(Some(_))));
+                    assert_eq!(full.status(), recent.status());
+                }
+                ledger_router.assert_not_stopped();
+            }
+
+            // Discard the first 5 runs for "warmup"
+            // FIXME: Maybe something better / do it at a different layer
+            test_results.full_timings = compute_basic_stats(&full_timings[5..], &logger);
+            test_results.recent_timings = compute_basic_stats(&recent_timings[5..], &logger);
+            log::crit!(
+                logger,
+                "Check key images timings ({} key images): since block 0: {}, since block {}: {}",
+                config.batch_size,
+                test_results.full_timings,
+                start_block,
+                test_results.recent_timings
+            );
+        }
+    }
+    // grpcio detaches all its threads and does not join them, see the ingest
+    // load test
+    sleep(Duration::from_millis(1000));
+
+    test_results
+}
+
+#[derive(Debug, Parser)]
+#[clap(
+    name = "fog-ledger-server-load-test",
+    about = "Spawns a fog key image store and ledger router and checks key images through them in order to measure their performance"
+)]
+struct LoadTestOptions {
+    #[clap(long, env = "MC_OMAP_CAPACITY")]
+    omap_capacity: Option<Vec<u64>>,
+    #[clap(long, env = "MC_NUM_BLOCKS", default_value = "1000")]
+    num_blocks: usize,
+    #[clap(long, env = "MC_KEY_IMAGES_PER_BLOCK", default_value = "4")]
+    key_images_per_block: usize,
+    #[clap(long, env = "MC_BATCH_SIZE", default_value = "16")]
+    batch_size: usize,
+    #[clap(long, env = "MC_RECENT_BLOCKS", default_value = "10")]
+    recent_blocks: usize,
+    #[clap(long, env = "MC_REPETITONS", default_value = "100")]
+    repetitions: usize,
+}
+
+fn main() {
+    mc_common::setup_panic_handler();
+
+    let opt = LoadTestOptions::parse();
+    assert!(
+        opt.recent_blocks > 0 && opt.recent_blocks <= opt.num_blocks,
+        "recent blocks must be between 1 and the number of blocks"
+    );
+    assert!(
+        opt.key_images_per_block > 0,
+        "key images per block must be at least 1"
+    );
+    assert!(opt.repetitions > 5, "repetitions must be more than 5");
+
+    let logger = mc_common::logger::create_root_logger();
+
+    let config = TestConfig {
+        key_image_store_binary: get_bin_path("key_image_store"),
+        ledger_router_binary: get_bin_path("ledger_router"),
+        num_blocks: opt.num_blocks,
+        key_images_per_block: opt.key_images_per_block,
+        batch_size: opt.batch_size,
+        recent_blocks: opt.recent_blocks,
+        repetitions: opt.repetitions,
+    };
+
+    let mut results = Vec::new();
+
+    let capacities_to_test = opt.omap_capacity.unwrap_or_else(|| vec![1024 * 1024]);
+    log::info!(
+        logger,
+        "Testing servers with these capacities: {:?}",
+        capacities_to_test
+    );
+
+    for cap in capacities_to_test.iter() {
+        results.push(load_test(
+            &config,
+            TestParams {
+                omap_capacity: *cap,
+            },
+            logger.clone(),
+        ));
+    }
+
+    println!("Load testing results\n================");
+    for result in results.iter() {
+        println!("{result}");
+    }
+}
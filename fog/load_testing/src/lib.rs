@@ -4,11 +4,98 @@
 
 #![deny(missing_docs)]
 
+use mc_common::logger::{log, Logger};
 use std::{
     env,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
+/// The mean and standard deviation of a series of timings
+#[derive(Default, Clone)]
+pub struct BasicTimingStats {
+    /// The number of timings
+    pub num_samples: usize,
+    /// The mean of the timings
+    pub mean: Duration,
+    /// The standard deviation of the timings
+    pub std_dev: Duration,
+}
+
+impl core::fmt::Display for BasicTimingStats {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            formatter,
+            "num samples: {}, avg: {} ms +/- {} ms",
+            self.num_samples,
+            self.mean.as_secs_f64() * 1000f64,
+            self.std_dev.as_secs_f64() * 1000f64
+        )
+    }
+}
+
+/// Take a series of durations, and compute their mean and standard deviation
+pub fn compute_basic_stats(data: &[Duration], logger: &Logger) -> BasicTimingStats {
+    let num_samples = data.len();
+
+    let mean = data.iter().fold(0f64, |l, r| l + r.as_secs_f64()) / num_samples as f64;
+    let variance = data
+        .iter()
+        .fold(0f64, |l, r| l + (r.as_secs_f64() - mean).powi(2))
+        / num_samples as f64;
+    let std_dev = variance.sqrt();
+
+    log::debug!(
+        logger,
+        "mean sec = {}, variance sec = {}, std_dev sec = {}",
+        mean,
+        variance,
+        std_dev
+    );
+
+    BasicTimingStats {
+        num_samples,
+        mean: Duration::from_secs_f64(mean),
+        std_dev: Duration::from_secs_f64(std_dev),
+    }
+}
+
+/// RAII gaurd for a child process, which kills the child on drop.
+pub struct AutoKillChild(pub std::process::Child);
+
+impl AutoKillChild {
+    /// Assert that the child process is still alive.
+    ///
+    /// Panics:
+    ///  * If the process stopped unexpectedly.
+    ///  * If there is an error while getting the process' status.
+    pub fn assert_not_stopped(&mut self) {
+        match self.0.try_wait() {
+            Ok(Some(stat)) => {
+                panic!("child stopped unexpectedly: status: {stat}")
+            }
+            Ok(None) => {}
+            Err(err) => {
+                panic!("error getting child status: {err}")
+            }
+        }
+    }
+}
+
+impl Drop for AutoKillChild {
+    fn drop(&mut self) {
+        if let Err(err) = self.0.kill() {
+            // Invalid input means the process is already dead, and we don't have to kill
+            // it. Anything else means that killing it failed somehow
+            if err.kind() != std::io::ErrorKind::InvalidInput {
+                panic!("Could not send SIGKILL to child: {err}")
+            }
+        }
+
+        self.0.wait().expect("Could not reap child");
+    }
+}
+
 /// Try to find the binary file, searching in whatever places make sense
 /// for our infrastructure.
 pub fn get_bin_path(filename: &str) -> PathBuf {
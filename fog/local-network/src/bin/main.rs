@@ -0,0 +1,52 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+#![deny(missing_docs)]
+
+//! Runs a local fog network until killed, printing its endpoints to stdout as
+//! JSON.
+
+use clap::Parser;
+use mc_common::logger::{create_app_logger, log, o};
+use mc_fog_local_network::{LocalFogNetwork, LocalFogNetworkConfig};
+use serde::Serialize;
+use std::{path::PathBuf, thread::sleep, time::Duration};
+
+/// The endpoints of the network, for scripts that drive it.
+#[derive(Serialize)]
+struct Endpoints {
+    chain_id: String,
+    consensus_uri: String,
+    fog_view_uri: String,
+    fog_ledger_uri: String,
+    fog_report_uri: String,
+    keys_dir: PathBuf,
+}
+
+fn main() {
+    let (logger, _global_logger_guard) = create_app_logger(o!());
+    mc_common::setup_panic_handler();
+    let config = LocalFogNetworkConfig::parse();
+    log::info!(
+        logger,
+        "Starting local fog network with config {:?}",
+        config
+    );
+
+    let network = LocalFogNetwork::new(config, logger);
+
+    let endpoints = Endpoints {
+        chain_id: network.chain_id().to_string(),
+        consensus_uri: network.consensus_uri().to_string(),
+        fog_view_uri: network.view_uri().to_string(),
+        fog_ledger_uri: network.ledger_uri().to_string(),
+        fog_report_uri: network.report_uri().to_string(),
+        keys_dir: network.keys_dir().to_path_buf(),
+    };
+    println!(
+        "{}",
+        serde_json::to_string(&endpoints).expect("Could not serialize endpoints")
+    );
+
+    loop {
+        sleep(Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Configuration parameters for the local fog network.
+
+use clap::Parser;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Configuration parameters for the local fog network.
+#[derive(Clone, Debug, Parser, Serialize)]
+#[clap(version)]
+pub struct LocalFogNetworkConfig {
+    /// The chain id of the network.
+    #[clap(long, default_value = "local", env = "MC_CHAIN_ID")]
+    pub chain_id: String,
+
+    /// Directory that holds the ledger, watcher and recovery databases, and
+    /// the sample account keyfiles.
+    ///
+    /// If omitted, a temporary directory is used and removed when the
+    /// network shuts down.
+    #[clap(long, env = "MC_WORK_DIR")]
+    pub work_dir: Option<PathBuf>,
+
+    /// Number of fog-enabled sample accounts to generate.
+    #[clap(long, default_value = "4", env = "MC_NUM_SAMPLE_ACCOUNTS")]
+    pub num_sample_accounts: usize,
+
+    /// Number of outputs each sample account receives in each block of the
+    /// sample ledger.
+    #[clap(long, default_value = "4", env = "MC_SAMPLE_OUTPUTS_PER_ACCOUNT")]
+    pub sample_outputs_per_account: usize,
+
+    /// Number of blocks in the sample ledger.
+    #[clap(long, default_value = "2", env = "MC_NUM_SAMPLE_BLOCKS")]
+    pub num_sample_blocks: usize,
+
+    /// Amount of picoMOB paid to each sample account, in a single output that
+    /// fog can find, once the network is up.
+    ///
+    /// The outputs of the sample ledger itself predate the ingress key, so
+    /// fog never finds them.
+    #[clap(
+        long,
+        default_value = "100000000000000",
+        env = "MC_SAMPLE_ACCOUNT_BALANCE"
+    )]
+    pub sample_account_balance: u64,
+
+    /// Seed for the sample accounts and the sample ledger (e.g.
+    /// 1234567812345678123456781234567812345678123456781234567812345678).
+    #[clap(long, value_parser = mc_util_parse::parse_hex::<[u8; 32]>, env = "MC_SEED")]
+    pub seed: Option<[u8; 32]>,
+
+    /// Capacity of the oblivious maps in the ingest, view and ledger enclaves.
+    #[clap(long, default_value = "65536", env = "MC_OMAP_CAPACITY")]
+    pub omap_capacity: u64,
+}
+
+impl Default for LocalFogNetworkConfig {
+    fn default() -> Self {
+        Self {
+            chain_id: "local".to_string(),
+            work_dir: None,
+            num_sample_accounts: 4,
+            sample_outputs_per_account: 4,
+            num_sample_blocks: 2,
+            sample_account_balance: 100_000_000_000_000,
+            seed: None,
+            omap_capacity: 65536,
+        }
+    }
+}
@@ -0,0 +1,442 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! A mock consensus node, which validates each transaction it is sent against
+//! the ledger and appends it in a block of its own.
+//!
+//! The node attests like a consensus enclave running in this process, so that
+//! `mc-connection` clients can submit transactions to it. There is no
+//! consensus between nodes, no minting and no fee output: the fees of
+//! submitted transactions are burned.
+
+use futures::executor::block_on;
+use grpcio::{
+    RpcContext, RpcStatus, RpcStatusCode, Server as GrpcioServer, ServerBuilder, UnarySink,
+};
+use mc_attest_api::{
+    attest::{AuthMessage, Message},
+    attest_grpc::{create_attested_api, AttestedApi},
+};
+use mc_attest_core::{DcapEvidence, EnclaveReportDataContents, Report, TargetInfo};
+use mc_attest_enclave_api::{ClientSession, EnclaveMessage};
+use mc_blockchain_types::{BlockData, BlockVersion};
+use mc_common::logger::{log, Logger};
+use mc_consensus_api::{
+    consensus_client::{ProposeMintConfigTxResponse, ProposeMintTxResponse},
+    consensus_client_grpc::{create_consensus_client_api, ConsensusClientApi},
+    consensus_common::{
+        BlocksRequest, BlocksResponse, LastBlockInfoResponse, ProposeTxResponse, ProposeTxResult,
+    },
+    consensus_common_grpc::{create_blockchain_api, BlockchainApi},
+    consensus_config::ConsensusNodeConfig,
+    empty::Empty,
+    external::{MintConfigTx, MintTx},
+};
+use mc_crypto_ake_enclave::{AkeEnclaveState, NullIdentity};
+use mc_ledger_db::{test_utils::add_txos_and_key_images_to_ledger, Ledger, LedgerDB};
+use mc_rand::McRng;
+use mc_sgx_report_cache_api::{ReportableEnclave, Result as ReportableEnclaveResult};
+use mc_sgx_report_cache_untrusted::ReportCacheThread;
+use mc_transaction_core::{
+    ring_signature::KeyImage,
+    tokens::Mob,
+    tx::{Tx, TxOut},
+    validation::{TransactionValidationError, TransactionValidationResult},
+    FeeMap, Token, TokenId,
+};
+use mc_util_grpc::{
+    check_request_chain_id, rpc_internal_error, rpc_invalid_arg_error, rpc_logger,
+    rpc_permissions_error, send_result, ConnectionUriGrpcioServer,
+};
+use mc_util_metrics::{IntGauge, OpMetrics};
+use mc_util_uri::{ConnectionUri, ConsensusClientUri};
+use mc_watcher::watcher_db::WatcherDB;
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+lazy_static::lazy_static! {
+    static ref OP_COUNTERS: OpMetrics = OpMetrics::new_and_registered("fog_local_network");
+
+    // Mock consensus attestation evidence timestamp, represented as seconds of UTC time since Unix epoch 1970-01-01T00:00:00Z.
+    static ref ENCLAVE_ATTESTATION_EVIDENCE_TIMESTAMP: IntGauge = OP_COUNTERS.gauge("consensus_enclave_attestation_evidence_timestamp");
+}
+
+/// The source url that blocks are recorded under in the watcher.
+const TX_SOURCE_URL: &str = "https://localhost";
+
+/// The url that blocks are recorded under in the watcher.
+pub fn tx_source_url() -> Url {
+    Url::from_str(TX_SOURCE_URL).expect("Could not parse tx source url")
+}
+
+/// Appends blocks to the ledger, and records them in the watcher so that fog
+/// can find their timestamps.
+pub struct BlockWriter {
+    ledger: LedgerDB,
+    watcher: WatcherDB,
+    rng: McRng,
+    logger: Logger,
+}
+
+impl BlockWriter {
+    /// Create a block writer for the given ledger and watcher.
+    pub fn new(ledger: LedgerDB, watcher: WatcherDB, logger: Logger) -> Self {
+        Self {
+            ledger,
+            watcher,
+            rng: McRng,
+            logger,
+        }
+    }
+
+    /// Append a block with the given outputs and key images.
+    ///
+    /// Nothing is validated beyond what the ledger db itself checks.
+    pub fn append_block(&mut self, outputs: Vec<TxOut>, key_images: Vec<KeyImage>) -> BlockData {
+        let block_data = add_txos_and_key_images_to_ledger(
+            &mut self.ledger,
+            BlockVersion::MAX,
+            outputs,
+            key_images,
+            &mut self.rng,
+        )
+        .expect("Could not append block");
+        add_block_to_watcher(&self.watcher, &block_data);
+        log::debug!(self.logger, "Appended block {}", block_data.block().index);
+        block_data
+    }
+
+    /// Validate a transaction against the ledger, like a consensus node
+    /// would, and append a block containing its outputs and key images.
+    pub fn append_tx(
+        &mut self,
+        tx: &Tx,
+        fee_map: &FeeMap,
+    ) -> TransactionValidationResult<BlockData> {
+        let num_blocks = self
+            .ledger
+            .num_blocks()
+            .map_err(|e| TransactionValidationError::Ledger(e.to_string()))?;
+
+        // The `key_images` must not have already been spent.
+        if tx
+            .key_images()
+            .iter()
+            .any(|key_image| self.ledger.contains_key_image(key_image).unwrap_or(true))
+        {
+            return Err(TransactionValidationError::ContainsSpentKeyImage);
+        }
+
+        // The `output_public_keys` must not appear in the ledger.
+        if tx.output_public_keys().iter().any(|public_key| {
+            self.ledger
+                .contains_tx_out_public_key(public_key)
+                .unwrap_or(true)
+        }) {
+            return Err(TransactionValidationError::ContainsExistingOutputPublicKey);
+        }
+
+        let proofs = self
+            .ledger
+            .get_tx_out_proof_of_memberships(&tx.get_membership_proof_highest_indices())
+            .map_err(|e| TransactionValidationError::Ledger(e.to_string()))?;
+        let minimum_fee = fee_map
+            .get_fee_for_token(&TokenId::from(tx.prefix.fee_token_id))
+            .ok_or(TransactionValidationError::TokenNotYetConfigured)?;
+        mc_transaction_core::validation::validate(
+            tx,
+            num_blocks,
+            BlockVersion::MAX,
+            &proofs,
+            minimum_fee,
+            &mut self.rng,
+        )?;
+
+        Ok(self.append_block(tx.prefix.outputs.clone(), tx.key_images()))
+    }
+}
+
+/// Record a block and its signature in the watcher, so that fog can find its
+/// timestamp.
+pub fn add_block_to_watcher(watcher: &WatcherDB, block_data: &BlockData) {
+    let tx_source_url = tx_source_url();
+    watcher
+        .add_block_data(&tx_source_url, block_data)
+        .expect("Could not add block data to watcher");
+    if let Some(signature) = block_data.signature() {
+        let block_index = block_data.block().index;
+        watcher
+            .add_block_signature(
+                &tx_source_url,
+                block_index,
+                signature.clone(),
+                format!("00/{block_index}"),
+            )
+            .expect("Could not add block signature to watcher");
+    }
+}
+
+/// A mock consensus node serving the client api.
+pub struct MockConsensus {
+    server: GrpcioServer,
+    _report_cache_thread: ReportCacheThread,
+}
+
+impl MockConsensus {
+    /// Start a mock consensus node on the given uri, which appends the
+    /// transactions it accepts with the given block writer.
+    pub fn start(
+        chain_id: String,
+        client_listen_uri: &ConsensusClientUri,
+        block_writer: Arc<Mutex<BlockWriter>>,
+        logger: Logger,
+    ) -> Self {
+        let enclave = MockConsensusEnclave::default();
+        enclave
+            .ake
+            .init(
+                Default::default(),
+                client_listen_uri
+                    .responder_id()
+                    .expect("Could not get consensus responder id"),
+            )
+            .expect("Could not initialize consensus AKE");
+        let report_cache_thread = ReportCacheThread::start(
+            enclave.clone(),
+            &ENCLAVE_ATTESTATION_EVIDENCE_TIMESTAMP,
+            logger.clone(),
+        )
+        .expect("Could not start consensus report cache thread");
+
+        let service = MockConsensusService {
+            chain_id,
+            enclave,
+            block_writer,
+            fee_map: FeeMap::default(),
+            logger: logger.clone(),
+        };
+
+        let env = Arc::new(
+            grpcio::EnvBuilder::new()
+                .name_prefix("MockConsensus-RPC".to_string())
+                .build(),
+        );
+        let mut server = ServerBuilder::new(env)
+            .register_service(create_attested_api(service.clone()))
+            .register_service(create_blockchain_api(service.clone()))
+            .register_service(create_consensus_client_api(service))
+            .build_using_uri(client_listen_uri, logger.clone())
+            .expect("Could not build mock consensus server");
+        server.start();
+        log::info!(
+            logger,
+            "Mock consensus listening on {}",
+            client_listen_uri.addr()
+        );
+
+        Self {
+            server,
+            _report_cache_thread: report_cache_thread,
+        }
+    }
+}
+
+impl Drop for MockConsensus {
+    fn drop(&mut self) {
+        block_on(self.server.shutdown()).expect("Could not stop grpc server");
+    }
+}
+
+/// The AKE state of the mock consensus node, which is attested like an
+/// enclave running in this process.
+#[derive(Clone, Default)]
+struct MockConsensusEnclave {
+    ake: Arc<AkeEnclaveState<NullIdentity>>,
+}
+
+impl ReportableEnclave for MockConsensusEnclave {
+    fn new_ereport(
+        &self,
+        qe_info: TargetInfo,
+    ) -> ReportableEnclaveResult<(Report, EnclaveReportDataContents)> {
+        Ok(self.ake.new_ereport(qe_info)?)
+    }
+
+    fn verify_attestation_evidence(
+        &self,
+        attestation_evidence: DcapEvidence,
+    ) -> ReportableEnclaveResult<()> {
+        Ok(self.ake.verify_attestation_evidence(attestation_evidence)?)
+    }
+
+    fn get_attestation_evidence(&self) -> ReportableEnclaveResult<DcapEvidence> {
+        Ok(self.ake.get_attestation_evidence()?)
+    }
+}
+
+#[derive(Clone)]
+struct MockConsensusService {
+    chain_id: String,
+    enclave: MockConsensusEnclave,
+    block_writer: Arc<Mutex<BlockWriter>>,
+    fee_map: FeeMap,
+    logger: Logger,
+}
+
+impl MockConsensusService {
+    fn propose_tx(&self, msg: Message, logger: &Logger) -> Result<ProposeTxResponse, RpcStatus> {
+        let tx_bytes = self
+            .enclave
+            .ake
+            .client_decrypt(EnclaveMessage::<ClientSession>::from(msg))
+            .map_err(|err| rpc_permissions_error("client_tx_propose", err, logger))?;
+        let tx: Tx = mc_util_serial::decode(&tx_bytes)
+            .map_err(|err| rpc_invalid_arg_error("client_tx_propose", err, logger))?;
+
+        let mut block_writer = self.block_writer.lock().expect("Mutex poisoned");
+        let mut response = ProposeTxResponse::new();
+        if let Err(err) = block_writer.append_tx(&tx, &self.fee_map) {
+            log::debug!(logger, "Rejected transaction: {}", err);
+            response.set_err_msg(err.to_string());
+            response.set_result(ProposeTxResult::from(err));
+        }
+        response.set_block_count(
+            block_writer
+                .ledger
+                .num_blocks()
+                .map_err(|err| rpc_internal_error("client_tx_propose", err, logger))?,
+        );
+        response.set_block_version(*BlockVersion::MAX);
+        Ok(response)
+    }
+
+    fn last_block_info(&self) -> Result<LastBlockInfoResponse, mc_ledger_db::Error> {
+        let num_blocks = self
+            .block_writer
+            .lock()
+            .expect("Mutex poisoned")
+            .ledger
+            .num_blocks()?;
+        let mut resp = LastBlockInfoResponse::new();
+        resp.set_index(num_blocks - 1);
+        resp.set_mob_minimum_fee(
+            self.fee_map
+                .get_fee_for_token(&Mob::ID)
+                .expect("should always have a fee for MOB"),
+        );
+        resp.set_minimum_fees(HashMap::from_iter(
+            self.fee_map
+                .iter()
+                .map(|(token_id, fee)| (**token_id, *fee)),
+        ));
+        resp.set_network_block_version(*BlockVersion::MAX);
+        Ok(resp)
+    }
+}
+
+fn unimplemented<T>(ctx: RpcContext, sink: UnarySink<T>, logger: &Logger) {
+    send_result(
+        ctx,
+        sink,
+        Err(RpcStatus::with_message(
+            RpcStatusCode::UNIMPLEMENTED,
+            "Not supported by the mock consensus node".into(),
+        )),
+        logger,
+    )
+}
+
+impl AttestedApi for MockConsensusService {
+    fn auth(&mut self, ctx: RpcContext, request: AuthMessage, sink: UnarySink<AuthMessage>) {
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            let result = self
+                .enclave
+                .ake
+                .client_accept(request.into())
+                .map(|(response, _session_id)| response.into())
+                .map_err(|err| rpc_permissions_error("client_auth", err, logger));
+            send_result(ctx, sink, result, logger);
+        });
+    }
+}
+
+impl BlockchainApi for MockConsensusService {
+    fn get_last_block_info(
+        &mut self,
+        ctx: RpcContext,
+        _request: Empty,
+        sink: UnarySink<LastBlockInfoResponse>,
+    ) {
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            let result = self
+                .last_block_info()
+                .map_err(|err| rpc_internal_error("get_last_block_info", err, logger));
+            send_result(ctx, sink, result, logger);
+        });
+    }
+
+    fn get_blocks(
+        &mut self,
+        ctx: RpcContext,
+        _request: BlocksRequest,
+        sink: UnarySink<BlocksResponse>,
+    ) {
+        unimplemented(ctx, sink, &self.logger)
+    }
+}
+
+impl ConsensusClientApi for MockConsensusService {
+    fn client_tx_propose(
+        &mut self,
+        ctx: RpcContext,
+        msg: Message,
+        sink: UnarySink<ProposeTxResponse>,
+    ) {
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            let result = self.propose_tx(msg, logger);
+            send_result(ctx, sink, result, logger);
+        });
+    }
+
+    fn propose_mint_config_tx(
+        &mut self,
+        ctx: RpcContext,
+        _request: MintConfigTx,
+        sink: UnarySink<ProposeMintConfigTxResponse>,
+    ) {
+        unimplemented(ctx, sink, &self.logger)
+    }
+
+    fn propose_mint_tx(
+        &mut self,
+        ctx: RpcContext,
+        _request: MintTx,
+        sink: UnarySink<ProposeMintTxResponse>,
+    ) {
+        unimplemented(ctx, sink, &self.logger)
+    }
+
+    fn get_node_config(
+        &mut self,
+        ctx: RpcContext,
+        _request: Empty,
+        sink: UnarySink<ConsensusNodeConfig>,
+    ) {
+        unimplemented(ctx, sink, &self.logger)
+    }
+}
@@ -0,0 +1,388 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! The fog enclaves, running in this process.
+//!
+//! The enclave implementation crates are built without SGX, so they use the
+//! mock reports and sealing of `mc-sgx-compat` and keep their oblivious maps
+//! on the heap. Their reports are quoted by the simulated quoting enclave, so
+//! the network must be built with `SGX_MODE=SW`. Every report has all-zero
+//! measurements, which is what [trusted_identity] trusts.
+
+use mc_attest_core::{
+    DcapEvidence, EnclaveReportDataContents, EvidenceKind, MrSigner, Report, TargetInfo,
+};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage, NonceAuthRequest,
+    NonceAuthResponse, NonceSession, PeerAuthRequest, PeerAuthResponse, PeerSession,
+    SealedClientMessage,
+};
+use mc_attestation_verifier::{TrustedIdentity, TrustedMrSignerIdentity};
+use mc_common::{logger::Logger, ResponderId};
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPublic, X25519Public};
+use mc_fog_ingest_enclave_api::{
+    IngestEnclave, IngestEnclaveInitParams, Result as IngestResult, SealedIngestKey,
+    SetIngressPrivateKeyResult,
+};
+use mc_fog_ingest_enclave_impl::SgxIngestEnclave;
+use mc_fog_kex_rng::KexRngPubkey;
+use mc_fog_ledger_enclave_api::{
+    GetOutputsResponse, KeyImageData, LedgerEnclave, OutputContext, Result as LedgerResult,
+    UntrustedKeyImageQueryResponse,
+};
+use mc_fog_ledger_enclave_impl::SgxLedgerEnclave;
+use mc_fog_types::{ingest::TxsForIngest, view::MultiViewStoreQueryResponse, ETxOutRecord};
+use mc_fog_view_enclave_api::{
+    Result as ViewResult, UntrustedQueryResponse, ViewEnclaveApi, ViewEnclaveInitParams,
+};
+use mc_fog_view_enclave_impl::ViewEnclave;
+use mc_oblivious_traits::HeapORAMStorageCreator;
+use mc_sgx_report_cache_api::{ReportableEnclave, Result as ReportableEnclaveResult};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// The ingest enclave, in this process.
+pub type InProcessIngestEnclave = InProcessEnclave<SgxIngestEnclave<HeapORAMStorageCreator>>;
+
+/// The view enclave, in this process.
+pub type InProcessViewEnclave = InProcessEnclave<ViewEnclave<HeapORAMStorageCreator>>;
+
+/// The ledger enclave, in this process.
+pub type InProcessLedgerEnclave = InProcessEnclave<SgxLedgerEnclave<HeapORAMStorageCreator>>;
+
+/// The identity that clients of the in-process enclaves must trust.
+pub fn trusted_identity() -> TrustedIdentity {
+    TrustedMrSignerIdentity::new(
+        MrSigner::from([0u8; 32]),
+        0,
+        0,
+        [] as [&str; 0],
+        [] as [&str; 0],
+    )
+    .into()
+}
+
+/// A handle to an enclave implementation running in this process, which the
+/// fog servers can share like a handle to an SGX enclave.
+pub struct InProcessEnclave<T>(Arc<T>);
+
+impl<T> Clone for InProcessEnclave<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl InProcessIngestEnclave {
+    /// Create and initialize an ingest enclave with a new ingress key.
+    pub fn new(responder_id: ResponderId, omap_capacity: u64, logger: Logger) -> Self {
+        let enclave = Self(Arc::new(SgxIngestEnclave::new(logger)));
+        IngestEnclave::enclave_init(
+            &enclave,
+            IngestEnclaveInitParams {
+                responder_id,
+                sealed_key: None,
+                desired_capacity: omap_capacity,
+            },
+        )
+        .expect("Could not initialize ingest enclave");
+        enclave
+    }
+}
+
+impl InProcessViewEnclave {
+    /// Create and initialize a view enclave.
+    pub fn new(client_responder_id: ResponderId, omap_capacity: u64, logger: Logger) -> Self {
+        let enclave = Self(Arc::new(ViewEnclave::new(logger)));
+        ViewEnclaveApi::init(
+            &enclave,
+            ViewEnclaveInitParams {
+                eid: 0,
+                self_client_id: client_responder_id,
+                desired_capacity: omap_capacity,
+            },
+        )
+        .expect("Could not initialize view enclave");
+        enclave
+    }
+}
+
+impl InProcessLedgerEnclave {
+    /// Create and initialize a ledger enclave.
+    pub fn new(client_responder_id: &ResponderId, omap_capacity: u64, logger: Logger) -> Self {
+        let enclave = Self(Arc::new(SgxLedgerEnclave::new(logger)));
+        LedgerEnclave::enclave_init(&enclave, client_responder_id, omap_capacity)
+            .expect("Could not initialize ledger enclave");
+        enclave
+    }
+}
+
+impl<T: ReportableEnclave> ReportableEnclave for InProcessEnclave<T> {
+    fn new_ereport(
+        &self,
+        qe_info: TargetInfo,
+    ) -> ReportableEnclaveResult<(Report, EnclaveReportDataContents)> {
+        self.0.new_ereport(qe_info)
+    }
+
+    fn verify_attestation_evidence(
+        &self,
+        attestation_evidence: DcapEvidence,
+    ) -> ReportableEnclaveResult<()> {
+        self.0.verify_attestation_evidence(attestation_evidence)
+    }
+
+    fn get_attestation_evidence(&self) -> ReportableEnclaveResult<DcapEvidence> {
+        self.0.get_attestation_evidence()
+    }
+}
+
+impl<T: IngestEnclave> IngestEnclave for InProcessEnclave<T> {
+    fn enclave_init(&self, params: IngestEnclaveInitParams) -> IngestResult<()> {
+        self.0.enclave_init(params)
+    }
+
+    fn new_keys(&self) -> IngestResult<()> {
+        self.0.new_keys()
+    }
+
+    fn new_egress_key(&self) -> IngestResult<()> {
+        self.0.new_egress_key()
+    }
+
+    fn get_ingress_pubkey(&self) -> IngestResult<RistrettoPublic> {
+        self.0.get_ingress_pubkey()
+    }
+
+    fn get_sealed_ingress_private_key(
+        &self,
+    ) -> IngestResult<(SealedIngestKey, CompressedRistrettoPublic)> {
+        self.0.get_sealed_ingress_private_key()
+    }
+
+    fn get_ingress_private_key(
+        &self,
+        peer: PeerSession,
+    ) -> IngestResult<(EnclaveMessage<PeerSession>, CompressedRistrettoPublic)> {
+        self.0.get_ingress_private_key(peer)
+    }
+
+    fn set_ingress_private_key(
+        &self,
+        msg: EnclaveMessage<PeerSession>,
+    ) -> IngestResult<SetIngressPrivateKeyResult> {
+        self.0.set_ingress_private_key(msg)
+    }
+
+    fn get_kex_rng_pubkey(&self) -> IngestResult<KexRngPubkey> {
+        self.0.get_kex_rng_pubkey()
+    }
+
+    fn ingest_txs(
+        &self,
+        chunk: TxsForIngest,
+    ) -> IngestResult<(Vec<ETxOutRecord>, Option<KexRngPubkey>)> {
+        self.0.ingest_txs(chunk)
+    }
+
+    fn get_identity(&self) -> IngestResult<X25519Public> {
+        self.0.get_identity()
+    }
+
+    fn peer_init(&self, peer_id: &ResponderId) -> IngestResult<PeerAuthRequest> {
+        self.0.peer_init(peer_id)
+    }
+
+    fn peer_accept(&self, req: PeerAuthRequest) -> IngestResult<(PeerAuthResponse, PeerSession)> {
+        self.0.peer_accept(req)
+    }
+
+    fn peer_connect(
+        &self,
+        peer_id: &ResponderId,
+        msg: PeerAuthResponse,
+    ) -> IngestResult<(PeerSession, EvidenceKind)> {
+        self.0.peer_connect(peer_id, msg)
+    }
+
+    fn peer_close(&self, session_id: &PeerSession) -> IngestResult<()> {
+        self.0.peer_close(session_id)
+    }
+}
+
+impl<T: ViewEnclaveApi> ViewEnclaveApi for InProcessEnclave<T> {
+    fn init(&self, params: ViewEnclaveInitParams) -> ViewResult<()> {
+        self.0.init(params)
+    }
+
+    fn get_identity(&self) -> ViewResult<X25519Public> {
+        self.0.get_identity()
+    }
+
+    fn client_accept(
+        &self,
+        req: ClientAuthRequest,
+    ) -> ViewResult<(ClientAuthResponse, ClientSession)> {
+        self.0.client_accept(req)
+    }
+
+    fn client_close(&self, channel_id: ClientSession) -> ViewResult<()> {
+        self.0.client_close(channel_id)
+    }
+
+    fn view_store_init(&self, view_store_id: ResponderId) -> ViewResult<NonceAuthRequest> {
+        self.0.view_store_init(view_store_id)
+    }
+
+    fn frontend_accept(
+        &self,
+        req: NonceAuthRequest,
+    ) -> ViewResult<(NonceAuthResponse, NonceSession)> {
+        self.0.frontend_accept(req)
+    }
+
+    fn view_store_connect(
+        &self,
+        view_store_id: ResponderId,
+        view_store_auth_response: NonceAuthResponse,
+    ) -> ViewResult<()> {
+        self.0
+            .view_store_connect(view_store_id, view_store_auth_response)
+    }
+
+    fn query(
+        &self,
+        payload: EnclaveMessage<ClientSession>,
+        untrusted_query_response: UntrustedQueryResponse,
+    ) -> ViewResult<Vec<u8>> {
+        self.0.query(payload, untrusted_query_response)
+    }
+
+    fn query_store(
+        &self,
+        payload: EnclaveMessage<NonceSession>,
+        untrusted_query_response: UntrustedQueryResponse,
+    ) -> ViewResult<EnclaveMessage<NonceSession>> {
+        self.0.query_store(payload, untrusted_query_response)
+    }
+
+    fn add_records(&self, records: Vec<ETxOutRecord>) -> ViewResult<()> {
+        self.0.add_records(records)
+    }
+
+    fn decrypt_and_seal_query(
+        &self,
+        client_query: EnclaveMessage<ClientSession>,
+    ) -> ViewResult<SealedClientMessage> {
+        self.0.decrypt_and_seal_query(client_query)
+    }
+
+    fn create_multi_view_store_query_data(
+        &self,
+        sealed_query: SealedClientMessage,
+    ) -> ViewResult<Vec<EnclaveMessage<NonceSession>>> {
+        self.0.create_multi_view_store_query_data(sealed_query)
+    }
+
+    fn collate_shard_query_responses(
+        &self,
+        sealed_query: SealedClientMessage,
+        shard_query_responses: Vec<MultiViewStoreQueryResponse>,
+    ) -> ViewResult<EnclaveMessage<ClientSession>> {
+        self.0
+            .collate_shard_query_responses(sealed_query, shard_query_responses)
+    }
+}
+
+impl<T: LedgerEnclave> LedgerEnclave for InProcessEnclave<T> {
+    fn enclave_init(&self, self_id: &ResponderId, desired_capacity: u64) -> LedgerResult<()> {
+        self.0.enclave_init(self_id, desired_capacity)
+    }
+
+    fn get_identity(&self) -> LedgerResult<X25519Public> {
+        self.0.get_identity()
+    }
+
+    fn client_accept(
+        &self,
+        req: ClientAuthRequest,
+    ) -> LedgerResult<(ClientAuthResponse, ClientSession)> {
+        self.0.client_accept(req)
+    }
+
+    fn client_close(&self, channel_id: ClientSession) -> LedgerResult<()> {
+        self.0.client_close(channel_id)
+    }
+
+    fn get_outputs(&self, msg: EnclaveMessage<ClientSession>) -> LedgerResult<OutputContext> {
+        self.0.get_outputs(msg)
+    }
+
+    fn get_outputs_data(
+        &self,
+        response: GetOutputsResponse,
+        client: ClientSession,
+    ) -> LedgerResult<EnclaveMessage<ClientSession>> {
+        self.0.get_outputs_data(response, client)
+    }
+
+    fn check_key_images(
+        &self,
+        msg: EnclaveMessage<ClientSession>,
+        response: UntrustedKeyImageQueryResponse,
+    ) -> LedgerResult<Vec<u8>> {
+        self.0.check_key_images(msg, response)
+    }
+
+    fn add_key_image_data(&self, records: Vec<KeyImageData>) -> LedgerResult<()> {
+        self.0.add_key_image_data(records)
+    }
+
+    fn ledger_store_init(&self, ledger_store_id: ResponderId) -> LedgerResult<NonceAuthRequest> {
+        self.0.ledger_store_init(ledger_store_id)
+    }
+
+    fn frontend_accept(
+        &self,
+        auth_request: NonceAuthRequest,
+    ) -> LedgerResult<(NonceAuthResponse, NonceSession)> {
+        self.0.frontend_accept(auth_request)
+    }
+
+    fn ledger_store_connect(
+        &self,
+        ledger_store_id: ResponderId,
+        ledger_store_auth_response: NonceAuthResponse,
+    ) -> LedgerResult<()> {
+        self.0
+            .ledger_store_connect(ledger_store_id, ledger_store_auth_response)
+    }
+
+    fn check_key_image_store(
+        &self,
+        msg: EnclaveMessage<NonceSession>,
+        response: UntrustedKeyImageQueryResponse,
+    ) -> LedgerResult<EnclaveMessage<NonceSession>> {
+        self.0.check_key_image_store(msg, response)
+    }
+
+    fn decrypt_and_seal_query(
+        &self,
+        client_query: EnclaveMessage<ClientSession>,
+    ) -> LedgerResult<SealedClientMessage> {
+        self.0.decrypt_and_seal_query(client_query)
+    }
+
+    fn create_multi_key_image_store_query_data(
+        &self,
+        sealed_query: SealedClientMessage,
+    ) -> LedgerResult<Vec<EnclaveMessage<NonceSession>>> {
+        self.0.create_multi_key_image_store_query_data(sealed_query)
+    }
+
+    fn collate_shard_query_responses(
+        &self,
+        sealed_query: SealedClientMessage,
+        shard_query_responses: BTreeMap<ResponderId, EnclaveMessage<NonceSession>>,
+    ) -> LedgerResult<EnclaveMessage<ClientSession>> {
+        self.0
+            .collate_shard_query_responses(sealed_query, shard_query_responses)
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! A complete fog stack running in a single process, for local development
+//! and downstream CI.
+//!
+//! `LocalFogNetwork` generates fog-enabled sample accounts and a sample
+//! ledger with `mc-util-generate-sample-ledger`, then starts an ingest
+//! server, a fog view router with one store, a fog ledger router with one key
+//! image store and a fog report server on free localhost ports. The recovery
+//! database is an embedded LMDB database, so no postgres is needed.
+//!
+//! The fog enclaves run in this process, without SGX: the enclave
+//! implementation crates are used directly, with their oblivious maps on the
+//! heap. Their reports are quoted by the simulated quoting enclave, so the
+//! network must be built with `SGX_MODE=SW`, and clients must trust the
+//! all-zero measurements of `LocalFogNetwork::trusted_identity`.
+//!
+//! There is no consensus network. A mock consensus node validates each
+//! transaction it is sent against the ledger and appends it in a block of its
+//! own, so `fog-sample-paykit` clients from `LocalFogNetwork::paykit_client`
+//! can send payments to each other. Blocks can also be appended directly with
+//! `LocalFogNetwork::append_block`, `LocalFogNetwork::pay` and
+//! `LocalFogNetwork::submit_tx`.
+
+#![deny(missing_docs)]
+
+mod config;
+mod consensus;
+mod enclaves;
+mod network;
+
+pub use crate::{config::LocalFogNetworkConfig, network::LocalFogNetwork};
+
+/// The report id used by the sample accounts and by ingest.
+pub const FOG_REPORT_ID: &str = "";
@@ -0,0 +1,686 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! The fog servers and a mock consensus node, running in this process.
+
+use crate::{
+    consensus::{add_block_to_watcher, tx_source_url, BlockWriter, MockConsensus},
+    enclaves::{self, InProcessIngestEnclave, InProcessLedgerEnclave, InProcessViewEnclave},
+    LocalFogNetworkConfig, FOG_REPORT_ID,
+};
+use grpcio::ChannelBuilder;
+use mc_account_keys::{AccountKey, PublicAddress, RootIdentity};
+use mc_attestation_verifier::TrustedIdentity;
+use mc_blockchain_types::{BlockData, BlockVersion};
+use mc_common::{
+    logger::{log, Logger},
+    time::SystemTimeProvider,
+};
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPrivate, RistrettoPublic};
+use mc_crypto_x509_utils::{X509CertificateChain, X509CertificateIterable};
+use mc_fog_api::view_grpc::FogViewStoreApiClient;
+use mc_fog_block_provider::LocalBlockProvider;
+use mc_fog_ingest_server::server::{IngestServer, IngestServerConfig};
+use mc_fog_ledger_server::{
+    sharding_strategy::EpochShardingStrategy as LedgerEpochShardingStrategy, KeyImageStoreServer,
+    LedgerRouterConfig, LedgerRouterServer, LedgerStoreConfig,
+    ShardingStrategy as LedgerShardingStrategy,
+};
+use mc_fog_lmdb_recovery_db::LmdbRecoveryDb;
+use mc_fog_recovery_db::AnyRecoveryDb;
+use mc_fog_report_server::{Materials, Server as ReportServer};
+use mc_fog_sample_paykit::{Client, ClientBuilder};
+use mc_fog_types::common::BlockRange;
+use mc_fog_uri::{
+    FogIngestUri, FogLedgerUri, FogViewStoreUri, FogViewUri, IngestPeerUri, KeyImageStoreUri,
+};
+use mc_fog_view_server::{
+    config::{
+        FogViewRouterConfig, MobileAcctViewConfig, RouterClientListenUri, ShardQueryConfig,
+        ShardingStrategy as ViewShardingStrategy,
+    },
+    fog_view_router_server::{FogViewRouterServer, Shard},
+    server::ViewServer,
+    sharding_strategy::EpochShardingStrategy as ViewEpochShardingStrategy,
+};
+use mc_ledger_db::{Ledger, LedgerDB};
+use mc_rand::{McRng, RngCore};
+use mc_transaction_core::{
+    fog_hint::FogHint,
+    ring_signature::KeyImage,
+    tokens::Mob,
+    tx::{Tx, TxOut},
+    Amount, Token,
+};
+use mc_util_from_random::FromRandom;
+use mc_util_grpc::ConnectionUriGrpcioChannel;
+use mc_util_uri::{AdminUri, ConnectionUri, ConsensusClientUri, FogUri};
+use mc_watcher::watcher_db::{create_or_open_rw_watcher_db, WatcherDB};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex, RwLock},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use tempfile::TempDir;
+use x509_signature::X509Certificate;
+
+/// How often the servers poll the ledger and the recovery database.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long [LocalFogNetwork::wait_for_sync] waits before giving up.
+const SYNC_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The seed used for the sample accounts and ledger when none is configured.
+const DEFAULT_SEED: [u8; 32] = [42u8; 32];
+
+/// A complete fog stack running in this process.
+///
+/// Dropping it shuts the servers down and, unless a work dir was configured,
+/// removes their databases.
+pub struct LocalFogNetwork {
+    // Fields are dropped in declaration order. Each router must shut down
+    // before the store it queries, and every server before the databases it
+    // polls.
+    _consensus: MockConsensus,
+    _view_router: FogViewRouterServer<InProcessViewEnclave>,
+    view_store: ViewServer<InProcessViewEnclave, AnyRecoveryDb, ViewEpochShardingStrategy>,
+    _ledger_router: LedgerRouterServer<InProcessLedgerEnclave>,
+    _key_image_store: KeyImageStoreServer<InProcessLedgerEnclave, LedgerEpochShardingStrategy>,
+    _report_server: ReportServer,
+    ingest_server: IngestServer<AnyRecoveryDb, InProcessIngestEnclave>,
+    block_writer: Arc<Mutex<BlockWriter>>,
+    ledger: LedgerDB,
+    chain_id: String,
+    consensus_uri: ConsensusClientUri,
+    view_uri: FogViewUri,
+    ledger_uri: FogLedgerUri,
+    report_uri: FogUri,
+    fog_authority_spki: Vec<u8>,
+    sample_accounts: Vec<AccountKey>,
+    keys_dir: PathBuf,
+    rng: McRng,
+    logger: Logger,
+    _temp_dir: Option<TempDir>,
+}
+
+impl LocalFogNetwork {
+    /// Start a fog network, fund the sample accounts, and wait until fog has
+    /// processed the whole ledger.
+    ///
+    /// The configured work dir, if any, must not contain a previous network.
+    pub fn new(config: LocalFogNetworkConfig, logger: Logger) -> Self {
+        let (work_dir, temp_dir) = match &config.work_dir {
+            Some(work_dir) => (work_dir.clone(), None),
+            None => {
+                let temp_dir = TempDir::new().expect("Could not create temporary work dir");
+                (temp_dir.path().to_path_buf(), Some(temp_dir))
+            }
+        };
+        let seed = config.seed.unwrap_or(DEFAULT_SEED);
+
+        // Every sample account, and every account made later, trusts the test
+        // vector chain that the report server signs reports with.
+        let (pem_chain, signing_keypair) = mc_crypto_x509_test_vectors::ok_rsa_chain_25519_leaf();
+        let fog_authority_spki = fog_authority_spki(&pem_chain);
+        let materials = Materials::from_pem_keypair(pem_chain, signing_keypair)
+            .expect("Could not load report signing materials");
+        let report_uri = FogUri::from_str(&format!("insecure-fog://{}/", free_address()))
+            .expect("Could not create report uri");
+
+        let keys_dir = work_dir.join("keys");
+        mc_util_keyfile::keygen::write_default_keyfiles(
+            &keys_dir,
+            config.num_sample_accounts,
+            Some(report_uri.to_string().as_str()),
+            FOG_REPORT_ID,
+            Some(fog_authority_spki.as_slice()),
+            seed,
+        )
+        .expect("Could not write sample account keyfiles");
+        let sample_accounts = mc_util_keyfile::keygen::read_default_keyfiles(&keys_dir)
+            .expect("Could not read sample account keyfiles");
+
+        let recipients = sample_accounts
+            .iter()
+            .map(AccountKey::default_subaddress)
+            .collect::<Vec<_>>();
+        let ledger = mc_util_generate_sample_ledger::bootstrap_ledger_db(
+            &work_dir.join("ledger"),
+            &recipients,
+            config.sample_outputs_per_account,
+            config.num_sample_blocks,
+            Some(seed),
+            0,
+            logger.clone(),
+        );
+
+        let watcher = create_or_open_rw_watcher_db(
+            &work_dir.join("watcher"),
+            &[tx_source_url()],
+            logger.clone(),
+        )
+        .expect("Could not create watcher db");
+        // The watcher does not accept data for the origin block.
+        for block_index in 1..ledger.num_blocks().expect("Could not get num blocks") {
+            let block_data = ledger
+                .get_block_data(block_index)
+                .expect("Could not get sample block data");
+            add_block_to_watcher(&watcher, &block_data);
+        }
+
+        let block_writer = Arc::new(Mutex::new(BlockWriter::new(
+            ledger.clone(),
+            watcher.clone(),
+            logger.clone(),
+        )));
+        let consensus_uri =
+            ConsensusClientUri::from_str(&format!("insecure-mc://{}/", free_address()))
+                .expect("Could not create consensus uri");
+        let consensus = MockConsensus::start(
+            config.chain_id.clone(),
+            &consensus_uri,
+            block_writer.clone(),
+            logger.clone(),
+        );
+
+        let recovery_db = AnyRecoveryDb::from(
+            LmdbRecoveryDb::open(work_dir.join("recovery_db"), logger.clone())
+                .expect("Could not open recovery db"),
+        );
+
+        let ingest_server = start_ingest_server(
+            config.omap_capacity,
+            &recovery_db,
+            &ledger,
+            &watcher,
+            logger.clone(),
+        );
+
+        let mut report_server = ReportServer::new(
+            recovery_db.clone(),
+            config.chain_id.clone(),
+            &report_uri,
+            materials,
+            logger.clone(),
+        );
+        report_server.start();
+
+        let (view_store, shard) = start_view_store(&config, &recovery_db, logger.clone());
+        let view_uri = FogViewUri::from_str(&format!("insecure-fog-view://{}/", free_address()))
+            .expect("Could not create fog view uri");
+        let view_router = start_view_router(&config, &view_uri, shard, logger.clone());
+
+        let (key_image_store, key_image_store_uri) =
+            start_key_image_store(&config, &ledger, &watcher, logger.clone());
+        let ledger_uri =
+            FogLedgerUri::from_str(&format!("insecure-fog-ledger://{}/", free_address()))
+                .expect("Could not create fog ledger uri");
+        let ledger_router = start_ledger_router(
+            &config,
+            &ledger_uri,
+            key_image_store_uri,
+            &ledger,
+            &watcher,
+            logger.clone(),
+        );
+
+        let mut network = Self {
+            _consensus: consensus,
+            _view_router: view_router,
+            view_store,
+            _ledger_router: ledger_router,
+            _key_image_store: key_image_store,
+            _report_server: report_server,
+            ingest_server,
+            block_writer,
+            ledger,
+            chain_id: config.chain_id,
+            consensus_uri,
+            view_uri,
+            ledger_uri,
+            report_uri,
+            fog_authority_spki,
+            sample_accounts,
+            keys_dir,
+            rng: McRng::default(),
+            logger,
+            _temp_dir: temp_dir,
+        };
+
+        // Fog only finds outputs whose hint is encrypted for the ingress key,
+        // which did not exist when the sample ledger was generated.
+        let payments = network
+            .sample_accounts
+            .iter()
+            .map(|account| {
+                (
+                    account.default_subaddress(),
+                    Amount::new(config.sample_account_balance, Mob::ID),
+                )
+            })
+            .collect::<Vec<_>>();
+        network.pay(&payments);
+        network.wait_for_sync();
+
+        log::info!(
+            network.logger,
+            "Local fog network is up: consensus {}, view {}, ledger {}, report {}",
+            network.consensus_uri,
+            network.view_uri,
+            network.ledger_uri,
+            network.report_uri
+        );
+
+        network
+    }
+
+    /// The chain id of the network.
+    pub fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
+
+    /// The uri of the mock consensus node.
+    pub fn consensus_uri(&self) -> &ConsensusClientUri {
+        &self.consensus_uri
+    }
+
+    /// The uri of the fog view router.
+    pub fn view_uri(&self) -> &FogViewUri {
+        &self.view_uri
+    }
+
+    /// The uri of the fog ledger router.
+    pub fn ledger_uri(&self) -> &FogLedgerUri {
+        &self.ledger_uri
+    }
+
+    /// The uri of the fog report server.
+    pub fn report_uri(&self) -> &FogUri {
+        &self.report_uri
+    }
+
+    /// The subjectPublicKeyInfo of the fog authority that signs reports.
+    pub fn fog_authority_spki(&self) -> &[u8] {
+        &self.fog_authority_spki
+    }
+
+    /// The identity that clients must trust to attest the consensus node and
+    /// the fog servers, whose enclaves run in this process.
+    pub fn trusted_identity(&self) -> TrustedIdentity {
+        enclaves::trusted_identity()
+    }
+
+    /// The funded sample accounts.
+    pub fn sample_accounts(&self) -> &[AccountKey] {
+        &self.sample_accounts
+    }
+
+    /// The directory holding the sample account keyfiles.
+    pub fn keys_dir(&self) -> &Path {
+        &self.keys_dir
+    }
+
+    /// The ledger that fog serves.
+    pub fn ledger(&self) -> &LedgerDB {
+        &self.ledger
+    }
+
+    /// The current ingress public key.
+    pub fn ingress_pubkey(&self) -> RistrettoPublic {
+        let summary = self.ingest_server.get_ingest_summary();
+        let pubkey: CompressedRistrettoPublic = summary
+            .get_ingress_pubkey()
+            .try_into()
+            .expect("Could not parse ingress pubkey");
+        RistrettoPublic::try_from(&pubkey).expect("Could not decompress ingress pubkey")
+    }
+
+    /// Make a new random account that uses this network for fog.
+    pub fn new_fog_account(&mut self) -> AccountKey {
+        let root_identity = RootIdentity::random_with_fog(
+            &mut self.rng,
+            &self.report_uri.to_string(),
+            FOG_REPORT_ID,
+            &self.fog_authority_spki,
+        );
+        AccountKey::from(&root_identity)
+    }
+
+    /// Append a block with the given outputs and key images to the ledger,
+    /// and record it in the watcher.
+    ///
+    /// Nothing is validated beyond what the ledger db itself checks, so the
+    /// outputs need not balance against any inputs. Every block after the
+    /// origin block needs at least one key image.
+    pub fn append_block(&mut self, outputs: Vec<TxOut>, key_images: Vec<KeyImage>) -> BlockData {
+        self.block_writer
+            .lock()
+            .expect("Mutex poisoned")
+            .append_block(outputs, key_images)
+    }
+
+    /// Append a block paying each recipient the given amount, in outputs that
+    /// fog can find.
+    ///
+    /// The block spends a random key image, so no account's balance goes
+    /// down.
+    pub fn pay(&mut self, payments: &[(PublicAddress, Amount)]) -> BlockData {
+        let ingress_pubkey = self.ingress_pubkey();
+        let outputs = payments
+            .iter()
+            .map(|(recipient, amount)| {
+                let e_fog_hint = FogHint::from(recipient).encrypt(&ingress_pubkey, &mut self.rng);
+                TxOut::new(
+                    BlockVersion::MAX,
+                    *amount,
+                    recipient,
+                    &RistrettoPrivate::from_random(&mut self.rng),
+                    e_fog_hint,
+                )
+                .expect("Could not create output")
+            })
+            .collect();
+        let key_images = vec![KeyImage::from(self.rng.next_u64())];
+        self.append_block(outputs, key_images)
+    }
+
+    /// Append a block containing the outputs and key images of a transaction.
+    ///
+    /// The transaction is not validated. Transactions sent to the consensus
+    /// node are validated before they are appended.
+    pub fn submit_tx(&mut self, tx: &Tx) -> BlockData {
+        self.append_block(tx.prefix.outputs.clone(), tx.key_images())
+    }
+
+    /// Wait until ingest and fog view have processed every block in the
+    /// ledger.
+    ///
+    /// The key image store polls the ledger on its own schedule, which is
+    /// not waited for. Neither are transactions still in flight to the
+    /// consensus node.
+    ///
+    /// Panics if that takes more than a minute.
+    pub fn wait_for_sync(&self) {
+        let num_blocks = self.ledger.num_blocks().expect("Could not get num blocks");
+        let start = Instant::now();
+        loop {
+            let ingest_count = self.ingest_server.get_ingest_summary().next_block_index;
+            let view_count = self.view_store.highest_processed_block_count();
+            if ingest_count >= num_blocks && view_count >= num_blocks {
+                break;
+            }
+            assert!(
+                start.elapsed() <= SYNC_TIMEOUT,
+                "Timed out waiting for fog to process {num_blocks} blocks; ingest processed {ingest_count}, view processed {view_count}",
+            );
+            sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Make a `fog-sample-paykit` client for the given account, which submits
+    /// transactions to the mock consensus node.
+    pub fn paykit_client(&self, account_key: AccountKey) -> Client {
+        ClientBuilder::new(
+            self.chain_id.clone(),
+            self.consensus_uri.clone(),
+            self.view_uri.clone(),
+            self.ledger_uri.clone(),
+            account_key,
+            self.logger.clone(),
+        )
+        .trusted_identity(Some(self.trusted_identity()))
+        .build()
+    }
+}
+
+/// Get the subjectPublicKeyInfo of the root of a PEM certificate chain.
+fn fog_authority_spki(pem_chain: &str) -> Vec<u8> {
+    pem::parse_many(pem_chain)
+        .expect("Could not parse PEM chain")
+        .iter_x509()
+        .collect::<Vec<X509Certificate>>()
+        .verified_root()
+        .expect("Could not verify PEM chain")
+        .subject_public_key_info()
+        .spki()
+        .to_vec()
+}
+
+/// A localhost address with a free port.
+fn free_address() -> String {
+    let port = portpicker::pick_unused_port().expect("pick_unused_port");
+    format!("127.0.0.1:{port}")
+}
+
+fn start_ingest_server(
+    omap_capacity: u64,
+    recovery_db: &AnyRecoveryDb,
+    ledger: &LedgerDB,
+    watcher: &WatcherDB,
+    logger: Logger,
+) -> IngestServer<AnyRecoveryDb, InProcessIngestEnclave> {
+    let client_listen_uri =
+        FogIngestUri::from_str(&format!("insecure-fog-ingest://{}/", free_address()))
+            .expect("Could not create ingest uri");
+    let peer_listen_uri = IngestPeerUri::from_str(&format!("insecure-igp://{}/", free_address()))
+        .expect("Could not create ingest peer uri");
+    let local_node_id = peer_listen_uri
+        .responder_id()
+        .expect("Could not get ingest responder id");
+
+    let config = IngestServerConfig {
+        local_node_id,
+        peer_listen_uri: peer_listen_uri.clone(),
+        peers: BTreeSet::from([peer_listen_uri]),
+        fog_report_id: FOG_REPORT_ID.to_string(),
+        client_listen_uri,
+        max_transactions: 10_000,
+        pubkey_expiry_window: 100,
+        peer_checkup_period: None,
+        watcher_timeout: Duration::from_secs(5),
+        state_file: None,
+        enclave_path: Default::default(),
+        omap_capacity,
+        poll_interval: POLL_INTERVAL,
+        block_batch_size: 1,
+    };
+
+    let enclave = InProcessIngestEnclave::new(
+        config.local_node_id.clone(),
+        config.omap_capacity,
+        logger.clone(),
+    );
+    let mut server = IngestServer::new_with_enclave(
+        config,
+        enclave,
+        recovery_db.clone(),
+        LocalBlockProvider::new(ledger.clone(), watcher.clone()),
+        logger,
+    );
+    server.start().expect("Could not start ingest server");
+    server.activate().expect("Could not activate ingest server");
+    server
+}
+
+fn start_view_store(
+    config: &LocalFogNetworkConfig,
+    recovery_db: &AnyRecoveryDb,
+    logger: Logger,
+) -> (
+    ViewServer<InProcessViewEnclave, AnyRecoveryDb, ViewEpochShardingStrategy>,
+    Shard,
+) {
+    let sharding_strategy = ViewEpochShardingStrategy::default();
+    let address = free_address();
+    let uri = FogViewStoreUri::from_str(&format!(
+        "insecure-fog-view-store://{address}?responder-id={address}&sharding_strategy={sharding_strategy}"
+    ))
+    .expect("Could not create fog view store uri");
+
+    let view_config = MobileAcctViewConfig {
+        chain_id: config.chain_id.clone(),
+        client_responder_id: uri
+            .responder_id()
+            .expect("Could not get fog view store responder id"),
+        client_listen_uri: uri.clone(),
+        client_auth_token_secret: None,
+        omap_capacity: config.omap_capacity,
+        admin_listen_uri: None,
+        client_auth_token_max_lifetime: Default::default(),
+        sharding_strategy: ViewShardingStrategy::Epoch(sharding_strategy.clone()),
+        postgres_config: Default::default(),
+        block_query_batch_size: 100,
+        db_polling_interval_ms: POLL_INTERVAL,
+    };
+    let enclave = InProcessViewEnclave::new(
+        view_config.client_responder_id.clone(),
+        view_config.omap_capacity,
+        logger.clone(),
+    );
+    let mut store = ViewServer::new(
+        view_config,
+        enclave,
+        recovery_db.clone(),
+        SystemTimeProvider,
+        sharding_strategy,
+        logger.clone(),
+    );
+    store.start();
+
+    let grpc_env = Arc::new(
+        grpcio::EnvBuilder::new()
+            .name_prefix("local-view-store".to_string())
+            .build(),
+    );
+    let store_client = FogViewStoreApiClient::new(
+        ChannelBuilder::default_channel_builder(grpc_env)
+            .keepalive_permit_without_calls(false)
+            .connect_to_uri(&uri, &logger),
+    );
+    let shard = Shard::new(uri, Arc::new(store_client), BlockRange::new(0, u64::MAX));
+
+    (store, shard)
+}
+
+fn start_view_router(
+    config: &LocalFogNetworkConfig,
+    view_uri: &FogViewUri,
+    shard: Shard,
+    logger: Logger,
+) -> FogViewRouterServer<InProcessViewEnclave> {
+    let router_config = FogViewRouterConfig {
+        chain_id: config.chain_id.clone(),
+        client_responder_id: view_uri
+            .responder_id()
+            .expect("Could not get fog view responder id"),
+        shard_uris: vec![shard.uri.clone()],
+        client_listen_uri: RouterClientListenUri::Unary(view_uri.clone()),
+        client_auth_token_max_lifetime: Default::default(),
+        client_auth_token_secret: None,
+        admin_listen_uri: free_admin_uri(),
+        shard_query_config: ShardQueryConfig {
+            shard_query_timeout: Duration::from_secs(2),
+            shard_hedge_delay: Duration::from_millis(100),
+        },
+    };
+    let enclave =
+        InProcessViewEnclave::new(router_config.client_responder_id.clone(), 0, logger.clone());
+    let mut router = FogViewRouterServer::new(
+        router_config,
+        enclave,
+        Arc::new(RwLock::new(vec![shard])),
+        SystemTimeProvider,
+        logger,
+    );
+    router.start();
+    router
+}
+
+fn start_key_image_store(
+    config: &LocalFogNetworkConfig,
+    ledger: &LedgerDB,
+    watcher: &WatcherDB,
+    logger: Logger,
+) -> (
+    KeyImageStoreServer<InProcessLedgerEnclave, LedgerEpochShardingStrategy>,
+    KeyImageStoreUri,
+) {
+    let uri = KeyImageStoreUri::from_str(&format!("insecure-key-image-store://{}", free_address()))
+        .expect("Could not create key image store uri");
+    let sharding_strategy = LedgerEpochShardingStrategy::default();
+
+    let store_config = LedgerStoreConfig {
+        chain_id: config.chain_id.clone(),
+        client_responder_id: uri
+            .responder_id()
+            .expect("Could not get key image store responder id"),
+        client_listen_uri: uri.clone(),
+        ledger_db: Some(Default::default()),
+        watcher_db: Some(Default::default()),
+        mobilecoind_uri: None,
+        admin_listen_uri: None,
+        client_auth_token_secret: None,
+        client_auth_token_max_lifetime: Default::default(),
+        omap_capacity: config.omap_capacity,
+        sharding_strategy: LedgerShardingStrategy::Epoch(sharding_strategy.clone()),
+        poll_interval: POLL_INTERVAL,
+    };
+    let enclave = InProcessLedgerEnclave::new(
+        &store_config.client_responder_id,
+        store_config.omap_capacity,
+        logger.clone(),
+    );
+    let mut store = KeyImageStoreServer::new_from_config(
+        store_config,
+        enclave,
+        LocalBlockProvider::new(ledger.clone(), watcher.clone()),
+        sharding_strategy,
+        SystemTimeProvider,
+        logger,
+    );
+    store.start();
+
+    (store, uri)
+}
+
+fn start_ledger_router(
+    config: &LocalFogNetworkConfig,
+    ledger_uri: &FogLedgerUri,
+    key_image_store_uri: KeyImageStoreUri,
+    ledger: &LedgerDB,
+    watcher: &WatcherDB,
+    logger: Logger,
+) -> LedgerRouterServer<InProcessLedgerEnclave> {
+    let router_config = LedgerRouterConfig {
+        chain_id: config.chain_id.clone(),
+        ledger_db: None,
+        watcher_db: None,
+        mobilecoind_uri: None,
+        shard_uris: vec![key_image_store_uri],
+        client_responder_id: ledger_uri
+            .responder_id()
+            .expect("Could not get fog ledger responder id"),
+        client_listen_uri: ledger_uri.clone(),
+        admin_listen_uri: free_admin_uri(),
+        client_auth_token_secret: None,
+        client_auth_token_max_lifetime: Default::default(),
+        query_retries: 3,
+    };
+    let enclave =
+        InProcessLedgerEnclave::new(&router_config.client_responder_id, 0, logger.clone());
+    let mut router = LedgerRouterServer::new(
+        router_config,
+        enclave,
+        LocalBlockProvider::new(ledger.clone(), watcher.clone()),
+        logger,
+    );
+    router.start();
+    router
+}
+
+fn free_admin_uri() -> AdminUri {
+    AdminUri::from_str(&format!("insecure-mca://{}/", free_address()))
+        .expect("Could not create admin uri")
+}
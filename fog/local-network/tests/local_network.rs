@@ -0,0 +1,119 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+// Starts a local fog network and checks that paykit clients find the outputs
+// paid to their accounts, and can pay each other through the mock consensus
+// node.
+
+use mc_common::logger::{test_with_logger, Logger};
+use mc_fog_local_network::{LocalFogNetwork, LocalFogNetworkConfig};
+use mc_fog_sample_paykit::Client;
+use mc_fog_types::BlockCount;
+use mc_ledger_db::Ledger;
+use mc_rand::McRng;
+use mc_transaction_core::{tokens::Mob, Amount, Token, TokenId};
+use std::{
+    collections::HashMap,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+/// Check the client's balance once it covers every block in the ledger.
+///
+/// The key image store isn't waited for by
+/// [LocalFogNetwork::wait_for_sync], so the client may need to poll a few
+/// times.
+fn check_balance(network: &LocalFogNetwork, client: &mut Client) -> HashMap<TokenId, u64> {
+    let num_blocks = BlockCount::from(network.ledger().num_blocks().unwrap());
+    let start = Instant::now();
+    loop {
+        let (balances, block_count) = client.check_balance().expect("Could not check balance");
+        if block_count >= num_blocks {
+            return balances;
+        }
+        assert!(
+            start.elapsed() <= Duration::from_secs(60),
+            "Timed out waiting for the balance to cover {num_blocks} blocks, it covers {block_count}"
+        );
+        sleep(Duration::from_millis(100));
+    }
+}
+
+#[test_with_logger]
+fn test_pay_and_check_balance(logger: Logger) {
+    let config = LocalFogNetworkConfig {
+        num_sample_accounts: 2,
+        ..Default::default()
+    };
+    let sample_account_balance = config.sample_account_balance;
+    let mut network = LocalFogNetwork::new(config, logger);
+
+    // The sample accounts are paid once the network is up.
+    let sample_account = network.sample_accounts()[0].clone();
+    let mut client = network.paykit_client(sample_account);
+    assert_eq!(
+        check_balance(&network, &mut client).get(&Mob::ID),
+        Some(&sample_account_balance)
+    );
+
+    let account = network.new_fog_account();
+    let amount = Amount::new(1_000_000_000, Mob::ID);
+    network.pay(&[(account.default_subaddress(), amount)]);
+    network.pay(&[(account.default_subaddress(), amount)]);
+    network.wait_for_sync();
+
+    let mut client = network.paykit_client(account);
+    assert_eq!(
+        check_balance(&network, &mut client).get(&Mob::ID),
+        Some(&(2 * amount.value))
+    );
+}
+
+#[test_with_logger]
+fn test_send_payment_between_clients(logger: Logger) {
+    let config = LocalFogNetworkConfig {
+        num_sample_accounts: 2,
+        ..Default::default()
+    };
+    let sample_account_balance = config.sample_account_balance;
+    let network = LocalFogNetwork::new(config, logger);
+
+    let sender_account = network.sample_accounts()[0].clone();
+    let recipient_account = network.sample_accounts()[1].clone();
+    let recipient_address = recipient_account.default_subaddress();
+    let mut sender = network.paykit_client(sender_account);
+    let mut recipient = network.paykit_client(recipient_account);
+    assert_eq!(
+        check_balance(&network, &mut sender).get(&Mob::ID),
+        Some(&sample_account_balance)
+    );
+    assert_eq!(
+        check_balance(&network, &mut recipient).get(&Mob::ID),
+        Some(&sample_account_balance)
+    );
+
+    let amount = Amount::new(1_000_000_000, Mob::ID);
+    let fee = sender
+        .get_minimum_fee(Mob::ID, true)
+        .expect("Could not get minimum fee")
+        .expect("No minimum fee for MOB");
+    let tx = sender
+        .build_transaction(amount, &recipient_address, &mut McRng, fee)
+        .expect("Could not build transaction");
+    sender
+        .send_transaction(&tx)
+        .expect("Could not send transaction");
+    network.wait_for_sync();
+
+    assert_eq!(
+        check_balance(&network, &mut sender).get(&Mob::ID),
+        Some(&(sample_account_balance - amount.value - fee))
+    );
+    assert_eq!(
+        check_balance(&network, &mut recipient).get(&Mob::ID),
+        Some(&(sample_account_balance + amount.value))
+    );
+
+    // The inputs are spent, so the consensus node rejects the transaction
+    // if it is sent again.
+    assert!(sender.send_transaction(&tx).is_err());
+}
@@ -0,0 +1,139 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! An in-memory log of the actions that Fog Overseer took against the Fog
+//! Ingest cluster, exposed through the `/audit_log` endpoint.
+//!
+//! Every entry is also logged, so that the history survives an Overseer
+//! restart in the service logs.
+
+use crate::error::OverseerError;
+use mc_common::logger::{log, Logger};
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_uri::FogIngestUri;
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// An action that Fog Overseer takes, or plans to take, against the Fog
+/// Ingest cluster.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub enum OverseerAction {
+    /// Set new ingress keys on an idle node.
+    SetNewKeys {
+        /// The index of the node in the list of monitored nodes.
+        #[serde(skip)]
+        node_index: usize,
+        /// The node.
+        node: FogIngestUri,
+    },
+
+    /// Activate an idle node.
+    Activate {
+        /// The index of the node in the list of monitored nodes.
+        #[serde(skip)]
+        node_index: usize,
+        /// The node.
+        node: FogIngestUri,
+    },
+
+    /// Retire the ingress key of an active node. The node keeps scanning
+    /// blocks with it until its pubkey expiry, and then becomes idle.
+    Retire {
+        /// The node.
+        node: FogIngestUri,
+        /// The ingress key being retired.
+        key: CompressedRistrettoPublic,
+    },
+
+    /// Report an ingress key as lost.
+    ReportLostKey {
+        /// The lost ingress key.
+        key: CompressedRistrettoPublic,
+    },
+
+    /// Disable Fog Overseer because the cluster needs manual intervention.
+    Disable,
+}
+
+/// Why Fog Overseer took an action.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum ActionTrigger {
+    /// There was no active node.
+    AutomaticFailover,
+    /// The active ingress key was due for rotation.
+    ScheduledKeyRotation,
+}
+
+/// An entry in the audit log.
+#[derive(Clone, Debug, Serialize)]
+pub struct AuditLogEntry {
+    /// When the action completed, in seconds since the unix epoch.
+    pub timestamp: u64,
+
+    /// Why the action was taken.
+    pub trigger: ActionTrigger,
+
+    /// The action.
+    pub action: OverseerAction,
+
+    /// The error message, if the action failed.
+    pub error: Option<String>,
+}
+
+/// A bounded log of actions, shared between the worker and the service.
+#[derive(Clone)]
+pub struct AuditLog {
+    entries: Arc<Mutex<VecDeque<AuditLogEntry>>>,
+    logger: Logger,
+}
+
+impl AuditLog {
+    /// The number of entries kept in memory. Older entries are dropped.
+    const CAPACITY: usize = 1000;
+
+    /// Create an empty audit log.
+    pub fn new(logger: Logger) -> Self {
+        Self {
+            entries: Default::default(),
+            logger,
+        }
+    }
+
+    /// Record the outcome of an action.
+    pub fn record(
+        &self,
+        trigger: ActionTrigger,
+        action: OverseerAction,
+        result: &Result<(), OverseerError>,
+    ) {
+        let entry = AuditLogEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            trigger,
+            action,
+            error: result.as_ref().err().map(ToString::to_string),
+        };
+        log::info!(self.logger, "Overseer audit log: {:?}", entry);
+
+        let mut entries = self.entries.lock().expect("mutex poisoned");
+        if entries.len() == Self::CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// The recorded entries, oldest first.
+    pub fn entries(&self) -> Vec<AuditLogEntry> {
+        self.entries
+            .lock()
+            .expect("mutex poisoned")
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
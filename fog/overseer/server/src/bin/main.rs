@@ -9,7 +9,7 @@ use mc_common::{
     sentry,
 };
 use mc_fog_overseer_server::{config::OverseerConfig, server, service::OverseerService};
-use mc_fog_sql_recovery_db::SqlRecoveryDb;
+use mc_fog_recovery_db::AnyRecoveryDb;
 use mc_util_cli::ParserWithBuildInfo;
 
 #[rocket::main]
@@ -23,7 +23,7 @@ async fn main() -> Result<(), rocket::Error> {
     // Open the database.
     let database_url =
         std::env::var("DATABASE_URL").expect("DATABASE_URL environment variable missing");
-    let recovery_db = SqlRecoveryDb::new_from_url(
+    let recovery_db = AnyRecoveryDb::new_from_url(
         &database_url,
         config.postgres_config.clone(),
         logger.clone(),
@@ -32,8 +32,12 @@ async fn main() -> Result<(), rocket::Error> {
         panic!("fog-overseer cannot connect to database '{database_url}': {err:?}")
     });
 
-    let mut overseer_service =
-        OverseerService::new(config.ingest_cluster_uris, recovery_db, logger.clone());
+    let mut overseer_service = OverseerService::new(
+        config.ingest_cluster_uris.clone(),
+        recovery_db,
+        config.key_rotation_policy(),
+        logger.clone(),
+    );
     overseer_service
         .start()
         .expect("OverseerService failed to start");
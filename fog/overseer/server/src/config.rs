@@ -3,10 +3,13 @@
 //! Configuration parameters for Fog Overseer.
 #![deny(missing_docs)]
 
+use crate::rotation::KeyRotationPolicy;
 use clap::Parser;
 use mc_fog_sql_recovery_db::SqlRecoveryDbConnectionConfig;
 use mc_fog_uri::FogIngestUri;
+use mc_util_parse::parse_duration_in_seconds;
 use serde::Serialize;
+use std::time::Duration;
 
 /// Parser configuration options for an Overseer Server
 #[derive(Clone, Serialize, Parser)]
@@ -29,11 +32,31 @@ pub struct OverseerConfig {
     #[clap(long, use_value_delimiter = true, env = "MC_INGEST_CLUSTER_URIS")]
     pub ingest_cluster_uris: Vec<FogIngestUri>,
 
+    /// Rotate the active ingress key once it has been used for this many
+    /// blocks. Rotation needs an idle node to move the key to.
+    #[clap(long, env = "MC_KEY_ROTATION_PERIOD_BLOCKS")]
+    pub key_rotation_period_blocks: Option<u64>,
+
+    /// Rotate the active ingress key once it has been used for this many
+    /// seconds, measured from the timestamp of the block it started at.
+    #[clap(long, value_parser = parse_duration_in_seconds, env = "MC_KEY_ROTATION_PERIOD")]
+    pub key_rotation_period: Option<Duration>,
+
     /// Postgres config
     #[clap(flatten)]
     pub postgres_config: SqlRecoveryDbConnectionConfig,
 }
 
+impl OverseerConfig {
+    /// The key rotation policy described by this config.
+    pub fn key_rotation_policy(&self) -> KeyRotationPolicy {
+        KeyRotationPolicy {
+            period_blocks: self.key_rotation_period_blocks,
+            period: self.key_rotation_period,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -55,5 +78,25 @@ mod tests {
 
         assert_eq!(config.ingest_cluster_uris[0].port(), 3226);
         assert_eq!(config.ingest_cluster_uris[1].port(), 3227);
+        assert!(!config.key_rotation_policy().is_enabled());
+    }
+
+    #[test]
+    fn key_rotation_config_example() {
+        let config = OverseerConfig::try_parse_from([
+            "/usr/bin/fog_overseer_server",
+            "--ingest-cluster-uris",
+            "insecure-fog-ingest://0.0.0.0:3226/",
+            "--key-rotation-period-blocks",
+            "100000",
+            "--key-rotation-period",
+            "604800",
+        ])
+        .expect("Could not parse command line arguments.");
+
+        let policy = config.key_rotation_policy();
+        assert!(policy.is_enabled());
+        assert_eq!(policy.period_blocks, Some(100000));
+        assert_eq!(policy.period, Some(Duration::from_secs(604800)));
     }
 }
@@ -3,6 +3,7 @@
 //! Errors that can occur during Fog Overseer operation.
 
 use displaydoc::Display;
+use mc_fog_recovery_db::Error as AnyRecoveryDbError;
 use mc_fog_recovery_db_iface::RecoveryDbError;
 use mc_fog_sql_recovery_db::Error as SqlRecoveryDbError;
 use retry::Error as RetryError;
@@ -25,6 +26,9 @@ pub enum OverseerError {
     /// Activating an idle node failed: {0}
     ActivateNode(String),
 
+    /// Retiring a key failed: {0}
+    RetireKey(String),
+
     /// Multiple inactive outstanding keys found: {0}
     MultipleInactiveOutstandingKeys(String),
 
@@ -38,6 +42,12 @@ impl From<SqlRecoveryDbError> for OverseerError {
     }
 }
 
+impl From<AnyRecoveryDbError> for OverseerError {
+    fn from(src: AnyRecoveryDbError) -> Self {
+        Self::RecoveryDb(Box::new(src))
+    }
+}
+
 impl From<RetryError<OverseerError>> for OverseerError {
     fn from(src: RetryError<OverseerError>) -> Self {
         src.error
@@ -5,9 +5,11 @@
 #![feature(proc_macro_hygiene, decl_macro)]
 #![deny(missing_docs)]
 
+pub mod audit_log;
 pub mod config;
 pub mod metrics;
 pub mod responses;
+pub mod rotation;
 pub mod server;
 pub mod service;
 
@@ -24,4 +24,7 @@ lazy_static::lazy_static! {
 
     /// Number of idle Fog Ingest nodes.
     pub static ref UNRESPONSIVE_NODE_COUNT: IntCounter = OP_COUNTERS.counter("unresponsive_node_count");
+
+    /// Number of completed scheduled ingress key rotations.
+    pub static ref KEY_ROTATION_COUNT: IntCounter = OP_COUNTERS.counter("key_rotation_count");
 }
@@ -14,6 +14,12 @@ pub fn increment_unresponsive_node_count(logger: &Logger) {
     counters::UNRESPONSIVE_NODE_COUNT.inc();
 }
 
+/// Increments the `key_rotation_count` metric.
+pub fn increment_key_rotation_count(logger: &Logger) {
+    log::trace!(logger, "Incrementing key rotation metric.");
+    counters::KEY_ROTATION_COUNT.inc();
+}
+
 /// Sets prometheus metrics.
 pub fn set_metrics(logger: &Logger, ingest_summaries: &[IngestSummary]) {
     log::trace!(logger, "Setting prometheus metrics.");
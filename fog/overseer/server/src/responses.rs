@@ -2,6 +2,10 @@
 
 //! Contains responses that are returned by Fog Overseer.
 
+use crate::{
+    audit_log::AuditLogEntry,
+    rotation::{KeyRotationPlan, KeyRotationPolicy},
+};
 use mc_fog_types::ingest_common::IngestSummary;
 use mc_fog_uri::FogIngestUri;
 use serde::Serialize;
@@ -17,3 +21,21 @@ pub struct GetIngestSummariesResponse {
     /// The ingest summaries.
     pub ingest_summaries: HashMap<FogIngestUri, Result<IngestSummary, String>>,
 }
+
+/// What Fog Overseer would do to rotate the ingress key right now, without
+/// doing it.
+#[derive(Serialize)]
+pub struct GetKeyRotationPlanResponse {
+    /// The configured key rotation policy.
+    pub policy: KeyRotationPolicy,
+
+    /// The plan. The worker only acts on it while Fog Overseer is enabled.
+    pub plan: KeyRotationPlan,
+}
+
+/// The actions Fog Overseer has taken against the Fog Ingest cluster.
+#[derive(Serialize)]
+pub struct GetAuditLogResponse {
+    /// The entries, oldest first.
+    pub entries: Vec<AuditLogEntry>,
+}
@@ -0,0 +1,437 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Plans scheduled rotation of the Fog Ingest cluster's ingress key.
+//!
+//! A rotation moves the cluster to a fresh ingress key in the same way an
+//! operator would by hand:
+//!   1.) Set new keys on an idle node.
+//!   2.) Activate that node, which starts publishing reports for the new key.
+//!   3.) Retire the old key.
+//!
+//! Retiring is graceful: the old node keeps scanning blocks with the old key
+//! until its pubkey expiry, so that transactions built against the last report
+//! it published are still found, and only then becomes idle. No new rotation
+//! is planned while a retired key is still being scanned.
+//!
+//! Planning only reads the cluster and the recovery db, so the same plan is
+//! served by the `/rotation_plan` endpoint without acting on it.
+
+use crate::{audit_log::OverseerAction, error::OverseerError};
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_api::ingest_common::{IngestControllerMode, IngestSummary};
+use mc_fog_recovery_db_iface::{IngressPublicKeyStatus, RecoveryDb};
+use mc_fog_uri::FogIngestUri;
+use serde::Serialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// When the active ingress key should be rotated. If both periods are set,
+/// the key is rotated when either of them is reached.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct KeyRotationPolicy {
+    /// Rotate the key once it has been used for this many blocks.
+    pub period_blocks: Option<u64>,
+
+    /// Rotate the key once it has been used for this long, measured from the
+    /// signature timestamp of its start block.
+    pub period: Option<Duration>,
+}
+
+impl KeyRotationPolicy {
+    /// Whether any scheduled rotation is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.period_blocks.is_some() || self.period.is_some()
+    }
+}
+
+/// What the planner knows about one Fog Ingest node.
+#[derive(Clone, Debug)]
+pub struct NodeKeyState {
+    /// The index of the node in the list of monitored nodes.
+    pub node_index: usize,
+
+    /// The node.
+    pub node: FogIngestUri,
+
+    /// Whether the node is active.
+    pub is_active: bool,
+
+    /// The node's ingress key, if it reported a valid one.
+    pub ingress_key: Option<CompressedRistrettoPublic>,
+
+    /// The recovery db status of the ingress key, only looked up for active
+    /// nodes.
+    pub key_status: Option<IngressPublicKeyStatus>,
+
+    /// The signature timestamp of the ingress key's start block, in seconds
+    /// since the unix epoch, if it is known.
+    pub key_start_timestamp: Option<u64>,
+}
+
+impl NodeKeyState {
+    /// Whether this node is still scanning with a key that was retired.
+    pub fn is_draining(&self) -> bool {
+        self.is_active
+            && self
+                .key_status
+                .as_ref()
+                .map(|status| status.retired)
+                .unwrap_or(false)
+    }
+}
+
+/// What Fog Overseer would do to rotate the ingress key, and why.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct KeyRotationPlan {
+    /// The node currently publishing reports, if there is exactly one.
+    pub active_node: Option<FogIngestUri>,
+
+    /// The ingress key of that node.
+    pub active_key: Option<CompressedRistrettoPublic>,
+
+    /// How many blocks the active key has been used for.
+    pub key_age_blocks: Option<u64>,
+
+    /// How many seconds the active key has been used for, if known.
+    pub key_age_seconds: Option<u64>,
+
+    /// Whether the active key is due for rotation.
+    pub is_due: bool,
+
+    /// A human readable explanation of the plan.
+    pub reason: String,
+
+    /// The actions to take, in order. Empty if nothing should be done.
+    pub actions: Vec<OverseerAction>,
+}
+
+/// Look up the recovery db state needed to plan a rotation for the given
+/// nodes.
+pub fn get_node_key_states<'a, DB: RecoveryDb>(
+    recovery_db: &DB,
+    ingest_summaries: impl IntoIterator<Item = (usize, &'a FogIngestUri, &'a IngestSummary)>,
+) -> Result<Vec<NodeKeyState>, OverseerError>
+where
+    OverseerError: From<DB::Error>,
+{
+    ingest_summaries
+        .into_iter()
+        .map(|(node_index, node, ingest_summary)| {
+            let is_active = ingest_summary.mode == IngestControllerMode::Active;
+            let ingress_key =
+                CompressedRistrettoPublic::try_from(ingest_summary.get_ingress_pubkey()).ok();
+            let key_status = match ingress_key.as_ref() {
+                Some(key) if is_active => recovery_db.get_ingress_key_status(key)?,
+                _ => None,
+            };
+            let key_start_timestamp = match key_status.as_ref() {
+                Some(status) => recovery_db
+                    .get_block_signature_timestamp_for_block(status.start_block)?
+                    // The origin block, and blocks whose timestamp the watcher
+                    // could not find, have this placeholder timestamp.
+                    .filter(|timestamp| *timestamp != u64::MAX),
+                None => None,
+            };
+            Ok(NodeKeyState {
+                node_index,
+                node: node.clone(),
+                is_active,
+                ingress_key,
+                key_status,
+                key_start_timestamp,
+            })
+        })
+        .collect()
+}
+
+/// Look up the state of the given nodes and plan a rotation at the current
+/// time.
+pub fn get_key_rotation_plan<'a, DB: RecoveryDb>(
+    policy: &KeyRotationPolicy,
+    recovery_db: &DB,
+    ingest_summaries: impl IntoIterator<Item = (usize, &'a FogIngestUri, &'a IngestSummary)>,
+) -> Result<KeyRotationPlan, OverseerError>
+where
+    OverseerError: From<DB::Error>,
+{
+    let nodes = get_node_key_states(recovery_db, ingest_summaries)?;
+    let highest_known_block_index = recovery_db.get_highest_known_block_index()?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    Ok(plan_key_rotation(
+        policy,
+        &nodes,
+        highest_known_block_index,
+        now,
+    ))
+}
+
+/// Decide whether the active key should be rotated, and how.
+///
+/// Arguments:
+/// * policy: The rotation policy
+/// * nodes: The state of every monitored node
+/// * highest_known_block_index: The highest block index in the recovery db
+/// * now: The current time, in seconds since the unix epoch
+pub fn plan_key_rotation(
+    policy: &KeyRotationPolicy,
+    nodes: &[NodeKeyState],
+    highest_known_block_index: Option<u64>,
+    now: u64,
+) -> KeyRotationPlan {
+    if !policy.is_enabled() {
+        return KeyRotationPlan {
+            reason: "Scheduled key rotation is disabled.".to_string(),
+            ..Default::default()
+        };
+    }
+
+    let current_nodes: Vec<&NodeKeyState> = nodes
+        .iter()
+        .filter(|node| node.is_active && !node.is_draining())
+        .collect();
+    if current_nodes.len() != 1 {
+        return KeyRotationPlan {
+            reason: format!(
+                "Expected exactly one active node with an unretired key, found {}. Rotation waits for the cluster to be healthy.",
+                current_nodes.len()
+            ),
+            ..Default::default()
+        };
+    }
+    let current = current_nodes[0];
+
+    let (key, status) = match (current.ingress_key, current.key_status.as_ref()) {
+        (Some(key), Some(status)) => (key, status),
+        _ => {
+            return KeyRotationPlan {
+                active_node: Some(current.node.clone()),
+                active_key: current.ingress_key,
+                reason: "The active key was not found in the recovery db.".to_string(),
+                ..Default::default()
+            }
+        }
+    };
+
+    let key_age_blocks = highest_known_block_index
+        .map(|index| (index + 1).saturating_sub(status.start_block))
+        .unwrap_or(0);
+    let key_age_seconds = current
+        .key_start_timestamp
+        .map(|timestamp| now.saturating_sub(timestamp));
+    let is_due = policy
+        .period_blocks
+        .map(|period_blocks| key_age_blocks >= period_blocks)
+        .unwrap_or(false)
+        || policy
+            .period
+            .zip(key_age_seconds)
+            .map(|(period, age)| age >= period.as_secs())
+            .unwrap_or(false);
+
+    let mut plan = KeyRotationPlan {
+        active_node: Some(current.node.clone()),
+        active_key: Some(key),
+        key_age_blocks: Some(key_age_blocks),
+        key_age_seconds,
+        is_due,
+        ..Default::default()
+    };
+
+    let draining: Vec<&FogIngestUri> = nodes
+        .iter()
+        .filter(|node| node.is_draining())
+        .map(|node| &node.node)
+        .collect();
+    if !draining.is_empty() {
+        plan.reason =
+            format!("Waiting for nodes with retired keys to finish scanning: {draining:?}");
+        return plan;
+    }
+
+    if !is_due {
+        plan.reason = "The active key is not due for rotation.".to_string();
+        return plan;
+    }
+
+    let idle = match nodes.iter().find(|node| !node.is_active) {
+        Some(idle) => idle,
+        None => {
+            plan.reason =
+                "The active key is due for rotation, but there is no idle node to take over."
+                    .to_string();
+            return plan;
+        }
+    };
+
+    plan.reason = format!(
+        "The active key is due for rotation, moving to node {}.",
+        idle.node
+    );
+    plan.actions = vec![
+        OverseerAction::SetNewKeys {
+            node_index: idle.node_index,
+            node: idle.node.clone(),
+        },
+        OverseerAction::Activate {
+            node_index: idle.node_index,
+            node: idle.node.clone(),
+        },
+        OverseerAction::Retire {
+            node: current.node.clone(),
+            key,
+        },
+    ];
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_util_from_random::FromRandom;
+    use rand_core::SeedableRng;
+    use rand_hc::Hc128Rng;
+
+    fn node(
+        rng: &mut Hc128Rng,
+        node_index: usize,
+        is_active: bool,
+        retired: bool,
+        start_block: u64,
+    ) -> NodeKeyState {
+        NodeKeyState {
+            node_index,
+            node: format!("insecure-fog-ingest://0.0.0.0:{}/", 3226 + node_index)
+                .parse()
+                .unwrap(),
+            is_active,
+            ingress_key: Some(CompressedRistrettoPublic::from_random(rng)),
+            key_status: is_active.then_some(IngressPublicKeyStatus {
+                start_block,
+                pubkey_expiry: start_block + 10,
+                retired,
+                lost: false,
+            }),
+            key_start_timestamp: Some(1000 + start_block),
+        }
+    }
+
+    fn policy(period_blocks: Option<u64>, period_secs: Option<u64>) -> KeyRotationPolicy {
+        KeyRotationPolicy {
+            period_blocks,
+            period: period_secs.map(Duration::from_secs),
+        }
+    }
+
+    #[test]
+    fn disabled_policy_does_nothing() {
+        let mut rng = Hc128Rng::from_seed([1u8; 32]);
+        let nodes = vec![
+            node(&mut rng, 0, true, false, 0),
+            node(&mut rng, 1, false, false, 0),
+        ];
+
+        let plan = plan_key_rotation(&Default::default(), &nodes, Some(1000), 1_000_000);
+        assert!(!plan.is_due);
+        assert!(plan.actions.is_empty());
+    }
+
+    #[test]
+    fn rotates_by_blocks() {
+        let mut rng = Hc128Rng::from_seed([2u8; 32]);
+        let nodes = vec![
+            node(&mut rng, 0, true, false, 10),
+            node(&mut rng, 1, false, false, 0),
+        ];
+        let policy = policy(Some(100), None);
+
+        let plan = plan_key_rotation(&policy, &nodes, Some(108), 0);
+        assert_eq!(plan.key_age_blocks, Some(99));
+        assert!(!plan.is_due);
+        assert!(plan.actions.is_empty());
+
+        let plan = plan_key_rotation(&policy, &nodes, Some(109), 0);
+        assert!(plan.is_due);
+        assert_eq!(
+            plan.actions,
+            vec![
+                OverseerAction::SetNewKeys {
+                    node_index: 1,
+                    node: nodes[1].node.clone(),
+                },
+                OverseerAction::Activate {
+                    node_index: 1,
+                    node: nodes[1].node.clone(),
+                },
+                OverseerAction::Retire {
+                    node: nodes[0].node.clone(),
+                    key: nodes[0].ingress_key.unwrap(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rotates_by_time() {
+        let mut rng = Hc128Rng::from_seed([3u8; 32]);
+        let nodes = vec![
+            node(&mut rng, 0, false, false, 0),
+            node(&mut rng, 1, true, false, 0),
+        ];
+        let policy = policy(None, Some(3600));
+
+        let plan = plan_key_rotation(&policy, &nodes, Some(5), 1000 + 3599);
+        assert!(!plan.is_due);
+
+        let plan = plan_key_rotation(&policy, &nodes, Some(5), 1000 + 3600);
+        assert!(plan.is_due);
+        assert_eq!(plan.actions.len(), 3);
+        assert_eq!(
+            plan.actions[0],
+            OverseerAction::SetNewKeys {
+                node_index: 0,
+                node: nodes[0].node.clone(),
+            }
+        );
+    }
+
+    #[test]
+    fn waits_for_retired_key_to_drain() {
+        let mut rng = Hc128Rng::from_seed([4u8; 32]);
+        let nodes = vec![
+            node(&mut rng, 0, true, true, 0),
+            node(&mut rng, 1, true, false, 0),
+            node(&mut rng, 2, false, false, 0),
+        ];
+
+        let plan = plan_key_rotation(&policy(Some(1), None), &nodes, Some(100), 0);
+        assert!(plan.is_due);
+        assert_eq!(plan.active_node, Some(nodes[1].node.clone()));
+        assert!(plan.actions.is_empty());
+    }
+
+    #[test]
+    fn needs_an_idle_node_and_a_healthy_cluster() {
+        let mut rng = Hc128Rng::from_seed([5u8; 32]);
+        let policy = policy(Some(1), None);
+
+        let nodes = vec![node(&mut rng, 0, true, false, 0)];
+        let plan = plan_key_rotation(&policy, &nodes, Some(100), 0);
+        assert!(plan.is_due);
+        assert!(plan.actions.is_empty());
+
+        let nodes = vec![
+            node(&mut rng, 0, true, false, 0),
+            node(&mut rng, 1, true, false, 0),
+        ];
+        let plan = plan_key_rotation(&policy, &nodes, Some(100), 0);
+        assert!(!plan.is_due);
+        assert!(plan.actions.is_empty());
+
+        let nodes = vec![node(&mut rng, 0, false, false, 0)];
+        let plan = plan_key_rotation(&policy, &nodes, Some(100), 0);
+        assert!(plan.active_node.is_none());
+        assert!(plan.actions.is_empty());
+    }
+}
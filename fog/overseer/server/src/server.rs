@@ -6,39 +6,57 @@
 //! HTTP Client -> *Overseer Rocket Server* -> OverseerService -> OverseerWorker
 
 use crate::{
-    error::OverseerError, responses::GetIngestSummariesResponse, service::OverseerService,
+    error::OverseerError,
+    responses::{GetAuditLogResponse, GetIngestSummariesResponse, GetKeyRotationPlanResponse},
+    service::OverseerService,
 };
+use mc_fog_recovery_db::AnyRecoveryDb;
 use mc_fog_recovery_db_iface::RecoveryDb;
-use mc_fog_sql_recovery_db::SqlRecoveryDb;
 use rocket::{get, post, routes, serde::json::Json};
 
 #[post("/enable")]
-fn enable(state: &rocket::State<OverseerState<SqlRecoveryDb>>) -> Result<String, String> {
+fn enable(state: &rocket::State<OverseerState<AnyRecoveryDb>>) -> Result<String, String> {
     state.overseer_service.enable()
 }
 
 #[post("/disable")]
-fn disable(state: &rocket::State<OverseerState<SqlRecoveryDb>>) -> Result<String, String> {
+fn disable(state: &rocket::State<OverseerState<AnyRecoveryDb>>) -> Result<String, String> {
     state.overseer_service.disable()
 }
 
 #[get("/status")]
-fn get_status(state: &rocket::State<OverseerState<SqlRecoveryDb>>) -> Result<String, String> {
+fn get_status(state: &rocket::State<OverseerState<AnyRecoveryDb>>) -> Result<String, String> {
     state.overseer_service.get_status()
 }
 
 #[get("/ingest_summaries")]
 fn get_ingest_summaries(
-    state: &rocket::State<OverseerState<SqlRecoveryDb>>,
+    state: &rocket::State<OverseerState<AnyRecoveryDb>>,
 ) -> Result<Json<GetIngestSummariesResponse>, String> {
     state.overseer_service.get_ingest_summaries().map(Json)
 }
 
+/// Shows what Fog Overseer would do to rotate the ingress key right now,
+/// without doing it.
+#[get("/rotation_plan")]
+fn get_key_rotation_plan(
+    state: &rocket::State<OverseerState<AnyRecoveryDb>>,
+) -> Result<Json<GetKeyRotationPlanResponse>, String> {
+    state.overseer_service.get_key_rotation_plan().map(Json)
+}
+
+#[get("/audit_log")]
+fn get_audit_log(
+    state: &rocket::State<OverseerState<AnyRecoveryDb>>,
+) -> Result<Json<GetAuditLogResponse>, String> {
+    state.overseer_service.get_audit_log().map(Json)
+}
+
 /// Produces metrics for Prometheus.
 ///
 /// Meant to be called only by the Prometheus pull mechanism.
 #[get("/metrics")]
-fn get_metrics(state: &rocket::State<OverseerState<SqlRecoveryDb>>) -> Result<String, String> {
+fn get_metrics(state: &rocket::State<OverseerState<AnyRecoveryDb>>) -> Result<String, String> {
     state.overseer_service.get_metrics()
 }
 
@@ -57,7 +75,7 @@ where
 #[must_use = "Use with a Client or call launch"]
 pub fn initialize_rocket_server<T: rocket::figment::Provider>(
     rocket_config: T,
-    state: OverseerState<SqlRecoveryDb>,
+    state: OverseerState<AnyRecoveryDb>,
 ) -> rocket::Rocket<rocket::Build> {
     rocket::custom(rocket_config).manage(state).mount(
         "/",
@@ -66,7 +84,9 @@ pub fn initialize_rocket_server<T: rocket::figment::Provider>(
             disable,
             get_status,
             get_metrics,
-            get_ingest_summaries
+            get_ingest_summaries,
+            get_key_rotation_plan,
+            get_audit_log
         ],
     )
 }
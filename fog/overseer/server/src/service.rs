@@ -7,8 +7,15 @@
 //!
 //! HTTP Client -> Overseer Rocket Server -> *OverseerService* -> OverseerWorker
 
-use crate::{error::OverseerError, responses::GetIngestSummariesResponse, worker::OverseerWorker};
+use crate::{
+    audit_log::AuditLog,
+    error::OverseerError,
+    responses::{GetAuditLogResponse, GetIngestSummariesResponse, GetKeyRotationPlanResponse},
+    rotation::{get_key_rotation_plan, KeyRotationPolicy},
+    worker::OverseerWorker,
+};
 use mc_common::logger::{log, Logger};
+use mc_fog_api::ingest_common::IngestSummary as ProtoIngestSummary;
 use mc_fog_ingest_client::FogIngestGrpcClient;
 use mc_fog_recovery_db_iface::RecoveryDb;
 use mc_fog_types::ingest_common::IngestSummary;
@@ -32,6 +39,8 @@ where
     logger: Logger,
     overseer_worker: Option<OverseerWorker>,
     recovery_db: DB,
+    key_rotation_policy: KeyRotationPolicy,
+    audit_log: AuditLog,
     is_enabled: Arc<AtomicBool>,
 }
 
@@ -42,8 +51,14 @@ where
     /// Retry failed GRPC requests every 10 seconds.
     const GRPC_RETRY_SECONDS: Duration = Duration::from_millis(10000);
 
-    /// Insantiate the service with the given URIs and DB.
-    pub fn new(ingest_cluster_uris: Vec<FogIngestUri>, recovery_db: DB, logger: Logger) -> Self {
+    /// Insantiate the service with the given URIs, DB and key rotation
+    /// policy.
+    pub fn new(
+        ingest_cluster_uris: Vec<FogIngestUri>,
+        recovery_db: DB,
+        key_rotation_policy: KeyRotationPolicy,
+        logger: Logger,
+    ) -> Self {
         let grpcio_env = Arc::new(grpcio::EnvBuilder::new().build());
         let ingest_clients: Vec<FogIngestGrpcClient> = ingest_cluster_uris
             .iter()
@@ -58,9 +73,11 @@ where
             .collect();
         Self {
             ingest_clients: Arc::new(ingest_clients),
+            audit_log: AuditLog::new(logger.clone()),
             logger,
             overseer_worker: None,
             recovery_db,
+            key_rotation_policy,
             is_enabled: Arc::new(AtomicBool::new(false)),
         }
     }
@@ -82,6 +99,8 @@ where
         self.overseer_worker = Some(OverseerWorker::new(
             self.ingest_clients.clone(),
             self.recovery_db.clone(),
+            self.key_rotation_policy.clone(),
+            self.audit_log.clone(),
             self.logger.clone(),
             self.is_enabled.clone(),
         ));
@@ -181,6 +200,48 @@ where
 
         Ok(GetIngestSummariesResponse { ingest_summaries })
     }
+
+    /// Plan a key rotation against the current state of the cluster, without
+    /// acting on it.
+    pub fn get_key_rotation_plan(&self) -> Result<GetKeyRotationPlanResponse, String> {
+        let ingest_summaries: Vec<(usize, &FogIngestUri, ProtoIngestSummary)> = self
+            .ingest_clients
+            .iter()
+            .enumerate()
+            .map(|(node_index, ingest_client)| {
+                let uri = ingest_client.get_uri();
+                ingest_client
+                    .get_status()
+                    .map(|ingest_summary| (node_index, uri, ingest_summary))
+                    .map_err(|err| {
+                        format!(
+                            "Unable to retrieve ingest summary for node with URI '{uri}': {err}"
+                        )
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+
+        let plan = get_key_rotation_plan(
+            &self.key_rotation_policy,
+            &self.recovery_db,
+            ingest_summaries
+                .iter()
+                .map(|(node_index, uri, ingest_summary)| (*node_index, *uri, ingest_summary)),
+        )
+        .map_err(|err| format!("Could not plan key rotation: {err}"))?;
+
+        Ok(GetKeyRotationPlanResponse {
+            policy: self.key_rotation_policy.clone(),
+            plan,
+        })
+    }
+
+    /// Get the actions taken against the cluster.
+    pub fn get_audit_log(&self) -> Result<GetAuditLogResponse, String> {
+        Ok(GetAuditLogResponse {
+            entries: self.audit_log.entries(),
+        })
+    }
 }
 
 impl<DB: RecoveryDb + Clone + Send + Sync + 'static> Drop for OverseerService<DB>
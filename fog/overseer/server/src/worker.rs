@@ -7,7 +7,12 @@
 //!
 //! HTTP Client -> Overseer Rocket Server -> OverseerService -> *OverseerWorker*
 
-use crate::{error::OverseerError, metrics};
+use crate::{
+    audit_log::{ActionTrigger, AuditLog, OverseerAction},
+    error::OverseerError,
+    metrics,
+    rotation::{get_key_rotation_plan, get_node_key_states, KeyRotationPolicy, NodeKeyState},
+};
 use mc_api::external;
 use mc_common::logger::{log, Logger};
 use mc_crypto_keys::CompressedRistrettoPublic;
@@ -33,6 +38,9 @@ use std::{
 /// there is no active key, then it promotes an idle node to active, and in the
 /// case where none of the idle nodes contain the previously active ingress key,
 /// it reports that key as lost.
+///
+/// If a key rotation policy is configured, it also rotates the active ingress
+/// key when it is due, see [crate::rotation].
 pub struct OverseerWorker {
     /// Join handle used to wait for the thread to terminate.
     join_handle: Option<JoinHandle<()>>,
@@ -45,6 +53,8 @@ impl OverseerWorker {
     pub fn new<DB: RecoveryDb + Clone + Send + Sync + 'static>(
         ingest_clients: Arc<Vec<FogIngestGrpcClient>>,
         recovery_db: DB,
+        key_rotation_policy: KeyRotationPolicy,
+        audit_log: AuditLog,
         logger: Logger,
         is_enabled: Arc<AtomicBool>,
     ) -> Self
@@ -61,6 +71,8 @@ impl OverseerWorker {
                     OverseerWorkerThread::start(
                         ingest_clients,
                         recovery_db,
+                        key_rotation_policy,
+                        audit_log,
                         thread_is_enabled,
                         thread_stop_requested,
                         HashSet::new(),
@@ -103,6 +115,12 @@ struct OverseerWorkerThread<DB: RecoveryDb> {
     /// cluster's ingress keys.
     recovery_db: DB,
 
+    /// When to rotate the active ingress key.
+    key_rotation_policy: KeyRotationPolicy,
+
+    /// Where the actions taken against the cluster are recorded.
+    audit_log: AuditLog,
+
     /// If this is true, the worker will not perform it's monitoring logic.
     is_enabled: Arc<AtomicBool>,
 
@@ -114,6 +132,11 @@ struct OverseerWorkerThread<DB: RecoveryDb> {
     /// This helps us debug when a node starts responding again.
     unresponsive_node_urls: HashSet<FogIngestUri>,
 
+    /// The retire step of a scheduled key rotation which failed after the new
+    /// key was activated. Until it succeeds, two nodes publish reports, so it
+    /// is retried on every poll.
+    pending_retire: Option<OverseerAction>,
+
     logger: Logger,
 }
 
@@ -140,6 +163,8 @@ where
     pub fn start(
         ingest_clients: Arc<Vec<FogIngestGrpcClient>>,
         recovery_db: DB,
+        key_rotation_policy: KeyRotationPolicy,
+        audit_log: AuditLog,
         is_enabled: Arc<AtomicBool>,
         stop_requested: Arc<AtomicBool>,
         unresponsive_node_urls: HashSet<FogIngestUri>,
@@ -148,9 +173,12 @@ where
         let thread = Self {
             ingest_clients,
             recovery_db,
+            key_rotation_policy,
+            audit_log,
             is_enabled,
             stop_requested,
             unresponsive_node_urls,
+            pending_retire: None,
             logger,
         };
         thread.run();
@@ -221,8 +249,28 @@ where
                         .ingest_summary
                         .get_ingress_pubkey()
                     );
+                    self.pending_retire = None;
+                    if self.key_rotation_policy.is_enabled() {
+                        if let Err(err) =
+                            self.perform_scheduled_key_rotation(&ingest_summary_node_mappings)
+                        {
+                            log::error!(self.logger, "Scheduled key rotation failed: {}", err);
+                        }
+                    }
+                    continue;
+                }
+                _ if self.is_draining_retired_keys(&ingest_summary_node_mappings) => {
+                    self.pending_retire = None;
+                    log::trace!(
+                        self.logger,
+                        "There are {} active nodes in the Fog Ingest cluster, all but one are scanning with retired keys until they expire.",
+                        active_node_count
+                    );
                     continue;
                 }
+                _ if self.pending_retire.is_some() => {
+                    self.retry_pending_retire();
+                }
                 _ => {
                     let active_node_ingress_pubkeys: Vec<&external::CompressedRistretto> =
                         active_ingest_summary_node_mappings
@@ -322,7 +370,10 @@ where
             0 => {
                 log::info!(self.logger, "Found 0 outstanding keys.");
                 let activated_node_index = self.set_new_key_on_a_node()?;
-                self.activate_a_node(activated_node_index)?;
+                self.take_action(
+                    ActionTrigger::AutomaticFailover,
+                    self.activate_action(activated_node_index),
+                )?;
                 Ok(())
             }
             1 => {
@@ -335,7 +386,7 @@ where
                 Ok(())
             }
             _ => {
-                self.is_enabled.store(false, Ordering::SeqCst);
+                self.take_action(ActionTrigger::AutomaticFailover, OverseerAction::Disable)?;
                 let error_message = format!("This is unexpected and requires manual intervention. As such, we've disabled overseer. Take the appropriate action and then re-enable overseer by calling the /enable endpoint. Inactive oustanding keys: {inactive_outstanding_keys:?}");
                 Err(OverseerError::MultipleInactiveOutstandingKeys(
                     error_message,
@@ -408,14 +459,14 @@ where
             };
             if inactive_outstanding_key.eq(&node_ingress_key) {
                 let node = &self.ingest_clients[ingest_summary_node_mapping.node_index];
-                match node.activate() {
+                let result = match node.activate() {
                     Ok(_) => {
                         log::info!(
                             self.logger,
                             "Successfully activated node {}.",
                             node.get_uri()
                         );
-                        return Ok(());
+                        Ok(())
                     }
                     Err(err) => {
                         let error_message = format!(
@@ -423,9 +474,15 @@ where
                             node.get_uri(),
                             err
                         );
-                        return Err(OverseerError::ActivateNode(error_message));
+                        Err(OverseerError::ActivateNode(error_message))
                     }
-                }
+                };
+                self.audit_log.record(
+                    ActionTrigger::AutomaticFailover,
+                    self.activate_action(ingest_summary_node_mapping.node_index),
+                    &result,
+                );
+                return result;
             }
         }
 
@@ -438,9 +495,17 @@ where
             "Could not find a node that has the inactive outstanding key: {:?}",
             &inactive_outstanding_key
         );
-        self.report_lost_ingress_key(inactive_outstanding_key)?;
+        self.take_action(
+            ActionTrigger::AutomaticFailover,
+            OverseerAction::ReportLostKey {
+                key: inactive_outstanding_key,
+            },
+        )?;
         let activated_node_index = self.set_new_key_on_a_node()?;
-        self.activate_a_node(activated_node_index)?;
+        self.take_action(
+            ActionTrigger::AutomaticFailover,
+            self.activate_action(activated_node_index),
+        )?;
 
         Ok(())
     }
@@ -475,34 +540,18 @@ where
         Ok(result?)
     }
 
-    /// Tries to set a new ingress key on a node. The node is assumed to be
-    /// idle.
+    /// Tries to set a new ingress key on any node. The nodes are assumed to
+    /// be idle.
     fn set_new_key_on_a_node(&self) -> Result<usize, OverseerError> {
         for (i, ingest_client) in self.ingest_clients.iter().enumerate() {
-            let result = retry_with_index(
-                Fixed::from_millis(200).take(Self::NUMBER_OF_TRIES),
-                |current_try| {
-                    match ingest_client.new_keys() {
-                        Ok(_) => {
-                            log::info!(
-                                self.logger,
-                                "New keys successfully set on the ingest node {}.",
-                                ingest_client.get_uri()
-                            );
-                            OperationResult::Ok(())
-                        }
-                        // TODO: We'll need to alert Ops to take manual action at this point.
-                        Err(err) => {
-                            let number_of_remaining_tries =
-                                Self::NUMBER_OF_TRIES - current_try as usize;
-                            let error_message = format!("Did not succeed in setting a new key on ingest node {}. Will try {} more times. Underlying error: {}", ingest_client.get_uri(), number_of_remaining_tries, err);
-                            OperationResult::Retry(OverseerError::SetNewKey(error_message))
-                        }
-                    }
-                },
-            );
-
-            if result.is_ok() {
+            let action = OverseerAction::SetNewKeys {
+                node_index: i,
+                node: ingest_client.get_uri().clone(),
+            };
+            if self
+                .take_action(ActionTrigger::AutomaticFailover, action)
+                .is_ok()
+            {
                 return Ok(i);
             }
         }
@@ -512,6 +561,36 @@ where
         ))
     }
 
+    /// Tries to set a new ingress key on a node. The node is assumed to be
+    /// idle.
+    fn set_new_key_on_node(&self, node_index: usize) -> Result<(), OverseerError> {
+        let ingest_client = &self.ingest_clients[node_index];
+        let result = retry_with_index(
+            Fixed::from_millis(200).take(Self::NUMBER_OF_TRIES),
+            |current_try| {
+                match ingest_client.new_keys() {
+                    Ok(_) => {
+                        log::info!(
+                            self.logger,
+                            "New keys successfully set on the ingest node {}.",
+                            ingest_client.get_uri()
+                        );
+                        OperationResult::Ok(())
+                    }
+                    // TODO: We'll need to alert Ops to take manual action at this point.
+                    Err(err) => {
+                        let number_of_remaining_tries =
+                            Self::NUMBER_OF_TRIES - current_try as usize;
+                        let error_message = format!("Did not succeed in setting a new key on ingest node {}. Will try {} more times. Underlying error: {}", ingest_client.get_uri(), number_of_remaining_tries, err);
+                        OperationResult::Retry(OverseerError::SetNewKey(error_message))
+                    }
+                }
+            },
+        );
+
+        Ok(result?)
+    }
+
     /// Tries to activate a node. The node is assumed to be idle.
     fn activate_a_node(&self, activated_node_index: usize) -> Result<(), OverseerError> {
         let result = retry_with_index(
@@ -545,4 +624,159 @@ where
 
         Ok(result?)
     }
+    /// Tries to mark an ingress key as retired. The node using it keeps
+    /// scanning blocks until the key's pubkey expiry, and then becomes idle.
+    fn retire_ingress_key(&self, key: CompressedRistrettoPublic) -> Result<(), OverseerError> {
+        let result = retry_with_index(
+            Fixed::from_millis(200).take(Self::NUMBER_OF_TRIES),
+            |current_try| match self.recovery_db.retire_ingress_key(&key, true) {
+                Ok(_) => {
+                    log::info!(
+                        self.logger,
+                        "The following key was successfully retired: {}",
+                        key
+                    );
+                    OperationResult::Ok(())
+                }
+                Err(err) => {
+                    let number_of_remaining_tries = Self::NUMBER_OF_TRIES - current_try as usize;
+                    let error_message = format!("The following key was not successfully retired: {key}. Will try {number_of_remaining_tries} more times. Underlying error: {err}");
+                    OperationResult::Retry(OverseerError::RetireKey(error_message))
+                }
+            },
+        );
+
+        Ok(result?)
+    }
+
+    /// The action of activating the given node.
+    fn activate_action(&self, node_index: usize) -> OverseerAction {
+        OverseerAction::Activate {
+            node_index,
+            node: self.ingest_clients[node_index].get_uri().clone(),
+        }
+    }
+
+    /// Performs an action against the cluster and records it in the audit log.
+    fn take_action(
+        &self,
+        trigger: ActionTrigger,
+        action: OverseerAction,
+    ) -> Result<(), OverseerError> {
+        let result = match &action {
+            OverseerAction::SetNewKeys { node_index, .. } => self.set_new_key_on_node(*node_index),
+            OverseerAction::Activate { node_index, .. } => self.activate_a_node(*node_index),
+            OverseerAction::Retire { key, .. } => self.retire_ingress_key(*key),
+            OverseerAction::ReportLostKey { key } => self.report_lost_ingress_key(*key),
+            OverseerAction::Disable => {
+                self.is_enabled.store(false, Ordering::SeqCst);
+                Ok(())
+            }
+        };
+        self.audit_log.record(trigger, action, &result);
+        result
+    }
+
+    /// Looks up the recovery db state of each node's ingress key.
+    fn get_node_key_states(
+        &self,
+        ingest_summary_node_mappings: &[IngestSummaryNodeMapping],
+    ) -> Result<Vec<NodeKeyState>, OverseerError> {
+        get_node_key_states(
+            &self.recovery_db,
+            ingest_summary_node_mappings.iter().map(|mapping| {
+                (
+                    mapping.node_index,
+                    self.ingest_clients[mapping.node_index].get_uri(),
+                    &mapping.ingest_summary,
+                )
+            }),
+        )
+    }
+
+    /// Returns true if exactly one active node has an unretired key, and the
+    /// other active nodes are still scanning with retired keys. This is the
+    /// expected state of the cluster right after a key rotation.
+    fn is_draining_retired_keys(
+        &self,
+        ingest_summary_node_mappings: &[IngestSummaryNodeMapping],
+    ) -> bool {
+        match self.get_node_key_states(ingest_summary_node_mappings) {
+            Ok(nodes) => {
+                nodes
+                    .iter()
+                    .filter(|node| node.is_active && !node.is_draining())
+                    .count()
+                    == 1
+            }
+            Err(err) => {
+                log::error!(
+                    self.logger,
+                    "Could not look up the ingress keys of the active nodes: {}",
+                    err
+                );
+                false
+            }
+        }
+    }
+
+    /// Rotates the active ingress key if the key rotation policy says it is
+    /// due. See [crate::rotation] for how the plan is made.
+    fn perform_scheduled_key_rotation(
+        &mut self,
+        ingest_summary_node_mappings: &[IngestSummaryNodeMapping],
+    ) -> Result<(), OverseerError> {
+        let plan = get_key_rotation_plan(
+            &self.key_rotation_policy,
+            &self.recovery_db,
+            ingest_summary_node_mappings.iter().map(|mapping| {
+                (
+                    mapping.node_index,
+                    self.ingest_clients[mapping.node_index].get_uri(),
+                    &mapping.ingest_summary,
+                )
+            }),
+        )?;
+        if plan.actions.is_empty() {
+            log::trace!(self.logger, "No key rotation: {}", plan.reason);
+            return Ok(());
+        }
+
+        log::info!(self.logger, "Starting key rotation: {}", plan.reason);
+        // The actions are taken in order, and we stop at the first failure, so
+        // that the old key is only retired once the new key is active. Once it
+        // is, a failed retire is remembered and retried on the next polls.
+        for action in plan.actions {
+            let retire = matches!(action, OverseerAction::Retire { .. }).then(|| action.clone());
+            if let Err(err) = self.take_action(ActionTrigger::ScheduledKeyRotation, action) {
+                self.pending_retire = retire;
+                return Err(err);
+            }
+        }
+        metrics::increment_key_rotation_count(&self.logger);
+        log::info!(self.logger, "Key rotation completed successfully.");
+
+        Ok(())
+    }
+
+    /// Retries the retire step of a scheduled key rotation, which failed
+    /// after the new key was activated.
+    fn retry_pending_retire(&mut self) {
+        let Some(retire) = self.pending_retire.clone() else {
+            return;
+        };
+        log::info!(
+            self.logger,
+            "Retrying the retire step of the last key rotation: {:?}",
+            retire
+        );
+        match self.take_action(ActionTrigger::ScheduledKeyRotation, retire) {
+            Ok(()) => {
+                self.pending_retire = None;
+                metrics::increment_key_rotation_count(&self.logger);
+                log::info!(self.logger, "Key rotation completed successfully.");
+            }
+            Err(err) => log::error!(self.logger, "Retiring the rotated key failed: {}", err),
+        }
+    }
 }
@@ -0,0 +1,72 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+mod utils;
+
+use mc_common::logger::{test_with_logger, Logger};
+use mc_fog_ingest_server_test_utils::{get_ingress_keys, IngestServerTestHelper};
+use mc_fog_overseer_server::rotation::KeyRotationPolicy;
+use utils::TestHelperExt;
+
+const BASE_PORT: u16 = 8900;
+
+// In this scenario, the Fog Ingest cluster has one active node whose key is
+// older than the key rotation period.
+//
+// Fog Overseer should set new keys on an idle node, activate it, and retire
+// the old key, which the old node keeps scanning until it expires.
+#[test_with_logger]
+fn active_key_past_rotation_period_is_rotated(logger: Logger) {
+    let mut helper = IngestServerTestHelper::new(BASE_PORT, logger);
+    helper.add_origin_block();
+    let nodes = helper.make_nodes(3);
+
+    nodes[0].activate().expect("first node failed to activate");
+    assert!(nodes[0].is_active());
+    assert!(!nodes[1].is_active());
+    assert!(!nodes[2].is_active());
+
+    helper.add_test_blocks(3);
+    helper.wait_till_recovery_db_in_sync();
+
+    let original_ingress_keys = get_ingress_keys(&nodes);
+
+    // Initialize an OverseerService with an associated server.
+    let client = helper.enable_overseer_with_key_rotation_policy(
+        nodes
+            .iter()
+            .map(|node| node.client_listen_uri.clone())
+            .collect(),
+        KeyRotationPolicy {
+            period_blocks: Some(2),
+            period: None,
+        },
+    );
+
+    // The first idle node should have taken over with a new key.
+    assert!(nodes[1].is_active());
+    assert!(!nodes[2].is_active());
+    let new_ingress_key = nodes[1].get_ingress_key();
+    assert_ne!(new_ingress_key, original_ingress_keys[0]);
+    helper.check_ingress_key(&new_ingress_key, false, false);
+
+    // The old key is retired, but not lost, and is still being scanned until
+    // its pubkey expiry.
+    helper.check_ingress_key(&original_ingress_keys[0], true, false);
+    assert!(nodes[0].is_active());
+
+    // No further rotation is planned while the old key is being scanned.
+    let body = client
+        .get("/rotation_plan")
+        .dispatch()
+        .into_string()
+        .unwrap();
+    assert!(body.contains("retired keys"), "{body}");
+
+    let body = client.get("/audit_log").dispatch().into_string().unwrap();
+    assert_eq!(
+        body.matches("ScheduledKeyRotation").count(),
+        3,
+        "Expected new keys, activate and retire in the audit log: {body}"
+    );
+    assert!(!body.contains("AutomaticFailover"), "{body}");
+}
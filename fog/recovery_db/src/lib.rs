@@ -0,0 +1,323 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! A fog recovery database whose backend is selected at runtime from the
+//! database url.
+//!
+//! Urls of the form `lmdb://<path>` open an embedded [LmdbRecoveryDb], which
+//! is convenient for local development and small deployments. Anything else
+//! is treated as a postgres url and opens a [SqlRecoveryDb].
+
+#![deny(missing_docs)]
+
+use chrono::NaiveDateTime;
+use displaydoc::Display;
+use mc_common::logger::Logger;
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_kex_rng::KexRngPubkey;
+use mc_fog_lmdb_recovery_db::{Error as LmdbRecoveryDbError, LmdbRecoveryDb, LMDB_URL_SCHEME};
+use mc_fog_recovery_db_iface::{
+    AddBlockDataStatus, Block, BlockRange, ETxOutRecord, ExpiredInvocationRecord, FogUserEvent,
+    IngestInvocationId, IngestableRange, IngressPublicKeyRecord, IngressPublicKeyRecordFilters,
+    IngressPublicKeyStatus, RecoveryDb, RecoveryDbError, ReportData, ReportDb,
+};
+use mc_fog_sql_recovery_db::{
+    Error as SqlRecoveryDbError, SqlRecoveryDb, SqlRecoveryDbConnectionConfig,
+};
+use mc_fog_types::view::FixedTxOutSearchResult;
+
+/// A recovery database backed by either postgres or LMDB.
+#[derive(Clone)]
+pub enum AnyRecoveryDb {
+    /// A postgres database
+    Sql(SqlRecoveryDb),
+
+    /// An embedded LMDB database
+    Lmdb(LmdbRecoveryDb),
+}
+
+/// An error from either recovery database backend.
+#[derive(Debug, Display)]
+pub enum Error {
+    /// Sql recovery db: {0}
+    Sql(SqlRecoveryDbError),
+
+    /// Lmdb recovery db: {0}
+    Lmdb(LmdbRecoveryDbError),
+}
+
+impl RecoveryDbError for Error {
+    fn should_retry(&self) -> bool {
+        match self {
+            Self::Sql(err) => err.should_retry(),
+            Self::Lmdb(err) => err.should_retry(),
+        }
+    }
+}
+
+impl From<SqlRecoveryDbError> for Error {
+    fn from(src: SqlRecoveryDbError) -> Self {
+        Self::Sql(src)
+    }
+}
+
+impl From<LmdbRecoveryDbError> for Error {
+    fn from(src: LmdbRecoveryDbError) -> Self {
+        Self::Lmdb(src)
+    }
+}
+
+impl AnyRecoveryDb {
+    /// Open the recovery database at the given url.
+    ///
+    /// The postgres config is ignored when the url selects LMDB.
+    pub fn new_from_url(
+        database_url: &str,
+        postgres_config: SqlRecoveryDbConnectionConfig,
+        logger: Logger,
+    ) -> Result<Self, Error> {
+        if is_lmdb_url(database_url) {
+            Ok(Self::Lmdb(LmdbRecoveryDb::new_from_url(
+                database_url,
+                logger,
+            )?))
+        } else {
+            Ok(Self::Sql(SqlRecoveryDb::new_from_url(
+                database_url,
+                postgres_config,
+                logger,
+            )?))
+        }
+    }
+}
+
+impl From<SqlRecoveryDb> for AnyRecoveryDb {
+    fn from(src: SqlRecoveryDb) -> Self {
+        Self::Sql(src)
+    }
+}
+
+impl From<LmdbRecoveryDb> for AnyRecoveryDb {
+    fn from(src: LmdbRecoveryDb) -> Self {
+        Self::Lmdb(src)
+    }
+}
+
+/// Whether the url selects the LMDB backend.
+fn is_lmdb_url(database_url: &str) -> bool {
+    database_url
+        .strip_prefix(LMDB_URL_SCHEME)
+        .is_some_and(|rest| rest.starts_with("://"))
+}
+
+/// Forward a call to whichever backend is in use, converting the error.
+macro_rules! dispatch {
+    ($self:ident, $db:ident => $call:expr) => {
+        match $self {
+            AnyRecoveryDb::Sql($db) => $call.map_err(Error::from),
+            AnyRecoveryDb::Lmdb($db) => $call.map_err(Error::from),
+        }
+    };
+}
+
+impl RecoveryDb for AnyRecoveryDb {
+    type Error = Error;
+
+    fn get_ingress_key_status(
+        &self,
+        key: &CompressedRistrettoPublic,
+    ) -> Result<Option<IngressPublicKeyStatus>, Self::Error> {
+        dispatch!(self, db => db.get_ingress_key_status(key))
+    }
+
+    fn new_ingress_key(
+        &self,
+        key: &CompressedRistrettoPublic,
+        start_block_count: u64,
+    ) -> Result<u64, Self::Error> {
+        dispatch!(self, db => db.new_ingress_key(key, start_block_count))
+    }
+
+    fn retire_ingress_key(
+        &self,
+        key: &CompressedRistrettoPublic,
+        set_retired: bool,
+    ) -> Result<(), Self::Error> {
+        dispatch!(self, db => db.retire_ingress_key(key, set_retired))
+    }
+
+    fn get_last_scanned_block_index(
+        &self,
+        key: &CompressedRistrettoPublic,
+    ) -> Result<Option<u64>, Self::Error> {
+        dispatch!(self, db => db.get_last_scanned_block_index(key))
+    }
+
+    fn get_ingress_key_records(
+        &self,
+        start_block_at_least: u64,
+        ingress_public_key_record_filters: &IngressPublicKeyRecordFilters,
+    ) -> Result<Vec<IngressPublicKeyRecord>, Self::Error> {
+        dispatch!(self, db => db.get_ingress_key_records(
+            start_block_at_least,
+            ingress_public_key_record_filters,
+        ))
+    }
+
+    fn new_ingest_invocation(
+        &self,
+        prev_ingest_invocation_id: Option<IngestInvocationId>,
+        ingress_public_key: &CompressedRistrettoPublic,
+        egress_public_key: &KexRngPubkey,
+        start_block: u64,
+    ) -> Result<IngestInvocationId, Self::Error> {
+        dispatch!(self, db => db.new_ingest_invocation(
+            prev_ingest_invocation_id,
+            ingress_public_key,
+            egress_public_key,
+            start_block,
+        ))
+    }
+
+    fn get_ingestable_ranges(&self) -> Result<Vec<IngestableRange>, Self::Error> {
+        dispatch!(self, db => db.get_ingestable_ranges())
+    }
+
+    fn decommission_ingest_invocation(
+        &self,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<(), Self::Error> {
+        dispatch!(self, db => db.decommission_ingest_invocation(ingest_invocation_id))
+    }
+
+    fn add_block_data(
+        &self,
+        ingest_invocation_id: &IngestInvocationId,
+        block: &Block,
+        block_signature_timestamp: u64,
+        txs: &[ETxOutRecord],
+    ) -> Result<AddBlockDataStatus, Self::Error> {
+        dispatch!(self, db => db.add_block_data(
+            ingest_invocation_id,
+            block,
+            block_signature_timestamp,
+            txs,
+        ))
+    }
+
+    fn report_lost_ingress_key(
+        &self,
+        lost_ingress_key: CompressedRistrettoPublic,
+    ) -> Result<(), Self::Error> {
+        dispatch!(self, db => db.report_lost_ingress_key(lost_ingress_key))
+    }
+
+    fn get_missed_block_ranges(&self) -> Result<Vec<BlockRange>, Self::Error> {
+        dispatch!(self, db => db.get_missed_block_ranges())
+    }
+
+    fn search_user_events(
+        &self,
+        start_from_user_event_id: i64,
+    ) -> Result<(Vec<FogUserEvent>, i64), Self::Error> {
+        dispatch!(self, db => db.search_user_events(start_from_user_event_id))
+    }
+
+    fn get_tx_outs(
+        &self,
+        start_block: u64,
+        search_keys: &[Vec<u8>],
+    ) -> Result<Vec<FixedTxOutSearchResult>, Self::Error> {
+        dispatch!(self, db => db.get_tx_outs(start_block, search_keys))
+    }
+
+    fn update_last_active_at(
+        &self,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<(), Self::Error> {
+        dispatch!(self, db => db.update_last_active_at(ingest_invocation_id))
+    }
+
+    fn get_tx_outs_by_block_and_key(
+        &self,
+        ingress_key: CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<Vec<ETxOutRecord>>, Self::Error> {
+        dispatch!(self, db => db.get_tx_outs_by_block_and_key(ingress_key, block_index))
+    }
+
+    fn get_tx_outs_by_block_range_and_key(
+        &self,
+        ingress_key: CompressedRistrettoPublic,
+        block_range: &BlockRange,
+    ) -> Result<Vec<Vec<ETxOutRecord>>, Self::Error> {
+        dispatch!(self, db => db.get_tx_outs_by_block_range_and_key(ingress_key, block_range))
+    }
+
+    fn get_invocation_id_by_block_and_key(
+        &self,
+        ingress_key: CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<IngestInvocationId>, Self::Error> {
+        dispatch!(self, db => db.get_invocation_id_by_block_and_key(ingress_key, block_index))
+    }
+
+    fn get_cumulative_txo_count_for_block(
+        &self,
+        block_index: u64,
+    ) -> Result<Option<u64>, Self::Error> {
+        dispatch!(self, db => db.get_cumulative_txo_count_for_block(block_index))
+    }
+
+    fn get_block_signature_timestamp_for_block(
+        &self,
+        block_index: u64,
+    ) -> Result<Option<u64>, Self::Error> {
+        dispatch!(self, db => db.get_block_signature_timestamp_for_block(block_index))
+    }
+
+    fn get_highest_known_block_index(&self) -> Result<Option<u64>, Self::Error> {
+        dispatch!(self, db => db.get_highest_known_block_index())
+    }
+
+    fn get_expired_invocations(
+        &self,
+        expiration: NaiveDateTime,
+    ) -> Result<Vec<ExpiredInvocationRecord>, Self::Error> {
+        dispatch!(self, db => db.get_expired_invocations(expiration))
+    }
+}
+
+impl ReportDb for AnyRecoveryDb {
+    type Error = Error;
+
+    fn get_all_reports(&self) -> Result<Vec<(String, ReportData)>, Self::Error> {
+        dispatch!(self, db => db.get_all_reports())
+    }
+
+    fn set_report(
+        &self,
+        ingress_public_key: &CompressedRistrettoPublic,
+        report_id: &str,
+        data: &ReportData,
+    ) -> Result<IngressPublicKeyStatus, Self::Error> {
+        dispatch!(self, db => db.set_report(ingress_public_key, report_id, data))
+    }
+
+    fn remove_report(&self, report_id: &str) -> Result<(), Self::Error> {
+        dispatch!(self, db => db.remove_report(report_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_lmdb_url() {
+        assert!(is_lmdb_url("lmdb:///var/lib/fog/recovery"));
+        assert!(is_lmdb_url("lmdb://relative/path"));
+        assert!(!is_lmdb_url("postgres://localhost/fog_recovery"));
+        assert!(!is_lmdb_url("lmdbx:///tmp/db"));
+        assert!(!is_lmdb_url("/tmp/lmdb://db"));
+    }
+}
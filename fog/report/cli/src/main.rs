@@ -22,16 +22,17 @@ use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPublic};
 use mc_fog_api::report_parse::try_extract_unvalidated_ingress_pubkey_from_fog_evidence;
 use mc_fog_report_connection::{Error, GrpcFogReportConnection};
 use mc_fog_report_resolver::FogResolver;
-use mc_fog_report_types::FogReportResponses;
+use mc_fog_report_types::{FogReportBundle, FogReportResponses};
 use mc_fog_report_validation::{FogPubkeyResolver, FullyValidatedFogPubkey};
 use mc_util_cli::ParserWithBuildInfo;
 use mc_util_uri::FogUri;
 use std::{
-    path::PathBuf,
+    fs,
+    path::{Path, PathBuf},
     process::exit,
     str::FromStr,
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 /// A command line utility to reach out to the fog report server and fetch the
@@ -49,6 +50,11 @@ use std::{
 ///   would be performed for a fog user with these values in their address.
 /// - Supply only a fog-url. This can only be used with the "no-validate"
 ///   option.
+///
+/// With bundle-output, the fog report responses for the public address, and
+/// any bundle addresses, are instead written to a fog report bundle file,
+/// which can be used to build transactions on a machine without network
+/// access.
 #[derive(Debug, clap::Parser)]
 #[clap(version)]
 struct Config {
@@ -92,12 +98,28 @@ struct Config {
     /// and fog authority signature.
     #[clap(long, short, env = "MC_NO_VALIDATE")]
     pub no_validate: bool,
+
+    /// Write a fog report bundle for the public address, and any bundle
+    /// addresses, to this json file, for building transactions offline.
+    /// The pubkey expiry of the bundle is printed as json.
+    #[clap(long, short = 'o', env = "MC_BUNDLE_OUTPUT")]
+    pub bundle_output: Option<PathBuf>,
+
+    /// Paths to additional public addresses to include in the fog report
+    /// bundle.
+    #[clap(
+        long = "bundle-address",
+        requires = "bundle_output",
+        use_value_delimiter = true,
+        env = "MC_BUNDLE_ADDRESSES"
+    )]
+    pub bundle_addresses: Vec<PathBuf>,
 }
 
-/// Get fog response with retries, retrying if NoReports error occurs
+/// Get fog responses with retries, retrying if NoReports error occurs
 fn get_fog_response_with_retries(
     chain_id: &str,
-    fog_uri: FogUri,
+    fog_uris: &[FogUri],
     retry_duration: Duration,
     logger: &Logger,
 ) -> FogReportResponses {
@@ -108,7 +130,7 @@ fn get_fog_response_with_retries(
 
     let deadline = Instant::now() + retry_duration;
     loop {
-        match conn.fetch_fog_reports(core::slice::from_ref(&fog_uri).iter().cloned()) {
+        match conn.fetch_fog_reports(fog_uris.iter().cloned()) {
             Ok(result) => {
                 return result;
             }
@@ -120,7 +142,7 @@ fn get_fog_response_with_retries(
                 }
             }
             Err(err) => {
-                eprintln!("Could not get fog response ({fog_uri}): {err}");
+                eprintln!("Could not get fog response ({fog_uris:?}): {err}");
                 exit(1);
             }
         }
@@ -171,6 +193,54 @@ fn get_unvalidated_pubkey(
     (pubkey, pubkey_expiry)
 }
 
+/// Fetch the fog reports for a set of public addresses and write them to a
+/// fog report bundle file, returning the pubkey expiry of the bundle
+fn write_bundle(
+    config: &Config,
+    pub_addrs: Vec<PublicAddress>,
+    output: &Path,
+    logger: &Logger,
+) -> u64 {
+    let mut fog_uris = pub_addrs
+        .iter()
+        .map(|addr| {
+            FogUri::from_str(
+                addr.fog_report_url()
+                    .expect("public address had no fog url"),
+            )
+            .expect("Could not parse fog report url as a valid fog url")
+        })
+        .collect::<Vec<_>>();
+    fog_uris.sort();
+    fog_uris.dedup();
+
+    let responses = get_fog_response_with_retries(
+        &config.chain_id,
+        &fog_uris,
+        Duration::from_secs(config.retry_seconds),
+        logger,
+    );
+
+    // Check that every address can be resolved with the bundle before it is
+    // taken offline
+    if !config.no_validate {
+        for pub_addr in pub_addrs {
+            get_validated_pubkey(responses.clone(), pub_addr, logger);
+        }
+    }
+
+    let fetched_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before the unix epoch")
+        .as_secs();
+    let bundle = FogReportBundle::new(responses, fetched_at);
+    let json = serde_json::to_string(&bundle).expect("Could not serialize fog report bundle");
+    fs::write(output, json).expect("Could not write fog report bundle");
+    log::info!(logger, "Wrote fog report bundle to {:?}", output);
+
+    bundle.pubkey_expiry
+}
+
 fn main() {
     // Logging must go to stderr to not interfere with STDOUT
     std::env::set_var("MC_LOG_STDERR", "1");
@@ -217,6 +287,22 @@ fn main() {
         None
     };
 
+    // Write a fog report bundle, if requested
+    if let Some(ref output) = config.bundle_output {
+        let pub_addrs = pub_addr
+            .into_iter()
+            .chain(config.bundle_addresses.iter().map(|path| {
+                mc_util_keyfile::read_pubfile(path).expect("Could not read public address file")
+            }))
+            .collect::<Vec<_>>();
+        if pub_addrs.is_empty() {
+            panic!("A public address or a fog spki is needed to write a fog report bundle");
+        }
+        let pubkey_expiry = write_bundle(&config, pub_addrs, output, &logger);
+        print!("{{ \"pubkey_expiry\": {pubkey_expiry} }}");
+        return;
+    }
+
     // Get pubkey and pubkey expiry, using either validated or unvalidated path
     let (pubkey, pubkey_expiry): (RistrettoPublic, u64) = if config.no_validate {
         let fog_uri_str: String = pub_addr
@@ -238,7 +324,7 @@ fn main() {
         // Try to make request
         let responses = get_fog_response_with_retries(
             &config.chain_id,
-            fog_uri.clone(),
+            core::slice::from_ref(&fog_uri),
             Duration::from_secs(config.retry_seconds),
             &logger,
         );
@@ -266,7 +352,7 @@ fn main() {
         // Try to make request
         let responses = get_fog_response_with_retries(
             &config.chain_id,
-            fog_uri,
+            &[fog_uri],
             Duration::from_secs(config.retry_seconds),
             &logger,
         );
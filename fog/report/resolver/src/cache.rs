@@ -0,0 +1,286 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! A client-side cache of fog report responses.
+//!
+//! Fog reports only change when the ingest enclave's key or pubkey expiry
+//! changes, so a wallet can keep using a report until the pubkey in it no
+//! longer covers the tombstone block of the transaction it is building,
+//! instead of fetching reports for every transaction.
+//!
+//! The cache can also pin the fog authorities that a fog report url is
+//! allowed to be signed by, so that responses signed by any other authority
+//! are rejected before they are used.
+
+use crate::FogResolver;
+use core::str::FromStr;
+use displaydoc::Display;
+use mc_account_keys::PublicAddress;
+use mc_attestation_verifier::TrustedIdentity;
+use mc_crypto_x509_utils::{X509CertificateChain, X509CertificateIter};
+use mc_fog_report_types::{FogReportResponses, ReportResponse};
+use mc_util_uri::{FogUri, UriParseError};
+use std::collections::{BTreeMap, BTreeSet};
+use x509_signature::X509Certificate;
+
+/// An error that can occur when using a [FogReportCache]
+#[derive(Debug, Display)]
+pub enum FogReportCacheError {
+    /// Failed to parse fog url: {0}
+    Url(UriParseError),
+    /// The report response for {0} has an invalid certificate chain
+    InvalidChain(String),
+    /// The report response for {0} is not signed by a pinned fog authority
+    PinMismatch(String),
+}
+
+impl From<UriParseError> for FogReportCacheError {
+    fn from(src: UriParseError) -> Self {
+        Self::Url(src)
+    }
+}
+
+/// Fog report responses kept across transactions, keyed by normalized fog
+/// report url.
+#[derive(Clone, Debug, Default)]
+pub struct FogReportCache {
+    responses: FogReportResponses,
+    pinned_authorities: BTreeMap<String, Vec<Vec<u8>>>,
+}
+
+impl FogReportCache {
+    /// Create an empty cache with no pinned authorities.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Only accept responses for the given fog report url whose certificate
+    /// chain has a root with this subjectPublicKeyInfo. Several authorities
+    /// can be pinned for the same url, e.g. while the authority is rolled
+    /// over. Cached responses which don't match are dropped.
+    pub fn pin_authority(&mut self, url: &str, spki: &[u8]) -> Result<(), FogReportCacheError> {
+        let url = FogUri::from_str(url)?.to_string();
+        self.pinned_authorities
+            .entry(url.clone())
+            .or_default()
+            .push(spki.to_vec());
+        if let Some(response) = self.responses.get(&url) {
+            if self.check_pins(&url, response).is_err() {
+                self.responses.remove(&url);
+            }
+        }
+        Ok(())
+    }
+
+    /// The fog report urls which have to be fetched to send to the given
+    /// recipients, because there is no cached report for the recipient's
+    /// report id, or its pubkey expires before `min_pubkey_expiry`.
+    ///
+    /// `min_pubkey_expiry` should be the tombstone block of the transaction,
+    /// since the transaction builder lowers the tombstone block to the pubkey
+    /// expiry of the fog reports it uses.
+    pub fn urls_to_fetch<'a>(
+        &self,
+        recipients: impl IntoIterator<Item = &'a PublicAddress>,
+        min_pubkey_expiry: u64,
+    ) -> Result<Vec<FogUri>, FogReportCacheError> {
+        let mut urls = BTreeSet::new();
+        for recipient in recipients {
+            let url = match recipient.fog_report_url() {
+                Some(url) => FogUri::from_str(url)?,
+                None => continue,
+            };
+            let report_id = recipient.fog_report_id().unwrap_or("");
+            let is_fresh = self
+                .responses
+                .get(&url.to_string())
+                .and_then(|response| {
+                    response
+                        .reports
+                        .iter()
+                        .find(|report| report.fog_report_id == report_id)
+                })
+                .map(|report| report.pubkey_expiry >= min_pubkey_expiry)
+                .unwrap_or(false);
+            if !is_fresh {
+                urls.insert(url);
+            }
+        }
+        Ok(urls.into_iter().collect())
+    }
+
+    /// Add fetched responses to the cache, replacing any cached responses for
+    /// the same urls. Nothing is added if any response fails a pin.
+    pub fn insert(&mut self, responses: FogReportResponses) -> Result<(), FogReportCacheError> {
+        let responses = responses
+            .into_iter()
+            .map(|(url, response)| {
+                let url = FogUri::from_str(&url)?.to_string();
+                self.check_pins(&url, &response)?;
+                Ok((url, response))
+            })
+            .collect::<Result<Vec<_>, FogReportCacheError>>()?;
+        self.responses.extend(responses);
+        Ok(())
+    }
+
+    /// Drop the responses in which every pubkey expired before the given
+    /// block index.
+    pub fn prune(&mut self, block_index: u64) {
+        self.responses.retain(|_, response| {
+            response
+                .reports
+                .iter()
+                .any(|report| report.pubkey_expiry >= block_index)
+        });
+    }
+
+    /// Make a resolver from the cached responses. Every pubkey it returns is
+    /// validated against the recipient and the trusted identities as usual.
+    pub fn resolver<'a>(
+        &self,
+        identities: impl IntoIterator<Item = &'a TrustedIdentity>,
+    ) -> FogResolver {
+        FogResolver {
+            responses: self.responses.clone(),
+            identities: Vec::from_iter(identities.into_iter().cloned()),
+        }
+    }
+
+    /// Check a response against the authorities pinned for its url, if any.
+    fn check_pins(&self, url: &str, response: &ReportResponse) -> Result<(), FogReportCacheError> {
+        let pins = match self.pinned_authorities.get(url) {
+            Some(pins) => pins,
+            None => return Ok(()),
+        };
+        let certs = X509CertificateIter::from(
+            response
+                .chain
+                .iter()
+                .map(|der| der.as_slice())
+                .collect::<Vec<&[u8]>>(),
+        )
+        .collect::<Vec<X509Certificate>>();
+        let root = certs
+            .verified_root()
+            .map_err(|_| FogReportCacheError::InvalidChain(url.to_string()))?;
+        let spki = root.subject_public_key_info().spki();
+        if pins.iter().any(|pin| pin.as_slice() == spki) {
+            Ok(())
+        } else {
+            Err(FogReportCacheError::PinMismatch(url.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_crypto_keys::RistrettoPublic;
+    use mc_crypto_x509_utils::X509CertificateIterable;
+    use mc_fog_report_types::Report;
+    use mc_util_from_random::FromRandom;
+    use pem::Pem;
+    use rand_core::SeedableRng;
+    use rand_hc::Hc128Rng;
+
+    const URL: &str = "fog://fog.unittest.mobilecoin.foundation";
+
+    fn address(rng: &mut Hc128Rng, report_id: &str) -> PublicAddress {
+        PublicAddress::new_with_fog(
+            &RistrettoPublic::from_random(rng),
+            &RistrettoPublic::from_random(rng),
+            URL,
+            report_id.to_string(),
+            Vec::<u8>::new(),
+        )
+    }
+
+    fn response(chain: Vec<Vec<u8>>, reports: &[(&str, u64)]) -> ReportResponse {
+        ReportResponse {
+            reports: reports
+                .iter()
+                .map(|(fog_report_id, pubkey_expiry)| Report {
+                    fog_report_id: fog_report_id.to_string(),
+                    attestation_evidence: None,
+                    pubkey_expiry: *pubkey_expiry,
+                })
+                .collect(),
+            chain,
+            signature: vec![],
+        }
+    }
+
+    fn test_chain() -> (Vec<Vec<u8>>, Vec<u8>) {
+        let (pem_chain, _keypair) = mc_crypto_x509_test_vectors::ok_rsa_chain_25519_leaf();
+        let der_chain = pem::parse_many(pem_chain).expect("Could not parse PEM chain");
+        let root_spki = der_chain
+            .iter_x509()
+            .collect::<Vec<X509Certificate>>()
+            .verified_root()
+            .expect("Could not verify test chain")
+            .subject_public_key_info()
+            .spki()
+            .to_vec();
+        (
+            der_chain.into_iter().map(Pem::into_contents).collect(),
+            root_spki,
+        )
+    }
+
+    #[test]
+    fn reuses_reports_until_expiry() {
+        let mut rng = Hc128Rng::seed_from_u64(1);
+        let alice = address(&mut rng, "");
+        let bob = address(&mut rng, "1");
+        let no_fog = PublicAddress::from_random(&mut rng);
+
+        let mut cache = FogReportCache::new();
+        let expected = vec![FogUri::from_str(URL).unwrap()];
+        assert_eq!(
+            cache.urls_to_fetch([&alice, &bob, &no_fog], 10).unwrap(),
+            expected
+        );
+
+        cache
+            .insert(FogReportResponses::from([(
+                URL.to_string(),
+                response(vec![], &[("", 20)]),
+            )]))
+            .unwrap();
+        assert!(cache.urls_to_fetch([&alice], 20).unwrap().is_empty());
+        assert_eq!(cache.urls_to_fetch([&alice], 21).unwrap(), expected);
+        // There is no cached report for bob's report id.
+        assert_eq!(cache.urls_to_fetch([&bob], 10).unwrap(), expected);
+
+        cache.prune(20);
+        assert!(cache.urls_to_fetch([&alice], 10).unwrap().is_empty());
+        cache.prune(21);
+        assert_eq!(cache.urls_to_fetch([&alice], 10).unwrap(), expected);
+    }
+
+    #[test]
+    fn pinned_authority() {
+        let (chain, root_spki) = test_chain();
+        let responses =
+            || FogReportResponses::from([(URL.to_string(), response(chain.clone(), &[("", 20)]))]);
+
+        let mut cache = FogReportCache::new();
+        cache.pin_authority(URL, b"some other authority").unwrap();
+        assert!(matches!(
+            cache.insert(responses()),
+            Err(FogReportCacheError::PinMismatch(_))
+        ));
+
+        cache.pin_authority(URL, &root_spki).unwrap();
+        cache.insert(responses()).unwrap();
+
+        // A response without a valid chain is rejected for a pinned url.
+        assert!(matches!(
+            cache.insert(FogReportResponses::from([(
+                URL.to_string(),
+                response(vec![], &[("", 20)])
+            )])),
+            Err(FogReportCacheError::InvalidChain(_))
+        ));
+    }
+}
@@ -7,6 +7,10 @@
 
 extern crate alloc;
 
+mod cache;
+
+pub use cache::{FogReportCache, FogReportCacheError};
+
 use mc_fog_report_validation::{FogPubkeyError, FogPubkeyResolver, FullyValidatedFogPubkey};
 
 use mc_fog_ingest_report::IngestAttestationEvidenceVerifier;
@@ -14,7 +18,9 @@ use mc_fog_ingest_report::IngestAttestationEvidenceVerifier;
 use core::str::FromStr;
 use mc_account_keys::PublicAddress;
 use mc_attestation_verifier::TrustedIdentity;
-use mc_fog_report_types::{FogReportResponses, ReportResponse};
+use mc_fog_report_types::{
+    FogReportBundle, FogReportBundleError, FogReportResponses, ReportResponse,
+};
 use mc_fog_sig::Verifier as FogSigVerifier;
 use mc_util_uri::{FogUri, UriParseError};
 
@@ -55,6 +61,22 @@ impl FogResolver {
             identities: Vec::from_iter(identities.into_iter().cloned()),
         })
     }
+
+    /// Create a new FogResolver object from a fog report bundle, for building
+    /// a transaction with the given tombstone block without network access.
+    ///
+    /// Fails if the bundle expires before the tombstone block, since the
+    /// transaction builder would otherwise lower the tombstone block to the
+    /// pubkey expiry.
+    pub fn from_bundle<'a>(
+        bundle: FogReportBundle,
+        identities: impl IntoIterator<Item = &'a TrustedIdentity>,
+        tombstone_block: u64,
+    ) -> Result<Self, FogReportBundleError> {
+        bundle.check_tombstone_block(tombstone_block)?;
+        Self::new(bundle.responses, identities)
+            .map_err(|err| FogReportBundleError::InvalidUrl(err.to_string()))
+    }
 }
 
 impl FogPubkeyResolver for FogResolver {
@@ -94,3 +116,69 @@ impl FogPubkeyResolver for FogResolver {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_fog_report_types::Report;
+
+    const URL: &str = "fog://fog.unittest.mobilecoin.foundation";
+
+    fn bundle(url: &str, pubkey_expiry: u64) -> FogReportBundle {
+        let response = ReportResponse {
+            reports: vec![Report {
+                fog_report_id: String::new(),
+                attestation_evidence: None,
+                pubkey_expiry,
+            }],
+            chain: vec![],
+            signature: vec![],
+        };
+        FogReportBundle::new(FogReportResponses::from([(url.to_owned(), response)]), 0)
+    }
+
+    #[test]
+    fn from_bundle_normalizes_urls() {
+        let bundle = bundle("fog://fog.unittest.mobilecoin.foundation:443", 10);
+        let resolver = FogResolver::from_bundle(bundle.clone(), &[], 10).unwrap();
+        let url = FogUri::from_str(URL).unwrap().to_string();
+        assert_eq!(
+            resolver.responses.get(&url),
+            bundle.responses.values().next()
+        );
+    }
+
+    #[test]
+    fn from_bundle_checks_tombstone_block() {
+        assert!(FogResolver::from_bundle(bundle(URL, 10), &[], 9).is_ok());
+        assert!(FogResolver::from_bundle(bundle(URL, 10), &[], 10).is_ok());
+        assert_eq!(
+            FogResolver::from_bundle(bundle(URL, 10), &[], 11).unwrap_err(),
+            FogReportBundleError::Expired {
+                pubkey_expiry: 10,
+                tombstone_block: 11
+            }
+        );
+    }
+
+    #[test]
+    fn from_bundle_recomputes_pubkey_expiry() {
+        let mut bundle = bundle(URL, 10);
+        bundle.pubkey_expiry = 100;
+        assert_eq!(
+            FogResolver::from_bundle(bundle, &[], 50).unwrap_err(),
+            FogReportBundleError::Expired {
+                pubkey_expiry: 10,
+                tombstone_block: 50
+            }
+        );
+    }
+
+    #[test]
+    fn from_bundle_rejects_invalid_urls() {
+        assert!(matches!(
+            FogResolver::from_bundle(bundle("not a fog url", 10), &[], 10),
+            Err(FogReportBundleError::InvalidUrl(_))
+        ));
+    }
+}
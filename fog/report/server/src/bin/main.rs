@@ -3,8 +3,8 @@
 //! Main Method for the Fog Report Server
 
 use mc_common::{logger, sentry};
-use mc_fog_report_server::{Config, Materials, Server};
-use mc_fog_sql_recovery_db::SqlRecoveryDb;
+use mc_fog_recovery_db::AnyRecoveryDb;
+use mc_fog_report_server::{Config, Materials, Server, Tenants};
 use mc_util_cli::ParserWithBuildInfo;
 use mc_util_grpc::AdminServer;
 use std::{env, sync::Arc};
@@ -18,9 +18,13 @@ fn main() {
     let config = Config::parse();
 
     let materials = Materials::try_from(&config).expect("Could not read cryptographic materials");
+    let tenants = match config.tenants.as_ref() {
+        Some(path) => Tenants::load(materials, path).expect("Could not load tenants"),
+        None => materials.into(),
+    };
 
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL environment variable missing");
-    let db = SqlRecoveryDb::new_from_url(
+    let db = AnyRecoveryDb::new_from_url(
         &database_url,
         config.postgres_config.clone(),
         logger.clone(),
@@ -33,7 +37,7 @@ fn main() {
         db,
         config.chain_id.clone(),
         &config.client_listen_uri,
-        materials,
+        tenants,
         logger.clone(),
     );
     server.start();
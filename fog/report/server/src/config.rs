@@ -42,6 +42,13 @@ pub struct Config {
     #[clap(long, env = "MC_SIGNING_KEY")]
     pub signing_key: PathBuf,
 
+    /// The path to a JSON file listing additional fog authorities to serve,
+    /// each with its hostnames, report id prefix, signing chain and signing
+    /// key. Requests for other hostnames are served with the signing chain
+    /// and key above.
+    #[clap(long, env = "MC_TENANTS")]
+    pub tenants: Option<PathBuf>,
+
     /// Postgres config
     #[clap(flatten)]
     pub postgres_config: SqlRecoveryDbConnectionConfig,
@@ -67,6 +74,8 @@ pub enum Error {
      * which  doesn't correspond to the given private key
      */
     ChainKeyMismatch,
+    /// The tenants configuration is invalid: {0}
+    InvalidTenant(String),
 }
 
 impl From<ChainError> for Error {
@@ -7,10 +7,12 @@
 mod config;
 mod server;
 mod service;
+mod tenant;
 
 pub use crate::{
     config::{Config, Error, Materials},
     server::Server,
+    tenant::{Tenant, TenantConfig, Tenants},
 };
 
 use mc_util_metrics::ServiceMetrics;
@@ -2,7 +2,7 @@
 
 //! Server for ingest reports.
 
-use crate::{config::Materials, service::Service};
+use crate::{service::Service, tenant::Tenants};
 use futures::executor::block_on;
 use grpcio::{Server as GrpcioServer, ServerBuilder};
 use mc_common::logger::{log, Logger};
@@ -21,11 +21,14 @@ pub struct Server {
 
 impl Server {
     /// Construct a new server object.
+    ///
+    /// The tenants can be given as the [crate::Materials] of a single fog
+    /// authority.
     pub fn new(
         db: impl ReportDb + Clone + Send + Sync + 'static,
         chain_id: String,
         client_listen_uri: &FogUri,
-        materials: Materials,
+        tenants: impl Into<Tenants>,
         logger: Logger,
     ) -> Self {
         let env = Arc::new(
@@ -34,8 +37,12 @@ impl Server {
                 .build(),
         );
 
-        let report_service =
-            report_grpc::create_report_api(Service::new(chain_id, db, materials, logger.clone()));
+        let report_service = report_grpc::create_report_api(Service::new(
+            chain_id,
+            db,
+            tenants.into(),
+            logger.clone(),
+        ));
         log::debug!(logger, "Constructed Report GRPC Service");
 
         // Health check service
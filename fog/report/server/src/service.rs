@@ -2,7 +2,10 @@
 
 //! Implementation of the ReportService
 
-pub use crate::{config::Materials, SVC_COUNTERS};
+pub use crate::{
+    tenant::{Tenant, Tenants},
+    SVC_COUNTERS,
+};
 use displaydoc::Display;
 use grpcio::{RpcContext, RpcStatus, UnarySink};
 use mc_common::logger::{self, log, Logger};
@@ -25,8 +28,9 @@ pub struct Service<R: ReportDb + Clone + Send + Sync> {
     /// clients.
     report_db: R,
 
-    /// Cryptographic materials used in response construction
-    materials: Materials,
+    /// The fog authorities served, and the cryptographic materials used in
+    /// response construction for each of them
+    tenants: Tenants,
 
     /// Chain id to check against the user-provided chain id (if present in
     /// request)
@@ -74,18 +78,18 @@ impl<E: RecoveryDbError> Error<E> {
 impl<R: ReportDb + Clone + Send + Sync> Service<R> {
     /// Creates a new report service node (but does not create sockets and start
     /// it etc.)
-    pub fn new(chain_id: String, report_db: R, materials: Materials, logger: Logger) -> Self {
+    pub fn new(chain_id: String, report_db: R, tenants: Tenants, logger: Logger) -> Self {
         Self {
             chain_id,
             report_db,
-            materials,
+            tenants,
             logger,
         }
     }
 
-    /// Loads report data from the database, signs it, and puts the results into
-    /// constructs a new response structure.
-    fn build_response(&self) -> Result<ReportResponse, Error<R::Error>> {
+    /// Loads report data from the database, keeps the tenant's reports, signs
+    /// them, and puts the results into constructs a new response structure.
+    fn build_response(&self, tenant: Option<&Tenant>) -> Result<ReportResponse, Error<R::Error>> {
         mc_common::trace_time!(self.logger, "Building prost response from report DB");
         let reports = self
             .report_db
@@ -100,9 +104,10 @@ impl<R: ReportDb + Clone + Send + Sync> Service<R> {
                 })
             })
             .collect::<Result<Vec<Report>, DecodeError>>()?;
+        let reports = self.tenants.filter_reports(tenant, reports);
         log::trace!(self.logger, "Got reports from DB, signing: {:?}", reports);
-        let signature = self
-            .materials
+        let materials = self.tenants.materials(tenant);
+        let signature = materials
             .signing_keypair
             .sign_reports(&reports[..])?
             .to_bytes()
@@ -110,7 +115,7 @@ impl<R: ReportDb + Clone + Send + Sync> Service<R> {
         log::trace!(self.logger, "Reports list signature: {:?}", signature);
         Ok(ReportResponse {
             reports,
-            chain: self.materials.chain.clone(),
+            chain: materials.chain.clone(),
             signature,
         })
     }
@@ -130,12 +135,17 @@ impl<R: ReportDb + Clone + Send + Sync> ReportApi for Service<R> {
                 return send_result(ctx, sink, Err(err), logger);
             }
 
+            let tenant = self.tenants.select(ctx.host());
+            if let Some(tenant) = tenant {
+                log::trace!(logger, "Serving reports for tenant {}", tenant.name);
+            }
+
             // Build a prost response, then convert it to rpc/protobuf types and the errors
             // to rpc status codes.
             send_result(
                 ctx,
                 sink,
-                self.build_response()
+                self.build_response(tenant)
                     .map(ProtobufReportResponse::from)
                     .map_err(|e| e.into_rpc_status(logger)),
                 logger,
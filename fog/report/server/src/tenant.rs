@@ -0,0 +1,282 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Support for serving several fog authorities from one report server.
+//!
+//! Each tenant is reached through its own hostnames, which appear in the fog
+//! report urls of its users, and owns the reports in the database whose report
+//! id starts with its prefix. The prefix is stripped before the reports are
+//! served, so a tenant's ingest servers publish reports as e.g. `tenant-a/`,
+//! while its users keep the plain report id `""` in their public addresses.
+//!
+//! Requests for any other host are served by the default tenant, with the
+//! reports which don't belong to any tenant.
+
+use crate::config::{Error, Materials};
+use mc_fog_report_types::Report;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The configuration of one tenant, as it appears in the tenants file.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TenantConfig {
+    /// A name for the tenant, used in logs.
+    pub name: String,
+
+    /// The hostnames used in the fog report urls of this tenant's users.
+    pub hosts: Vec<String>,
+
+    /// The prefix of this tenant's report ids in the database.
+    pub report_id_prefix: String,
+
+    /// The path to the tenant's X509 certificate chain in PEM format.
+    pub signing_chain: PathBuf,
+
+    /// The path to the tenant's signing key.
+    pub signing_key: PathBuf,
+}
+
+/// A tenant, with its cryptographic materials loaded.
+#[derive(Clone, Debug)]
+pub struct Tenant {
+    /// The name of the tenant.
+    pub name: String,
+
+    /// The lowercased hostnames of the tenant.
+    hosts: Vec<String>,
+
+    /// The prefix of this tenant's report ids in the database.
+    report_id_prefix: String,
+
+    /// The materials used to sign this tenant's responses.
+    pub(crate) materials: Materials,
+}
+
+impl Tenant {
+    /// Create a tenant from its hosts, report id prefix, and materials.
+    pub fn new(
+        name: impl Into<String>,
+        hosts: impl IntoIterator<Item = impl AsRef<str>>,
+        report_id_prefix: impl Into<String>,
+        materials: Materials,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            hosts: hosts
+                .into_iter()
+                .map(|host| host.as_ref().to_ascii_lowercase())
+                .collect(),
+            report_id_prefix: report_id_prefix.into(),
+            materials,
+        }
+    }
+}
+
+impl TryFrom<&TenantConfig> for Tenant {
+    type Error = Error;
+
+    fn try_from(src: &TenantConfig) -> Result<Self, Error> {
+        let materials = Materials::from_pems(
+            fs::read_to_string(&src.signing_chain)?,
+            fs::read_to_string(&src.signing_key)?,
+        )?;
+        Ok(Self::new(
+            src.name.clone(),
+            &src.hosts,
+            src.report_id_prefix.clone(),
+            materials,
+        ))
+    }
+}
+
+/// The default tenant and any additional tenants served by a report server.
+#[derive(Clone, Debug)]
+pub struct Tenants {
+    default_materials: Materials,
+    tenants: Vec<Tenant>,
+}
+
+impl Tenants {
+    /// Create the set of tenants, checking that hosts and report id prefixes
+    /// are unambiguous.
+    pub fn new(default_materials: Materials, tenants: Vec<Tenant>) -> Result<Self, Error> {
+        for (index, tenant) in tenants.iter().enumerate() {
+            if tenant.report_id_prefix.is_empty() {
+                return Err(Error::InvalidTenant(format!(
+                    "tenant {} has an empty report id prefix",
+                    tenant.name
+                )));
+            }
+            for other in &tenants[index + 1..] {
+                if tenant.report_id_prefix.starts_with(&other.report_id_prefix)
+                    || other.report_id_prefix.starts_with(&tenant.report_id_prefix)
+                {
+                    return Err(Error::InvalidTenant(format!(
+                        "the report id prefixes of tenants {} and {} overlap",
+                        tenant.name, other.name
+                    )));
+                }
+                if let Some(host) = tenant.hosts.iter().find(|host| other.hosts.contains(host)) {
+                    return Err(Error::InvalidTenant(format!(
+                        "tenants {} and {} share the host {}",
+                        tenant.name, other.name, host
+                    )));
+                }
+            }
+        }
+        Ok(Self {
+            default_materials,
+            tenants,
+        })
+    }
+
+    /// Load the tenants listed in a JSON tenants file.
+    pub fn load(default_materials: Materials, path: &Path) -> Result<Self, Error> {
+        let configs: Vec<TenantConfig> = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| Error::InvalidTenant(format!("could not parse {path:?}: {err}")))?;
+        let tenants = configs
+            .iter()
+            .map(Tenant::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(default_materials, tenants)
+    }
+
+    /// Find the tenant that a request for the given `:authority` is for, or
+    /// None for the default tenant.
+    pub fn select(&self, authority: &[u8]) -> Option<&Tenant> {
+        let authority = core::str::from_utf8(authority).ok()?;
+        let host = match authority.rsplit_once(':') {
+            Some((host, port)) if port.bytes().all(|byte| byte.is_ascii_digit()) => host,
+            _ => authority,
+        };
+        self.tenants
+            .iter()
+            .find(|tenant| tenant.hosts.iter().any(|h| h.eq_ignore_ascii_case(host)))
+    }
+
+    /// The materials used to sign responses for a tenant.
+    pub fn materials(&self, tenant: Option<&Tenant>) -> &Materials {
+        tenant
+            .map(|tenant| &tenant.materials)
+            .unwrap_or(&self.default_materials)
+    }
+
+    /// Keep the reports that belong to a tenant, with their report ids as the
+    /// tenant's users know them.
+    pub fn filter_reports(&self, tenant: Option<&Tenant>, reports: Vec<Report>) -> Vec<Report> {
+        reports
+            .into_iter()
+            .filter_map(|mut report| match tenant {
+                Some(tenant) => {
+                    let id = report
+                        .fog_report_id
+                        .strip_prefix(&tenant.report_id_prefix)?;
+                    report.fog_report_id = id.to_string();
+                    Some(report)
+                }
+                None => (!self
+                    .tenants
+                    .iter()
+                    .any(|tenant| report.fog_report_id.starts_with(&tenant.report_id_prefix)))
+                .then_some(report),
+            })
+            .collect()
+    }
+}
+
+impl From<Materials> for Tenants {
+    fn from(src: Materials) -> Self {
+        Self {
+            default_materials: src,
+            tenants: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn materials() -> Materials {
+        let (pem_chain, signing_keypair) = mc_crypto_x509_test_vectors::ok_rsa_chain_25519_leaf();
+        Materials::from_pem_keypair(pem_chain, signing_keypair).unwrap()
+    }
+
+    fn report(fog_report_id: &str) -> Report {
+        Report {
+            fog_report_id: fog_report_id.to_string(),
+            attestation_evidence: None,
+            pubkey_expiry: 10,
+        }
+    }
+
+    fn ids(reports: Vec<Report>) -> Vec<String> {
+        reports
+            .into_iter()
+            .map(|report| report.fog_report_id)
+            .collect()
+    }
+
+    #[test]
+    fn select_and_filter() {
+        let tenants = Tenants::new(
+            materials(),
+            vec![
+                Tenant::new("a", ["fog.a.com"], "a/", materials()),
+                Tenant::new("b", ["fog.b.com", "fog-b.com"], "b/", materials()),
+            ],
+        )
+        .unwrap();
+
+        let a = tenants.select(b"fog.a.com:443");
+        assert_eq!(a.unwrap().name, "a");
+        assert_eq!(tenants.select(b"FOG-B.com").unwrap().name, "b");
+        assert!(tenants.select(b"fog.c.com:443").is_none());
+        assert!(tenants.select(b"127.0.0.1:3400").is_none());
+
+        let reports = || vec![report(""), report("a/"), report("a/1"), report("b/")];
+        assert_eq!(ids(tenants.filter_reports(a, reports())), vec!["", "1"]);
+        assert_eq!(
+            ids(tenants.filter_reports(tenants.select(b"fog.b.com"), reports())),
+            vec![""]
+        );
+        assert_eq!(ids(tenants.filter_reports(None, reports())), vec![""]);
+    }
+
+    #[test]
+    fn single_tenant_serves_everything() {
+        let tenants = Tenants::from(materials());
+        assert!(tenants.select(b"fog.a.com").is_none());
+        assert_eq!(
+            ids(tenants.filter_reports(None, vec![report(""), report("a/")])),
+            vec!["", "a/"]
+        );
+    }
+
+    #[test]
+    fn ambiguous_tenants_are_rejected() {
+        assert!(Tenants::new(
+            materials(),
+            vec![
+                Tenant::new("a", ["fog.a.com"], "a", materials()),
+                Tenant::new("b", ["fog.b.com"], "a/b", materials()),
+            ],
+        )
+        .is_err());
+        assert!(Tenants::new(
+            materials(),
+            vec![
+                Tenant::new("a", ["fog.a.com"], "a/", materials()),
+                Tenant::new("b", ["FOG.A.COM"], "b/", materials()),
+            ],
+        )
+        .is_err());
+        assert!(Tenants::new(
+            materials(),
+            vec![Tenant::new("a", ["fog.a.com"], "", materials())],
+        )
+        .is_err());
+    }
+}
@@ -8,7 +8,7 @@ use mc_common::logger::{test_with_logger, Logger};
 use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPublic};
 use mc_fog_api::{report::ReportRequest as ProtobufReportRequest, report_grpc};
 use mc_fog_recovery_db_iface::{RecoveryDb, ReportData, ReportDb};
-use mc_fog_report_server::{Materials, Server};
+use mc_fog_report_server::{Materials, Server, Tenant, Tenants};
 use mc_fog_sql_recovery_db::test_utils::SqlRecoveryDbTestContext;
 use mc_fog_test_infra::db_tests::random_kex_rng_pubkey;
 use mc_util_from_random::FromRandom;
@@ -174,3 +174,79 @@ fn report_server_grpc_tests(logger: Logger) {
     );
     assert_eq!(resp.reports[0].get_pubkey_expiry(), report2.pubkey_expiry);
 }
+
+#[test_with_logger]
+fn report_server_multi_tenant_grpc_tests(logger: Logger) {
+    let mut rng: StdRng = SeedableRng::from_seed([124u8; 32]);
+    let db_test_context = SqlRecoveryDbTestContext::new(logger.clone());
+
+    let db = db_test_context.get_db_instance();
+    let ingress_key = CompressedRistrettoPublic::from(&RistrettoPublic::from_random(&mut rng));
+    db.new_ingress_key(&ingress_key, 1).unwrap();
+    let invoc_id = db
+        .new_ingest_invocation(None, &ingress_key, &random_kex_rng_pubkey(&mut rng), 123)
+        .unwrap();
+
+    // Publish a report for the default tenant, and for tenants a and b.
+    for (report_id, pubkey_expiry) in [("", 100), ("a/", 200), ("a/1", 201), ("b/", 300)] {
+        let report = ReportData {
+            ingest_invocation_id: Some(invoc_id),
+            attestation_evidence: prost::DcapEvidence::default().into(),
+            pubkey_expiry,
+        };
+        db.set_report(&ingress_key, report_id, &report).unwrap();
+    }
+
+    let materials = || {
+        let (pem_chain, signing_keypair) = mc_crypto_x509_test_vectors::ok_rsa_chain_25519_leaf();
+        Materials::from_pem_keypair(pem_chain, signing_keypair)
+            .expect("Could not parse x509 test vectors key")
+    };
+    let tenants = Tenants::new(
+        materials(),
+        vec![
+            Tenant::new("a", ["fog.a.test"], "a/", materials()),
+            Tenant::new("b", ["fog.b.test"], "b/", materials()),
+        ],
+    )
+    .expect("Could not create tenants");
+
+    let client_uri = FogUri::from_str("insecure-fog://0.0.0.0:3401").unwrap();
+    let mut server = Server::new(
+        db,
+        "local".to_string(),
+        &client_uri,
+        tenants,
+        logger.clone(),
+    );
+    server.start();
+
+    let env = Arc::new(grpcio::EnvBuilder::new().build());
+    let get_reports = |authority: Option<&str>| {
+        let mut builder = ChannelBuilder::default_channel_builder(env.clone());
+        if let Some(authority) = authority {
+            builder = builder.default_authority(authority);
+        }
+        let ch = builder.connect_to_uri(&client_uri, &logger);
+        let resp = report_grpc::ReportApiClient::new(ch)
+            .get_reports(&ProtobufReportRequest::new())
+            .unwrap();
+        resp.reports
+            .iter()
+            .map(|report| {
+                (
+                    report.get_fog_report_id().to_string(),
+                    report.get_pubkey_expiry(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(get_reports(None), vec![("".to_string(), 100)]);
+    assert_eq!(
+        get_reports(Some("fog.a.test:3401")),
+        vec![("".to_string(), 200), ("1".to_string(), 201)]
+    );
+    assert_eq!(get_reports(Some("fog.b.test")), vec![("".to_string(), 300)]);
+    assert_eq!(get_reports(Some("fog.c.test")), vec![("".to_string(), 100)]);
+}
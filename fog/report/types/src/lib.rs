@@ -11,6 +11,7 @@ extern crate alloc;
 
 use ::prost::Message;
 use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use displaydoc::Display;
 use mc_attest_verifier_types::{prost, EvidenceKind, VerificationReport};
 use mc_crypto_digestible::Digestible;
 use serde::{Deserialize, Serialize};
@@ -117,16 +118,92 @@ pub struct ReportResponse {
 /// FogReportResponses (3) Take FogReportResponses to the offline machine, and
 /// use with transaction builder,     to create the transaction offline.
 /// (4) Take the constructed transaction to the online machine and submit to
-/// consensus.
+/// consensus. [FogReportBundle] is a serializable form of the responses for
+/// step (3).
 ///
 /// Note: there is no particular reason for this to be BTreeMap instead of
 /// HashMap, except that it is slightly more portable, only requiring the alloc
 /// crate.
 pub type FogReportResponses = BTreeMap<String, ReportResponse>;
 
+/// Fog report responses exported from an online machine, for building
+/// transactions to fog recipients on a machine without network access.
+///
+/// The bundle can be used until the earliest pubkey expiry of its reports.
+/// Transactions built with it must have a tombstone block no later than that,
+/// otherwise the transaction builder would have to lower the tombstone block
+/// below what the offline machine expects.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FogReportBundle {
+    /// The fog report responses, keyed by normalized fog report url.
+    pub responses: FogReportResponses,
+    /// When the responses were fetched, in seconds since the unix epoch.
+    pub fetched_at: u64,
+    /// The earliest pubkey expiry of the bundled reports, for display. This is
+    /// not trusted when the bundle is used, see
+    /// [FogReportBundle::min_pubkey_expiry].
+    pub pubkey_expiry: u64,
+}
+
+impl FogReportBundle {
+    /// Create a bundle from fetched responses.
+    pub fn new(responses: FogReportResponses, fetched_at: u64) -> Self {
+        let mut result = Self {
+            responses,
+            fetched_at,
+            pubkey_expiry: 0,
+        };
+        result.pubkey_expiry = result.min_pubkey_expiry();
+        result
+    }
+
+    /// The earliest pubkey expiry of the bundled reports, computed from the
+    /// responses. This is the last tombstone block the bundle can be used for.
+    pub fn min_pubkey_expiry(&self) -> u64 {
+        self.responses
+            .values()
+            .flat_map(|response| response.reports.iter())
+            .map(|report| report.pubkey_expiry)
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Check that the bundle can be used for a transaction with the given
+    /// tombstone block.
+    pub fn check_tombstone_block(&self, tombstone_block: u64) -> Result<(), FogReportBundleError> {
+        let pubkey_expiry = self.min_pubkey_expiry();
+        if pubkey_expiry < tombstone_block {
+            return Err(FogReportBundleError::Expired {
+                pubkey_expiry,
+                tombstone_block,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// An error that can occur when using a [FogReportBundle]
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+pub enum FogReportBundleError {
+    /**
+     * The fog report bundle expires at block {pubkey_expiry}, before the
+     * tombstone block {tombstone_block}. Fetch a new bundle or lower the
+     * tombstone block
+     */
+    Expired {
+        /// The pubkey expiry of the bundle
+        pubkey_expiry: u64,
+        /// The tombstone block of the transaction
+        tombstone_block: u64,
+    },
+    /// Invalid fog report url in the bundle: {0}
+    InvalidUrl(String),
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use alloc::{format, vec};
     use mc_crypto_digestible::MerlinTranscript;
     use mc_util_test_helper::{Rng, RngCore};
 
@@ -191,4 +268,55 @@ mod test {
             assert_eq!(epid_digest, dcap_digest);
         })
     }
+
+    fn bundle_with_expiries(expiries: &[u64]) -> FogReportBundle {
+        let responses = expiries
+            .iter()
+            .enumerate()
+            .map(|(i, pubkey_expiry)| {
+                let response = ReportResponse {
+                    reports: vec![Report {
+                        pubkey_expiry: *pubkey_expiry,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                };
+                (format!("fog://fog{i}.example.com"), response)
+            })
+            .collect();
+        FogReportBundle::new(responses, 0)
+    }
+
+    #[test]
+    fn bundle_expiry() {
+        let bundle = bundle_with_expiries(&[20, 10, 30]);
+        assert_eq!(bundle.pubkey_expiry, 10);
+        assert_eq!(bundle.check_tombstone_block(10), Ok(()));
+        assert_eq!(
+            bundle.check_tombstone_block(11),
+            Err(FogReportBundleError::Expired {
+                pubkey_expiry: 10,
+                tombstone_block: 11
+            })
+        );
+        assert!(FogReportBundle::default().check_tombstone_block(1).is_err());
+    }
+
+    #[test]
+    fn bundle_ignores_edited_pubkey_expiry() {
+        let mut bundle = bundle_with_expiries(&[20, 10, 30]);
+        bundle.pubkey_expiry = 100;
+
+        let json = serde_json::to_string(&bundle).unwrap();
+        let bundle: FogReportBundle = serde_json::from_str(&json).unwrap();
+        assert_eq!(bundle.pubkey_expiry, 100);
+        assert_eq!(bundle.min_pubkey_expiry(), 10);
+        assert_eq!(
+            bundle.check_tombstone_block(50),
+            Err(FogReportBundleError::Expired {
+                pubkey_expiry: 10,
+                tombstone_block: 50
+            })
+        );
+    }
 }
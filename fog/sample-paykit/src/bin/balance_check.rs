@@ -13,16 +13,22 @@
 //! signals to dump debug information and exit (because the previous balance
 //! didn't have the expected value). See fog-conformance-test documentation for
 //! more details.
+//!
+//! If a checkpoint file is given, the fog view protocol state is saved there
+//! after every balance check, and restored from there at startup, so that a
+//! later run only has to fetch new data.
 
 use clap::Parser;
-use mc_common::logger::{create_root_logger, log};
-use mc_fog_sample_paykit::ClientBuilder;
+use mc_common::logger::{create_root_logger, log, Logger};
+use mc_fog_sample_paykit::{Client, ClientBuilder};
 use mc_fog_uri::{FogLedgerUri, FogViewUri};
 use mc_util_uri::ConsensusClientUri;
 use serde_json::json;
 use std::{
-    io::{ErrorKind, Read},
-    path::PathBuf,
+    fs::{self, OpenOptions, Permissions},
+    io::{self, ErrorKind, Read, Write},
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
@@ -45,6 +51,12 @@ struct Config {
     /// View server URI
     #[clap(long, env = "MC_VIEW_URI")]
     pub view_uri: FogViewUri,
+
+    /// Optional path to a file to save the fog view protocol state in, and
+    /// resume from on the next run. It is as sensitive as the keyfile, and is
+    /// only readable by its owner.
+    #[clap(long, env = "MC_CHECKPOINT_FILE")]
+    pub checkpoint_file: Option<PathBuf>,
 }
 
 fn main() {
@@ -71,11 +83,18 @@ fn main() {
     )
     .build();
 
+    if let Some(checkpoint_file) = config.checkpoint_file.as_ref() {
+        restore_checkpoint(&mut sample_paykit, checkpoint_file, &logger);
+    }
+
     loop {
         // Do a balance check and print result on one line in stdout
         let (balance, block_count) = sample_paykit
             .check_balance()
             .expect("Failed to compute balance!");
+        if let Some(checkpoint_file) = config.checkpoint_file.as_ref() {
+            save_checkpoint(&sample_paykit, checkpoint_file, &logger);
+        }
         println!(
             "{}",
             json!({ "block_count": u64::from(block_count), "balance": balance})
@@ -100,3 +119,58 @@ fn main() {
         }
     }
 }
+
+/// Resume from the checkpoint file, if there is one. Any problem with it is
+/// logged, and we fall back to a full resync.
+fn restore_checkpoint(sample_paykit: &mut Client, checkpoint_file: &Path, logger: &Logger) {
+    let bytes = match fs::read(checkpoint_file) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == ErrorKind::NotFound => return,
+        Err(err) => {
+            log::warn!(
+                logger,
+                "Could not read checkpoint {:?}, doing a full resync: {}",
+                checkpoint_file,
+                err
+            );
+            return;
+        }
+    };
+    if let Err(err) = sample_paykit.restore_fog_view_checkpoint(&bytes) {
+        log::warn!(
+            logger,
+            "Could not restore checkpoint {:?}, doing a full resync: {}",
+            checkpoint_file,
+            err
+        );
+    }
+}
+
+/// Save the checkpoint, replacing the file atomically so that a crash can't
+/// leave a truncated checkpoint behind.
+fn save_checkpoint(sample_paykit: &Client, checkpoint_file: &Path, logger: &Logger) {
+    let partial_file = checkpoint_file.with_extension("partial");
+    if let Err(err) = write_private_file(&partial_file, &sample_paykit.fog_view_checkpoint())
+        .and_then(|_| fs::rename(&partial_file, checkpoint_file))
+    {
+        log::warn!(
+            logger,
+            "Could not save checkpoint {:?}: {}",
+            checkpoint_file,
+            err
+        );
+    }
+}
+
+/// Write a file which only its owner can read or write. The permissions are
+/// also reset if the file already exists.
+fn write_private_file(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    file.write_all(bytes)
+}
@@ -7,7 +7,7 @@ use mc_account_keys::{AccountKey, PublicAddress, ShortAddressHash, CHANGE_SUBADD
 use mc_common::logger::{log, Logger};
 use mc_crypto_keys::{KeyError, RistrettoPublic};
 use mc_transaction_core::{get_tx_out_shared_secret, subaddress_matches_tx_out, tx::TxOut};
-use mc_transaction_extra::{MemoDecodingError, MemoType};
+use mc_transaction_extra::{MemoDecodingError, MemoType, TextMemoError};
 use std::collections::HashMap;
 
 /// A handler object that holds a contacts list and tries to recieve and
@@ -116,6 +116,27 @@ impl MemoHandler {
                     Err(MemoHandlerError::UnknownSender)
                 }
             }
+            MemoType::AuthenticatedSenderWithText(memo) => {
+                if let Some(addr) = self.contacts.get(&memo.sender_address_hash()) {
+                    if bool::from(memo.validate(
+                        addr,
+                        &account_key.default_subaddress_view_private(),
+                        &tx_out.public_key,
+                    )) {
+                        let chunk = memo.decrypt(
+                            addr,
+                            &account_key.default_subaddress_view_private(),
+                            &tx_out.public_key,
+                        )?;
+                        log::trace!(self.logger, "Obtained a text memo chunk: {:?}", chunk);
+                        Ok(Some(memo_type))
+                    } else {
+                        Err(MemoHandlerError::FailedHmacValidation)
+                    }
+                } else {
+                    Err(MemoHandlerError::UnknownSender)
+                }
+            }
             MemoType::Destination(_) => {
                 if subaddress_matches_tx_out(account_key, CHANGE_SUBADDRESS_INDEX, tx_out)? {
                     Ok(Some(memo_type))
@@ -177,6 +198,9 @@ pub enum MemoHandlerError {
 
     /// Memo Decoding: {0}
     MemoDecode(MemoDecodingError),
+
+    /// Text memo: {0}
+    TextMemo(TextMemoError),
 }
 
 impl From<KeyError> for MemoHandlerError {
@@ -190,3 +214,9 @@ impl From<MemoDecodingError> for MemoHandlerError {
         Self::MemoDecode(src)
     }
 }
+
+impl From<TextMemoError> for MemoHandlerError {
+    fn from(src: TextMemoError) -> Self {
+        Self::TextMemo(src)
+    }
+}
@@ -24,7 +24,9 @@ use mc_fog_types::{
     BlockCount,
 };
 use mc_fog_view_connection::FogViewGrpcClient;
-use mc_fog_view_protocol::{FogViewConnection, UserPrivate, UserRngSet};
+use mc_fog_view_protocol::{
+    CheckpointError, FogViewConnection, UserPrivate, UserRngSet, UserRngSetCheckpoint,
+};
 use mc_transaction_core::{
     get_tx_out_shared_secret,
     onetime_keys::{recover_onetime_private_key, recover_public_subaddress_spend_key},
@@ -129,6 +131,10 @@ pub struct CachedTxData {
     /// BlockRanges that Fog View has reported as missed, that we have not yet
     /// completely downloaded.
     missed_block_ranges: Vec<common::BlockRange>,
+    /// Every TxOutRecord we have recovered and confirmed belongs to us, from
+    /// fog view or from scanning missed blocks. These are kept so that a
+    /// checkpoint can rebuild owned_tx_outs.
+    recovered_tx_out_records: Vec<TxOutRecord>,
     /// A logger object
     logger: Logger,
 }
@@ -151,10 +157,54 @@ impl CachedTxData {
             memo_handler: MemoHandler::new(address_book, logger.clone()),
             spsk_to_index,
             missed_block_ranges: Vec::new(),
+            recovered_tx_out_records: Vec::new(),
             logger,
         }
     }
 
+    /// Serialize the fog view protocol state, so that a later session can
+    /// resume polling from here with [Self::restore_checkpoint].
+    pub fn checkpoint(&self) -> Vec<u8> {
+        UserRngSetCheckpoint {
+            rng_set: self.rng_set.clone(),
+            missed_block_ranges: self.missed_block_ranges.clone(),
+            tx_out_records: self.recovered_tx_out_records.clone(),
+        }
+        .encode(&UserPrivate::from(&self.account_key))
+    }
+
+    /// Resume from a checkpoint made by [Self::checkpoint], rebuilding the
+    /// owned TxOuts it recovered. Their key images are checked again on the
+    /// next poll.
+    ///
+    /// This should be called before polling. If the checkpoint is corrupt or
+    /// belongs to another account, an error is returned and nothing changes,
+    /// so the next poll does a full resync.
+    pub fn restore_checkpoint(&mut self, bytes: &[u8]) -> StdResult<(), CheckpointError> {
+        let checkpoint =
+            UserRngSetCheckpoint::decode(bytes, &UserPrivate::from(&self.account_key))?;
+
+        self.rng_set = checkpoint.rng_set;
+        self.missed_block_ranges = checkpoint.missed_block_ranges;
+        self.recovered_tx_out_records = Vec::new();
+
+        let errors = self.consume_new_txo_records(checkpoint.tx_out_records.into_iter());
+        for err in errors {
+            log::warn!(
+                self.logger,
+                "Could not restore a TxOut from the checkpoint: {}",
+                err
+            );
+        }
+        log::debug!(
+            self.logger,
+            "Restored checkpoint at num_blocks {} with {} txos",
+            self.rng_set.get_highest_processed_block_count(),
+            self.owned_tx_outs.len()
+        );
+        Ok(())
+    }
+
     /// Get the last processed memo
     pub fn get_last_memo(&self) -> &StdResult<Option<MemoType>, MemoHandlerError> {
         self.memo_handler.get_last_memo()
@@ -381,7 +431,7 @@ impl CachedTxData {
     /// New TxOuts may come from downloading ledger material, or from fog view.
     /// This function confirms that they belong to us ("view key matching")
     /// and then adds them to the cache, sorting the cache and deduplicating it
-    /// as well.
+    /// as well. Only the records which belong to us are kept for checkpoints.
     pub fn consume_new_txo_records<Iter: Iterator<Item = TxOutRecord>>(
         &mut self,
         records: Iter,
@@ -389,7 +439,7 @@ impl CachedTxData {
         let mut errors = Vec::new();
 
         for record in records {
-            match OwnedTxOut::new(record, &self.account_key, &self.spsk_to_index) {
+            match OwnedTxOut::new(record.clone(), &self.account_key, &self.spsk_to_index) {
                 Ok(otxo) => {
                     // Insert into owned_tx_outs
                     log::trace!(
@@ -409,6 +459,8 @@ impl CachedTxData {
                         if prev.amount != otxo.amount {
                             log::warn!(self.logger, "Got two different amounts after view key scanning new and old versions of {}'th tx_out", otxo.global_index);
                         }
+                    } else {
+                        self.recovered_tx_out_records.push(record);
                     }
                     // Keep the invariant of key_image_data_completeness working
                     match otxo.status {
@@ -7,7 +7,7 @@ use crate::{
     error::{Error, Result},
     BlockInfo, MemoHandlerError, TransactionStatus,
 };
-use core::{result::Result as StdResult, str::FromStr};
+use core::result::Result as StdResult;
 use mc_account_keys::{AccountKey, PublicAddress};
 use mc_attestation_verifier::TrustedIdentity;
 use mc_blockchain_types::{BlockIndex, BlockVersion};
@@ -24,10 +24,11 @@ use mc_fog_ledger_connection::{
     FogUntrustedLedgerGrpcClient, OutputResultExtension,
 };
 use mc_fog_report_connection::GrpcFogReportConnection;
-use mc_fog_report_resolver::FogResolver;
+use mc_fog_report_resolver::{FogReportCache, FogResolver};
 use mc_fog_report_validation::FogPubkeyResolver;
 use mc_fog_types::{ledger::KeyImageResultCode, BlockCount};
 use mc_fog_view_connection::FogViewGrpcClient;
+use mc_fog_view_protocol::CheckpointError;
 use mc_rand::{CryptoRng, RngCore};
 use mc_transaction_builder::{
     EmptyMemoBuilder, InputCredentials, RTHMemoBuilder, ReservedSubaddresses,
@@ -39,7 +40,7 @@ use mc_transaction_core::{
 };
 use mc_transaction_extra::{MemoType, SenderMemoCredential, SignedContingentInput};
 use mc_util_telemetry::{block_span_builder, telemetry_static_key, tracer, Key, Span};
-use mc_util_uri::{ConnectionUri, FogUri};
+use mc_util_uri::ConnectionUri;
 use rand::Rng;
 use std::collections::{HashMap, HashSet};
 
@@ -59,6 +60,7 @@ pub struct Client {
     fog_key_image: FogKeyImageGrpcClient,
     fog_block: FogBlockGrpcClient,
     fog_report_conn: GrpcFogReportConnection,
+    fog_report_cache: FogReportCache,
     fog_identities: Vec<TrustedIdentity>,
     fog_untrusted: FogUntrustedLedgerGrpcClient,
     ring_size: usize,
@@ -99,6 +101,7 @@ impl Client {
             fog_key_image,
             fog_block,
             fog_report_conn,
+            fog_report_cache: FogReportCache::new(),
             fog_identities: fog_identities.into(),
             fog_untrusted,
             ring_size,
@@ -149,6 +152,19 @@ impl Client {
         Ok(self.compute_balance())
     }
 
+    /// Serialize the fog view protocol state, so that a later session can
+    /// resume balance checks from here instead of starting from scratch.
+    pub fn fog_view_checkpoint(&self) -> Vec<u8> {
+        self.tx_data.checkpoint()
+    }
+
+    /// Resume from a checkpoint made by [Self::fog_view_checkpoint]. This
+    /// should be called before the first balance check. On error, nothing
+    /// changes and the next balance check does a full resync.
+    pub fn restore_fog_view_checkpoint(&mut self, bytes: &[u8]) -> StdResult<(), CheckpointError> {
+        self.tx_data.restore_checkpoint(bytes)
+    }
+
     /// Compute the balance based on locally available data.
     /// Does NOT make any new network calls.
     ///
@@ -369,15 +385,10 @@ impl Client {
         let fee_map = self.get_fee_map(true)?;
 
         // Make fog resolver
-        let fog_uris = [&self.account_key.change_subaddress(), target_address]
-            .iter()
-            .filter_map(|addr| addr.fog_report_url())
-            .map(FogUri::from_str)
-            .collect::<core::result::Result<Vec<_>, _>>()?;
-        let fog_responses = self
-            .fog_report_conn
-            .fetch_fog_reports(fog_uris.into_iter())?;
-        let fog_resolver = FogResolver::new(fog_responses, &self.fog_identities)?;
+        let fog_resolver = self.get_fog_resolver(
+            &[&self.account_key.change_subaddress(), target_address],
+            tombstone_block,
+        )?;
 
         let ring_signer = LocalRingSigner::from(&self.account_key);
 
@@ -442,15 +453,8 @@ impl Client {
         let block_version = BlockVersion::try_from(self.tx_data.get_latest_block_version())?;
 
         // Make fog resolver
-        let fog_uris = self
-            .account_key
-            .fog_report_url()
-            .map(FogUri::from_str)
-            .transpose()?;
-        let fog_responses = self
-            .fog_report_conn
-            .fetch_fog_reports(fog_uris.into_iter())?;
-        let fog_resolver = FogResolver::new(fog_responses, &self.fog_identities)?;
+        let fog_resolver =
+            self.get_fog_resolver(&[&self.account_key.change_subaddress()], tombstone_block)?;
 
         let (ring, membership_proofs): (Vec<TxOut>, Vec<TxOutMembershipProof>) =
             ring.into_iter().unzip();
@@ -581,15 +585,8 @@ impl Client {
         }
 
         // Make fog resolver
-        let fog_uris = self
-            .account_key
-            .fog_report_url()
-            .map(FogUri::from_str)
-            .transpose()?;
-        let fog_responses = self
-            .fog_report_conn
-            .fetch_fog_reports(fog_uris.into_iter())?;
-        let fog_resolver = FogResolver::new(fog_responses, &self.fog_identities)?;
+        let fog_resolver =
+            self.get_fog_resolver(&[&self.account_key.change_subaddress()], tombstone_block)?;
 
         let block_version = BlockVersion::try_from(self.tx_data.get_latest_block_version())?;
 
@@ -898,6 +895,27 @@ impl Client {
         Ok(rings_with_proofs)
     }
 
+    /// Make a fog resolver for the given recipients, only fetching the fog
+    /// reports which are not cached, or whose pubkeys expire before the
+    /// tombstone block.
+    fn get_fog_resolver(
+        &mut self,
+        recipients: &[&PublicAddress],
+        tombstone_block: BlockIndex,
+    ) -> Result<FogResolver> {
+        self.fog_report_cache.prune(tombstone_block);
+        let fog_uris = self
+            .fog_report_cache
+            .urls_to_fetch(recipients.iter().copied(), tombstone_block)?;
+        if !fog_uris.is_empty() {
+            let fog_responses = self
+                .fog_report_conn
+                .fetch_fog_reports(fog_uris.into_iter())?;
+            self.fog_report_cache.insert(fog_responses)?;
+        }
+        Ok(self.fog_report_cache.resolver(&self.fog_identities))
+    }
+
     /// Gets the approximate number of blocks in the ledger and adds
     /// self.new_tx_block_attempts value, to compute an appropriate
     /// tombstone block value
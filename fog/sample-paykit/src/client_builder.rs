@@ -47,6 +47,10 @@ pub struct ClientBuilder {
     fog_ingest_sigstruct: Option<Signature>,
     fog_ledger_sigstruct: Option<Signature>,
     fog_view_sigstruct: Option<Signature>,
+
+    // Optional identity trusted for every attested service, in place of the
+    // sigstructs
+    trusted_identity: Option<TrustedIdentity>,
 }
 
 impl ClientBuilder {
@@ -73,6 +77,7 @@ impl ClientBuilder {
             fog_ingest_sigstruct: None,
             fog_ledger_sigstruct: None,
             fog_view_sigstruct: None,
+            trusted_identity: None,
         }
     }
 
@@ -125,6 +130,16 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets an identity to trust for every attested service, in place of the
+    /// sigstructs and the build-time measurements. This is for enclaves that
+    /// aren't built and measured, like the in-process enclaves of a local
+    /// test network.
+    #[must_use]
+    pub fn trusted_identity(mut self, identity: Option<TrustedIdentity>) -> Self {
+        self.trusted_identity = identity;
+        self
+    }
+
     /// Create the client
     pub fn build(self) -> Client {
         let grpc_env = Arc::new(
@@ -257,7 +272,9 @@ impl ClientBuilder {
 
     // Get consensus attestation identity (dynamic or build time, MRSIGNER)
     fn consensus_identity(&self) -> TrustedIdentity {
-        if let Some(signature) = self.consensus_sigstruct.as_ref() {
+        if let Some(identity) = self.trusted_identity.as_ref() {
+            identity.clone()
+        } else if let Some(signature) = self.consensus_sigstruct.as_ref() {
             let mr_signer_identity = TrustedMrSignerIdentity::new(
                 signature.mrsigner().into(),
                 signature.product_id(),
@@ -273,7 +290,9 @@ impl ClientBuilder {
 
     // Get fog ingest attestation identity (dynamic or build time, MRSIGNER)
     fn fog_ingest_identity(&self) -> TrustedIdentity {
-        if let Some(signature) = self.fog_ingest_sigstruct.as_ref() {
+        if let Some(identity) = self.trusted_identity.as_ref() {
+            identity.clone()
+        } else if let Some(signature) = self.fog_ingest_sigstruct.as_ref() {
             let mr_signer_identity = TrustedMrSignerIdentity::new(
                 signature.mrsigner().into(),
                 signature.product_id(),
@@ -289,7 +308,9 @@ impl ClientBuilder {
 
     // Get fog ledger attestation identity (dynamic or build time, MRSIGNER)
     fn fog_ledger_identity(&self) -> TrustedIdentity {
-        if let Some(signature) = self.fog_ledger_sigstruct.as_ref() {
+        if let Some(identity) = self.trusted_identity.as_ref() {
+            identity.clone()
+        } else if let Some(signature) = self.fog_ledger_sigstruct.as_ref() {
             let mr_signer_identity = TrustedMrSignerIdentity::new(
                 signature.mrsigner().into(),
                 signature.product_id(),
@@ -305,7 +326,9 @@ impl ClientBuilder {
 
     // Get fog view attestation identity (dynamic or build time, MRSIGNER)
     fn fog_view_identity(&self) -> TrustedIdentity {
-        if let Some(signature) = self.fog_view_sigstruct.as_ref() {
+        if let Some(identity) = self.trusted_identity.as_ref() {
+            identity.clone()
+        } else if let Some(signature) = self.fog_view_sigstruct.as_ref() {
             let mr_signer_identity = TrustedMrSignerIdentity::new(
                 signature.mrsigner().into(),
                 signature.product_id(),
@@ -9,6 +9,7 @@ use mc_crypto_keys::KeyError;
 use mc_fog_enclave_connection::Error as EnclaveConnectionError;
 use mc_fog_ledger_connection::{Error as LedgerConnectionError, KeyImageQueryError};
 use mc_fog_report_connection::Error as FogResolutionError;
+use mc_fog_report_resolver::FogReportCacheError;
 use mc_fog_types::view::FogTxOutError;
 use mc_fog_view_protocol::TxOutPollingError;
 use mc_transaction_builder::{SignedContingentInputBuilderError, TxBuilderError};
@@ -113,6 +114,9 @@ pub enum Error {
     /// Could not obtain fog service public key: {0}
     FogResolution(FogResolutionError),
 
+    /// Fog report cache: {0}
+    FogReportCache(FogReportCacheError),
+
     /// Failed to decode ledger server response: {0}
     Conversion(ConversionError),
 
@@ -213,6 +217,12 @@ impl From<FogResolutionError> for Error {
     }
 }
 
+impl From<FogReportCacheError> for Error {
+    fn from(x: FogReportCacheError) -> Error {
+        Error::FogReportCache(x)
+    }
+}
+
 impl From<UriParseError> for Error {
     fn from(src: UriParseError) -> Self {
         Self::Uri(src)
@@ -4,6 +4,7 @@
 
 use clap::Parser;
 use serde::Serialize;
+use std::path::PathBuf;
 
 /// Configuration parameters for the Fog SQL recovery DB cleanup task.
 #[derive(Clone, Parser, Serialize)]
@@ -13,6 +14,28 @@ pub struct SqlRecoveryDbCleanupConfig {
     #[clap(long)]
     pub egress_keys: bool,
 
+    /// If set, archives the ETxOutRecords of retired, fully scanned ingress
+    /// keys to compressed files in this directory, and clears them from the
+    /// DB.
+    #[clap(long)]
+    pub archive_dir: Option<PathBuf>,
+
+    /// Only archive ingress keys whose last scanned block is at least this many
+    /// blocks behind the highest known block.
+    #[clap(long, default_value = "100000")]
+    pub archive_min_age_blocks: u64,
+
+    /// If set, prunes the user events of decommissioned ingest invocations
+    /// which stopped more than this many blocks behind the highest known
+    /// block. Only invocations whose ingress key was retired and already had
+    /// its ETxOutRecords archived (see --archive-dir) are pruned.
+    #[clap(long)]
+    pub user_events_horizon_blocks: Option<u64>,
+
+    /// If set, restores the ETxOutRecords in this archive file to the DB.
+    #[clap(long)]
+    pub restore_archive: Option<PathBuf>,
+
     /// If set to true, prints out any DB entries that would be cleared by the
     /// command and doesn't execute the deletion.
     #[clap(long)]
@@ -3,9 +3,59 @@
 //! A cleanup utility for the Fog SQL DB.
 
 use chrono::{prelude::*, Duration};
+use displaydoc::Display;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use mc_common::logger::{log, Logger};
-use mc_fog_recovery_db_iface::RecoveryDb;
-use mc_fog_sql_recovery_db::SqlRecoveryDb;
+use mc_fog_recovery_db_iface::{
+    BlockRange, IngressPublicKeyRecord, IngressPublicKeyRecordFilters, RecoveryDb,
+};
+use mc_fog_sql_recovery_db::{
+    ArchivedIngestedBlock, Error as SqlRecoveryDbError, RetentionStats, SqlRecoveryDb,
+};
+use prost::Message;
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// The number of blocks to read from or write to the DB at once while
+/// archiving.
+const ARCHIVE_BATCH_BLOCKS: u64 = 1000;
+
+/// An error while archiving or restoring ETxOutRecords.
+#[derive(Debug, Display)]
+enum ArchiveError {
+    /// Recovery db: {0}
+    Db(SqlRecoveryDbError),
+
+    /// IO: {0}
+    Io(std::io::Error),
+
+    /// Decode: {0}
+    Decode(prost::DecodeError),
+
+    /// Archive file already exists: {0:?}
+    AlreadyExists(PathBuf),
+}
+
+impl From<SqlRecoveryDbError> for ArchiveError {
+    fn from(src: SqlRecoveryDbError) -> Self {
+        Self::Db(src)
+    }
+}
+
+impl From<std::io::Error> for ArchiveError {
+    fn from(src: std::io::Error) -> Self {
+        Self::Io(src)
+    }
+}
+
+impl From<prost::DecodeError> for ArchiveError {
+    fn from(src: prost::DecodeError) -> Self {
+        Self::Decode(src)
+    }
+}
 
 /// Contains helper methods that cleanup the Fog SQL DB.
 pub struct DbCleaner {
@@ -71,4 +121,263 @@ impl DbCleaner {
             }
         }
     }
+
+    /// Archives the ETxOutRecords of retired ingress keys which were fully
+    /// scanned at least `min_age_blocks` ago to compressed files in
+    /// `archive_dir`, then clears them from the DB. Each key is written to a
+    /// file named after the key and the range of blocks archived.
+    ///
+    /// In a dry run, only reports how much data would be archived.
+    pub fn archive_retired_ingress_keys(
+        &self,
+        is_dry_run: bool,
+        archive_dir: &Path,
+        min_age_blocks: u64,
+    ) {
+        let Some(highest_known_block_index) = self
+            .db
+            .get_highest_known_block_index()
+            .expect("Could not retrieve highest known block index.")
+        else {
+            log::info!(self.logger, "No blocks have been ingested yet");
+            return;
+        };
+
+        let filters = IngressPublicKeyRecordFilters {
+            should_include_lost_keys: true,
+            should_include_retired_keys: true,
+            should_only_include_unexpired_keys: false,
+        };
+        let records = self
+            .db
+            .get_ingress_key_records(0, &filters)
+            .expect("Could not retrieve ingress keys.");
+
+        let mut total = RetentionStats::default();
+        for record in records
+            .iter()
+            .filter(|record| is_archivable(record, highest_known_block_index, min_age_blocks))
+        {
+            let stats = self
+                .db
+                .get_ingested_block_data_stats(&record.key)
+                .expect("Could not retrieve ingested block data stats.");
+            if stats.rows == 0 {
+                continue;
+            }
+            log::info!(
+                self.logger,
+                "Ingress key {:?} has {} blocks with {} bytes of ETxOutRecords to archive",
+                record.key,
+                stats.rows,
+                stats.bytes,
+            );
+
+            if !is_dry_run {
+                if let Err(err) = self.archive_ingress_key(record, archive_dir) {
+                    log::error!(
+                        self.logger,
+                        "Could not archive ingress key {:?}: {}",
+                        record.key,
+                        err
+                    );
+                    continue;
+                }
+            }
+            total.rows += stats.rows;
+            total.bytes += stats.bytes;
+        }
+
+        log::info!(
+            self.logger,
+            "{} {} blocks, reclaiming {} bytes",
+            if is_dry_run {
+                "Would archive"
+            } else {
+                "Archived"
+            },
+            total.rows,
+            total.bytes,
+        );
+    }
+
+    /// Prunes the user events of decommissioned ingest invocations which
+    /// stopped more than `horizon_blocks` blocks behind the highest known
+    /// block, and whose ingress key was already archived by
+    /// [Self::archive_retired_ingress_keys].
+    ///
+    /// In a dry run, only reports how many events would be pruned.
+    pub fn prune_user_events(&self, is_dry_run: bool, horizon_blocks: u64) {
+        let Some(highest_known_block_index) = self
+            .db
+            .get_highest_known_block_index()
+            .expect("Could not retrieve highest known block index.")
+        else {
+            log::info!(self.logger, "No blocks have been ingested yet");
+            return;
+        };
+        let horizon_block = highest_known_block_index.saturating_sub(horizon_blocks);
+
+        let stats = self
+            .db
+            .prune_user_events(horizon_block, is_dry_run)
+            .expect("Could not prune user events.");
+
+        log::info!(
+            self.logger,
+            "{} {} user events of ingest invocations which stopped before block {}, reclaiming {} bytes",
+            if is_dry_run { "Would prune" } else { "Pruned" },
+            stats.rows,
+            horizon_block,
+            stats.bytes,
+        );
+    }
+
+    /// Restores the ETxOutRecords in an archive file written by
+    /// [Self::archive_retired_ingress_keys] to the DB.
+    ///
+    /// In a dry run, only reports how many blocks the archive holds.
+    pub fn restore_archive(&self, is_dry_run: bool, archive_path: &Path) {
+        match self.try_restore_archive(is_dry_run, archive_path) {
+            Ok(restored) => log::info!(
+                self.logger,
+                "{} {} blocks from {:?}",
+                if is_dry_run {
+                    "Would restore"
+                } else {
+                    "Restored"
+                },
+                restored,
+                archive_path,
+            ),
+            Err(err) => log::error!(
+                self.logger,
+                "Could not restore archive {:?}: {}",
+                archive_path,
+                err
+            ),
+        }
+    }
+
+    /// Runs VACUUM on the tables the retention tasks shrink, so the space they
+    /// freed can be reused.
+    pub fn vacuum(&self) {
+        self.db
+            .vacuum_retention_tables()
+            .expect("Could not vacuum retention tables.");
+    }
+
+    fn archive_ingress_key(
+        &self,
+        record: &IngressPublicKeyRecord,
+        archive_dir: &Path,
+    ) -> Result<(), ArchiveError> {
+        let key_bytes: &[u8] = record.key.as_ref();
+        let key_hex = hex::encode(key_bytes);
+        let partial_path = archive_dir.join(format!("{key_hex}.pb.gz.partial"));
+
+        fs::create_dir_all(archive_dir)?;
+        let mut encoder = GzEncoder::new(
+            BufWriter::new(File::create(&partial_path)?),
+            Compression::default(),
+        );
+
+        let mut archived_range: Option<BlockRange> = None;
+        for batch in block_batches(&record.get_block_range()) {
+            for block in self
+                .db
+                .get_archivable_ingested_blocks(&record.key, &batch)?
+            {
+                let range = archived_range
+                    .get_or_insert_with(|| BlockRange::new(block.block_index, block.block_index));
+                range.end_block = block.block_index + 1;
+                encoder.write_all(&block.encode_length_delimited_to_vec())?;
+            }
+        }
+
+        let file = encoder
+            .finish()?
+            .into_inner()
+            .map_err(|err| err.into_error())?;
+        file.sync_all()?;
+
+        let Some(archived_range) = archived_range else {
+            fs::remove_file(&partial_path)?;
+            return Ok(());
+        };
+
+        let path = archive_dir.join(format!(
+            "{key_hex}-{}-{}.pb.gz",
+            archived_range.start_block, archived_range.end_block
+        ));
+        if path.exists() {
+            return Err(ArchiveError::AlreadyExists(path));
+        }
+        fs::rename(&partial_path, &path)?;
+        log::info!(
+            self.logger,
+            "Archived blocks {} of ingress key {:?} to {:?}",
+            archived_range,
+            record.key,
+            path,
+        );
+
+        // Only clear the data once the archive is safely on disk.
+        for batch in block_batches(&archived_range) {
+            self.db.clear_ingested_block_data(&record.key, &batch)?;
+        }
+        Ok(())
+    }
+
+    fn try_restore_archive(
+        &self,
+        is_dry_run: bool,
+        archive_path: &Path,
+    ) -> Result<u64, ArchiveError> {
+        let mut bytes = Vec::new();
+        GzDecoder::new(BufReader::new(File::open(archive_path)?)).read_to_end(&mut bytes)?;
+
+        let mut buf = &bytes[..];
+        let mut blocks = Vec::new();
+        while !buf.is_empty() {
+            blocks.push(ArchivedIngestedBlock::decode_length_delimited(&mut buf)?);
+        }
+        if is_dry_run {
+            return Ok(blocks.len() as u64);
+        }
+
+        let mut restored = 0;
+        for chunk in blocks.chunks(ARCHIVE_BATCH_BLOCKS as usize) {
+            restored += self.db.restore_ingested_block_data(chunk)?;
+        }
+        Ok(restored)
+    }
+}
+
+/// Whether an ingress key is retired, and every block it promised to scan was
+/// scanned at least `min_age_blocks` before the highest known block.
+fn is_archivable(
+    record: &IngressPublicKeyRecord,
+    highest_known_block_index: u64,
+    min_age_blocks: u64,
+) -> bool {
+    let Some(last_scanned_block) = record.last_scanned_block else {
+        return false;
+    };
+    record.status.retired
+        && last_scanned_block + 1 >= record.get_block_range().end_block
+        && last_scanned_block.saturating_add(min_age_blocks) <= highest_known_block_index
+}
+
+/// Splits a block range into batches of at most [ARCHIVE_BATCH_BLOCKS] blocks.
+fn block_batches(block_range: &BlockRange) -> impl Iterator<Item = BlockRange> {
+    let end_block = block_range.end_block;
+    (block_range.start_block..end_block)
+        .step_by(ARCHIVE_BATCH_BLOCKS as usize)
+        .map(move |start_block| {
+            BlockRange::new(
+                start_block,
+                (start_block + ARCHIVE_BATCH_BLOCKS).min(end_block),
+            )
+        })
 }
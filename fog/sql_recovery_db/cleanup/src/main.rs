@@ -28,4 +28,26 @@ fn main() {
     if config.egress_keys {
         db_cleaner.cleanup_egress_keys(config.dry_run, Duration::days(EXPIRATION_DAYS));
     }
+
+    if let Some(archive_dir) = config.archive_dir.as_ref() {
+        db_cleaner.archive_retired_ingress_keys(
+            config.dry_run,
+            archive_dir,
+            config.archive_min_age_blocks,
+        );
+    }
+
+    if let Some(horizon_blocks) = config.user_events_horizon_blocks {
+        db_cleaner.prune_user_events(config.dry_run, horizon_blocks);
+    }
+
+    if let Some(archive_path) = config.restore_archive.as_ref() {
+        db_cleaner.restore_archive(config.dry_run, archive_path);
+    }
+
+    if !config.dry_run
+        && (config.archive_dir.is_some() || config.user_events_horizon_blocks.is_some())
+    {
+        db_cleaner.vacuum();
+    }
 }
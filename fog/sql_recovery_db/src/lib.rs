@@ -8,12 +8,14 @@ extern crate diesel;
 extern crate diesel_migrations;
 
 pub use error::Error;
+pub use retention::{ArchivedIngestedBlock, RetentionStats};
 
 pub mod test_utils;
 
 mod error;
 mod models;
 mod proto_types;
+mod retention;
 mod schema;
 mod sql_types;
 
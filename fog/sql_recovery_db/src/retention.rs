@@ -0,0 +1,491 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Retention support for the recovery database: archiving the ETxOutRecords of
+//! ingress keys that are no longer needed, and pruning old user events.
+//!
+//! Archiving keeps every `ingested_blocks` row and only clears its
+//! `proto_ingested_block_data`, so the block numbers, txo counts and
+//! timestamps that `get_ingestable_ranges`, `get_last_scanned_block_index`
+//! and `search_user_events` rely on are unchanged.
+
+use crate::{our_retry, proto_types::ProtoIngestedBlockData, schema, Error, SqlRecoveryDb};
+use diesel::{
+    dsl::{count_star, not, sql},
+    prelude::*,
+    sql_types::{BigInt, Bool},
+};
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_types::{common::BlockRange, ETxOutRecord};
+use prost::Message;
+
+/// Maximal number of ids to use in one `IN (...)` filter.
+const MAX_IDS_PER_QUERY: usize = crate::SQL_MAX_PARAMS / 2;
+
+/// Filter for ingest invocations whose ingress key is retired, was used to
+/// scan at least one block, and has had the ETxOutRecords of all its blocks
+/// archived.
+const ARCHIVED_INGRESS_KEY_FILTER: &str = "\
+    ingest_invocations.ingress_public_key IN \
+        (SELECT ingress_public_key FROM ingress_keys WHERE retired) \
+    AND EXISTS (SELECT 1 FROM ingested_blocks \
+        WHERE ingested_blocks.ingress_public_key = ingest_invocations.ingress_public_key) \
+    AND NOT EXISTS (SELECT 1 FROM ingested_blocks \
+        WHERE ingested_blocks.ingress_public_key = ingest_invocations.ingress_public_key \
+        AND octet_length(ingested_blocks.proto_ingested_block_data) > 0)";
+
+/// An ingested block whose ETxOutRecords were moved out of the database.
+///
+/// Archive files are a sequence of these, each length-delimited.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct ArchivedIngestedBlock {
+    /// The ingress key the block was scanned with.
+    #[prost(bytes, tag = 1)]
+    pub ingress_public_key: Vec<u8>,
+
+    /// The invocation that produced this block record.
+    #[prost(int64, tag = 2)]
+    pub ingest_invocation_id: i64,
+
+    /// The block index.
+    #[prost(uint64, tag = 3)]
+    pub block_index: u64,
+
+    /// The cumulative txo count from the block header.
+    #[prost(uint64, tag = 4)]
+    pub cumulative_txo_count: u64,
+
+    /// The block signature timestamp, in seconds since the unix epoch.
+    #[prost(uint64, tag = 5)]
+    pub block_signature_timestamp: u64,
+
+    /// The ETxOutRecords fog ingest emitted for this block.
+    #[prost(message, repeated, tag = 6)]
+    pub e_tx_out_records: Vec<ETxOutRecord>,
+}
+
+/// How many rows, and roughly how many bytes, a retention task affects.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RetentionStats {
+    /// The number of rows.
+    pub rows: u64,
+
+    /// The number of bytes of data in those rows.
+    pub bytes: u64,
+}
+
+impl SqlRecoveryDb {
+    /// Count the ingested blocks for an ingress key which still hold their
+    /// ETxOutRecords, and the size of that data.
+    pub fn get_ingested_block_data_stats(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+    ) -> Result<RetentionStats, Error> {
+        our_retry(self.get_retries(), || {
+            self.get_ingested_block_data_stats_retriable(ingress_key)
+        })
+    }
+
+    /// Get the ingested blocks for an ingress key in a block range which still
+    /// hold their ETxOutRecords, ordered by block index.
+    pub fn get_archivable_ingested_blocks(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_range: &BlockRange,
+    ) -> Result<Vec<ArchivedIngestedBlock>, Error> {
+        our_retry(self.get_retries(), || {
+            self.get_archivable_ingested_blocks_retriable(ingress_key, block_range)
+        })
+    }
+
+    /// Clear the ETxOutRecords of the ingested blocks for an ingress key in a
+    /// block range. This should only be called once they have been archived.
+    ///
+    /// Returns the number of blocks cleared.
+    pub fn clear_ingested_block_data(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_range: &BlockRange,
+    ) -> Result<u64, Error> {
+        our_retry(self.get_retries(), || {
+            self.clear_ingested_block_data_retriable(ingress_key, block_range)
+        })
+    }
+
+    /// Put archived ETxOutRecords back into their ingested block rows.
+    ///
+    /// Blocks whose row is missing, or which still hold data, are skipped.
+    /// Returns the number of blocks restored.
+    pub fn restore_ingested_block_data(
+        &self,
+        blocks: &[ArchivedIngestedBlock],
+    ) -> Result<u64, Error> {
+        our_retry(self.get_retries(), || {
+            self.restore_ingested_block_data_retriable(blocks)
+        })
+    }
+
+    /// Delete the user events of decommissioned ingest invocations whose last
+    /// ingested block is before `horizon_block`, and whose ingress key was
+    /// retired and had all of its ETxOutRecords archived.
+    ///
+    /// The new and decommission events of an invocation are always deleted
+    /// together, and missing blocks events are never deleted. Clients which
+    /// start syncing after this will not learn about the RNGs of pruned
+    /// invocations, and so will not find their TxOuts from before the horizon.
+    /// Since those TxOuts were archived, they could not be found from the
+    /// database anyway until the archive is restored.
+    ///
+    /// When `is_dry_run` is set nothing is deleted, and the returned stats
+    /// describe what would have been.
+    pub fn prune_user_events(
+        &self,
+        horizon_block: u64,
+        is_dry_run: bool,
+    ) -> Result<RetentionStats, Error> {
+        our_retry(self.get_retries(), || {
+            self.prune_user_events_retriable(horizon_block, is_dry_run)
+        })
+    }
+
+    /// Run VACUUM on the tables retention tasks shrink. Autovacuum is disabled
+    /// on these tables, so this is what lets postgres reuse the space.
+    pub fn vacuum_retention_tables(&self) -> Result<(), Error> {
+        let conn = &mut self.pool.get()?;
+        diesel::sql_query("VACUUM ingested_blocks").execute(conn)?;
+        diesel::sql_query("VACUUM user_events").execute(conn)?;
+        Ok(())
+    }
+
+    fn get_ingested_block_data_stats_retriable(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+    ) -> Result<RetentionStats, Error> {
+        use schema::ingested_blocks::dsl;
+
+        let conn = &mut self.pool.get()?;
+        let key_bytes: &[u8] = ingress_key.as_ref();
+        let (rows, bytes) = dsl::ingested_blocks
+            .filter(dsl::ingress_public_key.eq(key_bytes))
+            .filter(has_block_data())
+            .select((
+                count_star(),
+                sql::<BigInt>("COALESCE(SUM(octet_length(proto_ingested_block_data)), 0)"),
+            ))
+            .first::<(i64, i64)>(conn)?;
+
+        Ok(RetentionStats {
+            rows: rows as u64,
+            bytes: bytes as u64,
+        })
+    }
+
+    fn get_archivable_ingested_blocks_retriable(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_range: &BlockRange,
+    ) -> Result<Vec<ArchivedIngestedBlock>, Error> {
+        use schema::ingested_blocks::dsl;
+
+        let conn = &mut self.pool.get()?;
+        let key_bytes: &[u8] = ingress_key.as_ref();
+        let rows = dsl::ingested_blocks
+            .filter(dsl::ingress_public_key.eq(key_bytes))
+            .filter(dsl::block_number.ge(block_range.start_block as i64))
+            .filter(dsl::block_number.lt(block_range.end_block as i64))
+            .filter(has_block_data())
+            .order_by(dsl::block_number)
+            .select((
+                dsl::ingest_invocation_id,
+                dsl::block_number,
+                dsl::cumulative_txo_count,
+                dsl::block_signature_timestamp,
+                dsl::proto_ingested_block_data,
+            ))
+            .load::<(i64, i64, i64, i64, Vec<u8>)>(conn)?;
+
+        rows.into_iter()
+            .map(
+                |(
+                    ingest_invocation_id,
+                    block_number,
+                    cumulative_txo_count,
+                    block_signature_timestamp,
+                    proto,
+                )| {
+                    let proto = ProtoIngestedBlockData::decode(&*proto)?;
+                    Ok(ArchivedIngestedBlock {
+                        ingress_public_key: key_bytes.to_vec(),
+                        ingest_invocation_id,
+                        block_index: block_number as u64,
+                        cumulative_txo_count: cumulative_txo_count as u64,
+                        block_signature_timestamp: block_signature_timestamp as u64,
+                        e_tx_out_records: proto.e_tx_out_records,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    fn clear_ingested_block_data_retriable(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_range: &BlockRange,
+    ) -> Result<u64, Error> {
+        use schema::ingested_blocks::dsl;
+
+        let conn = &mut self.pool.get()?;
+        let key_bytes: &[u8] = ingress_key.as_ref();
+        let empty_proto = ProtoIngestedBlockData::default().encode_to_vec();
+        let cleared = diesel::update(
+            dsl::ingested_blocks
+                .filter(dsl::ingress_public_key.eq(key_bytes))
+                .filter(dsl::block_number.ge(block_range.start_block as i64))
+                .filter(dsl::block_number.lt(block_range.end_block as i64))
+                .filter(has_block_data()),
+        )
+        .set(dsl::proto_ingested_block_data.eq(empty_proto))
+        .execute(conn)?;
+
+        Ok(cleared as u64)
+    }
+
+    fn restore_ingested_block_data_retriable(
+        &self,
+        blocks: &[ArchivedIngestedBlock],
+    ) -> Result<u64, Error> {
+        use schema::ingested_blocks::dsl;
+
+        let conn = &mut self.pool.get()?;
+        conn.build_transaction().read_write().run(|conn| {
+            let mut restored = 0;
+            for block in blocks {
+                let proto = ProtoIngestedBlockData {
+                    e_tx_out_records: block.e_tx_out_records.clone(),
+                };
+                restored += diesel::update(
+                    dsl::ingested_blocks
+                        .filter(dsl::ingress_public_key.eq(&block.ingress_public_key[..]))
+                        .filter(dsl::block_number.eq(block.block_index as i64))
+                        .filter(dsl::ingest_invocation_id.eq(block.ingest_invocation_id))
+                        .filter(not(has_block_data())),
+                )
+                .set(dsl::proto_ingested_block_data.eq(proto.encode_to_vec()))
+                .execute(conn)? as u64;
+            }
+            Ok(restored)
+        })
+    }
+
+    fn prune_user_events_retriable(
+        &self,
+        horizon_block: u64,
+        is_dry_run: bool,
+    ) -> Result<RetentionStats, Error> {
+        let conn = &mut self.pool.get()?;
+        conn.build_transaction().read_write().run(|conn| {
+            let invocation_ids = {
+                use schema::ingest_invocations::dsl;
+                dsl::ingest_invocations
+                    .filter(dsl::decommissioned.eq(true))
+                    .filter(
+                        sql::<Bool>(
+                            "COALESCE((SELECT MAX(block_number) FROM ingested_blocks WHERE ingested_blocks.ingest_invocation_id = ingest_invocations.id), ingest_invocations.start_block) < ",
+                        )
+                        .bind::<BigInt, _>(horizon_block as i64),
+                    )
+                    .filter(sql::<Bool>(ARCHIVED_INGRESS_KEY_FILTER))
+                    .select(dsl::id)
+                    .load::<i64>(conn)?
+            };
+
+            let mut stats = RetentionStats::default();
+            for ids in invocation_ids.chunks(MAX_IDS_PER_QUERY) {
+                use schema::user_events::dsl;
+
+                let events = || {
+                    dsl::user_events.filter(
+                        dsl::new_ingest_invocation_id
+                            .eq_any(ids)
+                            .or(dsl::decommission_ingest_invocation_id.eq_any(ids)),
+                    )
+                };
+
+                let (rows, bytes) = events()
+                    .select((
+                        count_star(),
+                        sql::<BigInt>("COALESCE(SUM(pg_column_size(user_events.*)), 0)"),
+                    ))
+                    .first::<(i64, i64)>(conn)?;
+                stats.rows += rows as u64;
+                stats.bytes += bytes as u64;
+
+                if !is_dry_run {
+                    diesel::delete(events()).execute(conn)?;
+                }
+            }
+            Ok(stats)
+        })
+    }
+}
+
+/// Filter for ingested blocks which still hold their ETxOutRecords.
+fn has_block_data() -> diesel::expression::SqlLiteral<Bool> {
+    sql::<Bool>("octet_length(proto_ingested_block_data) > 0")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SqlRecoveryDbTestContext;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_crypto_keys::RistrettoPublic;
+    use mc_fog_recovery_db_iface::{FogUserEvent, RecoveryDb};
+    use mc_fog_test_infra::db_tests::{random_block, random_kex_rng_pubkey};
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test_with_logger]
+    fn test_archive_and_restore_ingested_blocks(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let db_test_context = SqlRecoveryDbTestContext::new(logger);
+        let db = db_test_context.get_db_instance();
+
+        let ingress_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng));
+        db.new_ingress_key(&ingress_key, 0).unwrap();
+        let invoc_id = db
+            .new_ingest_invocation(None, &ingress_key, &random_kex_rng_pubkey(&mut rng), 0)
+            .unwrap();
+
+        let mut records = Vec::new();
+        for block_index in 0..10 {
+            let (block, block_records) = random_block(&mut rng, block_index, 5);
+            db.add_block_data(&invoc_id, &block, 0, &block_records)
+                .unwrap();
+            records.push(block_records);
+        }
+
+        let stats = db.get_ingested_block_data_stats(&ingress_key).unwrap();
+        assert_eq!(stats.rows, 10);
+        assert!(stats.bytes > 0);
+
+        let range = BlockRange::new(2, 6);
+        let archived = db
+            .get_archivable_ingested_blocks(&ingress_key, &range)
+            .unwrap();
+        assert_eq!(archived.len(), 4);
+        assert_eq!(archived[0].block_index, 2);
+        assert_eq!(archived[0].e_tx_out_records, records[2]);
+
+        assert_eq!(
+            db.clear_ingested_block_data(&ingress_key, &range).unwrap(),
+            4
+        );
+        assert_eq!(
+            db.get_ingested_block_data_stats(&ingress_key).unwrap().rows,
+            6
+        );
+        assert!(db
+            .get_archivable_ingested_blocks(&ingress_key, &range)
+            .unwrap()
+            .is_empty());
+
+        // The blocks are still known, but have no records.
+        assert_eq!(
+            db.get_tx_outs_by_block_and_key(ingress_key, 3).unwrap(),
+            Some(vec![])
+        );
+        assert_eq!(
+            db.get_last_scanned_block_index(&ingress_key).unwrap(),
+            Some(9)
+        );
+        assert_eq!(db.get_cumulative_txo_count_for_block(3).unwrap(), Some(0));
+
+        assert_eq!(db.restore_ingested_block_data(&archived).unwrap(), 4);
+        assert_eq!(db.restore_ingested_block_data(&archived).unwrap(), 0);
+        assert_eq!(
+            db.get_tx_outs_by_block_and_key(ingress_key, 3).unwrap(),
+            Some(records[3].clone())
+        );
+    }
+
+    #[test_with_logger]
+    fn test_prune_user_events(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let db_test_context = SqlRecoveryDbTestContext::new(logger);
+        let db = db_test_context.get_db_instance();
+
+        let old_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng));
+        db.new_ingress_key(&old_key, 0).unwrap();
+        let ingress_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng));
+        db.new_ingress_key(&ingress_key, 5).unwrap();
+
+        // An old decommissioned invocation with its own ingress key, a newer
+        // decommissioned one, and a live one.
+        let old_id = db
+            .new_ingest_invocation(None, &old_key, &random_kex_rng_pubkey(&mut rng), 0)
+            .unwrap();
+        for block_index in 0..5 {
+            let (block, records) = random_block(&mut rng, block_index, 1);
+            db.add_block_data(&old_id, &block, 0, &records).unwrap();
+        }
+        let new_id = db
+            .new_ingest_invocation(
+                Some(old_id),
+                &ingress_key,
+                &random_kex_rng_pubkey(&mut rng),
+                5,
+            )
+            .unwrap();
+        for block_index in 5..10 {
+            let (block, records) = random_block(&mut rng, block_index, 1);
+            db.add_block_data(&new_id, &block, 0, &records).unwrap();
+        }
+        let live_id = db
+            .new_ingest_invocation(
+                Some(new_id),
+                &ingress_key,
+                &random_kex_rng_pubkey(&mut rng),
+                10,
+            )
+            .unwrap();
+
+        let (events, _) = db.search_user_events(0).unwrap();
+        assert_eq!(events.len(), 5);
+
+        // The old invocation is only pruned once its ingress key is retired and
+        // its ETxOutRecords are archived.
+        assert_eq!(db.prune_user_events(5, false).unwrap().rows, 0);
+        db.retire_ingress_key(&old_key, true).unwrap();
+        assert_eq!(db.prune_user_events(5, false).unwrap().rows, 0);
+        db.clear_ingested_block_data(&old_key, &BlockRange::new(0, 3))
+            .unwrap();
+        assert_eq!(db.prune_user_events(5, false).unwrap().rows, 0);
+        db.clear_ingested_block_data(&old_key, &BlockRange::new(3, 5))
+            .unwrap();
+
+        let stats = db.prune_user_events(5, true).unwrap();
+        assert_eq!(stats.rows, 2);
+        assert!(stats.bytes > 0);
+        assert_eq!(db.search_user_events(0).unwrap().0.len(), 5);
+
+        assert_eq!(db.prune_user_events(5, false).unwrap(), stats);
+        let (events, _) = db.search_user_events(0).unwrap();
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|event| match event {
+            FogUserEvent::NewRngRecord(rng_record) => rng_record.ingest_invocation_id != *old_id,
+            FogUserEvent::DecommissionIngestInvocation(decommission) =>
+                decommission.ingest_invocation_id != *old_id,
+            FogUserEvent::MissingBlocks(_) => true,
+        }));
+
+        // Pruning doesn't change the ingestable ranges.
+        let ranges = db.get_ingestable_ranges().unwrap();
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(ranges[0].last_ingested_block, Some(4));
+        assert_eq!(ranges[2].id, live_id);
+
+        // The newer invocation is old enough, but its ingress key was not
+        // archived.
+        assert_eq!(db.prune_user_events(10, false).unwrap().rows, 0);
+    }
+}
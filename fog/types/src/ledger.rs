@@ -106,6 +106,19 @@ pub struct CheckKeyImagesRequest {
     pub queries: Vec<KeyImageQuery>,
 }
 
+/// The CheckKeyImagesRequestAAD structure, which may be passed as AAD when
+/// making an attested key image check.
+///
+/// Unlike the request itself, this is visible to the untrusted side of the
+/// server.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct CheckKeyImagesRequestAAD {
+    /// The first block index to search key images in. Key images spent before
+    /// it may be reported as not spent.
+    #[prost(fixed64, tag = "1")]
+    pub start_from_block_index: u64,
+}
+
 /// Query about a particular key image
 #[derive(Message, Eq, PartialEq)]
 pub struct KeyImageQuery {
@@ -22,7 +22,10 @@ use mc_fog_api::{
     view::{FogViewRouterRequest, FogViewRouterResponse},
     view_grpc::FogViewRouterApiClient,
 };
-use mc_fog_types::view::{QueryRequest, QueryRequestAAD, QueryResponse};
+use mc_fog_types::{
+    common::BlockRange,
+    view::{QueryRequest, QueryRequestAAD, QueryResponse},
+};
 use mc_fog_uri::{ConnectionUri, FogViewRouterUri};
 use mc_rand::McRng;
 use mc_util_grpc::ConnectionUriGrpcioChannel;
@@ -147,6 +150,49 @@ impl FogViewRouterGrpcClient {
         start_from_block_index: u64,
         search_keys: Vec<Vec<u8>>,
     ) -> Result<QueryResponse, Error> {
+        let (response, _unavailable_block_ranges) = self
+            .send_query(
+                start_from_user_event_id,
+                start_from_block_index,
+                search_keys,
+                false,
+            )
+            .await?;
+        Ok(response)
+    }
+
+    /// Makes streaming requests to the fog view router service, allowing the
+    /// router to leave out the shards which don't answer in time.
+    ///
+    /// Returns the response, and the block ranges of the shards which didn't
+    /// answer. The response doesn't cover those blocks, so the query should be
+    /// repeated later for them.
+    ///
+    /// The view protocol's polling client doesn't use this yet: it queries
+    /// through FogViewConnection, which only FogViewGrpcClient implements.
+    /// Letting it resume from partial responses is left for a later change.
+    pub async fn query_partial(
+        &mut self,
+        start_from_user_event_id: i64,
+        start_from_block_index: u64,
+        search_keys: Vec<Vec<u8>>,
+    ) -> Result<(QueryResponse, Vec<BlockRange>), Error> {
+        self.send_query(
+            start_from_user_event_id,
+            start_from_block_index,
+            search_keys,
+            true,
+        )
+        .await
+    }
+
+    async fn send_query(
+        &mut self,
+        start_from_user_event_id: i64,
+        start_from_block_index: u64,
+        search_keys: Vec<Vec<u8>>,
+        allow_partial_results: bool,
+    ) -> Result<(QueryResponse, Vec<BlockRange>), Error> {
         log::trace!(self.logger, "Query was called");
         if !self.is_attested() {
             let verification_report = self.attest().await;
@@ -182,17 +228,23 @@ impl FogViewRouterGrpcClient {
         };
         let mut request = FogViewRouterRequest::new();
         request.set_query(msg);
+        request.set_allow_partial_results(allow_partial_results);
 
         self.request_sender
             .send((request, grpcio::WriteFlags::default()))
             .await?;
 
-        let message = self
+        let mut response = self
             .response_receiver
             .try_next()
             .await?
-            .ok_or(Error::ResponseNotReceived)?
-            .take_query();
+            .ok_or(Error::ResponseNotReceived)?;
+        let message = response.take_query();
+        let unavailable_block_ranges = response
+            .take_unavailable_block_ranges()
+            .into_iter()
+            .map(BlockRange::from)
+            .collect();
 
         {
             let attest_cipher = self
@@ -202,7 +254,7 @@ impl FogViewRouterGrpcClient {
 
             let plaintext_bytes = attest_cipher.decrypt(message.get_aad(), message.get_data())?;
             let plaintext_response: QueryResponse = mc_util_serial::decode(&plaintext_bytes)?;
-            Ok(plaintext_response)
+            Ok((plaintext_response, unavailable_block_ranges))
         }
     }
 }
@@ -108,7 +108,17 @@ pub(crate) fn get_block_data(
     responses.sort_unstable_by_key(|response| response.block_range.start_block);
 
     let mut result = BlockData::default();
+    // The responses must cover the blocks from the origin block on.
+    let mut covered_end_block = 0;
     for response in responses.iter() {
+        // A shard before this one didn't answer, e.g. because the router
+        // returned partial results, so the blocks it is responsible for might
+        // not be processed.
+        if response.block_range.start_block > covered_end_block {
+            return result;
+        }
+        covered_end_block = core::cmp::max(covered_end_block, response.block_range.end_block);
+
         let shard_highest_processed_block_count =
             response.query_response.highest_processed_block_count;
         if shard_highest_processed_block_count > result.highest_processed_block_count {
@@ -1507,4 +1517,57 @@ mod get_block_data_tests {
             incomplete_timestamp_2
         );
     }
+
+    #[test]
+    fn missing_response_in_the_middle() {
+        let mut decrypted_query_responses = Vec::new();
+
+        // Make the first response fully processed.
+        let query_response = create_query_response(3, 3);
+        let block_range = BlockRange::new(0, 3);
+        decrypted_query_responses.push(DecryptedMultiViewStoreQueryResponse {
+            query_response,
+            block_range,
+        });
+
+        // There is no response for the blocks 3 to 6, so the fully processed
+        // response after them must not be used.
+        let query_response = create_query_response(9, 9);
+        let block_range = BlockRange::new(6, 9);
+        decrypted_query_responses.push(DecryptedMultiViewStoreQueryResponse {
+            query_response,
+            block_range,
+        });
+
+        let result = get_block_data(decrypted_query_responses.as_mut(), &[]);
+
+        assert_eq!(result.highest_processed_block_count, 3);
+        assert_eq!(result.highest_processed_block_signature_timestamp, 3);
+    }
+
+    #[test]
+    fn missing_first_response() {
+        let mut decrypted_query_responses = Vec::new();
+
+        // There is no response for the blocks 0 to 3, so the fully processed
+        // responses after them must not be used.
+        let query_response = create_query_response(6, 6);
+        let block_range = BlockRange::new(3, 6);
+        decrypted_query_responses.push(DecryptedMultiViewStoreQueryResponse {
+            query_response,
+            block_range,
+        });
+
+        let query_response = create_query_response(9, 9);
+        let block_range = BlockRange::new(6, 9);
+        decrypted_query_responses.push(DecryptedMultiViewStoreQueryResponse {
+            query_response,
+            block_range,
+        });
+
+        let result = get_block_data(decrypted_query_responses.as_mut(), &[]);
+
+        assert_eq!(result.highest_processed_block_count, 0);
+        assert_eq!(result.highest_processed_block_signature_timestamp, 0);
+    }
 }
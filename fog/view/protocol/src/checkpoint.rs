@@ -0,0 +1,267 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Persistence for the fog view protocol state of a client, so that it can
+//! resume polling where it left off instead of re-deriving everything.
+//!
+//! A checkpoint contains the KexRng secrets, which let anyone find the user's
+//! TxOuts, so it should be stored as carefully as the view private key.
+
+use crate::{user_rng_set::UserRngSet, UserPrivate};
+use alloc::vec::Vec;
+use displaydoc::Display;
+use mc_crypto_hashes::{Blake2b256, Digest};
+use mc_fog_kex_rng::{Error as KexRngError, StoredRng, VersionedKexRng};
+use mc_fog_types::{common::BlockRange, view::TxOutRecord};
+use mc_util_serial::DecodeError;
+use prost::Message;
+
+/// The current checkpoint format version.
+const CHECKPOINT_VERSION: u32 = 1;
+
+/// Domain separator for the checkpoint digest.
+const CHECKPOINT_DIGEST_DOMAIN_TAG: &[u8] = b"mc_fog_view_user_rng_set_checkpoint";
+
+/// The state a client needs to resume fog view polling.
+#[derive(Clone, Default)]
+pub struct UserRngSetCheckpoint {
+    /// The rngs, and the user event id and block count polled up to.
+    pub rng_set: UserRngSet,
+
+    /// Missed block ranges reported by fog view which the client has not yet
+    /// downloaded and scanned.
+    pub missed_block_ranges: Vec<BlockRange>,
+
+    /// The TxOutRecords recovered so far, so that the client can rebuild its
+    /// set of owned TxOuts without asking fog view for them again.
+    pub tx_out_records: Vec<TxOutRecord>,
+}
+
+impl UserRngSetCheckpoint {
+    /// Serialize the checkpoint. The result is bound to the user's view key,
+    /// and carries a digest so that corruption can be detected when decoding.
+    pub fn encode(&self, upriv: &UserPrivate) -> Vec<u8> {
+        let mut rngs: Vec<ProtoRng> = self
+            .rng_set
+            .get_rngs()
+            .iter()
+            .map(|(nonce, rng)| ProtoRng {
+                nonce: nonce.clone(),
+                rng: StoredRng::from(rng.clone()),
+            })
+            .collect();
+        // Hash map iteration order is arbitrary, sort to make the encoding stable.
+        rngs.sort_by(|a, b| a.nonce.cmp(&b.nonce));
+
+        let checkpoint = ProtoCheckpoint {
+            rngs,
+            highest_processed_block_count: self.rng_set.get_highest_processed_block_count().into(),
+            next_start_from_user_event_id: self.rng_set.get_next_start_from_user_event_id(),
+            missed_block_ranges: self.missed_block_ranges.clone(),
+            tx_out_records: self.tx_out_records.clone(),
+        }
+        .encode_to_vec();
+
+        ProtoEnvelope {
+            version: CHECKPOINT_VERSION,
+            digest: checkpoint_digest(upriv, &checkpoint),
+            checkpoint,
+        }
+        .encode_to_vec()
+    }
+
+    /// Deserialize a checkpoint produced by [Self::encode] with the same view
+    /// key.
+    ///
+    /// Any error means the checkpoint can't be trusted, and the client should
+    /// fall back to a full resync from a default checkpoint.
+    pub fn decode(bytes: &[u8], upriv: &UserPrivate) -> Result<Self, CheckpointError> {
+        let envelope = ProtoEnvelope::decode(bytes)?;
+        if envelope.version != CHECKPOINT_VERSION {
+            return Err(CheckpointError::UnsupportedVersion(envelope.version));
+        }
+        if envelope.digest != checkpoint_digest(upriv, &envelope.checkpoint) {
+            return Err(CheckpointError::DigestMismatch);
+        }
+
+        let checkpoint = ProtoCheckpoint::decode(&envelope.checkpoint[..])?;
+        let mut rng_set = UserRngSet::new();
+        for ProtoRng { nonce, rng } in checkpoint.rngs {
+            rng_set.insert_rng(nonce, VersionedKexRng::try_from(rng)?);
+        }
+        rng_set.set_highest_processed_block_count(checkpoint.highest_processed_block_count);
+        rng_set.set_next_start_from_user_event_id(checkpoint.next_start_from_user_event_id);
+
+        Ok(Self {
+            rng_set,
+            missed_block_ranges: checkpoint.missed_block_ranges,
+            tx_out_records: checkpoint.tx_out_records,
+        })
+    }
+}
+
+/// An error decoding a checkpoint
+#[derive(Debug, Display)]
+pub enum CheckpointError {
+    /// Could not decode checkpoint: {0}
+    Decode(DecodeError),
+    /// Unsupported checkpoint version: {0}
+    UnsupportedVersion(u32),
+    /// Checkpoint digest mismatch, it is corrupt or belongs to another account
+    DigestMismatch,
+    /// Could not restore rng: {0}
+    KexRng(KexRngError),
+}
+
+impl From<DecodeError> for CheckpointError {
+    fn from(src: DecodeError) -> Self {
+        Self::Decode(src)
+    }
+}
+
+impl From<KexRngError> for CheckpointError {
+    fn from(src: KexRngError) -> Self {
+        Self::KexRng(src)
+    }
+}
+
+/// The digest binding a checkpoint to a view key.
+fn checkpoint_digest(upriv: &UserPrivate, checkpoint: &[u8]) -> Vec<u8> {
+    let mut hasher = Blake2b256::new();
+    hasher.update(CHECKPOINT_DIGEST_DOMAIN_TAG);
+    hasher.update(upriv.get_view_pubkey().to_bytes());
+    hasher.update(checkpoint);
+    hasher.finalize().to_vec()
+}
+
+/// The outer, versioned wrapper of a checkpoint.
+#[derive(Clone, Eq, PartialEq, Message)]
+struct ProtoEnvelope {
+    #[prost(uint32, tag = 1)]
+    version: u32,
+    #[prost(bytes, tag = 2)]
+    checkpoint: Vec<u8>,
+    #[prost(bytes, tag = 3)]
+    digest: Vec<u8>,
+}
+
+#[derive(Clone, Eq, PartialEq, Message)]
+struct ProtoCheckpoint {
+    #[prost(message, repeated, tag = 1)]
+    rngs: Vec<ProtoRng>,
+    #[prost(uint64, tag = 2)]
+    highest_processed_block_count: u64,
+    #[prost(int64, tag = 3)]
+    next_start_from_user_event_id: i64,
+    #[prost(message, repeated, tag = 4)]
+    missed_block_ranges: Vec<BlockRange>,
+    #[prost(message, repeated, tag = 5)]
+    tx_out_records: Vec<TxOutRecord>,
+}
+
+#[derive(Clone, Eq, PartialEq, Message)]
+struct ProtoRng {
+    #[prost(bytes, tag = 1)]
+    nonce: Vec<u8>,
+    #[prost(message, required, tag = 2)]
+    rng: StoredRng,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use mc_crypto_keys::{Ristretto, RistrettoPrivate, RistrettoPublic};
+    use mc_fog_kex_rng::{BufferedRng, KexRngPubkey, LatestKexRngCore};
+    use mc_fog_types::view::RngRecord;
+    use mc_util_from_random::FromRandom;
+    use rand_core::SeedableRng;
+    use rand_hc::Hc128Rng;
+
+    fn rng_record(rng: &mut Hc128Rng, ingest_invocation_id: i64) -> RngRecord {
+        let egress_key = RistrettoPrivate::from_random(rng);
+        RngRecord {
+            ingest_invocation_id,
+            pubkey: KexRngPubkey::from_public_key::<LatestKexRngCore, Ristretto>(
+                &RistrettoPublic::from(&egress_key),
+            ),
+            start_block: 0,
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let mut rng = Hc128Rng::from_seed([7u8; 32]);
+        let upriv = UserPrivate::random(&mut rng);
+
+        let mut rng_set = UserRngSet::new();
+        rng_set
+            .ingest_rng_record(&upriv, &rng_record(&mut rng, 1))
+            .unwrap();
+        rng_set
+            .ingest_rng_record(&upriv, &rng_record(&mut rng, 2))
+            .unwrap();
+        for (nonce, mut kex_rng) in rng_set.get_rngs().clone() {
+            kex_rng.advance();
+            rng_set.insert_rng(nonce, kex_rng);
+        }
+        rng_set.set_highest_processed_block_count(42);
+        rng_set.set_next_start_from_user_event_id(7);
+
+        let checkpoint = UserRngSetCheckpoint {
+            rng_set,
+            missed_block_ranges: vec![BlockRange::new(3, 5)],
+            tx_out_records: vec![Default::default()],
+        };
+        let bytes = checkpoint.encode(&upriv);
+        assert_eq!(bytes, checkpoint.encode(&upriv));
+
+        let decoded = UserRngSetCheckpoint::decode(&bytes, &upriv).unwrap();
+        assert_eq!(decoded.encode(&upriv), bytes);
+        assert_eq!(decoded.rng_set.get_rngs().len(), 2);
+        for (nonce, kex_rng) in decoded.rng_set.get_rngs() {
+            let original = &checkpoint.rng_set.get_rngs()[nonce];
+            assert_eq!(kex_rng.index(), original.index());
+            assert_eq!(kex_rng.peek(), original.peek());
+        }
+        assert_eq!(
+            u64::from(decoded.rng_set.get_highest_processed_block_count()),
+            42
+        );
+        assert_eq!(decoded.rng_set.get_next_start_from_user_event_id(), 7);
+        assert_eq!(decoded.missed_block_ranges, checkpoint.missed_block_ranges);
+        assert_eq!(decoded.tx_out_records, checkpoint.tx_out_records);
+    }
+
+    #[test]
+    fn test_checkpoint_corruption() {
+        let mut rng = Hc128Rng::from_seed([8u8; 32]);
+        let upriv = UserPrivate::random(&mut rng);
+
+        let mut rng_set = UserRngSet::new();
+        rng_set
+            .ingest_rng_record(&upriv, &rng_record(&mut rng, 1))
+            .unwrap();
+        let bytes = UserRngSetCheckpoint {
+            rng_set,
+            ..Default::default()
+        }
+        .encode(&upriv);
+
+        // A checkpoint for another account is rejected.
+        let other = UserPrivate::random(&mut rng);
+        assert!(matches!(
+            UserRngSetCheckpoint::decode(&bytes, &other),
+            Err(CheckpointError::DigestMismatch)
+        ));
+
+        // Flipping any byte is detected.
+        for index in 0..bytes.len() {
+            let mut corrupt = bytes.clone();
+            corrupt[index] ^= 0x01;
+            assert!(UserRngSetCheckpoint::decode(&corrupt, &upriv).is_err());
+        }
+
+        // So is truncation.
+        assert!(UserRngSetCheckpoint::decode(&bytes[..bytes.len() - 1], &upriv).is_err());
+    }
+}
@@ -4,6 +4,9 @@
 
 extern crate alloc;
 
+mod checkpoint;
+pub use checkpoint::{CheckpointError, UserRngSetCheckpoint};
+
 mod polling;
 pub use polling::{FogViewConnection, TxOutPollingError};
 
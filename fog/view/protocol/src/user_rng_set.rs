@@ -40,6 +40,11 @@ impl UserRngSet {
         &self.rngs
     }
 
+    /// Set the rng for a nonce, replacing any existing one
+    pub(crate) fn insert_rng(&mut self, nonce: Vec<u8>, rng: VersionedKexRng) {
+        self.rngs.insert(nonce, rng);
+    }
+
     pub fn get_highest_processed_block_count(&self) -> BlockCount {
         BlockCount::from(self.highest_processed_block_count)
     }
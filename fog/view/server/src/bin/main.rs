@@ -3,7 +3,7 @@
 
 //! MobileCoin Fog View target
 use mc_common::{logger::log, time::SystemTimeProvider};
-use mc_fog_sql_recovery_db::SqlRecoveryDb;
+use mc_fog_recovery_db::AnyRecoveryDb;
 use mc_fog_view_enclave::{SgxViewEnclave, ENCLAVE_FILE};
 use mc_fog_view_server::{config, config::MobileAcctViewConfig, server::ViewServer};
 use mc_util_cli::ParserWithBuildInfo;
@@ -18,7 +18,7 @@ fn main() {
     let config = MobileAcctViewConfig::parse();
 
     let database_url = env::var("DATABASE_URL").expect("Missing DATABASE_URL environment variable");
-    let recovery_db = SqlRecoveryDb::new_from_url(
+    let recovery_db = AnyRecoveryDb::new_from_url(
         &database_url,
         config.postgres_config.clone(),
         logger.clone(),
@@ -139,6 +139,26 @@ pub struct FogViewRouterConfig {
     /// hours).
     #[clap(long, default_value = "86400", value_parser = parse_duration_in_seconds, env = "MC_CLIENT_AUTH_TOKEN_MAX_LIFETIME")]
     pub client_auth_token_max_lifetime: Duration,
+
+    /// How the router queries the Fog View Stores
+    #[clap(flatten)]
+    pub shard_query_config: ShardQueryConfig,
+}
+
+/// Configuration parameters for the Fog View Router's queries to the Fog View
+/// Stores.
+#[derive(Clone, Copy, Debug, Parser, Serialize)]
+pub struct ShardQueryConfig {
+    /// Deadline in ms for a single query to a Fog View Store. A store which
+    /// doesn't answer in time is retried like a store which failed.
+    #[clap(long = "shard-query-timeout-ms", default_value = "2000", value_parser = parse_duration_in_millis, env = "MC_SHARD_QUERY_TIMEOUT_MS")]
+    pub shard_query_timeout: Duration,
+
+    /// Delay in ms after which a query is also sent to the other replicas of a
+    /// shard, i.e. the stores with the same block range, if the first replica
+    /// hasn't answered yet. The first successful answer is used.
+    #[clap(long = "shard-hedge-delay-ms", default_value = "100", value_parser = parse_duration_in_millis, env = "MC_SHARD_HEDGE_DELAY_MS")]
+    pub shard_hedge_delay: Duration,
 }
 
 /// A FogViewRouterServer can either fulfill streaming or unary requests, and
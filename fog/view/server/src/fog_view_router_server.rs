@@ -110,6 +110,7 @@ where
                     view_grpc::create_fog_view_router_api(FogViewRouterService::new(
                         enclave.clone(),
                         shards,
+                        config.shard_query_config,
                         config.chain_id.clone(),
                         client_authenticator,
                         logger.clone(),
@@ -132,6 +133,7 @@ where
                     view_grpc::create_fog_view_api(FogViewRouterService::new(
                         enclave.clone(),
                         shards,
+                        config.shard_query_config,
                         config.chain_id.clone(),
                         client_authenticator,
                         logger.clone(),
@@ -1,6 +1,8 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
-use crate::{fog_view_router_server::Shard, router_request_handler, SVC_COUNTERS};
+use crate::{
+    config::ShardQueryConfig, fog_view_router_server::Shard, router_request_handler, SVC_COUNTERS,
+};
 use futures::{executor::block_on, FutureExt, TryFutureExt};
 use grpcio::{DuplexSink, RequestStream, RpcContext, UnarySink};
 use mc_attest_api::attest;
@@ -22,6 +24,7 @@ where
 {
     enclave: E,
     shards: Arc<RwLock<Vec<Shard>>>,
+    shard_query_config: ShardQueryConfig,
     chain_id: String,
     /// GRPC request authenticator.
     authenticator: Arc<dyn Authenticator + Send + Sync>,
@@ -37,6 +40,7 @@ impl<E: ViewEnclaveProxy> FogViewRouterService<E> {
     pub fn new(
         enclave: E,
         shards: Arc<RwLock<Vec<Shard>>>,
+        shard_query_config: ShardQueryConfig,
         chain_id: String,
         authenticator: Arc<dyn Authenticator + Send + Sync>,
         logger: Logger,
@@ -44,6 +48,7 @@ impl<E: ViewEnclaveProxy> FogViewRouterService<E> {
         Self {
             enclave,
             shards,
+            shard_query_config,
             chain_id,
             authenticator,
             logger,
@@ -70,6 +75,7 @@ where
             let future = router_request_handler::handle_requests(
                 method_name,
                 shards.clone(),
+                self.shard_query_config,
                 self.enclave.clone(),
                 requests,
                 responses,
@@ -132,10 +138,14 @@ where
             // This will block the async API. We should use some sort of differentiator...
             let shards = self.shards.read().expect("RwLock poisoned");
             let tracer = tracer!();
+            // The unary API has no way to tell the client which block ranges
+            // are not covered, so it never returns partial results.
             let result = block_on(router_request_handler::handle_query_request(
                 request,
+                false,
                 self.enclave.clone(),
                 shards.clone(),
+                self.shard_query_config,
                 self.logger.clone(),
                 &tracer,
             ))
@@ -31,4 +31,14 @@ lazy_static! {
         "Auth requests to stores"
     )
     .expect("metric cannot be created");
+    pub static ref SHARD_QUERY_HEDGES: IntCounter = register_int_counter!(
+        "fog_view_router_shard_query_hedges",
+        "Queries sent to another replica of a shard after the hedge delay"
+    )
+    .expect("metric cannot be created");
+    pub static ref PARTIAL_QUERY_RESPONSES: IntCounter = register_int_counter!(
+        "fog_view_router_partial_query_responses",
+        "Query responses which don't cover every shard"
+    )
+    .expect("metric cannot be created");
 }
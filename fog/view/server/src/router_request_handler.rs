@@ -1,15 +1,22 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
 use crate::{
+    config::ShardQueryConfig,
     error::{router_server_err_to_rpc_status, RouterServerError},
     fog_view_router_server::Shard,
     metrics::{
-        AUTH_CLIENT_REQUESTS, CLIENT_QUERY_RETRIES, ROUTER_QUERY_REQUESTS, STORE_QUERY_REQUESTS,
+        AUTH_CLIENT_REQUESTS, CLIENT_QUERY_RETRIES, PARTIAL_QUERY_RESPONSES, ROUTER_QUERY_REQUESTS,
+        SHARD_QUERY_HEDGES, STORE_QUERY_REQUESTS,
     },
     shard_responses_processor, SVC_COUNTERS,
 };
-use futures::{future::try_join_all, SinkExt, TryStreamExt};
-use grpcio::{ChannelBuilder, DuplexSink, RequestStream, RpcStatus, WriteFlags};
+use futures::{
+    future::{select, try_join_all, Either},
+    stream::FuturesUnordered,
+    SinkExt, StreamExt, TryStreamExt,
+};
+use futures_timer::Delay;
+use grpcio::{CallOption, ChannelBuilder, DuplexSink, RequestStream, RpcStatus, WriteFlags};
 use mc_attest_api::attest;
 use mc_attest_enclave_api::SealedClientMessage;
 use mc_common::logger::{log, Logger};
@@ -17,20 +24,28 @@ use mc_fog_api::{
     view::{FogViewRouterRequest, FogViewRouterResponse, MultiViewStoreQueryRequest},
     view_grpc::FogViewStoreApiClient,
 };
-use mc_fog_types::view::MultiViewStoreQueryResponse;
+use mc_fog_types::{
+    common::BlockRange,
+    view::{MultiViewStoreQueryResponse, MultiViewStoreQueryResponseStatus},
+};
 use mc_fog_uri::FogViewStoreUri;
 use mc_fog_view_enclave_api::ViewEnclaveProxy;
 use mc_util_grpc::{rpc_invalid_arg_error, ConnectionUriGrpcioChannel, ResponseStatus};
 use mc_util_metrics::GrpcMethodName;
 use mc_util_telemetry::{create_context, tracer, BoxedTracer, FutureExt, Tracer};
 use mc_util_uri::ConnectionUri;
-use std::{sync::Arc, time::Instant};
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 const RETRY_COUNT: usize = 3;
 
 /// Handles a series of requests sent by the Fog Router client.
 pub async fn handle_requests<E>(
     method_name: GrpcMethodName,
     shards: Vec<Shard>,
+    shard_query_config: ShardQueryConfig,
     enclave: E,
     mut requests: RequestStream<FogViewRouterRequest>,
     mut responses: DuplexSink<FogViewRouterResponse>,
@@ -41,7 +56,14 @@ where
 {
     while let Some(request) = requests.try_next().await? {
         let _timer = SVC_COUNTERS.req_impl(&method_name);
-        let result = handle_request(request, shards.clone(), enclave.clone(), logger.clone()).await;
+        let result = handle_request(
+            request,
+            shards.clone(),
+            shard_query_config,
+            enclave.clone(),
+            logger.clone(),
+        )
+        .await;
 
         // Perform prometheus logic before the match statement to ensure that
         // this logic is executed.
@@ -63,6 +85,7 @@ where
 pub async fn handle_request<E>(
     mut request: FogViewRouterRequest,
     shards: Vec<Shard>,
+    shard_query_config: ShardQueryConfig,
     enclave: E,
     logger: Logger,
 ) -> Result<FogViewRouterResponse, RpcStatus>
@@ -75,9 +98,18 @@ where
             handle_auth_request(enclave, request.take_auth(), logger)
         })
     } else if request.has_query() {
-        handle_query_request(request.take_query(), enclave, shards, logger, &tracer)
-            .with_context(create_context(&tracer, "router_query"))
-            .await
+        let allow_partial_results = request.get_allow_partial_results();
+        handle_query_request(
+            request.take_query(),
+            allow_partial_results,
+            enclave,
+            shards,
+            shard_query_config,
+            logger,
+            &tracer,
+        )
+        .with_context(create_context(&tracer, "router_query"))
+        .await
     } else {
         let rpc_status = rpc_invalid_arg_error(
             "Inavlid FogViewRouterRequest request",
@@ -108,10 +140,15 @@ where
 }
 
 /// Handles a client's query request.
+///
+/// If `allow_partial_results` is set, shards which don't answer after all
+/// retries are left out of the response instead of failing the request.
 pub async fn handle_query_request<E>(
     query: attest::Message,
+    allow_partial_results: bool,
     enclave: E,
     shards: Vec<Shard>,
+    shard_query_config: ShardQueryConfig,
     logger: Logger,
     tracer: &BoxedTracer,
 ) -> Result<FogViewRouterResponse, RpcStatus>
@@ -128,10 +165,12 @@ where
             )
         })?;
 
-    let query_responses = get_query_responses(
+    let (query_responses, unavailable_block_ranges) = get_query_responses(
         sealed_query.clone(),
         enclave.clone(),
         shards.clone(),
+        shard_query_config,
+        allow_partial_results,
         logger.clone(),
     )
     .with_context(create_context(tracer, "router_get_query_responses"))
@@ -151,15 +190,24 @@ where
 
     let mut response = FogViewRouterResponse::new();
     response.set_query(query_response.into());
+    for block_range in &unavailable_block_ranges {
+        response
+            .mut_unavailable_block_ranges()
+            .push(block_range.into());
+    }
     Ok(response)
 }
 
+/// Gets the query responses of the shards, and the block ranges of the shards
+/// which didn't answer.
 async fn get_query_responses<E>(
     sealed_query: SealedClientMessage,
     enclave: E,
     mut shards: Vec<Shard>,
+    shard_query_config: ShardQueryConfig,
+    allow_partial_results: bool,
     logger: Logger,
-) -> Result<Vec<MultiViewStoreQueryResponse>, RpcStatus>
+) -> Result<(Vec<MultiViewStoreQueryResponse>, Vec<BlockRange>), RpcStatus>
 where
     E: ViewEnclaveProxy,
 {
@@ -169,7 +217,15 @@ where
         shards.len()
     );
 
-    let mut query_responses: Vec<MultiViewStoreQueryResponse> = Vec::with_capacity(shards.len());
+    let mut block_ranges = shards
+        .iter()
+        .map(|shard| shard.block_range.clone())
+        .collect::<Vec<_>>();
+    block_ranges.sort();
+    block_ranges.dedup();
+
+    let mut query_responses: Vec<MultiViewStoreQueryResponse> =
+        Vec::with_capacity(block_ranges.len());
     let mut remaining_tries = RETRY_COUNT;
     let _timer = ROUTER_QUERY_REQUESTS.start_timer();
     while remaining_tries > 0 {
@@ -183,18 +239,16 @@ where
                 )
             })?
             .into();
-        let clients_and_responses = route_query(&multi_view_store_query_request, shards.clone())
-            .await
-            .map_err(|err| {
-                router_server_err_to_rpc_status(
-                    "Query: internal query routing error",
-                    err,
-                    logger.clone(),
-                )
-            })?;
+        let routed_query = route_query(
+            &multi_view_store_query_request,
+            shards,
+            &shard_query_config,
+            &logger,
+        )
+        .await;
 
         let processed_shard_response_data = shard_responses_processor::process_shard_responses(
-            clients_and_responses,
+            routed_query.responses,
             logger.clone(),
         )
         .map_err(|err| {
@@ -212,21 +266,35 @@ where
             query_responses.push(multi_view_store_query_response);
         }
 
-        shards = processed_shard_response_data.shards_for_retry;
-        if shards.is_empty() {
-            break;
-        }
+        // Replicas which failed aren't retried once another replica of the
+        // same shard answered, but they are still authenticated if needed.
+        shards = processed_shard_response_data
+            .shards_for_retry
+            .into_iter()
+            .chain(routed_query.failed_shards)
+            .filter(|shard| {
+                !query_responses
+                    .iter()
+                    .any(|response| response.block_range == shard.block_range)
+            })
+            .collect();
 
         let view_store_uris_for_authentication =
             processed_shard_response_data.view_store_uris_for_authentication;
-        if !view_store_uris_for_authentication.is_empty() {
+        let needs_authentication = !view_store_uris_for_authentication.is_empty();
+        if needs_authentication {
             authenticate_view_stores(
                 enclave.clone(),
                 view_store_uris_for_authentication,
                 logger.clone(),
             )
             .await?;
-        } else {
+        }
+
+        if shards.is_empty() {
+            break;
+        }
+        if !needs_authentication {
             CLIENT_QUERY_RETRIES.inc();
             remaining_tries -= 1;
         }
@@ -238,7 +306,7 @@ where
         query_responses.len()
     );
 
-    if remaining_tries == 0 {
+    if remaining_tries == 0 && !allow_partial_results {
         return Err(router_server_err_to_rpc_status(
             "Query: timed out connecting to view stores",
             RouterServerError::ViewStoreError(format!(
@@ -248,25 +316,156 @@ where
         ));
     }
 
-    Ok(query_responses)
+    let unavailable_block_ranges = block_ranges
+        .into_iter()
+        .filter(|block_range| {
+            !query_responses
+                .iter()
+                .any(|response| &response.block_range == block_range)
+        })
+        .collect::<Vec<_>>();
+    if !unavailable_block_ranges.is_empty() {
+        PARTIAL_QUERY_RESPONSES.inc();
+        log::debug!(
+            logger,
+            "get_query_responses has no responses for block ranges {:?}",
+            unavailable_block_ranges
+        );
+    }
+
+    Ok((query_responses, unavailable_block_ranges))
+}
+
+/// The responses to a client's query request from the Fog View shards.
+struct RoutedQuery {
+    /// The responses of the Fog View Stores which answered.
+    responses: Vec<(Shard, MultiViewStoreQueryResponse)>,
+    /// The Fog View Stores which failed or didn't answer before the deadline.
+    failed_shards: Vec<Shard>,
 }
 
 /// Sends a client's query request to all of the Fog View shards.
+///
+/// Fog View Stores with the same block range are replicas of one shard. The
+/// query is sent to one replica of each shard, and to the next replica when a
+/// replica doesn't answer successfully. Once the hedge delay passes, it is
+/// also sent to the next replica of each shard which hasn't answered yet. The
+/// first successful response of each shard is used, and the queries which are
+/// still in flight are dropped once every shard has one.
 async fn route_query(
     request: &MultiViewStoreQueryRequest,
     shards: Vec<Shard>,
-) -> Result<Vec<(Shard, MultiViewStoreQueryResponse)>, RouterServerError> {
-    let responses = shards
-        .into_iter()
-        .map(|shard_client| query_shard(request, shard_client));
-    try_join_all(responses).await
+    shard_query_config: &ShardQueryConfig,
+    logger: &Logger,
+) -> RoutedQuery {
+    let timeout = shard_query_config.shard_query_timeout;
+    let mut replica_sets: Vec<VecDeque<Shard>> = Vec::new();
+    for shard in shards {
+        match replica_sets
+            .iter_mut()
+            .find(|replicas| replicas[0].block_range == shard.block_range)
+        {
+            Some(replicas) => replicas.push_back(shard),
+            None => replica_sets.push(VecDeque::from([shard])),
+        }
+    }
+
+    let mut routed_query = RoutedQuery {
+        responses: Vec::new(),
+        failed_shards: Vec::new(),
+    };
+    let mut answered = vec![false; replica_sets.len()];
+    let mut pending = FuturesUnordered::new();
+    for (index, replicas) in replica_sets.iter_mut().enumerate() {
+        if let Some(shard) = replicas.pop_front() {
+            pending.push(query_replica(request, index, shard, timeout));
+        }
+    }
+    // grpcio doesn't provide timers to futures, so the delay is tracked by
+    // the global timer thread of futures-timer, shared by all queries.
+    let mut hedge_timer = replica_sets
+        .iter()
+        .any(|replicas| !replicas.is_empty())
+        .then(|| Delay::new(shard_query_config.shard_hedge_delay));
+
+    while answered.contains(&false) {
+        let next = match hedge_timer.as_mut() {
+            Some(timer) => match select(pending.next(), timer).await {
+                Either::Left((next, _)) => Some(next),
+                Either::Right(_) => None,
+            },
+            None => Some(pending.next().await),
+        };
+        let (index, shard, result) = match next {
+            Some(Some(next)) => next,
+            // Every replica which was queried has answered.
+            Some(None) => break,
+            // The hedge delay passed.
+            None => {
+                hedge_timer = None;
+                for (index, replicas) in replica_sets.iter_mut().enumerate() {
+                    if answered[index] {
+                        continue;
+                    }
+                    if let Some(shard) = replicas.pop_front() {
+                        SHARD_QUERY_HEDGES.inc();
+                        pending.push(query_replica(request, index, shard, timeout));
+                    }
+                }
+                continue;
+            }
+        };
+
+        match result {
+            Ok(response) => {
+                if response.status == MultiViewStoreQueryResponseStatus::Success {
+                    if answered[index] {
+                        continue;
+                    }
+                    answered[index] = true;
+                } else if !answered[index] {
+                    if let Some(shard) = replica_sets[index].pop_front() {
+                        pending.push(query_replica(request, index, shard, timeout));
+                    }
+                }
+                routed_query.responses.push((shard, response));
+            }
+            Err(err) => {
+                log::debug!(logger, "Query to store {} failed: {}", shard.uri, err);
+                if !answered[index] {
+                    if let Some(shard) = replica_sets[index].pop_front() {
+                        pending.push(query_replica(request, index, shard, timeout));
+                    }
+                }
+                routed_query.failed_shards.push(shard);
+            }
+        }
+    }
+
+    routed_query
+}
+
+/// Sends a client's query request to a replica of the `index`th shard.
+async fn query_replica(
+    request: &MultiViewStoreQueryRequest,
+    index: usize,
+    shard: Shard,
+    timeout: Duration,
+) -> (
+    usize,
+    Shard,
+    Result<MultiViewStoreQueryResponse, RouterServerError>,
+) {
+    let result = query_shard(request, &shard, timeout).await;
+    (index, shard, result)
 }
 
 /// Sends a client's query request to one of the Fog View shards.
 async fn query_shard(
     request: &MultiViewStoreQueryRequest,
-    shard: Shard,
-) -> Result<(Shard, MultiViewStoreQueryResponse), RouterServerError> {
+    shard: &Shard,
+    timeout: Duration,
+) -> Result<MultiViewStoreQueryResponse, RouterServerError> {
     let start_time = Instant::now();
     let subdomain = shard.uri.subdomain().unwrap_or("");
     let histogram_observe = |status: &str| {
@@ -277,7 +476,7 @@ async fn query_shard(
 
     let client_unary_receiver = shard
         .grpc_client
-        .multi_view_store_query_async(request)
+        .multi_view_store_query_async_opt(request, CallOption::default().timeout(timeout))
         .inspect_err(|err| {
             histogram_observe(&err.to_string());
         })?;
@@ -285,7 +484,7 @@ async fn query_shard(
         histogram_observe(&err.to_string());
     })?;
     histogram_observe("ok");
-    Ok((shard, response.try_into()?))
+    Ok(response.try_into()?)
 }
 
 /// Authenticates Fog View Stores that have previously not been authenticated.
@@ -25,7 +25,7 @@ use mc_fog_view_protocol::FogViewConnection;
 use mc_fog_view_server::{
     config::{
         FogViewRouterConfig, MobileAcctViewConfig as ViewConfig, RouterClientListenUri,
-        ShardingStrategy::Epoch,
+        ShardQueryConfig, ShardingStrategy::Epoch,
     },
     fog_view_router_server::{FogViewRouterServer, Shard},
     server::ViewServer,
@@ -87,6 +87,10 @@ impl RouterTestEnvironment {
             client_auth_token_max_lifetime: Default::default(),
             client_auth_token_secret: None,
             admin_listen_uri,
+            shard_query_config: ShardQueryConfig {
+                shard_query_timeout: Duration::from_secs(2),
+                shard_hedge_delay: Duration::from_millis(100),
+            },
         };
         let router_server = Self::create_router_server(config, store_clients, &logger);
         let router_client = Self::create_router_streaming_client(router_uri, logger);
@@ -124,6 +128,10 @@ impl RouterTestEnvironment {
             client_auth_token_max_lifetime: Default::default(),
             client_auth_token_secret: None,
             admin_listen_uri,
+            shard_query_config: ShardQueryConfig {
+                shard_query_timeout: Duration::from_secs(2),
+                shard_hedge_delay: Duration::from_millis(100),
+            },
         };
         let router_server = Self::create_router_server(config, store_clients, &logger);
         let router_client = Self::create_router_unary_client(chain_id, router_uri, logger);
@@ -435,3 +435,77 @@ fn test_streaming_integration(omap_capacity: u64, store_count: usize, blocks_per
     assert_eq!(result.missed_block_ranges[0], BlockRange::new(3, 4));
     assert_eq!(result.last_known_block_count, 6);
 }
+
+#[test]
+fn test_streaming_partial_results_with_replicated_stores() {
+    let logger = logger::create_test_logger("partial_results".to_string());
+    // The first shard has two replicas.
+    let store_block_ranges = vec![
+        BlockRange::new(0, 3),
+        BlockRange::new(0, 3),
+        BlockRange::new(3, 6),
+    ];
+    let mut test_environment =
+        RouterTestEnvironment::new(512, store_block_ranges.clone(), logger.clone());
+
+    let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+    let db = test_environment
+        .db_test_context
+        .as_ref()
+        .unwrap()
+        .get_db_instance();
+
+    let ingress_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng));
+    db.new_ingress_key(&ingress_key, 0).unwrap();
+    let egress_public_key = KexRngPubkey {
+        public_key: vec![1; 32],
+        version: 0,
+    };
+    let invoc_id = db
+        .new_ingest_invocation(None, &ingress_key, &egress_public_key, 0)
+        .unwrap();
+    for block_index in 0..6 {
+        mc_fog_view_server_test_utils::add_block_data(&db, &invoc_id, block_index, 0, &[]);
+    }
+    // Every replica has to be loaded, not just the store which is furthest
+    // along.
+    for (store, block_range) in test_environment
+        .store_servers
+        .as_ref()
+        .unwrap()
+        .iter()
+        .zip(&store_block_ranges)
+    {
+        mc_fog_view_server_test_utils::wait_for_block_to_load(
+            block_range.end_block,
+            std::slice::from_ref(store),
+            &logger,
+        );
+    }
+
+    let search_keys = vec![vec![50u8]];
+    let router_client = test_environment.router_streaming_client.as_mut().unwrap();
+    let (result, unavailable_block_ranges) =
+        block_on(router_client.query_partial(0, 0, search_keys.clone())).unwrap();
+    assert_eq!(result.highest_processed_block_count, 6);
+    assert!(unavailable_block_ranges.is_empty());
+
+    // The other replica of the first shard still answers.
+    test_environment.store_servers.as_mut().unwrap().remove(0);
+    let router_client = test_environment.router_streaming_client.as_mut().unwrap();
+    let (result, unavailable_block_ranges) =
+        block_on(router_client.query_partial(0, 0, search_keys.clone())).unwrap();
+    assert_eq!(result.highest_processed_block_count, 6);
+    assert!(unavailable_block_ranges.is_empty());
+
+    // Nothing answers for the second shard, so its blocks aren't covered.
+    test_environment.store_servers.as_mut().unwrap().remove(1);
+    let router_client = test_environment.router_streaming_client.as_mut().unwrap();
+    let (result, unavailable_block_ranges) =
+        block_on(router_client.query_partial(0, 0, search_keys.clone())).unwrap();
+    assert_eq!(result.highest_processed_block_count, 3);
+    assert_eq!(unavailable_block_ranges, vec![BlockRange::new(3, 6)]);
+
+    // Without partial results, the query fails.
+    assert!(block_on(router_client.query(0, 0, search_keys)).is_err());
+}
@@ -0,0 +1,247 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Accounting for the fees collected by the network.
+//!
+//! Every block pays the fees of the transactions it contains to the fee
+//! recipient, whose public keys are compiled into the consensus enclave. Fee
+//! outputs look like any other TxOut, so recognizing them requires the fee
+//! recipient's view private key.
+
+use crate::{Error, Ledger};
+use mc_blockchain_types::{BlockContents, BlockIndex};
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPrivate, RistrettoPublic};
+use mc_transaction_core::{
+    onetime_keys::recover_public_subaddress_spend_key, tx::TxOut, Amount, TokenId,
+};
+use std::{collections::BTreeMap, ops::Range};
+
+/// A fee output found in the ledger.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeOutput {
+    /// The block containing the output.
+    pub block_index: BlockIndex,
+
+    /// The output's public key.
+    pub public_key: CompressedRistrettoPublic,
+
+    /// The fee paid by the output.
+    pub amount: Amount,
+}
+
+/// Fees collected during a range of blocks.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FeePeriodReport {
+    /// The blocks covered by this report.
+    pub blocks: Range<BlockIndex>,
+
+    /// Total fees collected, per token. Tokens that collected no fees are
+    /// omitted.
+    pub totals: BTreeMap<TokenId, u128>,
+
+    /// Number of fee outputs, including zero-value outputs that the enclave
+    /// mints for privacy.
+    pub num_fee_outputs: u64,
+}
+
+/// Recognizes the outputs paid to the fee recipient.
+#[derive(Clone)]
+pub struct FeeScanner {
+    /// The fee recipient's view private key.
+    view_private_key: RistrettoPrivate,
+
+    /// The fee recipient's spend public key. When set, outputs that decrypt
+    /// with the view key but were sent to a different spend key are ignored.
+    spend_public_key: Option<RistrettoPublic>,
+}
+
+impl FeeScanner {
+    /// Create a scanner for the fee recipient.
+    ///
+    /// # Arguments
+    /// * `view_private_key` - The fee recipient's view private key.
+    /// * `spend_public_key` - The fee recipient's spend public key, if known.
+    pub fn new(
+        view_private_key: RistrettoPrivate,
+        spend_public_key: Option<RistrettoPublic>,
+    ) -> Self {
+        Self {
+            view_private_key,
+            spend_public_key,
+        }
+    }
+
+    /// Returns the amount of a TxOut if it was paid to the fee recipient.
+    pub fn match_tx_out(&self, tx_out: &TxOut) -> Option<Amount> {
+        let (amount, _shared_secret) = tx_out.view_key_match(&self.view_private_key).ok()?;
+
+        if let Some(spend_public_key) = self.spend_public_key.as_ref() {
+            let target_key = RistrettoPublic::try_from(&tx_out.target_key).ok()?;
+            let public_key = RistrettoPublic::try_from(&tx_out.public_key).ok()?;
+            let recovered = recover_public_subaddress_spend_key(
+                &self.view_private_key,
+                &target_key,
+                &public_key,
+            );
+            if recovered != *spend_public_key {
+                return None;
+            }
+        }
+
+        Some(amount)
+    }
+
+    /// Find the fee outputs in a block.
+    pub fn scan_block_contents(
+        &self,
+        block_index: BlockIndex,
+        block_contents: &BlockContents,
+    ) -> Vec<FeeOutput> {
+        block_contents
+            .outputs
+            .iter()
+            .filter_map(|tx_out| {
+                self.match_tx_out(tx_out).map(|amount| FeeOutput {
+                    block_index,
+                    public_key: tx_out.public_key,
+                    amount,
+                })
+            })
+            .collect()
+    }
+
+    /// Find the fee outputs in a range of blocks.
+    pub fn scan_ledger<L: Ledger + ?Sized>(
+        &self,
+        ledger: &L,
+        blocks: Range<BlockIndex>,
+    ) -> Result<Vec<FeeOutput>, Error> {
+        let mut fee_outputs = Vec::new();
+        for block_index in blocks {
+            let block_contents = ledger.get_block_contents(block_index)?;
+            fee_outputs.extend(self.scan_block_contents(block_index, &block_contents));
+        }
+        Ok(fee_outputs)
+    }
+
+    /// Total the fees collected in a range of blocks, split into periods of
+    /// `blocks_per_period` blocks. The last period may be shorter. A
+    /// `blocks_per_period` of zero produces a single period covering all of
+    /// `blocks`.
+    pub fn report<L: Ledger + ?Sized>(
+        &self,
+        ledger: &L,
+        blocks: Range<BlockIndex>,
+        blocks_per_period: u64,
+    ) -> Result<Vec<FeePeriodReport>, Error> {
+        let blocks_per_period = if blocks_per_period == 0 {
+            blocks.end.saturating_sub(blocks.start).max(1)
+        } else {
+            blocks_per_period
+        };
+
+        let mut reports = Vec::new();
+        let mut start = blocks.start;
+        while start < blocks.end {
+            let end = start.saturating_add(blocks_per_period).min(blocks.end);
+            let mut report = FeePeriodReport {
+                blocks: start..end,
+                ..Default::default()
+            };
+            for fee_output in self.scan_ledger(ledger, start..end)? {
+                report.num_fee_outputs += 1;
+                if fee_output.amount.value > 0 {
+                    *report.totals.entry(fee_output.amount.token_id).or_default() +=
+                        fee_output.amount.value as u128;
+                }
+            }
+            reports.push(report);
+            start = end;
+        }
+        Ok(reports)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{add_txos_to_ledger, create_ledger};
+    use mc_account_keys::{AccountKey, PublicAddress};
+    use mc_blockchain_types::BlockVersion;
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn fee_tx_out(recipient: &PublicAddress, value: u64, token_id: u64, rng: &mut StdRng) -> TxOut {
+        TxOut::new(
+            BlockVersion::MAX,
+            Amount::new(value, TokenId::from(token_id)),
+            recipient,
+            &RistrettoPrivate::from_random(rng),
+            Default::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn report_totals_fees_per_token_and_period() {
+        let mut rng: StdRng = SeedableRng::from_seed([9u8; 32]);
+        let mut ledger = create_ledger();
+
+        // The enclave pays fees directly to the fee recipient's keys, not to a
+        // subaddress.
+        let fee_view_private_key = RistrettoPrivate::from_random(&mut rng);
+        let fee_spend_private_key = RistrettoPrivate::from_random(&mut rng);
+        let fee_spend_public_key = RistrettoPublic::from(&fee_spend_private_key);
+        let fee_recipient = PublicAddress::new(
+            &fee_spend_public_key,
+            &RistrettoPublic::from(&fee_view_private_key),
+        );
+        let other = AccountKey::random(&mut rng).default_subaddress();
+
+        // A recipient sharing the fee view key but with a different spend key.
+        let impostor = PublicAddress::new(
+            &RistrettoPublic::from_random(&mut rng),
+            &RistrettoPublic::from(&fee_view_private_key),
+        );
+
+        let blocks = [
+            vec![fee_tx_out(&fee_recipient, 400, 0, &mut rng)],
+            vec![
+                fee_tx_out(&fee_recipient, 400, 0, &mut rng),
+                fee_tx_out(&fee_recipient, 1024, 1, &mut rng),
+                fee_tx_out(&other, 10, 0, &mut rng),
+            ],
+            vec![
+                fee_tx_out(&fee_recipient, 0, 1, &mut rng),
+                fee_tx_out(&impostor, 5, 0, &mut rng),
+            ],
+        ];
+        for outputs in blocks.iter() {
+            add_txos_to_ledger(&mut ledger, BlockVersion::MAX, outputs, &mut rng).unwrap();
+        }
+
+        let scanner = FeeScanner::new(fee_view_private_key, Some(fee_spend_public_key));
+        let fee_outputs = scanner.scan_ledger(&ledger, 0..3).unwrap();
+        assert_eq!(fee_outputs.len(), 4);
+        assert_eq!(fee_outputs[0].block_index, 0);
+        assert_eq!(fee_outputs[0].amount, Amount::new(400, TokenId::from(0)));
+
+        let reports = scanner.report(&ledger, 0..3, 2).unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].blocks, 0..2);
+        assert_eq!(reports[0].num_fee_outputs, 3);
+        assert_eq!(
+            reports[0].totals,
+            BTreeMap::from([(TokenId::from(0), 800), (TokenId::from(1), 1024)])
+        );
+        assert_eq!(reports[1].blocks, 2..3);
+        assert_eq!(reports[1].num_fee_outputs, 1);
+        assert!(reports[1].totals.is_empty());
+
+        // Without the spend key, the impostor output is counted as well.
+        let scanner = FeeScanner::new(fee_view_private_key, None);
+        let reports = scanner.report(&ledger, 0..3, 0).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].num_fee_outputs, 5);
+        assert_eq!(reports[0].totals[&TokenId::from(0)], 805);
+    }
+}
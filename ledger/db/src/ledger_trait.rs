@@ -110,6 +110,17 @@ pub trait Ledger: Send {
         self.get_block(num_blocks - 1)
     }
 
+    /// Get the timestamp in a block's metadata, in seconds since the Unix
+    /// epoch. This is 0 if the block has no metadata, or the metadata has no
+    /// timestamp.
+    fn get_block_timestamp(&self, block_number: BlockIndex) -> Result<u64, Error> {
+        match self.get_block_metadata(block_number) {
+            Ok(metadata) => Ok(metadata.contents().timestamp()),
+            Err(Error::NotFound) => Ok(0),
+            Err(err) => Err(err),
+        }
+    }
+
     /// Get active mint configurations for a given token id.
     fn get_active_mint_configs(
         &self,
@@ -54,6 +54,12 @@ impl<ID: GenericNodeId + Clone + Eq + PartialEq + Hash + Send> SCPNetworkState<I
         }
     }
 
+    /// Replace the local node's quorum set, keeping the slots that peers are
+    /// known to have reached.
+    pub fn set_quorum_set(&mut self, local_quorum_set: QuorumSet<ID>) {
+        self.local_quorum_set = local_quorum_set;
+    }
+
     pub fn peer_to_current_slot(&self) -> &HashMap<ID, SlotIndex> {
         &self.id_to_current_slot
     }
@@ -330,6 +336,27 @@ mod tests {
         );
     }
 
+    #[test]
+    // Replacing the quorum set keeps track of the slots peers have reached, and
+    // uses the new quorum set to decide whether the node is behind.
+    fn test_set_quorum_set_keeps_peer_slots() {
+        let local_node_id = test_node_id(1);
+        let node_2 = test_node_id(2);
+        let node_3 = test_node_id(3);
+        let mut network_state = SCPNetworkState::new(
+            local_node_id,
+            QuorumSet::new_with_node_ids(1, vec![node_2]),
+        );
+        network_state.id_to_current_slot.insert(node_3.clone(), 8);
+
+        // Node 3 is not in the quorum set, so its progress does not matter.
+        assert!(!network_state.is_behind(5));
+
+        network_state.set_quorum_set(QuorumSet::new_with_node_ids(1, vec![node_3.clone()]));
+        assert_eq!(network_state.peer_to_current_slot().get(&node_3), Some(&8));
+        assert!(network_state.is_behind(5));
+    }
+
     #[test]
     // NetworkState should correctly track the state of multiple senders.
     fn test_multiple_senders() {
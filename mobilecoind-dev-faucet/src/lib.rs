@@ -37,6 +37,8 @@ use tokio::select;
 )]
 pub struct Config {
     /// Path to json-formatted key file, containing mnemonic or root entropy.
+    /// Encrypted key files are decrypted with the password in
+    /// MC_KEYFILE_PASSWORD.
     #[clap(long, env = "MC_KEYFILE")]
     pub keyfile: PathBuf,
 
@@ -8,9 +8,10 @@ use mc_common::logger::{create_app_logger, log, o, Logger};
 use mc_ledger_db::{Ledger, LedgerDB};
 use mc_ledger_sync::{LedgerSyncServiceThread, PollingNetworkState, ReqwestTransactionsFetcher};
 use mc_mobilecoind::{
-    config::Config, database::Database, payments::TransactionsManager, service::Service,
-    t3_sync::T3SyncThread,
+    config::Config, database::Database, memo_registry::load_memo_registry,
+    payments::TransactionsManager, service::Service, t3_sync::T3SyncThread,
 };
+use mc_transaction_extra::MemoRegistry;
 use mc_util_telemetry::setup_default_tracer;
 use mc_watcher::{watcher::WatcherSyncThread, watcher_db::create_or_open_rw_watcher_db};
 use std::{
@@ -141,6 +142,11 @@ fn main() {
                 _ => None,
             };
 
+            let memo_registry = match &config.memo_types {
+                Some(path) => load_memo_registry(path).expect("Could not load memo types"),
+                None => MemoRegistry::new(),
+            };
+
             let _api_server = Service::new(
                 ledger_db,
                 mobilecoind_db,
@@ -150,6 +156,7 @@ fn main() {
                 listen_uri,
                 config.num_workers,
                 config.peers_config.chain_id.clone(),
+                Arc::new(memo_registry),
                 logger,
             );
 
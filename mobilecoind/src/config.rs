@@ -82,6 +82,12 @@ pub struct Config {
     #[clap(long, env = "MC_NUM_WORKERS")]
     pub num_workers: Option<usize>,
 
+    /// Path to a JSON file describing application-defined memo types. Memos
+    /// of these types are decoded into their fields in API responses. See
+    /// `mc_mobilecoind::memo_registry::memo_registry_from_json` for the format.
+    #[clap(long, env = "MC_MEMO_TYPES")]
+    pub memo_types: Option<PathBuf>,
+
     /// Offline mode.
     #[clap(long, env = "MC_OFFLINE")]
     pub offline: bool,
@@ -4,6 +4,7 @@
 //! types.
 
 use crate::{
+    memo_registry::registered_memo_to_json,
     payments::{Outlay, OutlayV2, SciForTx, TxProposal},
     utxo_store::UnspentTxOut,
 };
@@ -17,28 +18,31 @@ use mc_transaction_core::{
     tx::{Tx, TxOut},
     Amount, MemoPayload, TokenId,
 };
-use mc_transaction_extra::{MemoType, TxOutConfirmationNumber};
+use mc_transaction_extra::{MemoRegistry, MemoType, TxOutConfirmationNumber};
 use protobuf::RepeatedField;
 
-impl From<&UnspentTxOut> for api::UnspentTxOut {
-    fn from(src: &UnspentTxOut) -> Self {
-        let mut dst = Self::new();
-
-        dst.set_tx_out((&src.tx_out).into());
-        dst.set_subaddress_index(src.subaddress_index);
-        dst.set_key_image((&src.key_image).into());
-        dst.set_value(src.value);
-        dst.set_attempted_spend_height(src.attempted_spend_height);
-        dst.set_attempted_spend_tombstone(src.attempted_spend_tombstone);
-        dst.set_token_id(src.token_id);
-        dst.set_memo_payload(src.memo_payload.clone());
-
-        if let Ok(mp) = MemoPayload::try_from(&src.memo_payload[..]) {
-            dst.set_decoded_memo(decode_memo(&mp));
-        }
-
-        dst
+/// Convert an UnspentTxOut to its API representation, decoding memos of the
+/// types in `memo_registry` as `RegisteredMemo`s.
+pub fn unspent_tx_out_to_proto(
+    src: &UnspentTxOut,
+    memo_registry: &MemoRegistry,
+) -> api::UnspentTxOut {
+    let mut dst = api::UnspentTxOut::new();
+
+    dst.set_tx_out((&src.tx_out).into());
+    dst.set_subaddress_index(src.subaddress_index);
+    dst.set_key_image((&src.key_image).into());
+    dst.set_value(src.value);
+    dst.set_attempted_spend_height(src.attempted_spend_height);
+    dst.set_attempted_spend_tombstone(src.attempted_spend_tombstone);
+    dst.set_token_id(src.token_id);
+    dst.set_memo_payload(src.memo_payload.clone());
+
+    if let Ok(mp) = MemoPayload::try_from(&src.memo_payload[..]) {
+        dst.set_decoded_memo(decode_memo(&mp, memo_registry));
     }
+
+    dst
 }
 
 impl TryFrom<&api::UnspentTxOut> for UnspentTxOut {
@@ -81,7 +85,7 @@ fn bytes_to_tx_private_key(bytes: &[u8]) -> Result<Option<RistrettoPrivate>, Con
 //
 // Note: This could be From<&MemoPayload> for api::DecodedMemo, but there are
 // orphan rules issues.
-fn decode_memo(memo_payload: &MemoPayload) -> api::DecodedMemo {
+fn decode_memo(memo_payload: &MemoPayload, memo_registry: &MemoRegistry) -> api::DecodedMemo {
     let mut result = api::DecodedMemo::new();
 
     match MemoType::try_from(memo_payload) {
@@ -103,7 +107,21 @@ fn decode_memo(memo_payload: &MemoPayload) -> api::DecodedMemo {
             asm.set_payment_intent_id(memo.payment_intent_id());
             result.set_authenticated_sender_memo(asm);
         }
+        Ok(MemoType::AuthenticatedSenderWithText(memo)) => {
+            let mut asm = api::AuthenticatedSenderMemo::new();
+            asm.set_sender_hash(memo.sender_address_hash().as_ref().to_vec());
+            asm.set_has_text(true);
+            result.set_authenticated_sender_memo(asm);
+        }
         Ok(_) | Err(_) => {
+            if let Some(memo) = memo_registry.decode(memo_payload) {
+                let mut rm = api::RegisteredMemo::new();
+                rm.set_type_bytes(memo.memo_type_bytes.to_vec());
+                rm.set_name(memo.name.clone());
+                rm.set_fields_json(registered_memo_to_json(&memo));
+                result.set_registered_memo(rm);
+                return result;
+            }
             let mut um = api::UnknownMemo::new();
             um.set_type_bytes(memo_payload.get_memo_type().to_vec());
             result.set_unknown_memo(um);
@@ -174,34 +192,37 @@ impl TryFrom<&api::OutlayV2> for OutlayV2 {
     }
 }
 
-impl From<&TxProposal> for api::TxProposal {
-    fn from(src: &TxProposal) -> api::TxProposal {
-        let mut dst = api::TxProposal::new();
-
-        dst.set_input_list(RepeatedField::from_vec(
-            src.utxos.iter().map(|utxo| utxo.into()).collect(),
-        ));
-        dst.set_outlay_list(RepeatedField::from_vec(
-            src.outlays.iter().map(|outlay| outlay.into()).collect(),
-        ));
-        dst.set_tx((&src.tx).into());
-        dst.set_fee(src.tx.prefix.fee);
-        dst.set_outlay_index_to_tx_out_index(
-            src.outlay_index_to_tx_out_index
-                .iter()
-                .map(|(key, val)| (*key as u64, *val as u64))
-                .collect(),
-        );
-        dst.set_outlay_confirmation_numbers(
-            src.outlay_confirmation_numbers
-                .iter()
-                .map(|val| val.to_vec())
-                .collect(),
-        );
-        dst.set_scis(src.scis.iter().map(Into::into).collect());
+/// Convert a TxProposal to its API representation, decoding the memos of its
+/// inputs with `memo_registry`.
+pub fn tx_proposal_to_proto(src: &TxProposal, memo_registry: &MemoRegistry) -> api::TxProposal {
+    let mut dst = api::TxProposal::new();
 
-        dst
-    }
+    dst.set_input_list(RepeatedField::from_vec(
+        src.utxos
+            .iter()
+            .map(|utxo| unspent_tx_out_to_proto(utxo, memo_registry))
+            .collect(),
+    ));
+    dst.set_outlay_list(RepeatedField::from_vec(
+        src.outlays.iter().map(|outlay| outlay.into()).collect(),
+    ));
+    dst.set_tx((&src.tx).into());
+    dst.set_fee(src.tx.prefix.fee);
+    dst.set_outlay_index_to_tx_out_index(
+        src.outlay_index_to_tx_out_index
+            .iter()
+            .map(|(key, val)| (*key as u64, *val as u64))
+            .collect(),
+    );
+    dst.set_outlay_confirmation_numbers(
+        src.outlay_confirmation_numbers
+            .iter()
+            .map(|val| val.to_vec())
+            .collect(),
+    );
+    dst.set_scis(src.scis.iter().map(Into::into).collect());
+
+    dst
 }
 
 impl TryFrom<&api::TxProposal> for TxProposal {
@@ -308,11 +329,12 @@ mod test {
     use mc_transaction_core::{tokens::Mob, BlockVersion, Token};
     use mc_transaction_extra::{
         AuthenticatedSenderMemo, AuthenticatedSenderWithPaymentIntentIdMemo,
-        AuthenticatedSenderWithPaymentRequestIdMemo, DestinationMemo, SenderMemoCredential,
-        UnusedMemo,
+        AuthenticatedSenderWithPaymentRequestIdMemo, DestinationMemo, MemoFieldValue, MemoPlugin,
+        RegisteredMemoType, SenderMemoCredential, UnusedMemo,
     };
     use mc_util_from_random::FromRandom;
     use rand::{rngs::StdRng, SeedableRng};
+    use std::collections::BTreeMap;
 
     #[test]
     fn test_unspent_tx_out_conversion() {
@@ -349,7 +371,7 @@ mod test {
             memo_payload: vec![6u8, 66],
         };
 
-        let proto = api::UnspentTxOut::from(&rust);
+        let proto = unspent_tx_out_to_proto(&rust, &MemoRegistry::new());
 
         assert_eq!(tx_out, TxOut::try_from(proto.get_tx_out()).unwrap());
         assert_eq!(subaddress_index, proto.subaddress_index);
@@ -386,12 +408,12 @@ mod test {
         let tx_public_key = CompressedRistrettoPublic::from_random(&mut rng);
 
         let memo1 = UnusedMemo {};
-        let decoded = decode_memo(&MemoPayload::from(memo1));
+        let decoded = decode_memo(&MemoPayload::from(memo1), &MemoRegistry::new());
         assert_eq!(decoded.decoded_memo, None);
 
         let memo2 =
             AuthenticatedSenderMemo::new(&alice_cred, bob_addr.view_public_key(), &tx_public_key);
-        let decoded = decode_memo(&MemoPayload::from(memo2));
+        let decoded = decode_memo(&MemoPayload::from(memo2), &MemoRegistry::new());
         assert!(decoded.has_authenticated_sender_memo());
         let sender_memo = decoded.get_authenticated_sender_memo();
         assert_eq!(sender_memo.get_sender_hash(), alice_hash.as_ref());
@@ -404,7 +426,7 @@ mod test {
             &tx_public_key,
             7u64,
         );
-        let decoded = decode_memo(&MemoPayload::from(memo3));
+        let decoded = decode_memo(&MemoPayload::from(memo3), &MemoRegistry::new());
         assert!(decoded.has_authenticated_sender_memo());
         assert!(!decoded.has_unknown_memo());
         let sender_memo = decoded.get_authenticated_sender_memo();
@@ -419,7 +441,7 @@ mod test {
             &tx_public_key,
             9u64,
         );
-        let decoded = decode_memo(&MemoPayload::from(memo4));
+        let decoded = decode_memo(&MemoPayload::from(memo4), &MemoRegistry::new());
         assert!(decoded.has_authenticated_sender_memo());
         assert!(!decoded.has_unknown_memo());
         let sender_memo = decoded.get_authenticated_sender_memo();
@@ -430,7 +452,7 @@ mod test {
 
         // Destination memos are not implemented yet
         let memo5 = DestinationMemo::new(ShortAddressHash::from(&bob_addr), 17, 18).unwrap();
-        let decoded = decode_memo(&MemoPayload::from(memo5));
+        let decoded = decode_memo(&MemoPayload::from(memo5), &MemoRegistry::new());
         assert!(!decoded.has_authenticated_sender_memo());
         assert!(decoded.has_unknown_memo());
         let type_bytes = decoded.get_unknown_memo().get_type_bytes();
@@ -438,13 +460,76 @@ mod test {
 
         // This is an unassigned memo type
         let memo6 = MemoPayload::new([7u8, 8u8], [0u8; 64]);
-        let decoded = decode_memo(&memo6);
+        let decoded = decode_memo(&memo6, &MemoRegistry::new());
         assert!(!decoded.has_authenticated_sender_memo());
         assert!(decoded.has_unknown_memo());
         let type_bytes = decoded.get_unknown_memo().get_type_bytes();
         assert_eq!(&type_bytes, &[7u8, 8u8]);
     }
 
+    // A memo type defined outside of mc-transaction-extra.
+    #[derive(Clone, Debug)]
+    struct InvoiceMemo([u8; 64]);
+
+    impl RegisteredMemoType for InvoiceMemo {
+        const MEMO_TYPE_BYTES: [u8; 2] = [0xF1, 0x00];
+    }
+
+    impl MemoPlugin for InvoiceMemo {
+        const NAME: &'static str = "invoice";
+
+        fn fields(&self) -> BTreeMap<String, MemoFieldValue> {
+            BTreeMap::from([
+                (
+                    "invoice_number".to_owned(),
+                    MemoFieldValue::U64(u64::from_be_bytes(self.0[0..8].try_into().unwrap())),
+                ),
+                (
+                    "merchant_id".to_owned(),
+                    MemoFieldValue::Bytes(self.0[8..12].to_vec()),
+                ),
+            ])
+        }
+    }
+
+    impl From<&[u8; 64]> for InvoiceMemo {
+        fn from(src: &[u8; 64]) -> Self {
+            Self(*src)
+        }
+    }
+
+    impl From<InvoiceMemo> for [u8; 64] {
+        fn from(src: InvoiceMemo) -> [u8; 64] {
+            src.0
+        }
+    }
+
+    // Test that registered memo types are decoded as JSON
+    #[test]
+    fn test_registered_memo_conversion() {
+        let mut memo_data = [0u8; 64];
+        memo_data[0..8].copy_from_slice(&42u64.to_be_bytes());
+        memo_data[8..12].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        let memo = MemoPayload::new(InvoiceMemo::MEMO_TYPE_BYTES, memo_data);
+
+        // Without registration, this is an unknown memo.
+        let mut memo_registry = MemoRegistry::new();
+        let decoded = decode_memo(&memo, &memo_registry);
+        assert!(decoded.has_unknown_memo());
+
+        memo_registry.register::<InvoiceMemo>().unwrap();
+        let decoded = decode_memo(&memo, &memo_registry);
+        assert!(decoded.has_registered_memo());
+        let registered = decoded.get_registered_memo();
+        assert_eq!(registered.get_type_bytes(), &[0xF1, 0x00]);
+        assert_eq!(registered.get_name(), "invoice");
+        let fields: serde_json::Value = serde_json::from_str(registered.get_fields_json()).unwrap();
+        assert_eq!(
+            fields,
+            serde_json::json!({"invoice_number": 42, "merchant_id": "deadbeef"})
+        );
+    }
+
     #[test]
     fn test_outlay_conversion() {
         let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
@@ -569,7 +654,7 @@ mod test {
             scis: vec![],
         };
 
-        let proto = api::TxProposal::from(&rust);
+        let proto = tx_proposal_to_proto(&rust, &MemoRegistry::new());
 
         assert_eq!(
             rust.utxos,
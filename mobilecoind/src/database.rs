@@ -21,7 +21,7 @@ use mc_common::{
 use mc_t3_api::TransparentTransaction;
 use mc_transaction_core::ring_signature::KeyImage;
 use mc_util_lmdb::{MetadataStore, MetadataStoreSettings};
-use std::{path::Path, sync::Arc};
+use std::{ops::Range, path::Path, slice, sync::Arc};
 
 // LMDB Constants
 const MAX_LMDB_FILE_SIZE: usize = 1_099_511_627_776; // 1 TB
@@ -254,6 +254,101 @@ impl Database {
         Ok(())
     }
 
+    /// Extend a monitor's watched subaddress range so that at least its gap
+    /// limit of subaddresses follow `used_index`. Returns the newly watched
+    /// subaddress indexes, which is empty if the range did not grow. Blocks
+    /// scanned with the old range need rescanning for the new indexes.
+    pub fn discover_subaddresses(
+        &self,
+        monitor_id: &MonitorId,
+        used_index: u64,
+    ) -> Result<Range<u64>, Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        let mut monitor_data = self.monitor_store.get_data(&db_txn, monitor_id)?;
+        let indexes = monitor_data.subaddress_indexes_to_discover(used_index);
+        if indexes.is_empty() {
+            return Ok(indexes);
+        }
+
+        let num_inserted = self.subaddress_store.extend(
+            &mut db_txn,
+            monitor_id,
+            &monitor_data,
+            indexes.clone(),
+        )?;
+        let discovered = indexes.start..indexes.start + num_inserted;
+        if discovered.is_empty() {
+            return Ok(discovered);
+        }
+
+        monitor_data.num_discovered_subaddresses += num_inserted;
+        self.monitor_store
+            .set_data(&mut db_txn, monitor_id, &monitor_data)?;
+
+        db_txn.commit()?;
+
+        log::info!(
+            self.logger,
+            "Discovered {} subaddresses for monitor id {}, now watching {:?}",
+            num_inserted,
+            monitor_id,
+            monitor_data.subaddress_indexes(),
+        );
+        Ok(discovered)
+    }
+
+    /// Record TxOuts found by rescanning blocks that a monitor had already
+    /// processed, for subaddresses discovered after those blocks were scanned.
+    /// Each entry holds the index of the block the TxOut was received in, the
+    /// TxOut, and the index of the block it was spent in, if any. TxOuts that
+    /// are not spent are added to the monitor's unspent TxOuts.
+    pub fn rescanned_utxos_found(
+        &self,
+        monitor_id: &MonitorId,
+        found: &[(u64, UnspentTxOut, Option<u64>)],
+    ) -> Result<(), Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        for (received_block, utxo, spent_block) in found {
+            self.processed_block_store.block_processed(
+                &mut db_txn,
+                monitor_id,
+                *received_block,
+                slice::from_ref(utxo),
+                &[],
+            )?;
+            self.t3_store
+                .process_utxo(&mut db_txn, monitor_id, &self.monitor_store, utxo)?;
+
+            match spent_block {
+                Some(spent_block) => self.processed_block_store.block_processed(
+                    &mut db_txn,
+                    monitor_id,
+                    *spent_block,
+                    &[],
+                    slice::from_ref(utxo),
+                )?,
+                None => self.utxo_store.append_utxo(
+                    &mut db_txn,
+                    monitor_id,
+                    utxo.subaddress_index,
+                    utxo,
+                )?,
+            }
+        }
+
+        db_txn.commit()?;
+
+        log::info!(
+            self.logger,
+            "Found {} TxOuts at discovered subaddresses in processed blocks for monitor id {}",
+            found.len(),
+            monitor_id,
+        );
+        Ok(())
+    }
+
     /// Feed data processed from a given block into the various stores.
     pub fn block_processed(
         &self,
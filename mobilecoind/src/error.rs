@@ -125,6 +125,9 @@ pub enum Error {
 
     /// Protobuf error: {0}
     Protobuf(protobuf::ProtobufError),
+
+    /// Memo types configuration: {0}
+    MemoTypesConfig(String),
 }
 
 impl From<RetryError<ConnectionError>> for Error {
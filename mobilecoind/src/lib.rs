@@ -6,6 +6,7 @@ extern crate alloc;
 
 pub mod config;
 pub mod database;
+pub mod memo_registry;
 pub mod payments;
 pub mod service;
 pub mod t3_sync;
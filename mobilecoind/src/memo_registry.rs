@@ -0,0 +1,197 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! The application-defined memo types mobilecoind knows how to decode.
+//!
+//! Memo types registered in the service's `MemoRegistry` are reported in the
+//! API as `RegisteredMemo`s, with their fields as a JSON object, instead of as
+//! `UnknownMemo`s. The mobilecoind binary registers the memo types described
+//! in the file passed with `--memo-types`.
+
+use crate::error::Error;
+use mc_transaction_extra::{
+    MemoFieldKind, MemoFieldLayout, MemoFieldValue, MemoLayout, MemoRegistry, RegisteredMemo,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{fs, path::Path};
+
+/// A memo type, as described in the memo types file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MemoTypeConfig {
+    /// The memo type bytes, hex encoded.
+    type_bytes: String,
+    /// A short human-readable name for this memo type.
+    name: String,
+    /// The fields of the memo.
+    fields: Vec<MemoFieldConfig>,
+}
+
+/// A field of a memo type, as described in the memo types file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MemoFieldConfig {
+    name: String,
+    offset: usize,
+    len: usize,
+    kind: MemoFieldKindConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MemoFieldKindConfig {
+    Bool,
+    U64,
+    Text,
+    Bytes,
+}
+
+impl From<MemoFieldKindConfig> for MemoFieldKind {
+    fn from(src: MemoFieldKindConfig) -> Self {
+        match src {
+            MemoFieldKindConfig::Bool => Self::Bool,
+            MemoFieldKindConfig::U64 => Self::U64,
+            MemoFieldKindConfig::Text => Self::Text,
+            MemoFieldKindConfig::Bytes => Self::Bytes,
+        }
+    }
+}
+
+/// Build a memo registry from a JSON list of memo types, e.g.
+///
+/// ```json
+/// [{
+///     "type_bytes": "f100",
+///     "name": "invoice",
+///     "fields": [
+///         {"name": "invoice_number", "offset": 0, "len": 8, "kind": "u64"},
+///         {"name": "merchant_id", "offset": 8, "len": 4, "kind": "bytes"}
+///     ]
+/// }]
+/// ```
+///
+/// Field kinds are `bool` (one byte), `u64` (eight bytes, big-endian), `text`
+/// (UTF-8, padded with zero bytes) and `bytes`.
+pub fn memo_registry_from_json(json: &str) -> Result<MemoRegistry, Error> {
+    let memo_types: Vec<MemoTypeConfig> = serde_json::from_str(json)
+        .map_err(|err| Error::MemoTypesConfig(format!("invalid JSON: {err}")))?;
+
+    let mut registry = MemoRegistry::new();
+    for memo_type in memo_types {
+        let mut memo_type_bytes = [0u8; 2];
+        hex::decode_to_slice(&memo_type.type_bytes, &mut memo_type_bytes).map_err(|err| {
+            Error::MemoTypesConfig(format!("{}: type_bytes: {err}", memo_type.name))
+        })?;
+        let layout = MemoLayout {
+            memo_type_bytes,
+            name: memo_type.name.clone(),
+            fields: memo_type
+                .fields
+                .into_iter()
+                .map(|field| MemoFieldLayout {
+                    name: field.name,
+                    offset: field.offset,
+                    len: field.len,
+                    kind: field.kind.into(),
+                })
+                .collect(),
+        };
+        registry
+            .register_layout(layout)
+            .map_err(|err| Error::MemoTypesConfig(format!("{}: {err}", memo_type.name)))?;
+    }
+    Ok(registry)
+}
+
+/// Build a memo registry from a file holding a JSON list of memo types. See
+/// [memo_registry_from_json].
+pub fn load_memo_registry(path: impl AsRef<Path>) -> Result<MemoRegistry, Error> {
+    memo_registry_from_json(&fs::read_to_string(path)?)
+}
+
+/// Render the fields of a registered memo as a JSON object. Bytes are hex
+/// encoded.
+pub fn registered_memo_to_json(memo: &RegisteredMemo) -> String {
+    let fields: Map<String, Value> = memo
+        .fields
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                MemoFieldValue::Bool(b) => Value::from(*b),
+                MemoFieldValue::U64(n) => Value::from(*n),
+                MemoFieldValue::Text(text) => Value::from(text.clone()),
+                MemoFieldValue::Bytes(bytes) => Value::from(hex::encode(bytes)),
+            };
+            (name.clone(), value)
+        })
+        .collect();
+    Value::Object(fields).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_transaction_core::MemoPayload;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    const MEMO_TYPES_JSON: &str = r#"[{
+        "type_bytes": "f100",
+        "name": "invoice",
+        "fields": [
+            {"name": "invoice_number", "offset": 0, "len": 8, "kind": "u64"},
+            {"name": "merchant_id", "offset": 8, "len": 4, "kind": "bytes"}
+        ]
+    }]"#;
+
+    #[test]
+    fn test_load_memo_registry() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(MEMO_TYPES_JSON.as_bytes()).unwrap();
+        let registry = load_memo_registry(file.path()).unwrap();
+
+        let mut memo_data = [0u8; 64];
+        memo_data[0..8].copy_from_slice(&42u64.to_be_bytes());
+        memo_data[8..12].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        let memo = registry
+            .decode(&MemoPayload::new([0xF1, 0x00], memo_data))
+            .unwrap();
+        assert_eq!(memo.name, "invoice");
+
+        let fields: Value = serde_json::from_str(&registered_memo_to_json(&memo)).unwrap();
+        assert_eq!(
+            fields,
+            serde_json::json!({"invoice_number": 42, "merchant_id": "deadbeef"})
+        );
+    }
+
+    #[test]
+    fn test_memo_registry_from_bad_json() {
+        for bad in [
+            // Not a list
+            r#"{"type_bytes": "f100", "name": "invoice", "fields": []}"#,
+            // Bad type bytes
+            r#"[{"type_bytes": "f1", "name": "invoice", "fields": []}]"#,
+            // Unknown field kind
+            r#"[{"type_bytes": "f100", "name": "invoice", "fields": [
+                {"name": "amount", "offset": 0, "len": 8, "kind": "i64"}
+            ]}]"#,
+            // Field out of bounds
+            r#"[{"type_bytes": "f100", "name": "invoice", "fields": [
+                {"name": "amount", "offset": 60, "len": 8, "kind": "u64"}
+            ]}]"#,
+            // Builtin memo type
+            r#"[{"type_bytes": "0100", "name": "impostor", "fields": []}]"#,
+            // Registered twice
+            r#"[
+                {"type_bytes": "f100", "name": "invoice", "fields": []},
+                {"type_bytes": "f100", "name": "receipt", "fields": []}
+            ]"#,
+        ] {
+            assert!(
+                matches!(memo_registry_from_json(bad), Err(Error::MemoTypesConfig(_))),
+                "{bad}"
+            );
+        }
+    }
+}
@@ -47,6 +47,16 @@ pub struct MonitorData {
     /// Optional monitor name.
     #[prost(string, tag = "6")]
     pub name: String,
+
+    /// The gap limit for subaddress discovery. When a TxOut is found at a
+    /// subaddress within this many subaddresses of the end of the watched
+    /// range, the range is extended. Zero disables discovery.
+    #[prost(uint64, tag = "7")]
+    pub gap_limit: u64,
+
+    /// The number of subaddresses discovered past the initial range.
+    #[prost(uint64, tag = "8")]
+    pub num_discovered_subaddresses: u64,
 }
 
 impl MonitorData {
@@ -72,11 +82,34 @@ impl MonitorData {
             // The next block we need to sync is our first block.
             next_block: first_block,
             name: name.to_owned(),
+            gap_limit: 0,
+            num_discovered_subaddresses: 0,
         })
     }
 
+    /// Enable gap-limit subaddress discovery for this monitor.
+    pub fn with_gap_limit(mut self, gap_limit: u64) -> Self {
+        self.gap_limit = gap_limit;
+        self
+    }
+
+    /// The subaddress indexes this monitor watches, including any discovered
+    /// past the initial range.
     pub fn subaddress_indexes(&self) -> Range<u64> {
-        self.first_subaddress..self.first_subaddress + self.num_subaddresses
+        let first = self.first_subaddress;
+        first..first + self.num_subaddresses + self.num_discovered_subaddresses
+    }
+
+    /// The subaddress indexes that need to be added to the watched range so
+    /// that at least `gap_limit` subaddresses follow `used_index`. This is
+    /// empty if discovery is disabled or the range is already large enough.
+    pub fn subaddress_indexes_to_discover(&self, used_index: u64) -> Range<u64> {
+        let end = self.subaddress_indexes().end;
+        if self.gap_limit == 0 {
+            return end..end;
+        }
+        let wanted_end = used_index.saturating_add(self.gap_limit).saturating_add(1);
+        end..wanted_end.max(end)
     }
 }
 
@@ -85,7 +118,8 @@ pub type MonitorId = DatabaseByteArrayKey;
 
 impl From<&MonitorData> for MonitorId {
     // When constructing a MonitorId from a given MonitorData object we only want to
-    // hash the data that doesn't change over time.
+    // hash the data that doesn't change over time. This is why the discovered
+    // subaddresses (and the gap limit that drives discovery) are left out.
     // Name isn't included here - two monitors with identical address/subaddress
     // range/first_block should have the same id even if they have a different
     // name,
@@ -471,4 +505,31 @@ pKZkdp8MQU5TLFOE9qjNeVsCAwEAAQ==
             Err(Error::MonitorIdNotFound)
         );
     }
+
+    // Subaddress discovery keeps at least gap_limit subaddresses past the last used
+    // one.
+    #[test]
+    fn test_subaddress_indexes_to_discover() {
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        let key = AccountKey::from(&RootIdentity::from_random(&mut rng));
+
+        let mut data = MonitorData::new(key, 2, 5, 0, "").unwrap();
+        assert_eq!(data.subaddress_indexes(), 2..7);
+
+        // Discovery is disabled by default.
+        assert!(data.subaddress_indexes_to_discover(6).is_empty());
+
+        data = data.with_gap_limit(3);
+        assert!(data.subaddress_indexes_to_discover(2).is_empty());
+        assert!(data.subaddress_indexes_to_discover(3).is_empty());
+        assert_eq!(data.subaddress_indexes_to_discover(4), 7..8);
+        assert_eq!(data.subaddress_indexes_to_discover(6), 7..10);
+
+        // Discovered subaddresses extend the watched range without changing the id.
+        let id = MonitorId::from(&data);
+        data.num_discovered_subaddresses = 3;
+        assert_eq!(data.subaddress_indexes(), 2..10);
+        assert!(data.subaddress_indexes_to_discover(6).is_empty());
+        assert_eq!(MonitorId::from(&data), id);
+    }
 }
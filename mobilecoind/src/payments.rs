@@ -18,7 +18,7 @@ use mc_fog_report_validation::FogPubkeyResolver;
 use mc_ledger_db::{Error as LedgerError, Ledger, LedgerDB};
 use mc_rand::{CryptoRng, RngCore};
 use mc_transaction_builder::{
-    InputCredentials, MemoBuilder, ReservedSubaddresses, SignedContingentInputBuilder,
+    InputCredentials, MemoBuilder, ReservedSubaddresses, SciFill, SignedContingentInputBuilder,
     TransactionBuilder, TxOutContext,
 };
 use mc_transaction_core::{
@@ -28,9 +28,7 @@ use mc_transaction_core::{
     tx::{Tx, TxOut, TxOutMembershipProof},
     Amount, FeeMap, TokenId,
 };
-use mc_transaction_extra::{
-    SignedContingentInput, SignedContingentInputAmounts, TxOutConfirmationNumber,
-};
+use mc_transaction_extra::{SignedContingentInput, TxOutConfirmationNumber};
 use mc_util_uri::FogUri;
 use rand::Rng;
 use std::{
@@ -382,7 +380,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
                 outlay.amount.value as i128;
         }
 
-        let mut scis_and_amounts = Vec::default();
+        let mut scis_with_proofs = Vec::default();
         for sci_for_tx in scis {
             let sci_amounts = sci_for_tx.sci.validate()?;
             sci_amounts.add_to_balance_sheet(sci_for_tx.partial_fill_value, &mut balance_sheet)?;
@@ -393,7 +391,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             let mut sci_for_tx = sci_for_tx.clone();
             let proofs = self.get_membership_proofs(&sci_for_tx.sci.tx_in.ring)?;
             sci_for_tx.sci.tx_in.proofs = proofs;
-            scis_and_amounts.push((sci_for_tx, sci_amounts));
+            scis_with_proofs.push(sci_for_tx);
         }
 
         // Select the UTXOs to be used for this transaction.
@@ -468,7 +466,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         let tx_proposal = Self::build_tx_proposal(
             &selected_utxos_with_proofs,
             rings,
-            &scis_and_amounts,
+            &scis_with_proofs,
             block_version,
             fee_token_id,
             fee,
@@ -1107,7 +1105,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
     fn build_tx_proposal(
         inputs: &[(UnspentTxOut, TxOutMembershipProof)],
         rings: Vec<Vec<(TxOut, TxOutMembershipProof)>>,
-        scis: &[(SciForTx, SignedContingentInputAmounts)],
+        scis: &[SciForTx],
         block_version: BlockVersion,
         fee_token_id: TokenId,
         fee: u64,
@@ -1247,33 +1245,19 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             *balance_sheet.entry(utxo.token_id.into()).or_default() -= utxo.value as i128;
         }
 
-        for (sci_for_tx, sci_amounts) in scis {
-            if sci_for_tx.partial_fill_value == 0 {
-                tx_builder.add_presigned_input(sci_for_tx.sci.clone())?;
-            } else {
-                // The tx_builder expects to be given the partial fill change value,
-                // when the sci is a partial fill. However our API is that the user
-                // passes the amount the user wants to claim out of the partial
-                // fill change value. So we have to subtract.
-                let partial_fill_change = sci_amounts
-                    .partial_fill_change
-                    .as_ref()
-                    .ok_or_else(|| Error::TxBuild("sci is missing partial fill change".into()))?;
-
-                if sci_for_tx.partial_fill_value > partial_fill_change.value {
-                    return Err(Error::TxBuild(format!(
-                        "sci partial fill amount is invalid: {} > {}",
-                        sci_for_tx.partial_fill_value, partial_fill_change.value,
-                    )));
-                }
-                let real_change_amount = Amount::new(
-                    partial_fill_change.value - sci_for_tx.partial_fill_value,
-                    partial_fill_change.token_id,
-                );
-                tx_builder
-                    .add_presigned_partial_fill_input(sci_for_tx.sci.clone(), real_change_amount)?;
-            }
-            sci_amounts.add_to_balance_sheet(sci_for_tx.partial_fill_value, &mut balance_sheet)?;
+        // Add the SCIs, along with the outputs their rules require.
+        let sci_fills = scis
+            .iter()
+            .map(|sci_for_tx| SciFill {
+                sci: sci_for_tx.sci.clone(),
+                partial_fill_value: sci_for_tx.partial_fill_value,
+            })
+            .collect();
+        let sci_balance_sheet = tx_builder
+            .add_presigned_inputs_for_settlement(sci_fills)
+            .map_err(|err| Error::TxBuild(format!("failed adding scis: {err}")))?;
+        for (token_id, value) in sci_balance_sheet {
+            *balance_sheet.entry(token_id).or_default() += value;
         }
 
         // Add outputs to our destinations.
@@ -7,6 +7,7 @@
 //! * writes matching transactions to a local DB, organized by subaddress_id
 
 use crate::{
+    conversions::{tx_proposal_to_proto, unspent_tx_out_to_proto},
     database::Database,
     error::Error,
     monitor_store::{MonitorData, MonitorId},
@@ -30,16 +31,18 @@ use mc_common::{
 };
 use mc_connection::{BlockInfo, BlockchainConnection, UserTxConnection};
 use mc_core::slip10::Slip10KeyGenerator;
-use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPrivate, RistrettoPublic};
+use mc_crypto_keys::{
+    CompressedRistrettoPublic, Ed25519Pair, Ed25519Private, RistrettoPrivate, RistrettoPublic,
+};
 use mc_fog_report_validation::FogPubkeyResolver;
-use mc_ledger_db::{Error as LedgerError, Ledger, LedgerDB};
+use mc_ledger_db::{Error as LedgerError, FeeScanner, Ledger, LedgerDB};
 use mc_ledger_sync::{NetworkState, PollingNetworkState};
 use mc_mobilecoind_api::{
     self as api,
     mobilecoind_api_grpc::{create_mobilecoind_api, MobilecoindApi},
     MobilecoindUri,
 };
-use mc_transaction_builder::BurnRedemptionMemoBuilder;
+use mc_transaction_builder::{BurnRedemptionMemoBuilder, PaymentId};
 use mc_transaction_core::{
     get_tx_out_shared_secret,
     onetime_keys::recover_onetime_private_key,
@@ -47,7 +50,7 @@ use mc_transaction_core::{
     tx::{TxOut, TxOutMembershipProof},
     Amount, MemoPayload, TokenId,
 };
-use mc_transaction_extra::{BurnRedemptionMemo, MemoType, TxOutConfirmationNumber};
+use mc_transaction_extra::{BurnRedemptionMemo, MemoRegistry, MemoType, TxOutConfirmationNumber};
 use mc_util_from_random::FromRandom;
 use mc_util_grpc::{
     rpc_internal_error, rpc_invalid_arg_error, rpc_logger, send_result, AdminService,
@@ -56,7 +59,10 @@ use mc_util_grpc::{
 use mc_watcher::watcher_db::WatcherDB;
 use mc_watcher_api::TimestampResultCode;
 use protobuf::{ProtobufEnum, RepeatedField};
-use std::sync::{Arc, Mutex, RwLock};
+use std::{
+    sync::{Arc, Mutex, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 pub struct Service {
     /// Sync thread.
@@ -79,6 +85,7 @@ impl Service {
         listen_uri: &MobilecoindUri,
         num_workers: Option<usize>,
         chain_id: String,
+        memo_registry: Arc<MemoRegistry>,
         logger: Logger,
     ) -> Self {
         let sync_thread = if mobilecoind_db.is_db_encrypted() {
@@ -120,6 +127,7 @@ impl Service {
             network_state,
             start_sync_thread,
             chain_id,
+            memo_registry,
             logger.clone(),
         );
 
@@ -179,6 +187,7 @@ pub struct ServiceApi<
     network_state: Arc<RwLock<PollingNetworkState<T>>>,
     start_sync_thread: Arc<dyn Fn() + Send + Sync>,
     chain_id: String,
+    memo_registry: Arc<MemoRegistry>,
     logger: Logger,
 }
 
@@ -194,6 +203,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             network_state: self.network_state.clone(),
             start_sync_thread: self.start_sync_thread.clone(),
             chain_id: self.chain_id.clone(),
+            memo_registry: self.memo_registry.clone(),
             logger: self.logger.clone(),
         }
     }
@@ -210,6 +220,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         network_state: Arc<RwLock<PollingNetworkState<T>>>,
         start_sync_thread: Arc<dyn Fn() + Send + Sync>,
         chain_id: String,
+        memo_registry: Arc<MemoRegistry>,
         logger: Logger,
     ) -> Self {
         Self {
@@ -220,6 +231,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             network_state,
             start_sync_thread,
             chain_id,
+            memo_registry,
             logger,
         }
     }
@@ -264,7 +276,8 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             request.first_block,
             &request.name,
         )
-        .map_err(|err| rpc_internal_error("monitor_data.new", err, &self.logger))?;
+        .map_err(|err| rpc_internal_error("monitor_data.new", err, &self.logger))?
+        .with_gap_limit(request.gap_limit);
 
         // Insert into database. Return the id and flag if the monitor already existed.
         let (id, is_new) = match self.mobilecoind_db.add_monitor(&data) {
@@ -337,6 +350,8 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         status.set_num_subaddresses(data.num_subaddresses);
         status.set_first_block(data.first_block);
         status.set_next_block(data.next_block);
+        status.set_gap_limit(data.gap_limit);
+        status.set_num_discovered_subaddresses(data.num_discovered_subaddresses);
 
         let mut response = api::GetMonitorStatusResponse::new();
         response.set_status(status);
@@ -366,7 +381,10 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             .collect();
 
         // Convert to protos.
-        let proto_utxos: Vec<api::UnspentTxOut> = utxos.iter().map(|utxo| utxo.into()).collect();
+        let proto_utxos: Vec<api::UnspentTxOut> = utxos
+            .iter()
+            .map(|utxo| unspent_tx_out_to_proto(utxo, &self.memo_registry))
+            .collect();
 
         // Return response.
         let mut response = api::GetUnspentTxOutListResponse::new();
@@ -391,7 +409,10 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             })?;
 
         // Convert to protos.
-        let proto_utxos: Vec<api::UnspentTxOut> = utxos.iter().map(|utxo| utxo.into()).collect();
+        let proto_utxos: Vec<api::UnspentTxOut> = utxos
+            .iter()
+            .map(|utxo| unspent_tx_out_to_proto(utxo, &self.memo_registry))
+            .collect();
 
         // Return response.
         let mut response = api::GetAllUnspentTxOutResponse::new();
@@ -555,7 +576,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
 
         let mut response = api::ValidateAuthenticatedSenderMemoResponse::new();
 
-        response.set_success(bool::from(match MemoType::try_from(&memo_payload) {
+        let success = match MemoType::try_from(&memo_payload) {
             Ok(MemoType::AuthenticatedSender(memo)) => {
                 memo.validate(&sender, &subaddress_vpk, tx_out_public_key)
             }
@@ -565,6 +586,22 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             Ok(MemoType::AuthenticatedSenderWithPaymentIntentId(memo)) => {
                 memo.validate(&sender, &subaddress_vpk, tx_out_public_key)
             }
+            Ok(MemoType::AuthenticatedSenderWithText(memo)) => {
+                let success = memo.validate(&sender, &subaddress_vpk, tx_out_public_key);
+                if bool::from(success) {
+                    let chunk = memo
+                        .decrypt(&sender, &subaddress_vpk, tx_out_public_key)
+                        .map_err(|err| {
+                            rpc_invalid_arg_error("text_memo.decrypt", err, &self.logger)
+                        })?;
+                    let mut text_chunk = api::TextMemoChunk::new();
+                    text_chunk.set_index(chunk.index as u32);
+                    text_chunk.set_num_chunks(chunk.num_chunks as u32);
+                    text_chunk.set_text(chunk.text);
+                    response.set_text_chunk(text_chunk);
+                }
+                success
+            }
             Ok(other) => {
                 return Err(rpc_invalid_arg_error(
                     "Not an authenticated sender memo",
@@ -572,14 +609,23 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
                     &self.logger,
                 ));
             }
-            Err(err) => {
-                return Err(rpc_invalid_arg_error(
-                    "Not an authenticated sender memo",
-                    format!("{err:?}"),
-                    &self.logger,
-                ));
-            }
-        }));
+            // Application-defined memo types may be authenticated as well.
+            Err(err) => match self
+                .memo_registry
+                .decode(&memo_payload)
+                .and_then(|memo| memo.validate(&sender, &subaddress_vpk, tx_out_public_key))
+            {
+                Some(success) => success,
+                None => {
+                    return Err(rpc_invalid_arg_error(
+                        "Not an authenticated sender memo",
+                        format!("{err:?}"),
+                        &self.logger,
+                    ));
+                }
+            },
+        };
+        response.set_success(bool::from(success));
 
         Ok(response)
     }
@@ -770,7 +816,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         response.set_bip39_entropy(transfer_payload.get_bip39_entropy().to_vec());
         response.set_tx_public_key((&tx_public_key).into());
         response.set_memo(transfer_payload.get_memo().to_string());
-        response.set_utxo((&utxo).into());
+        response.set_utxo(unspent_tx_out_to_proto(&utxo, &self.memo_registry));
 
         Ok(response)
     }
@@ -850,7 +896,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
                     rpc_invalid_arg_error("PrintableWrapper_b58_decode", err, &self.logger)
                 })?;
 
-        // An address code could be a public address or a payment request
+        // An address code could be a public address, a payment request or an invoice
         if wrapper.has_payment_request() {
             let payment_request = wrapper.get_payment_request();
             let mut response = api::ParseAddressCodeResponse::new();
@@ -861,10 +907,15 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             let mut response = api::ParseAddressCodeResponse::new();
             response.set_receiver(public_address.clone());
             Ok(response)
+        } else if wrapper.has_invoice() {
+            let invoice = wrapper.get_invoice();
+            let mut response = api::ParseAddressCodeResponse::new();
+            response.set_receiver(invoice.get_public_address().clone());
+            Ok(response)
         } else {
             Err(RpcStatus::with_message(
                 RpcStatusCode::INVALID_ARGUMENT,
-                "Neither payment request nor public address".into(),
+                "Neither payment request, public address nor invoice".into(),
             ))
         }
     }
@@ -888,6 +939,97 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(response)
     }
 
+    fn parse_invoice_impl(
+        &mut self,
+        request: api::ParseInvoiceRequest,
+    ) -> Result<api::ParseInvoiceResponse, RpcStatus> {
+        let mut wrapper =
+            api::printable::PrintableWrapper::b58_decode(request.get_b58_code().to_string())
+                .map_err(|err| {
+                    rpc_invalid_arg_error("PrintableWrapper_b58_decode", err, &self.logger)
+                })?;
+
+        if !wrapper.has_invoice() {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "invoice".into(),
+            ));
+        }
+        let invoice = wrapper.take_invoice();
+
+        let totals = invoice
+            .validate()
+            .map_err(|err| rpc_invalid_arg_error("invoice.validate", err, &self.logger))?;
+
+        let num_blocks = self
+            .ledger_db
+            .num_blocks()
+            .map_err(|err| rpc_internal_error("ledger_db.num_blocks", err, &self.logger))?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| rpc_internal_error("duration_since", err, &self.logger))?
+            .as_secs();
+        let is_expired = invoice.check_expiry(num_blocks, now).is_err();
+
+        let mut response = api::ParseInvoiceResponse::new();
+        response.set_totals(
+            totals
+                .into_iter()
+                .map(|(token_id, value)| (&Amount::new(value, token_id)).into())
+                .collect(),
+        );
+        response.set_is_expired(is_expired);
+        response.set_has_merchant_signature(invoice.has_merchant_identity());
+        response.set_invoice(invoice);
+        Ok(response)
+    }
+
+    fn create_invoice_impl(
+        &mut self,
+        request: api::CreateInvoiceRequest,
+    ) -> Result<api::CreateInvoiceResponse, RpcStatus> {
+        let receiver = PublicAddress::try_from(request.get_receiver())
+            .map_err(|err| rpc_invalid_arg_error("PublicAddress.try_from", err, &self.logger))?;
+
+        let mut invoice = api::printable::Invoice::new();
+        invoice.set_public_address((&receiver).into());
+        invoice.set_line_items(request.get_line_items().into());
+        invoice.set_memo(request.get_memo().to_string());
+        invoice.set_payment_intent_id(request.payment_intent_id);
+        invoice.set_expiry_block(request.expiry_block);
+        invoice.set_expiry_timestamp(request.expiry_timestamp);
+        invoice.set_callback_url(request.get_callback_url().to_string());
+
+        if !request.merchant_signing_key.is_empty() {
+            let private_key = Ed25519Private::try_from(request.get_merchant_signing_key())
+                .map_err(|err| rpc_invalid_arg_error("merchant_signing_key", err, &self.logger))?;
+            invoice.sign_merchant_identity(
+                request.get_merchant_name(),
+                &Ed25519Pair::from(private_key),
+            );
+        } else if !request.merchant_name.is_empty() {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "merchant_name requires merchant_signing_key".into(),
+            ));
+        }
+
+        invoice
+            .validate()
+            .map_err(|err| rpc_invalid_arg_error("invoice.validate", err, &self.logger))?;
+
+        let mut wrapper = api::printable::PrintableWrapper::new();
+        wrapper.set_invoice(invoice);
+
+        let encoded = wrapper
+            .b58_encode()
+            .map_err(|err| rpc_internal_error("b58_encode", err, &self.logger))?;
+
+        let mut response = api::CreateInvoiceResponse::new();
+        response.set_b58_code(encoded);
+        Ok(response)
+    }
+
     /// Get mixins
     fn get_mixins_impl(
         &mut self,
@@ -1091,6 +1233,9 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         // Get transaction memo builder.
         let transaction_memo = TransactionMemo::try_from(request.get_memo())
             .map_err(|err| rpc_invalid_arg_error("transaction_memo.try_from", err, &self.logger))?;
+        transaction_memo
+            .check_outlays(&outlays)
+            .map_err(|err| rpc_invalid_arg_error("transaction_memo", err, &self.logger))?;
         let memo_builder = transaction_memo.memo_builder(&sender_monitor_data.account_key);
 
         // Attempt to construct a transaction.
@@ -1113,7 +1258,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
 
         // Success.
         let mut response = api::GenerateTxResponse::new();
-        response.set_tx_proposal((&tx_proposal).into());
+        response.set_tx_proposal(tx_proposal_to_proto(&tx_proposal, &self.memo_registry));
         Ok(response)
     }
 
@@ -1220,7 +1365,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
 
         // Success.
         let mut response = api::GenerateMixedTxResponse::new();
-        response.set_tx_proposal((&tx_proposal).into());
+        response.set_tx_proposal(tx_proposal_to_proto(&tx_proposal, &self.memo_registry));
         Ok(response)
     }
 
@@ -1252,7 +1397,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
 
         // Success.
         let mut response = api::GenerateOptimizationTxResponse::new();
-        response.set_tx_proposal((&tx_proposal).into());
+        response.set_tx_proposal(tx_proposal_to_proto(&tx_proposal, &self.memo_registry));
         Ok(response)
     }
 
@@ -1313,7 +1458,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             })?;
 
         let mut response = api::GenerateTxFromTxOutListResponse::new();
-        response.set_tx_proposal((&tx_proposal).into());
+        response.set_tx_proposal(tx_proposal_to_proto(&tx_proposal, &self.memo_registry));
         Ok(response)
     }
 
@@ -1428,7 +1573,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
 
         // Success.
         let mut response = api::GenerateBurnRedemptionTxResponse::new();
-        response.set_tx_proposal((&tx_proposal).into());
+        response.set_tx_proposal(tx_proposal_to_proto(&tx_proposal, &self.memo_registry));
         Ok(response)
     }
 
@@ -2284,6 +2429,91 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         })
     }
 
+    fn get_fee_report_impl(
+        &mut self,
+        request: api::GetFeeReportRequest,
+    ) -> Result<api::GetFeeReportResponse, RpcStatus> {
+        let view_private_key = RistrettoPrivate::try_from(request.get_fee_view_private_key())
+            .map_err(|err| {
+                rpc_invalid_arg_error("fee_view_private_key.try_from", err, &self.logger)
+            })?;
+        let spend_public_key = if request.has_fee_spend_public_key() {
+            Some(
+                RistrettoPublic::try_from(request.get_fee_spend_public_key()).map_err(|err| {
+                    rpc_invalid_arg_error("fee_spend_public_key.try_from", err, &self.logger)
+                })?,
+            )
+        } else {
+            None
+        };
+
+        let num_blocks = self
+            .ledger_db
+            .num_blocks()
+            .map_err(|err| rpc_internal_error("ledger_db.num_blocks", err, &self.logger))?;
+        let end_block = if request.end_block == 0 {
+            num_blocks
+        } else {
+            request.end_block
+        };
+        if request.start_block > end_block || end_block > num_blocks {
+            return Err(rpc_invalid_arg_error(
+                "end_block",
+                format!(
+                    "block range {}..{} is not within the ledger ({} blocks)",
+                    request.start_block, end_block, num_blocks
+                ),
+                &self.logger,
+            ));
+        }
+
+        let scanner = FeeScanner::new(view_private_key, spend_public_key);
+        let reports = scanner
+            .report(
+                &self.ledger_db,
+                request.start_block..end_block,
+                request.blocks_per_period,
+            )
+            .map_err(|err| rpc_internal_error("fee_scanner.report", err, &self.logger))?;
+
+        let mut periods = Vec::with_capacity(reports.len());
+        for report in reports {
+            let mut totals = Vec::with_capacity(report.totals.len());
+            for (token_id, total) in report.totals {
+                // It's possible the total does not fit into a u64.
+                if total > u64::MAX.into() {
+                    return Err(RpcStatus::with_message(
+                        RpcStatusCode::INTERNAL,
+                        format!(
+                            "fees of {total} for token {token_id} won't fit in u64, use shorter periods instead"
+                        ),
+                    ));
+                }
+                totals.push(api::TokenFeeTotal {
+                    token_id: *token_id,
+                    total: total as u64,
+                    ..Default::default()
+                });
+            }
+
+            let (timestamp, timestamp_result_code) = self.get_block_timestamp(report.blocks.start);
+            periods.push(api::FeePeriod {
+                start_block: report.blocks.start,
+                end_block: report.blocks.end,
+                totals: totals.into(),
+                num_fee_outputs: report.num_fee_outputs,
+                start_timestamp_result_code: (&timestamp_result_code).into(),
+                start_timestamp: timestamp,
+                ..Default::default()
+            });
+        }
+
+        Ok(api::GetFeeReportResponse {
+            periods: periods.into(),
+            ..Default::default()
+        })
+    }
+
     fn get_balance_impl(
         &mut self,
         request: api::GetBalanceRequest,
@@ -2325,6 +2555,70 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
     fn send_payment_impl(
         &mut self,
         request: api::SendPaymentRequest,
+    ) -> Result<api::SendPaymentResponse, RpcStatus> {
+        // Get transaction memo.
+        let transaction_memo = TransactionMemo::try_from(request.get_memo())
+            .map_err(|err| rpc_invalid_arg_error("transaction_memo.try_from", err, &self.logger))?;
+
+        self.send_payment_with_memo(request, transaction_memo)
+    }
+
+    fn send_batch_payment_impl(
+        &mut self,
+        request: api::SendBatchPaymentRequest,
+    ) -> Result<api::SendPaymentResponse, RpcStatus> {
+        if request.get_outlay_list().is_empty() {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "outlay_list".into(),
+            ));
+        }
+
+        // Each outlay carries its own payment id, which goes in the memo of its
+        // output.
+        let mut outlays = Vec::new();
+        let mut payments = Vec::new();
+        for batch_outlay in request.get_outlay_list() {
+            let receiver = PublicAddress::try_from(batch_outlay.get_outlay().get_receiver())
+                .map_err(|err| rpc_invalid_arg_error("receiver.try_from", err, &self.logger))?;
+            let payment_id = match batch_outlay.payment_id {
+                None => None,
+                Some(api::BatchOutlay_oneof_payment_id::payment_intent_id(id)) => {
+                    Some(PaymentId::Intent(id))
+                }
+                Some(api::BatchOutlay_oneof_payment_id::payment_request_id(id)) => {
+                    Some(PaymentId::Request(id))
+                }
+            };
+            outlays.push(batch_outlay.get_outlay().clone());
+            payments.push((receiver, payment_id));
+        }
+        let transaction_memo = TransactionMemo::BatchPayment {
+            subaddress_index: request
+                .has_memo_subaddress_index()
+                .then(|| request.get_memo_subaddress_index()),
+            payments,
+        };
+
+        // Forward to SendPayment
+        let mut send_payment_request = api::SendPaymentRequest::new();
+        send_payment_request.set_sender_monitor_id(request.get_sender_monitor_id().to_vec());
+        send_payment_request.set_sender_subaddress(request.get_sender_subaddress());
+        send_payment_request.set_outlay_list(RepeatedField::from_vec(outlays));
+        send_payment_request.set_fee(request.get_fee());
+        send_payment_request.set_tombstone(request.get_tombstone());
+        send_payment_request.set_max_input_utxo_value(request.get_max_input_utxo_value());
+        send_payment_request.set_override_change_subaddress(request.override_change_subaddress);
+        send_payment_request.set_change_subaddress(request.change_subaddress);
+        send_payment_request.set_token_id(request.token_id);
+
+        self.send_payment_with_memo(send_payment_request, transaction_memo)
+    }
+
+    fn send_payment_with_memo(
+        &mut self,
+        request: api::SendPaymentRequest,
+        transaction_memo: TransactionMemo,
     ) -> Result<api::SendPaymentResponse, RpcStatus> {
         // Get sender monitor id from request.
         let sender_monitor_id = MonitorId::try_from(&request.sender_monitor_id)
@@ -2372,8 +2666,9 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         };
 
         // Get transaction memo builder.
-        let transaction_memo = TransactionMemo::try_from(request.get_memo())
-            .map_err(|err| rpc_invalid_arg_error("transaction_memo.try_from", err, &self.logger))?;
+        transaction_memo
+            .check_outlays(&outlays)
+            .map_err(|err| rpc_invalid_arg_error("transaction_memo", err, &self.logger))?;
         let memo_builder = transaction_memo.memo_builder(&sender_monitor_data.account_key);
 
         // Attempt to construct a transaction.
@@ -2394,7 +2689,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
                 rpc_internal_error("transactions_manager.build_transaction", err, &self.logger)
             })?;
 
-        let proto_tx_proposal = api::TxProposal::from(&tx_proposal);
+        let proto_tx_proposal = tx_proposal_to_proto(&tx_proposal, &self.memo_registry);
 
         // Submit transaction.
         let mut submit_tx_request = api::SubmitTxRequest::new();
@@ -2663,6 +2958,8 @@ build_api! {
     create_transfer_code CreateTransferCodeRequest CreateTransferCodeResponse create_transfer_code_impl,
     parse_address_code ParseAddressCodeRequest ParseAddressCodeResponse parse_address_code_impl,
     create_address_code CreateAddressCodeRequest CreateAddressCodeResponse create_address_code_impl,
+    parse_invoice ParseInvoiceRequest ParseInvoiceResponse parse_invoice_impl,
+    create_invoice CreateInvoiceRequest CreateInvoiceResponse create_invoice_impl,
 
     // Transactions
     get_mixins GetMixinsRequest GetMixinsResponse get_mixins_impl,
@@ -2689,10 +2986,12 @@ build_api! {
     get_processed_block GetProcessedBlockRequest GetProcessedBlockResponse get_processed_block_impl,
     get_block_index_by_tx_pub_key GetBlockIndexByTxPubKeyRequest GetBlockIndexByTxPubKeyResponse get_block_index_by_tx_pub_key_impl,
     get_tx_out_results_by_pub_key GetTxOutResultsByPubKeyRequest GetTxOutResultsByPubKeyResponse get_tx_out_results_by_pub_key_impl,
+    get_fee_report GetFeeReportRequest GetFeeReportResponse get_fee_report_impl,
 
     // Convenience calls
     get_balance GetBalanceRequest GetBalanceResponse get_balance_impl,
     send_payment SendPaymentRequest SendPaymentResponse send_payment_impl,
+    send_batch_payment SendBatchPaymentRequest SendPaymentResponse send_batch_payment_impl,
     pay_address_code PayAddressCodeRequest SendPaymentResponse pay_address_code_impl,
 
     // Network status
@@ -2710,6 +3009,7 @@ build_api! {
 mod test {
     use super::*;
     use crate::{
+        memo_registry::memo_registry_from_json,
         payments::DEFAULT_NEW_TX_BLOCK_ATTEMPTS,
         subaddress_store::SubaddressSPKId,
         test_utils::{
@@ -2737,7 +3037,9 @@ mod test {
         tx::Tx,
         CompressedCommitment, EncryptedMemo, MaskedAmount, MaskedAmountV2, Token,
     };
-    use mc_transaction_extra::{SenderMemoCredential, SignedContingentInput};
+    use mc_transaction_extra::{
+        AuthenticatedSenderWithTextMemo, SenderMemoCredential, SignedContingentInput,
+    };
     use mc_util_repr_bytes::{typenum::U32, GenericArray, ReprBytes};
     use mc_util_uri::FogUri;
     use rand::{rngs::StdRng, SeedableRng};
@@ -3103,6 +3405,85 @@ mod test {
         );
     }
 
+    #[test_with_logger]
+    fn test_get_unspent_tx_out_list_decodes_registered_memos(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let account_key = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            account_key.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        let (mut ledger_db, mobilecoind_db) = test_utils::get_test_databases(
+            BLOCK_VERSION,
+            3,
+            &[],
+            test_utils::GET_TESTING_ENVIRONMENT_NUM_BLOCKS,
+            logger.clone(),
+            &mut rng,
+        );
+
+        // Pay the account a TxOut with an application-defined memo.
+        let memo_registry = memo_registry_from_json(
+            r#"[{
+                "type_bytes": "f100",
+                "name": "invoice",
+                "fields": [{"name": "invoice_number", "offset": 0, "len": 8, "kind": "u64"}]
+            }]"#,
+        )
+        .unwrap();
+        let mut memo_data = [0u8; 64];
+        memo_data[0..8].copy_from_slice(&42u64.to_be_bytes());
+        let amount = Amount::new(5000000, Mob::ID);
+        let tx_out = TxOut::new_with_memo(
+            BLOCK_VERSION,
+            amount,
+            &account_key.default_subaddress(),
+            &FromRandom::from_random(&mut rng),
+            EncryptedFogHint::fake_onetime_hint(&mut rng),
+            |_| Ok(MemoPayload::new([0xF1, 0x00], memo_data)),
+        )
+        .unwrap();
+        add_txos_to_ledger(&mut ledger_db, BLOCK_VERSION, &[tx_out], &mut rng).unwrap();
+
+        let port = test_utils::get_free_port();
+        let uri =
+            MobilecoindUri::from_str(&format!("insecure-mobilecoind://127.0.0.1:{port}/")).unwrap();
+        let (_server, _server_conn_manager) = test_utils::setup_server::<MockFogResolver>(
+            logger.clone(),
+            ledger_db.clone(),
+            mobilecoind_db.clone(),
+            None,
+            None,
+            memo_registry,
+            &uri,
+        );
+        let client = test_utils::setup_client(&uri, &logger);
+
+        let id = mobilecoind_db.add_monitor(&data).unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        let mut request = api::GetUnspentTxOutListRequest::new();
+        request.set_monitor_id(id.to_vec());
+        request.set_subaddress_index(0);
+        let response = client
+            .get_unspent_tx_out_list(&request)
+            .expect("failed to get unspent tx out list");
+
+        assert_eq!(response.output_list.len(), 1);
+        let decoded_memo = response.output_list[0].get_decoded_memo();
+        assert!(decoded_memo.has_registered_memo());
+        let registered = decoded_memo.get_registered_memo();
+        assert_eq!(registered.get_name(), "invoice");
+        let fields: serde_json::Value = serde_json::from_str(registered.get_fields_json()).unwrap();
+        assert_eq!(fields, serde_json::json!({"invoice_number": 42}));
+    }
+
     #[test_with_logger]
     fn test_get_all_unspent_tx_out_impl(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
@@ -4762,7 +5143,7 @@ mod test {
             utxos
                 .iter()
                 .filter(|utxo| utxo.token_id == *Mob::ID)
-                .map(api::UnspentTxOut::from)
+                .map(|utxo| unspent_tx_out_to_proto(utxo, &MemoRegistry::new()))
                 .next()
                 .unwrap(),
         );
@@ -4819,7 +5200,7 @@ mod test {
                 utxos
                     .iter()
                     .filter(|utxo| utxo.token_id == 1)
-                    .map(api::UnspentTxOut::from)
+                    .map(|utxo| unspent_tx_out_to_proto(utxo, &MemoRegistry::new()))
                     .next()
                     .unwrap(),
             );
@@ -4992,7 +5373,7 @@ mod test {
             utxos
                 .iter()
                 .filter(|utxo| utxo.token_id == *Mob::ID)
-                .map(api::UnspentTxOut::from)
+                .map(|utxo| unspent_tx_out_to_proto(utxo, &MemoRegistry::new()))
                 .collect(),
         ));
         request.set_outlay_list(RepeatedField::from_vec(
@@ -5114,7 +5495,7 @@ mod test {
                 utxos
                     .iter()
                     .filter(|utxo| utxo.token_id == 2)
-                    .map(api::UnspentTxOut::from)
+                    .map(|utxo| unspent_tx_out_to_proto(utxo, &MemoRegistry::new()))
                     .collect(),
             ));
             request.set_outlay_list(RepeatedField::from_vec(
@@ -5275,7 +5656,10 @@ mod test {
             request.set_sender_monitor_id(monitor_id.to_vec());
             request.set_change_subaddress(0);
             request.set_input_list(RepeatedField::from_vec(
-                utxos.iter().map(api::UnspentTxOut::from).collect(),
+                utxos
+                    .iter()
+                    .map(|utxo| unspent_tx_out_to_proto(utxo, &MemoRegistry::new()))
+                    .collect(),
             ));
             request.set_outlay_list(RepeatedField::from_vec(
                 outlays.iter().map(api::Outlay::from).collect(),
@@ -5354,7 +5738,7 @@ mod test {
             utxos
                 .iter()
                 .filter(|utxo| utxo.token_id == *Mob::ID)
-                .map(api::UnspentTxOut::from)
+                .map(|utxo| unspent_tx_out_to_proto(utxo, &MemoRegistry::new()))
                 .collect(),
         ));
         request.set_outlay_list(RepeatedField::from_vec(
@@ -5429,6 +5813,83 @@ mod test {
         }
     }
 
+    #[test_with_logger]
+    fn test_generate_tx_rejects_text_memo_that_does_not_fit(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let sender = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            sender.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[sender.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 0)
+            .unwrap();
+        let receiver = AccountKey::random(&mut rng).default_subaddress();
+        let outlay = Outlay {
+            value: 123,
+            receiver,
+            tx_private_key: None,
+        };
+
+        // The text needs two outputs to the recipient.
+        let mut text = mc_mobilecoind_api::TransactionMemo_Text::default();
+        text.set_text("a".repeat(AuthenticatedSenderWithTextMemo::TEXT_CHUNK_LEN + 1));
+
+        let mut request = api::GenerateTxRequest::new();
+        request.set_sender_monitor_id(monitor_id.to_vec());
+        request.set_change_subaddress(0);
+        request.set_input_list(RepeatedField::from_vec(
+            utxos
+                .iter()
+                .map(|utxo| unspent_tx_out_to_proto(utxo, &MemoRegistry::new()))
+                .collect(),
+        ));
+        request.set_outlay_list(RepeatedField::from_vec(vec![api::Outlay::from(&outlay)]));
+        request.set_memo(mc_mobilecoind_api::TransactionMemo {
+            transaction_memo: Some(
+                mc_mobilecoind_api::TransactionMemo_oneof_transaction_memo::text(text),
+            ),
+            ..Default::default()
+        });
+
+        // A single outlay is rejected as an invalid argument, before building.
+        match client.generate_tx(&request) {
+            Ok(_) => panic!("Should've returned an error"),
+            Err(GrpcError::RpcFailure(rpc_status)) => {
+                assert_eq!(rpc_status.code(), RpcStatusCode::INVALID_ARGUMENT);
+                assert!(rpc_status.message().starts_with("transaction_memo"));
+            }
+            Err(err) => panic!("Unexpected error: {err:?}"),
+        };
+
+        // Two outlays to the same recipient carry the whole message.
+        request.set_outlay_list(RepeatedField::from_vec(vec![
+            api::Outlay::from(&outlay),
+            api::Outlay::from(&outlay),
+        ]));
+        let response = client.generate_tx(&request).unwrap();
+        let tx = Tx::try_from(response.get_tx_proposal().get_tx()).unwrap();
+        assert_eq!(tx.prefix.outputs.len(), 3);
+    }
+
     #[test_with_logger]
     fn test_generate_mixed_tx(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
@@ -5499,7 +5960,7 @@ mod test {
         let offered_input = utxos
             .iter()
             .filter(|utxo| utxo.token_id == *Mob::ID)
-            .map(api::UnspentTxOut::from)
+            .map(|utxo| unspent_tx_out_to_proto(utxo, &MemoRegistry::new()))
             .next()
             .unwrap();
         let offered_value = offered_input.value;
@@ -6040,7 +6501,10 @@ mod test {
         request.set_sender_monitor_id(monitor_id.to_vec());
         request.set_change_subaddress(0);
         request.set_input_list(RepeatedField::from_vec(
-            utxos.iter().map(api::UnspentTxOut::from).collect(),
+            utxos
+                .iter()
+                .map(|utxo| unspent_tx_out_to_proto(utxo, &MemoRegistry::new()))
+                .collect(),
         ));
         request.set_value(1337);
 
@@ -6249,7 +6713,10 @@ mod test {
         let mut request = api::GenerateTxFromTxOutListRequest::new();
         request.set_account_key((&sender).into());
         request.set_input_list(RepeatedField::from_vec(
-            tx_utxos.iter().map(api::UnspentTxOut::from).collect(),
+            tx_utxos
+                .iter()
+                .map(|utxo| unspent_tx_out_to_proto(utxo, &MemoRegistry::new()))
+                .collect(),
         ));
         let receiver = AccountKey::random(&mut rng);
         request.set_receiver((&receiver.default_subaddress()).into());
@@ -6335,7 +6802,10 @@ mod test {
         request.set_sender_monitor_id(monitor_id.to_vec());
         request.set_change_subaddress(0);
         request.set_input_list(RepeatedField::from_vec(
-            utxos.iter().map(api::UnspentTxOut::from).collect(),
+            utxos
+                .iter()
+                .map(|utxo| unspent_tx_out_to_proto(utxo, &MemoRegistry::new()))
+                .collect(),
         ));
         request.set_outlay_list(RepeatedField::from_vec(
             outlays.iter().map(api::Outlay::from).collect(),
@@ -6350,7 +6820,7 @@ mod test {
         // Test the happy flow.
         {
             let mut request = api::SubmitTxRequest::new();
-            request.set_tx_proposal(api::TxProposal::from(&tx_proposal));
+            request.set_tx_proposal(tx_proposal_to_proto(&tx_proposal, &MemoRegistry::new()));
 
             let response = client.submit_tx(&request).unwrap();
 
@@ -6462,6 +6932,73 @@ mod test {
         }
     }
 
+    #[test_with_logger]
+    fn test_get_fee_report(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (mut ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger, &mut rng);
+
+        // Fees are paid directly to the fee recipient's keys.
+        let fee_view_private_key = RistrettoPrivate::from_random(&mut rng);
+        let fee_spend_public_key = RistrettoPublic::from_random(&mut rng);
+        let fee_recipient = PublicAddress::new(
+            &fee_spend_public_key,
+            &RistrettoPublic::from(&fee_view_private_key),
+        );
+        let fee_outputs = [(400, Mob::ID), (1024, TokenId::from(2)), (600, Mob::ID)]
+            .into_iter()
+            .map(|(value, token_id)| {
+                TxOut::new(
+                    BLOCK_VERSION,
+                    Amount::new(value, token_id),
+                    &fee_recipient,
+                    &RistrettoPrivate::from_random(&mut rng),
+                    Default::default(),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let first_fee_block = ledger_db.num_blocks().unwrap();
+        add_txos_to_ledger(&mut ledger_db, BLOCK_VERSION, &fee_outputs[..2], &mut rng).unwrap();
+        add_txos_to_ledger(&mut ledger_db, BLOCK_VERSION, &fee_outputs[2..], &mut rng).unwrap();
+
+        let request = api::GetFeeReportRequest {
+            fee_view_private_key: Some((&fee_view_private_key).into()).into(),
+            fee_spend_public_key: Some((&fee_spend_public_key).into()).into(),
+            start_block: 0,
+            end_block: 0,
+            blocks_per_period: first_fee_block + 1,
+            ..Default::default()
+        };
+        let response = client.get_fee_report(&request).unwrap();
+        assert_eq!(response.periods.len(), 2);
+
+        let first = &response.periods[0];
+        assert_eq!(first.start_block, 0);
+        assert_eq!(first.end_block, first_fee_block + 1);
+        assert_eq!(first.num_fee_outputs, 2);
+        assert_eq!(first.totals.len(), 2);
+        assert_eq!(first.totals[0].token_id, *Mob::ID);
+        assert_eq!(first.totals[0].total, 400);
+        assert_eq!(first.totals[1].token_id, 2);
+        assert_eq!(first.totals[1].total, 1024);
+
+        let second = &response.periods[1];
+        assert_eq!(second.start_block, first_fee_block + 1);
+        assert_eq!(second.end_block, first_fee_block + 2);
+        assert_eq!(second.num_fee_outputs, 1);
+        assert_eq!(second.totals[0].total, 600);
+
+        // A range past the end of the ledger is rejected.
+        let request = api::GetFeeReportRequest {
+            end_block: first_fee_block + 3,
+            ..request
+        };
+        assert!(client.get_fee_report(&request).is_err());
+    }
+
     #[test_with_logger]
     fn test_get_balance_impl(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
@@ -6705,6 +7242,136 @@ mod test {
         assert_eq!(matched_utxos, tx_proposal.utxos.len());
     }
 
+    #[test_with_logger]
+    fn test_send_batch_payment(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let sender = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            sender.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[sender.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+
+        // Insert into database.
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        // Three recipients, each paid with a different kind of payment id.
+        let receivers: Vec<_> = (0..3).map(|_| AccountKey::random(&mut rng)).collect();
+        let values = [123, 456, 789];
+
+        let mut batch_outlays = Vec::new();
+        for (i, (receiver, value)) in receivers.iter().zip(values).enumerate() {
+            let mut batch_outlay = api::BatchOutlay::new();
+            batch_outlay.set_outlay(api::Outlay::from(&Outlay {
+                value,
+                receiver: receiver.default_subaddress(),
+                tx_private_key: None,
+            }));
+            match i {
+                0 => batch_outlay.set_payment_request_id(1001),
+                1 => batch_outlay.set_payment_intent_id(2002),
+                _ => {}
+            }
+            batch_outlays.push(batch_outlay);
+        }
+
+        // An empty batch is rejected.
+        let mut request = api::SendBatchPaymentRequest::new();
+        request.set_sender_monitor_id(monitor_id.to_vec());
+        request.set_sender_subaddress(0);
+        assert!(client.send_batch_payment(&request).is_err());
+
+        request.set_outlay_list(RepeatedField::from_vec(batch_outlays));
+        let response = client.send_batch_payment(&request).unwrap();
+        let tx = Tx::try_from(response.get_tx_proposal().get_tx()).unwrap();
+
+        // One output per recipient, plus change.
+        assert_eq!(tx.prefix.outputs.len(), receivers.len() + 1);
+        assert_eq!(
+            response.get_receiver_tx_receipt_list().len(),
+            receivers.len()
+        );
+
+        for (i, account_key) in receivers.iter().chain([&sender]).enumerate() {
+            let (tx_out, shared_secret) = tx
+                .prefix
+                .outputs
+                .iter()
+                .find_map(|tx_out| {
+                    let output_public_key = RistrettoPublic::try_from(&tx_out.public_key).unwrap();
+                    let shared_secret = get_tx_out_shared_secret(
+                        account_key.view_private_key(),
+                        &output_public_key,
+                    );
+                    tx_out
+                        .get_masked_amount()
+                        .unwrap()
+                        .get_value(&shared_secret)
+                        .ok()
+                        .map(|_| (tx_out, shared_secret))
+                })
+                .expect("There should be an output belonging to the account key.");
+
+            // Each receiver gets its own payment id, and the sender gets a
+            // DestinationMemo describing the whole batch.
+            let memo = MemoType::try_from(&tx_out.e_memo.as_ref().unwrap().decrypt(&shared_secret))
+                .unwrap();
+            let view_private_key = account_key.default_subaddress_view_private();
+            let sender_address = sender.default_subaddress();
+            match (i, memo) {
+                (0, MemoType::AuthenticatedSenderWithPaymentRequestId(memo)) => {
+                    assert_eq!(memo.payment_request_id(), 1001);
+                    assert!(bool::from(memo.validate(
+                        &sender_address,
+                        &view_private_key,
+                        &tx_out.public_key
+                    )));
+                }
+                (1, MemoType::AuthenticatedSenderWithPaymentIntentId(memo)) => {
+                    assert_eq!(memo.payment_intent_id(), 2002);
+                    assert!(bool::from(memo.validate(
+                        &sender_address,
+                        &view_private_key,
+                        &tx_out.public_key
+                    )));
+                }
+                (2, MemoType::AuthenticatedSender(memo)) => {
+                    assert!(bool::from(memo.validate(
+                        &sender_address,
+                        &view_private_key,
+                        &tx_out.public_key
+                    )));
+                }
+                (3, MemoType::Destination(memo)) => {
+                    assert_eq!(memo.get_num_recipients(), 3);
+                    assert_eq!(
+                        memo.get_total_outlay(),
+                        values.iter().sum::<u64>() + memo.get_fee()
+                    );
+                }
+                (i, memo) => panic!("unexpected memo for output {i}: {memo:?}"),
+            }
+        }
+    }
+
     #[test_with_logger]
     fn test_send_payment_with_max_input_utxo_value(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
@@ -6883,6 +7550,7 @@ mod test {
             mobilecoind_db.clone(),
             None,
             Some(fog_pubkey_resolver_factory),
+            MemoRegistry::new(),
             &uri,
         );
         log::debug!(logger, "Setting up client {:?}", port);
@@ -7655,6 +8323,97 @@ mod test {
         }
     }
 
+    #[test_with_logger]
+    fn test_invoice(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger, &mut rng);
+
+        let receiver = AccountKey::random(&mut rng).default_subaddress();
+        let merchant_key = Ed25519Private::from_random(&mut rng);
+
+        let line_item = |value, token_id| {
+            let mut line_item = mc_api::printable::InvoiceLineItem::new();
+            line_item.set_description(format!("{value} of token {token_id}"));
+            line_item.set_amount((&Amount::new(value, TokenId::from(token_id))).into());
+            line_item
+        };
+
+        let mut request = api::CreateInvoiceRequest::new();
+        request.set_receiver(mc_api::external::PublicAddress::from(&receiver));
+        request.set_line_items(vec![line_item(10, 0), line_item(20, 1), line_item(5, 0)].into());
+        request.set_memo("order 17".to_owned());
+        request.set_payment_intent_id(17);
+        request.set_expiry_block(ledger_db.num_blocks().unwrap() + 10);
+        request.set_callback_url("https://merchant.example/paid".to_owned());
+        request.set_merchant_name("Merchant".to_owned());
+        request.set_merchant_signing_key(merchant_key.as_ref().to_vec());
+
+        let response = client.create_invoice(&request).unwrap();
+        let b58_code = response.get_b58_code().to_string();
+
+        // Parse it back.
+        let mut request = api::ParseInvoiceRequest::new();
+        request.set_b58_code(b58_code.clone());
+        let response = client.parse_invoice(&request).unwrap();
+
+        let invoice = response.get_invoice();
+        assert_eq!(
+            PublicAddress::try_from(invoice.get_public_address()).unwrap(),
+            receiver
+        );
+        assert_eq!(invoice.get_memo(), "order 17");
+        assert_eq!(invoice.get_payment_intent_id(), 17);
+        assert_eq!(invoice.get_merchant_identity().get_name(), "Merchant");
+        assert_eq!(
+            response
+                .get_totals()
+                .iter()
+                .map(|amount| (amount.get_value(), amount.get_token_id()))
+                .collect::<Vec<_>>(),
+            vec![(15, 0), (20, 1)]
+        );
+        assert!(!response.get_is_expired());
+        assert!(response.get_has_merchant_signature());
+
+        // An invoice is also accepted as an address code.
+        let mut request = api::ParseAddressCodeRequest::new();
+        request.set_b58_code(b58_code);
+        let response = client.parse_address_code(&request).unwrap();
+        assert_eq!(
+            PublicAddress::try_from(response.get_receiver()).unwrap(),
+            receiver
+        );
+
+        // An invoice with an expiry block in the past is reported as expired.
+        let mut request = api::CreateInvoiceRequest::new();
+        request.set_receiver(mc_api::external::PublicAddress::from(&receiver));
+        request.set_line_items(vec![line_item(10, 0)].into());
+        request.set_expiry_block(1);
+        let response = client.create_invoice(&request).unwrap();
+
+        let mut request = api::ParseInvoiceRequest::new();
+        request.set_b58_code(response.get_b58_code().to_string());
+        let response = client.parse_invoice(&request).unwrap();
+        assert!(response.get_is_expired());
+        assert!(!response.get_has_merchant_signature());
+
+        // An invoice without line items is rejected.
+        let mut request = api::CreateInvoiceRequest::new();
+        request.set_receiver(mc_api::external::PublicAddress::from(&receiver));
+        assert!(client.create_invoice(&request).is_err());
+
+        // Other codes are not invoices.
+        let mut request = api::CreateAddressCodeRequest::new();
+        request.set_receiver(mc_api::external::PublicAddress::from(&receiver));
+        let response = client.create_address_code(&request).unwrap();
+
+        let mut request = api::ParseInvoiceRequest::new();
+        request.set_b58_code(response.get_b58_code().to_string());
+        assert!(client.parse_invoice(&request).is_err());
+    }
+
     #[test_with_logger]
     fn test_get_network_status(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
@@ -4,6 +4,8 @@
 //! * A lookup table, mapping subaddress_spend_public_key to monitor_id and
 //!   subaddress index. This is used by the ledger sync code, allowing it to
 //!   match TxOuts into specific monitor_ids.
+//! * Monitors with a gap limit get new entries as the sync code discovers used
+//!   subaddresses near the end of their range.
 
 use crate::{
     database_key::DatabaseByteArrayKey,
@@ -15,7 +17,7 @@ use lmdb::{Database, DatabaseFlags, Environment, RwTransaction, Transaction, Wri
 use mc_common::logger::{log, Logger};
 use mc_crypto_keys::RistrettoPublic;
 use prost::Message;
-use std::sync::Arc;
+use std::{ops::Range, sync::Arc};
 
 // LMDB Database Names
 pub const SUBADDRESS_PUBLIC_SPEND_KEY_TO_INDEX_DATA_DB_NAME: &str =
@@ -144,6 +146,36 @@ impl SubaddressStore {
         Ok(())
     }
 
+    /// Insert the subaddress spend public keys for a range of indexes that
+    /// extends a monitor's watched range. Stops at the first index whose key
+    /// already belongs to another monitor, so the watched range stays
+    /// contiguous. Returns the number of subaddresses inserted.
+    pub fn extend(
+        &self,
+        db_txn: &mut RwTransaction<'_>,
+        monitor_id: &MonitorId,
+        data: &MonitorData,
+        indexes: Range<u64>,
+    ) -> Result<u64, Error> {
+        let mut num_inserted = 0;
+        for index in indexes {
+            match self.insert(db_txn, monitor_id, data, index) {
+                Ok(()) => num_inserted += 1,
+                Err(Error::SubaddressSPKIdExists) => {
+                    log::warn!(
+                        self.logger,
+                        "Subaddress {} of monitor {} is watched by another monitor, not extending",
+                        index,
+                        monitor_id,
+                    );
+                    break;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(num_inserted)
+    }
+
     /// Returns the SubaddressId associated with a given spk
     pub fn get_index_data(
         &self,
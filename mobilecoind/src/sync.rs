@@ -42,6 +42,7 @@ use mc_transaction_core::{
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use std::{
     cmp::min,
+    ops::Range,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex,
@@ -371,23 +372,44 @@ fn sync_monitor(
 
     // Now add everything to the database
     for (worker_idx, result) in parallel_results.into_iter().enumerate() {
-        if let Err(err) = result.and_then(|(utxos, key_images)| {
+        let block_idx = monitor_data.next_block + worker_idx as u64;
+        let result = result.and_then(|(utxos, key_images)| {
+            // If this block used a subaddress near the end of the watched range, extend
+            // the range first. The blocks before this one are rescanned for the new
+            // subaddresses right away. This block and the ones after it were matched
+            // against the old range, so they are scanned again on the next pass.
+            if let Some(used_index) = utxos.iter().map(|utxo| utxo.subaddress_index).max() {
+                let indexes = mobilecoind_db.discover_subaddresses(monitor_id, used_index)?;
+                if !indexes.is_empty() {
+                    rescan_discovered_subaddresses(
+                        ledger_db,
+                        mobilecoind_db,
+                        monitor_id,
+                        indexes,
+                        block_idx,
+                        logger,
+                    )?;
+                    return Ok(false);
+                }
+            }
+
             // Update database.
-            mobilecoind_db.block_processed(
-                monitor_id,
-                monitor_data.next_block + worker_idx as u64,
-                &utxos,
-                &key_images,
-            )
-        }) {
-            // Unfortunately, we have to abandon any work that could have been accomplished
-            // successfully by the threads after this one. To track this,
-            // we'll log a warning about work being abandoned.
-            let abandoned_successes: usize = worker_successes.iter().skip(worker_idx + 1).sum();
-            if abandoned_successes > 0 {
-                log::warn!(logger, "Due to an error while parallel scanning, had to abandon {} successful block scanning results", abandoned_successes);
+            mobilecoind_db.block_processed(monitor_id, block_idx, &utxos, &key_images)?;
+            Ok(true)
+        });
+        match result {
+            Ok(true) => {}
+            Ok(false) => return Ok(SyncMonitorOk::MoreBlocksPotentiallyAvailable),
+            Err(err) => {
+                // Unfortunately, we have to abandon any work that could have been accomplished
+                // successfully by the threads after this one. To track this,
+                // we'll log a warning about work being abandoned.
+                let abandoned_successes: usize = worker_successes.iter().skip(worker_idx + 1).sum();
+                if abandoned_successes > 0 {
+                    log::warn!(logger, "Due to an error while parallel scanning, had to abandon {} successful block scanning results", abandoned_successes);
+                }
+                return Err(err);
             }
-            return Err(err);
         }
     }
 
@@ -398,6 +420,62 @@ fn sync_monitor(
     })
 }
 
+/// Scan the blocks a monitor has already processed, up to `end_block`, for
+/// TxOuts sent to newly discovered subaddresses. TxOuts found this way can
+/// widen the watched range again, in which case the scan repeats for the
+/// subaddresses discovered next.
+fn rescan_discovered_subaddresses(
+    ledger_db: &LedgerDB,
+    mobilecoind_db: &Database,
+    monitor_id: &MonitorId,
+    mut indexes: Range<u64>,
+    end_block: u64,
+    logger: &Logger,
+) -> Result<(), Error> {
+    while !indexes.is_empty() {
+        let monitor_data = mobilecoind_db.get_monitor_data(monitor_id)?;
+
+        let mut found = Vec::new();
+        for block_idx in monitor_data.first_block..end_block {
+            let block_contents = ledger_db.get_block_contents(block_idx)?;
+            let utxos = match_tx_outs_into_utxos(
+                mobilecoind_db,
+                &block_contents.outputs,
+                monitor_id,
+                &monitor_data,
+                logger,
+            )?;
+            for utxo in utxos {
+                if !indexes.contains(&utxo.subaddress_index) {
+                    continue;
+                }
+                // Spends in end_block or later are handled by the regular sync.
+                let spent_block = ledger_db
+                    .check_key_image(&utxo.key_image)?
+                    .filter(|spent_block| *spent_block < end_block);
+                found.push((block_idx, utxo, spent_block));
+            }
+        }
+
+        log::debug!(
+            logger,
+            "Rescanned blocks {}..{} for subaddresses {:?} of monitor id {}",
+            monitor_data.first_block,
+            end_block,
+            indexes,
+            monitor_id,
+        );
+
+        let Some(used_index) = found.iter().map(|(_, utxo, _)| utxo.subaddress_index).max() else {
+            break;
+        };
+        mobilecoind_db.rescanned_utxos_found(monitor_id, &found)?;
+        indexes = mobilecoind_db.discover_subaddresses(monitor_id, used_index)?;
+    }
+
+    Ok(())
+}
+
 /// Helper function for matching a list of TxOuts to a given monitor.
 fn match_tx_outs_into_utxos(
     mobilecoind_db: &Database,
@@ -486,16 +564,19 @@ fn match_tx_outs_into_utxos(
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::test_utils::{
-        self, add_block_to_ledger, add_txos_to_ledger, get_test_databases, BlockVersion,
-        DEFAULT_PER_RECIPIENT_AMOUNT,
+    use crate::{
+        processed_block_store::ProcessedTxOutDirection,
+        test_utils::{
+            self, add_block_to_ledger, add_txos_to_ledger, get_test_databases, BlockVersion,
+            DEFAULT_PER_RECIPIENT_AMOUNT,
+        },
     };
     use mc_account_keys::{AccountKey, PublicAddress, DEFAULT_SUBADDRESS_INDEX};
     use mc_common::logger::test_with_logger;
     use mc_fog_report_validation_test_utils::MockFogResolver;
     use mc_transaction_builder::{EmptyMemoBuilder, TransactionBuilder, TxOutContext};
     use mc_transaction_core::{tokens::Mob, Amount, Token};
-    use rand::{rngs::StdRng, SeedableRng};
+    use rand::{rngs::StdRng, RngCore, SeedableRng};
     use std::time::Instant;
 
     const TEST_MAX_BLOCKS_PROCESSING_CHUNK_SIZE: usize = 5;
@@ -713,6 +794,172 @@ mod test {
         assert!(!utxos.contains(&first_utxo));
     }
 
+    #[test_with_logger]
+    fn test_sync_monitor_discovers_subaddresses(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([98u8; 32]);
+
+        let account_key = AccountKey::random(&mut rng);
+
+        // Only subaddress 3 is in the initial range. Subaddress 7 is within the gap
+        // limit of 3, and subaddress 12 is within the gap limit of 7.
+        let data = MonitorData::new(
+            account_key.clone(),
+            DEFAULT_SUBADDRESS_INDEX, // first subaddress
+            5,                        // number of subaddresses
+            0,                        // first block
+            "",                       // name
+        )
+        .unwrap()
+        .with_gap_limit(5);
+        let monitor_id = MonitorId::from(&data);
+
+        let used_indexes = [3, 7, 12];
+        let recipients: Vec<PublicAddress> = used_indexes
+            .iter()
+            .map(|index| account_key.subaddress(*index))
+            .collect();
+
+        let num_blocks = TEST_MAX_BLOCKS_PROCESSING_CHUNK_SIZE + 1;
+        let (ledger_db, mobilecoind_db) = get_test_databases(
+            BlockVersion::MAX,
+            0,
+            &recipients,
+            num_blocks,
+            logger.clone(),
+            &mut rng,
+        );
+        assert_eq!(mobilecoind_db.add_monitor(&data).unwrap(), monitor_id);
+
+        while sync_monitor(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            TEST_MAX_BLOCKS_PROCESSING_CHUNK_SIZE,
+            &logger,
+        )
+        .unwrap()
+            == SyncMonitorOk::MoreBlocksPotentiallyAvailable
+        {}
+
+        // The range was extended to keep 5 unused subaddresses after the last used
+        // one, and every block was scanned with the extended range.
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, num_blocks as u64);
+        assert_eq!(monitor_data.subaddress_indexes(), 0..18);
+
+        for index in used_indexes {
+            let utxos = mobilecoind_db
+                .get_utxos_for_subaddress(&monitor_id, index)
+                .unwrap();
+            assert_eq!(utxos.len(), num_blocks);
+        }
+    }
+
+    #[test_with_logger]
+    fn test_sync_monitor_rescans_for_discovered_subaddresses(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([99u8; 32]);
+
+        let account_key = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            account_key.clone(),
+            DEFAULT_SUBADDRESS_INDEX, // first subaddress
+            5,                        // number of subaddresses
+            0,                        // first block
+            "",                       // name
+        )
+        .unwrap()
+        .with_gap_limit(5);
+        let monitor_id = MonitorId::from(&data);
+
+        let (mut ledger_db, mobilecoind_db) =
+            get_test_databases(BlockVersion::MAX, 3, &[], 1, logger.clone(), &mut rng);
+        let amount = Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID);
+
+        // Blocks 1 and 2 only pay subaddress 7, which is watched once subaddress 3 is
+        // used. Block 3 only pays subaddress 12, which is watched once subaddress 7 is
+        // found, and spends the TxOut from block 1. Block 4 pays subaddress 3.
+        let block_data = add_block_to_ledger(
+            &mut ledger_db,
+            BlockVersion::MAX,
+            &[account_key.subaddress(7)],
+            amount,
+            &[KeyImage::from(rng.next_u64())],
+            &mut rng,
+        )
+        .unwrap();
+        let spent_tx_out = &block_data.contents().outputs[0];
+        let spent_key_image = KeyImage::from(&recover_onetime_private_key(
+            &RistrettoPublic::try_from(&spent_tx_out.public_key).unwrap(),
+            account_key.view_private_key(),
+            &account_key.subaddress_spend_private(7),
+        ));
+
+        for (index, key_image) in [
+            (7, KeyImage::from(rng.next_u64())),
+            (12, spent_key_image),
+            (3, KeyImage::from(rng.next_u64())),
+        ] {
+            add_block_to_ledger(
+                &mut ledger_db,
+                BlockVersion::MAX,
+                &[account_key.subaddress(index)],
+                amount,
+                &[key_image],
+                &mut rng,
+            )
+            .unwrap();
+        }
+
+        assert_eq!(mobilecoind_db.add_monitor(&data).unwrap(), monitor_id);
+        while sync_monitor(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            TEST_MAX_BLOCKS_PROCESSING_CHUNK_SIZE,
+            &logger,
+        )
+        .unwrap()
+            == SyncMonitorOk::MoreBlocksPotentiallyAvailable
+        {}
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, 5);
+        assert_eq!(monitor_data.subaddress_indexes(), 0..18);
+
+        // The TxOuts in the earlier blocks were found, and the spent one was removed.
+        for index in [3, 7, 12] {
+            let utxos = mobilecoind_db
+                .get_utxos_for_subaddress(&monitor_id, index)
+                .unwrap();
+            assert_eq!(utxos.len(), 1);
+        }
+        assert_ne!(
+            mobilecoind_db
+                .get_utxos_for_subaddress(&monitor_id, 7)
+                .unwrap()[0]
+                .key_image,
+            spent_key_image
+        );
+
+        // The processed blocks record where the TxOut was received and spent.
+        let directions = |block_num| {
+            let mut directions: Vec<_> = mobilecoind_db
+                .get_processed_block(&monitor_id, block_num)
+                .unwrap()
+                .into_iter()
+                .map(|tx_out| (tx_out.subaddress_index, tx_out.direction))
+                .collect();
+            directions.sort();
+            directions
+        };
+        let received = ProcessedTxOutDirection::Received as i32;
+        let spent = ProcessedTxOutDirection::Spent as i32;
+        assert_eq!(directions(1), vec![(7, received)]);
+        assert_eq!(directions(2), vec![(7, received)]);
+        assert_eq!(directions(3), vec![(7, spent), (12, received)]);
+        assert_eq!(directions(4), vec![(3, received)]);
+    }
+
     // TODO: make this a bench instead of a unit test.
     #[test_with_logger]
     fn test_sync_monitor_with_random_recipients(logger: Logger) {
@@ -121,6 +121,10 @@ impl T3Store {
                 (contents.sender_address_hash(), our_short_address_hash)
             }
 
+            MemoType::AuthenticatedSenderWithText(contents) => {
+                (contents.sender_address_hash(), our_short_address_hash)
+            }
+
             MemoType::Destination(contents) => {
                 (our_short_address_hash, *contents.get_address_hash())
             }
@@ -22,6 +22,7 @@ use mc_ledger_sync::PollingNetworkState;
 use mc_mobilecoind_api::{mobilecoind_api_grpc::MobilecoindApiClient, MobilecoindUri};
 use mc_rand::{CryptoRng, RngCore};
 use mc_transaction_core::{ring_signature::KeyImage, tokens::Mob, Amount, FeeMap, Token, TokenId};
+use mc_transaction_extra::MemoRegistry;
 use mc_util_grpc::ConnectionUriGrpcioChannel;
 use mc_util_uri::{ConnectionUri, FogUri};
 use mc_watcher::watcher_db::WatcherDB;
@@ -134,6 +135,7 @@ pub fn setup_server<FPR: FogPubkeyResolver + Default + Send + Sync + 'static>(
     mobilecoind_db: Database,
     watcher_db: Option<WatcherDB>,
     fog_resolver_factory: Option<Arc<dyn Fn(&[FogUri]) -> Result<FPR, String> + Send + Sync>>,
+    memo_registry: MemoRegistry,
     uri: &MobilecoindUri,
 ) -> (
     Service,
@@ -181,6 +183,7 @@ pub fn setup_server<FPR: FogPubkeyResolver + Default + Send + Sync + 'static>(
         uri,
         None,
         "unit-test".into(),
+        Arc::new(memo_registry),
         logger,
     );
 
@@ -244,6 +247,7 @@ pub fn get_testing_environment(
         mobilecoind_db.clone(),
         None,
         None,
+        MemoRegistry::new(),
         &uri,
     );
     log::debug!(logger, "Setting up client {:?}", port);
@@ -1,14 +1,18 @@
 // Copyright (c) 2018-2024 The MobileCoin Foundation
 
-use mc_account_keys::AccountKey;
+use crate::{error::Error, payments::Outlay};
+use mc_account_keys::{AccountKey, PublicAddress};
 use mc_api::ConversionError;
 use mc_mobilecoind_api::{
     mobilecoind_api, TransactionMemo_RTH_oneof_payment_id, TransactionMemo_oneof_transaction_memo,
 };
 use mc_transaction_builder::{
-    BurnRedemptionMemoBuilder, EmptyMemoBuilder, MemoBuilder, RTHMemoBuilder,
+    BatchPaymentMemoBuilder, BurnRedemptionMemoBuilder, EmptyMemoBuilder, MemoBuilder, PaymentId,
+    RTHMemoBuilder, TextMemoBuilder,
+};
+use mc_transaction_extra::{
+    AuthenticatedSenderWithTextMemo, BurnRedemptionMemo, SenderMemoCredential, TextMemoError,
 };
-use mc_transaction_extra::{BurnRedemptionMemo, SenderMemoCredential};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[allow(clippy::upper_case_acronyms)]
@@ -44,9 +48,89 @@ pub enum TransactionMemo {
 
     /// Burn Redemption memo.
     BurnRedemption([u8; BurnRedemptionMemo::MEMO_DATA_LEN]),
+
+    /// Text message memo, authenticated like the RTH memo.
+    Text(TextMemo),
+
+    /// Batch payment memos: an authenticated sender memo with its own
+    /// payment id on each output, in the order of the outlays.
+    BatchPayment {
+        /// Optional subaddress index to generate the sender memo credential
+        /// from.
+        subaddress_index: Option<u64>,
+
+        /// The recipient and payment id of each outlay.
+        payments: Vec<(PublicAddress, Option<PaymentId>)>,
+    },
+}
+
+/// A text message to send in memos, checked to fit in the memos of a
+/// transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextMemo {
+    /// Optional subaddress index to generate the sender memo credential from.
+    subaddress_index: Option<u64>,
+
+    /// The message.
+    text: String,
+
+    /// The number of outputs needed to carry the message.
+    num_chunks: usize,
+}
+
+impl TextMemo {
+    /// Create a text memo, checking that the message fits in the memos of a
+    /// transaction.
+    pub fn new(subaddress_index: Option<u64>, text: String) -> Result<Self, TextMemoError> {
+        let num_chunks = AuthenticatedSenderWithTextMemo::split_text(&text)?.len();
+        Ok(Self {
+            subaddress_index,
+            text,
+            num_chunks,
+        })
+    }
+
+    /// The subaddress index the sender memo credential is generated from.
+    pub fn subaddress_index(&self) -> Option<u64> {
+        self.subaddress_index
+    }
+
+    /// The message.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The number of outputs to the recipient needed to carry the message.
+    pub fn num_chunks(&self) -> usize {
+        self.num_chunks
+    }
 }
 
 impl TransactionMemo {
+    /// Check that the memo can be written to the outputs of `outlays`, so that
+    /// requests which cannot be built are rejected before any inputs are
+    /// selected.
+    pub fn check_outlays(&self, outlays: &[Outlay]) -> Result<(), Error> {
+        if let Self::Text(text_memo) = self {
+            let num_chunks = text_memo.num_chunks();
+            let chunk_outlays = &outlays[..num_chunks.min(outlays.len())];
+            if chunk_outlays.len() < num_chunks
+                || chunk_outlays
+                    .iter()
+                    .any(|outlay| outlay.receiver != chunk_outlays[0].receiver)
+            {
+                return Err(Error::InvalidArgument(
+                    "memo.text".into(),
+                    format!(
+                        "text needs {num_chunks} outputs, which must be the first outlays and \
+                         go to the same recipient"
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub fn memo_builder(&self, account_key: &AccountKey) -> Box<dyn MemoBuilder + Send + Sync> {
         match self {
             Self::Empty => Box::<EmptyMemoBuilder>::default(),
@@ -75,6 +159,28 @@ impl TransactionMemo {
                 memo_builder.enable_destination_memo();
                 Box::new(memo_builder)
             }
+            Self::Text(text_memo) => {
+                let mut memo_builder = TextMemoBuilder::new(
+                    generate_sender_memo_credential(&text_memo.subaddress_index, account_key),
+                    &text_memo.text,
+                )
+                .expect("text is checked by TextMemo::new");
+                memo_builder.enable_destination_memo();
+                Box::new(memo_builder)
+            }
+            Self::BatchPayment {
+                subaddress_index,
+                payments,
+            } => {
+                let mut memo_builder = BatchPaymentMemoBuilder::new(
+                    generate_sender_memo_credential(subaddress_index, account_key),
+                );
+                for (recipient, payment_id) in payments {
+                    memo_builder.add_payment(recipient, *payment_id);
+                }
+                memo_builder.enable_destination_memo();
+                Box::new(memo_builder)
+            }
         }
     }
 }
@@ -84,17 +190,26 @@ fn generate_rth_memo_builder(
     account_key: &AccountKey,
 ) -> RTHMemoBuilder {
     let mut memo_builder = RTHMemoBuilder::default();
-    let sender_memo_credential = match subaddress_index {
+    memo_builder.set_sender_credential(generate_sender_memo_credential(
+        subaddress_index,
+        account_key,
+    ));
+    memo_builder.enable_destination_memo();
+
+    memo_builder
+}
+
+fn generate_sender_memo_credential(
+    subaddress_index: &Option<u64>,
+    account_key: &AccountKey,
+) -> SenderMemoCredential {
+    match subaddress_index {
         Some(subaddress_index) => SenderMemoCredential::new_from_address_and_spend_private_key(
             &account_key.subaddress(*subaddress_index),
             account_key.subaddress_spend_private(*subaddress_index),
         ),
         None => SenderMemoCredential::from(account_key),
-    };
-    memo_builder.set_sender_credential(sender_memo_credential);
-    memo_builder.enable_destination_memo();
-
-    memo_builder
+    }
 }
 
 impl TryFrom<&mobilecoind_api::TransactionMemo> for TransactionMemo {
@@ -142,6 +257,17 @@ impl TryFrom<&mobilecoind_api::TransactionMemo> for TransactionMemo {
                 memo.copy_from_slice(&burn_redemption.memo_data);
                 Ok(TransactionMemo::BurnRedemption(memo))
             }
+
+            Some(TransactionMemo_oneof_transaction_memo::text(text)) => {
+                let subaddress_index = if text.has_subaddress_index() {
+                    Some(text.get_subaddress_index())
+                } else {
+                    None
+                };
+                let text_memo = TextMemo::new(subaddress_index, text.text.clone())
+                    .map_err(|_| ConversionError::InvalidContents)?;
+                Ok(TransactionMemo::Text(text_memo))
+            }
         }
     }
 }
@@ -149,6 +275,7 @@ impl TryFrom<&mobilecoind_api::TransactionMemo> for TransactionMemo {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
 
     #[test]
     fn transaction_memo_try_from_nothing() {
@@ -333,4 +460,72 @@ mod tests {
             super::TransactionMemo::BurnRedemption([1; BurnRedemptionMemo::MEMO_DATA_LEN])
         );
     }
+
+    #[test]
+    fn transaction_memo_try_from_text() {
+        let mut text = mc_mobilecoind_api::TransactionMemo_Text::default();
+        text.set_text("see you at the game".to_owned());
+        let src = mobilecoind_api::TransactionMemo {
+            transaction_memo: Some(TransactionMemo_oneof_transaction_memo::text(text.clone())),
+            ..Default::default()
+        };
+        let result = super::TransactionMemo::try_from(&src).unwrap();
+        assert_eq!(
+            result,
+            super::TransactionMemo::Text(
+                TextMemo::new(None, "see you at the game".to_owned()).unwrap()
+            )
+        );
+
+        text.set_subaddress_index(3);
+        text.set_text("a".repeat(AuthenticatedSenderWithTextMemo::MAX_TEXT_LEN + 1));
+        let src = mobilecoind_api::TransactionMemo {
+            transaction_memo: Some(TransactionMemo_oneof_transaction_memo::text(text)),
+            ..Default::default()
+        };
+        let result = super::TransactionMemo::try_from(&src).unwrap_err();
+        assert_eq!(result, ConversionError::InvalidContents);
+    }
+
+    #[test]
+    fn text_memo_rejects_text_needing_too_many_chunks() {
+        // Multi-byte characters are not split across chunks, so this needs more
+        // than MAX_CHUNKS chunks even though it is not longer than MAX_TEXT_LEN.
+        let text = "\u{20ac}".repeat(AuthenticatedSenderWithTextMemo::MAX_TEXT_LEN / 3);
+        assert!(text.len() <= AuthenticatedSenderWithTextMemo::MAX_TEXT_LEN);
+        assert!(TextMemo::new(None, text).is_err());
+
+        let text_memo = TextMemo::new(Some(2), "a".repeat(30)).unwrap();
+        assert_eq!(text_memo.num_chunks(), 2);
+        assert_eq!(text_memo.subaddress_index(), Some(2));
+    }
+
+    #[test]
+    fn check_outlays_for_text_memo() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let outlay = |receiver: &PublicAddress| Outlay {
+            value: 10,
+            receiver: receiver.clone(),
+            tx_private_key: None,
+        };
+        let alice = outlay(&AccountKey::random(&mut rng).default_subaddress());
+        let bob = outlay(&AccountKey::random(&mut rng).default_subaddress());
+
+        let short = TransactionMemo::Text(TextMemo::new(None, "hi".to_owned()).unwrap());
+        let long = TransactionMemo::Text(TextMemo::new(None, "a".repeat(30)).unwrap());
+
+        short.check_outlays(&[alice.clone()]).unwrap();
+        short.check_outlays(&[alice.clone(), bob.clone()]).unwrap();
+        long.check_outlays(&[alice.clone(), alice.clone(), bob.clone()])
+            .unwrap();
+
+        // A single outlay cannot carry a two-chunk message.
+        assert!(long.check_outlays(&[alice.clone()]).is_err());
+        // The chunks must all go to the same recipient.
+        assert!(long.check_outlays(&[alice.clone(), bob, alice]).is_err());
+        assert!(short.check_outlays(&[]).is_err());
+
+        // Other memos accept any outlays.
+        TransactionMemo::Empty.check_outlays(&[]).unwrap();
+    }
 }
@@ -0,0 +1,33 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Order book gRPC API.
+
+// workaround for #![allow(box_pointers)] in protobuf generated files.
+#![allow(renamed_and_removed_lints)]
+
+use mc_util_uri::{Uri, UriScheme};
+
+mod autogenerated_code {
+    // Expose proto data types from included third-party/external proto files.
+    pub use mc_api::external;
+
+    // Include the auto-generated code.
+    include!(concat!(env!("OUT_DIR"), "/protos-auto-gen/mod.rs"));
+}
+
+pub use autogenerated_code::{order_book::*, *};
+
+pub type OrderBookUri = Uri<OrderBookScheme>;
+
+/// Order book Uri Scheme
+#[derive(Debug, Hash, Ord, PartialOrd, Eq, PartialEq, Clone)]
+pub struct OrderBookScheme {}
+impl UriScheme for OrderBookScheme {
+    /// The part before the '://' of a URL.
+    const SCHEME_SECURE: &'static str = "order-book";
+    const SCHEME_INSECURE: &'static str = "insecure-order-book";
+
+    /// Default port numbers
+    const DEFAULT_SECURE_PORT: u16 = 7443;
+    const DEFAULT_INSECURE_PORT: u16 = 7444;
+}
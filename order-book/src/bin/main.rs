@@ -0,0 +1,58 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Main Method for the Order Book Server
+
+use mc_common::{logger, sentry};
+use mc_ledger_db::LedgerDB;
+use mc_order_book::{Config, InMemoryOrderBook, LmdbOrderBook, Server};
+use mc_util_cli::ParserWithBuildInfo;
+use mc_util_grpc::AdminServer;
+use std::sync::Arc;
+
+fn main() {
+    let (logger, _global_logger_guard) = logger::create_app_logger(logger::o!());
+
+    mc_common::setup_panic_handler();
+    let _sentry_guard = sentry::init();
+
+    let config = Config::parse();
+
+    let ledger = LedgerDB::open(&config.ledger_db).expect("Could not open ledger db");
+
+    let mut server = match config.order_book_db.as_ref() {
+        Some(path) => Server::new(
+            LmdbOrderBook::new(path).expect("Could not open order book db"),
+            ledger,
+            &config.client_listen_uri,
+            config.prune_interval,
+            logger.clone(),
+        ),
+        None => Server::new(
+            InMemoryOrderBook::new(),
+            ledger,
+            &config.client_listen_uri,
+            config.prune_interval,
+            logger.clone(),
+        ),
+    };
+    server.start();
+
+    let config_json = serde_json::to_string(&config).expect("failed to serialize config to JSON");
+    let get_config_json = Arc::new(move || Ok(config_json.clone()));
+    let _admin_server = config.admin_listen_uri.as_ref().map(|admin_listen_uri| {
+        AdminServer::start(
+            None,
+            admin_listen_uri,
+            "Order Book".to_owned(),
+            config.client_listen_uri.to_string(),
+            Some(get_config_json),
+            vec![],
+            logger,
+        )
+        .expect("Failed starting order-book admin server")
+    });
+
+    loop {
+        std::thread::sleep(std::time::Duration::from_millis(1000));
+    }
+}
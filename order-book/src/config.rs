@@ -0,0 +1,40 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Configuration parameters for the order book server
+
+use clap::Parser;
+use mc_order_book_api::OrderBookUri;
+use mc_util_parse::parse_duration_in_seconds;
+use mc_util_uri::AdminUri;
+use serde::Serialize;
+use std::{path::PathBuf, time::Duration};
+
+/// Configuration options for the order book server
+#[derive(Clone, Debug, Parser, Serialize)]
+#[clap(
+    name = "order-book",
+    about = "Order book of signed contingent inputs.",
+    version
+)]
+pub struct Config {
+    /// Path to the ledger database, which quotes are checked against.
+    #[clap(long, env = "MC_LEDGER_DB")]
+    pub ledger_db: PathBuf,
+
+    /// Path to the order book database. Quotes are only kept in memory if
+    /// this is not set.
+    #[clap(long, env = "MC_ORDER_BOOK_DB")]
+    pub order_book_db: Option<PathBuf>,
+
+    /// gRPC listening URI for client requests.
+    #[clap(long, env = "MC_CLIENT_LISTEN_URI")]
+    pub client_listen_uri: OrderBookUri,
+
+    /// Internal admin server used for metrics/debugging.
+    #[clap(long, env = "MC_ADMIN_LISTEN_URI")]
+    pub admin_listen_uri: Option<AdminUri>,
+
+    /// How often to remove spent and expired quotes, in seconds.
+    #[clap(long, default_value = "10", value_parser = parse_duration_in_seconds, env = "MC_PRUNE_INTERVAL")]
+    pub prune_interval: Duration,
+}
@@ -0,0 +1,50 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Conversions between order book types and their gRPC API counterparts.
+
+use crate::{Fill, Pair, Quote};
+use mc_api::external;
+use mc_order_book_api as api;
+
+impl From<&Pair> for api::Pair {
+    fn from(src: &Pair) -> Self {
+        let mut pair = api::Pair::new();
+        pair.set_base_token_id(*src.base_token_id);
+        pair.set_counter_token_id(*src.counter_token_id);
+        pair
+    }
+}
+
+impl From<&api::Pair> for Pair {
+    fn from(src: &api::Pair) -> Self {
+        Self {
+            base_token_id: src.get_base_token_id().into(),
+            counter_token_id: src.get_counter_token_id().into(),
+        }
+    }
+}
+
+impl From<&Quote> for api::Quote {
+    fn from(src: &Quote) -> Self {
+        let mut quote = api::Quote::new();
+        quote.set_id(src.id().0.to_vec());
+        quote.set_sci(external::SignedContingentInput::from(src.sci()));
+        quote.set_pair(src.pair().into());
+        quote.set_min_base_tokens(*src.base_range().start());
+        quote.set_max_base_tokens(*src.base_range().end());
+        quote.set_max_counter_tokens(src.max_counter_tokens());
+        quote.set_timestamp(src.timestamp());
+        quote
+    }
+}
+
+impl From<&Fill> for api::Fill {
+    fn from(src: &Fill) -> Self {
+        let mut fill = api::Fill::new();
+        fill.set_quote((&src.quote).into());
+        fill.set_base_tokens(src.base_tokens);
+        fill.set_partial_fill_value(src.partial_fill_value);
+        fill.set_counter_tokens(src.counter_tokens);
+        fill
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Errors generated by the order book
+
+use crate::QuoteId;
+use displaydoc::Display;
+use lmdb::Error as LmdbError;
+use mc_ledger_db::Error as LedgerDbError;
+use mc_transaction_builder::{SettlementError, TxBuilderError};
+use mc_transaction_extra::SignedContingentInputError;
+use mc_util_serial::DecodeError;
+
+#[derive(Debug, Display)]
+pub enum Error {
+    /// Invalid signed contingent input: {0}
+    Sci(SignedContingentInputError),
+
+    /// Unsupported signed contingent input: {0}
+    UnsupportedSci(String),
+
+    /// Quote {0} is already in the order book
+    QuoteAlreadyExists(QuoteId),
+
+    /// Quote {0} was not found
+    QuoteNotFound(QuoteId),
+
+    /// Invalid fill of {0} base tokens, the quote allows between {1} and {2}
+    InvalidFillAmount(u64, u64, u64),
+
+    /// The key image of the signed contingent input is already in the ledger
+    KeyImageSpent,
+
+    /// The signed contingent input expired at block {0}
+    Expired(u64),
+
+    /// Not enough quotes to fill the order: wanted {0} base tokens, found {1}
+    InsufficientLiquidity(u64, u64),
+
+    /// Failure with LedgerDB: {0}
+    LedgerDB(LedgerDbError),
+
+    /// Failure with LMDB: {0}
+    Lmdb(LmdbError),
+
+    /// IO error: {0}
+    IO(std::io::Error),
+
+    /// Failure with deserialization: {0}
+    Deserialization(DecodeError),
+
+    /// Transaction builder: {0}
+    TxBuilder(TxBuilderError),
+
+    /// Settlement: {0}
+    Settlement(SettlementError),
+}
+
+impl From<SignedContingentInputError> for Error {
+    fn from(src: SignedContingentInputError) -> Self {
+        Self::Sci(src)
+    }
+}
+
+impl From<LedgerDbError> for Error {
+    fn from(src: LedgerDbError) -> Self {
+        Self::LedgerDB(src)
+    }
+}
+
+impl From<LmdbError> for Error {
+    fn from(src: LmdbError) -> Self {
+        Self::Lmdb(src)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(src: std::io::Error) -> Self {
+        Self::IO(src)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(src: DecodeError) -> Self {
+        Self::Deserialization(src)
+    }
+}
+
+impl From<TxBuilderError> for Error {
+    fn from(src: TxBuilderError) -> Self {
+        Self::TxBuilder(src)
+    }
+}
+
+impl From<SettlementError> for Error {
+    fn from(src: SettlementError) -> Self {
+        Self::Settlement(src)
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! An order book which keeps its quotes in memory.
+
+use crate::{current_timestamp, Error, OrderBook, Pair, Quote, QuoteId};
+use mc_transaction_core::ring_signature::KeyImage;
+use mc_transaction_extra::SignedContingentInput;
+use std::{
+    collections::HashMap,
+    ops::{Bound, RangeBounds},
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+#[derive(Default)]
+struct InMemoryOrderBookInner {
+    /// The quotes of each pair, sorted by priority.
+    quotes_by_pair: HashMap<Pair, Vec<Quote>>,
+
+    /// The pair of each quote, for lookups by id.
+    pair_by_id: HashMap<QuoteId, Pair>,
+}
+
+impl InMemoryOrderBookInner {
+    fn add_quote(&mut self, quote: Quote) -> Result<(), Error> {
+        if self.pair_by_id.contains_key(quote.id()) {
+            return Err(Error::QuoteAlreadyExists(*quote.id()));
+        }
+        self.pair_by_id.insert(*quote.id(), *quote.pair());
+
+        let quotes = self.quotes_by_pair.entry(*quote.pair()).or_default();
+        let position = quotes.partition_point(|other| other.cmp_priority(&quote).is_lt());
+        quotes.insert(position, quote);
+        Ok(())
+    }
+
+    fn remove_quotes(&mut self, mut predicate: impl FnMut(&Quote) -> bool) -> Vec<Quote> {
+        let mut removed = Vec::new();
+        for quotes in self.quotes_by_pair.values_mut() {
+            let (matching, rest): (Vec<Quote>, Vec<Quote>) =
+                quotes.drain(..).partition(|quote| predicate(quote));
+            *quotes = rest;
+            removed.extend(matching);
+        }
+        self.quotes_by_pair.retain(|_, quotes| !quotes.is_empty());
+        for quote in removed.iter() {
+            self.pair_by_id.remove(quote.id());
+        }
+        removed
+    }
+}
+
+/// An order book which keeps its quotes in memory. Cloning it gives another
+/// handle to the same order book.
+#[derive(Clone, Default)]
+pub struct InMemoryOrderBook {
+    inner: Arc<RwLock<InMemoryOrderBookInner>>,
+}
+
+impl InMemoryOrderBook {
+    /// Create an empty order book.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a quote which was already created, e.g. when loading quotes from
+    /// storage.
+    pub fn add_quote(&self, quote: Quote) -> Result<(), Error> {
+        self.write().add_quote(quote)
+    }
+
+    fn read(&self) -> RwLockReadGuard<InMemoryOrderBookInner> {
+        self.inner.read().expect("order book lock poisoned")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<InMemoryOrderBookInner> {
+        self.inner.write().expect("order book lock poisoned")
+    }
+}
+
+impl OrderBook for InMemoryOrderBook {
+    fn add_sci(&self, sci: SignedContingentInput, timestamp: Option<u64>) -> Result<Quote, Error> {
+        let quote = Quote::new(sci, timestamp.unwrap_or_else(current_timestamp))?;
+        self.add_quote(quote.clone())?;
+        Ok(quote)
+    }
+
+    fn remove_quote_by_id(&self, id: &QuoteId) -> Result<Quote, Error> {
+        self.write()
+            .remove_quotes(|quote| quote.id() == id)
+            .pop()
+            .ok_or(Error::QuoteNotFound(*id))
+    }
+
+    fn remove_quotes_by_key_image(&self, key_image: &KeyImage) -> Result<Vec<Quote>, Error> {
+        Ok(self
+            .write()
+            .remove_quotes(|quote| &quote.key_image() == key_image))
+    }
+
+    fn remove_quotes_by_tombstone_block(&self, block_index: u64) -> Result<Vec<Quote>, Error> {
+        Ok(self.write().remove_quotes(|quote| {
+            let max_tombstone_block = quote.max_tombstone_block();
+            max_tombstone_block != 0 && max_tombstone_block <= block_index
+        }))
+    }
+
+    fn get_quotes(
+        &self,
+        pair: &Pair,
+        base_token_quantity: impl RangeBounds<u64>,
+        limit: usize,
+    ) -> Result<Vec<Quote>, Error> {
+        let inner = self.read();
+        let Some(quotes) = inner.quotes_by_pair.get(pair) else {
+            return Ok(Vec::new());
+        };
+        let limit = if limit == 0 { usize::MAX } else { limit };
+        Ok(quotes
+            .iter()
+            .filter(|quote| ranges_overlap(quote, &base_token_quantity))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    fn get_quote_by_id(&self, id: &QuoteId) -> Result<Option<Quote>, Error> {
+        let inner = self.read();
+        Ok(inner.pair_by_id.get(id).and_then(|pair| {
+            inner.quotes_by_pair[pair]
+                .iter()
+                .find(|quote| quote.id() == id)
+                .cloned()
+        }))
+    }
+
+    fn get_all_quotes(&self) -> Result<Vec<Quote>, Error> {
+        Ok(self
+            .read()
+            .quotes_by_pair
+            .values()
+            .flatten()
+            .cloned()
+            .collect())
+    }
+}
+
+/// Whether the base range of a quote overlaps a range of base token
+/// quantities.
+fn ranges_overlap(quote: &Quote, range: &impl RangeBounds<u64>) -> bool {
+    let base_range = quote.base_range();
+    let above_start = match range.start_bound() {
+        Bound::Included(start) => base_range.end() >= start,
+        Bound::Excluded(start) => base_range.end() > start,
+        Bound::Unbounded => true,
+    };
+    let below_end = match range.end_bound() {
+        Bound::Included(end) => base_range.start() <= end,
+        Bound::Excluded(end) => base_range.start() < end,
+        Bound::Unbounded => true,
+    };
+    above_start && below_end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_sci, create_test_ledger};
+    use assert_matches::assert_matches;
+    use mc_account_keys::AccountKey;
+    use mc_transaction_core::{Amount, TokenId};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_quotes_are_sorted_by_price_and_filtered() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let mut ledger = create_test_ledger(&mut rng);
+        let originator = AccountKey::random(&mut rng);
+        let order_book = InMemoryOrderBook::new();
+
+        let t1 = TokenId::from(1);
+        let t2 = TokenId::from(2);
+        let pair = Pair {
+            base_token_id: t1,
+            counter_token_id: t2,
+        };
+
+        // Prices of 0.5, 0.25 and 2 counter tokens per base token, and a quote
+        // for the opposite pair.
+        let expensive = create_sci(
+            &mut ledger,
+            &originator,
+            Amount::new(100, t1),
+            Amount::new(200, t2),
+            Some(10),
+            0,
+            &mut rng,
+        );
+        let cheap = create_sci(
+            &mut ledger,
+            &originator,
+            Amount::new(1000, t1),
+            Amount::new(250, t2),
+            None,
+            0,
+            &mut rng,
+        );
+        let medium = create_sci(
+            &mut ledger,
+            &originator,
+            Amount::new(500, t1),
+            Amount::new(250, t2),
+            Some(0),
+            20,
+            &mut rng,
+        );
+        let opposite = create_sci(
+            &mut ledger,
+            &originator,
+            Amount::new(500, t2),
+            Amount::new(250, t1),
+            None,
+            0,
+            &mut rng,
+        );
+
+        let expensive = order_book.add_sci(expensive, Some(1)).unwrap();
+        let cheap = order_book.add_sci(cheap, Some(2)).unwrap();
+        let medium = order_book.add_sci(medium, Some(3)).unwrap();
+        let opposite = order_book.add_sci(opposite, Some(4)).unwrap();
+
+        assert_matches!(
+            order_book.add_sci(cheap.sci().clone(), None),
+            Err(Error::QuoteAlreadyExists(_))
+        );
+
+        assert_eq!(
+            order_book.get_quotes(&pair, .., 0).unwrap(),
+            vec![cheap.clone(), medium.clone(), expensive.clone()]
+        );
+        assert_eq!(
+            order_book.get_quotes(&pair, .., 2).unwrap(),
+            vec![cheap.clone(), medium.clone()]
+        );
+        // The quote without partial fill only offers all 1000 base tokens.
+        assert_eq!(
+            order_book.get_quotes(&pair, 5..=200, 0).unwrap(),
+            vec![medium.clone(), expensive.clone()]
+        );
+        assert_eq!(
+            order_book.get_quote_by_id(opposite.id()).unwrap(),
+            Some(opposite.clone())
+        );
+
+        // Remove by tombstone block, by id, and by key image
+        assert_eq!(
+            order_book.remove_quotes_by_tombstone_block(20).unwrap(),
+            vec![medium.clone()]
+        );
+        assert_eq!(order_book.remove_quote_by_id(cheap.id()).unwrap(), cheap);
+        assert_matches!(
+            order_book.remove_quote_by_id(cheap.id()),
+            Err(Error::QuoteNotFound(_))
+        );
+        assert_eq!(
+            order_book
+                .remove_quotes_by_key_image(&expensive.key_image())
+                .unwrap(),
+            vec![expensive]
+        );
+        assert_eq!(order_book.get_all_quotes().unwrap(), vec![opposite]);
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! An order book of signed contingent inputs (MCIP #31), indexed by token
+//! pair and price, and a matching engine which fills orders from it.
+
+#![deny(missing_docs)]
+
+mod config;
+mod convert;
+mod error;
+mod in_memory;
+mod lmdb_order_book;
+mod matcher;
+mod order_book;
+mod quote;
+mod server;
+mod service;
+mod validity;
+
+#[cfg(test)]
+mod test_utils;
+
+pub use crate::{
+    config::Config,
+    error::Error,
+    in_memory::InMemoryOrderBook,
+    lmdb_order_book::LmdbOrderBook,
+    matcher::{match_quotes, Fill, Matcher},
+    order_book::{current_timestamp, OrderBook},
+    quote::{Pair, Quote, QuoteId},
+    server::Server,
+    service::OrderBookService,
+    validity::{check_sci_against_ledger, prune_order_book},
+};
+
+use mc_util_metrics::ServiceMetrics;
+
+lazy_static::lazy_static! {
+    /// Generates service metrics for tracking
+    pub static ref SVC_COUNTERS: ServiceMetrics = ServiceMetrics::new_and_registered("order_book_service");
+}
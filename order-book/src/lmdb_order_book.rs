@@ -0,0 +1,209 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! An order book which persists its quotes in LMDB.
+//!
+//! Quotes are indexed in memory by an `InMemoryOrderBook`, and every change is
+//! also written to LMDB, so that the order book survives restarts.
+
+use crate::{current_timestamp, Error, InMemoryOrderBook, OrderBook, Pair, Quote, QuoteId};
+use lmdb::{Cursor, Database, DatabaseFlags, Environment, Transaction, WriteFlags};
+use mc_transaction_core::ring_signature::KeyImage;
+use mc_transaction_extra::SignedContingentInput;
+use mc_util_serial::Message;
+use std::{fs, ops::RangeBounds, path::Path, sync::Arc};
+
+// LMDB Constants
+const MAX_LMDB_FILE_SIZE: usize = 1 << 36; // 64 GB
+
+// LMDB Database Names
+pub const QUOTE_ID_TO_STORED_QUOTE_DB_NAME: &str = "order_book:quote_id_to_stored_quote";
+
+/// Type used as the stored data in the quote_id_to_stored_quote database.
+#[derive(Clone, Eq, PartialEq, Message)]
+struct StoredQuote {
+    /// The signed contingent input of the quote.
+    #[prost(message, required, tag = "1")]
+    sci: SignedContingentInput,
+
+    /// The time the quote was added to the order book.
+    #[prost(uint64, tag = "2")]
+    timestamp: u64,
+}
+
+/// An order book which persists its quotes in LMDB. Cloning it gives another
+/// handle to the same order book.
+#[derive(Clone)]
+pub struct LmdbOrderBook {
+    /// LMDB Environment.
+    env: Arc<Environment>,
+
+    /// QuoteId -> StoredQuote
+    quote_id_to_stored_quote: Database,
+
+    /// The in-memory index of the quotes.
+    index: InMemoryOrderBook,
+}
+
+impl LmdbOrderBook {
+    /// Open the order book database at the given path, creating it if it
+    /// doesn't exist, and load its quotes.
+    pub fn new(path: &Path) -> Result<Self, Error> {
+        fs::create_dir_all(path)?;
+        let env = Arc::new(
+            Environment::new()
+                .set_max_dbs(1)
+                .set_map_size(MAX_LMDB_FILE_SIZE)
+                .open(path)?,
+        );
+        let quote_id_to_stored_quote = env.create_db(
+            Some(QUOTE_ID_TO_STORED_QUOTE_DB_NAME),
+            DatabaseFlags::empty(),
+        )?;
+
+        let index = InMemoryOrderBook::new();
+        {
+            let db_txn = env.begin_ro_txn()?;
+            let mut cursor = db_txn.open_ro_cursor(quote_id_to_stored_quote)?;
+            for result in cursor.iter_start() {
+                let (_quote_id_bytes, value_bytes) = result?;
+                let stored: StoredQuote = mc_util_serial::decode(value_bytes)?;
+                index.add_quote(Quote::new(stored.sci, stored.timestamp)?)?;
+            }
+        }
+
+        Ok(Self {
+            env,
+            quote_id_to_stored_quote,
+            index,
+        })
+    }
+
+    /// Delete removed quotes from LMDB.
+    fn delete_quotes(&self, quotes: &[Quote]) -> Result<(), Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+        for quote in quotes {
+            match db_txn.del(self.quote_id_to_stored_quote, &quote.id().0, None) {
+                Ok(()) | Err(lmdb::Error::NotFound) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        db_txn.commit()?;
+        Ok(())
+    }
+}
+
+impl OrderBook for LmdbOrderBook {
+    fn add_sci(&self, sci: SignedContingentInput, timestamp: Option<u64>) -> Result<Quote, Error> {
+        let quote = self
+            .index
+            .add_sci(sci, Some(timestamp.unwrap_or_else(current_timestamp)))?;
+
+        let stored = StoredQuote {
+            sci: quote.sci().clone(),
+            timestamp: quote.timestamp(),
+        };
+        let result = self.env.begin_rw_txn().and_then(|mut db_txn| {
+            db_txn.put(
+                self.quote_id_to_stored_quote,
+                &quote.id().0,
+                &mc_util_serial::encode(&stored),
+                WriteFlags::NO_OVERWRITE,
+            )?;
+            db_txn.commit()
+        });
+        if let Err(err) = result {
+            // Keep the index consistent with what was persisted.
+            self.index.remove_quote_by_id(quote.id())?;
+            return Err(err.into());
+        }
+
+        Ok(quote)
+    }
+
+    fn remove_quote_by_id(&self, id: &QuoteId) -> Result<Quote, Error> {
+        let quote = self.index.remove_quote_by_id(id)?;
+        self.delete_quotes(core::slice::from_ref(&quote))?;
+        Ok(quote)
+    }
+
+    fn remove_quotes_by_key_image(&self, key_image: &KeyImage) -> Result<Vec<Quote>, Error> {
+        let quotes = self.index.remove_quotes_by_key_image(key_image)?;
+        self.delete_quotes(&quotes)?;
+        Ok(quotes)
+    }
+
+    fn remove_quotes_by_tombstone_block(&self, block_index: u64) -> Result<Vec<Quote>, Error> {
+        let quotes = self.index.remove_quotes_by_tombstone_block(block_index)?;
+        self.delete_quotes(&quotes)?;
+        Ok(quotes)
+    }
+
+    fn get_quotes(
+        &self,
+        pair: &Pair,
+        base_token_quantity: impl RangeBounds<u64>,
+        limit: usize,
+    ) -> Result<Vec<Quote>, Error> {
+        self.index.get_quotes(pair, base_token_quantity, limit)
+    }
+
+    fn get_quote_by_id(&self, id: &QuoteId) -> Result<Option<Quote>, Error> {
+        self.index.get_quote_by_id(id)
+    }
+
+    fn get_all_quotes(&self) -> Result<Vec<Quote>, Error> {
+        self.index.get_all_quotes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_sci, create_test_ledger};
+    use mc_account_keys::AccountKey;
+    use mc_transaction_core::{Amount, TokenId};
+    use rand::{rngs::StdRng, SeedableRng};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_quotes_survive_reopening() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let mut ledger = create_test_ledger(&mut rng);
+        let originator = AccountKey::random(&mut rng);
+        let temp_dir = TempDir::new().unwrap();
+
+        let pair = Pair {
+            base_token_id: TokenId::from(1),
+            counter_token_id: TokenId::from(2),
+        };
+        let scis = (1..=3)
+            .map(|i| {
+                create_sci(
+                    &mut ledger,
+                    &originator,
+                    Amount::new(1000, pair.base_token_id),
+                    Amount::new(100 * i, pair.counter_token_id),
+                    Some(0),
+                    0,
+                    &mut rng,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let quotes = {
+            let order_book = LmdbOrderBook::new(temp_dir.path()).unwrap();
+            let quotes = scis
+                .into_iter()
+                .map(|sci| order_book.add_sci(sci, None).unwrap())
+                .collect::<Vec<_>>();
+            order_book.remove_quote_by_id(quotes[1].id()).unwrap();
+            quotes
+        };
+
+        let order_book = LmdbOrderBook::new(temp_dir.path()).unwrap();
+        assert_eq!(
+            order_book.get_quotes(&pair, .., 0).unwrap(),
+            vec![quotes[0].clone(), quotes[2].clone()]
+        );
+    }
+}
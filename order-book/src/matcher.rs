@@ -0,0 +1,289 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Matching orders against the quotes in an order book, and adding the
+//! resulting fills to transactions.
+
+use crate::{check_sci_against_ledger, Error, OrderBook, Pair, Quote};
+use mc_fog_report_validation::FogPubkeyResolver;
+use mc_ledger_db::Ledger;
+use mc_transaction_builder::{SciFill, TransactionBuilder};
+use mc_transaction_core::TokenId;
+use std::collections::{BTreeMap, HashSet};
+
+/// A quote, and how much of it to take when filling an order.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Fill {
+    /// The quote.
+    pub quote: Quote,
+
+    /// The quantity of the base token taken from the quote.
+    pub base_tokens: u64,
+
+    /// The partial fill value to use with the signed contingent input.
+    pub partial_fill_value: u64,
+
+    /// The quantity of the counter token paid for the base tokens.
+    pub counter_tokens: u64,
+}
+
+impl Fill {
+    /// Take a quantity of the base token from a quote.
+    pub fn new(quote: Quote, base_tokens: u64) -> Result<Self, Error> {
+        Ok(Self {
+            partial_fill_value: quote.partial_fill_value(base_tokens)?,
+            counter_tokens: quote.counter_tokens(base_tokens)?,
+            quote,
+            base_tokens,
+        })
+    }
+}
+
+/// Fill an order for a quantity of the base token, from quotes sorted best
+/// first.
+///
+/// Each quote is taken as much as possible before moving on to the next one.
+/// Quotes which can't be taken in part are skipped if the rest of the order is
+/// smaller than they are. Quotes spending the same input as a quote already
+/// taken are skipped too, since a transaction can only spend it once.
+pub fn match_quotes(
+    quotes: impl IntoIterator<Item = Quote>,
+    base_tokens: u64,
+) -> Result<Vec<Fill>, Error> {
+    let mut fills = Vec::new();
+    let mut key_images = HashSet::new();
+    let mut remaining = base_tokens;
+    for quote in quotes {
+        if remaining == 0 {
+            break;
+        }
+        let take = remaining.min(*quote.base_range().end());
+        if take == 0 || take < *quote.base_range().start() {
+            continue;
+        }
+        if !key_images.insert(quote.key_image()) {
+            continue;
+        }
+        fills.push(Fill::new(quote, take)?);
+        remaining -= take;
+    }
+    if remaining != 0 {
+        return Err(Error::InsufficientLiquidity(
+            base_tokens,
+            base_tokens - remaining,
+        ));
+    }
+    Ok(fills)
+}
+
+/// Matches orders against an order book, and adds the fills to transactions.
+#[derive(Clone)]
+pub struct Matcher<OB: OrderBook, L: Ledger + Clone> {
+    /// The order book quotes are taken from.
+    order_book: OB,
+
+    /// The ledger quotes are checked against, and which provides the
+    /// membership proofs of their rings.
+    ledger: L,
+}
+
+impl<OB: OrderBook, L: Ledger + Clone> Matcher<OB, L> {
+    /// Create a new matcher.
+    pub fn new(order_book: OB, ledger: L) -> Self {
+        Self { order_book, ledger }
+    }
+
+    /// Find the cheapest fills for an order for a quantity of the base token
+    /// of a pair.
+    ///
+    /// Quotes which can no longer be filled are removed from the order book.
+    pub fn find_fills(&self, pair: &Pair, base_tokens: u64) -> Result<Vec<Fill>, Error> {
+        let mut quotes = Vec::new();
+        for quote in self.order_book.get_quotes(pair, 1.., 0)? {
+            match check_sci_against_ledger(&self.ledger, quote.sci()) {
+                Ok(()) => quotes.push(quote),
+                Err(Error::KeyImageSpent | Error::Expired(_)) => {
+                    match self.order_book.remove_quote_by_id(quote.id()) {
+                        // Someone else may have removed it first.
+                        Ok(_) | Err(Error::QuoteNotFound(_)) => {}
+                        Err(err) => return Err(err),
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        match_quotes(quotes, base_tokens)
+    }
+
+    /// Add the signed contingent inputs of some fills to a transaction, along
+    /// with the outputs required by their input rules.
+    ///
+    /// Returns the balance sheet of the fills. Positive values must be supplied
+    /// by inputs of the taker, and negative values are received by the taker,
+    /// who must add outputs for them.
+    pub fn add_fills_to_tx_builder<FPR: FogPubkeyResolver>(
+        &self,
+        fills: &[Fill],
+        tx_builder: &mut TransactionBuilder<FPR>,
+    ) -> Result<BTreeMap<TokenId, i128>, Error> {
+        let sci_fills = fills
+            .iter()
+            .map(|fill| {
+                let mut sci = fill.quote.sci().clone();
+                sci.tx_in.proofs = self
+                    .ledger
+                    .get_tx_out_proof_of_memberships(&sci.tx_out_global_indices)?;
+                Ok(SciFill {
+                    sci,
+                    partial_fill_value: fill.partial_fill_value,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(tx_builder.add_presigned_inputs_for_settlement(sci_fills)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{create_sci, create_test_ledger, BLOCK_VERSION},
+        InMemoryOrderBook,
+    };
+    use assert_matches::assert_matches;
+    use mc_account_keys::AccountKey;
+    use mc_crypto_ring_signature_signer::NoKeysRingSigner;
+    use mc_fog_report_validation_test_utils::MockFogResolver;
+    use mc_transaction_builder::{
+        test_utils::get_input_credentials, EmptyMemoBuilder, ReservedSubaddresses,
+    };
+    use mc_transaction_core::{validation::validate_all_input_rules, Amount};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_match_and_fill_quotes() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let mut ledger = create_test_ledger(&mut rng);
+        let originator = AccountKey::random(&mut rng);
+        let taker = AccountKey::random(&mut rng);
+        let order_book = InMemoryOrderBook::new();
+
+        let t1 = TokenId::from(1);
+        let t2 = TokenId::from(2);
+        let pair = Pair {
+            base_token_id: t1,
+            counter_token_id: t2,
+        };
+
+        // 1000 at 0.25, which can only be taken whole, 500 at 0.5, and 100 at 2,
+        // of which at least 10 must be taken.
+        for (offered, asked, min_partial_fill_value) in
+            [(1000, 250, None), (500, 250, Some(0)), (100, 200, Some(10))]
+        {
+            let sci = create_sci(
+                &mut ledger,
+                &originator,
+                Amount::new(offered, t1),
+                Amount::new(asked, t2),
+                min_partial_fill_value,
+                0,
+                &mut rng,
+            );
+            order_book.add_sci(sci, None).unwrap();
+        }
+        let matcher = Matcher::new(order_book, ledger.clone());
+
+        let summarize = |fills: Vec<Fill>| {
+            fills
+                .iter()
+                .map(|fill| (fill.base_tokens, fill.counter_tokens))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            summarize(matcher.find_fills(&pair, 300).unwrap()),
+            vec![(300, 150)]
+        );
+        assert_eq!(
+            summarize(matcher.find_fills(&pair, 1510).unwrap()),
+            vec![(1000, 250), (500, 250), (10, 20)]
+        );
+        // The last quote can't be taken for less than 10
+        assert_matches!(
+            matcher.find_fills(&pair, 1505),
+            Err(Error::InsufficientLiquidity(1505, 1500))
+        );
+        assert_matches!(
+            matcher.find_fills(&pair, 1700),
+            Err(Error::InsufficientLiquidity(1700, 1600))
+        );
+
+        // Fill an order for 1200 of token 1
+        let fills = matcher.find_fills(&pair, 1200).unwrap();
+        assert_eq!(summarize(fills.clone()), vec![(1000, 250), (200, 100)]);
+
+        let fog_resolver = MockFogResolver(Default::default());
+        let fee = Amount::new(10, t2);
+        let mut tx_builder =
+            TransactionBuilder::new(BLOCK_VERSION, fee, fog_resolver.clone(), EmptyMemoBuilder)
+                .unwrap();
+        let balance_sheet = matcher
+            .add_fills_to_tx_builder(&fills, &mut tx_builder)
+            .unwrap();
+        assert_eq!(balance_sheet, BTreeMap::from([(t1, -1200), (t2, 350)]));
+
+        // The taker pays with 1000 of token 2
+        tx_builder.add_input(get_input_credentials(
+            BLOCK_VERSION,
+            Amount::new(1000, t2),
+            &taker,
+            &fog_resolver,
+            &mut rng,
+        ));
+        tx_builder
+            .add_output(Amount::new(1200, t1), &taker.default_subaddress(), &mut rng)
+            .unwrap();
+        tx_builder
+            .add_change_output(
+                Amount::new(1000 - 350 - fee.value, t2),
+                &ReservedSubaddresses::from(&taker),
+                &mut rng,
+            )
+            .unwrap();
+        let tx = tx_builder.build(&NoKeysRingSigner {}, &mut rng).unwrap();
+
+        assert_eq!(tx.prefix.inputs.len(), 3);
+        validate_all_input_rules(BLOCK_VERSION, &tx).unwrap();
+    }
+
+    #[test]
+    fn test_match_quotes_spends_each_input_once() {
+        let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
+        let mut ledger = create_test_ledger(&mut rng);
+        let originator = AccountKey::random(&mut rng);
+
+        let t1 = TokenId::from(1);
+        let t2 = TokenId::from(2);
+        let sci = create_sci(
+            &mut ledger,
+            &originator,
+            Amount::new(500, t1),
+            Amount::new(250, t2),
+            Some(0),
+            0,
+            &mut rng,
+        );
+
+        // The same input quoted twice only provides its liquidity once.
+        let quotes = vec![
+            Quote::new(sci.clone(), 1).unwrap(),
+            Quote::new(sci, 2).unwrap(),
+        ];
+        assert_matches!(
+            match_quotes(quotes.clone(), 600),
+            Err(Error::InsufficientLiquidity(600, 500))
+        );
+
+        let fills = match_quotes(quotes, 500).unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].base_tokens, 500);
+    }
+}
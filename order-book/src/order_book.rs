@@ -0,0 +1,56 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! The interface of an order book of signed contingent inputs.
+
+use crate::{Error, Pair, Quote, QuoteId};
+use mc_transaction_core::ring_signature::KeyImage;
+use mc_transaction_extra::SignedContingentInput;
+use std::{
+    ops::RangeBounds,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// An order book of signed contingent inputs, indexed by pair and price.
+pub trait OrderBook: Clone + Send + Sync + 'static {
+    /// Add a signed contingent input to the order book, and return the
+    /// resulting quote.
+    ///
+    /// The quote is timestamped with the current time unless a timestamp is
+    /// provided.
+    fn add_sci(&self, sci: SignedContingentInput, timestamp: Option<u64>) -> Result<Quote, Error>;
+
+    /// Remove a single quote by its id.
+    fn remove_quote_by_id(&self, id: &QuoteId) -> Result<Quote, Error>;
+
+    /// Remove all quotes whose signed input has this key image.
+    fn remove_quotes_by_key_image(&self, key_image: &KeyImage) -> Result<Vec<Quote>, Error>;
+
+    /// Remove all quotes which can't be included in the block with this index
+    /// or later blocks, because of their tombstone block.
+    fn remove_quotes_by_tombstone_block(&self, block_index: u64) -> Result<Vec<Quote>, Error>;
+
+    /// Get the quotes of a pair which can provide a quantity of the base
+    /// token in the given range, best price first.
+    ///
+    /// A limit of zero means no limit.
+    fn get_quotes(
+        &self,
+        pair: &Pair,
+        base_token_quantity: impl RangeBounds<u64>,
+        limit: usize,
+    ) -> Result<Vec<Quote>, Error>;
+
+    /// Get a quote by its id.
+    fn get_quote_by_id(&self, id: &QuoteId) -> Result<Option<Quote>, Error>;
+
+    /// Get all of the quotes in the order book.
+    fn get_all_quotes(&self) -> Result<Vec<Quote>, Error>;
+}
+
+/// The current time, in seconds since the epoch, used to timestamp quotes.
+pub fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time is before the epoch")
+        .as_secs()
+}
@@ -0,0 +1,368 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Quotes are signed contingent inputs in the order book, together with the
+//! trade they offer.
+
+use crate::Error;
+use mc_crypto_digestible::{Digestible, MerlinTranscript};
+use mc_transaction_core::{ring_signature::KeyImage, TokenId};
+use mc_transaction_extra::{
+    SignedContingentInput, SignedContingentInputAmounts, SignedContingentInputError,
+};
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    ops::RangeInclusive,
+};
+
+/// A pair of tokens which are traded against each other.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Pair {
+    /// The token offered by the originator of a quote.
+    pub base_token_id: TokenId,
+
+    /// The token the originator of a quote asks for in exchange.
+    pub counter_token_id: TokenId,
+}
+
+/// The unique identifier of a quote, a digest of its signed contingent input.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct QuoteId(pub [u8; 32]);
+
+impl From<&SignedContingentInput> for QuoteId {
+    fn from(src: &SignedContingentInput) -> Self {
+        Self(src.digest32::<MerlinTranscript>(b"mc-order-book-quote-id"))
+    }
+}
+
+impl TryFrom<&[u8]> for QuoteId {
+    type Error = core::array::TryFromSliceError;
+
+    fn try_from(src: &[u8]) -> Result<Self, Self::Error> {
+        Ok(Self(src.try_into()?))
+    }
+}
+
+impl Display for QuoteId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+/// A signed contingent input in the order book.
+///
+/// The originator of the quote offers the value of the signed input, in the
+/// base token, in exchange for the outputs required by the input rules, in the
+/// counter token. Quotes with partial fill rules (MCIP #42) may be taken in
+/// part, anywhere within their base range.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Quote {
+    /// The unique identifier of the quote
+    id: QuoteId,
+
+    /// The signed contingent input, without membership proofs
+    sci: SignedContingentInput,
+
+    /// The amounts revealed when validating the signed contingent input
+    amounts: SignedContingentInputAmounts,
+
+    /// The pair traded by the quote
+    pair: Pair,
+
+    /// The quantities of the base token which can be taken from the quote
+    base_range: RangeInclusive<u64>,
+
+    /// The quantity of the counter token it costs to take the whole quote
+    max_counter_tokens: u64,
+
+    /// The smallest partial fill value allowed by the input rules
+    min_partial_fill_value: u64,
+
+    /// The time the quote was added to the order book, in seconds since the
+    /// epoch
+    timestamp: u64,
+}
+
+impl Quote {
+    /// Create a quote from a signed contingent input.
+    ///
+    /// This checks that the signed contingent input is well-formed, and that it
+    /// offers one token in exchange for another. It doesn't check the
+    /// signed contingent input against the ledger.
+    pub fn new(mut sci: SignedContingentInput, timestamp: u64) -> Result<Self, Error> {
+        // Membership proofs are regenerated from the ledger when a quote is
+        // filled, so they are not part of the quote.
+        sci.tx_in.proofs.clear();
+
+        let amounts = sci.validate()?;
+        let base_token_id = amounts.pseudo_output.token_id;
+
+        let (min_partial_fill_value, max_partial_fill_value) =
+            match amounts.partial_fill_change.as_ref() {
+                Some(partial_fill_change) => {
+                    if partial_fill_change.token_id != base_token_id {
+                        return Err(Error::UnsupportedSci(
+                            "partial fill change is not in the token of the input".into(),
+                        ));
+                    }
+                    let rules = sci
+                        .tx_in
+                        .input_rules
+                        .as_ref()
+                        .ok_or(SignedContingentInputError::MissingRules)?;
+                    (rules.min_partial_fill_value, partial_fill_change.value)
+                }
+                None => (0, 0),
+            };
+
+        let max_balance_sheet = amounts.compute_balance_sheet(max_partial_fill_value)?;
+        let mut counter_entries = max_balance_sheet
+            .iter()
+            .filter(|(token_id, value)| **token_id != base_token_id && **value > 0);
+        let (counter_token_id, max_counter_tokens) =
+            match (counter_entries.next(), counter_entries.next()) {
+                (Some((token_id, value)), None) => (
+                    *token_id,
+                    u64::try_from(*value)
+                        .map_err(|_| Error::UnsupportedSci("counter value overflow".into()))?,
+                ),
+                (None, _) => {
+                    return Err(Error::UnsupportedSci(
+                        "does not ask for anything in exchange".into(),
+                    ))
+                }
+                _ => {
+                    return Err(Error::UnsupportedSci(
+                        "asks for more than one token in exchange".into(),
+                    ))
+                }
+            };
+
+        let min_balance_sheet = amounts.compute_balance_sheet(min_partial_fill_value)?;
+        let min_base_tokens = base_tokens_received(&min_balance_sheet, base_token_id)?;
+        let max_base_tokens = base_tokens_received(&max_balance_sheet, base_token_id)?;
+        if max_base_tokens == 0 {
+            return Err(Error::UnsupportedSci(
+                "does not offer any of the base token".into(),
+            ));
+        }
+
+        Ok(Self {
+            id: QuoteId::from(&sci),
+            sci,
+            amounts,
+            pair: Pair {
+                base_token_id,
+                counter_token_id,
+            },
+            base_range: min_base_tokens..=max_base_tokens,
+            max_counter_tokens,
+            min_partial_fill_value,
+            timestamp,
+        })
+    }
+
+    /// The unique identifier of the quote.
+    pub fn id(&self) -> &QuoteId {
+        &self.id
+    }
+
+    /// The signed contingent input, without membership proofs.
+    pub fn sci(&self) -> &SignedContingentInput {
+        &self.sci
+    }
+
+    /// The amounts revealed when validating the signed contingent input.
+    pub fn amounts(&self) -> &SignedContingentInputAmounts {
+        &self.amounts
+    }
+
+    /// The pair traded by the quote.
+    pub fn pair(&self) -> &Pair {
+        &self.pair
+    }
+
+    /// The quantities of the base token which can be taken from the quote.
+    /// For quotes without partial fill rules, this is a single quantity.
+    pub fn base_range(&self) -> &RangeInclusive<u64> {
+        &self.base_range
+    }
+
+    /// The quantity of the counter token it costs to take the whole quote.
+    pub fn max_counter_tokens(&self) -> u64 {
+        self.max_counter_tokens
+    }
+
+    /// The time the quote was added to the order book, in seconds since the
+    /// epoch.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// The key image of the signed input. The quote is no longer valid once it
+    /// appears in the ledger.
+    pub fn key_image(&self) -> KeyImage {
+        self.sci.key_image()
+    }
+
+    /// The block index at which the quote expires, or zero if it doesn't.
+    pub fn max_tombstone_block(&self) -> u64 {
+        self.sci
+            .tx_in
+            .input_rules
+            .as_ref()
+            .map(|rules| rules.max_tombstone_block)
+            .unwrap_or_default()
+    }
+
+    /// Whether the quote has partial fill rules.
+    pub fn is_partial_fill(&self) -> bool {
+        self.amounts.partial_fill_change.is_some()
+    }
+
+    /// The partial fill value to use with the signed contingent input in order
+    /// to take a quantity of the base token. This is zero for quotes without
+    /// partial fill rules.
+    pub fn partial_fill_value(&self, base_tokens: u64) -> Result<u64, Error> {
+        if !self.base_range.contains(&base_tokens) {
+            return Err(Error::InvalidFillAmount(
+                base_tokens,
+                *self.base_range.start(),
+                *self.base_range.end(),
+            ));
+        }
+        if !self.is_partial_fill() {
+            return Ok(0);
+        }
+        // Each unit of partial fill value gives the taker one more unit of the
+        // base token.
+        Ok(base_tokens - self.base_range.start() + self.min_partial_fill_value)
+    }
+
+    /// The quantity of the counter token it costs to take a quantity of the
+    /// base token.
+    pub fn counter_tokens(&self, base_tokens: u64) -> Result<u64, Error> {
+        let partial_fill_value = self.partial_fill_value(base_tokens)?;
+        let balance_sheet = self.amounts.compute_balance_sheet(partial_fill_value)?;
+        let counter_tokens = balance_sheet
+            .get(&self.pair.counter_token_id)
+            .copied()
+            .unwrap_or_default();
+        u64::try_from(counter_tokens)
+            .map_err(|_| Error::UnsupportedSci("counter value overflow".into()))
+    }
+
+    /// Compare the prices of two quotes, in counter tokens per base token.
+    /// Cheaper quotes come first.
+    pub fn cmp_price(&self, other: &Self) -> Ordering {
+        let lhs = self.max_counter_tokens as u128 * *other.base_range.end() as u128;
+        let rhs = other.max_counter_tokens as u128 * *self.base_range.end() as u128;
+        lhs.cmp(&rhs)
+    }
+
+    /// The order in which quotes are taken: cheapest first, then oldest first.
+    pub fn cmp_priority(&self, other: &Self) -> Ordering {
+        self.cmp_price(other)
+            .then(self.timestamp.cmp(&other.timestamp))
+            .then(self.id.cmp(&other.id))
+    }
+}
+
+/// The quantity of the base token the taker of a quote receives, given the
+/// balance sheet of its signed contingent input.
+fn base_tokens_received(
+    balance_sheet: &BTreeMap<TokenId, i128>,
+    base_token_id: TokenId,
+) -> Result<u64, Error> {
+    let base_value = balance_sheet
+        .get(&base_token_id)
+        .copied()
+        .unwrap_or_default();
+    u64::try_from(-base_value).map_err(|_| {
+        Error::UnsupportedSci("requires the base token from the taker of the quote".into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_sci, create_test_ledger};
+    use assert_matches::assert_matches;
+    use mc_account_keys::AccountKey;
+    use mc_transaction_core::Amount;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_partial_fill_quote() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let mut ledger = create_test_ledger(&mut rng);
+        let originator = AccountKey::random(&mut rng);
+
+        // Offer 1000 of token 1 for 250 of token 2, taking at least 100 of
+        // token 1.
+        let sci = create_sci(
+            &mut ledger,
+            &originator,
+            Amount::new(1000, TokenId::from(1)),
+            Amount::new(250, TokenId::from(2)),
+            Some(100),
+            1000,
+            &mut rng,
+        );
+        let quote = Quote::new(sci.clone(), 7).unwrap();
+
+        assert_eq!(quote.id(), &QuoteId::from(&sci));
+        assert_eq!(
+            quote.pair(),
+            &Pair {
+                base_token_id: TokenId::from(1),
+                counter_token_id: TokenId::from(2),
+            }
+        );
+        assert_eq!(quote.base_range(), &(100..=1000));
+        assert_eq!(quote.max_counter_tokens(), 250);
+        assert_eq!(quote.max_tombstone_block(), 1000);
+        assert_eq!(quote.timestamp(), 7);
+
+        assert_eq!(quote.partial_fill_value(400).unwrap(), 400);
+        assert_eq!(quote.counter_tokens(400).unwrap(), 100);
+        // Fractional outputs are rounded up
+        assert_eq!(quote.counter_tokens(401).unwrap(), 101);
+        assert_matches!(
+            quote.counter_tokens(99),
+            Err(Error::InvalidFillAmount(99, 100, 1000))
+        );
+        assert_matches!(
+            quote.counter_tokens(1001),
+            Err(Error::InvalidFillAmount(1001, 100, 1000))
+        );
+    }
+
+    #[test]
+    fn test_quote_without_partial_fill() {
+        let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
+        let mut ledger = create_test_ledger(&mut rng);
+        let originator = AccountKey::random(&mut rng);
+
+        let sci = create_sci(
+            &mut ledger,
+            &originator,
+            Amount::new(1000, TokenId::from(1)),
+            Amount::new(300, TokenId::from(2)),
+            None,
+            1000,
+            &mut rng,
+        );
+        let quote = Quote::new(sci, 0).unwrap();
+
+        assert!(!quote.is_partial_fill());
+        assert_eq!(quote.base_range(), &(1000..=1000));
+        assert_eq!(quote.partial_fill_value(1000).unwrap(), 0);
+        assert_eq!(quote.counter_tokens(1000).unwrap(), 300);
+        assert_matches!(
+            quote.counter_tokens(500),
+            Err(Error::InvalidFillAmount(500, 1000, 1000))
+        );
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Server for the order book.
+
+use crate::{prune_order_book, service::OrderBookService, OrderBook};
+use futures::executor::block_on;
+use grpcio::{Server as GrpcioServer, ServerBuilder};
+use mc_common::logger::{log, Logger};
+use mc_ledger_db::Ledger;
+use mc_order_book_api::{order_book_grpc, OrderBookUri};
+use mc_util_grpc::{ConnectionUriGrpcioServer, HealthService};
+use mc_util_uri::ConnectionUri;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{Builder as ThreadBuilder, JoinHandle},
+    time::Duration,
+};
+
+/// The order book server, which serves the gRPC API and periodically removes
+/// quotes which can no longer be filled.
+pub struct Server {
+    server: GrpcioServer,
+    uri: OrderBookUri,
+    prune_thread: Option<JoinHandle<()>>,
+    stop_requested: Arc<AtomicBool>,
+    logger: Logger,
+}
+
+impl Server {
+    /// Construct a new server object.
+    pub fn new<OB: OrderBook, L: Ledger + Clone + Send + Sync + 'static>(
+        order_book: OB,
+        ledger: L,
+        client_listen_uri: &OrderBookUri,
+        prune_interval: Duration,
+        logger: Logger,
+    ) -> Self {
+        let env = Arc::new(
+            grpcio::EnvBuilder::new()
+                .name_prefix("OrderBook-RPC".to_string())
+                .build(),
+        );
+
+        let order_book_service = order_book_grpc::create_order_book_api(OrderBookService::new(
+            order_book.clone(),
+            ledger.clone(),
+            logger.clone(),
+        ));
+        log::debug!(logger, "Constructed Order Book GRPC Service");
+
+        // Health check service
+        let health_service = HealthService::new(None, logger.clone()).into_service();
+
+        log::info!(
+            logger,
+            "Starting Order Book server on {}",
+            client_listen_uri.addr(),
+        );
+        let server = ServerBuilder::new(env)
+            .register_service(order_book_service)
+            .register_service(health_service)
+            .build_using_uri(client_listen_uri, logger.clone())
+            .expect("Could not build a server using the client listen URI");
+
+        let stop_requested = Arc::new(AtomicBool::new(false));
+        let thread_stop_requested = stop_requested.clone();
+        let thread_logger = logger.clone();
+        let prune_thread = ThreadBuilder::new()
+            .name("OrderBookPrune".to_string())
+            .spawn(move || {
+                while !thread_stop_requested.load(Ordering::SeqCst) {
+                    match prune_order_book(&order_book, &ledger) {
+                        Ok(removed) if !removed.is_empty() => {
+                            log::info!(thread_logger, "Pruned {} quotes", removed.len())
+                        }
+                        Ok(_) => {}
+                        Err(err) => {
+                            log::error!(thread_logger, "Failed pruning order book: {}", err)
+                        }
+                    }
+                    std::thread::sleep(prune_interval);
+                }
+            })
+            .expect("Failed spawning order book prune thread");
+
+        Self {
+            server,
+            uri: client_listen_uri.clone(),
+            prune_thread: Some(prune_thread),
+            stop_requested,
+            logger,
+        }
+    }
+
+    /// Start the server.
+    pub fn start(&mut self) {
+        self.server.start();
+        log::info!(self.logger, "API listening on {}", self.uri.addr());
+    }
+
+    /// Stop the server.
+    pub fn stop(&mut self) {
+        block_on(self.server.shutdown()).expect("Could not stop grpc server");
+        self.stop_requested.store(true, Ordering::SeqCst);
+        if let Some(prune_thread) = self.prune_thread.take() {
+            prune_thread
+                .join()
+                .expect("Order book prune thread panicked");
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Implementation of the OrderBookApi
+
+use crate::{check_sci_against_ledger, Error, Matcher, OrderBook, Pair, SVC_COUNTERS};
+use grpcio::{RpcContext, RpcStatus, UnarySink};
+use mc_common::logger::{self, Logger};
+use mc_ledger_db::Ledger;
+use mc_order_book_api::{
+    order_book_grpc::OrderBookApi, GetQuotesRequest, GetQuotesResponse, MatchQuotesRequest,
+    MatchQuotesResponse, QuoteStatusCode, SubmitQuotesRequest, SubmitQuotesResponse,
+};
+use mc_transaction_extra::SignedContingentInput;
+use mc_util_grpc::{rpc_internal_error, rpc_invalid_arg_error, rpc_logger, send_result};
+use std::ops::Bound;
+
+/// The order book gRPC service.
+#[derive(Clone)]
+pub struct OrderBookService<OB: OrderBook, L: Ledger + Clone + Send + Sync + 'static> {
+    /// The order book.
+    order_book: OB,
+
+    /// The ledger quotes are checked against.
+    ledger: L,
+
+    /// Matches orders against the order book.
+    matcher: Matcher<OB, L>,
+
+    /// Logger.
+    logger: Logger,
+}
+
+impl<OB: OrderBook, L: Ledger + Clone + Send + Sync + 'static> OrderBookService<OB, L> {
+    /// Create a new order book service.
+    pub fn new(order_book: OB, ledger: L, logger: Logger) -> Self {
+        let matcher = Matcher::new(order_book.clone(), ledger.clone());
+        Self {
+            order_book,
+            ledger,
+            matcher,
+            logger,
+        }
+    }
+
+    fn submit_quotes_impl(
+        &self,
+        request: SubmitQuotesRequest,
+        logger: &Logger,
+    ) -> Result<SubmitQuotesResponse, RpcStatus> {
+        let mut response = SubmitQuotesResponse::new();
+        for external_sci in request.get_quotes() {
+            let sci = SignedContingentInput::try_from(external_sci)
+                .map_err(|err| rpc_invalid_arg_error("sci.try_from", err, logger))?;
+
+            let result = check_sci_against_ledger(&self.ledger, &sci)
+                .and_then(|_| self.order_book.add_sci(sci.clone(), None));
+            let (status_code, error_message) = match result {
+                Ok(quote) => {
+                    response.mut_quotes().push((&quote).into());
+                    (QuoteStatusCode::Created, String::new())
+                }
+                Err(err @ Error::QuoteAlreadyExists(_)) => {
+                    let quote = self
+                        .order_book
+                        .get_quote_by_id(&(&sci).into())
+                        .map_err(|err| rpc_internal_error("get_quote_by_id", err, logger))?;
+                    if let Some(quote) = quote {
+                        response.mut_quotes().push((&quote).into());
+                    }
+                    (QuoteStatusCode::AlreadyExists, err.to_string())
+                }
+                Err(err @ Error::Sci(_)) => (QuoteStatusCode::InvalidSci, err.to_string()),
+                Err(err @ Error::UnsupportedSci(_)) => {
+                    (QuoteStatusCode::UnsupportedSci, err.to_string())
+                }
+                Err(err @ Error::KeyImageSpent) => {
+                    (QuoteStatusCode::KeyImageSpent, err.to_string())
+                }
+                Err(err @ Error::Expired(_)) => (QuoteStatusCode::Expired, err.to_string()),
+                Err(err) => return Err(rpc_internal_error("add_sci", err, logger)),
+            };
+            response.mut_status_codes().push(status_code);
+            response.mut_error_messages().push(error_message);
+        }
+        Ok(response)
+    }
+
+    fn get_quotes_impl(
+        &self,
+        request: GetQuotesRequest,
+        logger: &Logger,
+    ) -> Result<GetQuotesResponse, RpcStatus> {
+        let pair = Pair::from(request.get_pair());
+        let end = match request.get_base_range_max() {
+            0 => Bound::Unbounded,
+            max => Bound::Included(max),
+        };
+        let quotes = self
+            .order_book
+            .get_quotes(
+                &pair,
+                (Bound::Included(request.get_base_range_min()), end),
+                request.get_limit() as usize,
+            )
+            .map_err(|err| rpc_internal_error("get_quotes", err, logger))?;
+
+        let mut response = GetQuotesResponse::new();
+        response.set_quotes(quotes.iter().map(Into::into).collect());
+        Ok(response)
+    }
+
+    fn match_quotes_impl(
+        &self,
+        request: MatchQuotesRequest,
+        logger: &Logger,
+    ) -> Result<MatchQuotesResponse, RpcStatus> {
+        let pair = Pair::from(request.get_pair());
+        let fills = self
+            .matcher
+            .find_fills(&pair, request.get_base_tokens())
+            .map_err(|err| match err {
+                Error::InsufficientLiquidity(..) => {
+                    rpc_invalid_arg_error("find_fills", err, logger)
+                }
+                err => rpc_internal_error("find_fills", err, logger),
+            })?;
+
+        let mut response = MatchQuotesResponse::new();
+        response.set_total_counter_tokens(fills.iter().map(|fill| fill.counter_tokens).sum());
+        response.set_fills(fills.iter().map(Into::into).collect());
+        Ok(response)
+    }
+}
+
+impl<OB: OrderBook, L: Ledger + Clone + Send + Sync + 'static> OrderBookApi
+    for OrderBookService<OB, L>
+{
+    fn submit_quotes(
+        &mut self,
+        ctx: RpcContext,
+        request: SubmitQuotesRequest,
+        sink: UnarySink<SubmitQuotesResponse>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            send_result(ctx, sink, self.submit_quotes_impl(request, logger), logger)
+        })
+    }
+
+    fn get_quotes(
+        &mut self,
+        ctx: RpcContext,
+        request: GetQuotesRequest,
+        sink: UnarySink<GetQuotesResponse>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            send_result(ctx, sink, self.get_quotes_impl(request, logger), logger)
+        })
+    }
+
+    fn match_quotes(
+        &mut self,
+        ctx: RpcContext,
+        request: MatchQuotesRequest,
+        sink: UnarySink<MatchQuotesResponse>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            send_result(ctx, sink, self.match_quotes_impl(request, logger), logger)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{create_sci, create_test_ledger},
+        InMemoryOrderBook,
+    };
+    use mc_account_keys::AccountKey;
+    use mc_api::external;
+    use mc_common::logger::test_with_logger;
+    use mc_transaction_core::{Amount, TokenId};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test_with_logger]
+    fn test_submit_get_and_match_quotes(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let mut ledger = create_test_ledger(&mut rng);
+        let originator = AccountKey::random(&mut rng);
+        let service =
+            OrderBookService::new(InMemoryOrderBook::new(), ledger.clone(), logger.clone());
+
+        let t1 = TokenId::from(1);
+        let t2 = TokenId::from(2);
+        let sci = create_sci(
+            &mut ledger,
+            &originator,
+            Amount::new(500, t1),
+            Amount::new(250, t2),
+            Some(0),
+            0,
+            &mut rng,
+        );
+        let expired = create_sci(
+            &mut ledger,
+            &originator,
+            Amount::new(500, t1),
+            Amount::new(100, t2),
+            Some(0),
+            1,
+            &mut rng,
+        );
+
+        let mut request = SubmitQuotesRequest::new();
+        request.set_quotes(
+            [&sci, &sci, &expired]
+                .into_iter()
+                .map(external::SignedContingentInput::from)
+                .collect(),
+        );
+        let response = service.submit_quotes_impl(request, &logger).unwrap();
+        assert_eq!(
+            response.get_status_codes(),
+            &[
+                QuoteStatusCode::Created,
+                QuoteStatusCode::AlreadyExists,
+                QuoteStatusCode::Expired
+            ]
+        );
+        assert_eq!(response.get_quotes().len(), 2);
+        assert_eq!(response.get_quotes()[0], response.get_quotes()[1]);
+
+        let pair = Pair {
+            base_token_id: t1,
+            counter_token_id: t2,
+        };
+        let mut request = GetQuotesRequest::new();
+        request.set_pair((&pair).into());
+        let response = service.get_quotes_impl(request, &logger).unwrap();
+        assert_eq!(response.get_quotes().len(), 1);
+        assert_eq!(response.get_quotes()[0].get_max_base_tokens(), 500);
+        assert_eq!(response.get_quotes()[0].get_max_counter_tokens(), 250);
+
+        let mut request = MatchQuotesRequest::new();
+        request.set_pair((&pair).into());
+        request.set_base_tokens(200);
+        let response = service.match_quotes_impl(request, &logger).unwrap();
+        assert_eq!(response.get_fills().len(), 1);
+        assert_eq!(response.get_fills()[0].get_partial_fill_value(), 200);
+        assert_eq!(response.get_total_counter_tokens(), 100);
+
+        let mut request = MatchQuotesRequest::new();
+        request.set_pair((&pair).into());
+        request.set_base_tokens(600);
+        assert!(service.match_quotes_impl(request, &logger).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Utilities for testing the order book.
+
+use mc_account_keys::AccountKey;
+use mc_crypto_keys::RistrettoPublic;
+use mc_crypto_ring_signature_signer::NoKeysRingSigner;
+use mc_fog_report_validation_test_utils::MockFogResolver;
+use mc_ledger_db::{
+    test_utils::{add_txos_to_ledger, create_ledger, initialize_ledger},
+    Ledger, LedgerDB,
+};
+use mc_transaction_builder::{
+    test_utils::get_ring, EmptyMemoBuilder, InputCredentials, ReservedSubaddresses,
+    SignedContingentInputBuilder,
+};
+use mc_transaction_core::{
+    constants::RING_SIZE, onetime_keys::recover_onetime_private_key, Amount, BlockVersion,
+};
+use mc_transaction_extra::SignedContingentInput;
+use rand::{CryptoRng, RngCore};
+
+/// The block version used by the tests.
+pub const BLOCK_VERSION: BlockVersion = BlockVersion::MAX;
+
+/// Create a ledger with a few blocks in it.
+pub fn create_test_ledger(rng: &mut (impl CryptoRng + RngCore)) -> LedgerDB {
+    let mut ledger = create_ledger();
+    let account_key = AccountKey::random(rng);
+    initialize_ledger(BLOCK_VERSION, &mut ledger, 3, &account_key, rng);
+    ledger
+}
+
+/// Create a signed contingent input offering `offered` in exchange for
+/// `asked`. The ring of the input is added to the ledger.
+///
+/// If `min_partial_fill_value` is set, the signed contingent input has
+/// partial fill rules, otherwise `asked` is a required output.
+pub fn create_sci(
+    ledger: &mut LedgerDB,
+    originator: &AccountKey,
+    offered: Amount,
+    asked: Amount,
+    min_partial_fill_value: Option<u64>,
+    tombstone_block: u64,
+    rng: &mut (impl CryptoRng + RngCore),
+) -> SignedContingentInput {
+    let fog_resolver = MockFogResolver(Default::default());
+
+    let (ring, real_index) = get_ring(
+        BLOCK_VERSION,
+        offered,
+        RING_SIZE,
+        originator,
+        &fog_resolver,
+        rng,
+    );
+    add_txos_to_ledger(ledger, BLOCK_VERSION, &ring, rng).unwrap();
+    let indices = ring
+        .iter()
+        .map(|tx_out| {
+            ledger
+                .get_tx_out_index_by_public_key(&tx_out.public_key)
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let membership_proofs = ledger.get_tx_out_proof_of_memberships(&indices).unwrap();
+
+    let onetime_private_key = recover_onetime_private_key(
+        &RistrettoPublic::try_from(&ring[real_index].public_key).unwrap(),
+        originator.view_private_key(),
+        &originator.default_subaddress_spend_private(),
+    );
+    let input_credentials = InputCredentials::new(
+        ring,
+        membership_proofs,
+        real_index,
+        onetime_private_key,
+        *originator.view_private_key(),
+    )
+    .unwrap();
+
+    let mut builder = SignedContingentInputBuilder::new(
+        BLOCK_VERSION,
+        input_credentials,
+        fog_resolver,
+        EmptyMemoBuilder,
+    )
+    .unwrap();
+
+    match min_partial_fill_value {
+        Some(min_partial_fill_value) => {
+            builder
+                .add_partial_fill_output(asked, &originator.default_subaddress(), rng)
+                .unwrap();
+            builder
+                .add_partial_fill_change_output(
+                    offered,
+                    &ReservedSubaddresses::from(originator),
+                    rng,
+                )
+                .unwrap();
+            builder.set_min_partial_fill_value(min_partial_fill_value);
+        }
+        None => {
+            builder
+                .add_required_output(asked, &originator.default_subaddress(), rng)
+                .unwrap();
+        }
+    }
+    builder.set_tombstone_block(tombstone_block);
+
+    builder.build(&NoKeysRingSigner {}, rng).unwrap()
+}
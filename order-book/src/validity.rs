@@ -0,0 +1,129 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Checks of signed contingent inputs against the ledger.
+
+use crate::{Error, OrderBook, Quote};
+use mc_ledger_db::Ledger;
+use mc_transaction_extra::SignedContingentInput;
+
+/// Check that a signed contingent input can still be included in the next
+/// block: its key image must not be in the ledger, and its tombstone block
+/// must not have passed.
+pub fn check_sci_against_ledger(
+    ledger: &impl Ledger,
+    sci: &SignedContingentInput,
+) -> Result<(), Error> {
+    if ledger.contains_key_image(&sci.key_image())? {
+        return Err(Error::KeyImageSpent);
+    }
+    if let Some(rules) = sci.tx_in.input_rules.as_ref() {
+        if rules.max_tombstone_block != 0 && rules.max_tombstone_block <= ledger.num_blocks()? {
+            return Err(Error::Expired(rules.max_tombstone_block));
+        }
+    }
+    Ok(())
+}
+
+/// Remove the quotes which can no longer be filled from an order book, and
+/// return them.
+pub fn prune_order_book(
+    order_book: &impl OrderBook,
+    ledger: &impl Ledger,
+) -> Result<Vec<Quote>, Error> {
+    let mut removed = order_book.remove_quotes_by_tombstone_block(ledger.num_blocks()?)?;
+    for quote in order_book.get_all_quotes()? {
+        if ledger.contains_key_image(&quote.key_image())? {
+            removed.extend(order_book.remove_quotes_by_key_image(&quote.key_image())?);
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{create_sci, create_test_ledger, BLOCK_VERSION},
+        InMemoryOrderBook,
+    };
+    use assert_matches::assert_matches;
+    use mc_account_keys::AccountKey;
+    use mc_ledger_db::{test_utils::add_block_to_ledger, LedgerDB};
+    use mc_transaction_core::{ring_signature::KeyImage, Amount, TokenId};
+    use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+    #[test]
+    fn test_spent_and_expired_quotes_are_pruned() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let mut ledger = create_test_ledger(&mut rng);
+        let originator = AccountKey::random(&mut rng);
+        let order_book = InMemoryOrderBook::new();
+
+        let mut make_sci = |ledger: &mut LedgerDB, tombstone_block| {
+            create_sci(
+                ledger,
+                &originator,
+                Amount::new(1000, TokenId::from(1)),
+                Amount::new(100, TokenId::from(2)),
+                Some(0),
+                tombstone_block,
+                &mut rng,
+            )
+        };
+        let spent = make_sci(&mut ledger, 0);
+        let tombstone_block = ledger.num_blocks().unwrap() + 2;
+        let expiring = make_sci(&mut ledger, tombstone_block);
+        let valid = make_sci(&mut ledger, 0);
+        for sci in [&spent, &expiring, &valid] {
+            check_sci_against_ledger(&ledger, sci).unwrap();
+            order_book.add_sci(sci.clone(), None).unwrap();
+        }
+
+        // The key image of the first signed contingent input lands in the ledger
+        let recipient = AccountKey::random(&mut rng).default_subaddress();
+        add_block_to_ledger(
+            &mut ledger,
+            BLOCK_VERSION,
+            &[recipient.clone()],
+            Amount::new(1, TokenId::from(0)),
+            &[spent.key_image()],
+            &mut rng,
+        )
+        .unwrap();
+        assert_matches!(
+            check_sci_against_ledger(&ledger, &spent),
+            Err(Error::KeyImageSpent)
+        );
+        check_sci_against_ledger(&ledger, &expiring).unwrap();
+
+        // The tombstone block of the second one is reached
+        add_block_to_ledger(
+            &mut ledger,
+            BLOCK_VERSION,
+            &[recipient],
+            Amount::new(1, TokenId::from(0)),
+            &[KeyImage::from(rng.next_u64())],
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(ledger.num_blocks().unwrap(), tombstone_block);
+        assert_matches!(
+            check_sci_against_ledger(&ledger, &expiring),
+            Err(Error::Expired(_))
+        );
+
+        let mut pruned = prune_order_book(&order_book, &ledger)
+            .unwrap()
+            .iter()
+            .map(|quote| quote.key_image())
+            .collect::<Vec<_>>();
+        pruned.sort();
+        let mut expected = vec![spent.key_image(), expiring.key_image()];
+        expected.sort();
+        assert_eq!(pruned, expected);
+
+        let remaining = order_book.get_all_quotes().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].key_image(), valid.key_image());
+    }
+}
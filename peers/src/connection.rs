@@ -8,11 +8,12 @@ use crate::{
     traits::ConsensusConnection,
 };
 use core::fmt::{Display, Formatter, Result as FmtResult};
+use futures::{executor::block_on, TryStreamExt};
 use grpcio::{ChannelBuilder, Environment, Error as GrpcError};
 use mc_attest_api::attest_grpc::AttestedApiClient;
 use mc_attest_core::EvidenceKind;
 use mc_attest_enclave_api::PeerSession;
-use mc_blockchain_types::{Block, BlockID, BlockIndex};
+use mc_blockchain_types::{Block, BlockData, BlockID, BlockIndex};
 use mc_common::{
     logger::{log, o, Logger},
     trace_time, NodeID, ResponderId,
@@ -358,4 +359,21 @@ impl<Enclave: ConsensusEnclave + Clone + Send + Sync> ConsensusConnection
             Ok(Some(msg))
         }
     }
+
+    fn fetch_block_data(&mut self, range: Range<BlockIndex>) -> Result<Vec<BlockData>> {
+        trace_time!(self.logger, "PeerConnection::fetch_block_data");
+
+        let mut request = BlocksRequest::new();
+        request.set_offset(range.start);
+        let limit = u32::try_from(range.end - range.start).or(Err(Error::RequestTooLarge))?;
+        request.set_limit(limit);
+
+        self.log_attested_call("get_block_data", |this| {
+            let receiver = this.consensus_api_client.get_block_data(&request)?;
+            block_on(receiver.try_collect::<Vec<_>>())
+        })?
+        .iter()
+        .map(|archive_block| BlockData::try_from(archive_block).map_err(Error::from))
+        .collect()
+    }
 }
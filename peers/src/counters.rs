@@ -0,0 +1,7 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+use mc_util_metrics::OpMetrics;
+
+lazy_static::lazy_static! {
+    pub static ref OP_COUNTERS: OpMetrics = OpMetrics::new_and_registered("peers");
+}
@@ -8,7 +8,9 @@ extern crate alloc;
 mod broadcast;
 mod connection;
 mod consensus_msg;
+mod counters;
 mod error;
+mod peer_health;
 mod sync;
 mod threaded_broadcaster;
 mod threaded_broadcaster_retry;
@@ -21,8 +23,12 @@ pub use crate::{
         ConsensusMsg, ConsensusMsgError, ConsensusValue, TxProposeAAD, VerifiedConsensusMsg,
     },
     error::{Error, Result},
-    threaded_broadcaster::ThreadedBroadcaster,
+    peer_health::{
+        PeerHealth, PeerHealthStatus, PeerHealthTracker, UNHEALTHY_CONSECUTIVE_FAILURES,
+    },
+    threaded_broadcaster::{ThreadedBroadcaster, MAX_QUEUED_PROPOSE_TX_MSGS},
     threaded_broadcaster_retry::{
+        AdaptiveRetryPolicy as ThreadedBroadcasterAdaptiveRetryPolicy,
         FibonacciRetryPolicy as ThreadedBroadcasterFibonacciRetryPolicy,
         RetryPolicy as ThreadedBroadcasterRetryPolicy, DEFAULT_RETRY_MAX_ATTEMPTS,
     },
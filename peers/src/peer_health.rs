@@ -0,0 +1,273 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Per-peer health tracking, fed by the outcome of message deliveries.
+
+use crate::{counters, error::Error};
+use mc_common::{HashMap, ResponderId};
+use mc_consensus_enclave_api::Error as EnclaveError;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+/// Number of consecutive delivery failures after which a peer is considered
+/// unhealthy.
+pub const UNHEALTHY_CONSECUTIVE_FAILURES: u32 = 3;
+
+/// Error rate above which a peer is considered degraded.
+pub const DEGRADED_ERROR_RATE: f64 = 0.25;
+
+/// Weight given to the most recent sample when updating moving averages.
+const EWMA_WEIGHT: f64 = 0.2;
+
+/// Coarse health classification of a peer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PeerHealthStatus {
+    /// No message has been delivered to this peer yet.
+    Unknown,
+
+    /// Messages are being delivered.
+    Healthy,
+
+    /// Some recent deliveries failed.
+    Degraded,
+
+    /// Recent deliveries are consistently failing.
+    Unhealthy,
+}
+
+/// Health of a single peer, as observed by the broadcaster.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerHealth {
+    /// The peer this refers to.
+    pub responder_id: ResponderId,
+
+    /// Moving average of the time it takes to deliver a message, including
+    /// retries.
+    pub latency: Option<Duration>,
+
+    /// Moving average of the fraction of deliveries that failed.
+    pub error_rate: f64,
+
+    /// Number of messages delivered successfully.
+    pub successes: u64,
+
+    /// Number of messages that could not be delivered.
+    pub failures: u64,
+
+    /// Number of failed deliveries since the last successful one.
+    pub consecutive_failures: u32,
+
+    /// When a message was last delivered successfully.
+    pub last_success: Option<SystemTime>,
+
+    /// The most recent delivery error.
+    pub last_error: Option<String>,
+
+    /// Whether the peer's enclave is attested, if known. This is only updated
+    /// when a delivery succeeds or fails due to attestation.
+    pub attested: Option<bool>,
+
+    /// Number of tx proposals dropped because the peer was unhealthy or its
+    /// queue was full.
+    pub dropped_propose_txs: u64,
+}
+
+impl PeerHealth {
+    /// Create an entry for a peer we have not tried to reach yet.
+    pub fn new(responder_id: ResponderId) -> Self {
+        Self {
+            responder_id,
+            latency: None,
+            error_rate: 0.0,
+            successes: 0,
+            failures: 0,
+            consecutive_failures: 0,
+            last_success: None,
+            last_error: None,
+            attested: None,
+            dropped_propose_txs: 0,
+        }
+    }
+
+    /// Classify the peer's health.
+    pub fn status(&self) -> PeerHealthStatus {
+        if self.successes == 0 && self.failures == 0 {
+            PeerHealthStatus::Unknown
+        } else if self.consecutive_failures >= UNHEALTHY_CONSECUTIVE_FAILURES {
+            PeerHealthStatus::Unhealthy
+        } else if self.consecutive_failures > 0 || self.error_rate > DEGRADED_ERROR_RATE {
+            PeerHealthStatus::Degraded
+        } else {
+            PeerHealthStatus::Healthy
+        }
+    }
+
+    fn record_success(&mut self, latency: Duration) {
+        self.latency = Some(match self.latency {
+            None => latency,
+            Some(avg) => avg.mul_f64(1.0 - EWMA_WEIGHT) + latency.mul_f64(EWMA_WEIGHT),
+        });
+        self.error_rate *= 1.0 - EWMA_WEIGHT;
+        self.successes += 1;
+        self.consecutive_failures = 0;
+        self.last_success = Some(SystemTime::now());
+        self.attested = Some(true);
+    }
+
+    fn record_failure(&mut self, err: &Error) {
+        self.error_rate = self.error_rate * (1.0 - EWMA_WEIGHT) + EWMA_WEIGHT;
+        self.failures += 1;
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.last_error = Some(err.to_string());
+        if matches!(
+            err,
+            Error::Attestation(_) | Error::Enclave(EnclaveError::Attest(_))
+        ) {
+            self.attested = Some(false);
+        }
+    }
+}
+
+/// Shared, thread-safe record of the health of all peers.
+///
+/// Peer threads record delivery outcomes, and anyone holding a clone can take
+/// a snapshot.
+#[derive(Clone, Default)]
+pub struct PeerHealthTracker {
+    peers: Arc<Mutex<HashMap<ResponderId, PeerHealth>>>,
+}
+
+impl PeerHealthTracker {
+    /// Start tracking a peer. Existing health data is kept.
+    pub fn add_peer(&self, responder_id: &ResponderId) {
+        self.update(responder_id, |_| {});
+    }
+
+    /// Stop tracking a peer.
+    pub fn remove_peer(&self, responder_id: &ResponderId) {
+        self.peers
+            .lock()
+            .expect("mutex poisoned")
+            .remove(responder_id);
+    }
+
+    /// Record a successful delivery to a peer.
+    pub fn record_success(&self, responder_id: &ResponderId, latency: Duration) {
+        counters::OP_COUNTERS
+            .peer_counter("send_success", &responder_id.to_string())
+            .inc();
+        self.update(responder_id, |health| health.record_success(latency));
+    }
+
+    /// Record a failed delivery to a peer.
+    pub fn record_failure(&self, responder_id: &ResponderId, err: &Error) {
+        counters::OP_COUNTERS
+            .peer_counter("send_failure", &responder_id.to_string())
+            .inc();
+        self.update(responder_id, |health| health.record_failure(err));
+    }
+
+    /// Record that a tx proposal was not sent to a peer.
+    pub fn record_dropped_propose_tx(&self, responder_id: &ResponderId) {
+        counters::OP_COUNTERS
+            .peer_counter("dropped_propose_tx", &responder_id.to_string())
+            .inc();
+        self.update(responder_id, |health| health.dropped_propose_txs += 1);
+    }
+
+    /// Health of a single peer.
+    pub fn get(&self, responder_id: &ResponderId) -> PeerHealth {
+        self.peers
+            .lock()
+            .expect("mutex poisoned")
+            .get(responder_id)
+            .cloned()
+            .unwrap_or_else(|| PeerHealth::new(responder_id.clone()))
+    }
+
+    /// Health of all tracked peers, sorted by responder id.
+    pub fn snapshot(&self) -> Vec<PeerHealth> {
+        let mut peers: Vec<_> = self
+            .peers
+            .lock()
+            .expect("mutex poisoned")
+            .values()
+            .cloned()
+            .collect();
+        peers.sort_by(|a, b| a.responder_id.cmp(&b.responder_id));
+        peers
+    }
+
+    fn update(&self, responder_id: &ResponderId, f: impl FnOnce(&mut PeerHealth)) {
+        let mut peers = self.peers.lock().expect("mutex poisoned");
+        let health = peers
+            .entry(responder_id.clone())
+            .or_insert_with(|| PeerHealth::new(responder_id.clone()));
+        f(health);
+        update_gauges(health);
+    }
+}
+
+fn update_gauges(health: &PeerHealth) {
+    let peer_name = health.responder_id.to_string();
+    counters::OP_COUNTERS
+        .peer_gauge("latency_ms", &peer_name)
+        .set(
+            health
+                .latency
+                .map_or(0, |latency| latency.as_millis() as i64),
+        );
+    counters::OP_COUNTERS
+        .peer_gauge("consecutive_failures", &peer_name)
+        .set(health.consecutive_failures as i64);
+    counters::OP_COUNTERS
+        .peer_gauge("error_rate_percent", &peer_name)
+        .set((health.error_rate * 100.0) as i64);
+    counters::OP_COUNTERS
+        .peer_gauge("attested", &peer_name)
+        .set(health.attested.unwrap_or(false) as i64);
+    counters::OP_COUNTERS
+        .peer_gauge("healthy", &peer_name)
+        .set((health.status() != PeerHealthStatus::Unhealthy) as i64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_follows_delivery_results() {
+        let tracker = PeerHealthTracker::default();
+        let peer = ResponderId("peer:443".to_owned());
+
+        tracker.add_peer(&peer);
+        assert_eq!(tracker.get(&peer).status(), PeerHealthStatus::Unknown);
+
+        tracker.record_success(&peer, Duration::from_millis(10));
+        let health = tracker.get(&peer);
+        assert_eq!(health.status(), PeerHealthStatus::Healthy);
+        assert_eq!(health.latency, Some(Duration::from_millis(10)));
+        assert_eq!(health.attested, Some(true));
+        assert!(health.last_success.is_some());
+
+        tracker.record_failure(&peer, &Error::Other);
+        assert_eq!(tracker.get(&peer).status(), PeerHealthStatus::Degraded);
+
+        for _ in 1..UNHEALTHY_CONSECUTIVE_FAILURES {
+            tracker.record_failure(&peer, &Error::Other);
+        }
+        let health = tracker.get(&peer);
+        assert_eq!(health.status(), PeerHealthStatus::Unhealthy);
+        assert_eq!(health.failures, UNHEALTHY_CONSECUTIVE_FAILURES as u64);
+        assert_eq!(health.last_error, Some(Error::Other.to_string()));
+
+        // A single success clears consecutive failures, but the error rate is
+        // still high enough to keep the peer degraded.
+        tracker.record_success(&peer, Duration::from_millis(10));
+        assert_eq!(tracker.get(&peer).status(), PeerHealthStatus::Degraded);
+
+        tracker.remove_peer(&peer);
+        assert!(tracker.snapshot().is_empty());
+    }
+}
@@ -7,12 +7,13 @@ use crate::{
     error::RetryResult,
     traits::{ConsensusConnection, RetryableConsensusConnection},
 };
+use mc_blockchain_types::{BlockData, BlockIndex};
 use mc_common::{NodeID, ResponderId};
 use mc_connection::{impl_sync_connection_retry, SyncConnection};
 use mc_consensus_api::consensus_peer::ConsensusMsgResponse;
 use mc_consensus_enclave_api::{TxContext, WellFormedEncryptedTx};
 use mc_transaction_core::tx::TxHash;
-use std::time::Duration;
+use std::{ops::Range, time::Duration};
 
 /// Blanket implementation of RetryableConsensusConnection for SyncConnection
 /// objects which own a ConsensusConnection.
@@ -76,4 +77,18 @@ impl<CC: ConsensusConnection> RetryableConsensusConnection for SyncConnection<CC
             retry_iterator
         )
     }
+
+    fn fetch_block_data(
+        &self,
+        range: Range<BlockIndex>,
+        retry_iterator: impl IntoIterator<Item = Duration>,
+    ) -> RetryResult<Vec<BlockData>> {
+        impl_sync_connection_retry!(
+            self.write(),
+            self.logger(),
+            fetch_block_data,
+            retry_iterator,
+            range.clone()
+        )
+    }
 }
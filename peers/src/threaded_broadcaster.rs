@@ -5,6 +5,7 @@
 use crate::{
     consensus_msg::ConsensusMsg,
     error::Error,
+    peer_health::{PeerHealth, PeerHealthTracker},
     threaded_broadcaster_retry::{FibonacciRetryPolicy, IteratorWithDeadlineExt, RetryPolicy},
     traits::{ConsensusConnection, RetryableConsensusConnection},
     Broadcast,
@@ -31,6 +32,11 @@ use std::{
 /// Number of messages to keep track of.
 const HISTORY_SIZE: usize = 10000;
 
+/// Maximum number of tx proposals queued for a single peer. Proposals beyond
+/// this are dropped, so that a slow link does not accumulate an unbounded
+/// backlog. Consensus messages are not subject to this limit.
+pub const MAX_QUEUED_PROPOSE_TX_MSGS: usize = 10000;
+
 /// `ThreadedBroadcaster` is used to broadcast consensus messages and
 /// transactions to a list of peers.
 ///
@@ -41,6 +47,11 @@ const HISTORY_SIZE: usize = 10000;
 /// other peers. A thread is created per each peer to handle message delivery.
 /// This means a non-responsive peer does not slow message delivery to other
 /// peers, and does not block the caller of `broadcast_consensus_msg`.
+///
+/// Each peer thread delivers consensus messages ahead of any queued tx
+/// proposals, and records the outcome of every delivery in a shared
+/// [PeerHealthTracker]. The retry policy may use that to back off peers that
+/// are failing.
 pub struct ThreadedBroadcaster<RP: RetryPolicy = FibonacciRetryPolicy> {
     /// List of peers to communicate with.
     peer_threads: Vec<PeerThread>,
@@ -53,6 +64,9 @@ pub struct ThreadedBroadcaster<RP: RetryPolicy = FibonacciRetryPolicy> {
     /// Hashes of transactions we've already broadcasted.
     seen_tx_hashes: LruCache<TxHash, ()>,
 
+    /// Health of each peer, updated by the peer threads.
+    health: PeerHealthTracker,
+
     // Retry policy.
     retry_policy: RP,
 
@@ -66,30 +80,18 @@ impl<RP: RetryPolicy> ThreadedBroadcaster<RP> {
         retry_policy: &RP,
         logger: Logger,
     ) -> Self {
+        let health = PeerHealthTracker::default();
         let peer_threads: Vec<PeerThread> = manager
             .conns()
             .into_iter()
-            .filter(|conn| {
-                conn.uri()
-                    .get_param("broadcast-consensus-msgs")
-                    .unwrap_or_else(|| "1".to_string())
-                    == "1"
-            })
-            .map(|conn| {
-                let peer_name = conn.to_string();
-                PeerThread::new(
-                    conn,
-                    retry_policy,
-                    logger.new(o!(
-                        "mc.peers.peer_name" => peer_name,
-                    )),
-                )
-            })
+            .filter(Self::should_broadcast_to)
+            .map(|conn| Self::spawn_peer_thread(conn, retry_policy, &health, &logger))
             .collect();
         Self {
             peer_threads,
             seen_msg_hashes: LruCache::new(HISTORY_SIZE),
             seen_tx_hashes: LruCache::new(HISTORY_SIZE),
+            health,
             retry_policy: retry_policy.clone(),
             logger,
         }
@@ -101,6 +103,95 @@ impl<RP: RetryPolicy> ThreadedBroadcaster<RP> {
         }
     }
 
+    /// Start broadcasting to a peer, replacing any existing peer with the same
+    /// responder id. The health of a replaced peer is kept. Peers whose URI
+    /// opts out of broadcasts are ignored.
+    pub fn add_peer<CC: ConsensusConnection + 'static>(&mut self, conn: SyncConnection<CC>) {
+        if !Self::should_broadcast_to(&conn) {
+            return;
+        }
+
+        self.stop_peer_threads(&conn.remote_responder_id());
+        let peer_thread =
+            Self::spawn_peer_thread(conn, &self.retry_policy, &self.health, &self.logger);
+        log::info!(
+            self.logger,
+            "Started broadcasting to {}",
+            peer_thread.responder_id()
+        );
+        self.peer_threads.push(peer_thread);
+    }
+
+    /// Stop broadcasting to a peer, and forget its health. Returns true if the
+    /// peer was known.
+    pub fn remove_peer(&mut self, responder_id: &ResponderId) -> bool {
+        let removed = self.stop_peer_threads(responder_id);
+        self.health.remove_peer(responder_id);
+        removed
+    }
+
+    /// Stop the threads broadcasting to a peer. Returns true if there were
+    /// any.
+    fn stop_peer_threads(&mut self, responder_id: &ResponderId) -> bool {
+        let (mut removed, retained): (Vec<_>, Vec<_>) = self
+            .peer_threads
+            .drain(..)
+            .partition(|peer_thread| peer_thread.responder_id() == responder_id);
+        self.peer_threads = retained;
+
+        for peer_thread in removed.iter_mut() {
+            log::info!(self.logger, "Stopped broadcasting to {}", responder_id);
+            peer_thread.stop();
+        }
+
+        !removed.is_empty()
+    }
+
+    /// Responder ids of the peers we are broadcasting to.
+    pub fn responder_ids(&self) -> Vec<ResponderId> {
+        self.peer_threads
+            .iter()
+            .map(|peer_thread| peer_thread.responder_id().clone())
+            .collect()
+    }
+
+    /// Health of the peers we are broadcasting to.
+    pub fn peer_health(&self) -> Vec<PeerHealth> {
+        self.health.snapshot()
+    }
+
+    /// A handle for reading peer health without holding on to the broadcaster.
+    pub fn health_tracker(&self) -> PeerHealthTracker {
+        self.health.clone()
+    }
+
+    /// Peers can opt out of receiving broadcasts with the
+    /// `broadcast-consensus-msgs=0` URI parameter.
+    fn should_broadcast_to<CC: ConsensusConnection + 'static>(conn: &SyncConnection<CC>) -> bool {
+        conn.uri()
+            .get_param("broadcast-consensus-msgs")
+            .unwrap_or_else(|| "1".to_string())
+            == "1"
+    }
+
+    fn spawn_peer_thread<CC: ConsensusConnection + 'static>(
+        conn: SyncConnection<CC>,
+        retry_policy: &RP,
+        health: &PeerHealthTracker,
+        logger: &Logger,
+    ) -> PeerThread {
+        let peer_name = conn.to_string();
+        health.add_peer(&conn.remote_responder_id());
+        PeerThread::new(
+            conn,
+            retry_policy,
+            health.clone(),
+            logger.new(o!(
+                "mc.peers.peer_name" => peer_name,
+            )),
+        )
+    }
+
     /// Broadcasts a propose transaction message.
     ///
     /// # Arguments
@@ -257,7 +348,14 @@ enum ThreadMsg {
 /// A single peer thread.
 struct PeerThread {
     responder_id: ResponderId,
+
+    /// Consensus messages and control messages, always handled first.
+    priority_sender: crossbeam_channel::Sender<ThreadMsg>,
+
+    /// Tx proposals and barriers.
     sender: crossbeam_channel::Sender<ThreadMsg>,
+
+    health: PeerHealthTracker,
     join_handle: Option<thread::JoinHandle<()>>,
 }
 
@@ -265,25 +363,37 @@ impl PeerThread {
     pub fn new<CC: ConsensusConnection + 'static, RP: RetryPolicy>(
         conn: SyncConnection<CC>,
         retry_policy: &RP,
+        health: PeerHealthTracker,
         logger: Logger,
     ) -> Self {
-        let (sender, receiver) = crossbeam_channel::unbounded();
+        let (priority_sender, priority_receiver) = crossbeam_channel::unbounded();
+        let (sender, receiver) = crossbeam_channel::bounded(MAX_QUEUED_PROPOSE_TX_MSGS);
 
         let responder_id = conn.remote_responder_id();
 
         let retry_policy = retry_policy.clone();
+        let thread_health = health.clone();
 
         let join_handle = Some(
             thread::Builder::new()
                 .name(format!("{conn}"))
                 .spawn(move || {
-                    Self::thread_entrypoint(conn, retry_policy, receiver, logger);
+                    Self::thread_entrypoint(
+                        conn,
+                        retry_policy,
+                        priority_receiver,
+                        receiver,
+                        thread_health,
+                        logger,
+                    );
                 })
                 .expect("failed spawning peer thread"),
         );
         Self {
             responder_id,
+            priority_sender,
             sender,
+            health,
             join_handle,
         }
     }
@@ -297,7 +407,7 @@ impl PeerThread {
         msg: Arc<ConsensusMsg>,
         deadline: Instant,
     ) -> Result<(), Error> {
-        self.sender
+        self.priority_sender
             .send(ThreadMsg::HandleConsensusMsg { msg, deadline })
             .map_err(|_err| Error::ChannelSend)
     }
@@ -308,13 +418,19 @@ impl PeerThread {
         origin_node: Arc<NodeID>,
         deadline: Instant,
     ) -> Result<(), Error> {
-        self.sender
-            .send(ThreadMsg::HandleProposeTx {
-                encrypted_tx,
-                origin_node,
-                deadline,
-            })
-            .map_err(|_err| Error::ChannelSend)
+        match self.sender.try_send(ThreadMsg::HandleProposeTx {
+            encrypted_tx,
+            origin_node,
+            deadline,
+        }) {
+            Ok(()) => Ok(()),
+            // The link is saturated; drop the proposal rather than delaying consensus.
+            Err(crossbeam_channel::TrySendError::Full(_)) => {
+                self.health.record_dropped_propose_tx(&self.responder_id);
+                Ok(())
+            }
+            Err(crossbeam_channel::TrySendError::Disconnected(_)) => Err(Error::ChannelSend),
+        }
     }
 
     /// Tests helper: wait until a barrier message is processed (indicating
@@ -341,7 +457,7 @@ impl PeerThread {
 
     pub fn stop(&mut self) {
         if let Some(join_handle) = self.join_handle.take() {
-            let _ = self.sender.send(ThreadMsg::StopTrigger);
+            let _ = self.priority_sender.send(ThreadMsg::StopTrigger);
             let _ = join_handle.join();
         }
     }
@@ -349,15 +465,31 @@ impl PeerThread {
     fn thread_entrypoint<CC: ConsensusConnection + 'static, RP: RetryPolicy>(
         conn: SyncConnection<CC>,
         retry_policy: RP,
+        priority_receiver: crossbeam_channel::Receiver<ThreadMsg>,
         receiver: crossbeam_channel::Receiver<ThreadMsg>,
+        health: PeerHealthTracker,
         logger: Logger,
     ) {
         loop {
-            match receiver.recv() {
+            // Drain priority messages before looking at tx proposals.
+            let next_msg = match priority_receiver.try_recv() {
+                Ok(msg) => Ok(msg),
+                Err(_) => crossbeam_channel::select! {
+                    recv(priority_receiver) -> msg => msg,
+                    recv(receiver) -> msg => msg,
+                },
+            };
+
+            match next_msg {
                 Ok(msg) => match msg {
-                    ThreadMsg::HandleConsensusMsg { msg, deadline } => {
-                        Self::do_send_consensus_msg(&conn, &retry_policy, msg, deadline, &logger)
-                    }
+                    ThreadMsg::HandleConsensusMsg { msg, deadline } => Self::do_send_consensus_msg(
+                        &conn,
+                        &retry_policy,
+                        &health,
+                        msg,
+                        deadline,
+                        &logger,
+                    ),
                     ThreadMsg::HandleProposeTx {
                         encrypted_tx,
                         origin_node,
@@ -365,6 +497,7 @@ impl PeerThread {
                     } => Self::do_handle_propose_tx_msg(
                         &conn,
                         &retry_policy,
+                        &health,
                         &encrypted_tx,
                         &origin_node,
                         deadline,
@@ -388,6 +521,7 @@ impl PeerThread {
     fn do_send_consensus_msg<CC: ConsensusConnection + 'static, RP: RetryPolicy>(
         conn: &SyncConnection<CC>,
         retry_policy: &RP,
+        health: &PeerHealthTracker,
         arc_msg: Arc<ConsensusMsg>,
         deadline: Instant,
         logger: &Logger,
@@ -396,9 +530,19 @@ impl PeerThread {
             return;
         }
 
-        let retry_iterator = retry_policy.get_delay_iterator().with_deadline(deadline);
+        let responder_id = conn.remote_responder_id();
+        let retry_iterator = retry_policy
+            .get_delay_iterator_for_peer(&health.get(&responder_id))
+            .with_deadline(deadline);
+
+        let started_at = Instant::now();
+        let result = conn.send_consensus_msg(&arc_msg, retry_iterator);
+        match &result {
+            Ok(_) => health.record_success(&responder_id, started_at.elapsed()),
+            Err(err) => health.record_failure(&responder_id, &err.error),
+        }
 
-        match conn.send_consensus_msg(&arc_msg, retry_iterator) {
+        match result {
             Ok(resp) => match resp.get_result() {
                 ConsensusMsgResult::Ok => {}
                 ConsensusMsgResult::UnknownPeer => log::info!(
@@ -421,6 +565,7 @@ impl PeerThread {
     fn do_handle_propose_tx_msg<CC: ConsensusConnection + 'static, RP: RetryPolicy>(
         conn: &SyncConnection<CC>,
         retry_policy: &RP,
+        health: &PeerHealthTracker,
         encrypted_tx: &WellFormedEncryptedTx,
         origin_node: &NodeID,
         deadline: Instant,
@@ -430,9 +575,25 @@ impl PeerThread {
             return;
         }
 
-        let retry_iterator = retry_policy.get_delay_iterator().with_deadline(deadline);
+        let responder_id = conn.remote_responder_id();
+        let peer_health = health.get(&responder_id);
+        if !retry_policy.should_propose_txs_to(&peer_health) {
+            health.record_dropped_propose_tx(&responder_id);
+            return;
+        }
+
+        let retry_iterator = retry_policy
+            .get_delay_iterator_for_peer(&peer_health)
+            .with_deadline(deadline);
+
+        let started_at = Instant::now();
+        let result = conn.send_propose_tx(encrypted_tx, origin_node, retry_iterator);
+        match &result {
+            Ok(()) => health.record_success(&responder_id, started_at.elapsed()),
+            Err(err) => health.record_failure(&responder_id, &err.error),
+        }
 
-        if let Err(err) = conn.send_propose_tx(encrypted_tx, origin_node, retry_iterator) {
+        if let Err(err) = result {
             log::error!(
                 logger,
                 "failed broadcasting propose tx to {}: {:?}",
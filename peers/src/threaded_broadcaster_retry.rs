@@ -1,5 +1,6 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
+use crate::peer_health::{PeerHealth, PeerHealthStatus};
 use retry::delay::{jitter, Fibonacci};
 use std::time::{Duration, Instant};
 
@@ -12,11 +13,29 @@ pub const DEFAULT_RETRY_INITIAL_DELAY: Duration = Duration::from_secs(1);
 /// Maximal message age before we do not attempt to deliver it.
 pub const DEFAULT_MAX_MESSAGE_AGE: Duration = Duration::from_secs(30);
 
+/// Upper bound on the initial retry delay used for degraded peers.
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(8);
+
 /// An abstraction of retry parameters used by `ThreadedBroadcaster`.
 pub trait RetryPolicy: Clone + Send + 'static {
     /// Return an iterator to be used by `retry::retry()`.
     fn get_delay_iterator(&self) -> Box<dyn Iterator<Item = Duration>>;
 
+    /// Return an iterator to be used by `retry::retry()` when delivering to a
+    /// peer with the given health. Defaults to ignoring peer health.
+    fn get_delay_iterator_for_peer(
+        &self,
+        _health: &PeerHealth,
+    ) -> Box<dyn Iterator<Item = Duration>> {
+        self.get_delay_iterator()
+    }
+
+    /// Whether tx proposals should be sent to a peer with the given health.
+    /// Consensus messages are always sent. Defaults to true.
+    fn should_propose_txs_to(&self, _health: &PeerHealth) -> bool {
+        true
+    }
+
     /// Maximal message age to broadcast.
     fn get_max_message_age(&self) -> Duration;
 }
@@ -77,6 +96,76 @@ impl FibonacciRetryPolicy {
     }
 }
 
+/// A retry policy that adapts to the health of each peer.
+///
+/// Healthy peers are retried like [FibonacciRetryPolicy]. For degraded peers
+/// the initial delay doubles with every consecutive failure, up to
+/// `max_backoff`. Unhealthy peers get a single attempt per message and are not
+/// sent tx proposals, so that they do not hold up consensus traffic.
+#[derive(Clone)]
+pub struct AdaptiveRetryPolicy {
+    /// Policy used for healthy peers.
+    base: FibonacciRetryPolicy,
+
+    /// Upper bound on the initial delay used for degraded peers.
+    max_backoff: Duration,
+}
+impl Default for AdaptiveRetryPolicy {
+    fn default() -> Self {
+        Self {
+            base: FibonacciRetryPolicy::default(),
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+}
+impl RetryPolicy for AdaptiveRetryPolicy {
+    fn get_delay_iterator(&self) -> Box<dyn Iterator<Item = Duration>> {
+        self.base.get_delay_iterator()
+    }
+
+    fn get_max_message_age(&self) -> Duration {
+        self.base.get_max_message_age()
+    }
+
+    fn get_delay_iterator_for_peer(
+        &self,
+        health: &PeerHealth,
+    ) -> Box<dyn Iterator<Item = Duration>> {
+        match health.status() {
+            PeerHealthStatus::Unknown | PeerHealthStatus::Healthy => self.get_delay_iterator(),
+            PeerHealthStatus::Degraded => {
+                let factor = 1u32 << health.consecutive_failures.min(16);
+                let initial_delay = self
+                    .base
+                    .initial_delay
+                    .saturating_mul(factor)
+                    .min(self.max_backoff);
+                self.base
+                    .clone()
+                    .initial_delay(initial_delay)
+                    .get_delay_iterator()
+            }
+            PeerHealthStatus::Unhealthy => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn should_propose_txs_to(&self, health: &PeerHealth) -> bool {
+        health.status() != PeerHealthStatus::Unhealthy
+    }
+}
+impl AdaptiveRetryPolicy {
+    /// The policy used for healthy peers.
+    pub fn base(&mut self, val: FibonacciRetryPolicy) -> &mut Self {
+        self.base = val;
+        self
+    }
+
+    pub fn max_backoff(&mut self, val: Duration) -> &mut Self {
+        self.max_backoff = val;
+        self
+    }
+}
+
 /// An `Iterator` extension that adds the `.with_deadline()` method,
 /// forcing the `Iterator` to to terminate if a given deadline is exceeded.
 pub struct WithDeadline<I> {
@@ -116,3 +205,53 @@ pub trait IteratorWithDeadlineExt: Iterator {
 }
 
 impl<I: Iterator> IteratorWithDeadlineExt for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::Error, peer_health::PeerHealthTracker};
+    use mc_common::ResponderId;
+
+    #[test]
+    fn adaptive_policy_backs_off_failing_peers() {
+        let mut base = FibonacciRetryPolicy::default();
+        base.initial_delay(Duration::from_millis(100))
+            .max_attempts(3);
+        let mut policy = AdaptiveRetryPolicy::default();
+        policy.base(base).max_backoff(Duration::from_millis(300));
+
+        let tracker = PeerHealthTracker::default();
+        let peer = ResponderId("peer:443".to_owned());
+
+        // Healthy peers use the base policy.
+        tracker.record_success(&peer, Duration::from_millis(1));
+        assert_eq!(
+            policy
+                .get_delay_iterator_for_peer(&tracker.get(&peer))
+                .count(),
+            2
+        );
+        assert!(policy.should_propose_txs_to(&tracker.get(&peer)));
+
+        // Degraded peers are still retried, with longer delays.
+        tracker.record_failure(&peer, &Error::Other);
+        tracker.record_failure(&peer, &Error::Other);
+        assert_eq!(
+            policy
+                .get_delay_iterator_for_peer(&tracker.get(&peer))
+                .count(),
+            2
+        );
+        assert!(policy.should_propose_txs_to(&tracker.get(&peer)));
+
+        // Unhealthy peers get a single attempt and no tx proposals.
+        tracker.record_failure(&peer, &Error::Other);
+        assert_eq!(
+            policy
+                .get_delay_iterator_for_peer(&tracker.get(&peer))
+                .count(),
+            0
+        );
+        assert!(!policy.should_propose_txs_to(&tracker.get(&peer)));
+    }
+}
@@ -6,12 +6,13 @@ use crate::{
     error::{Result, RetryResult},
     ConsensusMsg,
 };
+use mc_blockchain_types::{BlockData, BlockIndex};
 use mc_common::{NodeID, ResponderId};
 use mc_connection::Connection;
 use mc_consensus_api::consensus_peer::ConsensusMsgResponse;
 use mc_consensus_enclave_api::{TxContext, WellFormedEncryptedTx};
 use mc_transaction_core::tx::TxHash;
-use std::time::Duration;
+use std::{ops::Range, time::Duration};
 
 /// A trait which describes a connection from one consensus node to another.
 pub trait ConsensusConnection: Connection {
@@ -36,6 +37,10 @@ pub trait ConsensusConnection: Connection {
 
     /// Retrieve the most recent consensus message sent by this peer.
     fn fetch_latest_msg(&mut self) -> Result<Option<ConsensusMsg>>;
+
+    /// Retrieve the full data of a range of blocks. The peer may return fewer
+    /// blocks than requested.
+    fn fetch_block_data(&mut self, range: Range<BlockIndex>) -> Result<Vec<BlockData>>;
 }
 
 /// Retriable versions of the ConsensusConnection methods
@@ -68,4 +73,10 @@ pub trait RetryableConsensusConnection {
         &self,
         retry_iterator: impl IntoIterator<Item = Duration>,
     ) -> RetryResult<Option<ConsensusMsg>>;
+
+    fn fetch_block_data(
+        &self,
+        range: Range<BlockIndex>,
+        retry_iterator: impl IntoIterator<Item = Duration>,
+    ) -> RetryResult<Vec<BlockData>>;
 }
@@ -4,7 +4,7 @@
 
 pub use mc_consensus_scp::test_utils::{test_node_id, test_node_id_and_signer};
 
-use mc_blockchain_types::{Block, BlockID, BlockIndex};
+use mc_blockchain_types::{Block, BlockData, BlockID, BlockIndex};
 use mc_common::{NodeID, ResponderId};
 use mc_connection::{
     BlockInfo, BlockchainConnection, Connection, Error as ConnectionError,
@@ -219,6 +219,20 @@ impl<L: Ledger + Sync> ConsensusConnection for MockPeerConnection<L> {
     fn fetch_latest_msg(&mut self) -> PeerResult<Option<ConsensusMsg>> {
         unimplemented!()
     }
+
+    fn fetch_block_data(&mut self, range: Range<BlockIndex>) -> PeerResult<Vec<BlockData>> {
+        thread::sleep(Duration::from_millis(self.latency_millis));
+
+        let num_blocks = self.ledger.num_blocks().unwrap();
+        if range.start >= num_blocks {
+            return Err(PeerError::NotFound);
+        }
+
+        (range.start..min(range.end, num_blocks))
+            .map(|block_index| self.ledger.get_block_data(block_index))
+            .collect::<Result<Vec<BlockData>, _>>()
+            .or(Err(PeerError::NotFound))
+    }
 }
 pub fn create_consensus_msg(
     ledger: &impl Ledger,
@@ -380,9 +394,10 @@ mod threaded_broadcaster_tests {
     use mc_connection::ConnectionManager;
     use mc_ledger_db::test_utils::get_mock_ledger;
     use mc_peers::{
-        Broadcast, ThreadedBroadcaster,
+        Broadcast, PeerHealthStatus, ThreadedBroadcaster,
+        ThreadedBroadcasterAdaptiveRetryPolicy as AdaptiveRetryPolicy,
         ThreadedBroadcasterFibonacciRetryPolicy as FibonacciRetryPolicy,
-        DEFAULT_RETRY_MAX_ATTEMPTS,
+        DEFAULT_RETRY_MAX_ATTEMPTS, UNHEALTHY_CONSECUTIVE_FAILURES,
     };
     use mc_util_from_random::FromRandom;
     use rand::SeedableRng;
@@ -652,4 +667,157 @@ mod threaded_broadcaster_tests {
             assert_eq!(peer3.state().send_consensus_msg_call_count, 2);
         }
     }
+
+    #[test_with_logger]
+    // Peers added to or removed from a running broadcaster should start or stop
+    // receiving messages.
+    fn test_add_and_remove_peer(logger: Logger) {
+        let (local_node_id, _) = test_node_id_and_signer(1);
+        let node2_uri = test_peer_uri(2);
+        let node2 = NodeID::from(&node2_uri);
+        let node3_uri = test_peer_uri(3);
+        let node3 = NodeID::from(&node3_uri);
+
+        let quorum_set = QuorumSet::new_with_node_ids(2, vec![node2.clone(), node3]);
+        let ledger = get_mock_ledger(1);
+        let peer2 = MockPeerConnection::new(node2_uri, local_node_id.clone(), ledger.clone(), 0);
+        let peer3 = MockPeerConnection::new(node3_uri, local_node_id.clone(), ledger.clone(), 0);
+
+        let peer_manager = ConnectionManager::new(vec![peer2.clone()], logger.clone());
+
+        let mut broadcaster =
+            ThreadedBroadcaster::new(&peer_manager, &FibonacciRetryPolicy::default(), logger);
+        assert_eq!(
+            broadcaster.responder_ids(),
+            vec![node2.responder_id.clone()]
+        );
+
+        let mut seeded_rng: FixedRng = SeedableRng::from_seed([1u8; 32]);
+        let local_signer_key = Ed25519Pair::from_random(&mut seeded_rng);
+
+        // Adding peer3 should make it receive subsequent messages.
+        let conn3 = peer_manager.add_conn(peer3.clone());
+        broadcaster.add_peer(conn3);
+        {
+            let msg1 = create_consensus_msg(
+                &ledger,
+                local_node_id.clone(),
+                quorum_set.clone(),
+                1,
+                "msg1",
+                &local_signer_key,
+            );
+            broadcaster.broadcast_consensus_msg(&msg1, msg1.issuer_responder_id());
+            broadcaster.barrier();
+
+            assert_eq!(peer2.msgs().len(), 1);
+            assert_eq!(peer3.msgs().len(), 1);
+        }
+
+        // Removing peer2 should stop it from receiving messages.
+        assert!(broadcaster.remove_peer(&node2.responder_id));
+        assert!(!broadcaster.remove_peer(&node2.responder_id));
+        assert!(peer_manager.remove_conn(&node2.responder_id).is_some());
+        assert_eq!(peer_manager.len(), 1);
+        {
+            let msg2 = create_consensus_msg(
+                &ledger,
+                local_node_id,
+                quorum_set,
+                1,
+                "msg2",
+                &local_signer_key,
+            );
+            broadcaster.broadcast_consensus_msg(&msg2, msg2.issuer_responder_id());
+            broadcaster.barrier();
+
+            assert_eq!(peer2.msgs().len(), 1);
+            assert_eq!(peer3.msgs().len(), 2);
+        }
+    }
+
+    #[test_with_logger]
+    // Delivery results should be reflected in peer health, and the adaptive
+    // policy should stop retrying a peer once it is unhealthy.
+    fn test_peer_health_and_adaptive_retry(logger: Logger) {
+        let (local_node_id, _) = test_node_id_and_signer(1);
+        let node2_uri = test_peer_uri(2);
+        let node2 = NodeID::from(&node2_uri);
+        let node3_uri = test_peer_uri(3);
+        let node3 = NodeID::from(&node3_uri);
+
+        let ledger = get_mock_ledger(1);
+
+        let quorum_set = QuorumSet::new_with_node_ids(1, vec![node2.clone(), node3.clone()]);
+        let mut peer2 =
+            MockPeerConnection::new(node2_uri, local_node_id.clone(), ledger.clone(), 0);
+        let peer3 = MockPeerConnection::new(node3_uri, local_node_id.clone(), ledger.clone(), 0);
+
+        let peer_manager =
+            ConnectionManager::new(vec![peer2.clone(), peer3.clone()], logger.clone());
+
+        let mut base = FibonacciRetryPolicy::default();
+        base.initial_delay(Duration::from_millis(10));
+        let mut retry_policy = AdaptiveRetryPolicy::default();
+        retry_policy
+            .base(base)
+            .max_backoff(Duration::from_millis(20));
+
+        let mut broadcaster = ThreadedBroadcaster::new(&peer_manager, &retry_policy, logger);
+        assert!(broadcaster
+            .peer_health()
+            .iter()
+            .all(|health| health.status() == PeerHealthStatus::Unknown));
+
+        peer2.set_send_consensus_msg_should_error_count(100);
+
+        let mut seeded_rng: FixedRng = SeedableRng::from_seed([1u8; 32]);
+        let local_signer_key = Ed25519Pair::from_random(&mut seeded_rng);
+
+        let mut broadcast = |name: &str| {
+            let msg = create_consensus_msg(
+                &ledger,
+                local_node_id.clone(),
+                quorum_set.clone(),
+                1,
+                name,
+                &local_signer_key,
+            );
+            broadcaster.broadcast_consensus_msg(&msg, msg.issuer_responder_id());
+            broadcaster.barrier();
+        };
+
+        // Every message is retried until peer2 becomes unhealthy.
+        for i in 0..UNHEALTHY_CONSECUTIVE_FAILURES {
+            broadcast(&format!("msg{i}"));
+        }
+        let expected_calls = DEFAULT_RETRY_MAX_ATTEMPTS * UNHEALTHY_CONSECUTIVE_FAILURES as usize;
+        assert_eq!(peer2.state().send_consensus_msg_call_count, expected_calls);
+
+        // After that, only a single attempt is made per message.
+        broadcast("last");
+        assert_eq!(
+            peer2.state().send_consensus_msg_call_count,
+            expected_calls + 1
+        );
+
+        let health = broadcaster.peer_health();
+        assert_eq!(health.len(), 2);
+        assert_eq!(health[0].responder_id, node2.responder_id);
+        assert_eq!(health[0].status(), PeerHealthStatus::Unhealthy);
+        assert_eq!(health[0].successes, 0);
+        assert_eq!(
+            health[0].failures,
+            UNHEALTHY_CONSECUTIVE_FAILURES as u64 + 1
+        );
+        assert!(health[0].last_error.is_some());
+
+        assert_eq!(health[1].responder_id, node3.responder_id);
+        assert_eq!(health[1].status(), PeerHealthStatus::Healthy);
+        assert_eq!(
+            health[1].successes,
+            UNHEALTHY_CONSECUTIVE_FAILURES as u64 + 1
+        );
+        assert!(health[1].last_success.is_some());
+    }
 }
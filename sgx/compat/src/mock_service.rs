@@ -6,12 +6,19 @@ use mc_sgx_types::{
     sgx_report_data_t, sgx_report_t, sgx_sealed_data_t, sgx_status_t, sgx_target_info_t,
 };
 
-/// Get a report (default)
+/// Get a report (default, apart from the given report data)
+///
+/// The report data is kept so that a quote of the report can be checked
+/// against the report data contents it was made for.
 pub fn report(
     _target_info: Option<&sgx_target_info_t>,
-    _report_data: Option<&sgx_report_data_t>,
+    report_data: Option<&sgx_report_data_t>,
 ) -> Result<sgx_report_t, sgx_status_t> {
-    Ok(Default::default())
+    let mut report = sgx_report_t::default();
+    if let Some(report_data) = report_data {
+        report.body.report_data.d = report_data.d;
+    }
+    Ok(report)
 }
 
 /// Verify report (ok)
@@ -7,6 +7,7 @@ use mc_transaction_core::{
     ring_ct::Error as RingCtError, AmountError, NewMemoError, NewTxError, TokenId,
     TxOutConversionError,
 };
+use mc_transaction_extra::SignedContingentInputError;
 
 /// An error that can occur when using the TransactionBuilder
 #[derive(Debug, Display)]
@@ -135,6 +136,46 @@ impl From<TxOutConversionError> for TxBuilderError {
     }
 }
 
+/// An error that can occur when settling a batch of signed contingent inputs
+/// with the TransactionBuilder.
+///
+/// Errors about a particular signed contingent input carry its index in the
+/// batch.
+#[derive(Debug, Display)]
+pub enum SettlementError {
+    /// Signed contingent input #{0}: {1}
+    Sci(usize, SignedContingentInputError),
+
+    /// Signed contingent input #{0}: partial fill value {1} is not in [{2},
+    /// {3}]
+    InvalidPartialFillValue(usize, u64, u64, u64),
+
+    /// Signed contingent input #{0} can't be partially filled (value {1})
+    NotPartialFill(usize, u64),
+
+    /// Signed contingent input #{0} has the same key image as #{1}
+    DuplicateKeyImage(usize, usize),
+
+    /// Insufficient inputs of token {0}: missing {1}
+    InsufficientInputs(TokenId, u128),
+
+    /// Balance of token {0} overflowed
+    BalanceOverflow(TokenId),
+
+    /// Tokens {0} and {1} both have change, but a settlement only creates one
+    /// change output
+    MultipleChangeTokens(TokenId, TokenId),
+
+    /// Transaction builder: {0}
+    TxBuilder(TxBuilderError),
+}
+
+impl From<TxBuilderError> for SettlementError {
+    fn from(src: TxBuilderError) -> Self {
+        SettlementError::TxBuilder(src)
+    }
+}
+
 /// An error that can occur when creating a signed contingent input builder
 #[derive(Debug, Display)]
 pub enum SignedContingentInputBuilderError {
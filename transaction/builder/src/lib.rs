@@ -21,15 +21,15 @@ mod transaction_builder;
 #[cfg(any(test, feature = "test-only"))]
 pub mod test_utils;
 
-pub use error::{SignedContingentInputBuilderError, TxBuilderError};
+pub use error::{SettlementError, SignedContingentInputBuilderError, TxBuilderError};
 pub use input_credentials::InputCredentials;
 pub use memo_builder::{
-    BurnRedemptionMemoBuilder, DefragmentationMemoBuilder, EmptyMemoBuilder,
-    GiftCodeCancellationMemoBuilder, GiftCodeFundingMemoBuilder, GiftCodeSenderMemoBuilder,
-    MemoBuilder, RTHMemoBuilder,
+    BatchPaymentMemoBuilder, BurnRedemptionMemoBuilder, DefragmentationMemoBuilder,
+    EmptyMemoBuilder, GiftCodeCancellationMemoBuilder, GiftCodeFundingMemoBuilder,
+    GiftCodeSenderMemoBuilder, MemoBuilder, PaymentId, RTHMemoBuilder, TextMemoBuilder,
 };
 pub use reserved_subaddresses::ReservedSubaddresses;
 pub use signed_contingent_input_builder::SignedContingentInputBuilder;
 pub use transaction_builder::{
-    DefaultTxOutputsOrdering, TransactionBuilder, TxOutContext, TxOutputsOrdering,
+    DefaultTxOutputsOrdering, SciFill, TransactionBuilder, TxOutContext, TxOutputsOrdering,
 };
@@ -0,0 +1,297 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Defines the BatchPaymentMemoBuilder.
+//! This MemoBuilder policy pays several recipients in one transaction, giving
+//! each output its own authenticated sender memo and payment id.
+
+use super::{MemoBuilder, RTHMemoBuilder};
+use crate::ReservedSubaddresses;
+use alloc::collections::VecDeque;
+use mc_account_keys::{PublicAddress, ShortAddressHash};
+use mc_transaction_core::{Amount, MemoContext, MemoPayload, NewMemoError};
+use mc_transaction_extra::SenderMemoCredential;
+
+/// The payment id attached to one payment of a batch.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PaymentId {
+    /// A payment request id, written in a 0x0101 memo
+    Request(u64),
+    /// A payment intent id, written in a 0x0102 memo
+    Intent(u64),
+}
+
+/// This memo builder attaches an authenticated sender memo to each output of
+/// a batch payment, and a 0x0200 Destination Memo to the change output.
+///
+/// Usage:
+/// You should usually use this like:
+///
+///   let cred = SenderMemoCredential::from(&account_key);
+///   let mut mb = BatchPaymentMemoBuilder::new(cred);
+///   mb.add_payment(&alice, Some(PaymentId::Request(17)));
+///   mb.add_payment(&bob, None);
+///   mb.enable_destination_memo();
+///
+/// Then use it to construct a transaction builder, and add one output per
+/// payment, in the order the payments were added.
+///
+/// Each output gets 0x0101 Authenticated Sender With Payment Request Id Memo,
+/// 0x0102 Authenticated Sender With Payment Intent Id Memo, or 0x0100
+/// Authenticated Sender Memo, depending on the payment id of its payment.
+///
+/// The Destination Memo on the change output records the total outlay and
+/// the number of recipients of the whole batch. Since it has room for a single
+/// address, it records the last recipient, and no payment id.
+///
+/// When invoking the transaction builder, the change output must be created
+/// last, after the outputs of all of the payments.
+#[derive(Clone, Debug)]
+pub struct BatchPaymentMemoBuilder {
+    // Writes the memos, and tracks the outlay for the destination memo.
+    rth_memo_builder: RTHMemoBuilder,
+    // The payments whose outputs haven't been created yet, in order.
+    pending_payments: VecDeque<(ShortAddressHash, Option<PaymentId>)>,
+}
+
+impl BatchPaymentMemoBuilder {
+    /// Create a memo builder for a batch payment, identifying the sender with
+    /// the given credential.
+    pub fn new(sender_cred: SenderMemoCredential) -> Self {
+        let mut rth_memo_builder = RTHMemoBuilder::default();
+        rth_memo_builder.set_sender_credential(sender_cred);
+        Self {
+            rth_memo_builder,
+            pending_payments: Default::default(),
+        }
+    }
+
+    /// Add a payment to the batch. Its output must be the next one created.
+    pub fn add_payment(&mut self, recipient: &PublicAddress, payment_id: Option<PaymentId>) {
+        self.pending_payments
+            .push_back((ShortAddressHash::from(recipient), payment_id));
+    }
+
+    /// Enable destination memos
+    pub fn enable_destination_memo(&mut self) {
+        self.rth_memo_builder.enable_destination_memo();
+    }
+
+    /// Disable destination memos
+    pub fn disable_destination_memo(&mut self) {
+        self.rth_memo_builder.disable_destination_memo();
+    }
+
+    fn set_payment_id(&mut self, payment_id: Option<PaymentId>) {
+        self.rth_memo_builder.clear_payment_request_id();
+        self.rth_memo_builder.clear_payment_intent_id();
+        match payment_id {
+            Some(PaymentId::Request(id)) => self.rth_memo_builder.set_payment_request_id(id),
+            Some(PaymentId::Intent(id)) => self.rth_memo_builder.set_payment_intent_id(id),
+            None => {}
+        }
+    }
+}
+
+impl MemoBuilder for BatchPaymentMemoBuilder {
+    /// Set the fee
+    fn set_fee(&mut self, fee: Amount) -> Result<(), NewMemoError> {
+        self.rth_memo_builder.set_fee(fee)
+    }
+
+    /// Build a memo for the output of the next payment.
+    fn make_memo_for_output(
+        &mut self,
+        amount: Amount,
+        recipient: &PublicAddress,
+        memo_context: MemoContext,
+    ) -> Result<MemoPayload, NewMemoError> {
+        let (expected_recipient, _) = self
+            .pending_payments
+            .front()
+            .ok_or_else(|| NewMemoError::BadInputs("More outputs than batch payments".into()))?;
+        // A rejected output leaves the payment pending, so the caller can retry it.
+        if *expected_recipient != ShortAddressHash::from(recipient) {
+            return Err(NewMemoError::InvalidRecipient);
+        }
+        let (_, payment_id) = self
+            .pending_payments
+            .pop_front()
+            .expect("pending payment was just checked");
+        self.set_payment_id(payment_id);
+        self.rth_memo_builder
+            .make_memo_for_output(amount, recipient, memo_context)
+    }
+
+    /// Build a memo for a change output (to ourselves).
+    fn make_memo_for_change_output(
+        &mut self,
+        amount: Amount,
+        change_destination: &ReservedSubaddresses,
+        memo_context: MemoContext,
+    ) -> Result<MemoPayload, NewMemoError> {
+        if !self.pending_payments.is_empty() {
+            return Err(NewMemoError::MissingOutput);
+        }
+        // The destination memo describes the whole batch, not one payment.
+        self.set_payment_id(None);
+        self.rth_memo_builder
+            .make_memo_for_change_output(amount, change_destination, memo_context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use assert_matches::assert_matches;
+    use mc_account_keys::AccountKey;
+    use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPublic};
+    use mc_transaction_extra::MemoType;
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_batch_payment_memos() {
+        let mut rng: StdRng = SeedableRng::from_seed([0u8; 32]);
+        let sender = AccountKey::random(&mut rng);
+        let sender_addr = sender.default_subaddress();
+        let recipients: Vec<AccountKey> = (0..3).map(|_| AccountKey::random(&mut rng)).collect();
+        let payment_ids = [
+            Some(PaymentId::Request(11)),
+            None,
+            Some(PaymentId::Intent(33)),
+        ];
+
+        let mut builder = BatchPaymentMemoBuilder::new(SenderMemoCredential::from(&sender));
+        builder.enable_destination_memo();
+        for (recipient, payment_id) in recipients.iter().zip(payment_ids) {
+            builder.add_payment(&recipient.default_subaddress(), payment_id);
+        }
+        let fee = Amount::new(4, 0.into());
+        builder.set_fee(fee).unwrap();
+
+        for (i, recipient) in recipients.iter().enumerate() {
+            let tx_public_key = RistrettoPublic::from_random(&mut rng);
+            let compressed = CompressedRistrettoPublic::from(&tx_public_key);
+            let view_private_key = recipient.default_subaddress_view_private();
+            let payload = builder
+                .make_memo_for_output(
+                    Amount::new(100 * (i as u64 + 1), 0.into()),
+                    &recipient.default_subaddress(),
+                    MemoContext {
+                        tx_public_key: &tx_public_key,
+                    },
+                )
+                .unwrap();
+            match (i, MemoType::try_from(&payload).unwrap()) {
+                (0, MemoType::AuthenticatedSenderWithPaymentRequestId(memo)) => {
+                    assert_eq!(memo.payment_request_id(), 11);
+                    assert!(bool::from(memo.validate(
+                        &sender_addr,
+                        &view_private_key,
+                        &compressed
+                    )));
+                }
+                (1, MemoType::AuthenticatedSender(memo)) => {
+                    assert!(bool::from(memo.validate(
+                        &sender_addr,
+                        &view_private_key,
+                        &compressed
+                    )));
+                }
+                (2, MemoType::AuthenticatedSenderWithPaymentIntentId(memo)) => {
+                    assert_eq!(memo.payment_intent_id(), 33);
+                    assert!(bool::from(memo.validate(
+                        &sender_addr,
+                        &view_private_key,
+                        &compressed
+                    )));
+                }
+                (i, other) => panic!("unexpected memo for output {i}: {other:?}"),
+            }
+        }
+
+        let change_tx_public_key = RistrettoPublic::from_random(&mut rng);
+        let payload = builder
+            .make_memo_for_change_output(
+                Amount::new(7, 0.into()),
+                &ReservedSubaddresses::from(&sender),
+                MemoContext {
+                    tx_public_key: &change_tx_public_key,
+                },
+            )
+            .unwrap();
+        match MemoType::try_from(&payload).unwrap() {
+            MemoType::Destination(memo) => {
+                assert_eq!(memo.get_num_recipients(), 3);
+                assert_eq!(memo.get_total_outlay(), 600 + fee.value);
+                assert_eq!(memo.get_fee(), fee.value);
+                assert_eq!(
+                    memo.get_address_hash(),
+                    &ShortAddressHash::from(&recipients[2].default_subaddress())
+                );
+            }
+            other => panic!("unexpected change memo: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_batch_payment_outputs_must_match_payments() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let sender = AccountKey::random(&mut rng);
+        let alice = AccountKey::random(&mut rng).default_subaddress();
+        let bob = AccountKey::random(&mut rng).default_subaddress();
+        let tx_public_key = RistrettoPublic::from_random(&mut rng);
+
+        let mut builder = BatchPaymentMemoBuilder::new(SenderMemoCredential::from(&sender));
+        builder.enable_destination_memo();
+        builder.add_payment(&alice, None);
+        builder.add_payment(&bob, Some(PaymentId::Request(2)));
+
+        // Outputs must be created in the order of the payments
+        assert_matches!(
+            builder.make_memo_for_output(
+                Amount::new(1, 0.into()),
+                &bob,
+                MemoContext {
+                    tx_public_key: &tx_public_key,
+                },
+            ),
+            Err(NewMemoError::InvalidRecipient)
+        );
+
+        // Every payment needs an output before the change output
+        assert_matches!(
+            builder.make_memo_for_change_output(
+                Amount::new(1, 0.into()),
+                &ReservedSubaddresses::from(&sender),
+                MemoContext {
+                    tx_public_key: &tx_public_key,
+                },
+            ),
+            Err(NewMemoError::MissingOutput)
+        );
+
+        // The rejected output didn't use up a payment.
+        for recipient in [&alice, &bob] {
+            assert!(builder
+                .make_memo_for_output(
+                    Amount::new(1, 0.into()),
+                    recipient,
+                    MemoContext {
+                        tx_public_key: &tx_public_key,
+                    },
+                )
+                .is_ok());
+        }
+        assert!(builder
+            .make_memo_for_change_output(
+                Amount::new(1, 0.into()),
+                &ReservedSubaddresses::from(&sender),
+                MemoContext {
+                    tx_public_key: &tx_public_key,
+                },
+            )
+            .is_ok());
+    }
+}
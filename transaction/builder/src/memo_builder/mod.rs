@@ -10,19 +10,23 @@ use mc_account_keys::PublicAddress;
 use mc_transaction_core::{Amount, MemoContext, MemoPayload, NewMemoError};
 use mc_transaction_extra::UnusedMemo;
 
+mod batch_payment_memo_builder;
 mod burn_redemption_memo_builder;
 mod defragmentation_memo_builder;
 mod gift_code_cancellation_memo_builder;
 mod gift_code_funding_memo_builder;
 mod gift_code_sender_memo_builder;
 mod rth_memo_builder;
+mod text_memo_builder;
 
+pub use batch_payment_memo_builder::{BatchPaymentMemoBuilder, PaymentId};
 pub use burn_redemption_memo_builder::BurnRedemptionMemoBuilder;
 pub use defragmentation_memo_builder::DefragmentationMemoBuilder;
 pub use gift_code_cancellation_memo_builder::GiftCodeCancellationMemoBuilder;
 pub use gift_code_funding_memo_builder::GiftCodeFundingMemoBuilder;
 pub use gift_code_sender_memo_builder::GiftCodeSenderMemoBuilder;
 pub use rth_memo_builder::RTHMemoBuilder;
+pub use text_memo_builder::TextMemoBuilder;
 
 /// The MemoBuilder trait defines the API that the transaction builder uses
 /// to ask the memo builder to build a memo for a particular TxOut.
@@ -0,0 +1,291 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Defines the TextMemoBuilder.
+//! This MemoBuilder policy sends a short text message from the sender, using
+//! 0x0103 Authenticated Sender With Text memos, and otherwise behaves like the
+//! RTHMemoBuilder.
+
+use super::{MemoBuilder, RTHMemoBuilder};
+use crate::ReservedSubaddresses;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use mc_account_keys::{PublicAddress, ShortAddressHash};
+use mc_transaction_core::{Amount, MemoContext, MemoPayload, NewMemoError};
+use mc_transaction_extra::{AuthenticatedSenderWithTextMemo, SenderMemoCredential};
+
+/// This memo builder attaches a text message to normal outputs, using 0x0103
+/// Authenticated Sender With Text Memos, and 0x0200 Destination Memos to
+/// change outputs.
+///
+/// Usage:
+/// You should usually use this like:
+///
+///   let cred = SenderMemoCredential::from(&account_key);
+///   let mut mb = TextMemoBuilder::new(cred, "thanks!")?;
+///   mb.enable_destination_memo();
+///
+/// Then use it to construct a transaction builder.
+///
+/// A message longer than `AuthenticatedSenderWithTextMemo::TEXT_CHUNK_LEN`
+/// bytes is split into chunks, and each chunk is attached to the next normal
+/// output, in the order the outputs are created. All of the chunks must go to
+/// the same recipient, so the transaction needs at least as many outputs to
+/// that recipient as there are chunks. Use `num_chunks` to find out how many
+/// that is; zero-value outputs are fine.
+///
+/// Normal outputs created after the message has been written get 0x0100
+/// Authenticated Sender Memos.
+///
+/// When invoking the transaction builder, the change output must be created
+/// last. Creating the change output before every chunk was written is an
+/// error.
+#[derive(Clone, Debug)]
+pub struct TextMemoBuilder {
+    // Builds the memos which don't carry text, and tracks the outlay for the
+    // destination memo.
+    rth_memo_builder: RTHMemoBuilder,
+    // The credential used to form 0x0103 memos.
+    sender_cred: SenderMemoCredential,
+    // The chunks of the message, in order.
+    chunks: Vec<String>,
+    // The number of chunks written so far.
+    num_written: usize,
+    // The recipient of the message, once the first chunk is written.
+    recipient: Option<ShortAddressHash>,
+}
+
+impl TextMemoBuilder {
+    /// Create a memo builder which sends a text message, identifying the
+    /// sender with the given credential.
+    pub fn new(sender_cred: SenderMemoCredential, text: &str) -> Result<Self, NewMemoError> {
+        let chunks = AuthenticatedSenderWithTextMemo::split_text(text)
+            .map_err(|err| NewMemoError::BadInputs(err.to_string()))?
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        let mut rth_memo_builder = RTHMemoBuilder::default();
+        rth_memo_builder.set_sender_credential(sender_cred.clone());
+
+        Ok(Self {
+            rth_memo_builder,
+            sender_cred,
+            chunks,
+            num_written: 0,
+            recipient: None,
+        })
+    }
+
+    /// The number of outputs needed to carry the message.
+    pub fn num_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Enable destination memos
+    pub fn enable_destination_memo(&mut self) {
+        self.rth_memo_builder.enable_destination_memo();
+    }
+
+    /// Disable destination memos
+    pub fn disable_destination_memo(&mut self) {
+        self.rth_memo_builder.disable_destination_memo();
+    }
+}
+
+impl MemoBuilder for TextMemoBuilder {
+    /// Set the fee
+    fn set_fee(&mut self, fee: Amount) -> Result<(), NewMemoError> {
+        self.rth_memo_builder.set_fee(fee)
+    }
+
+    /// Build a memo for a normal output (to another party).
+    fn make_memo_for_output(
+        &mut self,
+        amount: Amount,
+        recipient: &PublicAddress,
+        memo_context: MemoContext,
+    ) -> Result<MemoPayload, NewMemoError> {
+        // Let the RTH memo builder check the ordering of outputs and track the
+        // outlay, even when we replace its memo.
+        let tx_public_key = memo_context.tx_public_key;
+        let rth_memo = self.rth_memo_builder.make_memo_for_output(
+            amount,
+            recipient,
+            MemoContext { tx_public_key },
+        )?;
+
+        let Some(chunk) = self.chunks.get(self.num_written) else {
+            return Ok(rth_memo);
+        };
+
+        let recipient_hash = ShortAddressHash::from(recipient);
+        if *self.recipient.get_or_insert(recipient_hash) != recipient_hash {
+            return Err(NewMemoError::BadInputs(
+                "All chunks of a text memo must go to the same recipient".into(),
+            ));
+        }
+
+        let memo = AuthenticatedSenderWithTextMemo::new(
+            &self.sender_cred,
+            recipient.view_public_key(),
+            &tx_public_key.into(),
+            self.num_written as u8,
+            self.chunks.len() as u8,
+            chunk,
+        )
+        .map_err(|err| NewMemoError::BadInputs(err.to_string()))?;
+        self.num_written += 1;
+        Ok(memo.into())
+    }
+
+    /// Build a memo for a change output (to ourselves).
+    fn make_memo_for_change_output(
+        &mut self,
+        amount: Amount,
+        change_destination: &ReservedSubaddresses,
+        memo_context: MemoContext,
+    ) -> Result<MemoPayload, NewMemoError> {
+        if self.num_written < self.chunks.len() {
+            return Err(NewMemoError::BadInputs(format!(
+                "Text memo needs {} outputs, but only {} were created",
+                self.chunks.len(),
+                self.num_written
+            )));
+        }
+        self.rth_memo_builder
+            .make_memo_for_change_output(amount, change_destination, memo_context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use mc_account_keys::AccountKey;
+    use mc_crypto_keys::RistrettoPublic;
+    use mc_transaction_extra::{MemoType, TextMemoChunk};
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_text_memo_builder_chains_chunks() {
+        let mut rng: StdRng = SeedableRng::from_seed([0u8; 32]);
+        let alice = AccountKey::random(&mut rng);
+        let alice_addr = alice.default_subaddress();
+        let bob = AccountKey::random(&mut rng);
+        let bob_addr = bob.default_subaddress();
+
+        let text = "Here is the rent for March, plus the water bill.";
+        let mut builder = TextMemoBuilder::new(SenderMemoCredential::from(&alice), text).unwrap();
+        builder.enable_destination_memo();
+        assert_eq!(builder.num_chunks(), 2);
+
+        // One more output than needed, to check that it falls back to 0x0100.
+        let mut chunks = Vec::new();
+        for i in 0..3 {
+            let tx_public_key = RistrettoPublic::from_random(&mut rng);
+            let payload = builder
+                .make_memo_for_output(
+                    Amount::new(10, 0.into()),
+                    &bob_addr,
+                    MemoContext {
+                        tx_public_key: &tx_public_key,
+                    },
+                )
+                .unwrap();
+            match MemoType::try_from(&payload).unwrap() {
+                MemoType::AuthenticatedSenderWithText(memo) => {
+                    assert!(i < 2);
+                    assert!(bool::from(memo.validate(
+                        &alice_addr,
+                        &bob.default_subaddress_view_private(),
+                        &tx_public_key.into()
+                    )));
+                    chunks.push(
+                        memo.decrypt(
+                            &alice_addr,
+                            &bob.default_subaddress_view_private(),
+                            &tx_public_key.into(),
+                        )
+                        .unwrap(),
+                    );
+                }
+                MemoType::AuthenticatedSender(_) => assert_eq!(i, 2),
+                other => panic!("unexpected memo type {other:?}"),
+            }
+        }
+        assert_eq!(TextMemoChunk::join(chunks).unwrap(), text);
+
+        let change_tx_public_key = RistrettoPublic::from_random(&mut rng);
+        let payload = builder
+            .make_memo_for_change_output(
+                Amount::new(5, 0.into()),
+                &ReservedSubaddresses::from(&alice),
+                MemoContext {
+                    tx_public_key: &change_tx_public_key,
+                },
+            )
+            .unwrap();
+        assert_matches!(
+            MemoType::try_from(&payload).unwrap(),
+            MemoType::Destination(memo) if memo.get_num_recipients() == 3
+        );
+    }
+
+    #[test]
+    fn test_text_memo_builder_rejects_missing_outputs() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let alice = AccountKey::random(&mut rng);
+        let bob_addr = AccountKey::random(&mut rng).default_subaddress();
+        let carol_addr = AccountKey::random(&mut rng).default_subaddress();
+
+        let text = "a".repeat(AuthenticatedSenderWithTextMemo::TEXT_CHUNK_LEN * 2);
+        let mut builder = TextMemoBuilder::new(SenderMemoCredential::from(&alice), &text).unwrap();
+        builder.enable_destination_memo();
+
+        let tx_public_key = RistrettoPublic::from_random(&mut rng);
+        builder
+            .make_memo_for_output(
+                Amount::new(10, 0.into()),
+                &bob_addr,
+                MemoContext {
+                    tx_public_key: &tx_public_key,
+                },
+            )
+            .unwrap();
+
+        // The second chunk can't go to a different recipient
+        assert_matches!(
+            builder.make_memo_for_output(
+                Amount::new(10, 0.into()),
+                &carol_addr,
+                MemoContext {
+                    tx_public_key: &tx_public_key,
+                },
+            ),
+            Err(NewMemoError::BadInputs(_))
+        );
+
+        // The change output can't be written before the whole message
+        assert_matches!(
+            builder.make_memo_for_change_output(
+                Amount::new(5, 0.into()),
+                &ReservedSubaddresses::from(&alice),
+                MemoContext {
+                    tx_public_key: &tx_public_key,
+                },
+            ),
+            Err(NewMemoError::BadInputs(_))
+        );
+
+        // Messages which don't fit in the memos of one transaction are rejected
+        let text = "a".repeat(AuthenticatedSenderWithTextMemo::MAX_TEXT_LEN + 1);
+        assert_matches!(
+            TextMemoBuilder::new(SenderMemoCredential::from(&alice), &text),
+            Err(NewMemoError::BadInputs(_))
+        );
+    }
+}
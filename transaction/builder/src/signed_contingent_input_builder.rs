@@ -55,6 +55,11 @@ pub struct SignedContingentInputBuilder<FPR: FogPubkeyResolver> {
     /// The minimum partial fill value, if any. This is in the token id of the
     /// partial fill change output. (See MCIP #42).
     min_partial_fill_value: u64,
+    /// The first block index at which the signed input can be used, if any.
+    valid_from_block: u64,
+    /// The latest block timestamp at which the signed input can be used, if
+    /// any.
+    max_block_timestamp: u64,
     /// The source of validated fog pubkeys used for this signed contingent
     /// input
     fog_resolver: FPR,
@@ -132,6 +137,8 @@ impl<FPR: FogPubkeyResolver> SignedContingentInputBuilder<FPR> {
             partial_fill_outputs: Vec::new(),
             partial_fill_change: None,
             min_partial_fill_value: 0u64,
+            valid_from_block: 0u64,
+            max_block_timestamp: 0u64,
             fog_resolver,
             fog_tombstone_block_limit: u64::MAX,
             memo_builder: Some(memo_builder),
@@ -452,6 +459,29 @@ impl<FPR: FogPubkeyResolver> SignedContingentInputBuilder<FPR> {
         self.min_partial_fill_value = value;
     }
 
+    /// Sets the first block index at which the signed input can be used.
+    ///
+    /// Any transaction which incorporates the signed input must have a
+    /// tombstone block after this one, and is only valid from this block
+    /// onward. Requires block version 4.
+    ///
+    /// # Arguments
+    /// * `block_index` - The first block index, or 0 for no restriction.
+    pub fn set_valid_from_block(&mut self, block_index: u64) {
+        self.valid_from_block = block_index;
+    }
+
+    /// Sets the latest block timestamp at which the signed input can be used,
+    /// so that it expires in wall-clock terms. Nodes check this against the
+    /// timestamp in their latest block's metadata when the transaction is
+    /// proposed. Requires block version 4.
+    ///
+    /// # Arguments
+    /// * `timestamp` - Seconds since the epoch, or 0 for no restriction.
+    pub fn set_max_block_timestamp(&mut self, timestamp: u64) {
+        self.max_block_timestamp = timestamp;
+    }
+
     /// Consume the builder and return the transaction.
     pub fn build<RNG: CryptoRng + RngCore>(
         mut self,
@@ -474,6 +504,17 @@ impl<FPR: FogPubkeyResolver> SignedContingentInputBuilder<FPR> {
             ));
         }
 
+        if (self.valid_from_block != 0 || self.max_block_timestamp != 0)
+            && !self
+                .block_version
+                .input_rules_validity_window_is_supported()
+        {
+            return Err(TxBuilderError::FeatureNotSupportedAtBlockVersion(
+                *self.block_version,
+                "input rule validity window",
+            ));
+        }
+
         self.required_outputs_and_secrets
             .sort_by(|(a, _), (b, _)| a.public_key.cmp(&b.public_key));
 
@@ -493,6 +534,8 @@ impl<FPR: FogPubkeyResolver> SignedContingentInputBuilder<FPR> {
             partial_fill_outputs: self.partial_fill_outputs,
             partial_fill_change: self.partial_fill_change,
             min_partial_fill_value: self.min_partial_fill_value,
+            valid_from_block: self.valid_from_block,
+            max_block_timestamp: self.max_block_timestamp,
         };
 
         // Get the tx out indices from the proofs in the input credentials,
@@ -546,7 +589,10 @@ impl<FPR: FogPubkeyResolver> SignedContingentInputBuilder<FPR> {
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::{test_utils::get_input_credentials, EmptyMemoBuilder, TransactionBuilder};
+    use crate::{
+        test_utils::get_input_credentials, DefaultTxOutputsOrdering, EmptyMemoBuilder, SciFill,
+        SettlementError, TransactionBuilder,
+    };
     use alloc::{string::ToString, vec};
     use assert_matches::assert_matches;
     use maplit::btreemap;
@@ -3664,4 +3710,226 @@ pub mod tests {
             }
         }
     }
+
+    #[test]
+    // Test that a batch of signed contingent inputs in different tokens can be
+    // settled together, and that bad fills are reported with their index
+    fn test_settle_batch_of_signed_contingent_inputs() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let block_version = BlockVersion::MAX;
+
+        let originator1 = AccountKey::random(&mut rng);
+        let originator2 = AccountKey::random(&mut rng);
+        let counterparty = AccountKey::random(&mut rng);
+
+        let fog_resolver = MockFogResolver(Default::default());
+
+        let token1 = TokenId::from(1);
+        let token2 = TokenId::from(2);
+        let token3 = TokenId::from(3);
+
+        // Originator 1 offers 1000 of token 1 for 400 of token 2, at least 100 at a
+        // time
+        let input_credentials = get_input_credentials(
+            block_version,
+            Amount::new(1000, token1),
+            &originator1,
+            &fog_resolver,
+            &mut rng,
+        );
+        let proofs = input_credentials.membership_proofs.clone();
+        let mut builder = SignedContingentInputBuilder::new(
+            block_version,
+            input_credentials,
+            fog_resolver.clone(),
+            EmptyMemoBuilder,
+        )
+        .unwrap();
+        builder
+            .add_partial_fill_output(
+                Amount::new(400, token2),
+                &originator1.default_subaddress(),
+                &mut rng,
+            )
+            .unwrap();
+        builder
+            .add_partial_fill_change_output(
+                Amount::new(1000, token1),
+                &ReservedSubaddresses::from(&originator1),
+                &mut rng,
+            )
+            .unwrap();
+        builder.set_min_partial_fill_value(100);
+        let mut sci1 = builder.build(&NoKeysRingSigner {}, &mut rng).unwrap();
+        sci1.tx_in.proofs = proofs;
+
+        // Originator 2 offers all of 500 of token 3 for 200 of token 2
+        let input_credentials = get_input_credentials(
+            block_version,
+            Amount::new(500, token3),
+            &originator2,
+            &fog_resolver,
+            &mut rng,
+        );
+        let proofs = input_credentials.membership_proofs.clone();
+        let mut builder = SignedContingentInputBuilder::new(
+            block_version,
+            input_credentials,
+            fog_resolver.clone(),
+            EmptyMemoBuilder,
+        )
+        .unwrap();
+        builder
+            .add_required_output(
+                Amount::new(200, token2),
+                &originator2.default_subaddress(),
+                &mut rng,
+            )
+            .unwrap();
+        let mut sci2 = builder.build(&NoKeysRingSigner {}, &mut rng).unwrap();
+        sci2.tx_in.proofs = proofs;
+
+        let fee = Amount::new(10, token2);
+        let mut builder =
+            TransactionBuilder::new(block_version, fee, fog_resolver.clone(), EmptyMemoBuilder)
+                .unwrap();
+        let fill = |sci: &SignedContingentInput, partial_fill_value| SciFill {
+            sci: sci.clone(),
+            partial_fill_value,
+        };
+
+        // Bad fills are reported with their index, and don't change the builder
+        assert_matches!(
+            builder.add_presigned_inputs_for_settlement(vec![fill(&sci1, 50), fill(&sci2, 0)]),
+            Err(SettlementError::InvalidPartialFillValue(0, 50, 100, 1000))
+        );
+        assert_matches!(
+            builder.add_presigned_inputs_for_settlement(vec![fill(&sci1, 500), fill(&sci2, 5)]),
+            Err(SettlementError::NotPartialFill(1, 5))
+        );
+        assert_matches!(
+            builder.add_presigned_inputs_for_settlement(vec![fill(&sci1, 500), fill(&sci1, 500)]),
+            Err(SettlementError::DuplicateKeyImage(1, 0))
+        );
+        let mut sci2_without_proofs = sci2.clone();
+        sci2_without_proofs.tx_in.proofs.clear();
+        assert_matches!(
+            builder.add_presigned_inputs_for_settlement(vec![
+                fill(&sci1, 500),
+                fill(&sci2_without_proofs, 0)
+            ]),
+            Err(SettlementError::Sci(
+                1,
+                SignedContingentInputError::MissingProofs
+            ))
+        );
+        assert_eq!(
+            builder.settlement_balance_sheet(),
+            btreemap! { token2 => 10 }
+        );
+
+        // Counterparty takes half of the first offer, and all of the second
+        let balance_sheet = builder
+            .add_presigned_inputs_for_settlement(vec![fill(&sci1, 500), fill(&sci2, 0)])
+            .unwrap();
+        assert_eq!(
+            balance_sheet,
+            btreemap! { token1 => -500, token2 => 400, token3 => -500 }
+        );
+        assert_eq!(
+            builder.settlement_balance_sheet(),
+            btreemap! { token1 => -500, token2 => 410, token3 => -500 }
+        );
+
+        builder
+            .add_output(
+                Amount::new(500, token1),
+                &counterparty.default_subaddress(),
+                &mut rng,
+            )
+            .unwrap();
+        builder
+            .add_output(
+                Amount::new(500, token3),
+                &counterparty.default_subaddress(),
+                &mut rng,
+            )
+            .unwrap();
+
+        // Counterparty hasn't supplied any token 2 yet
+        let change_destination = ReservedSubaddresses::from(&counterparty);
+        let mut unbalanced =
+            TransactionBuilder::new(block_version, fee, fog_resolver.clone(), EmptyMemoBuilder)
+                .unwrap();
+        unbalanced
+            .add_presigned_inputs_for_settlement(vec![fill(&sci2, 0)])
+            .unwrap();
+        assert_matches!(
+            unbalanced
+                .build_settlement::<DefaultTxOutputsOrdering, _>(&change_destination, &mut rng),
+            Err(SettlementError::InsufficientInputs(token_id, 210)) if token_id == token2
+        );
+
+        // Change in more than one token is rejected, rather than leaving it to
+        // the memo builder
+        let mut two_change =
+            TransactionBuilder::new(block_version, fee, fog_resolver.clone(), EmptyMemoBuilder)
+                .unwrap();
+        two_change
+            .add_presigned_inputs_for_settlement(vec![fill(&sci1, 500)])
+            .unwrap();
+        two_change.add_input(get_input_credentials(
+            block_version,
+            Amount::new(1000, token2),
+            &counterparty,
+            &fog_resolver,
+            &mut rng,
+        ));
+        assert_matches!(
+            two_change
+                .build_settlement::<DefaultTxOutputsOrdering, _>(&change_destination, &mut rng),
+            Err(SettlementError::MultipleChangeTokens(_, _))
+        );
+
+        builder.add_input(get_input_credentials(
+            block_version,
+            Amount::new(1000, token2),
+            &counterparty,
+            &fog_resolver,
+            &mut rng,
+        ));
+        let unsigned_tx = builder
+            .build_settlement::<DefaultTxOutputsOrdering, _>(&change_destination, &mut rng)
+            .unwrap();
+        let tx = unsigned_tx
+            .sign(&NoKeysRingSigner {}, None, &mut rng)
+            .unwrap();
+
+        validate_signature(block_version, &tx, &mut rng).unwrap();
+        validate_all_input_rules(block_version, &tx).unwrap();
+
+        // Two outputs to the counterparty, their change, the fractional output and
+        // change of the first offer, and the required output of the second
+        assert_eq!(tx.prefix.inputs.len(), 3);
+        assert_eq!(tx.prefix.outputs.len(), 6);
+
+        let counterparty_change = tx
+            .prefix
+            .outputs
+            .iter()
+            .find(|tx_out| {
+                subaddress_matches_tx_out(&counterparty, CHANGE_SUBADDRESS_INDEX, tx_out).unwrap()
+            })
+            .expect("Didn't find counterparty's change");
+        let ss = get_tx_out_shared_secret(
+            counterparty.view_private_key(),
+            &RistrettoPublic::try_from(&counterparty_change.public_key).unwrap(),
+        );
+        let (amount, _) = counterparty_change
+            .get_masked_amount()
+            .unwrap()
+            .get_value(&ss)
+            .unwrap();
+        assert_eq!(amount, Amount::new(590, token2));
+    }
 }
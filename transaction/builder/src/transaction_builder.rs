@@ -6,9 +6,13 @@
 
 use crate::{
     input_materials::InputMaterials, InputCredentials, MemoBuilder, ReservedSubaddresses,
-    TxBuilderError,
+    SettlementError, TxBuilderError,
+};
+use alloc::{
+    boxed::Box,
+    collections::{btree_map::Entry, BTreeMap},
+    vec::Vec,
 };
-use alloc::{boxed::Box, vec::Vec};
 use core::{
     cmp::{min, Ordering},
     fmt::Debug,
@@ -29,7 +33,8 @@ use mc_transaction_core::{
     RevealedTxOutError, Token, TokenId,
 };
 use mc_transaction_extra::{
-    SignedContingentInput, SignedContingentInputError, TxOutConfirmationNumber, UnsignedTx,
+    SignedContingentInput, SignedContingentInputAmounts, SignedContingentInputError,
+    TxOutConfirmationNumber, UnsignedTx,
 };
 use mc_transaction_summary::TxOutSummaryUnblindingData;
 use mc_util_from_random::FromRandom;
@@ -66,6 +71,19 @@ pub struct TxOutContext {
     pub shared_secret: RistrettoPublic,
 }
 
+/// A signed contingent input to settle as part of a batch, and how much of it
+/// to take.
+#[derive(Clone, Debug)]
+pub struct SciFill {
+    /// The signed contingent input, with membership proofs attached.
+    pub sci: SignedContingentInput,
+
+    /// The amount of the partial fill change which is taken from the signed
+    /// contingent input, i.e. not returned to its originator. This must be
+    /// zero if the signed contingent input has no partial fill rules.
+    pub partial_fill_value: u64,
+}
+
 /// Helper utility for building and signing a CryptoNote-style transaction,
 /// and attaching fog hint and memos as appropriate.
 ///
@@ -432,6 +450,189 @@ impl<FPR: FogPubkeyResolver> TransactionBuilder<FPR> {
         self.input_materials.push(InputMaterials::Presigned(sci));
     }
 
+    /// Add a batch of pre-signed inputs to the transaction, each filled to a
+    /// requested degree, also fulfilling the requirements imposed by their
+    /// signed rules.
+    ///
+    /// Every signed contingent input is validated, and its partial fill value
+    /// checked against its rules, before any of them are added. If any of them
+    /// can't be added, the builder is left unchanged, and the error carries
+    /// the index of the offending signed contingent input.
+    ///
+    /// Note: The signed contingent inputs must already have membership proofs.
+    ///
+    /// # Arguments
+    /// * `fills` - The signed contingent inputs, and how much of each to take.
+    ///
+    /// # Returns
+    /// * The balance sheet of the batch, as computed by
+    ///   `SignedContingentInputAmounts::add_to_balance_sheet`. Positive values
+    ///   must be supplied by other inputs, and negative values are available to
+    ///   other outputs.
+    pub fn add_presigned_inputs_for_settlement(
+        &mut self,
+        fills: Vec<SciFill>,
+    ) -> Result<BTreeMap<TokenId, i128>, SettlementError> {
+        // Check everything we can before touching the builder.
+        let mut balance_sheet = BTreeMap::default();
+        let mut key_images = BTreeMap::default();
+        let mut fills_and_amounts = Vec::with_capacity(fills.len());
+        for (index, fill) in fills.into_iter().enumerate() {
+            match key_images.entry(fill.sci.key_image()) {
+                Entry::Occupied(entry) => {
+                    return Err(SettlementError::DuplicateKeyImage(index, *entry.get()))
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(index);
+                }
+            }
+
+            let amounts = fill
+                .sci
+                .validate()
+                .map_err(|err| SettlementError::Sci(index, err))?;
+            let rules = fill
+                .sci
+                .tx_in
+                .input_rules
+                .as_ref()
+                .ok_or(SettlementError::Sci(
+                    index,
+                    SignedContingentInputError::MissingRules,
+                ))?;
+            match amounts.partial_fill_change.as_ref() {
+                Some(partial_fill_change) => {
+                    if fill.partial_fill_value < rules.min_partial_fill_value
+                        || fill.partial_fill_value > partial_fill_change.value
+                    {
+                        return Err(SettlementError::InvalidPartialFillValue(
+                            index,
+                            fill.partial_fill_value,
+                            rules.min_partial_fill_value,
+                            partial_fill_change.value,
+                        ));
+                    }
+                }
+                None => {
+                    if fill.partial_fill_value != 0 {
+                        return Err(SettlementError::NotPartialFill(
+                            index,
+                            fill.partial_fill_value,
+                        ));
+                    }
+                }
+            }
+            amounts
+                .add_to_balance_sheet(fill.partial_fill_value, &mut balance_sheet)
+                .map_err(|err| SettlementError::Sci(index, err))?;
+            fills_and_amounts.push((fill, amounts));
+        }
+
+        // Remember where we were, so that we can roll back if a later input
+        // fails to be added.
+        let num_inputs = self.input_materials.len();
+        let num_outputs = self.outputs_and_secrets.len();
+        let tombstone_block = self.tombstone_block;
+        let fog_tombstone_block_limit = self.fog_tombstone_block_limit;
+
+        for (index, (fill, amounts)) in fills_and_amounts.into_iter().enumerate() {
+            if let Err(err) = self.add_presigned_fill(fill, &amounts) {
+                self.input_materials.truncate(num_inputs);
+                self.outputs_and_secrets.truncate(num_outputs);
+                self.tombstone_block = tombstone_block;
+                self.fog_tombstone_block_limit = fog_tombstone_block_limit;
+                return Err(SettlementError::Sci(index, err));
+            }
+        }
+
+        Ok(balance_sheet)
+    }
+
+    /// Add a single pre-signed input of a settlement, whose partial fill value
+    /// was already checked.
+    fn add_presigned_fill(
+        &mut self,
+        fill: SciFill,
+        amounts: &SignedContingentInputAmounts,
+    ) -> Result<(), SignedContingentInputError> {
+        match amounts.partial_fill_change.as_ref() {
+            Some(partial_fill_change) => {
+                // The partial fill value is what the counterparty takes, the
+                // change is what is returned to the originator.
+                let sci_change_amount = Amount::new(
+                    partial_fill_change.value - fill.partial_fill_value,
+                    partial_fill_change.token_id,
+                );
+                self.add_presigned_partial_fill_input(fill.sci, sci_change_amount)?;
+            }
+            None => self.add_presigned_input(fill.sci)?,
+        }
+        Ok(())
+    }
+
+    /// Compute the balance sheet of the transaction so far: for each token,
+    /// the value of the outputs plus the fee, minus the value of the inputs.
+    ///
+    /// Positive values must be supplied by more inputs, and negative values
+    /// are left over for more outputs, e.g. change.
+    pub fn settlement_balance_sheet(&self) -> BTreeMap<TokenId, i128> {
+        let mut balance_sheet = BTreeMap::<TokenId, i128>::default();
+        *balance_sheet.entry(self.fee.token_id).or_default() += self.fee.value as i128;
+        for input in self.input_materials.iter() {
+            let amount = input.amount();
+            *balance_sheet.entry(amount.token_id).or_default() -= amount.value as i128;
+        }
+        for (_, unblinding_data) in self.outputs_and_secrets.iter() {
+            let amount = &unblinding_data.unmasked_amount;
+            *balance_sheet
+                .entry(TokenId::from(amount.token_id))
+                .or_default() += amount.value as i128;
+        }
+        balance_sheet
+    }
+
+    /// Balance the transaction and return low level data to sign it with.
+    ///
+    /// Any value of the inputs which is not spent by the outputs and the fee
+    /// is returned to `change_destination` in a change output. If the inputs
+    /// of some token are insufficient, an error is returned instead.
+    ///
+    /// At most one token may have change: memo builders such as
+    /// [RTHMemoBuilder](crate::RTHMemoBuilder) write a single change memo
+    /// describing the whole transaction, and reject a second change output.
+    /// Callers must add outputs for the value left over in every other token,
+    /// otherwise [SettlementError::MultipleChangeTokens] is returned.
+    ///
+    /// # Arguments
+    /// * `change_destination` - An account to send change to, if any
+    /// * `rng` - RNG used to generate blinding for commitments
+    pub fn build_settlement<O: TxOutputsOrdering, RNG: CryptoRng + RngCore>(
+        mut self,
+        change_destination: &ReservedSubaddresses,
+        rng: &mut RNG,
+    ) -> Result<UnsignedTx, SettlementError> {
+        let balance_sheet = self.settlement_balance_sheet();
+        if let Some((token_id, missing)) = balance_sheet.iter().find(|(_, value)| **value > 0) {
+            return Err(SettlementError::InsufficientInputs(
+                *token_id,
+                missing.unsigned_abs(),
+            ));
+        }
+        let mut change = balance_sheet.into_iter().filter(|(_, value)| *value < 0);
+        if let Some((token_id, value)) = change.next() {
+            if let Some((other_token_id, _)) = change.next() {
+                return Err(SettlementError::MultipleChangeTokens(
+                    token_id,
+                    other_token_id,
+                ));
+            }
+            let change_value = u64::try_from(value.unsigned_abs())
+                .map_err(|_| SettlementError::BalanceOverflow(token_id))?;
+            self.add_change_output(Amount::new(change_value, token_id), change_destination, rng)?;
+        }
+        Ok(self.build_unsigned::<O>()?)
+    }
+
     /// Add a non-change output to the transaction.
     ///
     /// If a sender memo credential has been set, this will create an
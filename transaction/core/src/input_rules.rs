@@ -68,6 +68,28 @@ pub struct InputRules {
     /// This minimum has no effect if set to 0.
     #[prost(fixed64, tag = "5")]
     pub min_partial_fill_value: u64,
+
+    /// The first block index at which the transaction may be included in the
+    /// ledger. This lets the signer make an offer which only becomes valid
+    /// later.
+    /// This rule has no effect if set to 0.
+    #[prost(fixed64, tag = "6")]
+    #[digestible(omit_when = 0)]
+    pub valid_from_block: u64,
+
+    /// An upper bound on the timestamp, in seconds since the epoch, in the
+    /// latest block's metadata when the transaction is proposed. This lets the
+    /// signer make an offer which expires in wall-clock terms, rather than in
+    /// blocks.
+    ///
+    /// Block metadata is local to each node, so this is only checked when a
+    /// node accepts the transaction into its pool, and not when the block is
+    /// formed. A transaction accepted just before the deadline may still be
+    /// included in a block after it.
+    /// This rule has no effect if set to 0.
+    #[prost(fixed64, tag = "7")]
+    #[digestible(omit_when = 0)]
+    pub max_block_timestamp: u64,
 }
 
 impl InputRules {
@@ -101,6 +123,17 @@ impl InputRules {
         if self.max_tombstone_block != 0 && tx.prefix.tombstone_block > self.max_tombstone_block {
             return Err(InputRuleError::MaxTombstoneBlockExceeded);
         }
+        // Verify that the validity window rules are allowed, and that the Tx can be
+        // included at all. The rules themselves depend on the block including the
+        // Tx, see verify_block_index and verify_block_timestamp.
+        if self.valid_from_block != 0 || self.max_block_timestamp != 0 {
+            if !block_version.input_rules_validity_window_is_supported() {
+                return Err(InputRuleError::ValidityWindowNotSupported);
+            }
+            if tx.prefix.tombstone_block <= self.valid_from_block {
+                return Err(InputRuleError::TombstoneBlockBeforeValidFromBlock);
+            }
+        }
         // Verify required_outputs
         for required_output in self.required_outputs.iter() {
             if !tx.prefix.outputs.iter().any(|x| x == required_output) {
@@ -113,6 +146,35 @@ impl InputRules {
         Ok(())
     }
 
+    /// Verify that the rules allow the Tx to be included in the block with
+    /// this index.
+    pub fn verify_block_index(&self, block_index: u64) -> Result<(), InputRuleError> {
+        if block_index < self.valid_from_block {
+            return Err(InputRuleError::BlockIndexBeforeValidFromBlock);
+        }
+        Ok(())
+    }
+
+    /// Verify that the rules allow the Tx to be proposed when the latest block
+    /// has this timestamp, in seconds since the epoch. A timestamp of 0 means
+    /// the latest block's timestamp is unknown, which fails any max block
+    /// timestamp rule.
+    pub fn verify_block_timestamp(
+        &self,
+        latest_block_timestamp: u64,
+    ) -> Result<(), InputRuleError> {
+        if self.max_block_timestamp == 0 {
+            return Ok(());
+        }
+        if latest_block_timestamp == 0 {
+            return Err(InputRuleError::BlockTimestampUnknown);
+        }
+        if latest_block_timestamp > self.max_block_timestamp {
+            return Err(InputRuleError::MaxBlockTimestampExceeded);
+        }
+        Ok(())
+    }
+
     // Partial-Fill rules verification (MCIP #42)
     fn verify_partial_fill_rules(
         &self,
@@ -258,6 +320,17 @@ pub enum InputRuleError {
     FractionalOutputAmountDoesNotRespectFillFraction,
     /// Fractional change output exceeds limit
     FractionalChangeOutputAmountExceedsLimit,
+    /// Validity window rules are not supported at this block version
+    ValidityWindowNotSupported,
+    /// The tombstone block is not after the valid-from block
+    TombstoneBlockBeforeValidFromBlock,
+    /// The block index is before the valid-from block
+    BlockIndexBeforeValidFromBlock,
+    /// The block timestamp exceeds the limit
+    MaxBlockTimestampExceeded,
+    /// The block timestamp is unknown, so the max block timestamp can't be
+    /// checked
+    BlockTimestampUnknown,
     /// Revealed Tx Out: {0}
     RevealedTxOut(RevealedTxOutError),
 }
@@ -85,6 +85,7 @@ pub fn validate<R: RngCore + CryptoRng>(
 
     if block_version.signed_input_rules_are_supported() {
         validate_all_input_rules(block_version, tx)?;
+        validate_input_rules_block_index(tx, current_block_index)?;
     } else {
         validate_that_no_input_rules_exist(tx)?;
     }
@@ -476,6 +477,20 @@ pub fn validate_all_input_rules(
     Ok(())
 }
 
+/// All input rules must allow the tx to be included in the block with the
+/// given index.
+pub fn validate_input_rules_block_index(
+    tx: &Tx,
+    current_block_index: u64,
+) -> TransactionValidationResult<()> {
+    for input in tx.prefix.inputs.iter() {
+        if let Some(rules) = input.input_rules.as_ref() {
+            rules.verify_block_index(current_block_index)?;
+        }
+    }
+    Ok(())
+}
+
 /// Validate that no input have input rules
 pub fn validate_that_no_input_rules_exist(tx: &Tx) -> TransactionValidationResult<()> {
     for input in tx.prefix.inputs.iter() {
@@ -91,6 +91,61 @@ fn test_input_rules_verify_max_tombstone() {
     get_first_rules(&tx).verify(block_version, &tx).unwrap();
 }
 
+// Test that input rules verification is working for the validity window rules
+#[test]
+fn test_input_rules_verify_validity_window() {
+    let block_version = BlockVersion::FOUR;
+
+    let (mut tx, _ledger) = create_test_tx(block_version);
+
+    // Modify the Tx to have some (empty) input rules.
+    // (This invalidates the signature, but we aren't checking that here)
+    tx.prefix.inputs[0].input_rules = Some(InputRules::default());
+
+    // A valid-from block before the tombstone block is fine
+    get_first_rules_mut(&mut tx).valid_from_block = tx.prefix.tombstone_block - 1;
+    get_first_rules(&tx).verify(block_version, &tx).unwrap();
+
+    // A valid-from block at or after the tombstone block can never be satisfied
+    get_first_rules_mut(&mut tx).valid_from_block = tx.prefix.tombstone_block;
+    assert_matches!(
+        get_first_rules(&tx).verify(block_version, &tx),
+        Err(InputRuleError::TombstoneBlockBeforeValidFromBlock)
+    );
+
+    // The block index check is relative to the valid-from block
+    get_first_rules_mut(&mut tx).valid_from_block = 10;
+    let rules = get_first_rules(&tx);
+    assert_matches!(
+        rules.verify_block_index(9),
+        Err(InputRuleError::BlockIndexBeforeValidFromBlock)
+    );
+    rules.verify_block_index(10).unwrap();
+
+    // A zero max block timestamp means no limit
+    rules.verify_block_timestamp(u64::MAX).unwrap();
+    rules.verify_block_timestamp(0).unwrap();
+    get_first_rules_mut(&mut tx).max_block_timestamp = 1000;
+    let rules = get_first_rules(&tx);
+    rules.verify_block_timestamp(1000).unwrap();
+    assert_matches!(
+        rules.verify_block_timestamp(1001),
+        Err(InputRuleError::MaxBlockTimestampExceeded)
+    );
+
+    // An unknown parent block timestamp can't satisfy a max block timestamp
+    assert_matches!(
+        rules.verify_block_timestamp(0),
+        Err(InputRuleError::BlockTimestampUnknown)
+    );
+
+    // The validity window is rejected at block versions that don't support it
+    assert_matches!(
+        get_first_rules(&tx).verify(BlockVersion::THREE, &tx),
+        Err(InputRuleError::ValidityWindowNotSupported)
+    );
+}
+
 fn change_committed_amount(r_txo: &RevealedTxOut, new_amount: Amount) -> RevealedTxOut {
     // Confirm that this can even be revealed
     r_txo.reveal_amount().unwrap();
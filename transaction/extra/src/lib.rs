@@ -21,10 +21,13 @@ mod unsigned_tx;
 pub use memo::{
     compute_authenticated_sender_memo, compute_destination_memo, AuthenticatedSenderMemo,
     AuthenticatedSenderWithPaymentIntentIdMemo, AuthenticatedSenderWithPaymentRequestIdMemo,
-    BurnRedemptionMemo, DefragmentationMemo, DefragmentationMemoError, DestinationMemo,
-    DestinationMemoError, DestinationWithPaymentIntentIdMemo, DestinationWithPaymentRequestIdMemo,
-    GiftCodeCancellationMemo, GiftCodeFundingMemo, GiftCodeSenderMemo, MemoDecodingError, MemoType,
-    RegisteredMemoType, SenderMemoCredential, UnusedMemo,
+    AuthenticatedSenderWithTextMemo, BurnRedemptionMemo, DefragmentationMemo,
+    DefragmentationMemoError, DestinationMemo, DestinationMemoError,
+    DestinationWithPaymentIntentIdMemo, DestinationWithPaymentRequestIdMemo,
+    GiftCodeCancellationMemo, GiftCodeFundingMemo, GiftCodeSenderMemo, MemoDecodingError,
+    MemoFieldKind, MemoFieldLayout, MemoFieldValue, MemoLayout, MemoPlugin, MemoRegistry,
+    MemoRegistryError, MemoType, RegisteredMemo, RegisteredMemoType, SenderMemoCredential,
+    TextMemoChunk, TextMemoError, UnusedMemo,
 };
 pub use signed_contingent_input::{
     SignedContingentInput, SignedContingentInputAmounts, SignedContingentInputError,
@@ -0,0 +1,465 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Object for 0x0103 Authenticated Sender With Text memo type
+//!
+//! This memo carries a short UTF-8 message from the sender, encrypted and
+//! authenticated with the same sender / recipient shared secret as the
+//! 0x0100 Authenticated Sender memo. Messages which don't fit in one memo are
+//! split into chunks, each attached to a different output of the same
+//! transaction.
+
+use super::{
+    authenticated_common::{compute_authenticated_sender_memo, validate_authenticated_sender},
+    credential::SenderMemoCredential,
+    RegisteredMemoType,
+};
+use crate::impl_memo_type_conversions;
+use alloc::{string::String, vec::Vec};
+use core::str;
+use displaydoc::Display;
+use mc_account_keys::{PublicAddress, ShortAddressHash};
+use mc_crypto_hashes::{Blake2b512, Digest};
+use mc_crypto_keys::{
+    CompressedRistrettoPublic, KexReusablePrivate, RistrettoPrivate, RistrettoPublic,
+};
+use subtle::Choice;
+
+/// Domain separator for the keystream which encrypts the text.
+const TEXT_MEMO_KEYSTREAM_DOMAIN_TAG: &[u8] = b"mc-memo-text-keystream";
+
+/// A memo that the sender writes to send a short text message to the
+/// recipient of a TxOut, in an authenticated but deniable way.
+///
+/// The recipient of this memo type should:
+/// * First, use sender_address_hash to look up the address of the sender, from
+///   among their contacts. If the sender isn't known then we can neither
+///   validate nor decrypt.
+/// * Then, call validate to check the mac and confirm authenticity.
+/// * Then, call decrypt to obtain the text chunk, and combine the chunks found
+///   on the other outputs of the transaction with `TextMemoChunk::join`.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct AuthenticatedSenderWithTextMemo {
+    /// The memo data
+    memo_data: [u8; 64],
+}
+
+impl RegisteredMemoType for AuthenticatedSenderWithTextMemo {
+    const MEMO_TYPE_BYTES: [u8; 2] = [0x01, 0x03];
+}
+
+impl AuthenticatedSenderWithTextMemo {
+    /// The maximum number of text bytes in a single memo
+    pub const TEXT_CHUNK_LEN: usize = 29;
+
+    /// The maximum number of memos a message can be split across
+    pub const MAX_CHUNKS: usize = u8::MAX as usize;
+
+    /// The maximum number of text bytes in a message
+    pub const MAX_TEXT_LEN: usize = Self::TEXT_CHUNK_LEN * Self::MAX_CHUNKS;
+
+    /// Create a new AuthenticatedSenderWithTextMemo carrying one chunk of a
+    /// message.
+    ///
+    /// # Arguments:
+    /// * cred: A sender memo credential tied to the address we wish to identify
+    ///   ourselves as
+    /// * receiving_subaddress_view_public_key: This is the view public key from
+    ///   the public address of recipient
+    /// * tx_out_public_key: The public_key of the TxOut to which we will attach
+    ///   this memo
+    /// * chunk_index: The position of this chunk in the message
+    /// * num_chunks: The number of chunks in the message
+    /// * text: The text of this chunk, at most TEXT_CHUNK_LEN bytes. See
+    ///   `split_text` for splitting a longer message.
+    pub fn new(
+        cred: &SenderMemoCredential,
+        receiving_subaddress_view_public_key: &RistrettoPublic,
+        tx_out_public_key: &CompressedRistrettoPublic,
+        chunk_index: u8,
+        num_chunks: u8,
+        text: &str,
+    ) -> Result<Self, TextMemoError> {
+        if chunk_index >= num_chunks {
+            return Err(TextMemoError::BadChunkIndex(chunk_index, num_chunks));
+        }
+        if text.len() > Self::TEXT_CHUNK_LEN {
+            return Err(TextMemoError::ChunkTooLong(text.len()));
+        }
+
+        // The layout of the memo is:
+        // [0-16) address hash
+        // [16-48) encrypted with the sender / recipient shared secret:
+        //   [16-17) chunk index
+        //   [17-18) number of chunks
+        //   [18-19) text length
+        //   [19-48) text, zero padded
+        // [48-64) HMAC
+        let mut data = [0u8; (48 - 16)];
+        data[0] = chunk_index;
+        data[1] = num_chunks;
+        data[2] = text.len() as u8;
+        data[3..(3 + text.len())].copy_from_slice(text.as_bytes());
+
+        let shared_secret = cred
+            .subaddress_spend_private_key
+            .key_exchange(receiving_subaddress_view_public_key);
+        apply_keystream(shared_secret.as_ref(), tx_out_public_key, &mut data);
+
+        let memo_data = compute_authenticated_sender_memo(
+            Self::MEMO_TYPE_BYTES,
+            cred,
+            receiving_subaddress_view_public_key,
+            tx_out_public_key,
+            &data,
+        );
+
+        Ok(Self { memo_data })
+    }
+
+    /// Split a message into chunks which each fit in one memo. Chunks are
+    /// split on character boundaries, so each chunk is valid UTF-8 on its own.
+    ///
+    /// An empty message produces a single empty chunk.
+    pub fn split_text(text: &str) -> Result<Vec<&str>, TextMemoError> {
+        let mut chunks = Vec::new();
+        let mut rest = text;
+        loop {
+            let mut end = rest.len().min(Self::TEXT_CHUNK_LEN);
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            let (chunk, tail) = rest.split_at(end);
+            chunks.push(chunk);
+            rest = tail;
+            if rest.is_empty() {
+                break;
+            }
+        }
+        if chunks.len() > Self::MAX_CHUNKS {
+            return Err(TextMemoError::TooLong(text.len()));
+        }
+        Ok(chunks)
+    }
+
+    /// Get the sender address hash from the memo
+    pub fn sender_address_hash(&self) -> ShortAddressHash {
+        let bytes: [u8; 16] = self.memo_data[0..16].try_into().unwrap();
+        ShortAddressHash::from(bytes)
+    }
+
+    /// Validate an AuthenticatedSenderWithTextMemo
+    ///
+    /// The mac covers the encrypted text, so this can be checked before
+    /// decrypting.
+    ///
+    /// Arguments:
+    /// * sender_address: The public address of the sender. This can be looked
+    ///   up by the ShortAddressHash provided.
+    /// * receiving_subaddress_view_private_key: This is usually our
+    ///   default_subaddress_view_private_key, but should correspond to whatever
+    ///   subaddress recieved this TxOut.
+    /// * tx_out_public_key: The public key of the TxOut to which this memo is
+    ///   attached.
+    ///
+    /// Returns:
+    /// * subtle::Choice(1u8) if validation passed, subtle::Choice(0u8) if hmac
+    ///   comparison failed.
+    ///
+    /// This function is constant-time.
+    pub fn validate(
+        &self,
+        sender_address: &PublicAddress,
+        receiving_subaddress_view_private_key: &RistrettoPrivate,
+        tx_out_public_key: &CompressedRistrettoPublic,
+    ) -> Choice {
+        validate_authenticated_sender(
+            sender_address,
+            receiving_subaddress_view_private_key,
+            tx_out_public_key,
+            Self::MEMO_TYPE_BYTES,
+            &self.memo_data,
+        )
+    }
+
+    /// Decrypt the text chunk carried by this memo.
+    ///
+    /// This does not check the mac. Call validate first, otherwise the text
+    /// may not have been written by the sender.
+    ///
+    /// Arguments are the same as for validate.
+    pub fn decrypt(
+        &self,
+        sender_address: &PublicAddress,
+        receiving_subaddress_view_private_key: &RistrettoPrivate,
+        tx_out_public_key: &CompressedRistrettoPublic,
+    ) -> Result<TextMemoChunk, TextMemoError> {
+        let mut data: [u8; (48 - 16)] = self.memo_data[16..48].try_into().unwrap();
+        let shared_secret =
+            receiving_subaddress_view_private_key.key_exchange(sender_address.spend_public_key());
+        apply_keystream(shared_secret.as_ref(), tx_out_public_key, &mut data);
+
+        let (index, num_chunks, len) = (data[0], data[1], data[2] as usize);
+        if index >= num_chunks {
+            return Err(TextMemoError::BadChunkIndex(index, num_chunks));
+        }
+        if len > Self::TEXT_CHUNK_LEN {
+            return Err(TextMemoError::ChunkTooLong(len));
+        }
+        let text = str::from_utf8(&data[3..(3 + len)])
+            .map_err(|_| TextMemoError::Utf8Decoding)?
+            .into();
+
+        Ok(TextMemoChunk {
+            index,
+            num_chunks,
+            text,
+        })
+    }
+}
+
+/// XOR the encrypted part of the memo with a keystream derived from the
+/// shared secret. The keystream is bound to the TxOut, so it is never reused.
+fn apply_keystream(
+    shared_secret: &[u8; 32],
+    tx_out_public_key: &CompressedRistrettoPublic,
+    data: &mut [u8; (48 - 16)],
+) {
+    let mut hasher = Blake2b512::new();
+    hasher.update(TEXT_MEMO_KEYSTREAM_DOMAIN_TAG);
+    hasher.update(shared_secret);
+    hasher.update(tx_out_public_key.as_ref());
+    let keystream = hasher.finalize();
+    for (byte, key) in data.iter_mut().zip(keystream.iter()) {
+        *byte ^= key;
+    }
+}
+
+/// One decrypted chunk of a text message
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TextMemoChunk {
+    /// The position of this chunk in the message
+    pub index: u8,
+    /// The number of chunks in the message
+    pub num_chunks: u8,
+    /// The text of this chunk
+    pub text: String,
+}
+
+impl TextMemoChunk {
+    /// Reassemble a message from its chunks, which may be in any order.
+    pub fn join(chunks: impl IntoIterator<Item = TextMemoChunk>) -> Result<String, TextMemoError> {
+        let mut chunks: Vec<TextMemoChunk> = chunks.into_iter().collect();
+        chunks.sort_by_key(|chunk| chunk.index);
+
+        let num_chunks = chunks
+            .first()
+            .ok_or(TextMemoError::MissingChunk(0))?
+            .num_chunks;
+        let mut text = String::new();
+        for (expected_index, chunk) in (0..num_chunks).zip(chunks.iter()) {
+            if chunk.num_chunks != num_chunks {
+                return Err(TextMemoError::InconsistentChunkCount);
+            }
+            if chunk.index != expected_index {
+                return Err(TextMemoError::MissingChunk(expected_index));
+            }
+            text.push_str(&chunk.text);
+        }
+        if chunks.len() < num_chunks as usize {
+            return Err(TextMemoError::MissingChunk(chunks.len() as u8));
+        }
+        if chunks.len() > num_chunks as usize {
+            return Err(TextMemoError::InconsistentChunkCount);
+        }
+        Ok(text)
+    }
+}
+
+/// An error that can occur when creating or reading a text memo
+#[derive(Clone, Display, Debug, Eq, PartialEq)]
+pub enum TextMemoError {
+    /// Text of {0} bytes is too long to be sent in memos
+    TooLong(usize),
+    /// Chunk of {0} bytes is too long for one memo
+    ChunkTooLong(usize),
+    /// Chunk index {0} is out of range for a message of {1} chunks
+    BadChunkIndex(u8, u8),
+    /// Utf-8 did not properly decode
+    Utf8Decoding,
+    /// Chunk {0} of the message is missing
+    MissingChunk(u8),
+    /// Chunks disagree about the number of chunks in the message
+    InconsistentChunkCount,
+}
+
+impl From<&[u8; 64]> for AuthenticatedSenderWithTextMemo {
+    fn from(src: &[u8; 64]) -> Self {
+        let mut memo_data = [0u8; 64];
+        memo_data.copy_from_slice(src);
+        Self { memo_data }
+    }
+}
+
+impl From<AuthenticatedSenderWithTextMemo> for [u8; 64] {
+    fn from(src: AuthenticatedSenderWithTextMemo) -> [u8; 64] {
+        src.memo_data
+    }
+}
+
+impl_memo_type_conversions! { AuthenticatedSenderWithTextMemo }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use mc_account_keys::AccountKey;
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_split_text() {
+        assert_eq!(
+            AuthenticatedSenderWithTextMemo::split_text("").unwrap(),
+            vec![""]
+        );
+        assert_eq!(
+            AuthenticatedSenderWithTextMemo::split_text("lunch money").unwrap(),
+            vec!["lunch money"]
+        );
+
+        // Multi-byte characters are never split across chunks
+        let text = "🦀".repeat(10);
+        let chunks = AuthenticatedSenderWithTextMemo::split_text(&text).unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0], "🦀".repeat(7));
+        assert_eq!(chunks[1], "🦀".repeat(3));
+
+        let text = "a".repeat(AuthenticatedSenderWithTextMemo::MAX_TEXT_LEN);
+        assert_eq!(
+            AuthenticatedSenderWithTextMemo::split_text(&text)
+                .unwrap()
+                .len(),
+            AuthenticatedSenderWithTextMemo::MAX_CHUNKS
+        );
+        let text = "a".repeat(AuthenticatedSenderWithTextMemo::MAX_TEXT_LEN + 1);
+        assert_eq!(
+            AuthenticatedSenderWithTextMemo::split_text(&text),
+            Err(TextMemoError::TooLong(text.len()))
+        );
+    }
+
+    #[test]
+    fn test_text_memo_round_trip() {
+        let mut rng: StdRng = SeedableRng::from_seed([3u8; 32]);
+
+        let alice = AccountKey::random(&mut rng);
+        let alice_cred = SenderMemoCredential::from(&alice);
+        let alice_addr = alice.default_subaddress();
+        let bob = AccountKey::random(&mut rng);
+        let bob_addr = bob.default_subaddress();
+
+        let text = "Thanks for dinner, this one is on me!";
+        let chunks = AuthenticatedSenderWithTextMemo::split_text(text).unwrap();
+        assert_eq!(chunks.len(), 2);
+
+        let memos: Vec<_> = chunks
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let tx_public_key = CompressedRistrettoPublic::from_random(&mut rng);
+                let memo = AuthenticatedSenderWithTextMemo::new(
+                    &alice_cred,
+                    bob_addr.view_public_key(),
+                    &tx_public_key,
+                    index as u8,
+                    chunks.len() as u8,
+                    chunk,
+                )
+                .unwrap();
+                (memo, tx_public_key)
+            })
+            .collect();
+
+        let mut decrypted = Vec::new();
+        for (memo, tx_public_key) in memos.iter().rev() {
+            assert_eq!(
+                memo.sender_address_hash(),
+                ShortAddressHash::from(&alice_addr)
+            );
+            // The text is not visible in the memo data
+            let memo_data: [u8; 64] = memo.clone().into();
+            assert!(!memo_data.windows(5).any(|window| window == b"Thank"));
+
+            assert!(bool::from(memo.validate(
+                &alice_addr,
+                &bob.default_subaddress_view_private(),
+                tx_public_key
+            )));
+            assert!(!bool::from(memo.validate(
+                &bob_addr,
+                &bob.default_subaddress_view_private(),
+                tx_public_key
+            )));
+
+            decrypted.push(
+                memo.decrypt(
+                    &alice_addr,
+                    &bob.default_subaddress_view_private(),
+                    tx_public_key,
+                )
+                .unwrap(),
+            );
+        }
+        assert_eq!(TextMemoChunk::join(decrypted.clone()).unwrap(), text);
+
+        // Joining fails if a chunk is missing
+        assert_eq!(
+            TextMemoChunk::join(decrypted[..1].to_vec()),
+            Err(TextMemoError::MissingChunk(0))
+        );
+        assert_eq!(
+            TextMemoChunk::join(decrypted[1..].to_vec()),
+            Err(TextMemoError::MissingChunk(1))
+        );
+
+        // Decrypting with the wrong key does not produce the text
+        let (memo, tx_public_key) = &memos[0];
+        let result = memo.decrypt(
+            &alice_addr,
+            &alice.default_subaddress_view_private(),
+            tx_public_key,
+        );
+        assert_ne!(result.map(|chunk| chunk.text), Ok(String::from(chunks[0])));
+    }
+
+    #[test]
+    fn test_text_memo_rejects_bad_chunks() {
+        let mut rng: StdRng = SeedableRng::from_seed([4u8; 32]);
+        let alice_cred = SenderMemoCredential::from(&AccountKey::random(&mut rng));
+        let bob_addr = AccountKey::random(&mut rng).default_subaddress();
+        let tx_public_key = CompressedRistrettoPublic::from_random(&mut rng);
+
+        assert_eq!(
+            AuthenticatedSenderWithTextMemo::new(
+                &alice_cred,
+                bob_addr.view_public_key(),
+                &tx_public_key,
+                1,
+                1,
+                "hi"
+            ),
+            Err(TextMemoError::BadChunkIndex(1, 1))
+        );
+        assert_eq!(
+            AuthenticatedSenderWithTextMemo::new(
+                &alice_cred,
+                bob_addr.view_public_key(),
+                &tx_public_key,
+                0,
+                1,
+                &"a".repeat(30)
+            ),
+            Err(TextMemoError::ChunkTooLong(30))
+        );
+    }
+}
@@ -26,6 +26,10 @@
 //! exported, and will work as long as your memo types all implement
 //! RegisteredMemoType, and all have different MEMO_TYPE_BYTES.
 //!
+//! Alternatively, tools which need to handle memo types they don't know about
+//! at compile time can use a `MemoRegistry`, which decodes and validates the
+//! memo types registered in it at runtime. See the `registry` module.
+//!
 //! If you want to put new memo types into transactions, you will need to
 //! implement a new `MemoBuilder`. See the `memo_builder` module for examples.
 //! Or, if you don't want to use the `TransactionBuilder`, you can call
@@ -41,6 +45,7 @@
 //! | 0x0100          | Authenticated Sender Memo                         |
 //! | 0x0101          | Authenticated Sender With Payment Request Id Memo |
 //! | 0x0102          | Authenticated Sender With Payment Intent Id Memo  |
+//! | 0x0103          | Authenticated Sender With Text Memo               |
 //! | 0x0200          | Destination Memo                                  |
 //! | 0x0201          | Gift Code Funding Memo                            |
 //! | 0x0202          | Gift Code Cancellation Memo                       |
@@ -52,6 +57,9 @@ pub use self::{
     authenticated_sender::AuthenticatedSenderMemo,
     authenticated_sender_with_payment_intent_id::AuthenticatedSenderWithPaymentIntentIdMemo,
     authenticated_sender_with_payment_request_id::AuthenticatedSenderWithPaymentRequestIdMemo,
+    authenticated_sender_with_text::{
+        AuthenticatedSenderWithTextMemo, TextMemoChunk, TextMemoError,
+    },
     burn_redemption::BurnRedemptionMemo,
     credential::SenderMemoCredential,
     defragmentation::{DefragmentationMemo, DefragmentationMemoError},
@@ -61,6 +69,10 @@ pub use self::{
     gift_code_cancellation::GiftCodeCancellationMemo,
     gift_code_funding::GiftCodeFundingMemo,
     gift_code_sender::GiftCodeSenderMemo,
+    registry::{
+        MemoFieldKind, MemoFieldLayout, MemoFieldValue, MemoLayout, MemoPlugin, MemoRegistry,
+        MemoRegistryError, RegisteredMemo,
+    },
     unused::UnusedMemo,
 };
 
@@ -68,6 +80,7 @@ mod authenticated_common;
 mod authenticated_sender;
 mod authenticated_sender_with_payment_intent_id;
 mod authenticated_sender_with_payment_request_id;
+mod authenticated_sender_with_text;
 mod burn_redemption;
 mod credential;
 mod defragmentation;
@@ -78,6 +91,7 @@ mod gift_code_cancellation;
 mod gift_code_funding;
 mod gift_code_sender;
 mod macros;
+mod registry;
 mod unused;
 
 use crate::impl_memo_enum;
@@ -109,6 +123,7 @@ impl_memo_enum! { MemoType,
     AuthenticatedSender(AuthenticatedSenderMemo), //[0x01, 0x00]
     AuthenticatedSenderWithPaymentRequestId(AuthenticatedSenderWithPaymentRequestIdMemo), //[0x01, 0x01]
     AuthenticatedSenderWithPaymentIntentId(AuthenticatedSenderWithPaymentIntentIdMemo), //[0x01, 0x02]
+    AuthenticatedSenderWithText(AuthenticatedSenderWithTextMemo), //[0x01, 0x03]
     BurnRedemption(BurnRedemptionMemo), //[0x00, 0x01]
     Defragmentation(DefragmentationMemo), //[0x00, 0x03]
     Destination(DestinationMemo), //[0x02, 0x00]
@@ -212,6 +227,24 @@ mod tests {
                 panic!("unexpected deserialization");
             }
         }
+
+        let memo7 = AuthenticatedSenderWithTextMemo::new(
+            &alice_cred,
+            bob_addr.view_public_key(),
+            &tx_public_key,
+            0,
+            1,
+            "hello",
+        )
+        .unwrap();
+        match MemoType::try_from(&MemoPayload::from(memo7.clone())).unwrap() {
+            MemoType::AuthenticatedSenderWithText(memo) => {
+                assert_eq!(memo7, memo);
+            }
+            _ => {
+                panic!("unexpected deserialization");
+            }
+        }
     }
 
     #[test]
@@ -0,0 +1,499 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! A runtime registry of application-defined memo types.
+//!
+//! `MemoType` only knows about the memo types defined in this crate. Parties
+//! who allocate their own memo type bytes can implement `MemoPlugin` for
+//! their memo types and register them in a `MemoRegistry`, which lets generic
+//! tools like mobilecoind decode and validate them without knowing about them
+//! at compile time. Memo types can also be described by a `MemoLayout`, so
+//! that tools can decode them from configuration alone.
+
+use super::{MemoType, RegisteredMemoType};
+use crate::MemoPayload;
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::ops::Range;
+use displaydoc::Display;
+use mc_account_keys::PublicAddress;
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPrivate};
+use subtle::Choice;
+
+/// A memo type which can be registered in a `MemoRegistry`.
+pub trait MemoPlugin: RegisteredMemoType + 'static {
+    /// A short human-readable name for this memo type.
+    const NAME: &'static str;
+
+    /// The contents of the memo, as named fields.
+    fn fields(&self) -> BTreeMap<String, MemoFieldValue>;
+
+    /// Validate the memo, for memo types which are authenticated by the
+    /// sender. The arguments are the same as for
+    /// `AuthenticatedSenderMemo::validate`.
+    ///
+    /// Returns None if this memo type can't be validated this way.
+    fn validate(
+        &self,
+        _sender_address: &PublicAddress,
+        _receiving_subaddress_view_private_key: &RistrettoPrivate,
+        _tx_out_public_key: &CompressedRistrettoPublic,
+    ) -> Option<Choice> {
+        None
+    }
+}
+
+/// The value of a field of a registered memo.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MemoFieldValue {
+    /// A boolean
+    Bool(bool),
+    /// An unsigned integer
+    U64(u64),
+    /// UTF-8 text
+    Text(String),
+    /// Raw bytes
+    Bytes(Vec<u8>),
+}
+
+/// How a field of a memo described by a `MemoLayout` is encoded.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MemoFieldKind {
+    /// One byte, true unless zero
+    Bool,
+    /// Eight bytes, big-endian
+    U64,
+    /// UTF-8 text, padded with trailing zero bytes
+    Text,
+    /// Raw bytes
+    Bytes,
+}
+
+/// A field of a memo described by a `MemoLayout`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemoFieldLayout {
+    /// The name of the field
+    pub name: String,
+    /// The position of the field in the 64 bytes of memo data
+    pub offset: usize,
+    /// The length of the field in bytes
+    pub len: usize,
+    /// How the field is encoded
+    pub kind: MemoFieldKind,
+}
+
+impl MemoFieldLayout {
+    // The bytes of the memo data holding this field.
+    fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.len
+    }
+
+    fn decode(&self, memo_data: &[u8; 64]) -> MemoFieldValue {
+        let bytes = &memo_data[self.range()];
+        match self.kind {
+            MemoFieldKind::Bool => MemoFieldValue::Bool(bytes[0] != 0),
+            MemoFieldKind::U64 => MemoFieldValue::U64(u64::from_be_bytes(
+                bytes.try_into().expect("checked length"),
+            )),
+            MemoFieldKind::Text => {
+                let len = bytes.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+                MemoFieldValue::Text(String::from_utf8_lossy(&bytes[..len]).into_owned())
+            }
+            MemoFieldKind::Bytes => MemoFieldValue::Bytes(bytes.to_vec()),
+        }
+    }
+}
+
+/// A memo type described by where its fields are in the memo data, for memo
+/// types that are configured at runtime rather than implemented in Rust.
+/// These memos can be decoded, but not validated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemoLayout {
+    /// The memo type bytes
+    pub memo_type_bytes: [u8; 2],
+    /// A short human-readable name for this memo type
+    pub name: String,
+    /// The fields of the memo
+    pub fields: Vec<MemoFieldLayout>,
+}
+
+/// A memo decoded by a `MemoRegistry`.
+#[derive(Clone, Debug)]
+pub struct RegisteredMemo {
+    /// The memo type bytes
+    pub memo_type_bytes: [u8; 2],
+    /// The name the memo type was registered with
+    pub name: String,
+    /// The contents of the memo
+    pub fields: BTreeMap<String, MemoFieldValue>,
+    // The raw memo data, kept for validation
+    memo_data: [u8; 64],
+    // The validator of the registered memo type, if it has one
+    validate_fn: Option<ValidateFn>,
+}
+
+impl RegisteredMemo {
+    /// Validate the memo, if its memo type supports it. See
+    /// `MemoPlugin::validate`.
+    pub fn validate(
+        &self,
+        sender_address: &PublicAddress,
+        receiving_subaddress_view_private_key: &RistrettoPrivate,
+        tx_out_public_key: &CompressedRistrettoPublic,
+    ) -> Option<Choice> {
+        (self.validate_fn?)(
+            &self.memo_data,
+            sender_address,
+            receiving_subaddress_view_private_key,
+            tx_out_public_key,
+        )
+    }
+}
+
+type FieldsFn = fn(&[u8; 64]) -> BTreeMap<String, MemoFieldValue>;
+type ValidateFn =
+    fn(&[u8; 64], &PublicAddress, &RistrettoPrivate, &CompressedRistrettoPublic) -> Option<Choice>;
+
+/// A registered memo type.
+#[derive(Clone, Debug)]
+enum MemoPluginEntry {
+    /// The type-erased functions of a `MemoPlugin`.
+    Plugin {
+        name: &'static str,
+        fields_fn: FieldsFn,
+        validate_fn: ValidateFn,
+    },
+    /// A memo type registered by its layout.
+    Layout(MemoLayout),
+}
+
+impl MemoPluginEntry {
+    fn name(&self) -> &str {
+        match self {
+            Self::Plugin { name, .. } => name,
+            Self::Layout(layout) => &layout.name,
+        }
+    }
+}
+
+fn plugin_fields<T: MemoPlugin>(memo_data: &[u8; 64]) -> BTreeMap<String, MemoFieldValue> {
+    T::from(memo_data).fields()
+}
+
+fn plugin_validate<T: MemoPlugin>(
+    memo_data: &[u8; 64],
+    sender_address: &PublicAddress,
+    receiving_subaddress_view_private_key: &RistrettoPrivate,
+    tx_out_public_key: &CompressedRistrettoPublic,
+) -> Option<Choice> {
+    T::from(memo_data).validate(
+        sender_address,
+        receiving_subaddress_view_private_key,
+        tx_out_public_key,
+    )
+}
+
+/// A registry of application-defined memo types, keyed by memo type bytes.
+///
+/// The memo types defined in this crate are decoded by `MemoType`, and can't
+/// be registered here.
+#[derive(Clone, Debug, Default)]
+pub struct MemoRegistry {
+    plugins: BTreeMap<[u8; 2], MemoPluginEntry>,
+}
+
+impl MemoRegistry {
+    /// Create an empty registry.
+    pub const fn new() -> Self {
+        Self {
+            plugins: BTreeMap::new(),
+        }
+    }
+
+    /// Register a memo type.
+    pub fn register<T: MemoPlugin>(&mut self) -> Result<(), MemoRegistryError> {
+        self.check_available(T::MEMO_TYPE_BYTES)?;
+        self.plugins.insert(
+            T::MEMO_TYPE_BYTES,
+            MemoPluginEntry::Plugin {
+                name: T::NAME,
+                fields_fn: plugin_fields::<T>,
+                validate_fn: plugin_validate::<T>,
+            },
+        );
+        Ok(())
+    }
+
+    /// Register a memo type by its layout.
+    pub fn register_layout(&mut self, layout: MemoLayout) -> Result<(), MemoRegistryError> {
+        self.check_available(layout.memo_type_bytes)?;
+        for field in &layout.fields {
+            let len_ok = match field.kind {
+                MemoFieldKind::Bool => field.len == 1,
+                MemoFieldKind::U64 => field.len == 8,
+                MemoFieldKind::Text | MemoFieldKind::Bytes => field.len > 0,
+            };
+            let fits = field
+                .offset
+                .checked_add(field.len)
+                .map_or(false, |end| end <= 64);
+            if !len_ok || !fits {
+                return Err(MemoRegistryError::InvalidField(field.name.clone()));
+            }
+        }
+        self.plugins
+            .insert(layout.memo_type_bytes, MemoPluginEntry::Layout(layout));
+        Ok(())
+    }
+
+    // Check that a memo type can be registered.
+    fn check_available(&self, memo_type_bytes: [u8; 2]) -> Result<(), MemoRegistryError> {
+        if MemoType::try_from(&MemoPayload::new(memo_type_bytes, [0u8; 64])).is_ok() {
+            return Err(MemoRegistryError::BuiltinMemoType(memo_type_bytes));
+        }
+        if self.plugins.contains_key(&memo_type_bytes) {
+            return Err(MemoRegistryError::AlreadyRegistered(memo_type_bytes));
+        }
+        Ok(())
+    }
+
+    /// Whether a memo type is registered.
+    pub fn is_registered(&self, memo_type_bytes: &[u8; 2]) -> bool {
+        self.plugins.contains_key(memo_type_bytes)
+    }
+
+    /// The type bytes and names of the registered memo types.
+    pub fn registered_memo_types(&self) -> impl Iterator<Item = ([u8; 2], &str)> + '_ {
+        self.plugins
+            .iter()
+            .map(|(memo_type_bytes, entry)| (*memo_type_bytes, entry.name()))
+    }
+
+    /// Decode a memo payload, if its memo type is registered.
+    pub fn decode(&self, memo_payload: &MemoPayload) -> Option<RegisteredMemo> {
+        let memo_type_bytes = *memo_payload.get_memo_type();
+        let entry = self.plugins.get(&memo_type_bytes)?;
+        let memo_data = *memo_payload.get_memo_data();
+        let (fields, validate_fn) = match entry {
+            MemoPluginEntry::Plugin {
+                fields_fn,
+                validate_fn,
+                ..
+            } => (fields_fn(&memo_data), Some(*validate_fn)),
+            MemoPluginEntry::Layout(layout) => (
+                layout
+                    .fields
+                    .iter()
+                    .map(|field| (field.name.clone(), field.decode(&memo_data)))
+                    .collect(),
+                None,
+            ),
+        };
+        Some(RegisteredMemo {
+            memo_type_bytes,
+            name: entry.name().into(),
+            fields,
+            memo_data,
+            validate_fn,
+        })
+    }
+}
+
+/// An error that can occur when registering a memo type
+#[derive(Clone, Display, Debug, Eq, PartialEq)]
+pub enum MemoRegistryError {
+    /// Memo type {0:02X?} is defined by this crate and can't be registered
+    BuiltinMemoType([u8; 2]),
+    /// Memo type {0:02X?} is already registered
+    AlreadyRegistered([u8; 2]),
+    /// Field {0} does not fit in the memo data, or has the wrong length for its
+    /// kind
+    InvalidField(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{impl_memo_type_conversions, UnusedMemo};
+    use alloc::{borrow::ToOwned, vec};
+
+    /// A loyalty points memo, as a third party might define it.
+    #[derive(Clone, Debug)]
+    struct LoyaltyPointsMemo {
+        memo_data: [u8; 64],
+    }
+
+    impl RegisteredMemoType for LoyaltyPointsMemo {
+        const MEMO_TYPE_BYTES: [u8; 2] = [0xF0, 0x01];
+    }
+
+    impl MemoPlugin for LoyaltyPointsMemo {
+        const NAME: &'static str = "loyalty_points";
+
+        fn fields(&self) -> BTreeMap<String, MemoFieldValue> {
+            let points = u64::from_be_bytes(self.memo_data[0..8].try_into().unwrap());
+            BTreeMap::from([
+                ("points".to_owned(), MemoFieldValue::U64(points)),
+                (
+                    "expired".to_owned(),
+                    MemoFieldValue::Bool(self.memo_data[8] != 0),
+                ),
+            ])
+        }
+    }
+
+    impl From<&[u8; 64]> for LoyaltyPointsMemo {
+        fn from(src: &[u8; 64]) -> Self {
+            Self { memo_data: *src }
+        }
+    }
+
+    impl From<LoyaltyPointsMemo> for [u8; 64] {
+        fn from(src: LoyaltyPointsMemo) -> [u8; 64] {
+            src.memo_data
+        }
+    }
+
+    impl_memo_type_conversions! { LoyaltyPointsMemo }
+
+    #[derive(Clone, Debug)]
+    struct ImpostorMemo;
+
+    impl RegisteredMemoType for ImpostorMemo {
+        const MEMO_TYPE_BYTES: [u8; 2] = UnusedMemo::MEMO_TYPE_BYTES;
+    }
+
+    impl MemoPlugin for ImpostorMemo {
+        const NAME: &'static str = "impostor";
+
+        fn fields(&self) -> BTreeMap<String, MemoFieldValue> {
+            Default::default()
+        }
+    }
+
+    impl From<&[u8; 64]> for ImpostorMemo {
+        fn from(_: &[u8; 64]) -> Self {
+            Self
+        }
+    }
+
+    impl From<ImpostorMemo> for [u8; 64] {
+        fn from(_: ImpostorMemo) -> [u8; 64] {
+            [0u8; 64]
+        }
+    }
+
+    #[test]
+    fn test_registry_decodes_registered_types() {
+        let mut registry = MemoRegistry::new();
+        registry.register::<LoyaltyPointsMemo>().unwrap();
+        assert!(registry.is_registered(&[0xF0, 0x01]));
+        assert_eq!(
+            registry.register::<LoyaltyPointsMemo>(),
+            Err(MemoRegistryError::AlreadyRegistered([0xF0, 0x01]))
+        );
+        assert_eq!(
+            registry.register::<ImpostorMemo>(),
+            Err(MemoRegistryError::BuiltinMemoType([0x00, 0x00]))
+        );
+
+        let mut memo_data = [0u8; 64];
+        memo_data[0..8].copy_from_slice(&1500u64.to_be_bytes());
+        let payload = MemoPayload::from(LoyaltyPointsMemo { memo_data });
+
+        let decoded = registry.decode(&payload).unwrap();
+        assert_eq!(decoded.memo_type_bytes, [0xF0, 0x01]);
+        assert_eq!(decoded.name, "loyalty_points");
+        assert_eq!(decoded.fields["points"], MemoFieldValue::U64(1500));
+        assert_eq!(decoded.fields["expired"], MemoFieldValue::Bool(false));
+
+        assert!(registry.decode(&MemoPayload::from(UnusedMemo)).is_none());
+        assert!(registry
+            .decode(&MemoPayload::new([0xF0, 0x02], [0u8; 64]))
+            .is_none());
+    }
+
+    #[test]
+    fn test_registry_decodes_layouts() {
+        let field = |name: &str, offset, len, kind| MemoFieldLayout {
+            name: name.to_owned(),
+            offset,
+            len,
+            kind,
+        };
+        let layout = MemoLayout {
+            memo_type_bytes: [0xF0, 0x03],
+            name: "invoice".to_owned(),
+            fields: vec![
+                field("invoice_number", 0, 8, MemoFieldKind::U64),
+                field("paid", 8, 1, MemoFieldKind::Bool),
+                field("reference", 9, 16, MemoFieldKind::Text),
+                field("merchant_id", 60, 4, MemoFieldKind::Bytes),
+            ],
+        };
+
+        let mut registry = MemoRegistry::new();
+        registry.register::<LoyaltyPointsMemo>().unwrap();
+        registry.register_layout(layout.clone()).unwrap();
+        assert_eq!(
+            registry.register_layout(layout.clone()),
+            Err(MemoRegistryError::AlreadyRegistered([0xF0, 0x03]))
+        );
+        assert_eq!(
+            registry.registered_memo_types().collect::<Vec<_>>(),
+            vec![([0xF0, 0x01], "loyalty_points"), ([0xF0, 0x03], "invoice")]
+        );
+
+        let mut memo_data = [0u8; 64];
+        memo_data[0..8].copy_from_slice(&42u64.to_be_bytes());
+        memo_data[8] = 1;
+        memo_data[9..13].copy_from_slice(b"A-17");
+        memo_data[60..64].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        let decoded = registry
+            .decode(&MemoPayload::new([0xF0, 0x03], memo_data))
+            .unwrap();
+        assert_eq!(decoded.name, "invoice");
+        assert_eq!(decoded.fields["invoice_number"], MemoFieldValue::U64(42));
+        assert_eq!(decoded.fields["paid"], MemoFieldValue::Bool(true));
+        assert_eq!(
+            decoded.fields["reference"],
+            MemoFieldValue::Text("A-17".to_owned())
+        );
+        assert_eq!(
+            decoded.fields["merchant_id"],
+            MemoFieldValue::Bytes(vec![0xde, 0xad, 0xbe, 0xef])
+        );
+    }
+
+    #[test]
+    fn test_registry_rejects_bad_layouts() {
+        let layout = |kind, offset, len| MemoLayout {
+            memo_type_bytes: [0xF0, 0x04],
+            name: "bad".to_owned(),
+            fields: vec![MemoFieldLayout {
+                name: "field".to_owned(),
+                offset,
+                len,
+                kind,
+            }],
+        };
+        let mut registry = MemoRegistry::new();
+        for bad in [
+            layout(MemoFieldKind::U64, 0, 4),
+            layout(MemoFieldKind::Bool, 0, 2),
+            layout(MemoFieldKind::Bytes, 60, 8),
+            layout(MemoFieldKind::Bytes, usize::MAX, 2),
+            layout(MemoFieldKind::Text, 0, 0),
+        ] {
+            assert_eq!(
+                registry.register_layout(bad),
+                Err(MemoRegistryError::InvalidField("field".to_owned()))
+            );
+        }
+
+        let mut builtin = layout(MemoFieldKind::Bytes, 0, 64);
+        builtin.memo_type_bytes = UnusedMemo::MEMO_TYPE_BYTES;
+        assert_eq!(
+            registry.register_layout(builtin),
+            Err(MemoRegistryError::BuiltinMemoType([0x00, 0x00]))
+        );
+    }
+}
@@ -14,6 +14,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use mc_core::keys::TxOutPublic;
 use mc_crypto_keys::RistrettoPublic;
 use mc_crypto_ring_signature_signer::RingSigner;
+use mc_fog_report_types::FogReportBundle;
 use mc_transaction_core::{
     ring_ct::{
         Error as RingCtError, ExtendedMessageDigest, InputRing, SignatureRctBulletproofs,
@@ -71,6 +72,11 @@ pub enum Operations {
         /// Output file to write signed transaction
         #[clap(long)]
         output: String,
+
+        /// Fog report bundle the transaction was built with. Signing fails if
+        /// the transaction's tombstone block is past the bundle's expiry
+        #[clap(long)]
+        fog_bundle: Option<String>,
     },
 }
 
@@ -156,11 +162,22 @@ impl Operations {
     ///
     /// input - file containing the unsigned transaction object
     /// output - file to write the signed transaction output
-    pub fn sign_tx(ctx: impl RingSigner, input: &str, output: &str) -> anyhow::Result<()> {
+    /// fog_bundle - optional fog report bundle the transaction was built with
+    pub fn sign_tx(
+        ctx: impl RingSigner,
+        input: &str,
+        output: &str,
+        fog_bundle: Option<&str>,
+    ) -> anyhow::Result<()> {
         // Load unsigned transaction object
         debug!("Reading unsigned transaction from '{}'", input);
         let req: TxSignReq = read_input(input)?;
 
+        // Check the fog pubkeys used for the outputs outlive the transaction
+        if let Some(fog_bundle) = fog_bundle {
+            check_fog_bundle(fog_bundle, req.tx_prefix.tombstone_block)?;
+        }
+
         // Sign transaction
         let prefix = req.tx_prefix.clone();
         let signature = match SignatureRctBulletproofs::sign(
@@ -212,6 +229,17 @@ impl Operations {
     }
 }
 
+/// Check that a fog report bundle file can be used for a transaction with the
+/// given tombstone block
+pub fn check_fog_bundle(fog_bundle: &str, tombstone_block: u64) -> anyhow::Result<()> {
+    debug!("Reading fog report bundle from '{}'", fog_bundle);
+    let bundle: FogReportBundle = read_input(fog_bundle)?;
+    if let Err(e) = bundle.check_tombstone_block(tombstone_block) {
+        return Err(anyhow::anyhow!("Fog report bundle is not usable: {}", e));
+    }
+    Ok(())
+}
+
 /// Helper to read and deserialize input files
 pub fn read_input<T: DeserializeOwned>(file_name: &str) -> anyhow::Result<T> {
     debug!("Reading input from '{}'", file_name);
@@ -317,3 +345,90 @@ impl TxSignReq {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_crypto_ring_signature_signer::LocalRingSigner;
+    use mc_fog_report_types::{Report, ReportResponse};
+    use mc_transaction_core::{tx::TxPrefix, AccountKey, BlockVersion};
+    use tempfile::TempDir;
+
+    fn path(dir: &TempDir, name: &str) -> String {
+        dir.path().join(name).to_str().unwrap().to_owned()
+    }
+
+    /// Write a bundle with a single report, claiming `claimed_pubkey_expiry`
+    /// as the bundle's expiry.
+    fn write_bundle(dir: &TempDir, pubkey_expiry: u64, claimed_pubkey_expiry: u64) -> String {
+        let response = ReportResponse {
+            reports: vec![Report {
+                fog_report_id: String::new(),
+                attestation_evidence: None,
+                pubkey_expiry,
+            }],
+            chain: vec![],
+            signature: vec![],
+        };
+        let mut bundle =
+            FogReportBundle::new([("fog://fog.example.com".to_owned(), response)].into(), 0);
+        bundle.pubkey_expiry = claimed_pubkey_expiry;
+
+        let file_name = path(dir, "bundle.json");
+        write_output(&file_name, &bundle).unwrap();
+        file_name
+    }
+
+    fn write_request(dir: &TempDir, tombstone_block: u64) -> String {
+        let req = TxSignReq {
+            account_id: AccountId::from([0u8; 32]),
+            tx_prefix: TxPrefix {
+                tombstone_block,
+                ..Default::default()
+            },
+            rings: vec![],
+            secrets: TxSignSecrets::OutputSecrets(vec![]),
+            block_version: BlockVersion::MAX,
+        };
+
+        let file_name = path(dir, "unsigned.json");
+        write_output(&file_name, &req).unwrap();
+        file_name
+    }
+
+    #[test]
+    fn sign_tx_rejects_expired_fog_bundle() {
+        let dir = TempDir::new().unwrap();
+        let signer = LocalRingSigner::from(&AccountKey::random(&mut OsRng));
+        let input = write_request(&dir, 11);
+        let output = path(&dir, "signed.json");
+        let fog_bundle = write_bundle(&dir, 10, 10);
+
+        let err = Operations::sign_tx(&signer, &input, &output, Some(&fog_bundle)).unwrap_err();
+        assert!(err.to_string().contains("expires at block 10"), "{err}");
+        assert!(!Path::new(&output).exists());
+    }
+
+    #[test]
+    fn sign_tx_recomputes_fog_bundle_expiry() {
+        let dir = TempDir::new().unwrap();
+        let signer = LocalRingSigner::from(&AccountKey::random(&mut OsRng));
+        let input = write_request(&dir, 50);
+        let output = path(&dir, "signed.json");
+        let fog_bundle = write_bundle(&dir, 10, 100);
+
+        let err = Operations::sign_tx(&signer, &input, &output, Some(&fog_bundle)).unwrap_err();
+        assert!(err.to_string().contains("expires at block 10"), "{err}");
+        assert!(!Path::new(&output).exists());
+    }
+
+    #[test]
+    fn check_fog_bundle_accepts_tombstone_block_up_to_expiry() {
+        let dir = TempDir::new().unwrap();
+        let fog_bundle = write_bundle(&dir, 10, 10);
+
+        check_fog_bundle(&fog_bundle, 9).unwrap();
+        check_fog_bundle(&fog_bundle, 10).unwrap();
+        assert!(check_fog_bundle(&fog_bundle, 11).is_err());
+    }
+}
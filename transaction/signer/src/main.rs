@@ -8,13 +8,17 @@ use std::path::Path;
 
 use bip39::{Language, Mnemonic, MnemonicType};
 use clap::Parser;
-use log::{debug, info};
+use log::{debug, error, info};
 use serde::{Deserialize, Serialize};
 
 use mc_core::{account::Account, slip10::Slip10KeyGenerator};
 use mc_crypto_ring_signature_signer::LocalRingSigner;
 use mc_transaction_core::AccountKey;
 use mc_transaction_signer::{read_input, write_output, Operations};
+use mc_util_encrypted_keyfile::{
+    decrypt_json_value, password_from_env, password_from_env_or_prompt, EncryptedKeyfile,
+};
+use rand_core::OsRng;
 
 #[derive(Clone, PartialEq, Debug, Parser)]
 struct Args {
@@ -22,6 +26,13 @@ struct Args {
     #[clap(long, short, default_value = "mc_secrets.json")]
     secret_file: String,
 
+    /// Encrypt new account secrets files with a password. The password is
+    /// read from MC_KEYFILE_PASSWORD, or prompted for if that is not set.
+    /// New secrets files are also encrypted whenever MC_KEYFILE_PASSWORD is
+    /// set, and are written in plaintext otherwise.
+    #[clap(long)]
+    encrypt: bool,
+
     #[command(subcommand)]
     action: Actions,
 }
@@ -86,14 +97,39 @@ fn main() -> anyhow::Result<()> {
                 ));
             }
 
-            // Otherwise write out new secrets
-            write_output(output, &s)?;
+            // Otherwise write out new secrets, encrypted if we have a password
+            let password = if args.encrypt {
+                Some(
+                    password_from_env_or_prompt(true)
+                        .map_err(|e| anyhow::anyhow!("failed to read password: {}", e))?,
+                )
+            } else {
+                password_from_env()
+            };
+            match password {
+                Some(password) => {
+                    let envelope = EncryptedKeyfile::encrypt_json(&s, &password, &mut OsRng)
+                        .map_err(|e| anyhow::anyhow!("failed to encrypt secrets: {}", e))?;
+                    write_output(output, &envelope)?;
+                }
+                None => write_output(output, &s)?,
+            }
 
             info!("Account secrets written to '{}'", output);
         }
         Actions::Signer(c) => {
-            // Load account secrets
-            let secrets: AccountSecrets = read_input(&args.secret_file)?;
+            // Load account secrets, asking for the password if they are encrypted
+            let secrets = read_input(&args.secret_file)?;
+            let secrets =
+                decrypt_json_value(secrets, || match password_from_env_or_prompt(false) {
+                    Ok(password) => Some(password),
+                    Err(e) => {
+                        error!("failed to read password: {}", e);
+                        None
+                    }
+                })
+                .map_err(|e| anyhow::anyhow!("failed to decrypt secrets: {}", e))?;
+            let secrets: AccountSecrets = serde_json::from_value(secrets)?;
             let mnemonic = Mnemonic::from_phrase(&secrets.mnemonic, Language::English)?;
 
             // Perform SLIP-0010 derivation
@@ -113,7 +149,12 @@ fn main() -> anyhow::Result<()> {
                 Operations::SyncTxos { input, output, .. } => {
                     Operations::sync_txos(&a, input, output)?
                 }
-                Operations::SignTx { input, output, .. } => {
+                Operations::SignTx {
+                    input,
+                    output,
+                    fog_bundle,
+                    ..
+                } => {
                     // Setup local ring signer
                     let ring_signer = LocalRingSigner::from(&AccountKey::new(
                         a.spend_private_key().as_ref(),
@@ -121,7 +162,7 @@ fn main() -> anyhow::Result<()> {
                     ));
 
                     // Perform transaction signing
-                    Operations::sign_tx(&ring_signer, input, output)?;
+                    Operations::sign_tx(&ring_signer, input, output, fog_bundle.as_deref())?;
                 }
                 _ => (),
             }
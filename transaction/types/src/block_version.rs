@@ -154,6 +154,12 @@ impl BlockVersion {
     pub fn nested_multisigs_are_supported(&self) -> bool {
         self >= &Self::THREE
     }
+
+    /// Valid-from block and max block timestamp input rules are supported
+    /// starting from v4.
+    pub fn input_rules_validity_window_is_supported(&self) -> bool {
+        self >= &Self::FOUR
+    }
 }
 
 impl Deref for BlockVersion {
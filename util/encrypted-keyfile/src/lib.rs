@@ -0,0 +1,353 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+#![doc = include_str!("../README.md")]
+#![deny(missing_docs)]
+
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, Payload},
+    Aes256Gcm, KeyInit,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use displaydoc::Display;
+use rand_core::{CryptoRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+pub use zeroize::Zeroizing;
+
+/// The value of the `format` field which marks an encrypted key file.
+pub const ENCRYPTED_KEYFILE_FORMAT: &str = "mc-encrypted-keyfile";
+
+/// The current version of the encrypted key file format.
+pub const ENCRYPTED_KEYFILE_VERSION: u32 = 1;
+
+/// The environment variable tools read a key file password from.
+pub const KEYFILE_PASSWORD_ENV: &str = "MC_KEYFILE_PASSWORD";
+
+/// The largest Argon2 memory cost, in KiB, accepted when decrypting. This
+/// keeps a malicious file from making the reader allocate without bound.
+pub const MAX_MEMORY_COST: u32 = 1 << 20;
+
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// An error which can occur when encrypting or decrypting a key file.
+#[derive(Clone, Debug, Display, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Error {
+    /// Not an encrypted key file
+    NotEncrypted,
+    /// The key file is encrypted, but no password was given
+    PasswordRequired,
+    /// Unsupported encrypted key file version: {0}
+    UnsupportedVersion(u32),
+    /// Invalid key derivation parameters: {0}
+    Kdf(String),
+    /// Wrong password, or the key file is corrupted
+    Decryption,
+    /// Encryption failed
+    Encryption,
+    /// JSON error: {0}
+    Json(String),
+    /// Could not read the password: {0}
+    Prompt(String),
+    /// The passwords do not match
+    PasswordMismatch,
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(src: serde_json::Error) -> Self {
+        Self::Json(src.to_string())
+    }
+}
+
+/// Argon2id parameters used to derive the encryption key from a password.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct KdfParams {
+    /// Memory cost, in KiB.
+    pub m_cost: u32,
+    /// Number of iterations.
+    pub t_cost: u32,
+    /// Degree of parallelism.
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    fn derive_key(&self, password: &[u8], salt: &[u8]) -> Result<Zeroizing<[u8; KEY_LEN]>, Error> {
+        if self.m_cost > MAX_MEMORY_COST {
+            return Err(Error::Kdf(format!(
+                "memory cost {} is too large",
+                self.m_cost
+            )));
+        }
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(KEY_LEN))
+            .map_err(|e| Error::Kdf(e.to_string()))?;
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password, salt, &mut key[..])
+            .map_err(|e| Error::Kdf(e.to_string()))?;
+        Ok(key)
+    }
+}
+
+/// A password-encrypted key file.
+///
+/// The header fields are bound to the ciphertext as associated data, so they
+/// can't be altered without decryption failing.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EncryptedKeyfile {
+    /// Always [ENCRYPTED_KEYFILE_FORMAT].
+    pub format: String,
+    /// The format version.
+    pub version: u32,
+    /// The key derivation parameters.
+    pub kdf: KdfParams,
+    /// The key derivation salt, 16 bytes long.
+    #[serde(with = "hex::serde")]
+    pub salt: Vec<u8>,
+    /// The AES-256-GCM nonce.
+    #[serde(with = "hex::serde")]
+    pub nonce: Vec<u8>,
+    /// The encrypted key file contents, including the authentication tag.
+    #[serde(with = "hex::serde")]
+    pub ciphertext: Vec<u8>,
+}
+
+impl EncryptedKeyfile {
+    /// Encrypt key file contents with a password, using the default key
+    /// derivation parameters.
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        plaintext: &[u8],
+        password: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, Error> {
+        Self::encrypt_with_params(plaintext, password, KdfParams::default(), rng)
+    }
+
+    /// Encrypt key file contents with a password, using the given key
+    /// derivation parameters.
+    pub fn encrypt_with_params<R: RngCore + CryptoRng>(
+        plaintext: &[u8],
+        password: &[u8],
+        kdf: KdfParams,
+        rng: &mut R,
+    ) -> Result<Self, Error> {
+        let mut salt = vec![0u8; SALT_LEN];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = vec![0u8; NONCE_LEN];
+        rng.fill_bytes(&mut nonce);
+
+        let mut result = Self {
+            format: ENCRYPTED_KEYFILE_FORMAT.to_owned(),
+            version: ENCRYPTED_KEYFILE_VERSION,
+            kdf,
+            salt,
+            nonce,
+            ciphertext: Vec::new(),
+        };
+
+        let key = kdf.derive_key(password, &result.salt)?;
+        let aad = result.associated_data();
+        result.ciphertext = Aes256Gcm::new(GenericArray::from_slice(&key[..]))
+            .encrypt(
+                GenericArray::from_slice(&result.nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| Error::Encryption)?;
+        Ok(result)
+    }
+
+    /// Decrypt the key file contents.
+    pub fn decrypt(&self, password: &[u8]) -> Result<Zeroizing<Vec<u8>>, Error> {
+        if self.format != ENCRYPTED_KEYFILE_FORMAT {
+            return Err(Error::NotEncrypted);
+        }
+        if self.version != ENCRYPTED_KEYFILE_VERSION {
+            return Err(Error::UnsupportedVersion(self.version));
+        }
+        // Reject malformed headers before paying for key derivation.
+        if self.nonce.len() != NONCE_LEN || self.salt.len() != SALT_LEN {
+            return Err(Error::Decryption);
+        }
+
+        let key = self.kdf.derive_key(password, &self.salt)?;
+        let aad = self.associated_data();
+        Aes256Gcm::new(GenericArray::from_slice(&key[..]))
+            .decrypt(
+                GenericArray::from_slice(&self.nonce),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| Error::Decryption)
+    }
+
+    /// Serialize a value to JSON and encrypt it.
+    pub fn encrypt_json<T: Serialize, R: RngCore + CryptoRng>(
+        value: &T,
+        password: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, Error> {
+        let plaintext = Zeroizing::new(serde_json::to_vec(value)?);
+        Self::encrypt(&plaintext, password, rng)
+    }
+
+    /// Decrypt and deserialize a JSON value.
+    pub fn decrypt_json<T: DeserializeOwned>(&self, password: &[u8]) -> Result<T, Error> {
+        Ok(serde_json::from_slice(&self.decrypt(password)?)?)
+    }
+
+    /// If a parsed JSON key file is encrypted, return its envelope.
+    pub fn from_json_value(value: &serde_json::Value) -> Option<Self> {
+        if value.get("format")?.as_str()? != ENCRYPTED_KEYFILE_FORMAT {
+            return None;
+        }
+        serde_json::from_value(value.clone()).ok()
+    }
+
+    /// The header bytes which are authenticated along with the ciphertext.
+    fn associated_data(&self) -> Vec<u8> {
+        let mut aad = Vec::new();
+        aad.extend_from_slice(self.format.as_bytes());
+        aad.extend_from_slice(&self.version.to_le_bytes());
+        aad.extend_from_slice(&self.kdf.m_cost.to_le_bytes());
+        aad.extend_from_slice(&self.kdf.t_cost.to_le_bytes());
+        aad.extend_from_slice(&self.kdf.p_cost.to_le_bytes());
+        aad.extend_from_slice(&self.salt);
+        aad
+    }
+}
+
+/// Decrypt a parsed JSON key file if it is encrypted, or return it unchanged
+/// if it is plaintext. `password` is only called for encrypted files.
+pub fn decrypt_json_value(
+    value: serde_json::Value,
+    password: impl FnOnce() -> Option<Zeroizing<Vec<u8>>>,
+) -> Result<serde_json::Value, Error> {
+    match EncryptedKeyfile::from_json_value(&value) {
+        Some(envelope) => {
+            let password = password().ok_or(Error::PasswordRequired)?;
+            Ok(serde_json::from_slice(&envelope.decrypt(&password)?)?)
+        }
+        None => Ok(value),
+    }
+}
+
+/// Read the key file password from [KEYFILE_PASSWORD_ENV], if it is set.
+pub fn password_from_env() -> Option<Zeroizing<Vec<u8>>> {
+    std::env::var(KEYFILE_PASSWORD_ENV)
+        .ok()
+        .map(|password| Zeroizing::new(password.into_bytes()))
+}
+
+/// Read the key file password from [KEYFILE_PASSWORD_ENV] if it is set, and
+/// otherwise prompt for it on the terminal. If `confirm` is set, a prompted
+/// password has to be entered twice, which should be done for passwords that
+/// new key files are encrypted with.
+pub fn password_from_env_or_prompt(confirm: bool) -> Result<Zeroizing<Vec<u8>>, Error> {
+    if let Some(password) = password_from_env() {
+        return Ok(password);
+    }
+    let password = prompt_password("Key file password: ")?;
+    if confirm && prompt_password("Confirm key file password: ")? != password {
+        return Err(Error::PasswordMismatch);
+    }
+    Ok(password)
+}
+
+fn prompt_password(prompt: &str) -> Result<Zeroizing<Vec<u8>>, Error> {
+    rpassword::prompt_password(prompt)
+        .map(|password| Zeroizing::new(password.into_bytes()))
+        .map_err(|e| Error::Prompt(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_util_test_helper::{RngType, SeedableRng};
+
+    /// Cheap parameters, so the tests run quickly.
+    const TEST_PARAMS: KdfParams = KdfParams {
+        m_cost: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    #[test]
+    fn round_trip() {
+        let mut rng = RngType::from_seed([1u8; 32]);
+        let value = serde_json::json!({ "mnemonic": "abandon abandon", "account_index": 0 });
+        let plaintext = serde_json::to_vec(&value).unwrap();
+
+        let envelope =
+            EncryptedKeyfile::encrypt_with_params(&plaintext, b"hunter2", TEST_PARAMS, &mut rng)
+                .unwrap();
+        let json = serde_json::to_value(&envelope).unwrap();
+
+        let decrypted =
+            decrypt_json_value(json.clone(), || Some(Zeroizing::new(b"hunter2".to_vec()))).unwrap();
+        assert_eq!(decrypted, value);
+
+        assert_eq!(
+            decrypt_json_value(json, || Some(Zeroizing::new(b"hunter3".to_vec()))),
+            Err(Error::Decryption)
+        );
+
+        // Plaintext files pass through without asking for a password.
+        assert_eq!(
+            decrypt_json_value(value.clone(), || panic!("password requested")).unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn header_is_authenticated() {
+        let mut rng = RngType::from_seed([2u8; 32]);
+        let mut envelope =
+            EncryptedKeyfile::encrypt_with_params(b"{}", b"password", TEST_PARAMS, &mut rng)
+                .unwrap();
+        envelope.kdf.t_cost = 2;
+        assert_eq!(envelope.decrypt(b"password"), Err(Error::Decryption));
+
+        envelope.kdf.t_cost = 1;
+        envelope.version = 2;
+        assert_eq!(
+            envelope.decrypt(b"password"),
+            Err(Error::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn salt_length_is_checked_before_key_derivation() {
+        let mut rng = RngType::from_seed([3u8; 32]);
+        let envelope =
+            EncryptedKeyfile::encrypt_with_params(b"{}", b"password", TEST_PARAMS, &mut rng)
+                .unwrap();
+
+        // Parameters the KDF rejects show whether it ran.
+        let mut bad_kdf = envelope.clone();
+        bad_kdf.kdf.m_cost = MAX_MEMORY_COST + 1;
+        assert!(matches!(bad_kdf.decrypt(b"password"), Err(Error::Kdf(_))));
+
+        for salt_len in [0, SALT_LEN - 1, SALT_LEN + 1, 1 << 20] {
+            let mut envelope = envelope.clone();
+            envelope.salt = vec![0u8; salt_len];
+            envelope.kdf.m_cost = MAX_MEMORY_COST + 1;
+            assert_eq!(envelope.decrypt(b"password"), Err(Error::Decryption));
+        }
+    }
+}
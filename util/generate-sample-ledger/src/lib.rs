@@ -40,6 +40,42 @@ pub fn bootstrap_ledger(
     max_token_id: u64,
     logger: Logger,
 ) {
+    bootstrap_ledger_db(
+        path,
+        recipients,
+        outputs_per_recipient_per_block,
+        num_blocks,
+        seed,
+        max_token_id,
+        logger,
+    );
+
+    // Write conf.json
+    let mut file = std::fs::File::create("conf.json").expect("File creation");
+    use std::io::Write;
+    write!(&mut file,
+           r##"{{ "NUM_KEYS": {}, "NUM_UTXOS_PER_ACCOUNT": {}, "NUM_BLOCKS": {}, "NUM_EXTRA_KEY_IMAGES_PER_BLOCK": {}, "GIT_COMMIT": "{}" }}"##,
+           recipients.len(),
+           outputs_per_recipient_per_block,
+           num_blocks,
+           key_images_per_block,
+           mc_util_build_info::git_commit(),
+    ).expect("File I/O");
+}
+
+/// Deterministically populates a testnet ledger, like [bootstrap_ledger],
+/// without writing a `conf.json` to the working directory.
+///
+/// Returns the opened ledger.
+pub fn bootstrap_ledger_db(
+    path: &Path,
+    recipients: &[PublicAddress],
+    outputs_per_recipient_per_block: usize,
+    num_blocks: usize,
+    seed: Option<[u8; 32]>,
+    max_token_id: u64,
+    logger: Logger,
+) -> LedgerDB {
     // Create the DB
     std::fs::create_dir_all(path).expect("Could not create ledger dir");
     LedgerDB::create(path).expect("Could not create ledger_db");
@@ -96,15 +132,5 @@ pub fn bootstrap_ledger(
 
     log::info!(logger, "Wrote LedgerDB to {:?}", path);
 
-    // Write conf.json
-    let mut file = std::fs::File::create("conf.json").expect("File creation");
-    use std::io::Write;
-    write!(&mut file,
-           r##"{{ "NUM_KEYS": {}, "NUM_UTXOS_PER_ACCOUNT": {}, "NUM_BLOCKS": {}, "NUM_EXTRA_KEY_IMAGES_PER_BLOCK": {}, "GIT_COMMIT": "{}" }}"##,
-           recipients.len(),
-           outputs_per_recipient_per_block,
-           num_blocks,
-           key_images_per_block,
-           mc_util_build_info::git_commit(),
-    ).expect("File I/O");
+    db
 }
@@ -4,19 +4,121 @@
 //! A CLI tool for generating individual MobileCoin identities
 
 use bip39::{Language, Mnemonic};
-use clap::Parser;
-use mc_util_keyfile::{config::KeyConfig, keygen};
+use clap::{Parser, Subcommand};
+use mc_rand::McRng;
+use mc_util_encrypted_keyfile::{
+    password_from_env, password_from_env_or_prompt, EncryptedKeyfile, Zeroizing,
+};
+use mc_util_keyfile::{
+    config::KeyConfig,
+    keygen,
+    shamir::{self, SecretShare},
+};
 use rand::{RngCore, SeedableRng};
 use rand_hc::Hc128Rng;
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
 
 /// Keygen config.
 #[derive(Debug, Parser)]
+#[clap(subcommand_negates_reqs = true)]
 struct Config {
     #[clap(flatten)]
     pub general: KeyConfig,
 
     /// The key name.
-    pub name: String,
+    #[clap(required = true)]
+    pub name: Option<String>,
+
+    /// Encrypt written keyfiles with a password. The password is read from
+    /// MC_KEYFILE_PASSWORD, or prompted for if that is not set. Keyfiles are
+    /// also encrypted whenever MC_KEYFILE_PASSWORD is set, and are written in
+    /// plaintext otherwise.
+    #[clap(long)]
+    pub encrypt: bool,
+
+    /// Shamir secret sharing backups of existing keyfiles.
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Shamir secret sharing subcommands.
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Split the secret in a keyfile into shares, printed one per line.
+    Split {
+        /// The keyfile to split, in either the mnemonic or root entropy format.
+        keyfile: PathBuf,
+
+        /// The number of shares needed to recover the secret.
+        #[clap(long, short)]
+        threshold: u8,
+
+        /// The number of shares to generate.
+        #[clap(long, short)]
+        shares: u8,
+    },
+
+    /// Check that shares are well-formed and, if there are enough of them,
+    /// that they recombine into a valid secret.
+    VerifyShares {
+        /// The shares to check.
+        #[clap(required = true)]
+        shares: Vec<String>,
+    },
+
+    /// Encrypt an existing plaintext keyfile in place with the password.
+    Encrypt {
+        /// The keyfile to encrypt, in either the mnemonic or root entropy
+        /// format.
+        keyfile: PathBuf,
+    },
+
+    /// Recombine shares into a keyfile, written to the output directory.
+    Combine {
+        /// The key name.
+        name: String,
+
+        /// The account index to write into a mnemonic keyfile.
+        #[clap(long, default_value = "0")]
+        account_index: u32,
+
+        /// The shares to recombine.
+        #[clap(required = true)]
+        shares: Vec<String>,
+    },
+}
+
+fn parse_shares(shares: &[String]) -> Vec<SecretShare> {
+    shares
+        .iter()
+        .enumerate()
+        .map(|(i, share)| {
+            share
+                .parse()
+                .unwrap_or_else(|err| panic!("Share {} is not valid: {err}", i + 1))
+        })
+        .collect()
+}
+
+/// Read the password for a keyfile that is about to be read, prompting for it
+/// only if the keyfile is encrypted and MC_KEYFILE_PASSWORD is not set.
+fn read_password(keyfile: &Path) -> Option<Zeroizing<Vec<u8>>> {
+    let file = File::open(keyfile).expect("Could not open keyfile");
+    let value: serde_json::Value = serde_json::from_reader(file).expect("Could not parse keyfile");
+    EncryptedKeyfile::from_json_value(&value)?;
+    Some(password_from_env_or_prompt(false).expect("Could not read password"))
+}
+
+/// Read the password to encrypt written keyfiles with, if any.
+fn write_password(encrypt: bool) -> Option<Zeroizing<Vec<u8>>> {
+    if encrypt {
+        Some(password_from_env_or_prompt(true).expect("Could not read password"))
+    } else {
+        password_from_env()
+    }
 }
 
 fn main() {
@@ -33,25 +135,104 @@ fn main() {
         .fog_authority_spki
         .as_ref()
         .map(AsRef::<[u8]>::as_ref);
-    let name = config.name.as_str();
-
-    let mut csprng = Hc128Rng::from_seed(config.general.seed);
-
-    let mut entropy = [0u8; 32];
-    csprng.fill_bytes(&mut entropy[..]);
-    let mnemonic = Mnemonic::from_entropy(&entropy, Language::English)
-        .expect("Could not create mnemonic from entropy");
-
-    println!("Writing to {path:?}");
-
-    keygen::write_keyfiles(
-        path,
-        name,
-        &mnemonic,
-        0,
-        fog_report_url,
-        &fog_report_id,
-        fog_authority_spki,
-    )
-    .expect("Could not write keyfile");
+
+    match config.command {
+        Some(Command::Split {
+            keyfile,
+            threshold,
+            shares,
+        }) => {
+            let mut rng = McRng::default();
+            let password = read_password(&keyfile);
+            let shares = keygen::split_keyfile(
+                &keyfile,
+                password.as_deref().map(Vec::as_slice),
+                threshold,
+                shares,
+                &mut rng,
+            )
+            .expect("Could not split keyfile");
+            for share in shares {
+                println!("{share}");
+            }
+        }
+        Some(Command::Encrypt { keyfile }) => {
+            let password = password_from_env_or_prompt(true).expect("Could not read password");
+            mc_util_keyfile::encrypt_keyfile(&keyfile, &password, &mut McRng::default())
+                .expect("Could not encrypt keyfile");
+            println!("Encrypted {keyfile:?}");
+        }
+        Some(Command::VerifyShares { shares }) => {
+            let shares = parse_shares(&shares);
+            println!("All {} shares are well-formed", shares.len());
+            if shares.len() >= shares[0].threshold as usize {
+                let (kind, _secret) =
+                    shamir::combine_shares(&shares).expect("Shares do not recombine");
+                println!("Shares recombine into a valid {kind}");
+            } else {
+                println!(
+                    "{} shares are needed to check recombination",
+                    shares[0].threshold
+                );
+            }
+        }
+        Some(Command::Combine {
+            name,
+            account_index,
+            shares,
+        }) => {
+            let shares = parse_shares(&shares);
+            let password = write_password(config.encrypt);
+            println!("Writing to {path:?}");
+            let kind = keygen::write_keyfiles_from_shares(
+                path,
+                &name,
+                &shares,
+                account_index,
+                fog_report_url,
+                &fog_report_id,
+                fog_authority_spki,
+                password.as_deref().map(Vec::as_slice),
+            )
+            .expect("Could not recombine shares into keyfile");
+            println!("Recovered {kind}");
+        }
+        None => {
+            let name = config.name.expect("The key name is required");
+
+            let mut csprng = Hc128Rng::from_seed(config.general.seed);
+
+            let mut entropy = [0u8; 32];
+            csprng.fill_bytes(&mut entropy[..]);
+            let mnemonic = Mnemonic::from_entropy(&entropy, Language::English)
+                .expect("Could not create mnemonic from entropy");
+
+            let password = write_password(config.encrypt);
+            println!("Writing to {path:?}");
+
+            match password {
+                Some(password) => keygen::write_encrypted_keyfiles(
+                    path,
+                    &name,
+                    &mnemonic,
+                    0,
+                    fog_report_url,
+                    &fog_report_id,
+                    fog_authority_spki,
+                    &password,
+                    &mut McRng::default(),
+                ),
+                None => keygen::write_keyfiles(
+                    path,
+                    &name,
+                    &mnemonic,
+                    0,
+                    fog_report_url,
+                    &fog_report_id,
+                    fog_authority_spki,
+                ),
+            }
+            .expect("Could not write keyfile");
+        }
+    }
 }
@@ -3,9 +3,10 @@
 //! This module contains the error type which methods of the `keyfile` crate can
 //! generate.
 
-use crate::mnemonic_acct::Error as MnemonicAccountError;
+use crate::{mnemonic_acct::Error as MnemonicAccountError, shamir::ShamirError};
 use displaydoc::Display;
 use mc_account_keys::Error as AccountKeyError;
+use mc_util_encrypted_keyfile::Error as EncryptedKeyfileError;
 use prost::{DecodeError as ProstDecodeError, EncodeError as ProstEncodeError};
 use serde_json::Error as JsonError;
 use std::io::Error as IoError;
@@ -29,6 +30,12 @@ pub enum Error {
     MnemonicSize,
     /// Fog details are all or nothing, some were missing
     MissingFogDetails,
+    /// Shamir secret sharing error: {0}
+    Shamir(ShamirError),
+    /// Encrypted keyfile error: {0}
+    EncryptedKeyfile(EncryptedKeyfileError),
+    /// The keyfile is already encrypted
+    AlreadyEncrypted,
 }
 
 impl From<AccountKeyError> for Error {
@@ -66,3 +73,15 @@ impl From<MnemonicAccountError> for Error {
         Error::MnemonicAccount(src)
     }
 }
+
+impl From<ShamirError> for Error {
+    fn from(src: ShamirError) -> Error {
+        Error::Shamir(src)
+    }
+}
+
+impl From<EncryptedKeyfileError> for Error {
+    fn from(src: EncryptedKeyfileError) -> Error {
+        Error::EncryptedKeyfile(src)
+    }
+}
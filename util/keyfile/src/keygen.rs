@@ -5,13 +5,18 @@
 //! `mc_account_keys::PublicAddress` respectively.
 
 use crate::{
-    error::Error, read_keyfile, read_pubfile, read_root_entropy_keyfile, write_b58pubfile,
-    write_keyfile, write_pubfile,
+    error::Error,
+    parse_keyfile_value, read_keyfile, read_pubfile, read_root_entropy_keyfile,
+    shamir::{self, SecretKind, SecretShare},
+    write_b58pubfile, write_encrypted_keyfile, write_keyfile, write_pubfile, RootIdentityJson,
+    UncheckedMnemonicAccount,
 };
 use bip39::{Language, Mnemonic};
 use mc_account_keys::{AccountKey, PublicAddress, RootIdentity};
 use mc_core::slip10::Slip10KeyGenerator;
-use rand_core::{RngCore, SeedableRng};
+use mc_rand::McRng;
+use mc_util_encrypted_keyfile::EncryptedKeyfile;
+use rand_core::{CryptoRng, RngCore, SeedableRng};
 use rand_hc::Hc128Rng;
 use std::{
     cmp::Ordering,
@@ -29,6 +34,70 @@ pub fn write_keyfiles<P: AsRef<Path>>(
     fog_report_url: Option<&str>,
     fog_report_id: &str,
     fog_authority_spki: Option<&[u8]>,
+) -> Result<(), Error> {
+    write_pubfiles(
+        &path,
+        name,
+        mnemonic,
+        account_index,
+        fog_report_url,
+        fog_report_id,
+        fog_authority_spki,
+    )?;
+    write_keyfile(
+        path.as_ref().join(name).with_extension("json"),
+        mnemonic,
+        account_index,
+        fog_report_url,
+        fog_report_id,
+        fog_authority_spki,
+    )
+}
+
+/// Write a single pair of keyfiles using a given name and data, with the
+/// private keyfile encrypted with a password
+#[allow(clippy::too_many_arguments)]
+pub fn write_encrypted_keyfiles<P: AsRef<Path>, R: RngCore + CryptoRng>(
+    path: P,
+    name: &str,
+    mnemonic: &Mnemonic,
+    account_index: u32,
+    fog_report_url: Option<&str>,
+    fog_report_id: &str,
+    fog_authority_spki: Option<&[u8]>,
+    password: &[u8],
+    rng: &mut R,
+) -> Result<(), Error> {
+    write_pubfiles(
+        &path,
+        name,
+        mnemonic,
+        account_index,
+        fog_report_url,
+        fog_report_id,
+        fog_authority_spki,
+    )?;
+    write_encrypted_keyfile(
+        path.as_ref().join(name).with_extension("json"),
+        mnemonic,
+        account_index,
+        fog_report_url,
+        fog_report_id,
+        fog_authority_spki,
+        password,
+        rng,
+    )
+}
+
+/// Write the public address files for a mnemonic account
+fn write_pubfiles<P: AsRef<Path>>(
+    path: P,
+    name: &str,
+    mnemonic: &Mnemonic,
+    account_index: u32,
+    fog_report_url: Option<&str>,
+    fog_report_id: &str,
+    fog_authority_spki: Option<&[u8]>,
 ) -> Result<(), Error> {
     let slip10key = mnemonic.clone().derive_slip10_key(account_index);
     let acct_key = match (fog_report_url, fog_authority_spki) {
@@ -42,19 +111,133 @@ pub fn write_keyfiles<P: AsRef<Path>>(
 
     fs::create_dir_all(&path)?;
 
-    write_keyfile(
-        path.as_ref().join(name).with_extension("json"),
-        mnemonic,
-        account_index,
-        fog_report_url,
-        fog_report_id,
-        fog_authority_spki,
-    )?;
     write_pubfile(path.as_ref().join(name).with_extension("pub"), &addr)?;
     write_b58pubfile(path.as_ref().join(name).with_extension("b58pub"), &addr)?;
     Ok(())
 }
 
+/// Write a single pair of keyfiles in the legacy root entropy format
+///
+/// If a password is given, the private keyfile is encrypted with it.
+pub fn write_root_entropy_keyfiles<P: AsRef<Path>>(
+    path: P,
+    name: &str,
+    root_identity: &RootIdentity,
+    password: Option<&[u8]>,
+) -> Result<(), Error> {
+    let addr = AccountKey::from(root_identity).default_subaddress();
+
+    fs::create_dir_all(&path)?;
+
+    let json = RootIdentityJson::from(root_identity);
+    let file = fs::File::create(path.as_ref().join(name).with_extension("json"))?;
+    match password {
+        Some(password) => {
+            let envelope = EncryptedKeyfile::encrypt_json(&json, password, &mut McRng::default())?;
+            serde_json::to_writer(file, &envelope)?;
+        }
+        None => serde_json::to_writer(file, &json)?,
+    }
+    write_pubfile(path.as_ref().join(name).with_extension("pub"), &addr)?;
+    write_b58pubfile(path.as_ref().join(name).with_extension("b58pub"), &addr)?;
+    Ok(())
+}
+
+/// Split the secret in a keyfile into Shamir shares. Encrypted keyfiles are
+/// decrypted with the given password, or the one in `MC_KEYFILE_PASSWORD`.
+/// Keyfiles in the mnemonic format have their BIP39 entropy split, and keyfiles
+/// in the legacy format have their root entropy split. Fog details and the
+/// account index are not part of the shares.
+pub fn split_keyfile<P: AsRef<Path>, R: RngCore + CryptoRng>(
+    path: P,
+    password: Option<&[u8]>,
+    threshold: u8,
+    num_shares: u8,
+    rng: &mut R,
+) -> Result<Vec<SecretShare>, Error> {
+    let value: serde_json::Value =
+        parse_keyfile_value(serde_json::from_reader(fs::File::open(path)?)?, password)?;
+    let obj = value
+        .as_object()
+        .ok_or_else(|| Error::Json("Expected json object".to_string()))?;
+    if obj.contains_key("root_entropy") {
+        let root_identity = RootIdentity::from(serde_json::from_value::<RootIdentityJson>(value)?);
+        Ok(shamir::split_root_entropy(
+            &root_identity.root_entropy,
+            threshold,
+            num_shares,
+            rng,
+        )?)
+    } else {
+        let account: UncheckedMnemonicAccount = serde_json::from_value(value)?;
+        let mnemonic = Mnemonic::from_phrase(
+            account.mnemonic.as_deref().unwrap_or_default(),
+            Language::English,
+        )
+        .map_err(|e| crate::mnemonic_acct::Error::InvalidMnemonic(format!("{e}")))?;
+        Ok(shamir::split_mnemonic(
+            &mnemonic, threshold, num_shares, rng,
+        )?)
+    }
+}
+
+/// Recombine Shamir shares and write the recovered secret out as a pair of
+/// keyfiles, in the format the secret was split from. If a password is given,
+/// the private keyfile is encrypted with it.
+#[allow(clippy::too_many_arguments)]
+pub fn write_keyfiles_from_shares<P: AsRef<Path>>(
+    path: P,
+    name: &str,
+    shares: &[SecretShare],
+    account_index: u32,
+    fog_report_url: Option<&str>,
+    fog_report_id: &str,
+    fog_authority_spki: Option<&[u8]>,
+    password: Option<&[u8]>,
+) -> Result<SecretKind, Error> {
+    let kind = shares
+        .first()
+        .map(|share| share.kind)
+        .ok_or(shamir::ShamirError::NotEnoughShares(0, 1))?;
+    match kind {
+        SecretKind::Bip39Entropy => {
+            let mnemonic = shamir::combine_mnemonic(shares)?;
+            match password {
+                Some(password) => write_encrypted_keyfiles(
+                    path,
+                    name,
+                    &mnemonic,
+                    account_index,
+                    fog_report_url,
+                    fog_report_id,
+                    fog_authority_spki,
+                    password,
+                    &mut McRng::default(),
+                )?,
+                None => write_keyfiles(
+                    path,
+                    name,
+                    &mnemonic,
+                    account_index,
+                    fog_report_url,
+                    fog_report_id,
+                    fog_authority_spki,
+                )?,
+            }
+        }
+        SecretKind::RootEntropy => {
+            let root_identity = RootIdentity {
+                root_entropy: shamir::combine_root_entropy(shares)?,
+                fog_report_url: fog_report_url.unwrap_or_default().to_owned(),
+                fog_report_id: fog_report_id.to_owned(),
+                fog_authority_spki: fog_authority_spki.unwrap_or_default().to_vec(),
+            };
+            write_root_entropy_keyfiles(path, name, &root_identity, password)?;
+        }
+    }
+    Ok(kind)
+}
+
 // These functions help when implementing bootstrap / initialization / tests
 
 /// Helper: Make i'th user's keyfiles' names
@@ -163,7 +346,9 @@ fn compare_keyfile_names(a: &Path, b: &Path) -> Ordering {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::mnemonic_acct::UncheckedMnemonicAccount;
+    use bip39::MnemonicType;
+    use mc_util_from_random::FromRandom;
+    use mc_util_test_helper::RngType;
 
     /// A default seed for [write_default_keyfiles()] calls.
     const DEFAULT_SEED: [u8; 32] = [1u8; 32];
@@ -283,4 +468,31 @@ mod test {
 
         assert_eq!(expected, actual);
     }
+
+    /// Test that a keyfile split into shares can be recombined from any
+    /// threshold-sized subset, in either keyfile format.
+    #[test]
+    fn shamir_split_and_recombine() {
+        let mut rng = RngType::from_seed([3u8; 32]);
+        let dir = tempfile::tempdir().expect("Could not create temporary dir");
+
+        let mnemonic = Mnemonic::new(MnemonicType::Words24, Language::English);
+        write_keyfiles(&dir, "mnemonic", &mnemonic, 0, None, "", None).unwrap();
+        let root_identity = RootIdentity::from_random(&mut rng);
+        write_root_entropy_keyfiles(&dir, "legacy", &root_identity, None).unwrap();
+
+        for name in ["mnemonic", "legacy"] {
+            let path = dir.path().join(name).with_extension("json");
+            let shares = split_keyfile(&path, None, 2, 3, &mut rng).unwrap();
+
+            let restored = format!("{name}_restored");
+            write_keyfiles_from_shares(&dir, &restored, &shares[1..], 0, None, "", None, None)
+                .unwrap();
+
+            assert_eq!(
+                read_keyfile(path).unwrap(),
+                read_keyfile(dir.path().join(&restored).with_extension("json")).unwrap()
+            );
+        }
+    }
 }
@@ -10,13 +10,19 @@ pub use json_format::RootIdentityJson;
 pub use mnemonic_acct::UncheckedMnemonicAccount;
 pub mod config;
 pub mod keygen;
+pub mod shamir;
 
 use crate::error::Error;
 use bip39::Mnemonic;
 use mc_account_keys::{AccountKey, PublicAddress, RootIdentity};
 use mc_api::printable::PrintableWrapper;
+use mc_util_encrypted_keyfile::{
+    decrypt_json_value, password_from_env, EncryptedKeyfile, Zeroizing,
+};
+use rand_core::{CryptoRng, RngCore};
+use serde::de::DeserializeOwned;
 use std::{
-    fs::File,
+    fs::{self, File},
     io::{Read, Write},
     path::Path,
 };
@@ -40,6 +46,72 @@ pub fn write_keyfile<P: AsRef<Path>>(
     Ok(serde_json::to_writer(File::create(path)?, &json)?)
 }
 
+/// Write a user's account details to disk, encrypted with a password
+#[allow(clippy::too_many_arguments)]
+pub fn write_encrypted_keyfile<P: AsRef<Path>, R: RngCore + CryptoRng>(
+    path: P,
+    mnemonic: &Mnemonic,
+    account_index: u32,
+    fog_report_url: Option<&str>,
+    fog_report_id: &str,
+    fog_authority_spki: Option<&[u8]>,
+    password: &[u8],
+    rng: &mut R,
+) -> Result<(), Error> {
+    let json = UncheckedMnemonicAccount {
+        mnemonic: Some(mnemonic.clone().into_phrase()),
+        account_index: Some(account_index),
+        fog_report_url: fog_report_url.map(ToOwned::to_owned),
+        fog_report_id: Some(fog_report_id.to_owned()),
+        fog_authority_spki: fog_authority_spki.map(ToOwned::to_owned),
+    };
+    let envelope = EncryptedKeyfile::encrypt_json(&json, password, rng)?;
+    Ok(serde_json::to_writer(File::create(path)?, &envelope)?)
+}
+
+/// Encrypt an existing plaintext keyfile in place, in either format.
+///
+/// The encrypted file is written next to the original and then renamed over
+/// it, so the keyfile is never left half-written.
+pub fn encrypt_keyfile<P: AsRef<Path>, R: RngCore + CryptoRng>(
+    path: P,
+    password: &[u8],
+    rng: &mut R,
+) -> Result<(), Error> {
+    let path = path.as_ref();
+    let plaintext = Zeroizing::new(fs::read(path)?);
+    let value = serde_json::from_slice::<serde_json::Value>(&plaintext)?;
+    if EncryptedKeyfile::from_json_value(&value).is_some() {
+        return Err(Error::AlreadyEncrypted);
+    }
+    let envelope = EncryptedKeyfile::encrypt(&plaintext, password, rng)?;
+
+    let tmp_path = path.with_extension("json.tmp");
+    serde_json::to_writer(File::create(&tmp_path)?, &envelope)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+/// Parse keyfile data, decrypting it first if it is encrypted. The password
+/// for encrypted keyfiles is read from the `MC_KEYFILE_PASSWORD` environment
+/// variable.
+fn parse_keyfile_data<R: Read, T: DeserializeOwned>(buffer: R) -> Result<T, Error> {
+    parse_keyfile_value(serde_json::from_reader(buffer)?, None)
+}
+
+/// Decrypt a parsed keyfile if needed and deserialize it, using the given
+/// password or falling back to the environment.
+pub(crate) fn parse_keyfile_value<T: DeserializeOwned>(
+    value: serde_json::Value,
+    password: Option<&[u8]>,
+) -> Result<T, Error> {
+    let value = decrypt_json_value(value, || match password {
+        Some(password) => Some(Zeroizing::new(password.to_vec())),
+        None => password_from_env(),
+    })?;
+    Ok(serde_json::from_value(value)?)
+}
+
 /// Read a keyfile intended for use with the legacy `RootEntropy`
 /// key-derivation method.
 pub fn read_root_entropy_keyfile<P: AsRef<Path>>(path: P) -> Result<RootIdentity, Error> {
@@ -49,7 +121,7 @@ pub fn read_root_entropy_keyfile<P: AsRef<Path>>(path: P) -> Result<RootIdentity
 /// Read keyfile data from the given buffer into a legacy `RootIdentity`
 /// structure
 pub fn read_root_entropy_keyfile_data<R: Read>(buffer: R) -> Result<RootIdentity, Error> {
-    Ok(serde_json::from_reader::<R, RootIdentityJson>(buffer)?.into())
+    Ok(parse_keyfile_data::<R, RootIdentityJson>(buffer)?.into())
 }
 
 /// Read user mnemonic from disk
@@ -59,7 +131,7 @@ pub fn read_mnemonic_keyfile<P: AsRef<Path>>(path: P) -> Result<AccountKey, Erro
 
 /// Read user root identity from any implementor of `Read`
 pub fn read_mnemonic_keyfile_data<R: Read>(buffer: R) -> Result<AccountKey, Error> {
-    Ok(serde_json::from_reader::<R, UncheckedMnemonicAccount>(buffer)?.try_into()?)
+    Ok(parse_keyfile_data::<R, UncheckedMnemonicAccount>(buffer)?.try_into()?)
 }
 
 /// Read an account either in the RootIdentity format or the mnemonic format
@@ -68,9 +140,34 @@ pub fn read_keyfile<P: AsRef<Path>>(path: P) -> Result<AccountKey, Error> {
     read_keyfile_data(File::open(path)?)
 }
 
-/// Read an account key file in either format
+/// Read an account from disk, decrypting it with the given password if it is
+/// encrypted
+pub fn read_keyfile_with_password<P: AsRef<Path>>(
+    path: P,
+    password: &[u8],
+) -> Result<AccountKey, Error> {
+    read_keyfile_data_with_password(File::open(path)?, password)
+}
+
+/// Read an account key file in either format, encrypted or not. The password
+/// for encrypted keyfiles is read from the `MC_KEYFILE_PASSWORD` environment
+/// variable.
 pub fn read_keyfile_data<R: Read>(buffer: R) -> Result<AccountKey, Error> {
-    let value = serde_json::from_reader::<R, serde_json::Value>(buffer)?;
+    parse_keyfile_data::<R, serde_json::Value>(buffer).and_then(account_key_from_value)
+}
+
+/// Read an account key file in either format, decrypting it with the given
+/// password if it is encrypted
+pub fn read_keyfile_data_with_password<R: Read>(
+    buffer: R,
+    password: &[u8],
+) -> Result<AccountKey, Error> {
+    parse_keyfile_value::<serde_json::Value>(serde_json::from_reader(buffer)?, Some(password))
+        .and_then(account_key_from_value)
+}
+
+/// Build an account key from a decrypted keyfile in either format
+fn account_key_from_value(value: serde_json::Value) -> Result<AccountKey, Error> {
     let obj = value
         .as_object()
         .ok_or_else(|| Error::Json("Expected json object".to_string()))?;
@@ -153,6 +250,7 @@ mod testing {
     use super::*;
     use bip39::{Language, MnemonicType};
     use mc_core::slip10::Slip10KeyGenerator;
+    use mc_util_test_helper::{RngType, SeedableRng};
 
     /// Test that round-tripping through a keyfile without fog gets the same
     /// result as creating the key directly.
@@ -203,6 +301,48 @@ mod testing {
         assert_eq!(expected, actual);
     }
 
+    /// Test that an encrypted keyfile reads back with the right password only.
+    #[test]
+    fn encrypted_keyfile_roundtrip() {
+        let mut rng = RngType::from_seed([5u8; 32]);
+        let dir = tempfile::tempdir().expect("Could not create temp dir");
+        let mnemonic = Mnemonic::new(MnemonicType::Words24, Language::English);
+        let path = dir.path().join("encrypted");
+        write_encrypted_keyfile(&path, &mnemonic, 0, None, "", None, b"password", &mut rng)
+            .expect("Could not write keyfile");
+
+        let expected = AccountKey::from(mnemonic.derive_slip10_key(0));
+        let actual = read_keyfile_with_password(&path, b"password").expect("Could not read");
+        assert_eq!(expected, actual);
+
+        assert_eq!(
+            read_keyfile_with_password(&path, b"wrong password"),
+            Err(Error::EncryptedKeyfile(
+                mc_util_encrypted_keyfile::Error::Decryption
+            ))
+        );
+    }
+
+    /// Test that encrypting a plaintext keyfile in place keeps the same key.
+    #[test]
+    fn encrypt_plaintext_keyfile() {
+        let mut rng = RngType::from_seed([6u8; 32]);
+        let dir = tempfile::tempdir().expect("Could not create temp dir");
+        let mnemonic = Mnemonic::new(MnemonicType::Words24, Language::English);
+        let path = dir.path().join("plaintext.json");
+        write_keyfile(&path, &mnemonic, 0, None, "", None).expect("Could not write keyfile");
+        let expected = read_keyfile(&path).expect("Could not read keyfile");
+
+        encrypt_keyfile(&path, b"password", &mut rng).expect("Could not encrypt keyfile");
+        assert_eq!(
+            encrypt_keyfile(&path, b"password", &mut rng),
+            Err(Error::AlreadyEncrypted)
+        );
+
+        let actual = read_keyfile_with_password(&path, b"password").expect("Could not read");
+        assert_eq!(expected, actual);
+    }
+
     /// Test that writing a [`PublicAddress`](mc_account_keys::PublicAddress)
     /// and reading it back without fog details gets the same results.
     #[test]
@@ -0,0 +1,462 @@
+// Copyright (c) 2018-2024 The MobileCoin Foundation
+
+//! Shamir secret sharing backups for BIP39 entropy and legacy root entropy.
+//!
+//! Following the approach of SLIP-39, a secret is split into `n` shares, any
+//! `k` of which are enough to recover it. Sharing is done byte-wise over
+//! GF(256), and a short digest of the secret is shared along with it, so that
+//! recombining the wrong set of shares is detected instead of silently
+//! producing a different key. Each share also carries its own checksum, so a
+//! mistyped share is caught before it is used.
+//!
+//! Shares are written out as hex strings.
+
+use bip39::{Language, Mnemonic};
+use displaydoc::Display;
+use mc_account_keys::RootEntropy;
+use mc_rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+/// The version byte at the start of every encoded share.
+const SHARE_VERSION: u8 = 1;
+
+/// The number of bytes of the secret's digest shared along with it.
+const SECRET_DIGEST_LEN: usize = 4;
+
+/// The number of bytes of checksum at the end of every encoded share.
+const SHARE_CHECKSUM_LEN: usize = 4;
+
+/// The number of header bytes in an encoded share: version, kind, identifier,
+/// threshold and index.
+const SHARE_HEADER_LEN: usize = 6;
+
+/// An error which can occur when splitting or recombining shares.
+#[derive(Clone, Debug, Display, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ShamirError {
+    /// The threshold must be at least 1 and at most the number of shares
+    InvalidThreshold,
+    /// The secret is empty
+    EmptySecret,
+    /// The share is not valid hex: {0}
+    InvalidHex(String),
+    /// The share is too short
+    ShareTooShort,
+    /// Unsupported share version: {0}
+    UnsupportedVersion(u8),
+    /// Unknown secret kind: {0}
+    UnknownSecretKind(u8),
+    /// The share has index zero, which would reveal the secret
+    ZeroIndex,
+    /// The share checksum does not match, it may have been mistyped
+    BadChecksum,
+    /// Shares disagree on identifier, threshold, kind or length
+    MismatchedShares,
+    /// Two shares have the same index: {0}
+    DuplicateIndex(u8),
+    /// Not enough shares: {0} given, {1} needed
+    NotEnoughShares(usize, usize),
+    /// The recovered secret does not match its digest
+    BadSecretDigest,
+    /// Expected a secret of kind {0}, got {1}
+    WrongSecretKind(SecretKind, SecretKind),
+    /// The recovered secret is not valid for its kind: {0}
+    InvalidSecret(String),
+}
+
+/// The kind of secret a set of shares backs up.
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[repr(u8)]
+pub enum SecretKind {
+    /// BIP39 mnemonic entropy
+    Bip39Entropy = 0,
+    /// legacy root entropy
+    RootEntropy = 1,
+}
+
+impl TryFrom<u8> for SecretKind {
+    type Error = ShamirError;
+
+    fn try_from(src: u8) -> Result<Self, Self::Error> {
+        match src {
+            0 => Ok(Self::Bip39Entropy),
+            1 => Ok(Self::RootEntropy),
+            other => Err(ShamirError::UnknownSecretKind(other)),
+        }
+    }
+}
+
+/// One share of a secret.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SecretShare {
+    /// The kind of secret this share belongs to.
+    pub kind: SecretKind,
+    /// A random identifier shared by all shares from one split.
+    pub identifier: u16,
+    /// The number of shares needed to recover the secret.
+    pub threshold: u8,
+    /// The index of this share, which is its x-coordinate. Never zero.
+    pub index: u8,
+    /// The share value, one byte per byte of the secret and its digest.
+    pub value: Vec<u8>,
+}
+
+impl SecretShare {
+    /// Encode this share, including its checksum.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes =
+            Vec::with_capacity(SHARE_HEADER_LEN + self.value.len() + SHARE_CHECKSUM_LEN);
+        bytes.push(SHARE_VERSION);
+        bytes.push(self.kind as u8);
+        bytes.extend_from_slice(&self.identifier.to_be_bytes());
+        bytes.push(self.threshold);
+        bytes.push(self.index);
+        bytes.extend_from_slice(&self.value);
+        let checksum = Sha256::digest(&bytes);
+        bytes.extend_from_slice(&checksum[..SHARE_CHECKSUM_LEN]);
+        bytes
+    }
+
+    /// Decode a share and verify its checksum.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ShamirError> {
+        if bytes.len() <= SHARE_HEADER_LEN + SHARE_CHECKSUM_LEN {
+            return Err(ShamirError::ShareTooShort);
+        }
+        let (body, checksum) = bytes.split_at(bytes.len() - SHARE_CHECKSUM_LEN);
+        if Sha256::digest(body)[..SHARE_CHECKSUM_LEN] != *checksum {
+            return Err(ShamirError::BadChecksum);
+        }
+        if body[0] != SHARE_VERSION {
+            return Err(ShamirError::UnsupportedVersion(body[0]));
+        }
+        let share = Self {
+            kind: SecretKind::try_from(body[1])?,
+            identifier: u16::from_be_bytes([body[2], body[3]]),
+            threshold: body[4],
+            index: body[5],
+            value: body[SHARE_HEADER_LEN..].to_vec(),
+        };
+        if share.index == 0 {
+            return Err(ShamirError::ZeroIndex);
+        }
+        if share.threshold == 0 {
+            return Err(ShamirError::InvalidThreshold);
+        }
+        Ok(share)
+    }
+}
+
+impl fmt::Display for SecretShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.to_bytes()))
+    }
+}
+
+impl FromStr for SecretShare {
+    type Err = ShamirError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(src.trim()).map_err(|e| ShamirError::InvalidHex(e.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// Split a secret into `num_shares` shares, any `threshold` of which recover
+/// it.
+pub fn split_secret<R: RngCore + CryptoRng>(
+    kind: SecretKind,
+    secret: &[u8],
+    threshold: u8,
+    num_shares: u8,
+    rng: &mut R,
+) -> Result<Vec<SecretShare>, ShamirError> {
+    if threshold == 0 || threshold > num_shares {
+        return Err(ShamirError::InvalidThreshold);
+    }
+    if secret.is_empty() {
+        return Err(ShamirError::EmptySecret);
+    }
+
+    let mut shared = secret.to_vec();
+    shared.extend_from_slice(&secret_digest(secret));
+
+    let mut identifier = [0u8; 2];
+    rng.fill_bytes(&mut identifier);
+
+    // Each byte of the shared value is the constant term of its own random
+    // polynomial of degree threshold - 1.
+    let mut coefficients = vec![0u8; shared.len() * (threshold as usize - 1)];
+    rng.fill_bytes(&mut coefficients);
+
+    let shares = (1..=num_shares)
+        .map(|x| {
+            let value = shared
+                .iter()
+                .enumerate()
+                .map(|(byte_idx, secret_byte)| {
+                    let poly = coefficients
+                        .chunks(shared.len())
+                        .map(|chunk| chunk[byte_idx]);
+                    // Horner's method, highest degree coefficient first.
+                    poly.rev()
+                        .chain(std::iter::once(*secret_byte))
+                        .fold(0u8, |acc, coeff| gf256_mul(acc, x) ^ coeff)
+                })
+                .collect();
+            SecretShare {
+                kind,
+                identifier: u16::from_be_bytes(identifier),
+                threshold,
+                index: x,
+                value,
+            }
+        })
+        .collect();
+
+    Ok(shares)
+}
+
+/// Recover a secret from a set of shares, returning its kind and bytes.
+///
+/// Shares past the threshold are ignored.
+pub fn combine_shares(shares: &[SecretShare]) -> Result<(SecretKind, Vec<u8>), ShamirError> {
+    let first = shares.first().ok_or(ShamirError::NotEnoughShares(0, 1))?;
+    if shares.iter().any(|share| {
+        share.kind != first.kind
+            || share.identifier != first.identifier
+            || share.threshold != first.threshold
+            || share.value.len() != first.value.len()
+    }) {
+        return Err(ShamirError::MismatchedShares);
+    }
+    let mut indexes = BTreeSet::new();
+    for share in shares {
+        if !indexes.insert(share.index) {
+            return Err(ShamirError::DuplicateIndex(share.index));
+        }
+    }
+    let threshold = first.threshold as usize;
+    if shares.len() < threshold {
+        return Err(ShamirError::NotEnoughShares(shares.len(), threshold));
+    }
+    if first.value.len() <= SECRET_DIGEST_LEN {
+        return Err(ShamirError::ShareTooShort);
+    }
+
+    let shares = &shares[..threshold];
+
+    // Lagrange interpolation at x = 0. In GF(256), subtraction is xor.
+    let basis: Vec<u8> = shares
+        .iter()
+        .map(|share_i| {
+            let (num, den) = shares
+                .iter()
+                .filter(|share_m| share_m.index != share_i.index)
+                .fold((1u8, 1u8), |(num, den), share_m| {
+                    (
+                        gf256_mul(num, share_m.index),
+                        gf256_mul(den, share_m.index ^ share_i.index),
+                    )
+                });
+            gf256_mul(num, gf256_inv(den))
+        })
+        .collect();
+
+    let shared: Vec<u8> = (0..first.value.len())
+        .map(|byte_idx| {
+            shares
+                .iter()
+                .zip(basis.iter())
+                .fold(0u8, |acc, (share, l)| {
+                    acc ^ gf256_mul(share.value[byte_idx], *l)
+                })
+        })
+        .collect();
+
+    let (secret, digest) = shared.split_at(shared.len() - SECRET_DIGEST_LEN);
+    if secret_digest(secret) != digest {
+        return Err(ShamirError::BadSecretDigest);
+    }
+
+    Ok((first.kind, secret.to_vec()))
+}
+
+/// Split the entropy of a BIP39 mnemonic into shares.
+pub fn split_mnemonic<R: RngCore + CryptoRng>(
+    mnemonic: &Mnemonic,
+    threshold: u8,
+    num_shares: u8,
+    rng: &mut R,
+) -> Result<Vec<SecretShare>, ShamirError> {
+    split_secret(
+        SecretKind::Bip39Entropy,
+        mnemonic.entropy(),
+        threshold,
+        num_shares,
+        rng,
+    )
+}
+
+/// Recover a BIP39 mnemonic from shares of its entropy.
+pub fn combine_mnemonic(shares: &[SecretShare]) -> Result<Mnemonic, ShamirError> {
+    match combine_shares(shares)? {
+        (SecretKind::Bip39Entropy, entropy) => Mnemonic::from_entropy(&entropy, Language::English)
+            .map_err(|e| ShamirError::InvalidSecret(e.to_string())),
+        (kind, _) => Err(ShamirError::WrongSecretKind(SecretKind::Bip39Entropy, kind)),
+    }
+}
+
+/// Split a legacy root entropy into shares.
+pub fn split_root_entropy<R: RngCore + CryptoRng>(
+    root_entropy: &RootEntropy,
+    threshold: u8,
+    num_shares: u8,
+    rng: &mut R,
+) -> Result<Vec<SecretShare>, ShamirError> {
+    split_secret(
+        SecretKind::RootEntropy,
+        &root_entropy.bytes,
+        threshold,
+        num_shares,
+        rng,
+    )
+}
+
+/// Recover a legacy root entropy from shares.
+pub fn combine_root_entropy(shares: &[SecretShare]) -> Result<RootEntropy, ShamirError> {
+    match combine_shares(shares)? {
+        (SecretKind::RootEntropy, entropy) => {
+            let bytes: [u8; 32] = entropy
+                .as_slice()
+                .try_into()
+                .map_err(|_| ShamirError::InvalidSecret("expected 32 bytes".to_owned()))?;
+            Ok(RootEntropy::from(&bytes))
+        }
+        (kind, _) => Err(ShamirError::WrongSecretKind(SecretKind::RootEntropy, kind)),
+    }
+}
+
+/// The digest of a secret which is shared along with it.
+fn secret_digest(secret: &[u8]) -> [u8; SECRET_DIGEST_LEN] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"mc-util-keyfile-shamir-secret");
+    hasher.update(secret);
+    let mut digest = [0u8; SECRET_DIGEST_LEN];
+    digest.copy_from_slice(&hasher.finalize()[..SECRET_DIGEST_LEN]);
+    digest
+}
+
+/// Multiplication in GF(256), using the AES reduction polynomial. This runs
+/// in a fixed number of steps regardless of its inputs.
+fn gf256_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut result = 0u8;
+    for _ in 0..8 {
+        result ^= a & (b & 1).wrapping_neg();
+        let carry = (a >> 7).wrapping_neg();
+        a = (a << 1) ^ (carry & 0x1b);
+        b >>= 1;
+    }
+    result
+}
+
+/// Multiplicative inverse in GF(256), computed as a^254.
+fn gf256_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exp = 254u8;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = gf256_mul(result, base);
+        }
+        base = gf256_mul(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bip39::MnemonicType;
+    use mc_util_test_helper::{RngType, SeedableRng};
+
+    #[test]
+    fn gf256_inverse() {
+        for a in 1..=255u8 {
+            assert_eq!(gf256_mul(a, gf256_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn split_and_combine_any_subset() {
+        let mut rng = RngType::from_seed([7u8; 32]);
+        let secret = b"a secret of some length".to_vec();
+        let shares = split_secret(SecretKind::Bip39Entropy, &secret, 3, 5, &mut rng).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        for i in 0..5 {
+            for j in (i + 1)..5 {
+                for k in (j + 1)..5 {
+                    let subset = [shares[k].clone(), shares[i].clone(), shares[j].clone()];
+                    let (kind, recovered) = combine_shares(&subset).unwrap();
+                    assert_eq!(kind, SecretKind::Bip39Entropy);
+                    assert_eq!(recovered, secret);
+                }
+            }
+        }
+
+        assert_eq!(
+            combine_shares(&shares[..2]),
+            Err(ShamirError::NotEnoughShares(2, 3))
+        );
+        assert_eq!(
+            combine_shares(&[shares[0].clone(), shares[0].clone(), shares[1].clone()]),
+            Err(ShamirError::DuplicateIndex(shares[0].index))
+        );
+    }
+
+    #[test]
+    fn tampered_shares_are_detected() {
+        let mut rng = RngType::from_seed([8u8; 32]);
+        let shares = split_secret(SecretKind::RootEntropy, &[42u8; 32], 2, 3, &mut rng).unwrap();
+
+        // A share from a different split doesn't combine.
+        let other = split_secret(SecretKind::RootEntropy, &[42u8; 32], 2, 3, &mut rng).unwrap();
+        let mut foreign = other[1].clone();
+        foreign.identifier = shares[0].identifier;
+        assert_eq!(
+            combine_shares(&[shares[0].clone(), foreign]),
+            Err(ShamirError::BadSecretDigest)
+        );
+
+        // A mistyped share fails its checksum.
+        let mut text = shares[0].to_string().into_bytes();
+        text[20] = if text[20] == b'0' { b'1' } else { b'0' };
+        assert_eq!(
+            SecretShare::from_str(std::str::from_utf8(&text).unwrap()),
+            Err(ShamirError::BadChecksum)
+        );
+    }
+
+    #[test]
+    fn mnemonic_round_trip() {
+        let mut rng = RngType::from_seed([9u8; 32]);
+        let mnemonic = Mnemonic::new(MnemonicType::Words24, Language::English);
+        let shares = split_mnemonic(&mnemonic, 2, 3, &mut rng).unwrap();
+
+        let parsed = shares[1..]
+            .iter()
+            .map(|share| SecretShare::from_str(&share.to_string()).unwrap())
+            .collect::<Vec<_>>();
+        let recovered = combine_mnemonic(&parsed).unwrap();
+        assert_eq!(recovered.phrase(), mnemonic.phrase());
+
+        assert_eq!(
+            combine_root_entropy(&parsed),
+            Err(ShamirError::WrongSecretKind(
+                SecretKind::RootEntropy,
+                SecretKind::Bip39Entropy
+            ))
+        );
+    }
+}